@@ -1,7 +1,10 @@
 //! Structs used for auditing
+use std::time::Duration;
+
 use crate::time::Timestamp;
 
 #[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Activity<T> {
     pub when: Timestamp,
     pub who: T,
@@ -14,4 +17,82 @@ impl<T> Activity<T> {
             who: who.into(),
         }
     }
+
+    /// Complete the activity now
+    #[must_use]
+    pub fn completed(self) -> CompletedActivity<T> {
+        self.completed_at(Timestamp::now())
+    }
+
+    /// Complete the activity at the given time
+    ///
+    /// The duration is zero if the activity ended before it started,
+    /// e.g. if the system time has been adjusted in the meantime.
+    #[must_use]
+    pub fn completed_at(self, ended: Timestamp) -> CompletedActivity<T> {
+        let Self { when: started, who } = self;
+        let duration = (ended.to_inner() - started.to_inner())
+            .try_into()
+            .unwrap_or(Duration::ZERO);
+        CompletedActivity {
+            started,
+            ended,
+            duration,
+            who,
+        }
+    }
+}
+
+/// An activity with a measured duration
+#[derive(Debug, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CompletedActivity<T> {
+    pub started: Timestamp,
+    pub ended: Timestamp,
+    pub duration: Duration,
+    pub who: T,
+}
+
+#[cfg(test)]
+mod tests {
+    use time::macros::datetime;
+
+    use super::*;
+
+    #[test]
+    fn complete_activity() {
+        let activity = Activity {
+            when: Timestamp::new(datetime!(2023-09-10 12:00:00 UTC)),
+            who: "plugin",
+        };
+        let ended = Timestamp::new(datetime!(2023-09-10 12:00:01.5 UTC));
+        let completed = activity.clone().completed_at(ended);
+        assert_eq!(activity.when, completed.started);
+        assert_eq!(ended, completed.ended);
+        assert_eq!(Duration::from_millis(1500), completed.duration);
+        assert_eq!("plugin", completed.who);
+
+        // Adjusted system time
+        let completed = activity.completed_at(Timestamp::new(datetime!(2023-09-10 11:00:00 UTC)));
+        assert_eq!(Duration::ZERO, completed.duration);
+
+        let completed = Activity::<String>::now("plugin").completed();
+        assert!(completed.started <= completed.ended);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_completed_activity() {
+        let completed = Activity {
+            when: Timestamp::new(datetime!(2023-09-10 12:00:00 UTC)),
+            who: 7,
+        }
+        .completed_at(Timestamp::new(datetime!(2023-09-10 12:00:00.25 UTC)));
+        let json = serde_json::to_string(&completed).unwrap();
+        assert_eq!(
+            r#"{"started":"2023-09-10T12:00:00Z","ended":"2023-09-10T12:00:00.25Z","duration":{"secs":0,"nanos":250000000},"who":7}"#,
+            json
+        );
+        assert_eq!(completed, serde_json::from_str(&json).unwrap());
+    }
 }
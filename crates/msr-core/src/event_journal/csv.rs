@@ -32,6 +32,7 @@ impl FileRecordStorage {
         let file_name_template = RollingFileNameTemplate {
             prefix: file_name_prefix,
             suffix: ".csv".to_string(),
+            with_sequence_number: true,
         };
         let inner = csv::FileRecordStorage::try_new(
             binary_data_format,
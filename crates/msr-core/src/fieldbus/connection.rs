@@ -0,0 +1,79 @@
+//! Supervising the connection of a driver
+
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    time::Duration,
+};
+
+use super::schedule::exponential_backoff;
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum ConnectionState {
+    Connected,
+    Disconnected,
+}
+
+/// Reconnecting after the connection has been lost
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ReconnectPolicy {
+    /// The delay before the first attempt
+    ///
+    /// Doubled for each subsequent attempt.
+    pub initial_backoff: Duration,
+
+    /// Upper bound for the delay between attempts
+    pub max_backoff: Duration,
+}
+
+impl ReconnectPolicy {
+    /// The delay before the given attempt, starting at 1
+    #[must_use]
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        exponential_backoff(self.initial_backoff, self.max_backoff, attempt)
+    }
+}
+
+/// Check if an I/O error indicates that the connection has been lost
+///
+/// Intended for implementing [`Driver::is_connection_lost()`](super::Driver::is_connection_lost)
+/// for drivers that communicate via sockets or serial ports.
+#[must_use]
+pub fn is_io_connection_lost(err: &IoError) -> bool {
+    matches!(
+        err.kind(),
+        IoErrorKind::TimedOut
+            | IoErrorKind::UnexpectedEof
+            | IoErrorKind::BrokenPipe
+            | IoErrorKind::ConnectionReset
+            | IoErrorKind::ConnectionAborted
+            | IoErrorKind::NotConnected
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detect_lost_io_connections() {
+        assert!(is_io_connection_lost(&IoErrorKind::TimedOut.into()));
+        assert!(is_io_connection_lost(&IoErrorKind::UnexpectedEof.into()));
+        assert!(is_io_connection_lost(&IoErrorKind::ConnectionReset.into()));
+        assert!(!is_io_connection_lost(&IoErrorKind::InvalidData.into()));
+        assert!(!is_io_connection_lost(
+            &IoErrorKind::PermissionDenied.into()
+        ));
+    }
+
+    #[test]
+    fn exponential_reconnect_backoff() {
+        let reconnect = ReconnectPolicy {
+            initial_backoff: Duration::from_secs(1),
+            max_backoff: Duration::from_secs(60),
+        };
+        assert_eq!(Duration::from_secs(1), reconnect.backoff(1));
+        assert_eq!(Duration::from_secs(2), reconnect.backoff(2));
+        assert_eq!(Duration::from_secs(32), reconnect.backoff(6));
+        assert_eq!(Duration::from_secs(60), reconnect.backoff(7));
+    }
+}
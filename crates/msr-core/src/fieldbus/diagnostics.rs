@@ -0,0 +1,184 @@
+//! Counting requests and their outcome for diagnosing communication problems
+
+use std::time::Duration;
+
+/// The cause of a failed request
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum RequestErrorKind {
+    /// No response has been received in time
+    Timeout,
+
+    /// The response has been corrupted, e.g. an invalid CRC
+    Checksum,
+
+    /// The device has rejected the request, e.g. a Modbus exception
+    Exception,
+
+    Other,
+}
+
+/// Statistics of the round-trip times of successful requests
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct RoundTripTimeStats {
+    pub count: u64,
+    pub min: Duration,
+    pub max: Duration,
+    pub total: Duration,
+}
+
+impl RoundTripTimeStats {
+    pub fn record(&mut self, round_trip_time: Duration) {
+        if self.count == 0 {
+            self.min = round_trip_time;
+            self.max = round_trip_time;
+        } else {
+            self.min = self.min.min(round_trip_time);
+            self.max = self.max.max(round_trip_time);
+        }
+        self.count += 1;
+        self.total = self.total.saturating_add(round_trip_time);
+    }
+
+    /// The average round-trip time
+    ///
+    /// Returns `None` if nothing has been recorded yet.
+    #[must_use]
+    pub fn mean(&self) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+        let mean_nanos = self.total.as_nanos() / u128::from(self.count);
+        Some(Duration::from_nanos(
+            u64::try_from(mean_nanos).unwrap_or(u64::MAX),
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub struct DiagnosticCounters {
+    /// All requests, including failed requests
+    pub requests: u64,
+
+    pub timeouts: u64,
+    pub checksum_errors: u64,
+    pub exceptions: u64,
+    pub other_errors: u64,
+
+    /// Failed requests after which the connection had to be re-established
+    pub connection_losses: u64,
+
+    pub round_trip_time: RoundTripTimeStats,
+}
+
+impl DiagnosticCounters {
+    #[must_use]
+    pub const fn failed_requests(&self) -> u64 {
+        self.timeouts + self.checksum_errors + self.exceptions + self.other_errors
+    }
+
+    pub fn record_success(&mut self, round_trip_time: Duration) {
+        self.requests += 1;
+        self.round_trip_time.record(round_trip_time);
+    }
+
+    pub fn record_failure(&mut self, error_kind: RequestErrorKind, connection_lost: bool) {
+        self.requests += 1;
+        let counter = match error_kind {
+            RequestErrorKind::Timeout => &mut self.timeouts,
+            RequestErrorKind::Checksum => &mut self.checksum_errors,
+            RequestErrorKind::Exception => &mut self.exceptions,
+            RequestErrorKind::Other => &mut self.other_errors,
+        };
+        *counter += 1;
+        if connection_lost {
+            self.connection_losses += 1;
+        }
+    }
+}
+
+/// Diagnostics of a connection and its point groups
+#[derive(Debug, Clone, Default, Eq, PartialEq)]
+pub struct Diagnostics {
+    /// All requests of the connection
+    pub connection: DiagnosticCounters,
+
+    /// The requests of each group, in the order of the groups
+    pub groups: Vec<DiagnosticCounters>,
+}
+
+impl Diagnostics {
+    #[must_use]
+    pub fn new(group_count: usize) -> Self {
+        Self {
+            connection: Default::default(),
+            groups: vec![Default::default(); group_count],
+        }
+    }
+
+    pub fn record_success(&mut self, group: usize, round_trip_time: Duration) {
+        self.connection.record_success(round_trip_time);
+        if let Some(counters) = self.groups.get_mut(group) {
+            counters.record_success(round_trip_time);
+        }
+    }
+
+    pub fn record_failure(
+        &mut self,
+        group: usize,
+        error_kind: RequestErrorKind,
+        connection_lost: bool,
+    ) {
+        self.connection.record_failure(error_kind, connection_lost);
+        if let Some(counters) = self.groups.get_mut(group) {
+            counters.record_failure(error_kind, connection_lost);
+        }
+    }
+
+    /// Reset all counters
+    pub fn reset(&mut self) {
+        *self = Self::new(self.groups.len());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip_time_stats() {
+        let mut stats = RoundTripTimeStats::default();
+        assert_eq!(None, stats.mean());
+        stats.record(Duration::from_millis(20));
+        stats.record(Duration::from_millis(10));
+        stats.record(Duration::from_millis(60));
+        assert_eq!(3, stats.count);
+        assert_eq!(Duration::from_millis(10), stats.min);
+        assert_eq!(Duration::from_millis(60), stats.max);
+        assert_eq!(Some(Duration::from_millis(30)), stats.mean());
+    }
+
+    #[test]
+    fn count_requests_per_connection_and_group() {
+        let mut diagnostics = Diagnostics::new(2);
+        diagnostics.record_success(0, Duration::from_millis(10));
+        diagnostics.record_failure(1, RequestErrorKind::Checksum, false);
+        diagnostics.record_failure(1, RequestErrorKind::Timeout, true);
+        diagnostics.record_failure(1, RequestErrorKind::Exception, false);
+
+        assert_eq!(4, diagnostics.connection.requests);
+        assert_eq!(3, diagnostics.connection.failed_requests());
+        assert_eq!(1, diagnostics.connection.connection_losses);
+        assert_eq!(1, diagnostics.connection.round_trip_time.count);
+
+        assert_eq!(1, diagnostics.groups[0].requests);
+        assert_eq!(0, diagnostics.groups[0].failed_requests());
+        assert_eq!(3, diagnostics.groups[1].requests);
+        assert_eq!(1, diagnostics.groups[1].timeouts);
+        assert_eq!(1, diagnostics.groups[1].checksum_errors);
+        assert_eq!(1, diagnostics.groups[1].exceptions);
+        assert_eq!(0, diagnostics.groups[1].round_trip_time.count);
+
+        diagnostics.reset();
+        assert_eq!(Diagnostics::new(2), diagnostics);
+    }
+}
@@ -0,0 +1,433 @@
+//! Reusable building blocks for fieldbus drivers
+//!
+//! Protocol specific drivers only need to implement [`Driver`].
+//! Scheduling and the mapping of driver addresses onto registers
+//! are shared by all drivers.
+
+use std::time::{Duration, Instant};
+
+use thiserror::Error;
+
+use crate::{
+    register::{Index, ObservedValues},
+    time::SystemInstant,
+    Quality, TimestampedValue, Value, ValueType,
+};
+
+mod connection;
+pub use self::connection::{is_io_connection_lost, ConnectionState, ReconnectPolicy};
+
+mod diagnostics;
+pub use self::diagnostics::{
+    DiagnosticCounters, Diagnostics, RequestErrorKind, RoundTripTimeStats,
+};
+
+mod schedule;
+pub use self::schedule::{PollingConfig, PollingScheduler, RetryPolicy};
+
+/// A register that is mapped onto a device address
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Point<Address> {
+    pub index: Index,
+
+    /// The protocol specific address
+    pub address: Address,
+
+    pub value_type: ValueType,
+}
+
+/// Points that are read together in a single request
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PointGroup<Address> {
+    pub points: Vec<Point<Address>>,
+    pub polling: PollingConfig,
+}
+
+/// Protocol specific access to devices
+pub trait Driver {
+    type Address;
+    type Error;
+
+    /// Establish the connection
+    fn connect(&mut self) -> Result<(), Self::Error>;
+
+    /// Read the values of all points in a group
+    ///
+    /// Returns the values in the same order as the points. Values
+    /// that are not available are `None`.
+    fn read_point_group(
+        &mut self,
+        points: &[Point<Self::Address>],
+    ) -> Result<Vec<Option<Value>>, Self::Error>;
+
+    /// Write the value of a single point
+    fn write_point(
+        &mut self,
+        point: &Point<Self::Address>,
+        value: Value,
+    ) -> Result<(), Self::Error>;
+
+    /// Check if an error indicates that the connection has been lost
+    ///
+    /// The connection needs to be established again after timeouts
+    /// or when the peer has closed the connection.
+    fn is_connection_lost(&self, err: &Self::Error) -> bool;
+
+    /// Classify a failed request for diagnostics
+    fn request_error_kind(&self, err: &Self::Error) -> RequestErrorKind {
+        let _ = err;
+        RequestErrorKind::Other
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum Error<E> {
+    #[error("register {0} is not mapped")]
+    UnmappedRegister(Index),
+
+    #[error("not connected")]
+    NotConnected,
+
+    #[error("driver returned {actual} instead of {expected} value(s)")]
+    UnexpectedValueCount { expected: usize, actual: usize },
+
+    #[error(transparent)]
+    Driver(E),
+}
+
+/// The outcome of polling a group
+#[derive(Debug)]
+pub struct PollResult<E> {
+    pub group: usize,
+    pub result: Result<ObservedValues<Value>, Error<E>>,
+}
+
+#[derive(Debug)]
+pub enum PollEvent<E> {
+    /// A group has been polled
+    Polled(PollResult<E>),
+
+    /// The connection has been established
+    ///
+    /// All groups are polled immediately.
+    ConnectionUp,
+
+    /// The connection has been lost while polling a group
+    ///
+    /// The last observed values of all registers have been marked
+    /// as [`Quality::Bad`].
+    ConnectionDown {
+        group: usize,
+        error: Error<E>,
+        affected_registers: Vec<Index>,
+    },
+
+    /// Establishing the connection failed
+    ReconnectFailed { error: Error<E>, retry_in: Duration },
+
+    /// Periodic report of the diagnostics
+    Diagnostics(Diagnostics),
+}
+
+/// Polls the point groups of a driver according to their schedule
+///
+/// The connection is established on demand and re-established
+/// after it has been lost.
+#[derive(Debug)]
+pub struct Poller<D: Driver> {
+    driver: D,
+    groups: Vec<Vec<Point<D::Address>>>,
+    last_observed: Vec<Vec<Option<TimestampedValue<Value>>>>,
+    scheduler: PollingScheduler,
+    reconnect: ReconnectPolicy,
+    connection_state: ConnectionState,
+    reconnect_attempts: u32,
+    reconnect_at: Instant,
+    diagnostics: Diagnostics,
+    diagnostics_interval: Option<Duration>,
+    diagnostics_reported_at: Instant,
+}
+
+impl<D: Driver> Poller<D> {
+    /// Create a new poller
+    ///
+    /// The connection is established by the first poll.
+    #[must_use]
+    pub fn new(
+        driver: D,
+        groups: Vec<PointGroup<D::Address>>,
+        reconnect: ReconnectPolicy,
+        now: &SystemInstant,
+    ) -> Self {
+        let (groups, polling): (Vec<_>, Vec<_>) = groups
+            .into_iter()
+            .map(|PointGroup { points, polling }| (points, polling))
+            .unzip();
+        let last_observed = groups
+            .iter()
+            .map(|points| vec![None; points.len()])
+            .collect();
+        let group_count = groups.len();
+        let scheduler = PollingScheduler::new(polling, now.instant());
+        Self {
+            driver,
+            groups,
+            last_observed,
+            scheduler,
+            reconnect,
+            connection_state: ConnectionState::Disconnected,
+            reconnect_attempts: 0,
+            reconnect_at: now.instant(),
+            diagnostics: Diagnostics::new(group_count),
+            diagnostics_interval: None,
+            diagnostics_reported_at: now.instant(),
+        }
+    }
+
+    /// Report the diagnostics periodically
+    ///
+    /// See also [`PollEvent::Diagnostics`].
+    #[must_use]
+    pub fn with_diagnostics_interval(self, diagnostics_interval: Duration) -> Self {
+        Self {
+            diagnostics_interval: Some(diagnostics_interval),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub const fn driver(&self) -> &D {
+        &self.driver
+    }
+
+    pub fn driver_mut(&mut self) -> &mut D {
+        &mut self.driver
+    }
+
+    #[must_use]
+    pub const fn scheduler(&self) -> &PollingScheduler {
+        &self.scheduler
+    }
+
+    #[must_use]
+    pub const fn connection_state(&self) -> ConnectionState {
+        self.connection_state
+    }
+
+    /// The diagnostics since creation or the last reset
+    #[must_use]
+    pub const fn diagnostics(&self) -> &Diagnostics {
+        &self.diagnostics
+    }
+
+    pub fn reset_diagnostics(&mut self) {
+        self.diagnostics.reset();
+    }
+
+    fn diagnostics_due_at(&self) -> Option<Instant> {
+        self.diagnostics_interval
+            .map(|interval| self.diagnostics_reported_at + interval)
+    }
+
+    /// The points of a group
+    #[must_use]
+    pub fn points(&self, group: usize) -> Option<&[Point<D::Address>]> {
+        self.groups.get(group).map(Vec::as_slice)
+    }
+
+    /// The next time when [`Self::poll_next()`] has something to do
+    #[must_use]
+    pub fn next_due_at(&self) -> Option<Instant> {
+        let next_due_at = match self.connection_state {
+            ConnectionState::Connected => self.scheduler.next_due_at(),
+            ConnectionState::Disconnected => Some(self.reconnect_at),
+        };
+        match (next_due_at, self.diagnostics_due_at()) {
+            (Some(next_due_at), Some(diagnostics_due_at)) => {
+                Some(next_due_at.min(diagnostics_due_at))
+            }
+            (next_due_at, diagnostics_due_at) => next_due_at.or(diagnostics_due_at),
+        }
+    }
+
+    fn try_connect(&mut self, now: Instant) -> PollEvent<D::Error> {
+        match self.driver.connect() {
+            Ok(()) => {
+                log::info!("Connection established");
+                self.connection_state = ConnectionState::Connected;
+                self.reconnect_attempts = 0;
+                for group in 0..self.groups.len() {
+                    self.scheduler.reschedule_now(group, now);
+                }
+                PollEvent::ConnectionUp
+            }
+            Err(err) => {
+                self.reconnect_attempts = self.reconnect_attempts.saturating_add(1);
+                let retry_in = self.reconnect.backoff(self.reconnect_attempts);
+                log::debug!(
+                    "Failed to connect (attempt {}), retrying in {retry_in:?}",
+                    self.reconnect_attempts
+                );
+                self.reconnect_at = now + retry_in;
+                PollEvent::ReconnectFailed {
+                    error: Error::Driver(err),
+                    retry_in,
+                }
+            }
+        }
+    }
+
+    fn connection_lost(&mut self, now: Instant) -> Vec<Index> {
+        log::warn!("Connection lost");
+        self.connection_state = ConnectionState::Disconnected;
+        self.reconnect_attempts = 0;
+        self.reconnect_at = now + self.reconnect.backoff(1);
+        for value in self.last_observed.iter_mut().flatten().flatten() {
+            value.quality = Quality::Bad;
+        }
+        self.groups
+            .iter()
+            .flatten()
+            .map(|point| point.index)
+            .collect()
+    }
+
+    /// Poll the next due group or reconnect
+    ///
+    /// Returns `None` if nothing is due. Failed polls are retried
+    /// according to the [`RetryPolicy`] of the group. After the
+    /// connection has been lost it is re-established according
+    /// to the [`ReconnectPolicy`].
+    pub fn poll_next(&mut self, now: &SystemInstant) -> Option<PollEvent<D::Error>> {
+        if self
+            .diagnostics_due_at()
+            .is_some_and(|due_at| due_at <= now.instant())
+        {
+            self.diagnostics_reported_at = now.instant();
+            return Some(PollEvent::Diagnostics(self.diagnostics.clone()));
+        }
+        if self.connection_state == ConnectionState::Disconnected {
+            if now.instant() < self.reconnect_at {
+                return None;
+            }
+            return Some(self.try_connect(now.instant()));
+        }
+        let group = self.scheduler.next_due(now.instant())?;
+        let points = &self.groups[group];
+        let requested_at = Instant::now();
+        let read_result = self.driver.read_point_group(points);
+        let round_trip_time = requested_at.elapsed();
+        if let Err(err) = &read_result {
+            self.diagnostics.record_failure(
+                group,
+                self.driver.request_error_kind(err),
+                self.driver.is_connection_lost(err),
+            );
+        } else {
+            self.diagnostics.record_success(group, round_trip_time);
+        }
+        let result = match read_result {
+            Ok(values) if values.len() == points.len() => Ok(ObservedValues {
+                observed_at: now.clone(),
+                values: values
+                    .into_iter()
+                    .map(|value| value.map(|value| TimestampedValue::new(value, now.clone())))
+                    .collect(),
+            }),
+            Ok(values) => Err(Error::UnexpectedValueCount {
+                expected: points.len(),
+                actual: values.len(),
+            }),
+            Err(err) if self.driver.is_connection_lost(&err) => {
+                let affected_registers = self.connection_lost(now.instant());
+                return Some(PollEvent::ConnectionDown {
+                    group,
+                    error: Error::Driver(err),
+                    affected_registers,
+                });
+            }
+            Err(err) => Err(Error::Driver(err)),
+        };
+        match &result {
+            Ok(observed) => {
+                self.scheduler.report_success(group, now.instant());
+                for (last_observed, value) in
+                    self.last_observed[group].iter_mut().zip(&observed.values)
+                {
+                    last_observed.clone_from(value);
+                }
+            }
+            Err(_) => {
+                if let Some(backoff) = self.scheduler.report_failure(group, now.instant()) {
+                    log::debug!("Retrying to poll group {group} in {backoff:?}");
+                } else {
+                    log::warn!("Failed to poll group {group}");
+                }
+            }
+        }
+        Some(PollEvent::Polled(PollResult { group, result }))
+    }
+
+    /// Find the point of a register
+    #[must_use]
+    pub fn find_point(&self, index: Index) -> Option<&Point<D::Address>> {
+        self.groups
+            .iter()
+            .flatten()
+            .find(|point| point.index == index)
+    }
+
+    /// The last observed value of a register
+    ///
+    /// The quality is [`Quality::Bad`] after the connection has been lost.
+    #[must_use]
+    pub fn last_observed(&self, index: Index) -> Option<&TimestampedValue<Value>> {
+        self.groups
+            .iter()
+            .flatten()
+            .zip(self.last_observed.iter().flatten())
+            .find(|(point, _)| point.index == index)
+            .and_then(|(_, value)| value.as_ref())
+    }
+
+    /// Write the value of a register
+    ///
+    /// A lost connection is only detected while polling.
+    pub fn write(&mut self, index: Index, value: Value) -> Result<(), Error<D::Error>> {
+        let Self {
+            driver,
+            groups,
+            connection_state,
+            diagnostics,
+            ..
+        } = self;
+        let (group, point) = groups
+            .iter()
+            .enumerate()
+            .find_map(|(group, points)| {
+                points
+                    .iter()
+                    .find(|point| point.index == index)
+                    .map(|point| (group, point))
+            })
+            .ok_or(Error::UnmappedRegister(index))?;
+        if *connection_state == ConnectionState::Disconnected {
+            return Err(Error::NotConnected);
+        }
+        let requested_at = Instant::now();
+        let result = driver.write_point(point, value);
+        let round_trip_time = requested_at.elapsed();
+        match &result {
+            Ok(()) => diagnostics.record_success(group, round_trip_time),
+            Err(err) => diagnostics.record_failure(
+                group,
+                driver.request_error_kind(err),
+                driver.is_connection_lost(err),
+            ),
+        }
+        result.map_err(Error::Driver)
+    }
+}
+
+#[cfg(test)]
+mod tests;
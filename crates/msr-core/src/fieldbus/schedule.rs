@@ -0,0 +1,296 @@
+//! Scheduling the cyclic polling of point groups
+
+use std::{
+    cmp::Reverse,
+    time::{Duration, Instant},
+};
+
+/// Retrying failed requests
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RetryPolicy {
+    /// The number of retries before waiting for the next regular cycle
+    pub max_retries: u32,
+
+    /// The delay before the first retry
+    ///
+    /// Doubled for each subsequent retry.
+    pub initial_backoff: Duration,
+
+    /// Upper bound for the delay between retries
+    pub max_backoff: Duration,
+}
+
+impl RetryPolicy {
+    /// Never retry failed requests
+    #[must_use]
+    pub const fn never() -> Self {
+        Self {
+            max_retries: 0,
+            initial_backoff: Duration::ZERO,
+            max_backoff: Duration::ZERO,
+        }
+    }
+
+    /// The delay before the given retry, starting at 1
+    #[must_use]
+    pub fn backoff(&self, retry: u32) -> Duration {
+        exponential_backoff(self.initial_backoff, self.max_backoff, retry)
+    }
+}
+
+/// Doubles the initial delay for each attempt, starting at 1
+pub(super) fn exponential_backoff(initial: Duration, max: Duration, attempt: u32) -> Duration {
+    let factor = 1u32
+        .checked_shl(attempt.saturating_sub(1))
+        .unwrap_or(u32::MAX);
+    initial.checked_mul(factor).unwrap_or(max).min(max)
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct PollingConfig {
+    /// The time between two subsequent regular polls
+    pub cycle_time: Duration,
+
+    /// Due groups with a higher priority are polled first
+    pub priority: u8,
+
+    pub retry: RetryPolicy,
+}
+
+#[derive(Debug, Clone)]
+struct GroupState {
+    config: PollingConfig,
+    /// The regular cycle, independent of retries
+    cycle_due_at: Instant,
+    due_at: Instant,
+    failed_retries: u32,
+}
+
+impl GroupState {
+    fn schedule_next_cycle(&mut self, now: Instant) {
+        self.failed_retries = 0;
+        let next_due_at = self.cycle_due_at + self.config.cycle_time;
+        // Missed cycles are skipped
+        self.cycle_due_at = if next_due_at > now {
+            next_due_at
+        } else {
+            now + self.config.cycle_time
+        };
+        self.due_at = self.cycle_due_at;
+    }
+}
+
+/// Determines which group of points needs to be polled next
+///
+/// Groups are identified by their index in the order of the
+/// configurations that have been passed when creating the
+/// scheduler. The scheduler is driven by the caller who passes
+/// the current time and reports the outcome of each poll.
+#[derive(Debug, Clone)]
+pub struct PollingScheduler {
+    groups: Vec<GroupState>,
+}
+
+impl PollingScheduler {
+    /// Create a new scheduler
+    ///
+    /// All groups are initially due.
+    #[must_use]
+    pub fn new(configs: impl IntoIterator<Item = PollingConfig>, now: Instant) -> Self {
+        let groups = configs
+            .into_iter()
+            .map(|config| GroupState {
+                config,
+                cycle_due_at: now,
+                due_at: now,
+                failed_retries: 0,
+            })
+            .collect();
+        Self { groups }
+    }
+
+    #[must_use]
+    pub fn group_count(&self) -> usize {
+        self.groups.len()
+    }
+
+    #[must_use]
+    pub fn config(&self, group: usize) -> Option<&PollingConfig> {
+        self.groups.get(group).map(|state| &state.config)
+    }
+
+    /// The next time when any group becomes due
+    #[must_use]
+    pub fn next_due_at(&self) -> Option<Instant> {
+        self.groups.iter().map(|state| state.due_at).min()
+    }
+
+    /// The group that should be polled now
+    ///
+    /// Returns the due group with the highest priority. Groups with
+    /// the same priority are polled in the order they became due.
+    #[must_use]
+    pub fn next_due(&self, now: Instant) -> Option<usize> {
+        self.groups
+            .iter()
+            .enumerate()
+            .filter(|(_, state)| state.due_at <= now)
+            .max_by_key(|(group, state)| {
+                (
+                    state.config.priority,
+                    Reverse(state.due_at),
+                    Reverse(*group),
+                )
+            })
+            .map(|(group, _)| group)
+    }
+
+    /// Schedule the next regular poll of a group
+    pub fn report_success(&mut self, group: usize, now: Instant) {
+        let Some(state) = self.groups.get_mut(group) else {
+            return;
+        };
+        state.schedule_next_cycle(now);
+    }
+
+    /// Schedule a retry or the next regular poll of a group
+    ///
+    /// Returns the delay until the retry or `None` if all retries
+    /// have been exhausted.
+    pub fn report_failure(&mut self, group: usize, now: Instant) -> Option<Duration> {
+        let state = self.groups.get_mut(group)?;
+        if state.failed_retries >= state.config.retry.max_retries {
+            state.schedule_next_cycle(now);
+            return None;
+        }
+        state.failed_retries += 1;
+        let backoff = state.config.retry.backoff(state.failed_retries);
+        state.due_at = now + backoff;
+        Some(backoff)
+    }
+
+    /// Poll a group as soon as possible, e.g. after reconnecting
+    pub fn reschedule_now(&mut self, group: usize, now: Instant) {
+        let Some(state) = self.groups.get_mut(group) else {
+            return;
+        };
+        state.due_at = state.due_at.min(now);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(cycle_time_millis: u64, priority: u8) -> PollingConfig {
+        PollingConfig {
+            cycle_time: Duration::from_millis(cycle_time_millis),
+            priority,
+            retry: RetryPolicy {
+                max_retries: 2,
+                initial_backoff: Duration::from_millis(10),
+                max_backoff: Duration::from_millis(15),
+            },
+        }
+    }
+
+    #[test]
+    fn exponential_backoff_is_bounded() {
+        let retry = RetryPolicy {
+            max_retries: u32::MAX,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(Duration::from_millis(100), retry.backoff(1));
+        assert_eq!(Duration::from_millis(200), retry.backoff(2));
+        assert_eq!(Duration::from_millis(800), retry.backoff(4));
+        assert_eq!(Duration::from_secs(1), retry.backoff(5));
+        assert_eq!(Duration::from_secs(1), retry.backoff(u32::MAX));
+    }
+
+    #[test]
+    fn poll_due_groups_by_priority() {
+        let t0 = Instant::now();
+        let mut scheduler = PollingScheduler::new([config(100, 0), config(50, 1)], t0);
+        assert_eq!(2, scheduler.group_count());
+        assert_eq!(Some(t0), scheduler.next_due_at());
+
+        assert_eq!(Some(1), scheduler.next_due(t0));
+        scheduler.report_success(1, t0);
+        assert_eq!(Some(0), scheduler.next_due(t0));
+        scheduler.report_success(0, t0);
+        assert_eq!(None, scheduler.next_due(t0));
+        assert_eq!(
+            Some(t0 + Duration::from_millis(50)),
+            scheduler.next_due_at()
+        );
+
+        let t1 = t0 + Duration::from_millis(100);
+        assert_eq!(Some(1), scheduler.next_due(t1));
+        scheduler.report_success(1, t1);
+        assert_eq!(Some(0), scheduler.next_due(t1));
+        scheduler.report_success(0, t1);
+        // The missed cycle of group 1 has been skipped
+        assert_eq!(
+            Some(t1 + Duration::from_millis(50)),
+            scheduler.next_due_at()
+        );
+    }
+
+    #[test]
+    fn poll_groups_with_same_priority_in_due_order() {
+        let t0 = Instant::now();
+        let mut scheduler = PollingScheduler::new([config(100, 0), config(50, 0)], t0);
+        assert_eq!(Some(0), scheduler.next_due(t0));
+        scheduler.report_success(0, t0);
+        scheduler.report_success(1, t0);
+        let t1 = t0 + Duration::from_millis(100);
+        assert_eq!(Some(1), scheduler.next_due(t1));
+    }
+
+    #[test]
+    fn retry_failed_polls_before_next_cycle() {
+        let t0 = Instant::now();
+        let mut scheduler = PollingScheduler::new([config(100, 0)], t0);
+        assert_eq!(
+            Some(Duration::from_millis(10)),
+            scheduler.report_failure(0, t0)
+        );
+        assert_eq!(
+            Some(t0 + Duration::from_millis(10)),
+            scheduler.next_due_at()
+        );
+        let t1 = t0 + Duration::from_millis(10);
+        assert_eq!(
+            Some(Duration::from_millis(15)),
+            scheduler.report_failure(0, t1)
+        );
+        let t2 = t1 + Duration::from_millis(15);
+        // Retries exhausted
+        assert_eq!(None, scheduler.report_failure(0, t2));
+        assert_eq!(
+            Some(t0 + Duration::from_millis(100)),
+            scheduler.next_due_at()
+        );
+
+        // Retries are available again
+        let t3 = t0 + Duration::from_millis(100);
+        assert!(scheduler.report_failure(0, t3).is_some());
+        scheduler.report_success(0, t3 + Duration::from_millis(10));
+        assert_eq!(
+            Some(t0 + Duration::from_millis(200)),
+            scheduler.next_due_at()
+        );
+    }
+
+    #[test]
+    fn reschedule_now() {
+        let t0 = Instant::now();
+        let mut scheduler = PollingScheduler::new([config(100, 0)], t0);
+        scheduler.report_success(0, t0);
+        let t1 = t0 + Duration::from_millis(1);
+        assert_eq!(None, scheduler.next_due(t1));
+        scheduler.reschedule_now(0, t1);
+        assert_eq!(Some(0), scheduler.next_due(t1));
+    }
+}
@@ -0,0 +1,344 @@
+use std::{
+    io::{Error as IoError, ErrorKind as IoErrorKind},
+    time::{Duration, Instant, SystemTime},
+};
+
+use super::*;
+
+#[derive(Debug, Default)]
+struct MockDriver {
+    connected: bool,
+    fail_connects: usize,
+    fail_reads: usize,
+    registers: Vec<(u16, Value)>,
+}
+
+impl Driver for MockDriver {
+    type Address = u16;
+    type Error = IoError;
+
+    fn connect(&mut self) -> Result<(), Self::Error> {
+        if self.fail_connects > 0 {
+            self.fail_connects -= 1;
+            return Err(IoErrorKind::ConnectionRefused.into());
+        }
+        self.connected = true;
+        Ok(())
+    }
+
+    fn read_point_group(
+        &mut self,
+        points: &[Point<Self::Address>],
+    ) -> Result<Vec<Option<Value>>, Self::Error> {
+        if !self.connected {
+            return Err(IoErrorKind::NotConnected.into());
+        }
+        if self.fail_reads > 0 {
+            self.fail_reads -= 1;
+            return Err(IoErrorKind::InvalidData.into());
+        }
+        Ok(points
+            .iter()
+            .map(|point| {
+                self.registers
+                    .iter()
+                    .find(|(address, _)| *address == point.address)
+                    .map(|(_, value)| value.clone())
+            })
+            .collect())
+    }
+
+    fn write_point(
+        &mut self,
+        point: &Point<Self::Address>,
+        value: Value,
+    ) -> Result<(), Self::Error> {
+        self.registers
+            .retain(|(address, _)| *address != point.address);
+        self.registers.push((point.address, value));
+        Ok(())
+    }
+
+    fn is_connection_lost(&self, err: &Self::Error) -> bool {
+        is_io_connection_lost(err)
+    }
+
+    fn request_error_kind(&self, err: &Self::Error) -> RequestErrorKind {
+        match err.kind() {
+            IoErrorKind::TimedOut => RequestErrorKind::Timeout,
+            IoErrorKind::InvalidData => RequestErrorKind::Checksum,
+            _ => RequestErrorKind::Other,
+        }
+    }
+}
+
+fn point(index: u64, address: u16) -> Point<u16> {
+    Point {
+        index: Index::new(index),
+        address,
+        value_type: ValueType::Scalar(crate::ScalarType::U16),
+    }
+}
+
+fn group(points: Vec<Point<u16>>, cycle_time_millis: u64) -> PointGroup<u16> {
+    PointGroup {
+        points,
+        polling: PollingConfig {
+            cycle_time: Duration::from_millis(cycle_time_millis),
+            priority: 0,
+            retry: RetryPolicy {
+                max_retries: 1,
+                initial_backoff: Duration::from_millis(10),
+                max_backoff: Duration::from_millis(10),
+            },
+        },
+    }
+}
+
+fn reconnect() -> ReconnectPolicy {
+    ReconnectPolicy {
+        initial_backoff: Duration::from_millis(100),
+        max_backoff: Duration::from_secs(1),
+    }
+}
+
+fn system_instant(t0: &SystemInstant, millis: u64) -> SystemInstant {
+    let offset = Duration::from_millis(millis);
+    SystemInstant::new(t0.system_time() + offset, t0.instant() + offset)
+}
+
+#[test]
+fn poll_and_write_mapped_registers() {
+    let t0 = SystemInstant::new(SystemTime::now(), Instant::now());
+    let driver = MockDriver {
+        registers: vec![(1, Value::from(1u16))],
+        ..Default::default()
+    };
+    let mut poller = Poller::new(
+        driver,
+        vec![group(vec![point(10, 1), point(11, 2)], 100)],
+        reconnect(),
+        &t0,
+    );
+    assert!(matches!(
+        poller.write(Index::new(11), Value::from(2u16)),
+        Err(Error::NotConnected)
+    ));
+    assert!(matches!(
+        poller.poll_next(&t0).unwrap(),
+        PollEvent::ConnectionUp
+    ));
+    assert_eq!(ConnectionState::Connected, poller.connection_state());
+
+    let Some(PollEvent::Polled(PollResult { group, result })) = poller.poll_next(&t0) else {
+        panic!("not polled");
+    };
+    assert_eq!(0, group);
+    let observed = result.unwrap();
+    assert_eq!(t0, observed.observed_at);
+    assert_eq!(
+        vec![
+            Some(TimestampedValue::new(Value::from(1u16), t0.clone())),
+            None
+        ],
+        observed.values
+    );
+    assert!(poller.poll_next(&t0).is_none());
+
+    poller.write(Index::new(11), Value::from(2u16)).unwrap();
+    assert!(matches!(
+        poller.write(Index::new(12), Value::from(3u16)),
+        Err(Error::UnmappedRegister(index)) if index == Index::new(12)
+    ));
+    assert_eq!(2, poller.find_point(Index::new(11)).unwrap().address);
+
+    let t1 = system_instant(&t0, 100);
+    let Some(PollEvent::Polled(PollResult { result, .. })) = poller.poll_next(&t1) else {
+        panic!("not polled");
+    };
+    let observed = result.unwrap();
+    assert_eq!(
+        vec![
+            Some(TimestampedValue::new(Value::from(1u16), t1.clone())),
+            Some(TimestampedValue::new(Value::from(2u16), t1.clone())),
+        ],
+        observed.values
+    );
+}
+
+fn poll_result(event: Option<PollEvent<IoError>>) -> Result<ObservedValues<Value>, Error<IoError>> {
+    let Some(PollEvent::Polled(PollResult { result, .. })) = event else {
+        panic!("not polled");
+    };
+    result
+}
+
+#[test]
+fn retry_failed_polls() {
+    let t0 = SystemInstant::new(SystemTime::now(), Instant::now());
+    let driver = MockDriver {
+        connected: true,
+        fail_reads: 2,
+        ..Default::default()
+    };
+    let mut poller = Poller::new(
+        driver,
+        vec![group(vec![point(10, 1)], 100)],
+        reconnect(),
+        &t0,
+    );
+    assert!(matches!(
+        poller.poll_next(&t0).unwrap(),
+        PollEvent::ConnectionUp
+    ));
+
+    assert!(matches!(
+        poll_result(poller.poll_next(&t0)),
+        Err(Error::Driver(_))
+    ));
+    let t1 = system_instant(&t0, 10);
+    assert_eq!(Some(t1.instant()), poller.next_due_at());
+    assert!(poll_result(poller.poll_next(&t1)).is_err());
+    // Retries exhausted
+    let t2 = system_instant(&t0, 100);
+    assert_eq!(Some(t2.instant()), poller.next_due_at());
+    assert!(poll_result(poller.poll_next(&t2)).is_ok());
+    assert_eq!(ConnectionState::Connected, poller.connection_state());
+}
+
+#[test]
+fn reconnect_after_connection_has_been_lost() {
+    let t0 = SystemInstant::new(SystemTime::now(), Instant::now());
+    let driver = MockDriver {
+        fail_connects: 1,
+        registers: vec![(1, Value::from(1u16))],
+        ..Default::default()
+    };
+    let mut poller = Poller::new(
+        driver,
+        vec![
+            group(vec![point(10, 1)], 100),
+            group(vec![point(20, 2)], 1_000),
+        ],
+        reconnect(),
+        &t0,
+    );
+
+    let Some(PollEvent::ReconnectFailed { retry_in, .. }) = poller.poll_next(&t0) else {
+        panic!("unexpected event");
+    };
+    assert_eq!(Duration::from_millis(100), retry_in);
+    assert!(poller.poll_next(&system_instant(&t0, 99)).is_none());
+    let t1 = system_instant(&t0, 100);
+    assert_eq!(Some(t1.instant()), poller.next_due_at());
+    assert!(matches!(
+        poller.poll_next(&t1).unwrap(),
+        PollEvent::ConnectionUp
+    ));
+    assert!(poll_result(poller.poll_next(&t1)).is_ok());
+    let last_observed = poller.last_observed(Index::new(10)).unwrap();
+    assert_eq!(Value::from(1u16), last_observed.value);
+    assert_eq!(Quality::Good, last_observed.quality);
+    assert!(poller.last_observed(Index::new(20)).is_none());
+
+    // The peer has closed the connection
+    poller.driver_mut().connected = false;
+    let Some(PollEvent::ConnectionDown {
+        group,
+        affected_registers,
+        ..
+    }) = poller.poll_next(&t1)
+    else {
+        panic!("unexpected event");
+    };
+    assert_eq!(1, group);
+    assert_eq!(vec![Index::new(10), Index::new(20)], affected_registers);
+    assert_eq!(ConnectionState::Disconnected, poller.connection_state());
+    assert_eq!(
+        Quality::Bad,
+        poller.last_observed(Index::new(10)).unwrap().quality
+    );
+
+    // All groups are polled immediately after reconnecting
+    let t2 = system_instant(&t1, 100);
+    assert_eq!(Some(t2.instant()), poller.next_due_at());
+    assert!(matches!(
+        poller.poll_next(&t2).unwrap(),
+        PollEvent::ConnectionUp
+    ));
+    assert!(poller.next_due_at().unwrap() <= t2.instant());
+    assert!(poll_result(poller.poll_next(&t2)).is_ok());
+    assert!(poll_result(poller.poll_next(&t2)).is_ok());
+    assert_eq!(
+        Quality::Good,
+        poller.last_observed(Index::new(10)).unwrap().quality
+    );
+}
+
+#[test]
+fn count_requests_and_report_diagnostics_periodically() {
+    let t0 = SystemInstant::new(SystemTime::now(), Instant::now());
+    let driver = MockDriver {
+        fail_reads: 1,
+        ..Default::default()
+    };
+    let mut poller = Poller::new(
+        driver,
+        vec![
+            group(vec![point(10, 1)], 100),
+            group(vec![point(20, 2)], 100),
+        ],
+        reconnect(),
+        &t0,
+    )
+    .with_diagnostics_interval(Duration::from_secs(1));
+    assert!(matches!(
+        poller.poll_next(&t0).unwrap(),
+        PollEvent::ConnectionUp
+    ));
+    assert!(poll_result(poller.poll_next(&t0)).is_err());
+    assert!(poll_result(poller.poll_next(&t0)).is_ok());
+    poller.write(Index::new(20), Value::from(2u16)).unwrap();
+
+    let diagnostics = poller.diagnostics();
+    assert_eq!(3, diagnostics.connection.requests);
+    assert_eq!(1, diagnostics.connection.checksum_errors);
+    assert_eq!(1, diagnostics.connection.failed_requests());
+    assert_eq!(2, diagnostics.connection.round_trip_time.count);
+    assert_eq!(1, diagnostics.groups[0].requests);
+    assert_eq!(1, diagnostics.groups[0].checksum_errors);
+    assert_eq!(2, diagnostics.groups[1].requests);
+    assert_eq!(0, diagnostics.groups[1].failed_requests());
+
+    // The connection is lost
+    poller.driver_mut().connected = false;
+    let t1 = system_instant(&t0, 100);
+    assert!(matches!(
+        poller.poll_next(&t1).unwrap(),
+        PollEvent::ConnectionDown { .. }
+    ));
+    assert_eq!(1, poller.diagnostics().connection.connection_losses);
+
+    let t2 = system_instant(&t0, 1_000);
+    assert_eq!(
+        Some(t1.instant() + Duration::from_millis(100)),
+        poller.next_due_at()
+    );
+    let Some(PollEvent::Diagnostics(diagnostics)) = poller.poll_next(&t2) else {
+        panic!("no diagnostics");
+    };
+    assert_eq!(poller.diagnostics(), &diagnostics);
+    assert_eq!(4, diagnostics.connection.requests);
+    // Not reported again before the interval has elapsed
+    assert!(matches!(
+        poller.poll_next(&t2).unwrap(),
+        PollEvent::ConnectionUp
+    ));
+    assert_eq!(
+        Some(system_instant(&t2, 1_000).instant()),
+        poller.diagnostics_due_at()
+    );
+
+    poller.reset_diagnostics();
+    assert_eq!(&Diagnostics::new(2), poller.diagnostics());
+}
@@ -0,0 +1,145 @@
+//! Replacing files atomically
+
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// Write the contents of a file atomically
+///
+/// Like [`std::fs::write`], but either the previous or the new contents
+/// are found at `path` if the process or the system crashes while writing.
+/// See [`write_atomically_with`] for details.
+pub fn write_atomically(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    write_atomically_with(path, |writer| writer.write_all(contents.as_ref()))
+}
+
+/// Write a file atomically by a function
+///
+/// The contents are written into a temporary file in the same
+/// directory, which is flushed to disk and then renamed to `path`.
+/// On Unix the directory is flushed to disk afterwards for persisting
+/// the rename.
+///
+/// The temporary file is removed if writing fails.
+pub fn write_atomically_with(
+    path: impl AsRef<Path>,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let tmp_path = tmp_path(path)?;
+    let res = write_and_rename(path, &tmp_path, write);
+    if res.is_err() {
+        // The temporary file might not exist
+        let _ = fs::remove_file(&tmp_path);
+    }
+    res
+}
+
+fn write_and_rename(
+    path: &Path,
+    tmp_path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> io::Result<()>,
+) -> io::Result<()> {
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(tmp_path)?;
+    let mut writer = BufWriter::new(file);
+    write(&mut writer)?;
+    let file = writer
+        .into_inner()
+        .map_err(io::IntoInnerError::into_error)?;
+    file.sync_all()?;
+    drop(file);
+    fs::rename(tmp_path, path)?;
+    sync_parent_dir(path)
+}
+
+/// A unique path for the temporary file in the same directory
+fn tmp_path(path: &Path) -> io::Result<PathBuf> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let Some(file_name) = path.file_name() else {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("no file name: {}", path.display()),
+        ));
+    };
+    let mut tmp_file_name = std::ffi::OsString::from(".");
+    tmp_file_name.push(file_name);
+    tmp_file_name.push(format!(
+        ".{}-{}.tmp",
+        process::id(),
+        COUNTER.fetch_add(1, Ordering::Relaxed)
+    ));
+    Ok(path.with_file_name(tmp_file_name))
+}
+
+#[cfg(target_family = "unix")]
+fn sync_parent_dir(path: &Path) -> io::Result<()> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    File::open(dir)?.sync_all()
+}
+
+#[cfg(not(target_family = "unix"))]
+#[allow(clippy::unnecessary_wraps)]
+fn sync_parent_dir(_path: &Path) -> io::Result<()> {
+    // Directories cannot be opened as files
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dir_entries(dir: &Path) -> Vec<PathBuf> {
+        fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect()
+    }
+
+    #[test]
+    fn create_and_replace_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("config.json");
+
+        write_atomically(&path, "first").unwrap();
+        assert_eq!("first", fs::read_to_string(&path).unwrap());
+
+        write_atomically(&path, "second").unwrap();
+        assert_eq!("second", fs::read_to_string(&path).unwrap());
+
+        assert_eq!(vec![path], dir_entries(dir.path()));
+    }
+
+    #[test]
+    fn keep_previous_contents_on_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state.yaml");
+        write_atomically(&path, "previous").unwrap();
+
+        let err = write_atomically_with(&path, |writer| {
+            writer.write_all(b"incomplete")?;
+            Err(io::Error::new(io::ErrorKind::Other, "failed"))
+        })
+        .unwrap_err();
+        assert_eq!("failed", err.to_string());
+
+        assert_eq!("previous", fs::read_to_string(&path).unwrap());
+        assert_eq!(vec![path], dir_entries(dir.path()));
+    }
+
+    #[test]
+    fn reject_path_without_file_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let err = write_atomically(dir.path().join(".."), "contents").unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidInput, err.kind());
+    }
+}
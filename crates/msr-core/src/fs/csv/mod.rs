@@ -2,7 +2,7 @@ use std::{
     fs::File,
     io::{Error as IoError, ErrorKind as IoErrorKind},
     result::Result as StdResult,
-    time::SystemTime,
+    time::{Instant, SystemTime},
 };
 
 use ::csv::{
@@ -19,12 +19,18 @@ use crate::{
 
 use super::{
     policy::{
-        OpenRollingFile, RollingFileConfig, RollingFileInfo, RollingFileInfoWithSize,
-        RollingFileLimits, RollingFileStatus as PolicyRollingFileStatus,
+        DurabilityPolicy, OpenRollingFile, RollingFileConfig, RollingFileInfo,
+        RollingFileInfoWithSize, RollingFileLimits, RollingFileStatus as PolicyRollingFileStatus,
     },
     WriteError, WriteResult,
 };
 
+mod reader;
+pub use self::reader::{RollingFileReader, RollingFileRecord, RollingFileRecords};
+
+mod recovery;
+pub use self::recovery::truncate_incomplete_trailing_record;
+
 type CountingFileWriter = CsvWriter<CountingWrite<File>>;
 
 #[derive(Error, Debug)]
@@ -89,15 +95,46 @@ struct RollingFile {
     status: RollingFileStatus,
     writer: CountingFileWriter,
     last_os_error_code: Option<i32>,
+    records_written_since_sync: u64,
+    synced_at: Instant,
 }
 
 impl RollingFile {
+    fn sync(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        self.writer.get_ref().get_ref().sync_data()?;
+        self.records_written_since_sync = 0;
+        self.synced_at = Instant::now();
+        Ok(())
+    }
+
+    fn sync_after_record_written(
+        &mut self,
+        durability: DurabilityPolicy,
+        now: Instant,
+    ) -> Result<()> {
+        let should_sync = match durability {
+            DurabilityPolicy::Never | DurabilityPolicy::OnFlush => false,
+            DurabilityPolicy::EveryRecords(records) => {
+                self.records_written_since_sync >= records.get()
+            }
+            DurabilityPolicy::EveryInterval(interval) => {
+                now.saturating_duration_since(self.synced_at) >= interval
+            }
+        };
+        if should_sync {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
     // Custom handling and transformation of I/O errors
     #[allow(clippy::panic_in_result_fn)] // unreachable!()
     fn after_record_written(&mut self, res: StdResult<(), ::csv::Error>) -> Result<WriteResult> {
         match res {
             Ok(()) => {
                 self.status.records_written += 1;
+                self.records_written_since_sync += 1;
                 // No error -> Reset last OS error
                 self.last_os_error_code = None;
                 Ok(Ok(()))
@@ -185,6 +222,8 @@ impl RollingFileWriter {
                         .has_headers(self.custom_header.is_none())
                         .from_writer(writer),
                     last_os_error_code: None,
+                    records_written_since_sync: 0,
+                    synced_at: starting_at.instant(),
                 };
                 Ok(Some(rolling_file))
             }
@@ -255,7 +294,11 @@ impl RollingFileWriter {
                 &self.config.limits,
             ) {
                 // Try to flush all buffered contents before closing the current file.
-                self.flush()?;
+                if self.config.durability == DurabilityPolicy::Never {
+                    self.flush()?;
+                } else {
+                    self.sync()?;
+                }
                 let closed_file_info = self.roll_file_now(now)?;
                 let created_new_file = closed_file_info.is_some();
                 (closed_file_info, created_new_file)
@@ -291,7 +334,9 @@ impl RollingFileWriter {
         let closed_file_info = self.before_writing(now, now_nanoseconds_offset)?;
         let record_written = if let Some(current_file) = self.current_file.as_mut() {
             let res = current_file.writer.write_record(record);
-            current_file.after_record_written(res)?
+            let record_written = current_file.after_record_written(res)?;
+            current_file.sync_after_record_written(self.config.durability, now.instant())?;
+            record_written
         } else {
             Err(WriteError::NoFile)
         };
@@ -308,20 +353,42 @@ impl RollingFileWriter {
         let closed_file_info = self.before_writing(now, now_nanoseconds_offset)?;
         let record_written = if let Some(current_file) = self.current_file.as_mut() {
             let res = current_file.writer.serialize(record);
-            current_file.after_record_written(res)?
+            let record_written = current_file.after_record_written(res)?;
+            current_file.sync_after_record_written(self.config.durability, now.instant())?;
+            record_written
         } else {
             Err(WriteError::NoFile)
         };
         Ok((record_written, closed_file_info))
     }
 
-    /// Flush all written records to disk, clearing the internal cache
+    /// Flush all written records, clearing the internal cache
+    ///
+    /// Synchronizes the current file with the storage device
+    /// if required by [`DurabilityPolicy::OnFlush`].
     pub fn flush(&mut self) -> Result<()> {
+        if self.config.durability == DurabilityPolicy::OnFlush {
+            return self.sync();
+        }
         if let Some(current_file) = self.current_file.as_mut() {
             current_file.writer.flush()?;
         }
         Ok(())
     }
+
+    /// Flush all written records and synchronize the current file
+    /// with the storage device
+    pub fn sync(&mut self) -> Result<()> {
+        if let Some(current_file) = self.current_file.as_mut() {
+            current_file.sync()?;
+        }
+        Ok(())
+    }
+
+    /// Replace the durability policy
+    pub fn set_durability(&mut self, durability: DurabilityPolicy) {
+        self.config.durability = durability;
+    }
 }
 
 #[cfg(test)]
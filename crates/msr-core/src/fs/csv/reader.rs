@@ -0,0 +1,338 @@
+use std::{
+    fs::File,
+    io::{
+        BufRead as _, BufReader, Error as IoError, ErrorKind as IoErrorKind, Seek as _, SeekFrom,
+    },
+    time::{Duration, SystemTime},
+    vec,
+};
+
+use ::csv::{
+    Position, Reader as CsvReader, ReaderBuilder as CsvReaderBuilder, StringRecord, Terminator,
+};
+
+use crate::fs::policy::{
+    FileInfoFilter, RollingFileInfoWithSize, RollingFileSystem, SystemTimeRange,
+};
+
+use super::Result;
+
+// Segments are read sequentially below this size
+const MIN_SEEK_BYTES: u64 = 4_096;
+
+/// A record read from a rolling file
+#[derive(Debug, Clone)]
+pub struct RollingFileRecord {
+    /// The creation time of the file that contains the record
+    pub file_created_at: SystemTime,
+
+    /// The creation time of the record
+    pub created_at: SystemTime,
+
+    pub record: StringRecord,
+}
+
+/// Reads records from rolling files within a time range
+///
+/// The reader counterpart of [`RollingFileWriter`](super::RollingFileWriter).
+///
+/// The first column of each record must contain the offset in
+/// nanoseconds since the creation of the file, i.e. the _created at
+/// offset_. The offsets must increase monotonically within each file.
+///
+/// The relevant files are selected by the time stamps in their names.
+/// Within large files the first record is located by a binary search
+/// that resynchronizes on line breaks. Fields with embedded line breaks
+/// that resemble a valid record might misguide this search.
+#[derive(Debug, Clone)]
+pub struct RollingFileReader {
+    system: RollingFileSystem,
+}
+
+impl RollingFileReader {
+    #[must_use]
+    pub const fn new(system: RollingFileSystem) -> Self {
+        Self { system }
+    }
+
+    /// Read all records that have been created within a time range
+    ///
+    /// Both bounds are inclusive. Records are read lazily in
+    /// chronological order.
+    pub fn read_records(
+        &self,
+        since: Option<SystemTime>,
+        until: Option<SystemTime>,
+    ) -> Result<RollingFileRecords> {
+        let filter = if since.is_none() && until.is_none() {
+            FileInfoFilter::default()
+        } else {
+            let since = since.unwrap_or(SystemTime::UNIX_EPOCH);
+            let until = until.unwrap_or_else(SystemTime::now);
+            FileInfoFilter {
+                created_at: Some(SystemTimeRange::InclusiveUpperBound(since..=until)),
+            }
+        };
+        let files = self
+            .system
+            .read_all_dir_entries_filtered_chronologically(&filter)?;
+        Ok(RollingFileRecords {
+            files: files.into_iter(),
+            current_file: None,
+            since,
+            until,
+        })
+    }
+}
+
+#[derive(Debug)]
+struct CurrentFile {
+    created_at: SystemTime,
+    reader: CsvReader<File>,
+}
+
+/// Lazily reads the records of [`RollingFileReader::read_records()`]
+#[derive(Debug)]
+pub struct RollingFileRecords {
+    files: vec::IntoIter<RollingFileInfoWithSize>,
+    current_file: Option<CurrentFile>,
+    since: Option<SystemTime>,
+    until: Option<SystemTime>,
+}
+
+impl RollingFileRecords {
+    fn open_next_file(&mut self) -> Result<bool> {
+        let Some(file_info) = self.files.next() else {
+            return Ok(false);
+        };
+        let created_at = file_info.created_at.into();
+        let mut reader = CsvReaderBuilder::new()
+            .has_headers(true)
+            .terminator(Terminator::CRLF)
+            .from_path(&file_info.path)?;
+        if let Some(since) = self.since {
+            if created_at < since {
+                let offset = since.duration_since(created_at).unwrap_or_default();
+                seek_before_offset(&mut reader, &file_info, offset)?;
+            }
+        }
+        self.current_file = Some(CurrentFile { created_at, reader });
+        Ok(true)
+    }
+
+    fn read_next(&mut self) -> Result<Option<RollingFileRecord>> {
+        loop {
+            let Some(current_file) = &mut self.current_file else {
+                if self.open_next_file()? {
+                    continue;
+                }
+                return Ok(None);
+            };
+            let mut record = StringRecord::new();
+            if !current_file.reader.read_record(&mut record)? {
+                self.current_file = None;
+                continue;
+            }
+            let offset = parse_created_at_offset(&record)?;
+            let created_at = current_file.created_at + offset;
+            if self.since.is_some_and(|since| created_at < since) {
+                continue;
+            }
+            if self.until.is_some_and(|until| created_at > until) {
+                // All subsequent records have been created even later
+                self.current_file = None;
+                self.files = Vec::new().into_iter();
+                return Ok(None);
+            }
+            return Ok(Some(RollingFileRecord {
+                file_created_at: current_file.created_at,
+                created_at,
+                record,
+            }));
+        }
+    }
+}
+
+impl Iterator for RollingFileRecords {
+    type Item = Result<RollingFileRecord>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
+
+fn parse_created_at_offset(record: &StringRecord) -> Result<Duration> {
+    record
+        .get(0)
+        .and_then(|field| field.parse().ok())
+        .map(Duration::from_nanos)
+        .ok_or_else(|| {
+            IoError::new(
+                IoErrorKind::InvalidData,
+                format!("invalid created at offset in record {record:?}"),
+            )
+            .into()
+        })
+}
+
+/// Position the reader before the first record with the given offset
+///
+/// The reader might be positioned before some preceding records that
+/// need to be skipped.
+fn seek_before_offset(
+    reader: &mut CsvReader<File>,
+    file_info: &RollingFileInfoWithSize,
+    offset: Duration,
+) -> Result<()> {
+    let field_count = reader.headers()?.len();
+    // All records before this byte offset have been created before
+    let mut lower_bound = reader.position().byte();
+    let mut upper_bound = file_info.size_in_bytes;
+    if upper_bound.saturating_sub(lower_bound) < MIN_SEEK_BYTES {
+        return Ok(());
+    }
+    let mut probe = BufReader::new(File::open(&file_info.path)?);
+    let mut line = Vec::new();
+    let mut record = StringRecord::new();
+    while upper_bound.saturating_sub(lower_bound) >= MIN_SEEK_BYTES {
+        let middle = lower_bound + (upper_bound - lower_bound) / 2;
+        // Skip the remainder of the current line
+        probe.seek(SeekFrom::Start(middle))?;
+        line.clear();
+        let record_start = middle + probe.read_until(b'\n', &mut line)? as u64;
+        if record_start >= upper_bound {
+            upper_bound = middle;
+            continue;
+        }
+        reader.seek(byte_position(record_start))?;
+        let created_before = reader.read_record(&mut record).unwrap_or(false)
+            && record.len() == field_count
+            && parse_created_at_offset(&record).is_ok_and(|probed| probed < offset);
+        if created_before {
+            lower_bound = record_start;
+        } else {
+            upper_bound = middle;
+        }
+    }
+    reader.seek(byte_position(lower_bound))?;
+    Ok(())
+}
+
+fn byte_position(byte: u64) -> Position {
+    let mut position = Position::new();
+    position.set_byte(byte);
+    position
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use tempfile::TempDir;
+
+    use crate::{
+        fs::{
+            csv::RollingFileWriter,
+            policy::{
+                DurabilityPolicy, RollingFileConfig, RollingFileLimits, RollingFileNameTemplate,
+            },
+        },
+        time::SystemInstant,
+    };
+
+    use super::*;
+
+    const RECORDS_PER_FILE: u64 = 1_000;
+
+    const FILE_COUNT: u64 = 3;
+
+    fn write_files(temp_dir: &TempDir) -> (RollingFileSystem, SystemTime) {
+        let system = RollingFileSystem {
+            base_path: temp_dir.path().to_path_buf(),
+            file_name_template: RollingFileNameTemplate {
+                prefix: "prefix_".into(),
+                suffix: "_suffix.csv".into(),
+                with_sequence_number: false,
+            },
+        };
+        let config = RollingFileConfig {
+            system: system.clone(),
+            limits: RollingFileLimits {
+                max_records_written: Some(RECORDS_PER_FILE),
+                ..Default::default()
+            },
+            durability: DurabilityPolicy::Never,
+            archival: None,
+        };
+        let header = StringRecord::from(vec!["created_at_offset_ns", "value"]);
+        let mut writer = RollingFileWriter::new(config, Some(header));
+        // All records must have been created in the past
+        let started_at = SystemInstant::new(
+            SystemTime::now() - Duration::from_secs(RECORDS_PER_FILE * FILE_COUNT + 60),
+            Instant::now(),
+        );
+        let mut file_created_at = started_at.clone();
+        for index in 0..RECORDS_PER_FILE * FILE_COUNT {
+            // One record per second
+            let now = started_at.clone() + Duration::from_secs(index);
+            if index % RECORDS_PER_FILE == 0 {
+                file_created_at = now.clone();
+            }
+            let offset = (now.instant() - file_created_at.instant()).as_nanos() as u64;
+            let (written, _) = writer
+                .write_record(&now, offset, [offset.to_string(), index.to_string()])
+                .unwrap();
+            assert!(written.is_ok());
+        }
+        writer.flush().unwrap();
+        (system, started_at.system_time())
+    }
+
+    fn read_indexes(
+        reader: &RollingFileReader,
+        since: Option<SystemTime>,
+        until: Option<SystemTime>,
+    ) -> Vec<u64> {
+        reader
+            .read_records(since, until)
+            .unwrap()
+            .map(|record| record.unwrap().record[1].parse().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn read_all_records() {
+        let temp_dir = TempDir::new().unwrap();
+        let (system, _) = write_files(&temp_dir);
+        let reader = RollingFileReader::new(system);
+        let expected: Vec<_> = (0..RECORDS_PER_FILE * FILE_COUNT).collect();
+        assert_eq!(expected, read_indexes(&reader, None, None));
+    }
+
+    #[test]
+    fn read_records_within_time_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let (system, started_at) = write_files(&temp_dir);
+        let reader = RollingFileReader::new(system);
+        let at = |index| started_at + Duration::from_secs(index);
+        for (since, until) in [
+            (0, 0),
+            (1, 998),
+            (500, 1_500),
+            (999, 1_000),
+            (1_234, 2_999),
+            (2_999, 2_999),
+        ] {
+            let expected: Vec<_> = (since..=until).collect();
+            assert_eq!(
+                expected,
+                read_indexes(&reader, Some(at(since)), Some(at(until)))
+            );
+        }
+        let expected: Vec<_> = (2_500..RECORDS_PER_FILE * FILE_COUNT).collect();
+        assert_eq!(expected, read_indexes(&reader, Some(at(2_500)), None));
+        let expected: Vec<_> = (0..=10).collect();
+        assert_eq!(expected, read_indexes(&reader, None, Some(at(10))));
+        assert!(read_indexes(&reader, Some(at(3_000)), None).is_empty());
+    }
+}
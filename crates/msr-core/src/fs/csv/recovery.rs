@@ -0,0 +1,100 @@
+use std::{
+    fs::OpenOptions,
+    io::{Read as _, Seek as _, SeekFrom},
+    path::Path,
+};
+
+use ::csv::{ByteRecord, ReaderBuilder as CsvReaderBuilder, Terminator};
+
+use super::Result;
+
+/// Remove an incomplete record from the end of a CSV file
+///
+/// The last record of a file is incomplete if it is not terminated
+/// by a line break, e.g. after a power failure while writing. Parsing
+/// such a file or appending to it would fail.
+///
+/// Returns the number of bytes that have been removed.
+pub fn truncate_incomplete_trailing_record(path: &Path) -> Result<u64> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    let file_len = file.metadata()?.len();
+    if file_len == 0 {
+        return Ok(0);
+    }
+    file.seek(SeekFrom::Start(file_len - 1))?;
+    let mut last_byte = [0u8];
+    file.read_exact(&mut last_byte)?;
+    if matches!(last_byte[0], b'\n' | b'\r') {
+        return Ok(0);
+    }
+    // The position of the last record needs to be determined by
+    // parsing, because quoted fields might contain line breaks
+    file.rewind()?;
+    let mut reader = CsvReaderBuilder::new()
+        .has_headers(false)
+        .flexible(true)
+        .terminator(Terminator::CRLF)
+        .from_reader(&mut file);
+    let mut record = ByteRecord::new();
+    let mut last_record_start = 0;
+    loop {
+        let record_start = reader.position().byte();
+        if !reader.read_byte_record(&mut record)? {
+            break;
+        }
+        last_record_start = record_start;
+    }
+    drop(reader);
+    // Keep the line break that terminates the preceding record
+    file.seek(SeekFrom::Start(last_record_start))?;
+    let mut next_bytes = Vec::with_capacity(2);
+    (&mut file).take(2).read_to_end(&mut next_bytes)?;
+    last_record_start += next_bytes
+        .iter()
+        .take_while(|byte| matches!(byte, b'\n' | b'\r'))
+        .count() as u64;
+    file.set_len(last_record_start)?;
+    file.sync_data()?;
+    let removed_len = file_len - last_record_start;
+    log::warn!(
+        "Removed incomplete trailing record ({removed_len} byte(s)) from {}",
+        path.display()
+    );
+    Ok(removed_len)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn truncate(contents: &str) -> (u64, String) {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("file.csv");
+        fs::write(&path, contents).unwrap();
+        let removed_len = truncate_incomplete_trailing_record(&path).unwrap();
+        (removed_len, fs::read_to_string(&path).unwrap())
+    }
+
+    #[test]
+    fn keep_complete_records() {
+        assert_eq!((0, String::new()), truncate(""));
+        assert_eq!((0, "a,b\n".into()), truncate("a,b\n"));
+        assert_eq!((0, "a,b\r".into()), truncate("a,b\r"));
+        assert_eq!((0, "a,b\r\n1,2\r\n".into()), truncate("a,b\r\n1,2\r\n"));
+        assert_eq!(
+            (0, "a,b\n1,\"x\ny\"\n".into()),
+            truncate("a,b\n1,\"x\ny\"\n")
+        );
+    }
+
+    #[test]
+    fn remove_incomplete_trailing_record() {
+        assert_eq!((3, String::new()), truncate("a,b"));
+        assert_eq!((2, "a,b\n1,2\n".into()), truncate("a,b\n1,2\n3,"));
+        assert_eq!((1, "a,b\r\n".into()), truncate("a,b\r\n1"));
+        // Quoted line breaks
+        assert_eq!((6, "a,b\n".into()), truncate("a,b\n1,\"x\ny"));
+    }
+}
@@ -15,6 +15,7 @@ fn write_records_with_max_bytes_written_limit() {
             file_name_template: RollingFileNameTemplate {
                 prefix: "prefix_".into(),
                 suffix: "_suffix.csv".into(),
+                with_sequence_number: false,
             },
         },
         limits: RollingFileLimits {
@@ -23,6 +24,8 @@ fn write_records_with_max_bytes_written_limit() {
             max_nanoseconds_offset: None,
             interval: None,
         },
+        durability: DurabilityPolicy::Never,
+        archival: None,
     };
     let mut writer = RollingFileWriter::new(config, None);
     assert!(writer.current_file_info().is_none());
@@ -64,6 +67,7 @@ fn write_records_with_max_records_written_limits() {
             file_name_template: RollingFileNameTemplate {
                 prefix: "prefix_".into(),
                 suffix: "_suffix.csv".into(),
+                with_sequence_number: false,
             },
         },
         limits: RollingFileLimits {
@@ -72,6 +76,8 @@ fn write_records_with_max_records_written_limits() {
             max_nanoseconds_offset: None,
             interval: None,
         },
+        durability: DurabilityPolicy::Never,
+        archival: None,
     };
     let mut writer = RollingFileWriter::new(config, None);
     assert!(writer.current_file_info().is_none());
@@ -102,3 +108,41 @@ fn write_records_with_max_records_written_limits() {
         closed_file_info.map(ClosedFileInfo::into_inner).as_ref()
     );
 }
+
+#[test]
+fn sync_records_according_to_durability_policy() {
+    let temp_dir = TempDir::new().unwrap();
+    let config = RollingFileConfig {
+        system: RollingFileSystem {
+            base_path: temp_dir.path().to_path_buf(),
+            file_name_template: RollingFileNameTemplate {
+                prefix: "prefix_".into(),
+                suffix: "_suffix.csv".into(),
+                with_sequence_number: false,
+            },
+        },
+        limits: RollingFileLimits::default(),
+        durability: DurabilityPolicy::EveryRecords(2.try_into().unwrap()),
+        archival: None,
+    };
+    let mut writer = RollingFileWriter::new(config, None);
+    let read_file = |writer: &RollingFileWriter| {
+        std::fs::read_to_string(&writer.current_file_info().unwrap().path).unwrap()
+    };
+
+    let now = SystemInstant::now();
+    let (record_written, _) = writer.write_record(&now, 0, ["hello", "1.0"]).unwrap();
+    assert!(record_written.is_ok());
+    // Still buffered
+    assert!(read_file(&writer).is_empty());
+    let (record_written, _) = writer.write_record(&now, 0, ["world", "-1.0"]).unwrap();
+    assert!(record_written.is_ok());
+    assert_eq!("hello,1.0\nworld,-1.0\n", read_file(&writer));
+
+    writer.set_durability(DurabilityPolicy::OnFlush);
+    let (record_written, _) = writer.write_record(&now, 0, ["!", "0.0"]).unwrap();
+    assert!(record_written.is_ok());
+    assert_eq!("hello,1.0\nworld,-1.0\n", read_file(&writer));
+    writer.flush().unwrap();
+    assert_eq!("hello,1.0\nworld,-1.0\n!,0.0\n", read_file(&writer));
+}
@@ -0,0 +1,152 @@
+//! Preventing concurrent access to data directories
+
+use std::{
+    fs::{self, File},
+    io::{Error as IoError, Write as _},
+    path::{Path, PathBuf},
+    process,
+};
+
+use thiserror::Error;
+
+/// The name of the lock file within a locked directory
+pub const LOCK_FILE_NAME: &str = ".msr.lock";
+
+#[derive(Error, Debug)]
+pub enum LockDirError {
+    #[error("directory {} is already in use{}", .dir_path.display(), .holder_pid.map(|pid| format!(" by process {pid}")).unwrap_or_default())]
+    InUse {
+        dir_path: PathBuf,
+
+        /// The id of the process that holds the lock (if known)
+        holder_pid: Option<u32>,
+    },
+
+    #[error(transparent)]
+    Io(#[from] IoError),
+}
+
+/// An advisory lock on a directory
+///
+/// Exclusively locks a lock file within the directory that contains
+/// the id of the owning process. The lock is released when dropped
+/// or when the owning process terminates. The lock file itself is
+/// left behind.
+///
+/// Only processes that use the same locking mechanism are excluded,
+/// i.e. other processes could still access the directory.
+#[derive(Debug)]
+pub struct DirLock {
+    file_path: PathBuf,
+    _file: File,
+}
+
+impl DirLock {
+    /// Lock a directory
+    ///
+    /// Fails with [`LockDirError::InUse`] without blocking if the
+    /// directory is already locked, even if by the current process.
+    pub fn try_acquire(dir_path: impl AsRef<Path>) -> Result<Self, LockDirError> {
+        let dir_path = dir_path.as_ref();
+        let file_path = dir_path.join(LOCK_FILE_NAME);
+        let Some(mut file) = try_lock_file(&file_path)? else {
+            let holder_pid = fs::read_to_string(&file_path)
+                .ok()
+                .and_then(|contents| contents.trim().parse().ok());
+            return Err(LockDirError::InUse {
+                dir_path: dir_path.to_path_buf(),
+                holder_pid,
+            });
+        };
+        // The contents are only written after the lock has been acquired
+        file.set_len(0)?;
+        writeln!(file, "{}", process::id())?;
+        Ok(Self {
+            file_path,
+            _file: file,
+        })
+    }
+
+    /// The path of the lock file
+    #[must_use]
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+}
+
+fn lock_file_open_options() -> fs::OpenOptions {
+    let mut open_options = fs::OpenOptions::new();
+    open_options.read(true).write(true).create(true);
+    open_options
+}
+
+/// Returns `None` if the lock is held by someone else
+#[cfg(target_family = "unix")]
+#[allow(unsafe_code)]
+fn try_lock_file(file_path: &Path) -> Result<Option<File>, IoError> {
+    use std::os::unix::io::AsRawFd as _;
+    let file = lock_file_open_options().open(file_path)?;
+    // SAFETY: The file descriptor remains valid while the file is open
+    let res = unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) };
+    if res == 0 {
+        return Ok(Some(file));
+    }
+    let err = IoError::last_os_error();
+    if err.raw_os_error() == Some(libc::EWOULDBLOCK) {
+        return Ok(None);
+    }
+    Err(err)
+}
+
+/// Returns `None` if the lock is held by someone else
+#[cfg(target_family = "windows")]
+fn try_lock_file(file_path: &Path) -> Result<Option<File>, IoError> {
+    use std::os::windows::fs::OpenOptionsExt as _;
+    // ERROR_SHARING_VIOLATION
+    const SHARING_VIOLATION: i32 = 32;
+    // Opening the file without sharing excludes all other handles
+    match lock_file_open_options().share_mode(0).open(file_path) {
+        Ok(file) => Ok(Some(file)),
+        Err(err) if err.raw_os_error() == Some(SHARING_VIOLATION) => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Returns `None` if the lock is held by someone else
+#[cfg(not(any(target_family = "unix", target_family = "windows")))]
+fn try_lock_file(file_path: &Path) -> Result<Option<File>, IoError> {
+    log::warn!("Locking of {} is not supported", file_path.display());
+    lock_file_open_options().open(file_path).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lock_dir_exclusively() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let dir_lock = DirLock::try_acquire(temp_dir.path()).unwrap();
+        assert_eq!(temp_dir.path().join(LOCK_FILE_NAME), dir_lock.file_path());
+
+        let err = DirLock::try_acquire(temp_dir.path()).unwrap_err();
+        assert!(matches!(
+            &err,
+            LockDirError::InUse { dir_path, holder_pid: Some(pid) }
+                if dir_path == temp_dir.path() && *pid == process::id()
+        ));
+        assert!(err
+            .to_string()
+            .ends_with(&format!(" by process {}", process::id())));
+
+        drop(dir_lock);
+        assert!(DirLock::try_acquire(temp_dir.path()).is_ok());
+    }
+
+    #[test]
+    fn fail_on_missing_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let err = DirLock::try_acquire(temp_dir.path().join("missing")).unwrap_err();
+        assert!(matches!(err, LockDirError::Io(_)));
+    }
+}
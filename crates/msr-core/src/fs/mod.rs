@@ -4,6 +4,15 @@ use thiserror::Error;
 
 pub mod policy;
 
+mod atomic;
+pub use self::atomic::{write_atomically, write_atomically_with};
+
+mod lock;
+pub use self::lock::{DirLock, LockDirError, LOCK_FILE_NAME};
+
+mod space;
+pub use self::space::{disk_space, DiskSpace};
+
 #[cfg(feature = "csv-storage")]
 pub mod csv;
 
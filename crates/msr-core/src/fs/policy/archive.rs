@@ -0,0 +1,344 @@
+//! Archiving rolling files on secondary storage
+
+use std::{
+    ffi::OsStr,
+    fmt::Write as _,
+    fs::{self, File},
+    io::{self, Error as IoError, ErrorKind as IoErrorKind, Result as IoResult},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
+
+use crate::fs::{write_atomically, write_atomically_with};
+
+use super::{FileInfoFilter, RollingFileInfoWithSize, RollingFileSystem, SystemTimeRange};
+
+/// Appended to the file name prefix for naming the index file
+pub const ARCHIVE_INDEX_FILE_NAME_SUFFIX: &str = "archive-index.tsv";
+
+/// Move old files into an archive instead of deleting them
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct RollingFileArchival {
+    /// The directory of the archive, e.g. on slower and cheaper storage
+    pub archive_path: PathBuf,
+
+    /// Files that only contain records older than this are archived
+    pub min_age: Duration,
+}
+
+impl RollingFileArchival {
+    /// Files with records created before this time are archived
+    #[must_use]
+    pub fn archive_created_before(&self, now: SystemTime) -> SystemTime {
+        now.checked_sub(self.min_age)
+            .unwrap_or(SystemTime::UNIX_EPOCH)
+    }
+}
+
+// Moving files by renaming fails across file systems
+fn move_file(from: &Path, to: &Path) -> IoResult<()> {
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    write_atomically_with(to, |writer| {
+        io::copy(&mut File::open(from)?, writer).map(drop)
+    })?;
+    fs::remove_file(from)
+}
+
+fn invalid_index_line(line: &str) -> IoError {
+    IoError::new(
+        IoErrorKind::InvalidData,
+        format!("invalid archive index entry: {line}"),
+    )
+}
+
+impl RollingFileSystem {
+    fn archive_index_path(&self, archive_path: &Path) -> PathBuf {
+        let mut file_name = self.file_name_template.prefix.clone();
+        file_name.push_str(ARCHIVE_INDEX_FILE_NAME_SUFFIX);
+        archive_path.join(file_name)
+    }
+
+    /// Read the index of all archived files
+    ///
+    /// The entries are sorted by _created at_ in ascending order.
+    /// Returns an empty index if nothing has been archived yet.
+    pub fn read_archive_index(
+        &self,
+        archive_path: &Path,
+    ) -> IoResult<Vec<RollingFileInfoWithSize>> {
+        let contents = match fs::read_to_string(self.archive_index_path(archive_path)) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == IoErrorKind::NotFound => return Ok(Vec::new()),
+            Err(err) => return Err(err),
+        };
+        // Format: <created at>\t<size in bytes>\t<file name>
+        contents
+            .lines()
+            .map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                let (Some(created_at), Some(size_in_bytes), Some(file_name)) =
+                    (fields.next(), fields.next(), fields.next())
+                else {
+                    return Err(invalid_index_line(line));
+                };
+                Ok(RollingFileInfoWithSize {
+                    path: archive_path.join(file_name),
+                    created_at: created_at.parse().map_err(|_| invalid_index_line(line))?,
+                    size_in_bytes: size_in_bytes
+                        .parse()
+                        .map_err(|_| invalid_index_line(line))?,
+                })
+            })
+            .collect()
+    }
+
+    fn write_archive_index(
+        &self,
+        archive_path: &Path,
+        index: &mut [RollingFileInfoWithSize],
+    ) -> IoResult<()> {
+        index.sort_unstable_by(RollingFileInfoWithSize::cmp_created_at);
+        let mut contents = String::new();
+        for file_info in index.iter() {
+            let file_name = file_info
+                .path
+                .file_name()
+                .and_then(|file_name| file_name.to_str())
+                .ok_or_else(|| {
+                    IoError::new(
+                        IoErrorKind::InvalidInput,
+                        format!("invalid file name: {}", file_info.path.display()),
+                    )
+                })?;
+            // Writing into a string never fails
+            let _ = writeln!(
+                contents,
+                "{}\t{}\t{}",
+                file_info.created_at, file_info.size_in_bytes, file_name
+            );
+        }
+        write_atomically(self.archive_index_path(archive_path), contents)
+    }
+
+    /// Move all files that only contain records created before the given time into an archive
+    ///
+    /// The archived files are added to the index of the archive.
+    /// Returns the archived files.
+    pub fn archive_files_created_before(
+        &self,
+        archive_path: &Path,
+        created_before: SystemTime,
+    ) -> IoResult<Vec<RollingFileInfoWithSize>> {
+        let mut file_infos =
+            self.read_all_dir_entries_filtered_chronologically(&FileInfoFilter {
+                created_at: Some(SystemTimeRange::ExclusiveUpperBound(
+                    SystemTime::UNIX_EPOCH..created_before,
+                )),
+            })?;
+        // The last file might contain records that have been created later
+        file_infos.pop();
+        if file_infos.is_empty() {
+            return Ok(file_infos);
+        }
+        fs::create_dir_all(archive_path)?;
+        let mut index = self.read_archive_index(archive_path)?;
+        let mut archived = Vec::with_capacity(file_infos.len());
+        let mut res = Ok(());
+        for file_info in file_infos {
+            let Some(file_name) = file_info.path.file_name() else {
+                continue;
+            };
+            let archived_path = archive_path.join(file_name);
+            log::info!(
+                "Archiving file {} as {}",
+                file_info.path.display(),
+                archived_path.display()
+            );
+            if let Err(err) = move_file(&file_info.path, &archived_path) {
+                res = Err(err);
+                break;
+            }
+            archived.push(RollingFileInfoWithSize {
+                path: archived_path,
+                ..file_info
+            });
+        }
+        if !archived.is_empty() {
+            index.extend(archived.iter().cloned());
+            self.write_archive_index(archive_path, &mut index)?;
+        }
+        res.map(|()| archived)
+    }
+
+    /// Move an archived file back
+    ///
+    /// The file is identified by its name, because multiple files
+    /// with sequence numbers might have been created at the same time.
+    /// The file is removed from the index of the archive. Returns
+    /// the restored file or `None` if the file has not been archived.
+    pub fn restore_archived_file(
+        &self,
+        archive_path: &Path,
+        file_name: &OsStr,
+    ) -> IoResult<Option<RollingFileInfoWithSize>> {
+        let mut index = self.read_archive_index(archive_path)?;
+        let Some(pos) = index
+            .iter()
+            .position(|file_info| file_info.path.file_name() == Some(file_name))
+        else {
+            return Ok(None);
+        };
+        let archived = index.remove(pos);
+        let restored_path = self.base_path.join(file_name);
+        if restored_path.exists() {
+            return Err(IoError::new(
+                IoErrorKind::AlreadyExists,
+                format!("file already exists: {}", restored_path.display()),
+            ));
+        }
+        log::info!(
+            "Restoring archived file {} as {}",
+            archived.path.display(),
+            restored_path.display()
+        );
+        move_file(&archived.path, &restored_path)?;
+        self.write_archive_index(archive_path, &mut index)?;
+        Ok(Some(RollingFileInfoWithSize {
+            path: restored_path,
+            ..archived
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tempfile::TempDir;
+
+    use super::{
+        super::{FileNameTimeStamp, RollingFileNameTemplate},
+        *,
+    };
+
+    fn create_files(rolling_fs: &RollingFileSystem, created_at: &[SystemTime]) {
+        for (index, created_at) in created_at.iter().enumerate() {
+            let path = rolling_fs.new_file_path((*created_at).into());
+            fs::write(path, index.to_string()).unwrap();
+        }
+    }
+
+    #[test]
+    fn archive_and_restore_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path().join("data");
+        let archive_path = temp_dir.path().join("archive");
+        fs::create_dir(&base_path).unwrap();
+        let rolling_fs = RollingFileSystem {
+            base_path,
+            file_name_template: RollingFileNameTemplate {
+                prefix: "prefix_".into(),
+                suffix: "_suffix.csv".into(),
+                with_sequence_number: false,
+            },
+        };
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let created_at = [
+            t0,
+            t0 + Duration::from_secs(10),
+            t0 + Duration::from_secs(20),
+            t0 + Duration::from_secs(30),
+        ];
+        create_files(&rolling_fs, &created_at);
+        assert!(rolling_fs
+            .read_archive_index(&archive_path)
+            .unwrap()
+            .is_empty());
+
+        // Keeps the file that might contain later records
+        let archived = rolling_fs
+            .archive_files_created_before(&archive_path, created_at[2] + Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(2, archived.len());
+        assert_eq!(
+            archived,
+            rolling_fs.read_archive_index(&archive_path).unwrap()
+        );
+        for (archived, created_at) in archived.iter().zip(&created_at) {
+            assert_eq!(FileNameTimeStamp::from(*created_at), archived.created_at);
+            assert!(archived.path.starts_with(&archive_path));
+            assert_eq!(1, archived.size_in_bytes);
+        }
+        let remaining = rolling_fs
+            .read_all_dir_entries_filtered_chronologically(&FileInfoFilter::default())
+            .unwrap();
+        assert_eq!(2, remaining.len());
+        assert_eq!(
+            FileNameTimeStamp::from(created_at[2]),
+            remaining[0].created_at
+        );
+
+        // Nothing to archive
+        assert!(rolling_fs
+            .archive_files_created_before(&archive_path, created_at[2])
+            .unwrap()
+            .is_empty());
+
+        let file_name = archived[0].path.file_name().unwrap();
+        let restored = rolling_fs
+            .restore_archived_file(&archive_path, file_name)
+            .unwrap()
+            .unwrap();
+        assert!(restored.path.starts_with(&rolling_fs.base_path));
+        assert_eq!("0", fs::read_to_string(&restored.path).unwrap());
+        let index = rolling_fs.read_archive_index(&archive_path).unwrap();
+        assert_eq!(1, index.len());
+        assert_eq!(FileNameTimeStamp::from(created_at[1]), index[0].created_at);
+        assert!(rolling_fs
+            .restore_archived_file(&archive_path, file_name)
+            .unwrap()
+            .is_none());
+    }
+
+    #[test]
+    fn restore_files_created_at_the_same_time() {
+        let temp_dir = TempDir::new().unwrap();
+        let base_path = temp_dir.path().join("data");
+        let archive_path = temp_dir.path().join("archive");
+        fs::create_dir(&base_path).unwrap();
+        let rolling_fs = RollingFileSystem {
+            base_path,
+            file_name_template: RollingFileNameTemplate {
+                prefix: "prefix_".into(),
+                suffix: "_suffix.csv".into(),
+                with_sequence_number: true,
+            },
+        };
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000);
+        let t1 = t0 + Duration::from_secs(10);
+        create_files(&rolling_fs, &[t0, t1]);
+        let file_name = rolling_fs
+            .file_name_template
+            .format_os_string_with_time_stamp_and_sequence_number(t0.into(), 1);
+        fs::write(rolling_fs.base_path.join(&file_name), "2").unwrap();
+
+        let archived = rolling_fs
+            .archive_files_created_before(&archive_path, t1 + Duration::from_secs(5))
+            .unwrap();
+        assert_eq!(2, archived.len());
+        assert!(archived
+            .iter()
+            .all(|file_info| file_info.created_at == FileNameTimeStamp::from(t0)));
+
+        let restored = rolling_fs
+            .restore_archived_file(&archive_path, &file_name)
+            .unwrap()
+            .unwrap();
+        assert_eq!(rolling_fs.base_path.join(&file_name), restored.path);
+        assert_eq!("2", fs::read_to_string(&restored.path).unwrap());
+        let index = rolling_fs.read_archive_index(&archive_path).unwrap();
+        assert_eq!(1, index.len());
+        assert_ne!(Some(file_name.as_os_str()), index[0].path.file_name());
+        assert_eq!("0", fs::read_to_string(&index[0].path).unwrap());
+    }
+}
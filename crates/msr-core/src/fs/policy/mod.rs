@@ -3,10 +3,11 @@ use std::{
     ffi::{OsStr, OsString},
     fmt, fs,
     io::{Cursor, ErrorKind as IoErrorKind, Result as IoResult},
+    num::NonZeroU64,
     ops::{Range, RangeInclusive},
     path::PathBuf,
     str::{from_utf8, FromStr},
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 
 use thiserror::Error;
@@ -14,6 +15,9 @@ use time::{format_description::FormatItem, macros::format_description, Primitive
 
 use crate::time::{Interval, Timestamp};
 
+mod archive;
+pub use self::archive::{RollingFileArchival, ARCHIVE_INDEX_FILE_NAME_SUFFIX};
+
 // The full precision of nanoseconds is required to prevent that
 // the time stamp in the file name of the next file could be less
 // or equal than the time stamp of the last entry in the previous
@@ -24,6 +28,9 @@ const TIME_STAMP_FORMAT: &[FormatItem<'static>] = format_description!(
 );
 const TIME_STAMP_STRING_LEN: usize = 4 + 2 + 2 + 1 + 2 + 2 + 2 + 1 + 9 + 1;
 
+// Separates the optional sequence number from the time stamp
+const SEQUENCE_NUMBER_SEPARATOR: char = '-';
+
 // 1 year, 1 file per day
 const PREALLOCATE_NUMBER_OF_DIR_ENTRIES: usize = 365;
 
@@ -121,6 +128,13 @@ impl RollingFileStatus {
 pub struct RollingFileNameTemplate {
     pub prefix: String,
     pub suffix: String,
+
+    /// Append a sequence number to the time stamp if a file with
+    /// the same time stamp already exists, e.g. after the system
+    /// clock has been adjusted
+    ///
+    /// The first file for a time stamp has no sequence number.
+    pub with_sequence_number: bool,
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
@@ -182,14 +196,28 @@ impl FromStr for FileNameTimeStamp {
 impl RollingFileNameTemplate {
     #[must_use]
     pub fn format_os_string_with_time_stamp(&self, ts: FileNameTimeStamp) -> OsString {
-        let Self { prefix, suffix } = self;
+        self.format_os_string_with_time_stamp_and_sequence_number(ts, 0)
+    }
+
+    /// Format a file name with a sequence number
+    ///
+    /// The sequence number 0 is omitted.
+    #[must_use]
+    pub fn format_os_string_with_time_stamp_and_sequence_number(
+        &self,
+        ts: FileNameTimeStamp,
+        sequence_number: u32,
+    ) -> OsString {
+        let Self { prefix, suffix, .. } = self;
         // Reserve 2 bytes per character (Windows/UTF-16) for the time stamp infix
         let infix_capacity = TIME_STAMP_STRING_LEN * 2;
         let mut file_name = OsString::with_capacity(prefix.len() + infix_capacity + suffix.len());
         file_name.push(prefix);
         file_name.push(ts.to_string());
+        if sequence_number > 0 {
+            file_name.push(format!("{SEQUENCE_NUMBER_SEPARATOR}{sequence_number}"));
+        }
         file_name.push(suffix);
-        debug_assert!(file_name.len() <= file_name.capacity());
         file_name
     }
 
@@ -197,18 +225,46 @@ impl RollingFileNameTemplate {
         &self,
         file_name: &OsStr,
     ) -> Result<FileNameTimeStamp, FileNameError> {
-        let Self { prefix, suffix } = self;
+        self.parse_time_stamp_and_sequence_number_from_file_name(file_name)
+            .map(|(ts, _)| ts)
+    }
+
+    /// Parse the time stamp and the sequence number from a file name
+    ///
+    /// The sequence number is 0 if the file name does not contain one.
+    pub fn parse_time_stamp_and_sequence_number_from_file_name(
+        &self,
+        file_name: &OsStr,
+    ) -> Result<(FileNameTimeStamp, u32), FileNameError> {
+        let Self {
+            prefix,
+            suffix,
+            with_sequence_number,
+        } = self;
         let file_name = file_name.to_str().ok_or(FileNameError::Pattern)?;
-        // TODO: Replace with strip_prefix/strip_suffix when available
-        if !file_name.starts_with(prefix) || !file_name.ends_with(suffix) {
+        let infix = file_name
+            .strip_prefix(prefix.as_str())
+            .and_then(|without_prefix| without_prefix.strip_suffix(suffix.as_str()))
+            .ok_or(FileNameError::Pattern)?;
+        if infix.len() < TIME_STAMP_STRING_LEN || !infix.is_char_boundary(TIME_STAMP_STRING_LEN) {
             return Err(FileNameError::Pattern);
         }
-        let (_, without_prefix) = file_name.split_at(prefix.len());
-        let (ts, _) = without_prefix.split_at(without_prefix.len() - suffix.len());
-        if ts.len() != TIME_STAMP_STRING_LEN {
-            return Err(FileNameError::Pattern);
-        }
-        Ok(ts.parse()?)
+        let (ts, sequence_number) = infix.split_at(TIME_STAMP_STRING_LEN);
+        let sequence_number = if sequence_number.is_empty() {
+            0
+        } else {
+            if !with_sequence_number {
+                return Err(FileNameError::Pattern);
+            }
+            sequence_number
+                .strip_prefix(SEQUENCE_NUMBER_SEPARATOR)
+                .filter(|digits| {
+                    !digits.starts_with('0') && digits.bytes().all(|byte| byte.is_ascii_digit())
+                })
+                .and_then(|digits| digits.parse().ok())
+                .ok_or(FileNameError::Pattern)?
+        };
+        Ok((ts.parse()?, sequence_number))
     }
 }
 
@@ -243,7 +299,16 @@ impl RollingFileInfoWithSize {
     }
 
     fn cmp_created_at(&self, other: &Self) -> Ordering {
-        self.created_at.cmp(&other.created_at)
+        // Files with the same time stamp only differ by their sequence
+        // number, which is ordered by length first and then lexicographically
+        // (no leading zeros)
+        let file_name_len_and_file_name = |path: &PathBuf| {
+            path.file_name()
+                .map(|file_name| (file_name.len(), file_name.to_owned()))
+        };
+        self.created_at.cmp(&other.created_at).then_with(|| {
+            file_name_len_and_file_name(&self.path).cmp(&file_name_len_and_file_name(&other.path))
+        })
     }
 }
 
@@ -288,10 +353,19 @@ pub struct FileInfoFilter {
 impl RollingFileSystem {
     #[must_use]
     pub fn new_file_path(&self, created_at: FileNameTimeStamp) -> PathBuf {
+        self.new_file_path_with_sequence_number(created_at, 0)
+    }
+
+    #[must_use]
+    pub fn new_file_path_with_sequence_number(
+        &self,
+        created_at: FileNameTimeStamp,
+        sequence_number: u32,
+    ) -> PathBuf {
         debug_assert!(PathBuf::from(self.file_name_template.prefix.clone()).is_relative());
         let new_name = self
             .file_name_template
-            .format_os_string_with_time_stamp(created_at);
+            .format_os_string_with_time_stamp_and_sequence_number(created_at, sequence_number);
         debug_assert_eq!(
             PathBuf::from(new_name.clone()).is_relative(),
             PathBuf::from(self.file_name_template.prefix.clone()).is_relative()
@@ -302,23 +376,38 @@ impl RollingFileSystem {
         new_file_path
     }
 
+    /// Create a new file
+    ///
+    /// If the file name template permits sequence numbers then the
+    /// next unused sequence number is chosen if a file with the same
+    /// time stamp already exists.
     pub fn open_new_file_for_writing(
         &self,
         created_at: FileNameTimeStamp,
     ) -> IoResult<OpenRollingFile> {
         let mut open_options = fs::OpenOptions::new();
         open_options.write(true).create_new(true);
-        let path = self.new_file_path(created_at);
-        match open_options.open(&path) {
-            Ok(file) => {
-                let info = RollingFileInfo { path, created_at };
-                Ok(OpenRollingFile::Opened(file, info))
-            }
-            Err(e) => {
-                if e.kind() == IoErrorKind::AlreadyExists {
-                    Ok(OpenRollingFile::AlreadyExists(path))
-                } else {
-                    Err(e)
+        let mut sequence_number = 0;
+        loop {
+            let path = self.new_file_path_with_sequence_number(created_at, sequence_number);
+            match open_options.open(&path) {
+                Ok(file) => {
+                    let info = RollingFileInfo { path, created_at };
+                    return Ok(OpenRollingFile::Opened(file, info));
+                }
+                Err(e) => {
+                    if e.kind() != IoErrorKind::AlreadyExists {
+                        return Err(e);
+                    }
+                    let next_sequence_number = self
+                        .file_name_template
+                        .with_sequence_number
+                        .then(|| sequence_number.checked_add(1))
+                        .flatten();
+                    let Some(next_sequence_number) = next_sequence_number else {
+                        return Ok(OpenRollingFile::AlreadyExists(path));
+                    };
+                    sequence_number = next_sequence_number;
                 }
             }
         }
@@ -350,7 +439,9 @@ impl RollingFileSystem {
                     if let Some(filter_created_at) = &filter.created_at {
                         let filter_created_at_start = match filter_created_at {
                             SystemTimeRange::OnlyMostRecent => {
-                                if created_at.0 >= first_created_at_filtered.unwrap_or(created_at.0)
+                                // Keep files with the same time stamp that only
+                                // differ by their sequence number
+                                if created_at.0 > first_created_at_filtered.unwrap_or(created_at.0)
                                 {
                                     entries.clear();
                                 }
@@ -417,10 +508,35 @@ impl RollingFileSystem {
     }
 }
 
+/// Controls when written data is synchronized with the storage device
+///
+/// Synchronizing more often reduces the amount of data that could
+/// get lost on a power failure at the cost of throughput.
+#[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+pub enum DurabilityPolicy {
+    /// Leave it to the operating system
+    #[default]
+    Never,
+
+    /// Whenever written data is flushed explicitly
+    OnFlush,
+
+    /// After writing the given number of records
+    EveryRecords(NonZeroU64),
+
+    /// After writing a record if the given time has elapsed since
+    /// the last synchronization
+    EveryInterval(Duration),
+}
+
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct RollingFileConfig {
     pub system: RollingFileSystem,
     pub limits: RollingFileLimits,
+    pub durability: DurabilityPolicy,
+
+    /// Archive old files instead of deleting them
+    pub archival: Option<RollingFileArchival>,
 }
 
 #[cfg(test)]
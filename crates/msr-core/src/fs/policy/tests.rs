@@ -24,6 +24,7 @@ fn verify_file_path(
     let RollingFileNameTemplate {
         prefix: file_name_prefix,
         suffix: file_name_suffix,
+        with_sequence_number: _,
     } = file_name_template;
     let actual_file_path_str = actual_file_path.to_str().unwrap();
     let base_path_str = base_path.to_str().unwrap();
@@ -67,6 +68,7 @@ fn format_file_name_from_config() {
         file_name_template: RollingFileNameTemplate {
             prefix: "prefix_".into(),
             suffix: "_suffix.ext".into(),
+            with_sequence_number: false,
         },
     };
 
@@ -98,3 +100,128 @@ fn file_info_cmp_created_at_later() {
     assert_eq!(Ordering::Equal, later.cmp_created_at(&later));
     assert_eq!(Ordering::Greater, later.cmp_created_at(&earlier));
 }
+
+#[test]
+fn format_and_parse_file_name_with_sequence_number() {
+    let template = RollingFileNameTemplate {
+        prefix: "prefix_".into(),
+        suffix: "_suffix.ext".into(),
+        with_sequence_number: true,
+    };
+    let created_at =
+        SystemTime::from(Timestamp::parse_rfc3339("1978-01-02T23:04:05.12345678Z").unwrap()).into();
+    let file_name = template.format_os_string_with_time_stamp_and_sequence_number(created_at, 0);
+    assert_eq!(
+        "prefix_19780102T230405.123456780Z_suffix.ext",
+        file_name.to_str().unwrap()
+    );
+    assert_eq!(
+        (created_at, 0),
+        template
+            .parse_time_stamp_and_sequence_number_from_file_name(&file_name)
+            .unwrap()
+    );
+    let file_name = template.format_os_string_with_time_stamp_and_sequence_number(created_at, 12);
+    assert_eq!(
+        "prefix_19780102T230405.123456780Z-12_suffix.ext",
+        file_name.to_str().unwrap()
+    );
+    assert_eq!(
+        (created_at, 12),
+        template
+            .parse_time_stamp_and_sequence_number_from_file_name(&file_name)
+            .unwrap()
+    );
+    assert_eq!(
+        created_at,
+        template
+            .parse_time_stamp_from_file_name(&file_name)
+            .unwrap()
+    );
+
+    // Invalid sequence numbers
+    for file_name in [
+        "prefix_19780102T230405.123456780Z-_suffix.ext",
+        "prefix_19780102T230405.123456780Z-0_suffix.ext",
+        "prefix_19780102T230405.123456780Z-01_suffix.ext",
+        "prefix_19780102T230405.123456780Z-1a_suffix.ext",
+        "prefix_19780102T230405.123456780Z_1_suffix.ext",
+    ] {
+        assert!(template
+            .parse_time_stamp_and_sequence_number_from_file_name(OsStr::new(file_name))
+            .is_err());
+    }
+
+    // Sequence numbers are rejected if not permitted
+    let template = RollingFileNameTemplate {
+        with_sequence_number: false,
+        ..template
+    };
+    assert!(template
+        .parse_time_stamp_from_file_name(OsStr::new(
+            "prefix_19780102T230405.123456780Z-1_suffix.ext"
+        ))
+        .is_err());
+}
+
+#[test]
+fn open_new_file_with_colliding_time_stamp() {
+    let temp_dir = tempfile::tempdir().unwrap();
+    let mut rolling_fs = RollingFileSystem {
+        base_path: temp_dir.path().to_path_buf(),
+        file_name_template: RollingFileNameTemplate {
+            prefix: "prefix_".into(),
+            suffix: "_suffix.ext".into(),
+            with_sequence_number: false,
+        },
+    };
+    let created_at = SystemTime::now().into();
+    let OpenRollingFile::Opened(_, first) =
+        rolling_fs.open_new_file_for_writing(created_at).unwrap()
+    else {
+        panic!("file not opened");
+    };
+    assert!(matches!(
+        rolling_fs.open_new_file_for_writing(created_at).unwrap(),
+        OpenRollingFile::AlreadyExists(path) if path == first.path
+    ));
+
+    rolling_fs.file_name_template.with_sequence_number = true;
+    let mut paths = vec![first.path];
+    for sequence_number in 1..=10 {
+        let OpenRollingFile::Opened(_, info) =
+            rolling_fs.open_new_file_for_writing(created_at).unwrap()
+        else {
+            panic!("file not opened");
+        };
+        assert_eq!(created_at, info.created_at);
+        assert_eq!(
+            (created_at, sequence_number),
+            rolling_fs
+                .file_name_template
+                .parse_time_stamp_and_sequence_number_from_file_name(info.path.file_name().unwrap())
+                .unwrap()
+        );
+        paths.push(info.path);
+    }
+
+    // Ordered by sequence number
+    let entries = rolling_fs
+        .read_all_dir_entries_filtered_chronologically(&FileInfoFilter::default())
+        .unwrap();
+    assert_eq!(
+        paths,
+        entries
+            .into_iter()
+            .map(|entry| entry.path)
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        paths.last(),
+        rolling_fs
+            .read_most_recent_dir_entry()
+            .unwrap()
+            .map(|entry| entry.path)
+            .as_ref()
+    );
+}
@@ -0,0 +1,93 @@
+//! Querying the capacity of file systems
+
+use std::{io::Result as IoResult, path::Path};
+
+/// The space of the file system that contains a path
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct DiskSpace {
+    /// The number of bytes available to the current user
+    pub available_bytes: u64,
+
+    pub total_bytes: u64,
+}
+
+/// Query the space of the file system that contains a path
+///
+/// The path must exist.
+#[cfg(target_family = "unix")]
+#[allow(unsafe_code)]
+#[allow(clippy::unnecessary_cast)] // platform dependent types
+pub fn disk_space(path: &Path) -> IoResult<DiskSpace> {
+    use std::{ffi::CString, io::Error as IoError, mem, os::unix::ffi::OsStrExt as _};
+    let path = CString::new(path.as_os_str().as_bytes())?;
+    // SAFETY: The struct only contains integers
+    let mut stat: libc::statvfs = unsafe { mem::zeroed() };
+    // SAFETY: Both pointers are valid during the call
+    if unsafe { libc::statvfs(path.as_ptr(), &mut stat) } != 0 {
+        return Err(IoError::last_os_error());
+    }
+    let fragment_size = stat.f_frsize as u64;
+    Ok(DiskSpace {
+        available_bytes: (stat.f_bavail as u64).saturating_mul(fragment_size),
+        total_bytes: (stat.f_blocks as u64).saturating_mul(fragment_size),
+    })
+}
+
+/// Query the space of the file system that contains a path
+///
+/// The path must exist.
+#[cfg(target_family = "windows")]
+#[allow(unsafe_code)]
+pub fn disk_space(path: &Path) -> IoResult<DiskSpace> {
+    use std::{io::Error as IoError, iter, mem, os::windows::ffi::OsStrExt as _, ptr};
+    use winapi::um::{fileapi::GetDiskFreeSpaceExW, winnt::ULARGE_INTEGER};
+    let path: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(iter::once(0))
+        .collect();
+    // SAFETY: The unions only contain integers
+    let mut available: ULARGE_INTEGER = unsafe { mem::zeroed() };
+    let mut total: ULARGE_INTEGER = unsafe { mem::zeroed() };
+    // SAFETY: All pointers are valid during the call
+    if unsafe { GetDiskFreeSpaceExW(path.as_ptr(), &mut available, &mut total, ptr::null_mut()) }
+        == 0
+    {
+        return Err(IoError::last_os_error());
+    }
+    // SAFETY: The unions have been initialized by the call
+    Ok(unsafe {
+        DiskSpace {
+            available_bytes: *available.QuadPart(),
+            total_bytes: *total.QuadPart(),
+        }
+    })
+}
+
+/// Query the space of the file system that contains a path
+///
+/// Not supported on this platform.
+#[cfg(not(any(target_family = "unix", target_family = "windows")))]
+pub fn disk_space(path: &Path) -> IoResult<DiskSpace> {
+    let _ = path;
+    Err(std::io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn query_disk_space_of_existing_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let disk_space = disk_space(temp_dir.path()).unwrap();
+        assert!(disk_space.total_bytes > 0);
+        assert!(disk_space.available_bytes <= disk_space.total_bytes);
+    }
+
+    #[test]
+    fn fail_on_missing_dir() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        assert!(disk_space(&temp_dir.path().join("missing")).is_err());
+    }
+}
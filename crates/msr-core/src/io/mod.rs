@@ -1,6 +1,16 @@
 //! I/O related utilities
 
-use std::io::{Result, Write};
+#[cfg(feature = "tokio")]
+use std::{
+    io::IoSlice,
+    pin::Pin,
+    task::{Context, Poll},
+};
+use std::{
+    io::{ErrorKind, Result, Write},
+    num::NonZeroU64,
+    time::{Duration, Instant},
+};
 
 use crate::sync::{
     atomic::{AtomicU64, Ordering},
@@ -17,6 +27,15 @@ impl BytesWritten {
     }
 }
 
+// The writer has exclusive mutable access on the number of octets written,
+// i.e. we can safely get-modify-set this value without race conditions here!
+fn add_bytes_written(sum_bytes_written: &AtomicU64, bytes_written: usize) {
+    let sum = sum_bytes_written
+        .load(Ordering::Relaxed)
+        .saturating_add(bytes_written as u64);
+    sum_bytes_written.store(sum, Ordering::Relaxed);
+}
+
 #[derive(Debug)]
 pub struct CountingWrite<W: Write> {
     writer: W,
@@ -40,17 +59,139 @@ impl<W: Write> CountingWrite<W> {
     pub fn into_value(self) -> W {
         self.writer
     }
+
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
 }
 
 impl<W: Write> Write for CountingWrite<W> {
     fn write(&mut self, buf: &[u8]) -> Result<usize> {
         let bytes_written = self.writer.write(buf)?;
-        // self has exclusive mutable access on the number of octets written, i.e.
-        // we can safely get-modify-set this value without race conditions here!
-        let mut sum_bytes_written = self.bytes_written.load(Ordering::Relaxed);
-        sum_bytes_written = sum_bytes_written.saturating_add(bytes_written as u64);
-        self.bytes_written
-            .store(sum_bytes_written, Ordering::Relaxed);
+        add_bytes_written(&self.bytes_written, bytes_written);
+        Ok(bytes_written)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.writer.flush()
+    }
+}
+
+/// A token bucket for limiting the data rate
+///
+/// Permits bursts of up to one second worth of bytes. Starts
+/// with a full bucket.
+#[derive(Debug, Clone)]
+pub struct ByteRateLimiter {
+    bytes_per_second: NonZeroU64,
+    available_bytes: u64,
+    refilled_at: Instant,
+}
+
+impl ByteRateLimiter {
+    #[must_use]
+    pub const fn new(bytes_per_second: NonZeroU64, now: Instant) -> Self {
+        Self {
+            bytes_per_second,
+            available_bytes: bytes_per_second.get(),
+            refilled_at: now,
+        }
+    }
+
+    #[must_use]
+    pub const fn bytes_per_second(&self) -> NonZeroU64 {
+        self.bytes_per_second
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed_nanos = now.saturating_duration_since(self.refilled_at).as_nanos();
+        let refilled_bytes =
+            elapsed_nanos * u128::from(self.bytes_per_second.get()) / NANOS_PER_SECOND;
+        if refilled_bytes == 0 {
+            // Accumulate the elapsed time until at least 1 byte is available
+            return;
+        }
+        let max_bytes = self.bytes_per_second.get();
+        self.available_bytes = u64::try_from(refilled_bytes)
+            .unwrap_or(max_bytes)
+            .saturating_add(self.available_bytes)
+            .min(max_bytes);
+        self.refilled_at = now;
+    }
+
+    /// The number of bytes that could be consumed now
+    pub fn available_bytes(&mut self, now: Instant) -> u64 {
+        self.refill(now);
+        self.available_bytes
+    }
+
+    /// Consume bytes from the budget
+    pub fn consume(&mut self, bytes: u64) {
+        self.available_bytes = self.available_bytes.saturating_sub(bytes);
+    }
+
+    /// The time until at least 1 byte is available
+    pub fn delay(&mut self, now: Instant) -> Duration {
+        if self.available_bytes(now) > 0 {
+            return Duration::ZERO;
+        }
+        let elapsed = now.saturating_duration_since(self.refilled_at);
+        let bytes_per_second = u128::from(self.bytes_per_second.get());
+        // Rounded up
+        let nanos_per_byte = (NANOS_PER_SECOND + bytes_per_second - 1) / bytes_per_second;
+        let nanos_per_byte = u64::try_from(nanos_per_byte).unwrap_or(u64::MAX);
+        Duration::from_nanos(nanos_per_byte).saturating_sub(elapsed)
+    }
+}
+
+const NANOS_PER_SECOND: u128 = 1_000_000_000;
+
+/// Limits the data rate of a writer without blocking
+///
+/// Writes are truncated to the available budget. If the budget is
+/// exhausted writing fails with [`ErrorKind::WouldBlock`] instead of
+/// blocking the calling thread, e.g. a plugin message loop. The caller
+/// is supposed to retry after [`RateLimitedWrite::delay()`].
+///
+/// Note that [`Write::write_all()`] doesn't retry on
+/// [`ErrorKind::WouldBlock`].
+#[derive(Debug)]
+pub struct RateLimitedWrite<W: Write> {
+    writer: W,
+    limiter: ByteRateLimiter,
+}
+
+impl<W: Write> RateLimitedWrite<W> {
+    pub fn new(writer: W, bytes_per_second: NonZeroU64) -> Self {
+        Self {
+            writer,
+            limiter: ByteRateLimiter::new(bytes_per_second, Instant::now()),
+        }
+    }
+
+    /// The time until writing could continue
+    pub fn delay(&mut self) -> Duration {
+        self.limiter.delay(Instant::now())
+    }
+
+    /// Dismantle the wrapped writer
+    pub fn into_value(self) -> W {
+        self.writer
+    }
+}
+
+impl<W: Write> Write for RateLimitedWrite<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        if buf.is_empty() {
+            return self.writer.write(buf);
+        }
+        let available_bytes = self.limiter.available_bytes(Instant::now());
+        if available_bytes == 0 {
+            return Err(ErrorKind::WouldBlock.into());
+        }
+        let max_len = usize::try_from(available_bytes).unwrap_or(usize::MAX);
+        let bytes_written = self.writer.write(&buf[..buf.len().min(max_len)])?;
+        self.limiter.consume(bytes_written as u64);
         Ok(bytes_written)
     }
 
@@ -59,5 +200,76 @@ impl<W: Write> Write for CountingWrite<W> {
     }
 }
 
+/// The asynchronous counterpart of [`CountingWrite`]
+#[cfg(feature = "tokio")]
+#[derive(Debug)]
+pub struct AsyncCountingWrite<W> {
+    writer: W,
+    bytes_written: Arc<AtomicU64>,
+}
+
+#[cfg(feature = "tokio")]
+impl<W> AsyncCountingWrite<W>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    /// Wrap a writer and start counting
+    pub fn from_writer(writer: W) -> (Self, BytesWritten) {
+        let bytes_written = Arc::new(AtomicU64::new(0));
+        (
+            Self {
+                writer,
+                bytes_written: Arc::clone(&bytes_written),
+            },
+            BytesWritten(bytes_written),
+        )
+    }
+
+    /// Dismantle the wrapped writer and stop counting
+    pub fn into_value(self) -> W {
+        self.writer
+    }
+}
+
+#[cfg(feature = "tokio")]
+impl<W> tokio::io::AsyncWrite for AsyncCountingWrite<W>
+where
+    W: tokio::io::AsyncWrite + Unpin,
+{
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.writer).poll_write(cx, buf);
+        if let Poll::Ready(Ok(bytes_written)) = poll {
+            add_bytes_written(&this.bytes_written, bytes_written);
+        }
+        poll
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[IoSlice<'_>],
+    ) -> Poll<Result<usize>> {
+        let this = self.get_mut();
+        let poll = Pin::new(&mut this.writer).poll_write_vectored(cx, bufs);
+        if let Poll::Ready(Ok(bytes_written)) = poll {
+            add_bytes_written(&this.bytes_written, bytes_written);
+        }
+        poll
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.writer.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.get_mut().writer).poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<()>> {
+        Pin::new(&mut self.get_mut().writer).poll_shutdown(cx)
+    }
+}
+
 #[cfg(test)]
 mod tests;
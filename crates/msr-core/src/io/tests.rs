@@ -1,3 +1,5 @@
+use std::io::ErrorKind;
+
 use super::*;
 
 #[test]
@@ -13,3 +15,58 @@ fn counting_write() {
     assert!(writer.write(&[4, 5, 6, 7]).is_ok());
     assert_eq!(7, bytes_written.value());
 }
+
+#[cfg(feature = "tokio")]
+#[tokio::test]
+async fn async_counting_write() {
+    use tokio::io::AsyncWriteExt as _;
+
+    let (mut writer, bytes_written) = AsyncCountingWrite::from_writer(Vec::with_capacity(100));
+    assert_eq!(0, bytes_written.value());
+    writer.write_all(&[1]).await.unwrap();
+    assert_eq!(1, bytes_written.value());
+    writer.write_all(&[2, 3]).await.unwrap();
+    assert_eq!(3, bytes_written.value());
+    writer.write_all(&[]).await.unwrap();
+    assert_eq!(3, bytes_written.value());
+    writer.write_all(&[4, 5, 6, 7]).await.unwrap();
+    writer.flush().await.unwrap();
+    assert_eq!(7, bytes_written.value());
+    assert_eq!(vec![1, 2, 3, 4, 5, 6, 7], writer.into_value());
+}
+
+#[test]
+fn byte_rate_limiter() {
+    let now = Instant::now();
+    let mut limiter = ByteRateLimiter::new(NonZeroU64::new(1_000).unwrap(), now);
+    assert_eq!(1_000, limiter.available_bytes(now));
+    assert_eq!(Duration::ZERO, limiter.delay(now));
+    limiter.consume(1_000);
+    assert_eq!(0, limiter.available_bytes(now));
+    assert_eq!(Duration::from_millis(1), limiter.delay(now));
+    // Less than 1 byte
+    let now = now + Duration::from_micros(500);
+    assert_eq!(0, limiter.available_bytes(now));
+    assert_eq!(Duration::from_micros(500), limiter.delay(now));
+    let now = now + Duration::from_micros(500);
+    assert_eq!(1, limiter.available_bytes(now));
+    let now = now + Duration::from_millis(100);
+    assert_eq!(101, limiter.available_bytes(now));
+    // Bursts are limited
+    let now = now + Duration::from_secs(10);
+    assert_eq!(1_000, limiter.available_bytes(now));
+}
+
+#[test]
+fn rate_limited_write() {
+    let mut writer = RateLimitedWrite::new(Vec::new(), NonZeroU64::new(3).unwrap());
+    assert_eq!(2, writer.write(&[1, 2]).unwrap());
+    assert_eq!(1, writer.write(&[3, 4]).unwrap());
+    assert_eq!(
+        ErrorKind::WouldBlock,
+        writer.write(&[4]).unwrap_err().kind()
+    );
+    assert!(writer.delay() > Duration::ZERO);
+    assert_eq!(0, writer.write(&[]).unwrap());
+    assert_eq!(vec![1, 2, 3], writer.into_value());
+}
@@ -28,6 +28,7 @@ pub use self::{measure::*, value::*};
 
 pub mod audit;
 pub mod control;
+pub mod fieldbus;
 pub mod fs;
 pub mod io;
 pub mod register;
@@ -35,6 +36,7 @@ pub mod storage;
 pub mod sync;
 pub mod thread;
 pub mod time;
+pub mod unit;
 
 #[cfg(feature = "realtime-worker-thread")]
 pub mod realtime;
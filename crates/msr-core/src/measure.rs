@@ -1,5 +1,7 @@
 use std::time::Instant;
 
+use crate::time::SystemInstant;
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Measurement<V> {
     /// A time stamp
@@ -8,3 +10,78 @@ pub struct Measurement<V> {
     /// The measured value
     pub val: Option<V>,
 }
+
+/// The reliability of an observed value
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Quality {
+    /// The value is reliable
+    #[default]
+    Good,
+
+    /// The value might be inaccurate, e.g. a substitute value
+    /// or a sensor outside of its calibrated range
+    Uncertain,
+
+    /// The value must not be used, e.g. after a communication
+    /// failure or a sensor fault
+    Bad,
+}
+
+/// A value together with the time and the quality of its observation
+///
+/// Each value keeps its own time stamp, even if multiple values
+/// are observed and processed together as a group.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimestampedValue<V> {
+    /// The observed value
+    pub value: V,
+
+    /// The time of the observation
+    pub observed_at: SystemInstant,
+
+    /// The quality of the observation
+    pub quality: Quality,
+}
+
+impl<V> TimestampedValue<V> {
+    /// Create a new value with [`Quality::Good`].
+    #[must_use]
+    pub const fn new(value: V, observed_at: SystemInstant) -> Self {
+        Self {
+            value,
+            observed_at,
+            quality: Quality::Good,
+        }
+    }
+
+    #[must_use]
+    pub fn with_quality(self, quality: Quality) -> Self {
+        Self { quality, ..self }
+    }
+
+    #[must_use]
+    pub fn is_good(&self) -> bool {
+        self.quality == Quality::Good
+    }
+
+    /// The value if it is not [`Quality::Bad`]
+    #[must_use]
+    pub fn usable_value(self) -> Option<V> {
+        (self.quality != Quality::Bad).then_some(self.value)
+    }
+
+    /// Convert the value while keeping time stamp and quality.
+    #[must_use]
+    pub fn map<T>(self, f: impl FnOnce(V) -> T) -> TimestampedValue<T> {
+        let Self {
+            value,
+            observed_at,
+            quality,
+        } = self;
+        TimestampedValue {
+            value: f(value),
+            observed_at,
+            quality,
+        }
+    }
+}
@@ -0,0 +1,214 @@
+//! Logging from real-time threads
+//!
+//! Log records are formatted into preallocated, fixed-size slots of a
+//! ring buffer. A non-real-time thread drains the buffer and forwards
+//! the records, e.g. to the [`log`] facade. Neither formatting nor
+//! submitting a record allocates or blocks, provided that the
+//! formatted arguments don't allocate.
+
+use std::{
+    fmt::{self, Write as _},
+    time::Instant,
+};
+
+use log::Level;
+
+use crate::sync::{ring_buffer, RingBufferConsumer, RingBufferProducer};
+
+/// The maximum length of a message in bytes
+///
+/// Longer messages are truncated.
+pub const MESSAGE_CAPACITY: usize = 200;
+
+/// A log record that doesn't require any allocations
+#[derive(Clone, Copy)]
+pub struct RealtimeLogRecord {
+    level: Level,
+    target: &'static str,
+    timestamp: Instant,
+    message_len: usize,
+    message_buf: [u8; MESSAGE_CAPACITY],
+    truncated: bool,
+}
+
+impl RealtimeLogRecord {
+    fn new(level: Level, target: &'static str, args: fmt::Arguments<'_>) -> Self {
+        let mut record = Self {
+            level,
+            target,
+            timestamp: Instant::now(),
+            message_len: 0,
+            message_buf: [0; MESSAGE_CAPACITY],
+            truncated: false,
+        };
+        // Formatting only fails if the arguments fail
+        let _ = record.write_fmt(args);
+        record
+    }
+
+    #[must_use]
+    pub const fn level(&self) -> Level {
+        self.level
+    }
+
+    #[must_use]
+    pub const fn target(&self) -> &'static str {
+        self.target
+    }
+
+    /// The time when the record has been created
+    #[must_use]
+    pub const fn timestamp(&self) -> Instant {
+        self.timestamp
+    }
+
+    #[must_use]
+    pub fn message(&self) -> &str {
+        // Only complete UTF-8 sequences are written into the buffer
+        std::str::from_utf8(&self.message_buf[..self.message_len]).unwrap_or_default()
+    }
+
+    /// Check if the message has been truncated
+    #[must_use]
+    pub const fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+}
+
+impl fmt::Write for RealtimeLogRecord {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let remaining = MESSAGE_CAPACITY - self.message_len;
+        let mut len = s.len();
+        if len > remaining {
+            len = remaining;
+            while !s.is_char_boundary(len) {
+                len -= 1;
+            }
+            self.truncated = true;
+        }
+        self.message_buf[self.message_len..self.message_len + len]
+            .copy_from_slice(&s.as_bytes()[..len]);
+        self.message_len += len;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for RealtimeLogRecord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RealtimeLogRecord")
+            .field("level", &self.level)
+            .field("target", &self.target)
+            .field("timestamp", &self.timestamp)
+            .field("message", &self.message())
+            .field("truncated", &self.truncated)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Create a logger for a real-time thread and the corresponding drain
+///
+/// The capacity determines how many records could be buffered before
+/// they are dropped. It is rounded up to the next power of 2.
+#[must_use]
+pub fn realtime_logger(
+    target: &'static str,
+    capacity: usize,
+) -> (RealtimeLogger, RealtimeLogDrain) {
+    let (records_tx, records_rx) = ring_buffer(capacity);
+    let logger = RealtimeLogger { target, records_tx };
+    let drain = RealtimeLogDrain { records_rx };
+    (logger, drain)
+}
+
+/// Submits log records from a real-time thread (wait-free)
+///
+/// Records are dropped and counted if the buffer is full. Records
+/// with a level that is disabled by [`log::max_level()`] are discarded
+/// immediately.
+#[derive(Debug)]
+pub struct RealtimeLogger {
+    target: &'static str,
+    records_tx: RingBufferProducer<RealtimeLogRecord>,
+}
+
+impl RealtimeLogger {
+    #[must_use]
+    pub fn enabled(&self, level: Level) -> bool {
+        level <= log::max_level()
+    }
+
+    /// Submit a new log record
+    ///
+    /// Returns `false` if the record has been dropped.
+    #[allow(clippy::must_use_candidate)]
+    pub fn log(&mut self, level: Level, args: fmt::Arguments<'_>) -> bool {
+        if !self.enabled(level) {
+            return true;
+        }
+        let record = RealtimeLogRecord::new(level, self.target, args);
+        self.records_tx.try_push(record).is_ok()
+    }
+
+    pub fn error(&mut self, args: fmt::Arguments<'_>) {
+        self.log(Level::Error, args);
+    }
+
+    pub fn warn(&mut self, args: fmt::Arguments<'_>) {
+        self.log(Level::Warn, args);
+    }
+
+    pub fn info(&mut self, args: fmt::Arguments<'_>) {
+        self.log(Level::Info, args);
+    }
+
+    pub fn debug(&mut self, args: fmt::Arguments<'_>) {
+        self.log(Level::Debug, args);
+    }
+
+    pub fn trace(&mut self, args: fmt::Arguments<'_>) {
+        self.log(Level::Trace, args);
+    }
+}
+
+/// Drains log records submitted from a real-time thread
+///
+/// Supposed to be invoked periodically by a non-real-time thread.
+#[derive(Debug)]
+pub struct RealtimeLogDrain {
+    records_rx: RingBufferConsumer<RealtimeLogRecord>,
+}
+
+impl RealtimeLogDrain {
+    /// Take all pending records
+    ///
+    /// Returns the number of records that have been dropped since
+    /// the last invocation.
+    pub fn drain(&mut self, mut handle_record: impl FnMut(&RealtimeLogRecord)) -> u64 {
+        while let Some(record) = self.records_rx.pop() {
+            handle_record(&record);
+        }
+        self.records_rx.reset_overflow_count()
+    }
+
+    /// Forward all pending records to the [`log`] facade
+    ///
+    /// A warning is logged if records have been dropped.
+    pub fn forward(&mut self) {
+        let logger = log::logger();
+        let dropped_count = self.drain(|record| {
+            logger.log(
+                &log::Record::builder()
+                    .level(record.level())
+                    .target(record.target())
+                    .args(format_args!("{}", record.message()))
+                    .build(),
+            );
+        });
+        if dropped_count > 0 {
+            log::warn!("Dropped {dropped_count} real-time log record(s)");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,38 @@
+use super::*;
+
+#[test]
+fn drain_records() {
+    log::set_max_level(log::LevelFilter::Debug);
+    let (mut logger, mut drain) = realtime_logger("test", 2);
+    let started = Instant::now();
+    assert!(logger.log(Level::Info, format_args!("cycle {}", 1)));
+    logger.debug(format_args!("cycle {}", 2));
+    // Disabled
+    assert!(!logger.enabled(Level::Trace));
+    logger.trace(format_args!("cycle {}", 3));
+    // Dropped
+    assert!(!logger.log(Level::Warn, format_args!("cycle {}", 4)));
+
+    let mut records = Vec::new();
+    assert_eq!(1, drain.drain(|record| records.push(*record)));
+    assert_eq!(2, records.len());
+    assert_eq!(Level::Info, records[0].level());
+    assert_eq!("test", records[0].target());
+    assert_eq!("cycle 1", records[0].message());
+    assert!(records[0].timestamp() >= started);
+    assert_eq!(Level::Debug, records[1].level());
+    assert_eq!("cycle 2", records[1].message());
+
+    assert_eq!(0, drain.drain(|_| unreachable!()));
+}
+
+#[test]
+fn truncate_long_messages() {
+    let record = RealtimeLogRecord::new(Level::Error, "test", format_args!("{}", "ä".repeat(150)));
+    assert!(record.is_truncated());
+    assert_eq!("ä".repeat(MESSAGE_CAPACITY / 2), record.message());
+
+    let record = RealtimeLogRecord::new(Level::Error, "test", format_args!("x{}", "ä".repeat(150)));
+    assert!(record.is_truncated());
+    assert_eq!(MESSAGE_CAPACITY - 1, record.message().len());
+}
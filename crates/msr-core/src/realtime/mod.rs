@@ -1 +1,3 @@
+pub mod logging;
+pub mod sleep;
 pub mod worker;
@@ -0,0 +1,104 @@
+//! Precise sleeping until absolute deadlines
+//!
+//! On Linux the thread sleeps with `clock_nanosleep()` on the
+//! monotonic clock with an absolute deadline (`TIMER_ABSTIME`).
+//! In contrast to sleeping for a relative duration the wake-up
+//! time is not delayed if the thread is preempted before going
+//! to sleep. Sub-millisecond accuracy is achievable for threads
+//! with real-time scheduling.
+//!
+//! On other platforms [`std::thread::sleep()`] is used as a fallback.
+
+use std::time::Instant;
+
+/// Puts the current thread to sleep until the deadline
+///
+/// The thread will never wake up before the deadline. It returns
+/// immediately if the deadline has already passed.
+pub fn sleep_until(deadline: Instant) {
+    imp::sleep_until(deadline);
+}
+
+#[cfg(target_os = "linux")]
+#[allow(unsafe_code)]
+mod imp {
+    use std::{
+        io,
+        mem::MaybeUninit,
+        time::{Duration, Instant},
+    };
+
+    const NANOS_PER_SEC: libc::c_long = 1_000_000_000;
+
+    fn monotonic_now() -> libc::timespec {
+        let mut now = MaybeUninit::<libc::timespec>::uninit();
+        // SAFETY: The pointer is valid and CLOCK_MONOTONIC is supported on Linux
+        let res = unsafe { libc::clock_gettime(libc::CLOCK_MONOTONIC, now.as_mut_ptr()) };
+        assert_eq!(0, res, "{}", io::Error::last_os_error());
+        // SAFETY: Initialized by clock_gettime()
+        unsafe { now.assume_init() }
+    }
+
+    pub(super) fn add_duration(ts: libc::timespec, duration: Duration) -> libc::timespec {
+        let mut secs = ts
+            .tv_sec
+            .saturating_add(duration.as_secs().try_into().unwrap_or(libc::time_t::MAX));
+        let mut nanos = ts.tv_nsec + libc::c_long::from(duration.subsec_nanos());
+        if nanos >= NANOS_PER_SEC {
+            nanos -= NANOS_PER_SEC;
+            secs = secs.saturating_add(1);
+        }
+        libc::timespec {
+            tv_sec: secs,
+            tv_nsec: nanos,
+        }
+    }
+
+    pub(super) fn sleep_until(deadline: Instant) {
+        // Both Instant and CLOCK_MONOTONIC refer to the same clock on Linux,
+        // i.e. the remaining duration could be mapped onto the monotonic
+        // clock without any loss of precision.
+        let now = Instant::now();
+        if deadline <= now {
+            return;
+        }
+        let deadline_ts = add_duration(monotonic_now(), deadline.duration_since(now));
+        loop {
+            // SAFETY: The pointer to the deadline is valid and the
+            // remaining time is not needed for absolute deadlines
+            let res = unsafe {
+                libc::clock_nanosleep(
+                    libc::CLOCK_MONOTONIC,
+                    libc::TIMER_ABSTIME,
+                    &deadline_ts,
+                    std::ptr::null_mut(),
+                )
+            };
+            match res {
+                0 => break,
+                // Interrupted by a signal handler: Resume sleeping
+                libc::EINTR => {}
+                err => {
+                    log::warn!(
+                        "clock_nanosleep() failed: {}",
+                        io::Error::from_raw_os_error(err)
+                    );
+                    crate::thread::sleep_until(deadline);
+                    break;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use std::time::Instant;
+
+    pub(super) fn sleep_until(deadline: Instant) {
+        crate::thread::sleep_until(deadline);
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,35 @@
+use std::time::{Duration, Instant};
+
+use super::*;
+
+#[test]
+fn sleep_until_deadline() {
+    for millis in [0, 1, 5] {
+        let deadline = Instant::now() + Duration::from_millis(millis);
+        sleep_until(deadline);
+        assert!(Instant::now() >= deadline);
+    }
+}
+
+#[test]
+fn sleep_until_past_deadline() {
+    let deadline = Instant::now();
+    std::thread::sleep(Duration::from_millis(1));
+    let before = Instant::now();
+    sleep_until(deadline);
+    // Returns immediately (generous upper bound for slow CI runners)
+    assert!(before.elapsed() < Duration::from_millis(50));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn add_duration_to_timespec() {
+    let ts = libc::timespec {
+        tv_sec: 1,
+        tv_nsec: 999_999_999,
+    };
+    let sum = imp::add_duration(ts, Duration::new(2, 2));
+    assert_eq!((4, 1), (sum.tv_sec, sum.tv_nsec));
+    let sum = imp::add_duration(ts, Duration::ZERO);
+    assert_eq!((1, 999_999_999), (sum.tv_sec, sum.tv_nsec));
+}
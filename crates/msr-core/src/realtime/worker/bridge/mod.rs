@@ -0,0 +1,166 @@
+//! Driving workers from asynchronous code
+//!
+//! The [`AsyncWorkerThread`] runs a [`Worker`] on a dedicated
+//! [`WorkerThread`] and exposes its progress hints and state changes
+//! through non-blocking handles that can be awaited within a tokio
+//! runtime, e.g. by the message loop of a plugin.
+
+use std::thread;
+
+use tokio::sync::{oneshot, watch};
+
+use super::{
+    progress::{ProgressHintSender, SwitchProgressHintResult},
+    thread::{Context, JoinedThread, SpawnOptions, State, SuspendError, WorkerThread},
+    Worker,
+};
+
+/// A worker thread with asynchronous handles
+///
+/// The state of the worker thread is observed by a separate, plain
+/// thread that forwards all state changes. Intermediate states might
+/// be skipped if the worker thread switches its state faster than
+/// the observer wakes up.
+///
+/// Neither spawning nor controlling the worker thread requires a
+/// running tokio runtime.
+#[allow(missing_debug_implementations)]
+pub struct AsyncWorkerThread<W: Worker> {
+    progress_hint_tx: ProgressHintSender,
+    state_rx: watch::Receiver<State>,
+    joined_rx: oneshot::Receiver<JoinedThread<W>>,
+}
+
+impl<W> AsyncWorkerThread<W>
+where
+    W: Worker + Send + 'static,
+    <W as Worker>::Environment: Send + 'static,
+{
+    pub fn spawn(context: Context<W>, options: SpawnOptions) -> Self {
+        let progress_hint_tx = ProgressHintSender::attach(&context.progress_hint_rx);
+        let (state_tx, state_rx) = watch::channel(State::Initial);
+        let (joined_tx, joined_rx) = oneshot::channel();
+        let worker_thread = WorkerThread::spawn_with_options(context, options);
+        thread::spawn(move || {
+            let mut state = State::Initial;
+            while state != State::Terminating {
+                state = worker_thread.wait_until_state_changed(state);
+                state_tx.send_replace(state);
+            }
+            // The receiver might have been dropped already
+            let _ = joined_tx.send(worker_thread.join());
+        });
+        Self {
+            progress_hint_tx,
+            state_rx,
+            joined_rx,
+        }
+    }
+}
+
+impl<W> AsyncWorkerThread<W>
+where
+    W: Worker,
+{
+    #[must_use]
+    pub fn progress_hint_tx(&self) -> &ProgressHintSender {
+        &self.progress_hint_tx
+    }
+
+    #[must_use]
+    pub fn load_state(&self) -> State {
+        *self.state_rx.borrow()
+    }
+
+    /// Subscribe to state changes
+    ///
+    /// The returned receiver yields the most recent state of the
+    /// worker thread.
+    #[must_use]
+    pub fn subscribe_state(&self) -> watch::Receiver<State> {
+        self.state_rx.clone()
+    }
+
+    pub fn suspend(&self) -> SwitchProgressHintResult {
+        self.progress_hint_tx.suspend()
+    }
+
+    pub fn resume(&self) -> SwitchProgressHintResult {
+        self.progress_hint_tx.resume()
+    }
+
+    pub fn finish(&self) -> SwitchProgressHintResult {
+        self.progress_hint_tx.finish()
+    }
+
+    pub fn abort(&self) -> SwitchProgressHintResult {
+        self.progress_hint_tx.abort()
+    }
+
+    /// Ask the worker to suspend and wait until it has suspended
+    ///
+    /// Never returns [`SuspendError::Timeout`]. Use a timer of the
+    /// async runtime for limiting the waiting time if needed.
+    pub async fn suspend_and_wait(&mut self) -> Result<(), SuspendError> {
+        self.suspend()?;
+        match self.wait_until_not_running().await {
+            State::Suspending => Ok(()),
+            state => Err(SuspendError::Stopped { state }),
+        }
+    }
+
+    /// Wait until the state of the worker thread satisfies a condition
+    ///
+    /// Returns the current state immediately if it already satisfies
+    /// the condition. Returns the final state after the worker thread
+    /// has terminated, even if it doesn't satisfy the condition.
+    pub async fn wait_until_state_condition(
+        &mut self,
+        mut state_condition: impl FnMut(State) -> bool,
+    ) -> State {
+        // The observer only terminates after sending the final state,
+        // which is evaluated before reporting that the sender is closed
+        if let Ok(state) = self
+            .state_rx
+            .wait_for(|state| state_condition(*state))
+            .await
+        {
+            return *state;
+        }
+        *self.state_rx.borrow()
+    }
+
+    pub async fn wait_until_started(&mut self) -> State {
+        self.wait_until_state_condition(|state| match state {
+            State::Initial | State::Starting => false,
+            State::Running | State::Suspending | State::Finishing | State::Terminating => true,
+        })
+        .await
+    }
+
+    pub async fn wait_until_not_running(&mut self) -> State {
+        self.wait_until_state_condition(|state| match state {
+            State::Initial | State::Starting | State::Running => false,
+            State::Suspending | State::Finishing | State::Terminating => true,
+        })
+        .await
+    }
+
+    /// Wait until the worker thread has terminated and join it
+    pub async fn join(self) -> JoinedThread<W> {
+        self.joined_rx
+            .await
+            .unwrap_or_else(|err| JoinedThread::JoinError(Box::new(err)))
+    }
+
+    /// Ask the worker to finish and join the worker thread
+    pub async fn finish_and_join(self) -> JoinedThread<W> {
+        if let Err(err) = self.finish() {
+            log::warn!("Failed to finish worker: {err}");
+        }
+        self.join().await
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,105 @@
+use anyhow::Result;
+
+use super::{
+    super::{
+        progress::{ProgressHint, ProgressHintReceiver},
+        thread::TerminatedThread,
+        CompletionStatus,
+    },
+    *,
+};
+
+/// Suspends after each invocation until finished
+#[derive(Default)]
+struct SuspendingWorker {
+    perform_work_invocations: usize,
+}
+
+impl Worker for SuspendingWorker {
+    type Environment = ();
+
+    fn start_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+        Ok(())
+    }
+
+    fn perform_work(
+        &mut self,
+        _env: &Self::Environment,
+        progress_hint_rx: &ProgressHintReceiver,
+    ) -> Result<CompletionStatus> {
+        self.perform_work_invocations += 1;
+        match progress_hint_rx.peek() {
+            ProgressHint::Continue | ProgressHint::Suspend => Ok(CompletionStatus::Suspending),
+            ProgressHint::Finish => Ok(CompletionStatus::Finishing),
+            ProgressHint::Abort => Ok(CompletionStatus::Aborting),
+        }
+    }
+}
+
+fn spawn() -> AsyncWorkerThread<SuspendingWorker> {
+    let context = Context {
+        progress_hint_rx: ProgressHintReceiver::default(),
+        worker: SuspendingWorker::default(),
+        environment: (),
+    };
+    AsyncWorkerThread::spawn(context, SpawnOptions::default())
+}
+
+#[tokio::test]
+async fn suspend_resume_finish() {
+    let mut worker_thread = spawn();
+    let mut state_rx = worker_thread.subscribe_state();
+
+    assert_ne!(State::Initial, worker_thread.wait_until_started().await);
+    assert_eq!(
+        State::Suspending,
+        worker_thread.wait_until_not_running().await
+    );
+    // The short-lived running state might be skipped after resuming
+    assert!(worker_thread.resume().is_ok());
+    assert!(worker_thread.suspend_and_wait().await.is_ok());
+    assert!(worker_thread.resume().is_ok());
+
+    match worker_thread.finish_and_join().await {
+        JoinedThread::Terminated(TerminatedThread { result, context }) => {
+            assert!(result.is_ok());
+            assert!(context.worker.perform_work_invocations >= 2);
+        }
+        JoinedThread::JoinError(_) => unreachable!(),
+    }
+
+    // The final state is still available after joining
+    assert_eq!(
+        State::Terminating,
+        *state_rx
+            .wait_for(|state| *state == State::Terminating)
+            .await
+            .unwrap()
+    );
+}
+
+#[tokio::test]
+async fn wait_until_terminated() {
+    let mut worker_thread = spawn();
+    assert!(worker_thread.finish().is_ok());
+    assert_eq!(
+        State::Terminating,
+        worker_thread
+            .wait_until_state_condition(|state| state == State::Terminating)
+            .await
+    );
+    assert_eq!(State::Terminating, worker_thread.load_state());
+    // Waiting after the observer has terminated returns immediately
+    assert_eq!(
+        State::Terminating,
+        worker_thread.wait_until_not_running().await
+    );
+    assert!(matches!(
+        worker_thread.join().await,
+        JoinedThread::Terminated(TerminatedThread { result: Ok(()), .. })
+    ));
+}
@@ -0,0 +1,210 @@
+use std::{
+    fmt,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+use anyhow::Result;
+
+use crate::{
+    realtime::sleep::sleep_until,
+    sync::Arc,
+    time::{CyclicTick, CyclicTimer, CyclicTimerStats},
+};
+
+use super::{
+    histogram::CyclicHistograms,
+    progress::{ProgressHint, ProgressHintReceiver},
+    CompletionStatus, Worker,
+};
+
+/// How to idle until the next cycle
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum CyclicWorkerTiming {
+    /// Sleep until the next cycle
+    ///
+    /// Progress hints are only checked once before each cycle,
+    /// i.e. the responsiveness depends on the period. Avoids being
+    /// blocked by lower priority threads due to priority inversion.
+    #[default]
+    Sleeping,
+
+    /// Interrupt idling when progress hint updates arrive
+    Waiting,
+}
+
+/// Configuration of a [`CyclicWorker`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CyclicWorkerConfig {
+    /// The fixed period of the cycles
+    pub period: Duration,
+
+    pub timing: CyclicWorkerTiming,
+}
+
+impl CyclicWorkerConfig {
+    #[must_use]
+    pub fn new(period: Duration) -> Self {
+        Self {
+            period,
+            timing: Default::default(),
+        }
+    }
+}
+
+/// Adapter for invoking a closure periodically
+///
+/// The closure is invoked once per cycle with the [`CyclicTick`]
+/// that contains the measured jitter. It returns `None` for
+/// continuing with the next cycle or a [`CompletionStatus`] for
+/// exiting [`Worker::perform_work()`].
+///
+/// Cycles are scheduled by a [`CyclicTimer`] without drift. Missed
+/// cycles are skipped. The schedule restarts after resuming.
+///
+/// The execution time and the wake-up jitter of all cycles are
+/// collected in [`CyclicHistograms`].
+pub struct CyclicWorker<E, F> {
+    config: CyclicWorkerConfig,
+    cycle: F,
+    timer: Option<CyclicTimer>,
+    histograms: Arc<CyclicHistograms>,
+    _environment: PhantomData<fn(&E)>,
+}
+
+impl<E, F> fmt::Debug for CyclicWorker<E, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CyclicWorker")
+            .field("config", &self.config)
+            .field("timer", &self.timer)
+            .field("histograms", &self.histograms)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E, F> CyclicWorker<E, F>
+where
+    F: FnMut(&E, &CyclicTick) -> Result<Option<CompletionStatus>>,
+{
+    /// Create a new cyclic worker
+    ///
+    /// # Panics
+    ///
+    /// Panics if the period is zero.
+    #[must_use]
+    pub fn new(config: CyclicWorkerConfig, cycle: F) -> Self {
+        assert!(config.period > Duration::ZERO, "period must not be zero");
+        Self {
+            config,
+            cycle,
+            timer: None,
+            histograms: Default::default(),
+            _environment: PhantomData,
+        }
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &CyclicWorkerConfig {
+        &self.config
+    }
+
+    /// Shared timing histograms
+    ///
+    /// Obtain the histograms before spawning the worker thread for
+    /// reading them while the worker is running.
+    #[must_use]
+    pub fn histograms(&self) -> Arc<CyclicHistograms> {
+        Arc::clone(&self.histograms)
+    }
+
+    /// Timing statistics since the schedule has been (re-)started
+    #[must_use]
+    pub fn stats(&self) -> Option<&CyclicTimerStats> {
+        self.timer.as_ref().map(CyclicTimer::stats)
+    }
+
+    /// Idle until the next deadline
+    ///
+    /// Returns a progress hint that interrupts cycling.
+    fn idle_until(
+        &self,
+        deadline: Instant,
+        progress_hint_rx: &ProgressHintReceiver,
+    ) -> ProgressHint {
+        match self.config.timing {
+            CyclicWorkerTiming::Sleeping => {
+                sleep_until(deadline);
+                progress_hint_rx.peek()
+            }
+            CyclicWorkerTiming::Waiting => {
+                while progress_hint_rx.wait_until(deadline) {
+                    let progress_hint = progress_hint_rx.peek();
+                    if progress_hint != ProgressHint::Continue {
+                        return progress_hint;
+                    }
+                }
+                progress_hint_rx.peek()
+            }
+        }
+    }
+}
+
+impl<E, F> Worker for CyclicWorker<E, F>
+where
+    F: FnMut(&E, &CyclicTick) -> Result<Option<CompletionStatus>>,
+{
+    type Environment = E;
+
+    fn start_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+        self.timer = None;
+        Ok(())
+    }
+
+    fn perform_work(
+        &mut self,
+        env: &Self::Environment,
+        progress_hint_rx: &ProgressHintReceiver,
+    ) -> Result<CompletionStatus> {
+        let now = Instant::now();
+        let mut timer = match self.timer.take() {
+            Some(mut timer) => {
+                // Resuming
+                timer.restart(now);
+                timer
+            }
+            None => CyclicTimer::new(now, self.config.period),
+        };
+        let completion_status = loop {
+            match self.idle_until(timer.next_deadline(), progress_hint_rx) {
+                ProgressHint::Continue => (),
+                ProgressHint::Suspend => break CompletionStatus::Suspending,
+                ProgressHint::Finish => break CompletionStatus::Finishing,
+                ProgressHint::Abort => break CompletionStatus::Aborting,
+            }
+            let Some(tick) = timer.poll_tick(Instant::now()) else {
+                // Woke up early while waiting for progress hints
+                continue;
+            };
+            self.histograms.wakeup_jitter.record(tick.jitter());
+            let result = (self.cycle)(env, &tick);
+            self.histograms.execution_time.record(tick.actual.elapsed());
+            match result {
+                Ok(None) => (),
+                Ok(Some(completion_status)) => break completion_status,
+                Err(err) => {
+                    self.timer = Some(timer);
+                    return Err(err);
+                }
+            }
+        };
+        self.timer = Some(timer);
+        Ok(completion_status)
+    }
+
+    fn finish_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests;
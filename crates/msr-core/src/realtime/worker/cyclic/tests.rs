@@ -0,0 +1,93 @@
+use crate::realtime::worker::{
+    progress::ProgressHintSender,
+    thread::{Context, JoinedThread, State, TerminatedThread, ThreadScheduling, WorkerThread},
+};
+
+use super::*;
+
+const PERIOD: Duration = Duration::from_millis(2);
+
+fn join<W>(worker_thread: WorkerThread<W>) -> W
+where
+    W: Worker + Send + 'static,
+    <W as Worker>::Environment: Send + 'static,
+{
+    match worker_thread.join() {
+        JoinedThread::Terminated(TerminatedThread { result, context }) => {
+            assert!(result.is_ok());
+            context.worker
+        }
+        JoinedThread::JoinError(err) => panic!("{err:?}"),
+    }
+}
+
+#[test]
+fn finish_after_cycles() {
+    for timing in [CyclicWorkerTiming::Sleeping, CyclicWorkerTiming::Waiting] {
+        let mut cycles = 0;
+        let worker = CyclicWorker::new(
+            CyclicWorkerConfig {
+                period: PERIOD,
+                timing,
+            },
+            move |_env: &(), tick: &CyclicTick| {
+                assert!(tick.jitter() < PERIOD);
+                cycles += 1;
+                Ok((cycles == 5).then_some(CompletionStatus::Finishing))
+            },
+        );
+        let context = Context {
+            progress_hint_rx: ProgressHintReceiver::default(),
+            worker,
+            environment: (),
+        };
+        let histograms = context.worker.histograms();
+        let started = Instant::now();
+        let worker = join(WorkerThread::spawn(context, ThreadScheduling::Default));
+        // The first cycle starts immediately
+        assert!(started.elapsed() >= 4 * PERIOD);
+        assert_eq!(5, worker.stats().unwrap().ticks);
+        assert_eq!(5, histograms.wakeup_jitter.snapshot().count());
+        assert_eq!(5, histograms.execution_time.snapshot().count());
+    }
+}
+
+#[test]
+fn suspend_resume_and_finish_by_progress_hint() {
+    for timing in [CyclicWorkerTiming::Sleeping, CyclicWorkerTiming::Waiting] {
+        let worker = CyclicWorker::new(
+            CyclicWorkerConfig {
+                period: PERIOD,
+                timing,
+            },
+            |_env: &(), _tick: &CyclicTick| Ok(None),
+        );
+        let progress_hint_rx = ProgressHintReceiver::default();
+        let progress_hint_tx = ProgressHintSender::attach(&progress_hint_rx);
+        let context = Context {
+            progress_hint_rx,
+            worker,
+            environment: (),
+        };
+        let worker_thread = WorkerThread::spawn(context, ThreadScheduling::Default);
+        assert!(progress_hint_tx.suspend().is_ok());
+        assert_eq!(State::Suspending, worker_thread.wait_until_not_running());
+        assert!(progress_hint_tx.resume().is_ok());
+        std::thread::sleep(3 * PERIOD);
+        assert!(progress_hint_tx.finish().is_ok());
+        let worker = join(worker_thread);
+        assert!(worker.stats().unwrap().ticks > 0);
+    }
+}
+
+#[test]
+fn abort_on_error() {
+    let mut worker = CyclicWorker::new(
+        CyclicWorkerConfig::new(PERIOD),
+        |_env: &(), _tick: &CyclicTick| anyhow::bail!("failed"),
+    );
+    let progress_hint_rx = ProgressHintReceiver::default();
+    let err = worker.perform_work(&(), &progress_hint_rx).unwrap_err();
+    assert_eq!("failed", err.to_string());
+    assert_eq!(1, worker.stats().unwrap().ticks);
+}
@@ -0,0 +1,240 @@
+use std::{
+    fmt,
+    time::{Duration, Instant},
+};
+
+use crate::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc,
+};
+
+/// Timing constraints for invocations of [`super::Worker::perform_work()`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlineSpec {
+    /// Maximum duration from the release until the completion
+    /// of an invocation
+    pub deadline: Duration,
+
+    /// Nominal interval between the releases of subsequent invocations
+    ///
+    /// If present, invocation `n` is released at `first + n * period`
+    /// independent of when it actually started. Otherwise each
+    /// invocation is released when it starts.
+    pub period: Option<Duration>,
+}
+
+impl DeadlineSpec {
+    /// Relative deadline for each invocation
+    #[must_use]
+    pub const fn new(deadline: Duration) -> Self {
+        Self {
+            deadline,
+            period: None,
+        }
+    }
+
+    /// Periodic invocations with an implicit deadline, i.e. each
+    /// invocation must complete before the next one is released
+    #[must_use]
+    pub const fn periodic(period: Duration) -> Self {
+        Self {
+            deadline: period,
+            period: Some(period),
+        }
+    }
+
+    #[must_use]
+    pub const fn with_period(self, period: Duration) -> Self {
+        Self {
+            period: Some(period),
+            ..self
+        }
+    }
+}
+
+/// An invocation that completed after its deadline
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DeadlineMiss {
+    /// Number of the invocation, starting at 0
+    pub invocation: u64,
+
+    pub released_at: Instant,
+    pub started_at: Instant,
+    pub completed_at: Instant,
+    pub deadline: Instant,
+}
+
+impl DeadlineMiss {
+    /// How much the deadline has been exceeded
+    #[must_use]
+    pub fn lateness(&self) -> Duration {
+        self.completed_at.duration_since(self.deadline)
+    }
+}
+
+impl fmt::Display for DeadlineMiss {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invocation {} missed its deadline by {:.3} ms",
+            self.invocation,
+            self.lateness().as_secs_f64() * 1000.0
+        )
+    }
+}
+
+/// Timing statistics of all invocations
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeadlineStats {
+    pub invocations: u64,
+    pub misses: u64,
+    pub last_execution_time: Duration,
+    pub max_execution_time: Duration,
+}
+
+/// Lock-free statistics that are shared with other threads
+#[derive(Debug, Default)]
+pub(super) struct SharedDeadlineStats {
+    invocations: AtomicU64,
+    misses: AtomicU64,
+    last_execution_nanos: AtomicU64,
+    max_execution_nanos: AtomicU64,
+}
+
+fn duration_to_nanos(duration: Duration) -> u64 {
+    duration.as_nanos().min(u128::from(u64::MAX)) as u64
+}
+
+impl SharedDeadlineStats {
+    fn record(&self, execution_time: Duration, missed: bool) {
+        let execution_nanos = duration_to_nanos(execution_time);
+        self.last_execution_nanos
+            .store(execution_nanos, Ordering::Relaxed);
+        self.max_execution_nanos
+            .fetch_max(execution_nanos, Ordering::Relaxed);
+        if missed {
+            self.misses.fetch_add(1, Ordering::Relaxed);
+        }
+        // Publish all preceding updates
+        self.invocations.fetch_add(1, Ordering::Release);
+    }
+
+    pub(super) fn load(&self) -> DeadlineStats {
+        let invocations = self.invocations.load(Ordering::Acquire);
+        DeadlineStats {
+            invocations,
+            misses: self.misses.load(Ordering::Relaxed),
+            last_execution_time: Duration::from_nanos(
+                self.last_execution_nanos.load(Ordering::Relaxed),
+            ),
+            max_execution_time: Duration::from_nanos(
+                self.max_execution_nanos.load(Ordering::Relaxed),
+            ),
+        }
+    }
+}
+
+/// Callback for reporting deadline misses
+///
+/// Invoked on the worker thread and thus should return quickly,
+/// e.g. by only sending the event through a channel.
+pub type DeadlineMissCallback = Box<dyn FnMut(&DeadlineMiss) + Send>;
+
+/// Measures the invocations of a worker and detects deadline misses
+pub struct DeadlineMonitor {
+    spec: DeadlineSpec,
+    on_miss: Option<DeadlineMissCallback>,
+    stats: Arc<SharedDeadlineStats>,
+    first_release: Option<(u64, Instant)>,
+}
+
+impl fmt::Debug for DeadlineMonitor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeadlineMonitor")
+            .field("spec", &self.spec)
+            .field("stats", &self.stats)
+            .field("first_release", &self.first_release)
+            .finish_non_exhaustive()
+    }
+}
+
+impl DeadlineMonitor {
+    #[must_use]
+    pub fn new(spec: DeadlineSpec) -> Self {
+        Self {
+            spec,
+            on_miss: None,
+            stats: Default::default(),
+            first_release: None,
+        }
+    }
+
+    /// Report each deadline miss to the callback
+    #[must_use]
+    pub fn with_callback(self, on_miss: impl FnMut(&DeadlineMiss) + Send + 'static) -> Self {
+        Self {
+            on_miss: Some(Box::new(on_miss)),
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub const fn spec(&self) -> &DeadlineSpec {
+        &self.spec
+    }
+
+    #[must_use]
+    pub fn stats(&self) -> DeadlineStats {
+        self.stats.load()
+    }
+
+    pub(super) fn shared_stats(&self) -> Arc<SharedDeadlineStats> {
+        Arc::clone(&self.stats)
+    }
+
+    /// Restart the periodic schedule
+    ///
+    /// The next invocation will be released when it starts, e.g.
+    /// after resuming a suspended worker.
+    pub fn restart(&mut self) {
+        self.first_release = None;
+    }
+
+    /// Record an invocation
+    ///
+    /// Returns the deadline miss after it has been reported to
+    /// the callback.
+    pub fn record(&mut self, started_at: Instant, completed_at: Instant) -> Option<DeadlineMiss> {
+        let invocation = self.stats.invocations.load(Ordering::Relaxed);
+        let released_at = match self.spec.period {
+            Some(period) => {
+                let (first_invocation, first_released_at) =
+                    *self.first_release.get_or_insert((invocation, started_at));
+                let periods = (invocation - first_invocation).min(u64::from(u32::MAX)) as u32;
+                first_released_at + period * periods
+            }
+            None => started_at,
+        };
+        let deadline = released_at + self.spec.deadline;
+        let missed = completed_at > deadline;
+        self.stats
+            .record(completed_at.duration_since(started_at), missed);
+        if !missed {
+            return None;
+        }
+        let miss = DeadlineMiss {
+            invocation,
+            released_at,
+            started_at,
+            completed_at,
+            deadline,
+        };
+        if let Some(on_miss) = &mut self.on_miss {
+            on_miss(&miss);
+        }
+        Some(miss)
+    }
+}
+
+#[cfg(test)]
+mod tests;
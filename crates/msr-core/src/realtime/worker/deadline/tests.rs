@@ -0,0 +1,56 @@
+use std::sync::mpsc;
+
+use super::*;
+
+const MS: Duration = Duration::from_millis(1);
+
+#[test]
+fn relative_deadline() {
+    let mut monitor = DeadlineMonitor::new(DeadlineSpec::new(2 * MS));
+    let t0 = Instant::now();
+    assert_eq!(None, monitor.record(t0, t0 + 2 * MS));
+    // Starting late doesn't matter without a period
+    assert_eq!(None, monitor.record(t0 + 10 * MS, t0 + 11 * MS));
+    let miss = monitor.record(t0 + 20 * MS, t0 + 23 * MS).unwrap();
+    assert_eq!(2, miss.invocation);
+    assert_eq!(t0 + 22 * MS, miss.deadline);
+    assert_eq!(MS, miss.lateness());
+    assert_eq!(
+        "invocation 2 missed its deadline by 1.000 ms",
+        miss.to_string()
+    );
+    assert_eq!(
+        DeadlineStats {
+            invocations: 3,
+            misses: 1,
+            last_execution_time: 3 * MS,
+            max_execution_time: 3 * MS,
+        },
+        monitor.stats()
+    );
+}
+
+#[test]
+fn periodic_deadline() {
+    let (tx, rx) = mpsc::channel();
+    let mut monitor = DeadlineMonitor::new(DeadlineSpec::periodic(5 * MS)).with_callback(
+        move |miss: &DeadlineMiss| {
+            tx.send(*miss).unwrap();
+        },
+    );
+    let t0 = Instant::now();
+    assert_eq!(None, monitor.record(t0, t0 + 4 * MS));
+    // Released at t0 + 5 ms, but started late
+    let miss = monitor.record(t0 + 8 * MS, t0 + 11 * MS).unwrap();
+    assert_eq!(t0 + 5 * MS, miss.released_at);
+    assert_eq!(t0 + 10 * MS, miss.deadline);
+    assert_eq!(Ok(miss), rx.try_recv());
+
+    // Restart the schedule, e.g. after resuming
+    monitor.restart();
+    assert_eq!(None, monitor.record(t0 + 100 * MS, t0 + 104 * MS));
+    assert_eq!(None, monitor.record(t0 + 105 * MS, t0 + 109 * MS));
+    assert!(rx.try_recv().is_err());
+    assert_eq!(1, monitor.stats().misses);
+    assert_eq!(4, monitor.stats().invocations);
+}
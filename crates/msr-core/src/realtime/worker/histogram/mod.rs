@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use crate::sync::atomic::{AtomicU64, Ordering};
+
+/// Number of bits for the sub-buckets of each power of two
+///
+/// The relative error of recorded values is less than 2^-PRECISION_BITS,
+/// i.e. 12.5%.
+const PRECISION_BITS: u32 = 3;
+
+const SUB_BUCKETS: u64 = 1 << PRECISION_BITS;
+
+/// Sufficient to cover all values of `u64`
+const BUCKETS: usize = ((u64::BITS - PRECISION_BITS + 1) as usize) * SUB_BUCKETS as usize;
+
+fn bucket_index(nanos: u64) -> usize {
+    if nanos < SUB_BUCKETS {
+        return nanos as usize;
+    }
+    let msb = u64::BITS - 1 - nanos.leading_zeros();
+    let shift = msb - PRECISION_BITS;
+    let sub_bucket = (nanos >> shift) - SUB_BUCKETS;
+    ((u64::from(shift) + 1) * SUB_BUCKETS + sub_bucket) as usize
+}
+
+/// The lowest value of a bucket
+fn bucket_lower_bound(index: usize) -> u64 {
+    let index = index as u64;
+    if index < SUB_BUCKETS {
+        return index;
+    }
+    let shift = index / SUB_BUCKETS - 1;
+    let sub_bucket = index % SUB_BUCKETS;
+    (SUB_BUCKETS + sub_bucket) << shift
+}
+
+/// The highest value of a bucket
+fn bucket_upper_bound(index: usize) -> u64 {
+    if index + 1 < BUCKETS {
+        bucket_lower_bound(index + 1) - 1
+    } else {
+        u64::MAX
+    }
+}
+
+/// Histogram of durations with logarithmic buckets
+///
+/// All buckets are preallocated. Recording is lock-free and doesn't
+/// allocate. It could safely be invoked in a real-time context while
+/// other threads read snapshots.
+///
+/// Durations are recorded with nanosecond resolution and a relative
+/// error of less than 12.5%.
+#[derive(Debug)]
+pub struct DurationHistogram {
+    buckets: Box<[AtomicU64]>,
+}
+
+impl Default for DurationHistogram {
+    fn default() -> Self {
+        Self {
+            buckets: (0..BUCKETS).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+}
+
+impl DurationHistogram {
+    /// Record a duration (lock-free)
+    pub fn record(&self, duration: Duration) {
+        let nanos = duration.as_nanos().min(u128::from(u64::MAX)) as u64;
+        self.buckets[bucket_index(nanos)].fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Reset all buckets
+    ///
+    /// Values that are recorded concurrently might get lost.
+    pub fn reset(&self) {
+        for bucket in &*self.buckets {
+            bucket.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// Take a snapshot of all non-empty buckets (allocating)
+    #[must_use]
+    pub fn snapshot(&self) -> DurationHistogramSnapshot {
+        let buckets = self
+            .buckets
+            .iter()
+            .enumerate()
+            .filter_map(|(index, bucket)| {
+                let count = bucket.load(Ordering::Relaxed);
+                (count > 0).then(|| HistogramBucket {
+                    lower_bound: Duration::from_nanos(bucket_lower_bound(index)),
+                    upper_bound: Duration::from_nanos(bucket_upper_bound(index)),
+                    count,
+                })
+            })
+            .collect();
+        DurationHistogramSnapshot { buckets }
+    }
+}
+
+/// A range of durations with the number of recorded values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HistogramBucket {
+    pub lower_bound: Duration,
+    pub upper_bound: Duration,
+    pub count: u64,
+}
+
+/// The contents of a [`DurationHistogram`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DurationHistogramSnapshot {
+    /// Non-empty buckets in ascending order
+    pub buckets: Vec<HistogramBucket>,
+}
+
+impl DurationHistogramSnapshot {
+    /// Total number of recorded values
+    #[must_use]
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().map(|bucket| bucket.count).sum()
+    }
+
+    /// Upper bound of the lowest recorded value
+    #[must_use]
+    pub fn min(&self) -> Option<Duration> {
+        self.buckets.first().map(|bucket| bucket.upper_bound)
+    }
+
+    /// Upper bound of the highest recorded value
+    #[must_use]
+    pub fn max(&self) -> Option<Duration> {
+        self.buckets.last().map(|bucket| bucket.upper_bound)
+    }
+
+    /// Upper bound of the value at the given percentile (0.0..=100.0)
+    #[must_use]
+    pub fn value_at_percentile(&self, percentile: f64) -> Option<Duration> {
+        let count = self.count();
+        if count == 0 {
+            return None;
+        }
+        #[allow(clippy::cast_precision_loss, clippy::cast_sign_loss)]
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0 * count as f64).ceil() as u64).max(1);
+        let mut cumulative_count = 0;
+        self.buckets.iter().find_map(|bucket| {
+            cumulative_count += bucket.count;
+            (cumulative_count >= rank).then_some(bucket.upper_bound)
+        })
+    }
+}
+
+/// Timing histograms of a cyclic worker
+#[derive(Debug, Default)]
+pub struct CyclicHistograms {
+    /// Execution time of each cycle
+    pub execution_time: DurationHistogram,
+
+    /// Delay between the nominal and the actual start of each cycle
+    pub wakeup_jitter: DurationHistogram,
+}
+
+#[cfg(test)]
+mod tests;
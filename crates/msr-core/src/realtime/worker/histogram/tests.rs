@@ -0,0 +1,44 @@
+use super::*;
+
+#[test]
+fn bucket_bounds() {
+    for nanos in [0, 1, 7, 8, 9, 15, 16, 17, 1_000, 123_456_789, u64::MAX] {
+        let index = bucket_index(nanos);
+        assert!(index < BUCKETS);
+        assert!(bucket_lower_bound(index) <= nanos);
+        assert!(nanos <= bucket_upper_bound(index));
+    }
+    assert_eq!(BUCKETS - 1, bucket_index(u64::MAX));
+    // Buckets are contiguous
+    for index in 1..BUCKETS {
+        assert_eq!(bucket_upper_bound(index - 1) + 1, bucket_lower_bound(index));
+    }
+    // Relative error
+    let index = bucket_index(1_000_000);
+    let width = bucket_upper_bound(index) - bucket_lower_bound(index) + 1;
+    assert!(width * SUB_BUCKETS <= bucket_lower_bound(index));
+}
+
+#[test]
+fn record_and_snapshot() {
+    let histogram = DurationHistogram::default();
+    assert_eq!(None, histogram.snapshot().value_at_percentile(50.0));
+    for micros in 1..=100 {
+        histogram.record(Duration::from_micros(micros));
+    }
+    histogram.record(Duration::from_millis(10));
+    let snapshot = histogram.snapshot();
+    assert_eq!(101, snapshot.count());
+    assert!(snapshot.min().unwrap() >= Duration::from_micros(1));
+    assert!(snapshot.max().unwrap() >= Duration::from_millis(10));
+    let median = snapshot.value_at_percentile(50.0).unwrap();
+    assert!(median >= Duration::from_micros(51));
+    assert!(median < Duration::from_micros(58));
+    let p99 = snapshot.value_at_percentile(99.0).unwrap();
+    assert!(p99 >= Duration::from_micros(100));
+    assert!(p99 < Duration::from_micros(113));
+    assert_eq!(snapshot.max(), snapshot.value_at_percentile(100.0));
+
+    histogram.reset();
+    assert_eq!(0, histogram.snapshot().count());
+}
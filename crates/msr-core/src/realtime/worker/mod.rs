@@ -3,7 +3,14 @@ use anyhow::Result;
 pub mod progress;
 use self::progress::ProgressHintReceiver;
 
+#[cfg(feature = "realtime-worker-tokio")]
+pub mod bridge;
+pub mod cyclic;
+pub mod deadline;
+pub mod histogram;
+pub mod pool;
 pub mod thread;
+pub mod watchdog;
 
 /// Completion status
 ///
@@ -24,6 +31,12 @@ pub enum CompletionStatus {
     /// The worker has accomplished its task and expects to be
     /// finished.
     Finishing,
+
+    /// Working should be aborted immediately
+    ///
+    /// The worker will not be finished, i.e. [`Worker::finish_working()`]
+    /// is not invoked.
+    Aborting,
 }
 
 /// Callback interface for performing work under real-time constraints
@@ -91,5 +104,7 @@ pub trait Worker {
     ///
     /// Invoked once after the last call to [`Worker::perform_work()`] for
     /// finalizing results, releasing resources, and performing cleanup.
+    ///
+    /// Not invoked if the worker has been aborted.
     fn finish_working(&mut self, env: &mut Self::Environment) -> Result<()>;
 }
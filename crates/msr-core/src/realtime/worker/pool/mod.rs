@@ -0,0 +1,182 @@
+use std::{collections::VecDeque, time::Duration};
+
+use crate::sync::{Arc, Condvar, Mutex};
+
+use super::{
+    progress::{ProgressHintReceiver, ProgressHintSenderGroup},
+    thread::{Context, JoinedThread, State, ThreadScheduling, WorkerThread},
+    Worker,
+};
+
+/// Queue for distributing work among the workers of a pool
+///
+/// Pushing and popping items requires locking and might block
+/// briefly. Workers should only access the queue in between
+/// time-critical sections.
+#[derive(Debug)]
+pub struct WorkQueue<T> {
+    items: Mutex<VecDeque<T>>,
+    item_pushed: Condvar,
+}
+
+impl<T> Default for WorkQueue<T> {
+    fn default() -> Self {
+        Self {
+            items: Default::default(),
+            item_pushed: Default::default(),
+        }
+    }
+}
+
+impl<T> WorkQueue<T> {
+    /// Append an item and wake up a single waiting worker
+    #[allow(clippy::missing_panics_doc)]
+    pub fn push(&self, item: T) {
+        let mut items = self.items.lock().expect("not poisoned");
+        items.push_back(item);
+        drop(items);
+        self.item_pushed.notify_one();
+    }
+
+    /// Take the next item (non-blocking)
+    #[allow(clippy::missing_panics_doc)]
+    pub fn pop(&self) -> Option<T> {
+        self.items.lock().expect("not poisoned").pop_front()
+    }
+
+    /// Take the next item and wait for it with a timeout (blocking)
+    ///
+    /// Returns `None` if no item became available before the
+    /// timeout expired.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn pop_timeout(&self, timeout: Duration) -> Option<T> {
+        let items = self.items.lock().expect("not poisoned");
+        let (mut items, _) = self
+            .item_pushed
+            .wait_timeout_while(items, timeout, |items| items.is_empty())
+            .expect("not poisoned");
+        items.pop_front()
+    }
+
+    #[allow(clippy::missing_panics_doc)]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.items.lock().expect("not poisoned").len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// A pool of worker threads
+///
+/// All workers share the same [`WorkQueue`] and are controlled
+/// together by a [`ProgressHintSenderGroup`].
+#[derive(Debug)]
+pub struct WorkerPool<W: Worker, T> {
+    threads: Vec<WorkerThread<W>>,
+    progress_hint_tx: ProgressHintSenderGroup,
+    queue: Arc<WorkQueue<T>>,
+}
+
+impl<W, T> WorkerPool<W, T>
+where
+    W: Worker + Send + 'static,
+    <W as Worker>::Environment: Send + 'static,
+{
+    /// Spawn a pool with `size` worker threads
+    ///
+    /// The workers and their environment are created by invoking
+    /// `new_worker` with the index of the worker and the shared
+    /// work queue.
+    pub fn spawn(
+        size: usize,
+        mut new_worker: impl FnMut(usize, &Arc<WorkQueue<T>>) -> (W, W::Environment),
+        thread_scheduling: ThreadScheduling,
+    ) -> Self {
+        let queue = Arc::new(WorkQueue::default());
+        let mut progress_hint_tx = ProgressHintSenderGroup::default();
+        let threads = (0..size)
+            .map(|index| {
+                let (worker, environment) = new_worker(index, &queue);
+                let progress_hint_rx = ProgressHintReceiver::default();
+                progress_hint_tx.attach(&progress_hint_rx);
+                let context = Context {
+                    progress_hint_rx,
+                    worker,
+                    environment,
+                };
+                WorkerThread::spawn(context, thread_scheduling)
+            })
+            .collect();
+        Self {
+            threads,
+            progress_hint_tx,
+            queue,
+        }
+    }
+
+    /// Suspend, resume, or finish all workers
+    #[must_use]
+    pub const fn progress_hint_tx(&self) -> &ProgressHintSenderGroup {
+        &self.progress_hint_tx
+    }
+
+    #[must_use]
+    pub const fn queue(&self) -> &Arc<WorkQueue<T>> {
+        &self.queue
+    }
+
+    /// Submit an item to the work queue
+    pub fn submit(&self, item: T) {
+        self.queue.push(item);
+    }
+
+    /// The number of worker threads
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.threads.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.threads.is_empty()
+    }
+
+    /// The current state of each worker
+    #[must_use]
+    pub fn load_states(&self) -> Vec<State> {
+        self.threads.iter().map(WorkerThread::load_state).collect()
+    }
+
+    /// Wait until all workers have stopped running
+    #[allow(clippy::must_use_candidate)]
+    pub fn wait_until_not_running(&self) -> Vec<State> {
+        self.threads
+            .iter()
+            .map(WorkerThread::wait_until_not_running)
+            .collect()
+    }
+
+    /// Ask all workers to finish and join their threads
+    ///
+    /// Items that are still pending in the work queue are discarded.
+    pub fn finish_and_join(self) -> Vec<JoinedThread<W>> {
+        let Self {
+            threads,
+            progress_hint_tx,
+            queue: _,
+        } = self;
+        for result in progress_hint_tx.finish() {
+            if let Err(err) = result {
+                log::warn!("Failed to finish worker: {err}");
+            }
+        }
+        threads.into_iter().map(WorkerThread::join).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,131 @@
+use anyhow::Result;
+
+use crate::realtime::worker::{
+    progress::{ProgressHint, SwitchProgressHintOk},
+    thread::TerminatedThread,
+    CompletionStatus,
+};
+
+use super::*;
+
+struct SummingWorker {
+    queue: Arc<WorkQueue<u64>>,
+    sum: u64,
+    count: usize,
+}
+
+impl Worker for SummingWorker {
+    type Environment = ();
+
+    fn start_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+        Ok(())
+    }
+
+    fn perform_work(
+        &mut self,
+        _env: &Self::Environment,
+        progress_hint_rx: &ProgressHintReceiver,
+    ) -> Result<CompletionStatus> {
+        loop {
+            match progress_hint_rx.peek() {
+                ProgressHint::Continue => (),
+                ProgressHint::Suspend => return Ok(CompletionStatus::Suspending),
+                ProgressHint::Finish => return Ok(CompletionStatus::Finishing),
+                ProgressHint::Abort => return Ok(CompletionStatus::Aborting),
+            }
+            if let Some(item) = self.queue.pop_timeout(Duration::from_millis(1)) {
+                self.sum += item;
+                self.count += 1;
+            }
+        }
+    }
+}
+
+fn spawn_pool(size: usize) -> WorkerPool<SummingWorker, u64> {
+    WorkerPool::spawn(
+        size,
+        |_index, queue| {
+            let worker = SummingWorker {
+                queue: Arc::clone(queue),
+                sum: 0,
+                count: 0,
+            };
+            (worker, ())
+        },
+        ThreadScheduling::Default,
+    )
+}
+
+fn wait_until_queue_is_empty<T>(queue: &WorkQueue<T>) {
+    while !queue.is_empty() {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+}
+
+#[test]
+fn work_queue() {
+    let queue = WorkQueue::default();
+    assert!(queue.is_empty());
+    assert_eq!(None, queue.pop_timeout(Duration::ZERO));
+    queue.push(1);
+    queue.push(2);
+    assert_eq!(2, queue.len());
+    assert_eq!(Some(1), queue.pop());
+    assert_eq!(Some(2), queue.pop_timeout(Duration::ZERO));
+    assert_eq!(None, queue.pop());
+}
+
+#[test]
+fn distribute_work_and_join() {
+    let pool = spawn_pool(3);
+    assert_eq!(3, pool.len());
+    for item in 1..=100 {
+        pool.submit(item);
+    }
+    wait_until_queue_is_empty(pool.queue());
+    let workers = pool
+        .finish_and_join()
+        .into_iter()
+        .map(|joined| match joined {
+            JoinedThread::Terminated(TerminatedThread { result, context }) => {
+                assert!(result.is_ok());
+                context.worker
+            }
+            JoinedThread::JoinError(err) => panic!("{err:?}"),
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(3, workers.len());
+    assert_eq!(5050, workers.iter().map(|w| w.sum).sum::<u64>());
+    assert_eq!(100, workers.iter().map(|w| w.count).sum::<usize>());
+}
+
+#[test]
+fn suspend_and_resume_all_workers() {
+    let pool = spawn_pool(2);
+    assert!(pool
+        .progress_hint_tx()
+        .suspend()
+        .into_iter()
+        .all(|res| matches!(res, Ok(SwitchProgressHintOk::Accepted { .. }))));
+    assert_eq!(vec![State::Suspending; 2], pool.wait_until_not_running());
+    assert_eq!(vec![State::Suspending; 2], pool.load_states());
+
+    // Pending work is not processed while suspended
+    pool.submit(1);
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(1, pool.queue().len());
+
+    assert!(pool.progress_hint_tx().resume().iter().all(Result::is_ok));
+    wait_until_queue_is_empty(pool.queue());
+
+    for joined in pool.finish_and_join() {
+        assert!(matches!(
+            joined,
+            JoinedThread::Terminated(TerminatedThread { result: Ok(()), .. })
+        ));
+    }
+}
@@ -30,6 +30,12 @@ pub enum ProgressHint {
     /// Worker should complete the current unit of work asap
     /// with [`super::CompletionStatus::Finishing`].
     Finish,
+
+    /// Worker should stop immediately with [`super::CompletionStatus::Aborting`]
+    ///
+    /// Intended for emergency stops. The current unit of work is
+    /// interrupted and the worker will not be finished.
+    Abort,
 }
 
 type AtomicValue = u8;
@@ -37,6 +43,7 @@ type AtomicValue = u8;
 const PROGRESS_HINT_CONTINUE: AtomicValue = 0;
 const PROGRESS_HINT_SUSPENDING: AtomicValue = 1;
 const PROGRESS_HINT_FINISHING: AtomicValue = 2;
+const PROGRESS_HINT_ABORTING: AtomicValue = 3;
 
 /// Atomic [`ProgressHint`]
 #[derive(Debug)]
@@ -47,6 +54,7 @@ fn progress_hint_from_atomic_state(from: AtomicValue) -> ProgressHint {
         PROGRESS_HINT_CONTINUE => ProgressHint::Continue,
         PROGRESS_HINT_SUSPENDING => ProgressHint::Suspend,
         PROGRESS_HINT_FINISHING => ProgressHint::Finish,
+        PROGRESS_HINT_ABORTING => ProgressHint::Abort,
         unexpected_value => unreachable!("unexpected progress hint value: {}", unexpected_value),
     }
 }
@@ -56,6 +64,7 @@ const fn progress_hint_to_atomic_state(from: ProgressHint) -> AtomicValue {
         ProgressHint::Continue => PROGRESS_HINT_CONTINUE,
         ProgressHint::Suspend => PROGRESS_HINT_SUSPENDING,
         ProgressHint::Finish => PROGRESS_HINT_FINISHING,
+        ProgressHint::Abort => PROGRESS_HINT_ABORTING,
     }
 }
 
@@ -155,13 +164,27 @@ impl AtomicProgressHint {
         self.switch_from_expected_to_desired(ProgressHint::Suspend, ProgressHint::Continue)
     }
 
-    /// Switch from any state to [`ProgressHint::Finish`]
+    /// Switch from any state except [`ProgressHint::Abort`] to [`ProgressHint::Finish`]
     ///
-    /// Currently, finishing is permitted in any state. But this
-    /// may change in the future.
-    #[allow(clippy::unnecessary_wraps)] // Result
+    /// An abort request must not be overridden by a less urgent request.
     fn finish(&self) -> SwitchAtomicStateResult<ProgressHint> {
-        Ok(self.switch_to_desired(ProgressHint::Finish))
+        let mut current_state = self.load();
+        loop {
+            if current_state == ProgressHint::Abort {
+                return Err(SwitchAtomicStateErr::Rejected { current_state });
+            }
+            match self.switch_from_expected_to_desired(current_state, ProgressHint::Finish) {
+                Ok(ok) => return Ok(ok),
+                Err(SwitchAtomicStateErr::Rejected {
+                    current_state: next_state,
+                }) => current_state = next_state,
+            }
+        }
+    }
+
+    /// Switch from any state to [`ProgressHint::Abort`]
+    fn abort(&self) -> SwitchAtomicStateOk<ProgressHint> {
+        self.switch_to_desired(ProgressHint::Abort)
     }
 
     /// Reset to [`ProgressHint::default()`]
@@ -283,6 +306,10 @@ impl ProgressHintHandover {
         self.after_latest_progress_hint_switched_result(self.latest_progress_hint.finish())
     }
 
+    fn abort(&self) -> SwitchProgressHintOk {
+        self.after_latest_progress_hint_switched_ok(self.latest_progress_hint.abort())
+    }
+
     fn wait(&self) {
         self.update_notification_relay.wait();
     }
@@ -313,6 +340,12 @@ impl ProgressHintHandover {
         // is supposed to be invoked only by the single receiver!
         self.latest_progress_hint.finish().is_ok()
     }
+
+    fn set_aborting(&self) {
+        // No update notification needed nor intended as this function
+        // is supposed to be invoked only by the single receiver!
+        self.latest_progress_hint.abort();
+    }
 }
 
 /// Sender of the progress hint handover protocol
@@ -356,6 +389,72 @@ impl ProgressHintSender {
         self.upgrade_handover()
             .and_then(|handover| handover.finish())
     }
+
+    /// Ask the receiver to abort immediately without finishing
+    ///
+    /// Aborting is permitted in any state and could not be reverted
+    /// by the other requests.
+    pub fn abort(&self) -> SwitchProgressHintResult {
+        self.upgrade_handover().map(|handover| handover.abort())
+    }
+}
+
+/// Senders for a group of receivers
+///
+/// Broadcasts progress hints to multiple receivers, e.g. to all
+/// workers of a pool.
+#[derive(Debug, Clone, Default)]
+pub struct ProgressHintSenderGroup {
+    senders: Vec<ProgressHintSender>,
+}
+
+impl ProgressHintSenderGroup {
+    /// Attach a new receiver to the group
+    pub fn attach(&mut self, rx: &ProgressHintReceiver) {
+        self.senders.push(ProgressHintSender::attach(rx));
+    }
+
+    #[must_use]
+    pub fn senders(&self) -> &[ProgressHintSender] {
+        &self.senders
+    }
+
+    /// Ask all receivers to suspend while running
+    ///
+    /// Returns the results for each receiver in order of attachment.
+    pub fn suspend(&self) -> Vec<SwitchProgressHintResult> {
+        self.senders
+            .iter()
+            .map(ProgressHintSender::suspend)
+            .collect()
+    }
+
+    /// Ask all receivers to resume while suspended
+    ///
+    /// Returns the results for each receiver in order of attachment.
+    pub fn resume(&self) -> Vec<SwitchProgressHintResult> {
+        self.senders
+            .iter()
+            .map(ProgressHintSender::resume)
+            .collect()
+    }
+
+    /// Ask all receivers to finish
+    ///
+    /// Returns the results for each receiver in order of attachment.
+    pub fn finish(&self) -> Vec<SwitchProgressHintResult> {
+        self.senders
+            .iter()
+            .map(ProgressHintSender::finish)
+            .collect()
+    }
+
+    /// Ask all receivers to abort immediately without finishing
+    ///
+    /// Returns the results for each receiver in order of attachment.
+    pub fn abort(&self) -> Vec<SwitchProgressHintResult> {
+        self.senders.iter().map(ProgressHintSender::abort).collect()
+    }
 }
 
 /// Receiver of the progress hint handover protocol
@@ -472,6 +571,16 @@ impl ProgressHintReceiver {
         self.handover.try_finishing()
     }
 
+    /// Reserved for internal usage
+    ///
+    /// Silently switch to [`ProgressHint::Abort`] (lock-free).
+    ///
+    /// Intentionally declared as &mut to make it inaccessible for
+    /// borrowed references!
+    pub fn set_aborting(&mut self) {
+        self.handover.set_aborting();
+    }
+
     /// Reserved for internal usage
     ///
     /// Reset the handover (blocking).
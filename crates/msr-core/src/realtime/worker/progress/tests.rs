@@ -110,6 +110,55 @@ fn atomic_progress_hint_sequence() {
     assert_eq!(ProgressHint::Finish, progress_hint.load());
 }
 
+#[test]
+fn atomic_progress_hint_abort() {
+    let progress_hint = AtomicProgressHint::default();
+
+    // Abort while suspended
+    assert!(progress_hint.suspend().is_ok());
+    assert_eq!(
+        SwitchAtomicStateOk::Accepted {
+            previous_state: ProgressHint::Suspend,
+        },
+        progress_hint.abort()
+    );
+    assert_eq!(ProgressHint::Abort, progress_hint.load());
+
+    // Abort again
+    assert_eq!(SwitchAtomicStateOk::Ignored, progress_hint.abort());
+
+    // Reject all other requests after aborted
+    for switch in [
+        AtomicProgressHint::suspend,
+        AtomicProgressHint::resume,
+        AtomicProgressHint::finish,
+    ] {
+        assert_eq!(
+            Err(SwitchAtomicStateErr::Rejected {
+                current_state: ProgressHint::Abort,
+            }),
+            switch(&progress_hint)
+        );
+        assert_eq!(ProgressHint::Abort, progress_hint.load());
+    }
+
+    // Abort after finished
+    assert_eq!(
+        SwitchAtomicStateOk::Accepted {
+            previous_state: ProgressHint::Abort,
+        },
+        progress_hint.reset()
+    );
+    assert!(progress_hint.finish().is_ok());
+    assert_eq!(
+        SwitchAtomicStateOk::Accepted {
+            previous_state: ProgressHint::Finish,
+        },
+        progress_hint.abort()
+    );
+    assert_eq!(ProgressHint::Abort, progress_hint.load());
+}
+
 #[test]
 fn progress_hint_sender_receiver_attach_detach() -> anyhow::Result<()> {
     let mut rx = ProgressHintReceiver::default();
@@ -250,3 +299,35 @@ fn progress_hint_handover_try_switch_without_update_notification() {
     // No update notification after try_finishing()
     assert!(!rx.wait_until(Instant::now()));
 }
+
+#[test]
+fn progress_hint_sender_group_broadcast() {
+    let rx1 = ProgressHintReceiver::default();
+    let mut rx2 = ProgressHintReceiver::default();
+    let mut tx = ProgressHintSenderGroup::default();
+    tx.attach(&rx1);
+    tx.attach(&rx2);
+    assert_eq!(2, tx.senders().len());
+
+    assert!(tx.suspend().into_iter().all(|res| matches!(
+        res,
+        Ok(SwitchProgressHintOk::Accepted {
+            previous_state: ProgressHint::Continue
+        })
+    )));
+    assert_eq!(ProgressHint::Suspend, rx1.load());
+    assert_eq!(ProgressHint::Suspend, rx2.load());
+
+    // Results are reported per receiver
+    rx2.detach();
+    let results = tx.resume();
+    assert!(matches!(
+        results[0],
+        Ok(SwitchProgressHintOk::Accepted { .. })
+    ));
+    assert!(matches!(results[1], Err(SwitchProgressHintError::Detached)));
+    assert_eq!(ProgressHint::Continue, rx1.load());
+
+    assert!(tx.finish()[0].is_ok());
+    assert_eq!(ProgressHint::Finish, rx1.load());
+}
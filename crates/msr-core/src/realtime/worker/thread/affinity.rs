@@ -0,0 +1,182 @@
+//! Pinning of threads to CPU cores
+
+use std::{fmt, io};
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum CpuAffinityError {
+    #[error("no CPUs")]
+    Empty,
+
+    #[error("invalid CPU {0}")]
+    InvalidCpu(usize),
+
+    #[error("CPU affinity is not supported on this platform")]
+    Unsupported,
+
+    #[error(transparent)]
+    Os(#[from] io::Error),
+}
+
+/// The set of CPU cores on which a thread is permitted to run
+///
+/// Pinning real-time threads to isolated cores (e.g. by the kernel
+/// parameter `isolcpus` on Linux) reduces scheduling jitter.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CpuAffinity {
+    cpus: Vec<usize>,
+}
+
+impl CpuAffinity {
+    /// Create a new affinity for the given CPU indexes
+    ///
+    /// Duplicate indexes are removed.
+    #[must_use]
+    pub fn new(cpus: impl IntoIterator<Item = usize>) -> Self {
+        let mut cpus: Vec<_> = cpus.into_iter().collect();
+        cpus.sort_unstable();
+        cpus.dedup();
+        Self { cpus }
+    }
+
+    /// Pin to a single CPU
+    #[must_use]
+    pub fn single(cpu: usize) -> Self {
+        Self { cpus: vec![cpu] }
+    }
+
+    /// The CPU indexes in ascending order
+    #[must_use]
+    pub fn cpus(&self) -> &[usize] {
+        &self.cpus
+    }
+
+    /// Query the affinity of the current thread
+    pub fn of_current_thread() -> Result<Self, CpuAffinityError> {
+        imp::current_thread_affinity().map(|cpus| Self { cpus })
+    }
+
+    /// Pin the current thread
+    pub fn apply_to_current_thread(&self) -> Result<(), CpuAffinityError> {
+        if self.cpus.is_empty() {
+            return Err(CpuAffinityError::Empty);
+        }
+        imp::set_current_thread_affinity(&self.cpus)
+    }
+}
+
+impl fmt::Display for CpuAffinity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cpus: Vec<_> = self.cpus.iter().map(ToString::to_string).collect();
+        write!(f, "[{}]", cpus.join(","))
+    }
+}
+
+#[cfg(target_os = "linux")]
+#[allow(unsafe_code)]
+mod imp {
+    use std::{io, mem};
+
+    use super::CpuAffinityError;
+
+    pub(super) fn set_current_thread_affinity(cpus: &[usize]) -> Result<(), CpuAffinityError> {
+        // SAFETY: An all-zero cpu_set_t is a valid, empty set
+        let mut cpu_set: libc::cpu_set_t = unsafe { mem::zeroed() };
+        let max_cpus = mem::size_of::<libc::cpu_set_t>() * 8;
+        for &cpu in cpus {
+            if cpu >= max_cpus {
+                return Err(CpuAffinityError::InvalidCpu(cpu));
+            }
+            // SAFETY: The index is within the bounds of the set
+            unsafe { libc::CPU_SET(cpu, &mut cpu_set) };
+        }
+        // SAFETY: The pointer and size refer to a valid set and
+        // pid 0 refers to the current thread
+        let res =
+            unsafe { libc::sched_setaffinity(0, mem::size_of::<libc::cpu_set_t>(), &cpu_set) };
+        if res != 0 {
+            let err = io::Error::last_os_error();
+            if err.raw_os_error() == Some(libc::EINVAL) && cpus.len() == 1 {
+                // None of the CPUs is available
+                return Err(CpuAffinityError::InvalidCpu(cpus[0]));
+            }
+            return Err(err.into());
+        }
+        Ok(())
+    }
+
+    pub(super) fn current_thread_affinity() -> Result<Vec<usize>, CpuAffinityError> {
+        // SAFETY: An all-zero cpu_set_t is a valid, empty set
+        let mut cpu_set: libc::cpu_set_t = unsafe { mem::zeroed() };
+        // SAFETY: The pointer and size refer to a valid set and
+        // pid 0 refers to the current thread
+        let res =
+            unsafe { libc::sched_getaffinity(0, mem::size_of::<libc::cpu_set_t>(), &mut cpu_set) };
+        if res != 0 {
+            return Err(io::Error::last_os_error().into());
+        }
+        let max_cpus = mem::size_of::<libc::cpu_set_t>() * 8;
+        // SAFETY: All indexes are within the bounds of the set
+        let cpus = (0..max_cpus)
+            .filter(|&cpu| unsafe { libc::CPU_ISSET(cpu, &cpu_set) })
+            .collect();
+        Ok(cpus)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use super::CpuAffinityError;
+
+    pub(super) fn set_current_thread_affinity(_cpus: &[usize]) -> Result<(), CpuAffinityError> {
+        Err(CpuAffinityError::Unsupported)
+    }
+
+    pub(super) fn current_thread_affinity() -> Result<Vec<usize>, CpuAffinityError> {
+        Err(CpuAffinityError::Unsupported)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn new_cpu_affinity() {
+        let affinity = CpuAffinity::new([3, 1, 3]);
+        assert_eq!(&[1, 3], affinity.cpus());
+        assert_eq!("[1,3]", affinity.to_string());
+        assert_eq!(&[2], CpuAffinity::single(2).cpus());
+    }
+
+    #[test]
+    fn reject_invalid_cpus() {
+        assert!(matches!(
+            CpuAffinity::new([]).apply_to_current_thread(),
+            Err(CpuAffinityError::Empty)
+        ));
+        let err = std::thread::spawn(|| CpuAffinity::single(usize::MAX).apply_to_current_thread())
+            .join()
+            .unwrap()
+            .unwrap_err();
+        if cfg!(target_os = "linux") {
+            assert!(matches!(err, CpuAffinityError::InvalidCpu(usize::MAX)));
+        } else {
+            assert!(matches!(err, CpuAffinityError::Unsupported));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn pin_current_thread() {
+        std::thread::spawn(|| {
+            let available = CpuAffinity::of_current_thread().unwrap();
+            let pinned = CpuAffinity::single(available.cpus()[0]);
+            pinned.apply_to_current_thread().unwrap();
+            assert_eq!(pinned, CpuAffinity::of_current_thread().unwrap());
+        })
+        .join()
+        .unwrap();
+    }
+}
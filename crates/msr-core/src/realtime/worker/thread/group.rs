@@ -0,0 +1,285 @@
+//! Consistent control of multiple worker threads
+
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use super::{
+    super::{
+        progress::{ProgressHintSender, SwitchProgressHintOk, SwitchProgressHintResult},
+        Worker,
+    },
+    SharedState, State, WorkerThread,
+};
+
+#[derive(Debug)]
+struct Member {
+    progress_hint_tx: ProgressHintSender,
+    shared_state: Arc<SharedState>,
+}
+
+/// Outcome of switching the progress hints of all workers in a group
+///
+/// Contains the results for each worker in order of attachment
+/// if switching failed for at least one worker.
+pub type GroupSwitchResult = Result<Vec<SwitchProgressHintOk>, Vec<SwitchProgressHintResult>>;
+
+/// Controls a group of worker threads as a whole
+///
+/// In contrast to a [`ProgressHintSenderGroup`](super::super::progress::ProgressHintSenderGroup)
+/// the group also observes the states of the attached worker threads,
+/// e.g. for pausing an entire acquisition pipeline consistently.
+///
+/// Switching is all or nothing: If a single worker rejects a request
+/// the switches that have already been accepted by other workers are
+/// reverted. Finishing could not be reverted.
+#[derive(Debug, Default)]
+pub struct ProgressHintGroup {
+    members: Vec<Member>,
+}
+
+impl ProgressHintGroup {
+    /// Attach a worker thread to the group
+    pub fn attach<W: Worker>(&mut self, worker_thread: &WorkerThread<W>) {
+        self.members.push(Member {
+            progress_hint_tx: worker_thread.progress_hint_tx.clone(),
+            shared_state: Arc::clone(&worker_thread.shared_state),
+        });
+    }
+
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// The current states of all worker threads in order of attachment
+    #[must_use]
+    pub fn load_states(&self) -> Vec<State> {
+        self.members
+            .iter()
+            .map(|member| member.shared_state.load_state())
+            .collect()
+    }
+
+    fn switch_all(
+        &self,
+        switch: impl Fn(&ProgressHintSender) -> SwitchProgressHintResult,
+        revert: Option<impl Fn(&ProgressHintSender) -> SwitchProgressHintResult>,
+    ) -> GroupSwitchResult {
+        let results: Vec<_> = self
+            .members
+            .iter()
+            .map(|member| switch(&member.progress_hint_tx))
+            .collect();
+        if results.iter().all(Result::is_ok) {
+            return Ok(results.into_iter().map(Result::unwrap).collect());
+        }
+        if let Some(revert) = revert {
+            for (member, result) in self.members.iter().zip(&results) {
+                if !matches!(result, Ok(SwitchProgressHintOk::Accepted { .. })) {
+                    continue;
+                }
+                if let Err(err) = revert(&member.progress_hint_tx) {
+                    log::warn!("Failed to revert progress hint: {err}");
+                }
+            }
+        }
+        Err(results)
+    }
+
+    /// Ask all workers to suspend while running
+    pub fn suspend(&self) -> GroupSwitchResult {
+        self.switch_all(
+            ProgressHintSender::suspend,
+            Some(ProgressHintSender::resume),
+        )
+    }
+
+    /// Ask all workers to resume while suspended
+    pub fn resume(&self) -> GroupSwitchResult {
+        self.switch_all(
+            ProgressHintSender::resume,
+            Some(ProgressHintSender::suspend),
+        )
+    }
+
+    /// Ask all workers to finish
+    pub fn finish(&self) -> GroupSwitchResult {
+        self.switch_all(
+            ProgressHintSender::finish,
+            None::<fn(&ProgressHintSender) -> SwitchProgressHintResult>,
+        )
+    }
+
+    /// Wait until the states of all worker threads satisfy a condition
+    ///
+    /// Returns the current states of all worker threads as an error
+    /// if the timeout expired.
+    pub fn wait_until_all(
+        &self,
+        timeout: Duration,
+        mut state_condition: impl FnMut(State) -> bool,
+    ) -> Result<(), Vec<State>> {
+        // Wait without a deadline if the result cannot be represented
+        // by an Instant
+        let deadline = Instant::now().checked_add(timeout);
+        for member in &self.members {
+            let timeout = deadline.map_or(timeout, |deadline| {
+                deadline.saturating_duration_since(Instant::now())
+            });
+            if member
+                .shared_state
+                .wait_until_state_condition_timeout(timeout, &mut state_condition)
+                .is_err()
+            {
+                return Err(self.load_states());
+            }
+        }
+        Ok(())
+    }
+
+    /// Ask all workers to suspend and wait until all of them have suspended
+    pub fn suspend_and_wait(&self, timeout: Duration) -> Result<(), GroupSuspendError> {
+        self.suspend().map_err(GroupSuspendError::Switch)?;
+        self.wait_until_all(timeout, |state| state == State::Suspending)
+            .map_err(GroupSuspendError::Timeout)
+    }
+}
+
+/// Failed to suspend a group of worker threads
+#[derive(Debug)]
+pub enum GroupSuspendError {
+    /// At least one worker rejected the request
+    ///
+    /// Contains the switch results of all workers.
+    Switch(Vec<SwitchProgressHintResult>),
+
+    /// Not all workers have suspended in time
+    ///
+    /// Contains the last states of all workers.
+    Timeout(Vec<State>),
+}
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    use super::{
+        super::{
+            super::{
+                progress::{ProgressHint, ProgressHintReceiver, SwitchProgressHintError},
+                CompletionStatus,
+            },
+            Context, JoinedThread, TerminatedThread, ThreadScheduling,
+        },
+        *,
+    };
+
+    struct RunningWorker;
+
+    impl Worker for RunningWorker {
+        type Environment = ();
+
+        fn start_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+            Ok(())
+        }
+
+        fn finish_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+            Ok(())
+        }
+
+        fn perform_work(
+            &mut self,
+            _env: &Self::Environment,
+            progress_hint_rx: &ProgressHintReceiver,
+        ) -> Result<CompletionStatus> {
+            loop {
+                match progress_hint_rx.peek() {
+                    ProgressHint::Continue => std::thread::sleep(Duration::from_millis(1)),
+                    ProgressHint::Suspend => return Ok(CompletionStatus::Suspending),
+                    ProgressHint::Finish => return Ok(CompletionStatus::Finishing),
+                    ProgressHint::Abort => return Ok(CompletionStatus::Aborting),
+                }
+            }
+        }
+    }
+
+    fn spawn() -> WorkerThread<RunningWorker> {
+        let context = Context {
+            progress_hint_rx: ProgressHintReceiver::default(),
+            worker: RunningWorker,
+            environment: (),
+        };
+        WorkerThread::spawn(context, ThreadScheduling::Default)
+    }
+
+    const TIMEOUT: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn suspend_resume_finish_all() {
+        let worker_threads = [spawn(), spawn(), spawn()];
+        let mut group = ProgressHintGroup::default();
+        for worker_thread in &worker_threads {
+            group.attach(worker_thread);
+        }
+        assert_eq!(3, group.len());
+
+        assert!(group.suspend_and_wait(Duration::MAX).is_ok());
+        assert_eq!(vec![State::Suspending; 3], group.load_states());
+        assert_eq!(3, group.resume().unwrap().len());
+        assert!(group.finish().is_ok());
+        assert!(group
+            .wait_until_all(TIMEOUT, |state| state == State::Terminating)
+            .is_ok());
+
+        for worker_thread in worker_threads {
+            assert!(matches!(
+                worker_thread.join(),
+                JoinedThread::Terminated(TerminatedThread { result: Ok(()), .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn revert_if_rejected() {
+        let worker_threads = [spawn(), spawn()];
+        let mut group = ProgressHintGroup::default();
+        for worker_thread in &worker_threads {
+            group.attach(worker_thread);
+        }
+        worker_threads[1].progress_hint_tx().finish().unwrap();
+
+        let results = group.suspend().unwrap_err();
+        assert!(matches!(
+            results[0],
+            Ok(SwitchProgressHintOk::Accepted {
+                previous_state: ProgressHint::Continue,
+            })
+        ));
+        assert!(matches!(
+            results[1],
+            Err(SwitchProgressHintError::Rejected {
+                current_state: ProgressHint::Finish,
+            })
+        ));
+        // The first worker keeps running
+        assert!(matches!(
+            worker_threads[0].progress_hint_tx().resume(),
+            Ok(SwitchProgressHintOk::Ignored)
+        ));
+
+        assert!(group.finish().is_ok());
+        for worker_thread in worker_threads {
+            assert!(matches!(
+                worker_thread.join(),
+                JoinedThread::Terminated(TerminatedThread { result: Ok(()), .. })
+            ));
+        }
+    }
+}
@@ -1,16 +1,35 @@
 use std::{
     any::Any,
+    fmt,
+    panic::{self, AssertUnwindSafe},
     sync::{
         atomic::{AtomicU8, Ordering},
         Arc, Condvar, Mutex,
     },
-    thread::{self, JoinHandle},
+    thread::JoinHandle,
+    time::{Duration, Instant},
 };
 
 use anyhow::Result;
-use thread_priority::{ThreadId as NativeThreadId, ThreadPriority};
+use thiserror::Error;
 
-use super::{progress::ProgressHintReceiver, CompletionStatus, Worker};
+mod affinity;
+pub use self::affinity::{CpuAffinity, CpuAffinityError};
+
+mod group;
+pub use self::group::{GroupSuspendError, GroupSwitchResult, ProgressHintGroup};
+
+mod scheduling;
+use self::scheduling::ThreadSchedulingScope;
+
+mod supervisor;
+pub use self::supervisor::{RestartPolicy, Supervisor};
+
+use super::{
+    deadline::{DeadlineMonitor, DeadlineStats, SharedDeadlineStats},
+    progress::{ProgressHintReceiver, ProgressHintSender, SwitchProgressHintError},
+    CompletionStatus, Worker,
+};
 
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq, num_derive::FromPrimitive)]
 #[repr(u8)]
@@ -50,9 +69,27 @@ pub struct Context<W: Worker> {
     pub environment: <W as Worker>::Environment,
 }
 
+/// Failed to suspend a worker thread
+#[derive(Debug, Error)]
+pub enum SuspendError {
+    /// The progress hint could not be switched
+    #[error(transparent)]
+    Switch(#[from] SwitchProgressHintError),
+
+    /// The worker has not suspended in time
+    #[error("timed out while {state:?}")]
+    Timeout { state: State },
+
+    /// The worker has stopped instead of suspending
+    #[error("stopped while {state:?}")]
+    Stopped { state: State },
+}
+
 #[derive(Debug)]
 pub struct WorkerThread<W: Worker> {
     shared_state: Arc<SharedState>,
+    progress_hint_tx: ProgressHintSender,
+    deadline_stats: Option<Arc<SharedDeadlineStats>>,
     join_handle: JoinHandle<TerminatedThread<W>>,
 }
 
@@ -65,6 +102,20 @@ where
         self.shared_state.load_state()
     }
 
+    /// Sender attached to the progress hint receiver of the worker
+    #[must_use]
+    pub fn progress_hint_tx(&self) -> &ProgressHintSender {
+        &self.progress_hint_tx
+    }
+
+    /// Timing statistics if spawned with a [`DeadlineMonitor`]
+    #[must_use]
+    pub fn deadline_stats(&self) -> Option<DeadlineStats> {
+        self.deadline_stats
+            .as_deref()
+            .map(SharedDeadlineStats::load)
+    }
+
     #[allow(clippy::must_use_candidate)]
     pub fn wait_until_started(&self) -> State {
         self.shared_state
@@ -82,173 +133,99 @@ where
                 State::Suspending | State::Finishing | State::Terminating => true,
             })
     }
-}
-
-struct ThreadSchedulingScope {
-    native_id: NativeThreadId,
-    saved_priority: ThreadPriority,
 
-    #[cfg(target_family = "unix")]
-    saved_policy: thread_priority::ThreadSchedulePolicy,
-}
-
-// TODO: Prevent passing of instances to different threads
-//#![feature(negative_impls)]
-//impl !Send for ThreadSchedulingScope {}
-
-impl ThreadSchedulingScope {
-    #[cfg(not(target_family = "windows"))]
-    fn enter() -> anyhow::Result<Self> {
-        log::debug!("Entering real-time scope");
-        let native_id = thread_priority::thread_native_id();
-        let thread_id = thread::current().id();
-        let saved_policy = thread_priority::thread_schedule_policy().map_err(|err| {
-            anyhow::anyhow!(
-                "Failed to save the thread scheduling policy of the current process: {:?}",
-                err,
-            )
-        })?;
-        let saved_priority = thread_priority::get_thread_priority(native_id).map_err(|err| {
-            anyhow::anyhow!(
-                "Failed to save the priority of thread {:?} ({:?}): {:?}",
-                thread_id,
-                native_id,
-                err,
-            )
-        })?;
-        let adjusted_priority = ThreadPriority::Max;
-        if adjusted_priority != saved_priority {
-            log::debug!(
-                "Adjusting priority of thread {:?} ({:?}): {:?} -> {:?}",
-                thread_id,
-                native_id,
-                saved_priority,
-                adjusted_priority
-            );
-        }
-        let adjusted_policy = thread_priority::ThreadSchedulePolicy::Realtime(
-            // Non-preemptive scheduling (in contrast to RoundRobin)
-            thread_priority::RealtimeThreadSchedulePolicy::Fifo,
-        );
-        if adjusted_policy != saved_policy {
-            log::debug!(
-                "Adjusting scheduling policy of thread {:?} ({:?}): {:?} -> {:?}",
-                thread_id,
-                native_id,
-                saved_policy,
-                adjusted_policy
-            );
-        }
-        if let Err(err) = thread_priority::set_thread_priority_and_policy(
-            native_id,
-            adjusted_priority,
-            adjusted_policy,
-        ) {
-            log::warn!(
-                "Failed to adjust priority and scheduling policy of thread {:?} ({:?}): {:?}",
-                thread_id,
-                native_id,
-                err
-            );
-            // Fallback: Only try to adjust the priority
-            thread_priority::set_current_thread_priority(adjusted_priority).map_err(|err| {
-                anyhow::anyhow!(
-                    "Failed to adjust priority of thread {:?} ({:?}): {:?}",
-                    thread_id,
-                    native_id,
-                    err
-                )
-            })?;
-        }
-        Ok(Self {
-            native_id,
-            saved_priority,
-            saved_policy,
-        })
+    /// Wait until the state differs from the given state
+    ///
+    /// Intermediate states might be skipped if the worker thread
+    /// switches its state faster than the waiting thread wakes up.
+    #[allow(clippy::must_use_candidate)]
+    pub fn wait_until_state_changed(&self, state: State) -> State {
+        self.shared_state
+            .wait_until_state_condition(|next_state| next_state != state)
     }
 
-    #[cfg(target_family = "windows")]
-    fn maximize_current_thread_priority() -> anyhow::Result<(NativeThreadId, ThreadPriority)> {
-        let native_id = thread_priority::thread_native_id();
-        let thread_id = thread::current().id();
-        let saved_priority = thread_priority::get_thread_priority(native_id).map_err(|err| {
-            anyhow::anyhow!(
-                "Failed to save the priority of thread {:?} ({:?}): {:?}",
-                thread_id,
-                native_id,
-                err,
-            )
-        })?;
-        let adjusted_priority = ThreadPriority::Max;
-        if adjusted_priority != saved_priority {
-            log::debug!(
-                "Adjusting priority of thread {:?} ({:?}): {:?} -> {:?}",
-                thread_id,
-                native_id,
-                saved_priority,
-                adjusted_priority
-            );
+    /// Ask the worker to suspend and wait until it has suspended
+    ///
+    /// Returns after the worker thread has acknowledged the request
+    /// by switching into [`State::Suspending`] or when the timeout
+    /// has expired.
+    pub fn suspend_and_wait(&self, timeout: Duration) -> Result<(), SuspendError> {
+        self.progress_hint_tx.suspend()?;
+        let state = self
+            .shared_state
+            .wait_until_state_condition_timeout(timeout, |state| match state {
+                State::Initial | State::Starting | State::Running => false,
+                State::Suspending | State::Finishing | State::Terminating => true,
+            })
+            .map_err(|state| SuspendError::Timeout { state })?;
+        match state {
+            State::Suspending => Ok(()),
+            state => Err(SuspendError::Stopped { state }),
         }
-        thread_priority::set_current_thread_priority(adjusted_priority).map_err(|err| {
-            anyhow::anyhow!(
-                "Failed to adjust priority of thread {:?} ({:?}): {:?}",
-                thread_id,
-                native_id,
-                err
-            )
-        })?;
-        Ok((native_id, saved_priority))
     }
+}
 
-    #[cfg(target_family = "windows")]
-    fn enter() -> anyhow::Result<Self> {
-        log::debug!("Entering real-time scope");
-        let (native_id, saved_priority) = Self::maximize_current_thread_priority()?;
-        Ok(Self {
-            native_id,
-            saved_priority,
-        })
-    }
+/// A transition between two states of a worker thread
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StateChange {
+    pub previous_state: State,
+    pub state: State,
+
+    /// The time of the transition
+    pub changed_at: Instant,
+
+    /// The time spent in the previous state, e.g. how long
+    /// a worker has been suspended
+    pub previous_state_duration: Duration,
 }
 
-impl Drop for ThreadSchedulingScope {
-    #[cfg(not(target_family = "windows"))]
-    fn drop(&mut self) {
-        log::debug!("Leaving real-time scope");
-        assert_eq!(self.native_id, thread_priority::thread_native_id());
-        if let Err(err) = thread_priority::set_thread_priority_and_policy(
-            self.native_id,
-            self.saved_priority,
-            self.saved_policy,
-        ) {
-            log::error!(
-                "Failed to restore priority and scheduling policy of thread {:?} ({:?}): {:?}",
-                thread::current().id(),
-                self.native_id,
-                err
-            );
+/// Callback for observing state changes
+///
+/// Invoked on the worker thread and thus should return quickly.
+pub type StateChangeCallback = Box<dyn FnMut(&StateChange) + Send>;
+
+/// Stores state changes and reports them to the callback
+struct StateRecorder<'a> {
+    shared_state: &'a SharedState,
+    state: State,
+    since: Instant,
+    on_state_change: Option<StateChangeCallback>,
+}
+
+impl<'a> StateRecorder<'a> {
+    fn new(shared_state: &'a SharedState, on_state_change: Option<StateChangeCallback>) -> Self {
+        Self {
+            shared_state,
+            state: shared_state.load_state(),
+            since: Instant::now(),
+            on_state_change,
         }
     }
 
-    #[cfg(target_family = "windows")]
-    fn drop(&mut self) {
-        log::debug!("Leaving real-time scope");
-        assert_eq!(self.native_id, thread_priority::thread_native_id());
-        if let Err(err) = thread_priority::set_current_thread_priority(self.saved_priority) {
-            log::error!(
-                "Failed to restore priority of thread {:?} ({:?}): {:?}",
-                thread::current().id(),
-                self.native_id,
-                err
-            )
+    fn store_state(&mut self, state: State) {
+        self.shared_state.store_state(state);
+        if state == self.state {
+            return;
+        }
+        let changed_at = Instant::now();
+        let state_change = StateChange {
+            previous_state: self.state,
+            state,
+            changed_at,
+            previous_state_duration: changed_at.duration_since(self.since),
+        };
+        self.state = state;
+        self.since = changed_at;
+        if let Some(on_state_change) = &mut self.on_state_change {
+            on_state_change(&state_change);
         }
     }
 }
 
 fn thread_fn<W>(
     context: &mut Context<W>,
-    thread_scheduling: ThreadScheduling,
-    shared_state: &SharedState,
+    options: SpawnOptions,
+    state_recorder: &mut StateRecorder<'_>,
 ) -> Result<()>
 where
     W: Worker,
@@ -259,8 +236,21 @@ where
         environment,
     } = context;
 
+    let SpawnOptions {
+        thread_scheduling,
+        cpu_affinity,
+        mut deadline_monitor,
+        on_state_change: _,
+    } = options;
+
     log::debug!("Starting");
-    shared_state.store_state(State::Starting);
+    state_recorder.store_state(State::Starting);
+    if let Some(cpu_affinity) = cpu_affinity {
+        log::debug!("Pinning thread to CPUs {cpu_affinity}");
+        cpu_affinity
+            .apply_to_current_thread()
+            .map_err(|err| anyhow::anyhow!("Failed to pin thread to CPUs {cpu_affinity}: {err}"))?;
+    }
     worker.start_working(environment)?;
     log::debug!("Started");
 
@@ -271,10 +261,17 @@ where
     };
 
     log::debug!("Running");
-    shared_state.store_state(State::Running);
+    state_recorder.store_state(State::Running);
 
     loop {
-        match worker.perform_work(environment, progress_hint_rx)? {
+        let started_at = Instant::now();
+        let completion_status = worker.perform_work(environment, progress_hint_rx)?;
+        if let Some(deadline_monitor) = &mut deadline_monitor {
+            if let Some(miss) = deadline_monitor.record(started_at, Instant::now()) {
+                log::debug!("Deadline missed: {miss}");
+            }
+        }
+        match completion_status {
             CompletionStatus::Suspending => {
                 // The worker may have decided to suspend itself independent
                 // of the current progress hint.
@@ -284,10 +281,13 @@ where
                     continue;
                 }
                 log::debug!("Suspending");
-                shared_state.store_state(State::Suspending);
+                state_recorder.store_state(State::Suspending);
                 progress_hint_rx.wait_while_suspending();
                 log::debug!("Resuming");
-                shared_state.store_state(State::Running);
+                if let Some(deadline_monitor) = &mut deadline_monitor {
+                    deadline_monitor.restart();
+                }
+                state_recorder.store_state(State::Running);
             }
             CompletionStatus::Finishing => {
                 // The worker may have decided to finish itself independent
@@ -302,24 +302,67 @@ where
                 // Exit running loop
                 break;
             }
+            CompletionStatus::Aborting => {
+                // The worker may have decided to abort itself independent
+                // of the current progress hint.
+                progress_hint_rx.set_aborting();
+                log::debug!("Aborting");
+                drop(scheduling_scope);
+                log::debug!("Terminating");
+                state_recorder.store_state(State::Terminating);
+                return Ok(());
+            }
         }
     }
 
     log::debug!("Finishing");
-    shared_state.store_state(State::Finishing);
+    state_recorder.store_state(State::Finishing);
     worker.finish_working(environment)?;
     log::debug!("Finished");
 
     log::debug!("Terminating");
-    shared_state.store_state(State::Terminating);
+    state_recorder.store_state(State::Terminating);
 
     Ok(())
 }
 
+/// The worker thread panicked
+///
+/// The thread terminates with this error if the worker panics. The
+/// context is recovered as if the worker failed with an error.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorkerPanic {
+    /// The panic message (if available)
+    pub message: Option<String>,
+}
+
+impl WorkerPanic {
+    fn from_payload(payload: &(dyn Any + Send)) -> Self {
+        let message = payload
+            .downcast_ref::<&str>()
+            .map(ToString::to_string)
+            .or_else(|| payload.downcast_ref::<String>().cloned());
+        Self { message }
+    }
+}
+
+impl fmt::Display for WorkerPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.message {
+            Some(message) => write!(f, "panicked: {message}"),
+            None => f.write_str("panicked"),
+        }
+    }
+}
+
+impl std::error::Error for WorkerPanic {}
+
 /// Outcome of [`WorkerThread::join()`]
 #[allow(missing_debug_implementations)]
 pub struct TerminatedThread<W: Worker> {
     /// The result of the thread function
+    ///
+    /// Contains a [`WorkerPanic`] error if the worker panicked.
     pub result: Result<()>,
 
     /// The recovered parameters
@@ -330,15 +373,21 @@ pub struct TerminatedThread<W: Worker> {
 #[allow(missing_debug_implementations)]
 pub enum JoinedThread<W: Worker> {
     Terminated(TerminatedThread<W>),
+
+    /// The thread could not be joined and the context is lost
+    ///
+    /// Only happens if panics could not be caught, e.g. when a
+    /// panic occurs while unwinding.
     JoinError(Box<dyn Any + Send + 'static>),
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, Default)]
 pub enum ThreadScheduling {
     /// Default
     ///
     /// Do not modify the current thread's priority and leave the
     /// process's scheduling policy untouched.
+    #[default]
     Default,
 
     /// Real-time
@@ -357,6 +406,42 @@ pub enum ThreadScheduling {
     RealtimeOrDefault,
 }
 
+/// Parameters for spawning a [`WorkerThread`]
+#[derive(Default)]
+pub struct SpawnOptions {
+    pub thread_scheduling: ThreadScheduling,
+
+    /// Pin the thread to CPU cores
+    ///
+    /// The thread terminates with an error if pinning fails.
+    pub cpu_affinity: Option<CpuAffinity>,
+
+    /// Monitor the deadlines of all [`Worker::perform_work()`] invocations
+    pub deadline_monitor: Option<DeadlineMonitor>,
+
+    /// Observe all state changes of the worker thread
+    pub on_state_change: Option<StateChangeCallback>,
+}
+
+impl fmt::Debug for SpawnOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SpawnOptions")
+            .field("thread_scheduling", &self.thread_scheduling)
+            .field("cpu_affinity", &self.cpu_affinity)
+            .field("deadline_monitor", &self.deadline_monitor)
+            .finish_non_exhaustive()
+    }
+}
+
+impl From<ThreadScheduling> for SpawnOptions {
+    fn from(thread_scheduling: ThreadScheduling) -> Self {
+        Self {
+            thread_scheduling,
+            ..Default::default()
+        }
+    }
+}
+
 #[derive(Debug)]
 struct SharedState {
     state: AtomicU8,
@@ -377,6 +462,29 @@ impl SharedState {
         self.notify_state_changed_condvar.notify_all();
     }
 
+    /// Returns the last state as an error if the timeout expired
+    fn wait_until_state_condition_timeout(
+        &self,
+        timeout: Duration,
+        mut state_condition: impl FnMut(State) -> bool,
+    ) -> Result<State, State> {
+        let guard = self
+            .notify_state_changed_mutex
+            .lock()
+            .expect("not poisoned");
+        let (guard, _) = self
+            .notify_state_changed_condvar
+            .wait_timeout_while(guard, timeout, |()| !state_condition(self.load_state()))
+            .expect("not poisoned");
+        let state = self.load_state();
+        drop(guard);
+        if state_condition(state) {
+            Ok(state)
+        } else {
+            Err(state)
+        }
+    }
+
     fn wait_until_state_condition(&self, mut state_condition: impl FnMut(State) -> bool) -> State {
         // Try non-blocking first
         let state = self.load_state();
@@ -417,14 +525,53 @@ where
     <W as Worker>::Environment: Send + 'static,
 {
     pub fn spawn(context: Context<W>, thread_scheduling: ThreadScheduling) -> Self {
+        Self::spawn_with_options(context, thread_scheduling.into())
+    }
+
+    /// Spawn a worker thread and monitor the deadlines of all
+    /// [`Worker::perform_work()`] invocations
+    pub fn spawn_with_deadline_monitor(
+        context: Context<W>,
+        thread_scheduling: ThreadScheduling,
+        deadline_monitor: DeadlineMonitor,
+    ) -> Self {
+        Self::spawn_with_options(
+            context,
+            SpawnOptions {
+                deadline_monitor: Some(deadline_monitor),
+                ..thread_scheduling.into()
+            },
+        )
+    }
+
+    pub fn spawn_with_options(context: Context<W>, options: SpawnOptions) -> Self {
         let shared_state = Arc::new(SharedState::default());
+        let progress_hint_tx = ProgressHintSender::attach(&context.progress_hint_rx);
+        let deadline_stats = options
+            .deadline_monitor
+            .as_ref()
+            .map(DeadlineMonitor::shared_stats);
         let join_handle = {
             let shared_state = Arc::clone(&shared_state);
             std::thread::spawn({
                 move || {
                     // The function parameters need to be mutable within the real-time thread
                     let mut context = context;
-                    let result = thread_fn(&mut context, thread_scheduling, &shared_state);
+                    // Recover the context if the worker panics
+                    let mut options = options;
+                    let mut state_recorder =
+                        StateRecorder::new(&shared_state, options.on_state_change.take());
+                    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                        thread_fn(&mut context, options, &mut state_recorder)
+                    }))
+                    .unwrap_or_else(|payload| {
+                        let panic = WorkerPanic::from_payload(payload.as_ref());
+                        log::error!("Worker {panic}");
+                        Err(panic.into())
+                    });
+                    if result.is_err() {
+                        state_recorder.store_state(State::Terminating);
+                    }
                     let context = context;
                     TerminatedThread { result, context }
                 }
@@ -432,6 +579,8 @@ where
         };
         Self {
             shared_state,
+            progress_hint_tx,
+            deadline_stats,
             join_handle,
         }
     }
@@ -440,6 +589,8 @@ where
         let Self {
             join_handle,
             shared_state,
+            progress_hint_tx: _,
+            deadline_stats: _,
         } = self;
         log::debug!("Joining thread");
         let joined_thread = join_handle
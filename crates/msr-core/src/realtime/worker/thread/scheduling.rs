@@ -0,0 +1,272 @@
+//! Real-time scheduling of the current thread
+//!
+//! The [`ThreadSchedulingScope`] is implemented separately for each
+//! platform:
+//!
+//! - Linux and other Unix systems: Maximum priority with the real-time
+//!   FIFO scheduling policy.
+//! - macOS: The quality of service class `USER_INTERACTIVE`. Threads that
+//!   change their scheduling policy would implicitly opt out of it.
+//! - Windows: Time critical priority and registration with the
+//!   Multimedia Class Scheduler Service (MMCSS).
+
+pub(super) use self::imp::ThreadSchedulingScope;
+
+// TODO: Prevent passing of instances to different threads
+//#![feature(negative_impls)]
+//impl !Send for ThreadSchedulingScope {}
+
+#[cfg(all(target_family = "unix", not(target_os = "macos")))]
+mod imp {
+    use std::thread;
+
+    use thread_priority::{ThreadId as NativeThreadId, ThreadPriority};
+
+    pub(in super::super) struct ThreadSchedulingScope {
+        native_id: NativeThreadId,
+        saved_priority: ThreadPriority,
+        saved_policy: thread_priority::ThreadSchedulePolicy,
+    }
+
+    impl ThreadSchedulingScope {
+        pub(in super::super) fn enter() -> anyhow::Result<Self> {
+            log::debug!("Entering real-time scope");
+            let native_id = thread_priority::thread_native_id();
+            let thread_id = thread::current().id();
+            let saved_policy = thread_priority::thread_schedule_policy().map_err(|err| {
+                anyhow::anyhow!(
+                    "Failed to save the thread scheduling policy of the current process: {err:?}"
+                )
+            })?;
+            let saved_priority =
+                thread_priority::get_thread_priority(native_id).map_err(|err| {
+                    anyhow::anyhow!(
+                        "Failed to save the priority of thread {thread_id:?} ({native_id:?}): {err:?}"
+                    )
+                })?;
+            let adjusted_priority = ThreadPriority::Max;
+            if adjusted_priority != saved_priority {
+                log::debug!(
+                    "Adjusting priority of thread {thread_id:?} ({native_id:?}): {saved_priority:?} -> {adjusted_priority:?}"
+                );
+            }
+            let adjusted_policy = thread_priority::ThreadSchedulePolicy::Realtime(
+                // Non-preemptive scheduling (in contrast to RoundRobin)
+                thread_priority::RealtimeThreadSchedulePolicy::Fifo,
+            );
+            if adjusted_policy != saved_policy {
+                log::debug!(
+                    "Adjusting scheduling policy of thread {thread_id:?} ({native_id:?}): {saved_policy:?} -> {adjusted_policy:?}"
+                );
+            }
+            if let Err(err) = thread_priority::set_thread_priority_and_policy(
+                native_id,
+                adjusted_priority,
+                adjusted_policy,
+            ) {
+                log::warn!(
+                    "Failed to adjust priority and scheduling policy of thread {thread_id:?} ({native_id:?}): {err:?}"
+                );
+                // Fallback: Only try to adjust the priority
+                thread_priority::set_current_thread_priority(adjusted_priority).map_err(|err| {
+                    anyhow::anyhow!(
+                        "Failed to adjust priority of thread {thread_id:?} ({native_id:?}): {err:?}"
+                    )
+                })?;
+            }
+            Ok(Self {
+                native_id,
+                saved_priority,
+                saved_policy,
+            })
+        }
+    }
+
+    impl Drop for ThreadSchedulingScope {
+        fn drop(&mut self) {
+            log::debug!("Leaving real-time scope");
+            assert_eq!(self.native_id, thread_priority::thread_native_id());
+            if let Err(err) = thread_priority::set_thread_priority_and_policy(
+                self.native_id,
+                self.saved_priority,
+                self.saved_policy,
+            ) {
+                log::error!(
+                    "Failed to restore priority and scheduling policy of thread {:?} ({:?}): {:?}",
+                    thread::current().id(),
+                    self.native_id,
+                    err
+                );
+            }
+        }
+    }
+}
+
+#[cfg(target_os = "macos")]
+#[allow(unsafe_code)]
+mod imp {
+    use std::{io, thread};
+
+    pub(in super::super) struct ThreadSchedulingScope {
+        saved_qos_class: libc::qos_class_t,
+        saved_relative_priority: libc::c_int,
+    }
+
+    impl ThreadSchedulingScope {
+        pub(in super::super) fn enter() -> anyhow::Result<Self> {
+            log::debug!("Entering real-time scope");
+            let thread_id = thread::current().id();
+            let mut saved_qos_class = libc::qos_class_t::QOS_CLASS_UNSPECIFIED;
+            let mut saved_relative_priority = 0;
+            // SAFETY: Both pointers are valid and refer to the current thread
+            let res = unsafe {
+                libc::pthread_get_qos_class_np(
+                    libc::pthread_self(),
+                    &mut saved_qos_class,
+                    &mut saved_relative_priority,
+                )
+            };
+            if res != 0 {
+                anyhow::bail!(
+                    "Failed to save the QoS class of thread {:?}: {}",
+                    thread_id,
+                    io::Error::from_raw_os_error(res),
+                );
+            }
+            let adjusted_qos_class = libc::qos_class_t::QOS_CLASS_USER_INTERACTIVE;
+            log::debug!(
+                "Adjusting QoS class of thread {thread_id:?}: {saved_qos_class:?} -> {adjusted_qos_class:?}"
+            );
+            // SAFETY: Only affects the current thread
+            let res = unsafe { libc::pthread_set_qos_class_self_np(adjusted_qos_class, 0) };
+            if res != 0 {
+                anyhow::bail!(
+                    "Failed to adjust QoS class of thread {:?}: {}",
+                    thread_id,
+                    io::Error::from_raw_os_error(res),
+                );
+            }
+            Ok(Self {
+                saved_qos_class,
+                saved_relative_priority,
+            })
+        }
+    }
+
+    impl Drop for ThreadSchedulingScope {
+        fn drop(&mut self) {
+            log::debug!("Leaving real-time scope");
+            // SAFETY: Only affects the current thread
+            let res = unsafe {
+                libc::pthread_set_qos_class_self_np(
+                    self.saved_qos_class,
+                    self.saved_relative_priority,
+                )
+            };
+            if res != 0 {
+                log::error!(
+                    "Failed to restore QoS class of thread {:?}: {}",
+                    thread::current().id(),
+                    io::Error::from_raw_os_error(res),
+                );
+            }
+        }
+    }
+}
+
+#[cfg(target_family = "windows")]
+#[allow(unsafe_code)]
+mod imp {
+    use std::{io, thread};
+
+    use thread_priority::{ThreadId as NativeThreadId, ThreadPriority, WinAPIThreadPriority};
+    use winapi::{shared::minwindef::DWORD, um::avrt, um::winnt::HANDLE};
+
+    /// The MMCSS task with the highest scheduling priority
+    const MMCSS_TASK_NAME: &str = "Pro Audio";
+
+    pub(in super::super) struct ThreadSchedulingScope {
+        native_id: NativeThreadId,
+        saved_priority: ThreadPriority,
+        mmcss_handle: Option<HANDLE>,
+    }
+
+    fn register_mmcss_task() -> io::Result<HANDLE> {
+        let task_name: Vec<u16> = MMCSS_TASK_NAME
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let mut task_index: DWORD = 0;
+        // SAFETY: The task name is null-terminated and the index is valid
+        let handle =
+            unsafe { avrt::AvSetMmThreadCharacteristicsW(task_name.as_ptr(), &mut task_index) };
+        if handle.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(handle)
+    }
+
+    impl ThreadSchedulingScope {
+        pub(in super::super) fn enter() -> anyhow::Result<Self> {
+            log::debug!("Entering real-time scope");
+            let native_id = thread_priority::thread_native_id();
+            let thread_id = thread::current().id();
+            let saved_priority =
+                thread_priority::get_thread_priority(native_id).map_err(|err| {
+                    anyhow::anyhow!(
+                        "Failed to save the priority of thread {thread_id:?} ({native_id:?}): {err:?}"
+                    )
+                })?;
+            let adjusted_priority = WinAPIThreadPriority::TimeCritical;
+            log::debug!(
+                "Adjusting priority of thread {thread_id:?} ({native_id:?}): {saved_priority:?} -> {adjusted_priority:?}"
+            );
+            thread_priority::set_winapi_thread_priority(native_id, adjusted_priority).map_err(
+                |err| {
+                    anyhow::anyhow!(
+                        "Failed to adjust priority of thread {thread_id:?} ({native_id:?}): {err:?}"
+                    )
+                },
+            )?;
+            // Optional: The service might not be available
+            let mmcss_handle = register_mmcss_task()
+                .map_err(|err| {
+                    log::warn!(
+                        "Failed to register thread {thread_id:?} ({native_id:?}) with MMCSS: {err}"
+                    );
+                })
+                .ok();
+            Ok(Self {
+                native_id,
+                saved_priority,
+                mmcss_handle,
+            })
+        }
+    }
+
+    impl Drop for ThreadSchedulingScope {
+        fn drop(&mut self) {
+            log::debug!("Leaving real-time scope");
+            assert_eq!(self.native_id, thread_priority::thread_native_id());
+            if let Some(mmcss_handle) = self.mmcss_handle.take() {
+                // SAFETY: The handle has been obtained on this thread
+                if unsafe { avrt::AvRevertMmThreadCharacteristics(mmcss_handle) } == 0 {
+                    log::error!(
+                        "Failed to unregister thread {:?} ({:?}) from MMCSS: {}",
+                        thread::current().id(),
+                        self.native_id,
+                        io::Error::last_os_error()
+                    );
+                }
+            }
+            if let Err(err) = thread_priority::set_current_thread_priority(self.saved_priority) {
+                log::error!(
+                    "Failed to restore priority of thread {:?} ({:?}): {:?}",
+                    thread::current().id(),
+                    self.native_id,
+                    err
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,283 @@
+//! Restarting of failed worker threads
+
+use std::{
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    thread::JoinHandle,
+    time::Duration,
+};
+
+use super::{
+    super::{progress::ProgressHint, Worker},
+    Context, JoinedThread, TerminatedThread, ThreadScheduling, WorkerThread,
+};
+
+/// When and how often to restart a failed worker
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestartPolicy {
+    /// Maximum number of restarts or `None` for unlimited restarts
+    pub max_restarts: Option<u32>,
+
+    /// Delay before the first restart
+    pub initial_backoff: Duration,
+
+    /// Upper bound for the delay, which doubles with each restart
+    pub max_backoff: Duration,
+}
+
+impl Default for RestartPolicy {
+    fn default() -> Self {
+        Self {
+            max_restarts: None,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+impl RestartPolicy {
+    /// The delay before the given restart, starting at 0
+    #[must_use]
+    pub fn backoff(&self, restart: u32) -> Duration {
+        self.initial_backoff
+            .checked_mul(2u32.saturating_pow(restart))
+            .map_or(self.max_backoff, |backoff| backoff.min(self.max_backoff))
+    }
+
+    #[must_use]
+    pub fn permits_restart(&self, restarts: u32) -> bool {
+        self.max_restarts
+            .map_or(true, |max_restarts| restarts < max_restarts)
+    }
+}
+
+/// Supervises a worker thread and restarts it after failures
+///
+/// A worker that fails with an error or panics is restarted with
+/// its recovered context, i.e. the same worker and environment.
+/// Senders of progress hints stay attached to the restarted worker.
+///
+/// The supervisor terminates when the worker has finished successfully,
+/// when the restart policy is exhausted, or when the worker failed
+/// after it has been asked to finish or abort.
+#[derive(Debug)]
+pub struct Supervisor<W: Worker> {
+    restarts: Arc<AtomicU32>,
+    join_handle: JoinHandle<JoinedThread<W>>,
+}
+
+impl<W> Supervisor<W>
+where
+    W: Worker + Send + 'static,
+    <W as Worker>::Environment: Send + 'static,
+{
+    pub fn spawn(
+        context: Context<W>,
+        thread_scheduling: ThreadScheduling,
+        restart_policy: RestartPolicy,
+    ) -> Self {
+        let restarts = Arc::new(AtomicU32::new(0));
+        let join_handle = {
+            let restarts = Arc::clone(&restarts);
+            std::thread::spawn(move || {
+                supervise(context, thread_scheduling, restart_policy, &restarts)
+            })
+        };
+        Self {
+            restarts,
+            join_handle,
+        }
+    }
+
+    /// The number of restarts so far
+    #[must_use]
+    pub fn restarts(&self) -> u32 {
+        self.restarts.load(Ordering::Acquire)
+    }
+
+    /// Wait until the supervisor has terminated
+    ///
+    /// Returns the outcome of the last worker thread.
+    pub fn join(self) -> JoinedThread<W> {
+        self.join_handle
+            .join()
+            .unwrap_or_else(JoinedThread::JoinError)
+    }
+}
+
+fn supervise<W>(
+    mut context: Context<W>,
+    thread_scheduling: ThreadScheduling,
+    restart_policy: RestartPolicy,
+    restarts: &AtomicU32,
+) -> JoinedThread<W>
+where
+    W: Worker + Send + 'static,
+    <W as Worker>::Environment: Send + 'static,
+{
+    loop {
+        let worker_thread = WorkerThread::spawn(context, thread_scheduling);
+        let (err, recovered_context) = match worker_thread.join() {
+            JoinedThread::Terminated(TerminatedThread {
+                result: Err(err),
+                context,
+            }) => (err, context),
+            joined_thread => return joined_thread,
+        };
+        let restart = restarts.load(Ordering::Relaxed);
+        if !restart_policy.permits_restart(restart) {
+            log::error!("Worker failed after {restart} restart(s): {err}");
+            return JoinedThread::Terminated(TerminatedThread {
+                result: Err(err),
+                context: recovered_context,
+            });
+        }
+        let backoff = restart_policy.backoff(restart);
+        log::warn!(
+            "Worker failed: {err} - restarting in {:.3} s",
+            backoff.as_secs_f64()
+        );
+        // Give up if asked to finish or abort while waiting
+        let progress_hint_rx = &recovered_context.progress_hint_rx;
+        let is_stop_requested = || {
+            matches!(
+                progress_hint_rx.load(),
+                ProgressHint::Finish | ProgressHint::Abort
+            )
+        };
+        if is_stop_requested() || (progress_hint_rx.wait_for(backoff) && is_stop_requested()) {
+            log::debug!("Not restarting the worker after it has been asked to stop");
+            return JoinedThread::Terminated(TerminatedThread {
+                result: Err(err),
+                context: recovered_context,
+            });
+        }
+        restarts.store(restart + 1, Ordering::Release);
+        context = recovered_context;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use anyhow::Result;
+
+    use super::{
+        super::{
+            super::{progress::ProgressHintReceiver, CompletionStatus},
+            WorkerPanic,
+        },
+        *,
+    };
+
+    /// Fails a given number of times before finishing
+    struct FailingWorker {
+        failures: usize,
+        invocations: Arc<AtomicUsize>,
+    }
+
+    impl Worker for FailingWorker {
+        type Environment = ();
+
+        fn start_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+            Ok(())
+        }
+
+        fn finish_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+            Ok(())
+        }
+
+        fn perform_work(
+            &mut self,
+            _env: &Self::Environment,
+            _progress_hint_rx: &ProgressHintReceiver,
+        ) -> Result<CompletionStatus> {
+            let invocation = self.invocations.fetch_add(1, Ordering::Relaxed);
+            if invocation < self.failures {
+                // Alternate between panics and errors
+                assert!(invocation % 2 != 0, "invocation {invocation}");
+                anyhow::bail!("invocation {invocation}");
+            }
+            Ok(CompletionStatus::Finishing)
+        }
+    }
+
+    fn context(failures: usize) -> Context<FailingWorker> {
+        Context {
+            progress_hint_rx: ProgressHintReceiver::default(),
+            worker: FailingWorker {
+                failures,
+                invocations: Default::default(),
+            },
+            environment: (),
+        }
+    }
+
+    const RESTART_POLICY: RestartPolicy = RestartPolicy {
+        max_restarts: Some(3),
+        initial_backoff: Duration::from_millis(1),
+        max_backoff: Duration::from_millis(2),
+    };
+
+    #[test]
+    fn backoff() {
+        let policy = RestartPolicy {
+            max_restarts: None,
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(1),
+        };
+        assert_eq!(Duration::from_millis(100), policy.backoff(0));
+        assert_eq!(Duration::from_millis(400), policy.backoff(2));
+        assert_eq!(Duration::from_secs(1), policy.backoff(4));
+        assert_eq!(Duration::from_secs(1), policy.backoff(u32::MAX));
+        assert!(policy.permits_restart(u32::MAX));
+        assert!(!RESTART_POLICY.permits_restart(3));
+    }
+
+    #[test]
+    fn recover_context_after_panic() {
+        let worker_thread = WorkerThread::spawn(context(1), ThreadScheduling::Default);
+        match worker_thread.join() {
+            JoinedThread::Terminated(TerminatedThread { result, context }) => {
+                let err = result.unwrap_err();
+                assert_eq!(
+                    Some(&WorkerPanic {
+                        message: Some("invocation 0".to_string())
+                    }),
+                    err.downcast_ref::<WorkerPanic>()
+                );
+                assert_eq!(1, context.worker.invocations.load(Ordering::Relaxed));
+            }
+            JoinedThread::JoinError(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn restart_until_finished() {
+        let supervisor = Supervisor::spawn(context(3), ThreadScheduling::Default, RESTART_POLICY);
+        match supervisor.join() {
+            JoinedThread::Terminated(TerminatedThread { result, context }) => {
+                assert!(result.is_ok());
+                assert_eq!(4, context.worker.invocations.load(Ordering::Relaxed));
+            }
+            JoinedThread::JoinError(_) => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn give_up_after_max_restarts() {
+        let supervisor = Supervisor::spawn(context(10), ThreadScheduling::Default, RESTART_POLICY);
+        let restarts = Arc::clone(&supervisor.restarts);
+        match supervisor.join() {
+            JoinedThread::Terminated(TerminatedThread { result, context }) => {
+                assert_eq!("invocation 3", result.unwrap_err().to_string());
+                assert_eq!(4, context.worker.invocations.load(Ordering::Relaxed));
+            }
+            JoinedThread::JoinError(_) => unreachable!(),
+        }
+        assert_eq!(3, restarts.load(Ordering::Relaxed));
+    }
+}
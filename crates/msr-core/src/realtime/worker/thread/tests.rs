@@ -53,6 +53,7 @@ impl Worker for SmokeTestWorker {
             }
             ProgressHint::Suspend => CompletionStatus::Suspending,
             ProgressHint::Finish => CompletionStatus::Finishing,
+            ProgressHint::Abort => CompletionStatus::Aborting,
         };
         Ok(progress)
     }
@@ -172,3 +173,229 @@ fn suspend_before_starting_and_finish_while_suspended() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn abort_while_suspended_without_finishing() -> anyhow::Result<()> {
+    let worker = SmokeTestWorker::new(0);
+    let progress_hint_rx = ProgressHintReceiver::default();
+    let progress_hint_tx = ProgressHintSender::attach(&progress_hint_rx);
+    progress_hint_tx.suspend()?;
+    let context = Context {
+        progress_hint_rx,
+        worker,
+        environment: SmokeTestEnvironment,
+    };
+    let worker_thread = WorkerThread::spawn(context, ThreadScheduling::Default);
+    assert_eq!(State::Suspending, worker_thread.wait_until_not_running());
+    assert!(matches!(
+        progress_hint_tx.abort(),
+        Ok(SwitchProgressHintOk::Accepted {
+            previous_state: ProgressHint::Suspend,
+        })
+    ));
+    // Finishing must not override the abort request
+    assert!(progress_hint_tx.finish().is_err());
+    match worker_thread.join() {
+        JoinedThread::Terminated(TerminatedThread { context, result }) => {
+            result?;
+            assert_eq!(ProgressHint::Abort, context.progress_hint_rx.load());
+            let worker = context.worker;
+            assert_eq!(1, worker.start_working_invocations);
+            assert_eq!(0, worker.finish_working_invocations);
+            assert_eq!(2, worker.actual_perform_work_invocations);
+        }
+        JoinedThread::JoinError(err) => {
+            return Err(anyhow::anyhow!("Failed to join worker thread: {err:?}"))
+        }
+    }
+
+    Ok(())
+}
+
+/// Keeps running until asked to suspend or finish
+struct RunningWorker;
+
+impl Worker for RunningWorker {
+    type Environment = ();
+
+    fn start_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+        Ok(())
+    }
+
+    fn perform_work(
+        &mut self,
+        _env: &Self::Environment,
+        progress_hint_rx: &ProgressHintReceiver,
+    ) -> Result<CompletionStatus> {
+        loop {
+            match progress_hint_rx.peek() {
+                ProgressHint::Continue => std::thread::sleep(Duration::from_millis(1)),
+                ProgressHint::Suspend => return Ok(CompletionStatus::Suspending),
+                ProgressHint::Finish => return Ok(CompletionStatus::Finishing),
+                ProgressHint::Abort => return Ok(CompletionStatus::Aborting),
+            }
+        }
+    }
+}
+
+#[test]
+fn suspend_and_wait_until_suspended() -> anyhow::Result<()> {
+    let context = Context {
+        progress_hint_rx: ProgressHintReceiver::default(),
+        worker: RunningWorker,
+        environment: (),
+    };
+    let worker_thread = WorkerThread::spawn(context, ThreadScheduling::Default);
+    let timeout = Duration::from_secs(10);
+    for _ in 0..3 {
+        worker_thread.suspend_and_wait(timeout)?;
+        assert_eq!(State::Suspending, worker_thread.load_state());
+        // Suspending again is acknowledged immediately
+        worker_thread.suspend_and_wait(Duration::ZERO)?;
+        worker_thread.progress_hint_tx().resume()?;
+    }
+    worker_thread.progress_hint_tx().finish()?;
+    assert!(matches!(
+        worker_thread.suspend_and_wait(timeout),
+        Err(SuspendError::Switch(SwitchProgressHintError::Rejected {
+            current_state: ProgressHint::Finish,
+        }))
+    ));
+    assert!(matches!(
+        worker_thread.join(),
+        JoinedThread::Terminated(TerminatedThread { result: Ok(()), .. })
+    ));
+
+    Ok(())
+}
+
+struct SleepingWorker;
+
+impl Worker for SleepingWorker {
+    type Environment = ();
+
+    fn start_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+        Ok(())
+    }
+
+    fn finish_working(&mut self, _env: &mut Self::Environment) -> Result<()> {
+        Ok(())
+    }
+
+    fn perform_work(
+        &mut self,
+        _env: &Self::Environment,
+        _progress_hint_rx: &ProgressHintReceiver,
+    ) -> Result<CompletionStatus> {
+        std::thread::sleep(std::time::Duration::from_millis(2));
+        Ok(CompletionStatus::Finishing)
+    }
+}
+
+#[test]
+fn report_deadline_misses() {
+    use std::{sync::mpsc, time::Duration};
+
+    use crate::realtime::worker::deadline::DeadlineSpec;
+
+    let (miss_tx, miss_rx) = mpsc::channel();
+    let deadline_monitor = DeadlineMonitor::new(DeadlineSpec::new(Duration::from_millis(1)))
+        .with_callback(move |miss| {
+            miss_tx.send(*miss).unwrap();
+        });
+    let context = Context {
+        progress_hint_rx: ProgressHintReceiver::default(),
+        worker: SleepingWorker,
+        environment: (),
+    };
+    let worker_thread = WorkerThread::spawn_with_deadline_monitor(
+        context,
+        ThreadScheduling::Default,
+        deadline_monitor,
+    );
+    let miss = miss_rx.recv().unwrap();
+    assert_eq!(0, miss.invocation);
+    assert!(miss.lateness() > Duration::ZERO);
+    let stats = worker_thread.deadline_stats().unwrap();
+    assert_eq!(1, stats.invocations);
+    assert_eq!(1, stats.misses);
+    assert!(stats.max_execution_time >= Duration::from_millis(2));
+    assert!(matches!(
+        worker_thread.join(),
+        JoinedThread::Terminated(TerminatedThread { result: Ok(()), .. })
+    ));
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn spawn_pinned_thread() {
+    let available = CpuAffinity::of_current_thread().unwrap();
+    let context = Context {
+        progress_hint_rx: ProgressHintReceiver::default(),
+        worker: SleepingWorker,
+        environment: (),
+    };
+    let options = SpawnOptions {
+        cpu_affinity: Some(CpuAffinity::single(available.cpus()[0])),
+        ..Default::default()
+    };
+    let worker_thread = WorkerThread::spawn_with_options(context, options);
+    assert!(worker_thread.deadline_stats().is_none());
+    assert!(matches!(
+        worker_thread.join(),
+        JoinedThread::Terminated(TerminatedThread { result: Ok(()), .. })
+    ));
+}
+
+#[test]
+fn report_state_changes() -> anyhow::Result<()> {
+    let state_changes = Arc::new(Mutex::new(Vec::new()));
+    let context = Context {
+        progress_hint_rx: ProgressHintReceiver::default(),
+        worker: RunningWorker,
+        environment: (),
+    };
+    let options = SpawnOptions {
+        on_state_change: Some(Box::new({
+            let state_changes = Arc::clone(&state_changes);
+            move |state_change: &StateChange| state_changes.lock().unwrap().push(*state_change)
+        })),
+        ..Default::default()
+    };
+    let worker_thread = WorkerThread::spawn_with_options(context, options);
+    worker_thread.suspend_and_wait(Duration::from_secs(10))?;
+    let suspended_for = Duration::from_millis(5);
+    std::thread::sleep(suspended_for);
+    worker_thread.progress_hint_tx().resume()?;
+    worker_thread.progress_hint_tx().finish()?;
+    assert!(matches!(
+        worker_thread.join(),
+        JoinedThread::Terminated(TerminatedThread { result: Ok(()), .. })
+    ));
+
+    let state_changes = state_changes.lock().unwrap();
+    assert_eq!(
+        vec![
+            (State::Initial, State::Starting),
+            (State::Starting, State::Running),
+            (State::Running, State::Suspending),
+            (State::Suspending, State::Running),
+            (State::Running, State::Finishing),
+            (State::Finishing, State::Terminating),
+        ],
+        state_changes
+            .iter()
+            .map(|state_change| (state_change.previous_state, state_change.state))
+            .collect::<Vec<_>>()
+    );
+    assert!(state_changes[3].previous_state_duration >= suspended_for);
+    assert!(state_changes
+        .windows(2)
+        .all(|pair| pair[0].changed_at <= pair[1].changed_at));
+
+    Ok(())
+}
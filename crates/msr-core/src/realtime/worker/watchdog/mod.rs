@@ -0,0 +1,197 @@
+use std::{
+    fmt,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, Relay,
+};
+
+/// Counter value of a disarmed heartbeat
+const DISARMED: u64 = 0;
+
+/// Lock-free heartbeat of a worker
+///
+/// Workers feed the heartbeat once per cycle. Feeding never blocks
+/// and could safely be invoked in a real-time context.
+#[derive(Debug, Clone, Default)]
+pub struct Heartbeat {
+    counter: Arc<AtomicU64>,
+}
+
+impl Heartbeat {
+    /// Signal progress and arm the watchdog
+    pub fn feed(&self) {
+        // Skip the disarmed value on overflow
+        let previous = self.counter.fetch_add(1, Ordering::Release);
+        if previous == u64::MAX {
+            self.counter.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    /// Stop monitoring until the next heartbeat, e.g. while suspended
+    pub fn disarm(&self) {
+        self.counter.store(DISARMED, Ordering::Release);
+    }
+
+    fn load(&self) -> u64 {
+        self.counter.load(Ordering::Acquire)
+    }
+}
+
+/// Observed by the watchdog
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogEvent {
+    /// No heartbeat has been received within the timeout
+    Stalled {
+        /// The time of the last heartbeat
+        last_heartbeat: Instant,
+    },
+
+    /// Heartbeats have been received again after a stall
+    Recovered {
+        /// The time between the last heartbeat before the stall
+        /// and the first heartbeat after the stall
+        stalled_for: Duration,
+    },
+}
+
+impl fmt::Display for WatchdogEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Stalled { last_heartbeat } => write!(
+                f,
+                "stalled: no heartbeat for {:.3} s",
+                last_heartbeat.elapsed().as_secs_f64()
+            ),
+            Self::Recovered { stalled_for } => write!(
+                f,
+                "recovered: stalled for {:.3} s",
+                stalled_for.as_secs_f64()
+            ),
+        }
+    }
+}
+
+/// Detects missed heartbeats
+#[derive(Debug)]
+struct HeartbeatMonitor {
+    timeout: Duration,
+    last_counter: u64,
+    last_heartbeat: Instant,
+    stalled: bool,
+}
+
+impl HeartbeatMonitor {
+    fn new(timeout: Duration, now: Instant) -> Self {
+        Self {
+            timeout,
+            last_counter: DISARMED,
+            last_heartbeat: now,
+            stalled: false,
+        }
+    }
+
+    fn check(&mut self, counter: u64, now: Instant) -> Option<WatchdogEvent> {
+        if counter != self.last_counter {
+            self.last_counter = counter;
+            let last_heartbeat = self.last_heartbeat;
+            self.last_heartbeat = now;
+            if self.stalled {
+                self.stalled = false;
+                return Some(WatchdogEvent::Recovered {
+                    stalled_for: now.duration_since(last_heartbeat),
+                });
+            }
+            return None;
+        }
+        if counter == DISARMED || self.stalled {
+            return None;
+        }
+        if now.duration_since(self.last_heartbeat) < self.timeout {
+            return None;
+        }
+        self.stalled = true;
+        Some(WatchdogEvent::Stalled {
+            last_heartbeat: self.last_heartbeat,
+        })
+    }
+}
+
+/// Watchdog for stalled workers
+///
+/// A monitor thread checks the [`Heartbeat`] periodically and reports
+/// a [`WatchdogEvent`] to a callback when no heartbeat arrived within
+/// the timeout and again after the worker has recovered.
+///
+/// The callback is invoked on the monitor thread. It could react by
+/// finishing the stalled worker with a
+/// [`super::progress::ProgressHintSender`] and spawning a new one.
+///
+/// The watchdog is armed by the first heartbeat.
+#[derive(Debug)]
+pub struct Watchdog {
+    heartbeat: Heartbeat,
+    stop: Arc<Relay<()>>,
+    join_handle: JoinHandle<()>,
+}
+
+impl Watchdog {
+    /// Spawn the monitor thread
+    ///
+    /// # Panics
+    ///
+    /// Panics if the timeout is zero.
+    pub fn spawn(
+        timeout: Duration,
+        mut on_event: impl FnMut(WatchdogEvent) + Send + 'static,
+    ) -> Self {
+        assert!(timeout > Duration::ZERO, "timeout must not be zero");
+        let heartbeat = Heartbeat::default();
+        let stop = Arc::new(Relay::new());
+        let join_handle = {
+            let heartbeat = heartbeat.clone();
+            let stop = Arc::clone(&stop);
+            // Detect stalls with a delay of at most 25% of the timeout
+            let check_interval = timeout / 4;
+            thread::spawn(move || {
+                let mut monitor = HeartbeatMonitor::new(timeout, Instant::now());
+                while stop.wait_for(check_interval).is_none() {
+                    if let Some(event) = monitor.check(heartbeat.load(), Instant::now()) {
+                        log::debug!("Watchdog {event}");
+                        on_event(event);
+                    }
+                }
+            })
+        };
+        Self {
+            heartbeat,
+            stop,
+            join_handle,
+        }
+    }
+
+    /// The heartbeat that is supposed to be fed by the worker
+    #[must_use]
+    pub fn heartbeat(&self) -> Heartbeat {
+        self.heartbeat.clone()
+    }
+
+    /// Stop and join the monitor thread
+    pub fn stop(self) {
+        let Self {
+            heartbeat: _,
+            stop,
+            join_handle,
+        } = self;
+        stop.replace_notify_one(());
+        if join_handle.join().is_err() {
+            log::error!("Watchdog monitor thread panicked");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,67 @@
+use std::sync::mpsc;
+
+use super::*;
+
+const TIMEOUT: Duration = Duration::from_millis(10);
+
+#[test]
+fn detect_stalls_and_recovery() {
+    let t0 = Instant::now();
+    let heartbeat = Heartbeat::default();
+    let mut monitor = HeartbeatMonitor::new(TIMEOUT, t0);
+
+    // Disarmed
+    assert_eq!(None, monitor.check(heartbeat.load(), t0 + 10 * TIMEOUT));
+
+    heartbeat.feed();
+    let t1 = t0 + 10 * TIMEOUT;
+    assert_eq!(None, monitor.check(heartbeat.load(), t1));
+    assert_eq!(None, monitor.check(heartbeat.load(), t1 + TIMEOUT / 2));
+    assert_eq!(
+        Some(WatchdogEvent::Stalled { last_heartbeat: t1 }),
+        monitor.check(heartbeat.load(), t1 + TIMEOUT)
+    );
+    // Reported only once
+    assert_eq!(None, monitor.check(heartbeat.load(), t1 + 2 * TIMEOUT));
+
+    heartbeat.feed();
+    assert_eq!(
+        Some(WatchdogEvent::Recovered {
+            stalled_for: 3 * TIMEOUT
+        }),
+        monitor.check(heartbeat.load(), t1 + 3 * TIMEOUT)
+    );
+
+    heartbeat.disarm();
+    assert_eq!(None, monitor.check(heartbeat.load(), t1 + 4 * TIMEOUT));
+    assert_eq!(None, monitor.check(heartbeat.load(), t1 + 10 * TIMEOUT));
+}
+
+#[test]
+fn watchdog_thread() {
+    // Generous timeout for slow CI runners
+    let timeout = Duration::from_millis(100);
+    let (event_tx, event_rx) = mpsc::channel();
+    let watchdog = Watchdog::spawn(timeout, move |event| {
+        event_tx.send(event).unwrap();
+    });
+    let heartbeat = watchdog.heartbeat();
+    for _ in 0..5 {
+        heartbeat.feed();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+    assert!(event_rx.try_recv().is_err());
+
+    // Stall
+    assert!(matches!(
+        event_rx.recv_timeout(10 * timeout),
+        Ok(WatchdogEvent::Stalled { .. })
+    ));
+    heartbeat.feed();
+    match event_rx.recv_timeout(10 * timeout) {
+        Ok(WatchdogEvent::Recovered { stalled_for }) => assert!(stalled_for >= timeout),
+        unexpected => panic!("{unexpected:?}"),
+    }
+
+    watchdog.stop();
+}
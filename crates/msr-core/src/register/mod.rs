@@ -1,10 +1,17 @@
 use std::fmt;
 
-use crate::{time::SystemInstant, Measurement};
+use crate::{
+    time::SystemInstant, unit::Unit, BitfieldDefinition, Measurement, TimestampedValue, ValueType,
+};
 
 #[cfg(feature = "register-recorder")]
 pub mod recorder;
 
+mod snapshot;
+pub use self::snapshot::{
+    register_snapshot_cache, RegisterSnapshot, RegisterSnapshotReader, RegisterSnapshotWriter,
+};
+
 /// Address of a register
 ///
 /// Each register is addressed by a uniform, 64-bit unsigned integer value.
@@ -46,6 +53,50 @@ impl fmt::Display for Index {
     }
 }
 
+/// Static properties of a register
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Metadata {
+    /// The type of the register values
+    pub value_type: ValueType,
+
+    /// The unit of numeric register values
+    pub unit: Option<Unit>,
+
+    /// The named bits of status or control words
+    pub bitfield: Option<BitfieldDefinition>,
+}
+
+impl Metadata {
+    #[must_use]
+    pub const fn new(value_type: ValueType) -> Self {
+        Self {
+            value_type,
+            unit: None,
+            bitfield: None,
+        }
+    }
+
+    /// Metadata of a register with named bits
+    ///
+    /// The value type is derived from the width of the bitfield.
+    #[must_use]
+    pub fn from_bitfield(bitfield: BitfieldDefinition) -> Self {
+        Self {
+            value_type: bitfield.width.to_scalar_type().into(),
+            unit: None,
+            bitfield: Some(bitfield),
+        }
+    }
+
+    #[must_use]
+    pub fn with_unit(self, unit: Unit) -> Self {
+        Self {
+            unit: Some(unit),
+            ..self
+        }
+    }
+}
+
 /// Measurement of a single register
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct IndexedMeasurement<Value> {
@@ -54,19 +105,68 @@ pub struct IndexedMeasurement<Value> {
 }
 
 /// An observation of a single register value
-#[derive(Debug, Clone, Eq, PartialEq)]
-pub struct ObservedValue<Value> {
-    pub observed_at: SystemInstant,
-    pub value: Value,
-}
+pub type ObservedValue<Value> = TimestampedValue<Value>;
 
 /// A partial observation of multiple register values
 ///
 /// The indexes of the registers are implicitly defined by their
 /// order, i.e. the mapping to a register index is defined in the
 /// outer context.
+///
+/// Each value keeps its own time stamp and quality.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct ObservedValues<Value> {
+    /// The time of the most recent observation
     pub observed_at: SystemInstant,
-    pub values: Vec<Option<Value>>,
+    pub values: Vec<Option<ObservedValue<Value>>>,
+}
+
+impl<Value> ObservedValues<Value> {
+    /// Group individually observed values.
+    ///
+    /// The group is observed at the time of the most recent
+    /// observation.
+    ///
+    /// Returns `None` if none of the values has been observed.
+    #[must_use]
+    pub fn from_timestamped<I>(values: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Option<TimestampedValue<Value>>>,
+    {
+        let values: Vec<_> = values.into_iter().collect();
+        let observed_at = values
+            .iter()
+            .flatten()
+            .map(|value| &value.observed_at)
+            .max_by_key(|observed_at| observed_at.system_time())?
+            .clone();
+        Some(Self {
+            observed_at,
+            values,
+        })
+    }
+
+    /// Split the group into individually observed values.
+    pub fn into_timestamped(self) -> impl Iterator<Item = Option<TimestampedValue<Value>>> {
+        self.values.into_iter()
+    }
+}
+
+#[test]
+fn group_timestamped_values() {
+    use std::time::Duration;
+
+    use crate::Quality;
+
+    let t0 = SystemInstant::now();
+    let t1 = t0.clone() + Duration::from_secs(1);
+    let values = vec![
+        Some(TimestampedValue::new(1, t0.clone())),
+        None,
+        Some(TimestampedValue::new(2, t1.clone()).with_quality(Quality::Bad)),
+    ];
+    let observed = ObservedValues::from_timestamped(values.clone()).unwrap();
+    assert_eq!(t1, observed.observed_at);
+    assert_eq!(values, observed.into_timestamped().collect::<Vec<_>>());
+    assert!(ObservedValues::<i32>::from_timestamped([None, None]).is_none());
 }
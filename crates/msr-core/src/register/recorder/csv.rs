@@ -1,4 +1,6 @@
-use std::{io, iter, num::NonZeroUsize, path::PathBuf, time::SystemTime};
+use std::{
+    io, iter, num::NonZeroUsize, path::PathBuf, result::Result as StdResult, time::SystemTime,
+};
 
 use ::csv::StringRecord as CsvStringRecord;
 
@@ -10,26 +12,29 @@ use crate::{
         RecordStorageWrite as _, StorageConfig, StorageDescriptor, StorageStatistics,
     },
     time::SystemInstant,
-    ScalarType, ToValueType, ValueType,
+    Decimal, NonFinitePolicy, ScalarType, ToValueType, ValueType,
 };
 
 use super::{
-    Error, Record, RecordStorage, RecordStorageBase, Result, SerdeRegisterValue, StorageRecord,
-    StorageRecordDeserializeError, StorageRecordDeserializer, StoredRecord, StoredRecordPrelude,
-    Timestamp,
+    quality_from_value, Error, QualityValue, Record, RecordStorage, RecordStorageBase, Result,
+    SerdeRegisterValue, StorageRecord, StorageRecordDeserializeError, StorageRecordDeserializer,
+    StoredRecord, StoredRecordPrelude, Timestamp,
 };
 
 impl csv::StringRecordDeserializer<StorageRecord> for StorageRecordDeserializer {
-    #[allow(clippy::panic_in_result_fn)] // unimplemented!()
     fn deserialize_string_record(
         &self,
         record: &CsvStringRecord,
     ) -> storage::Result<StorageRecord> {
-        if record.len() != 2 + self.registers.len() {
+        // Records that have been written without ages and qualities
+        // of the register values are still accepted
+        let register_count = self.registers.len();
+        let with_ages_and_qualities = record.len() == 2 + 3 * register_count;
+        if !with_ages_and_qualities && record.len() != 2 + register_count {
             return Err(io::Error::new(
                 io::ErrorKind::Other,
                 StorageRecordDeserializeError::MismatchingNumberOfFields {
-                    expected: 2 + self.registers.len(),
+                    expected: 2 + 3 * register_count,
                     actual: record.len(),
                 },
             )
@@ -45,58 +50,17 @@ impl csv::StringRecordDeserializer<StorageRecord> for StorageRecordDeserializer
         let observed_at = Timestamp::parse_rfc3339(record_fields.next().unwrap())
             .map_err(StorageRecordDeserializeError::ParseObservedAt)
             .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
-        let mut register_values = Vec::with_capacity(self.registers.len());
-        for (record_field, (register_index, register_type)) in record_fields.zip(&self.registers) {
+        let mut register_values = Vec::with_capacity(register_count);
+        // The registers must be zipped first to not consume
+        // the field that follows the last register value
+        for ((register_index, register_type), record_field) in
+            self.registers.iter().zip(record_fields.by_ref())
+        {
             if record_field.is_empty() {
                 register_values.push(None);
                 continue;
             }
-            let parsed_record_field = match register_type {
-                ValueType::Scalar(t) => match t {
-                    ScalarType::Bool => record_field
-                        .parse::<bool>()
-                        .map(SerdeRegisterValue::Bool)
-                        .map_err(|err| err.to_string()),
-                    ScalarType::I16 => record_field
-                        .parse::<i64>()
-                        .map(SerdeRegisterValue::I64)
-                        .map_err(|err| err.to_string()),
-                    ScalarType::U16 => record_field
-                        .parse::<u64>()
-                        .map(SerdeRegisterValue::U64)
-                        .map_err(|err| err.to_string()),
-                    ScalarType::I32 => record_field
-                        .parse::<i64>()
-                        .map(SerdeRegisterValue::I64)
-                        .map_err(|err| err.to_string()),
-                    ScalarType::U32 => record_field
-                        .parse::<u64>()
-                        .map(SerdeRegisterValue::U64)
-                        .map_err(|err| err.to_string()),
-                    ScalarType::F32 => record_field
-                        .parse::<f64>()
-                        .map(SerdeRegisterValue::F64)
-                        .map_err(|err| err.to_string()),
-                    ScalarType::I64 => record_field
-                        .parse::<i64>()
-                        .map(SerdeRegisterValue::I64)
-                        .map_err(|err| err.to_string()),
-                    ScalarType::U64 => record_field
-                        .parse::<u64>()
-                        .map(SerdeRegisterValue::U64)
-                        .map_err(|err| err.to_string()),
-                    ScalarType::F64 => record_field
-                        .parse::<f64>()
-                        .map(SerdeRegisterValue::F64)
-                        .map_err(|err| err.to_string()),
-                    _ => unimplemented!(),
-                },
-                ValueType::String => record_field
-                    .parse::<String>()
-                    .map(SerdeRegisterValue::String)
-                    .map_err(|err| err.to_string()),
-                _ => unimplemented!(),
-            };
+            let parsed_record_field = parse_register_value(*register_type, record_field);
             let register_value = match parsed_record_field {
                 Ok(val) => val,
                 Err(err) => {
@@ -115,17 +79,111 @@ impl csv::StringRecordDeserializer<StorageRecord> for StorageRecordDeserializer
             };
             register_values.push(Some(register_value));
         }
+        let mut register_ages_ns = Vec::with_capacity(register_count);
+        let mut register_qualities = Vec::with_capacity(register_count);
+        if with_ages_and_qualities {
+            for record_field in record_fields.by_ref().take(register_count) {
+                register_ages_ns.push(parse_register_age(record_field)?);
+            }
+            for record_field in record_fields {
+                register_qualities.push(parse_register_quality(record_field)?);
+            }
+        }
         Ok(StorageRecord {
             created_at_offset_ns,
             observed_at,
             register_values,
+            register_ages_ns,
+            register_qualities,
         })
     }
 }
 
+#[allow(clippy::panic_in_result_fn)] // unimplemented!()
+fn parse_register_value(
+    register_type: ValueType,
+    record_field: &str,
+) -> StdResult<SerdeRegisterValue, String> {
+    match register_type {
+        ValueType::Scalar(t) => match t {
+            ScalarType::Bool => record_field
+                .parse::<bool>()
+                .map(SerdeRegisterValue::Bool)
+                .map_err(|err| err.to_string()),
+            ScalarType::I16 => record_field
+                .parse::<i64>()
+                .map(SerdeRegisterValue::I64)
+                .map_err(|err| err.to_string()),
+            ScalarType::U16 => record_field
+                .parse::<u64>()
+                .map(SerdeRegisterValue::U64)
+                .map_err(|err| err.to_string()),
+            ScalarType::I32 => record_field
+                .parse::<i64>()
+                .map(SerdeRegisterValue::I64)
+                .map_err(|err| err.to_string()),
+            ScalarType::U32 => record_field
+                .parse::<u64>()
+                .map(SerdeRegisterValue::U64)
+                .map_err(|err| err.to_string()),
+            ScalarType::F32 => record_field
+                .parse::<f64>()
+                .map(SerdeRegisterValue::F64)
+                .map_err(|err| err.to_string()),
+            ScalarType::I64 => record_field
+                .parse::<i64>()
+                .map(SerdeRegisterValue::I64)
+                .map_err(|err| err.to_string()),
+            ScalarType::U64 => record_field
+                .parse::<u64>()
+                .map(SerdeRegisterValue::U64)
+                .map_err(|err| err.to_string()),
+            ScalarType::F64 => record_field
+                .parse::<f64>()
+                .map(SerdeRegisterValue::F64)
+                .map_err(|err| err.to_string()),
+            ScalarType::Decimal => record_field
+                .parse::<Decimal>()
+                .map(SerdeRegisterValue::Decimal)
+                .map_err(|err| err.to_string()),
+            _ => unimplemented!(),
+        },
+        ValueType::String => record_field
+            .parse::<String>()
+            .map(SerdeRegisterValue::String)
+            .map_err(|err| err.to_string()),
+        _ => unimplemented!(),
+    }
+}
+
+fn parse_register_age(record_field: &str) -> storage::Result<Option<u64>> {
+    if record_field.is_empty() {
+        return Ok(None);
+    }
+    let age_ns = record_field
+        .parse()
+        .map_err(StorageRecordDeserializeError::ParseRegisterAge)
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    Ok(Some(age_ns))
+}
+
+fn parse_register_quality(record_field: &str) -> storage::Result<Option<QualityValue>> {
+    if record_field.is_empty() {
+        return Ok(None);
+    }
+    let quality = record_field
+        .parse()
+        .ok()
+        .filter(|value| quality_from_value(*value).is_some())
+        .ok_or_else(|| StorageRecordDeserializeError::ParseRegisterQuality(record_field.to_owned()))
+        .map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+    Ok(Some(quality))
+}
+
 #[allow(missing_debug_implementations)]
 pub struct FileRecordStorage {
     register_types: Vec<ValueType>,
+    non_finite_policy: NonFinitePolicy,
     inner: csv::FileRecordStorageWithDeserializer<StorageRecordDeserializer, StorageRecord>,
 }
 
@@ -133,8 +191,17 @@ const FILE_NAME_SUFFIX: &str = ".csv";
 
 const CREATED_AT_COLUMN_HEADER: &str = "created_at";
 const OBSERVED_AT_COLUMN_HEADER: &str = "observed_at";
+const AGE_COLUMN_HEADER_SUFFIX: &str = ".age_ns";
+const QUALITY_COLUMN_HEADER_SUFFIX: &str = ".quality";
 
 impl FileRecordStorage {
+    /// Create a new storage for the given registers.
+    ///
+    /// Arrays and maps cannot be recorded in a single CSV field
+    /// and registers of these types are rejected.
+    ///
+    /// The ages and qualities of the register values are recorded
+    /// in additional columns after all register values.
     pub fn try_new<I>(
         config: StorageConfig,
         base_path: PathBuf,
@@ -147,20 +214,36 @@ impl FileRecordStorage {
         let file_name_template = RollingFileNameTemplate {
             prefix: file_name_prefix,
             suffix: FILE_NAME_SUFFIX.to_owned(),
+            with_sequence_number: true,
         };
-        let mut register_types = Vec::new();
-        let mut registers = Vec::new();
-        let custom_headers = iter::once(CREATED_AT_COLUMN_HEADER.to_owned())
-            .chain(iter::once(OBSERVED_AT_COLUMN_HEADER.to_owned()))
-            .chain(
-                registers_iter
-                    .into_iter()
-                    .map(|(register_index, register_type)| {
-                        register_types.push(register_type);
-                        registers.push((register_index, register_type));
-                        register_index.to_string()
-                    }),
-            );
+        let registers: Vec<_> = registers_iter.into_iter().collect();
+        if let Some((register_index, register_type)) = registers
+            .iter()
+            .find(|(_, register_type)| matches!(register_type, ValueType::Array | ValueType::Map))
+        {
+            return Err(Error::UnsupportedRegisterType {
+                register_index: *register_index,
+                register_type: *register_type,
+            });
+        }
+        let register_types = registers
+            .iter()
+            .map(|(_, register_type)| *register_type)
+            .collect();
+        let custom_headers =
+            iter::once(CREATED_AT_COLUMN_HEADER.to_owned())
+                .chain(iter::once(OBSERVED_AT_COLUMN_HEADER.to_owned()))
+                .chain(
+                    registers
+                        .iter()
+                        .map(|(register_index, _)| register_index.to_string()),
+                )
+                .chain(registers.iter().map(|(register_index, _)| {
+                    format!("{register_index}{AGE_COLUMN_HEADER_SUFFIX}")
+                }))
+                .chain(registers.iter().map(|(register_index, _)| {
+                    format!("{register_index}{QUALITY_COLUMN_HEADER_SUFFIX}")
+                }));
         let inner = csv::FileRecordStorageWithDeserializer::try_new(
             Default::default(), // no binary data
             config,
@@ -171,9 +254,17 @@ impl FileRecordStorage {
         )?;
         Ok(Self {
             register_types,
+            non_finite_policy: Default::default(),
             inner,
         })
     }
+
+    /// Configure how NaN and infinite values are recorded.
+    ///
+    /// Non-finite values are recorded as is by default.
+    pub fn set_non_finite_policy(&mut self, non_finite_policy: NonFinitePolicy) {
+        self.non_finite_policy = non_finite_policy;
+    }
 }
 
 impl RecordStorageBase for FileRecordStorage {
@@ -220,17 +311,17 @@ where
             .zip(record.observation.register_values.iter())
         {
             if let Some(register_value) = register_value {
-                if *register_type != register_value.to_value_type() {
+                if *register_type != register_value.value.to_value_type() {
                     return Err(Error::MismatchingRegisterTypes {
                         expected: *register_type,
-                        actual: register_value.to_value_type(),
+                        actual: register_value.value.to_value_type(),
                     });
                 }
             }
         }
-        let (record_written, _) = self
-            .inner
-            .append_record(created_at, StorageRecord::from(record))?;
+        let storage_record =
+            StorageRecord::from(record).apply_non_finite_policy(self.non_finite_policy)?;
+        let (record_written, _) = self.inner.append_record(created_at, storage_record)?;
         // Silently ignore expected write errors here
         // TODO: How to report them without overwhelming clients??
         if let Err(err) = record_written {
@@ -267,3 +358,104 @@ where
             })?)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        num::{NonZeroU32, NonZeroU64},
+        time::Duration,
+    };
+
+    use crate::{
+        storage::{DurabilityPolicy, MemorySize, StorageSegmentConfig, TimeInterval},
+        Quality, Value,
+    };
+
+    use super::super::{ObservedRegisterValue, ObservedRegisterValues};
+
+    use super::*;
+
+    fn storage_config() -> StorageConfig {
+        StorageConfig {
+            retention_time: TimeInterval::Days(NonZeroU32::new(1).unwrap()),
+            segmentation: StorageSegmentConfig {
+                time_interval: TimeInterval::Days(NonZeroU32::new(1).unwrap()),
+                size_limit: MemorySize::Bytes(NonZeroU64::new(1_048_576).unwrap()),
+            },
+            durability: DurabilityPolicy::Never,
+        }
+    }
+
+    #[test]
+    fn restore_ages_and_qualities_of_register_values() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let mut storage = FileRecordStorage::try_new(
+            storage_config(),
+            temp_dir.path().to_path_buf(),
+            "registers_".to_owned(),
+            [
+                (register::Index::new(1), ValueType::Scalar(ScalarType::F64)),
+                (register::Index::new(2), ValueType::Scalar(ScalarType::F64)),
+                (register::Index::new(3), ValueType::Scalar(ScalarType::F64)),
+            ],
+        )
+        .unwrap();
+        let observation = ObservedRegisterValues {
+            observed_at: Timestamp::now_utc(),
+            register_values: vec![
+                Some(ObservedRegisterValue::new(Value::from(1.0))),
+                None,
+                Some(ObservedRegisterValue {
+                    value: Value::from(3.0),
+                    age: Duration::from_millis(250),
+                    quality: Quality::Bad,
+                }),
+            ],
+        };
+        storage
+            .append_record(
+                &SystemInstant::now(),
+                Record {
+                    prelude: Default::default(),
+                    observation: observation.clone(),
+                },
+            )
+            .unwrap();
+        let records: Vec<StoredRecord<Value>> = storage
+            .recent_records(NonZeroUsize::new(1).unwrap())
+            .unwrap();
+        assert_eq!(1, records.len());
+        assert_eq!(observation, records[0].observation);
+    }
+
+    #[test]
+    fn reject_structured_register_types() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        for register_type in [ValueType::Array, ValueType::Map] {
+            let registers = [
+                (register::Index::new(1), ValueType::Scalar(ScalarType::F64)),
+                (register::Index::new(2), register_type),
+            ];
+            let res = FileRecordStorage::try_new(
+                storage_config(),
+                temp_dir.path().to_path_buf(),
+                "registers_".to_owned(),
+                registers,
+            );
+            assert!(matches!(
+                res,
+                Err(Error::UnsupportedRegisterType {
+                    register_index,
+                    register_type: rejected_type,
+                }) if register_index == register::Index::new(2) && rejected_type == register_type
+            ));
+        }
+        assert!(FileRecordStorage::try_new(
+            storage_config(),
+            temp_dir.path().to_path_buf(),
+            "registers_".to_owned(),
+            [(register::Index::new(1), ValueType::String)],
+        )
+        .is_ok());
+    }
+}
@@ -1,7 +1,9 @@
 use std::{
+    collections::BTreeMap,
+    iter,
     num::{NonZeroUsize, ParseIntError},
     result::Result as StdResult,
-    time::SystemTime,
+    time::{Duration, SystemTime},
 };
 
 use serde::{Deserialize, Serialize};
@@ -14,7 +16,8 @@ use crate::{
         RecordStorageBase, WritableRecordPrelude,
     },
     time::{SystemInstant, Timestamp},
-    ScalarValue, Value, ValueType,
+    Decimal, NonFiniteError, NonFinitePolicy, Quality, ScalarValue, TimestampedValue, Value,
+    ValueType,
 };
 
 #[cfg(feature = "csv-register-recorder")]
@@ -28,6 +31,15 @@ pub enum Error {
         actual: ValueType,
     },
 
+    #[error("unsupported register type: register index = {register_index}, register type = {register_type:?}")]
+    UnsupportedRegisterType {
+        register_index: register::Index,
+        register_type: ValueType,
+    },
+
+    #[error(transparent)]
+    NonFiniteValue(#[from] NonFiniteError),
+
     #[error(transparent)]
     Storage(#[from] storage::Error),
 
@@ -37,12 +49,58 @@ pub enum Error {
 
 pub type Result<T> = StdResult<T, Error>;
 
+/// A single value of an observation of register values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ObservedRegisterValue<RegisterValue> {
+    pub value: RegisterValue,
+
+    /// Time between the observation of this value and the
+    /// observation of the whole group
+    pub age: Duration,
+
+    /// The quality of the observation
+    pub quality: Quality,
+}
+
+impl<RegisterValue> ObservedRegisterValue<RegisterValue> {
+    /// A value with [`Quality::Good`] that has been observed
+    /// together with the group.
+    #[must_use]
+    pub const fn new(value: RegisterValue) -> Self {
+        Self {
+            value,
+            age: Duration::ZERO,
+            quality: Quality::Good,
+        }
+    }
+}
+
+impl<RegisterValue> From<RegisterValue> for ObservedRegisterValue<RegisterValue> {
+    fn from(from: RegisterValue) -> Self {
+        Self::new(from)
+    }
+}
+
 /// An observation of register values
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct ObservedRegisterValues<RegisterValue> {
+    /// The time of the most recent observation
     pub observed_at: Timestamp,
 
-    pub register_values: Vec<Option<RegisterValue>>,
+    pub register_values: Vec<Option<ObservedRegisterValue<RegisterValue>>>,
+}
+
+impl<RegisterValue> ObservedRegisterValues<RegisterValue> {
+    /// Record individually observed values as a group.
+    ///
+    /// See also [`register::ObservedValues::from_timestamped`].
+    #[must_use]
+    pub fn from_timestamped<I>(values: I) -> Option<Self>
+    where
+        I: IntoIterator<Item = Option<TimestampedValue<RegisterValue>>>,
+    {
+        register::ObservedValues::from_timestamped(values).map(Into::into)
+    }
 }
 
 impl<RegisterValue> From<register::ObservedValues<RegisterValue>>
@@ -53,10 +111,31 @@ impl<RegisterValue> From<register::ObservedValues<RegisterValue>>
             observed_at,
             values,
         } = from;
+        let register_values = values
+            .into_iter()
+            .map(|value| {
+                value.map(|value| {
+                    let TimestampedValue {
+                        value,
+                        observed_at: value_observed_at,
+                        quality,
+                    } = value;
+                    let age = observed_at
+                        .system_time()
+                        .duration_since(value_observed_at.system_time())
+                        .unwrap_or_default();
+                    ObservedRegisterValue {
+                        value,
+                        age,
+                        quality,
+                    }
+                })
+            })
+            .collect();
         Self {
             // Drop the Instant part
             observed_at: observed_at.timestamp_utc(),
-            register_values: values,
+            register_values,
         }
     }
 }
@@ -138,6 +217,11 @@ pub trait RecordStorage<RegisterValue>: RecordStorageBase {
     ) -> Result<Vec<StoredRecord<RegisterValue>>>;
 }
 
+/// Compact representation of register values for storage
+///
+/// Values are stored untagged and their exact types are restored
+/// from the register types. Use the serde implementation of
+/// [`Value`] for a self-describing representation.
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(untagged)]
 pub enum SerdeRegisterValue {
@@ -151,6 +235,47 @@ pub enum SerdeRegisterValue {
     F64(f64),
     /// A string
     String(String),
+    /// A sequence of values
+    Array(Vec<SerdeRegisterValue>),
+    /// Values with named keys
+    Map(BTreeMap<String, SerdeRegisterValue>),
+    /// A decimal number, stored as a string
+    ///
+    /// Only restored from the register type, because a string
+    /// is always deserialized into the `String` variant.
+    Decimal(Decimal),
+}
+
+impl SerdeRegisterValue {
+    /// Apply the policy to all floating-point values.
+    ///
+    /// Returns `None` if the value should be stored as missing.
+    pub fn apply_non_finite_policy(
+        self,
+        policy: NonFinitePolicy,
+    ) -> StdResult<Option<Self>, NonFiniteError> {
+        match self {
+            Self::F64(val) => {
+                Ok(ScalarValue::F64(val)
+                    .apply_non_finite_policy(policy)?
+                    .map(|val| match val {
+                        ScalarValue::F64(val) => Self::F64(val),
+                        _ => unreachable!(),
+                    }))
+            }
+            Self::Array(vals) => vals
+                .into_iter()
+                .map(|val| val.apply_non_finite_policy(policy))
+                .collect::<StdResult<Option<Vec<_>>, _>>()
+                .map(|vals| vals.map(Self::Array)),
+            Self::Map(vals) => vals
+                .into_iter()
+                .map(|(key, val)| Ok(val.apply_non_finite_policy(policy)?.map(|val| (key, val))))
+                .collect::<StdResult<Option<BTreeMap<_, _>>, _>>()
+                .map(|vals| vals.map(Self::Map)),
+            val => Ok(Some(val)),
+        }
+    }
 }
 
 #[test]
@@ -181,6 +306,104 @@ fn deserialize_scalar_value() {
     );
 }
 
+#[test]
+fn record_timestamped_values() {
+    let t0 = SystemInstant::now();
+    let t1 = t0.clone() + Duration::from_secs(1);
+    let observed = ObservedRegisterValues::from_timestamped([
+        Some(TimestampedValue::new(Value::from(1u8), t1.clone())),
+        None,
+        Some(TimestampedValue::new(Value::from(2u8), t0.clone()).with_quality(Quality::Bad)),
+        Some(TimestampedValue::new(Value::from(3u8), t0).with_quality(Quality::Uncertain)),
+    ])
+    .unwrap();
+    assert_eq!(observed.observed_at, t1.timestamp_utc());
+    assert_eq!(
+        observed.register_values,
+        vec![
+            Some(ObservedRegisterValue::new(Value::from(1u8))),
+            None,
+            Some(ObservedRegisterValue {
+                value: Value::from(2u8),
+                age: Duration::from_secs(1),
+                quality: Quality::Bad,
+            }),
+            Some(ObservedRegisterValue {
+                value: Value::from(3u8),
+                age: Duration::from_secs(1),
+                quality: Quality::Uncertain,
+            }),
+        ]
+    );
+    assert!(ObservedRegisterValues::<Value>::from_timestamped([None, None]).is_none());
+}
+
+#[test]
+fn apply_non_finite_policy() {
+    let value = SerdeRegisterValue::Array(vec![
+        SerdeRegisterValue::F64(1.0),
+        SerdeRegisterValue::F64(f64::NEG_INFINITY),
+    ]);
+    assert_eq!(
+        value
+            .clone()
+            .apply_non_finite_policy(NonFinitePolicy::Clamp)
+            .unwrap(),
+        Some(SerdeRegisterValue::Array(vec![
+            SerdeRegisterValue::F64(1.0),
+            SerdeRegisterValue::F64(f64::MIN),
+        ]))
+    );
+    assert_eq!(
+        value
+            .clone()
+            .apply_non_finite_policy(NonFinitePolicy::Missing)
+            .unwrap(),
+        None
+    );
+    assert!(value
+        .apply_non_finite_policy(NonFinitePolicy::Reject)
+        .is_err());
+    assert_eq!(
+        SerdeRegisterValue::U64(1)
+            .apply_non_finite_policy(NonFinitePolicy::Reject)
+            .unwrap(),
+        Some(SerdeRegisterValue::U64(1))
+    );
+}
+
+#[test]
+fn serialize_decimal_value() {
+    let value = Value::from("-0.50".parse::<Decimal>().unwrap());
+    let serde_value = SerdeRegisterValue::from(value.clone());
+    assert_eq!(serde_json::to_string(&serde_value).unwrap(), r#""-0.50""#);
+    assert_eq!(Value::from(serde_value), value);
+}
+
+#[test]
+fn serialize_structured_value() {
+    let value = Value::Map(BTreeMap::from_iter([
+        (
+            "spectrum".to_owned(),
+            Value::Array(vec![Value::from(1u8), Value::from(0.5)]),
+        ),
+        ("unit".to_owned(), Value::from("V".to_owned())),
+    ]));
+    let json = serde_json::to_string(&SerdeRegisterValue::from(value.clone())).unwrap();
+    assert_eq!(json, r#"{"spectrum":[1,0.5],"unit":"V"}"#);
+    let deserialized = serde_json::from_str::<SerdeRegisterValue>(&json).unwrap();
+    assert_eq!(
+        Value::from(deserialized),
+        Value::Map(BTreeMap::from_iter([
+            (
+                "spectrum".to_owned(),
+                Value::Array(vec![Value::from(1i64), Value::from(0.5)]),
+            ),
+            ("unit".to_owned(), Value::from("V".to_owned())),
+        ]))
+    );
+}
+
 impl From<ScalarValue> for SerdeRegisterValue {
     fn from(from: ScalarValue) -> Self {
         use ScalarValue as S;
@@ -196,6 +419,7 @@ impl From<ScalarValue> for SerdeRegisterValue {
             S::I64(val) => Self::I64(val),
             S::U64(val) => Self::U64(val),
             S::F64(val) => Self::F64(val),
+            S::Decimal(val) => Self::Decimal(val),
         }
     }
 }
@@ -206,6 +430,8 @@ impl From<Value> for SerdeRegisterValue {
         match from {
             V::Scalar(val) => Self::from(val),
             V::String(val) => Self::String(val),
+            V::Array(val) => Self::Array(val.into_iter().map(Into::into).collect()),
+            V::Map(val) => Self::Map(val.into_iter().map(|(k, v)| (k, v.into())).collect()),
             V::Duration(_) => unimplemented!(),
             V::Bytes(_) => unimplemented!(),
         }
@@ -222,6 +448,9 @@ impl From<SerdeRegisterValue> for crate::Value {
             U64(val) => Self::Scalar(S::U64(val)),
             F64(val) => Self::Scalar(S::F64(val)),
             String(val) => Self::String(val),
+            Decimal(val) => Self::Scalar(S::Decimal(val)),
+            Array(val) => Self::Array(val.into_iter().map(Into::into).collect()),
+            Map(val) => Self::Map(val.into_iter().map(|(k, v)| (k, v.into())).collect()),
         }
     }
 }
@@ -249,6 +478,32 @@ impl<RegisterValue> StoredRecord<RegisterValue> {
     }
 }
 
+/// Qualities are stored as numbers like the severity of
+/// event journal entries.
+type QualityValue = u8;
+
+const fn quality_to_value(quality: Quality) -> QualityValue {
+    match quality {
+        Quality::Good => 0,
+        Quality::Uncertain => 1,
+        Quality::Bad => 2,
+    }
+}
+
+const fn quality_from_value(value: QualityValue) -> Option<Quality> {
+    match value {
+        0 => Some(Quality::Good),
+        1 => Some(Quality::Uncertain),
+        2 => Some(Quality::Bad),
+        _ => None,
+    }
+}
+
+/// Flat representation of a record for storage
+///
+/// The ages and qualities of the register values are stored
+/// after all register values. They are missing if the
+/// corresponding register value is missing.
 #[derive(Debug, Serialize)]
 pub struct StorageRecord {
     created_at_offset_ns: CreatedAtOffsetNanos,
@@ -256,6 +511,41 @@ pub struct StorageRecord {
     observed_at: Timestamp,
 
     register_values: Vec<Option<SerdeRegisterValue>>,
+
+    register_ages_ns: Vec<Option<u64>>,
+
+    register_qualities: Vec<Option<QualityValue>>,
+}
+
+impl StorageRecord {
+    // Only used when a storage backend like CSV is enabled
+    #[allow(dead_code)]
+    fn apply_non_finite_policy(mut self, policy: NonFinitePolicy) -> Result<Self> {
+        if policy == NonFinitePolicy::Propagate {
+            return Ok(self);
+        }
+        self.register_values = self
+            .register_values
+            .into_iter()
+            .map(|val| {
+                val.map(|val| val.apply_non_finite_policy(policy))
+                    .transpose()
+                    .map(Option::flatten)
+            })
+            .collect::<StdResult<_, _>>()?;
+        for ((val, age_ns), quality) in self
+            .register_values
+            .iter()
+            .zip(&mut self.register_ages_ns)
+            .zip(&mut self.register_qualities)
+        {
+            if val.is_none() {
+                *age_ns = None;
+                *quality = None;
+            }
+        }
+        Ok(self)
+    }
 }
 
 impl ReadableRecordPrelude for StorageRecord {
@@ -293,6 +583,12 @@ pub enum StorageRecordDeserializeError {
 
     #[error(transparent)]
     ParseRegisterValue(anyhow::Error),
+
+    #[error(transparent)]
+    ParseRegisterAge(ParseIntError),
+
+    #[error("invalid register quality: {0}")]
+    ParseRegisterQuality(String),
 }
 
 impl<RegisterValue> From<Record<RegisterValue>> for StorageRecord
@@ -308,13 +604,31 @@ where
                     register_values,
                 },
         } = from;
+        let mut storage_values = Vec::with_capacity(register_values.len());
+        let mut register_ages_ns = Vec::with_capacity(register_values.len());
+        let mut register_qualities = Vec::with_capacity(register_values.len());
+        for register_value in register_values {
+            let Some(ObservedRegisterValue {
+                value,
+                age,
+                quality,
+            }) = register_value
+            else {
+                storage_values.push(None);
+                register_ages_ns.push(None);
+                register_qualities.push(None);
+                continue;
+            };
+            storage_values.push(Some(value.into()));
+            register_ages_ns.push(Some(u64::try_from(age.as_nanos()).unwrap_or(u64::MAX)));
+            register_qualities.push(Some(quality_to_value(quality)));
+        }
         Self {
             created_at_offset_ns: created_at_offset.into(),
             observed_at,
-            register_values: register_values
-                .into_iter()
-                .map(|v| v.map(Into::into))
-                .collect(),
+            register_values: storage_values,
+            register_ages_ns,
+            register_qualities,
         }
     }
 }
@@ -328,17 +642,32 @@ where
             created_at_offset_ns,
             observed_at,
             register_values,
+            register_ages_ns,
+            register_qualities,
         } = from;
+        // Records without ages and qualities contain values
+        // that have been observed together with good quality
+        let register_values = register_values
+            .into_iter()
+            .zip(register_ages_ns.into_iter().chain(iter::repeat(None)))
+            .zip(register_qualities.into_iter().chain(iter::repeat(None)))
+            .map(|((value, age_ns), quality)| {
+                value.map(|value| ObservedRegisterValue {
+                    value: value.into(),
+                    age: age_ns.map(Duration::from_nanos).unwrap_or_default(),
+                    quality: quality.map_or(Quality::Good, |quality| {
+                        quality_from_value(quality).unwrap_or(Quality::Bad)
+                    }),
+                })
+            })
+            .collect();
         Self {
             prelude: RecordPrelude {
                 created_at_offset: created_at_offset_ns.into(),
             },
             observation: ObservedRegisterValues {
                 observed_at,
-                register_values: register_values
-                    .into_iter()
-                    .map(|v| v.map(Into::into))
-                    .collect(),
+                register_values,
             },
         }
     }
@@ -0,0 +1,212 @@
+//! Sharing the latest register values with readers
+
+use crate::{
+    sync::{triple_buffer, TripleBufferReader, TripleBufferWriter},
+    time::SystemInstant,
+};
+
+/// The register values at a certain point in time
+///
+/// The indexes of the registers are implicitly defined by the
+/// order of the values like for [`super::ObservedValues`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct RegisterSnapshot<Value> {
+    /// Incremented for each published snapshot, starting at 0
+    /// for the initial snapshot
+    pub sequence_number: u64,
+
+    /// The time of the observation or `None` if not observed yet
+    pub observed_at: Option<SystemInstant>,
+
+    pub values: Vec<Option<Value>>,
+}
+
+impl<Value> RegisterSnapshot<Value> {
+    fn new(register_count: usize) -> Self {
+        Self {
+            sequence_number: 0,
+            observed_at: None,
+            values: std::iter::repeat_with(|| None)
+                .take(register_count)
+                .collect(),
+        }
+    }
+}
+
+impl<Value> Clone for RegisterSnapshot<Value>
+where
+    Value: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            sequence_number: self.sequence_number,
+            observed_at: self.observed_at.clone(),
+            values: self.values.clone(),
+        }
+    }
+
+    // Reuses the allocated memory
+    fn clone_from(&mut self, source: &Self) {
+        self.sequence_number = source.sequence_number;
+        self.observed_at.clone_from(&source.observed_at);
+        self.values.clone_from(&source.values);
+    }
+}
+
+/// Create a cache for the latest register values
+///
+/// The writer, e.g. a cyclic acquisition worker, publishes snapshots
+/// of all register values. A fixed number of readers, e.g. HMIs or
+/// servers, pick up the latest snapshot at any time.
+///
+/// Neither the writer nor the readers ever block. Publishing doesn't
+/// allocate memory as long as the values don't require allocations
+/// when cloned, e.g. for [`ScalarValue`](crate::ScalarValue).
+#[must_use]
+pub fn register_snapshot_cache<Value>(
+    register_count: usize,
+    reader_count: usize,
+) -> (
+    RegisterSnapshotWriter<Value>,
+    Vec<RegisterSnapshotReader<Value>>,
+)
+where
+    Value: Clone,
+{
+    let snapshot = RegisterSnapshot::new(register_count);
+    let (snapshots_tx, readers) = std::iter::repeat_with(|| triple_buffer(snapshot.clone()))
+        .take(reader_count)
+        .map(|(tx, rx)| (tx, RegisterSnapshotReader { snapshot_rx: rx }))
+        .unzip();
+    let writer = RegisterSnapshotWriter {
+        snapshot,
+        snapshots_tx,
+    };
+    (writer, readers)
+}
+
+/// Publishes register snapshots (wait-free)
+#[derive(Debug)]
+pub struct RegisterSnapshotWriter<Value> {
+    snapshot: RegisterSnapshot<Value>,
+    snapshots_tx: Vec<TripleBufferWriter<RegisterSnapshot<Value>>>,
+}
+
+impl<Value> RegisterSnapshotWriter<Value>
+where
+    Value: Clone,
+{
+    /// The current, unpublished snapshot
+    #[must_use]
+    pub fn snapshot(&self) -> &RegisterSnapshot<Value> {
+        &self.snapshot
+    }
+
+    /// Access the values of the next snapshot for updating them
+    ///
+    /// Values that are not updated keep their previous value.
+    pub fn values_mut(&mut self) -> &mut [Option<Value>] {
+        &mut self.snapshot.values
+    }
+
+    /// Publish the updated values for all readers
+    ///
+    /// Returns the sequence number of the published snapshot.
+    pub fn publish(&mut self, observed_at: SystemInstant) -> u64 {
+        self.snapshot.sequence_number += 1;
+        self.snapshot.observed_at = Some(observed_at);
+        for snapshot_tx in &mut self.snapshots_tx {
+            snapshot_tx.input_mut().clone_from(&self.snapshot);
+            snapshot_tx.publish();
+        }
+        self.snapshot.sequence_number
+    }
+}
+
+/// Reads the latest register snapshot (wait-free)
+#[derive(Debug)]
+pub struct RegisterSnapshotReader<Value> {
+    snapshot_rx: TripleBufferReader<RegisterSnapshot<Value>>,
+}
+
+impl<Value> RegisterSnapshotReader<Value> {
+    /// Check for a new snapshot that has not been read yet
+    #[must_use]
+    pub fn has_update(&self) -> bool {
+        self.snapshot_rx.has_update()
+    }
+
+    /// Pick up and access the latest snapshot
+    pub fn read(&mut self) -> &RegisterSnapshot<Value> {
+        self.snapshot_rx.read()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn read_latest_snapshot() {
+        let (mut writer, mut readers) = register_snapshot_cache::<i32>(2, 2);
+        assert_eq!(2, readers.len());
+        for reader in &mut readers {
+            let snapshot = reader.read();
+            assert_eq!(0, snapshot.sequence_number);
+            assert!(snapshot.observed_at.is_none());
+            assert_eq!(&[None, None], snapshot.values.as_slice());
+        }
+
+        writer.values_mut()[0] = Some(1);
+        assert_eq!(1, writer.publish(SystemInstant::now()));
+        writer.values_mut()[1] = Some(2);
+        let observed_at = SystemInstant::now();
+        assert_eq!(2, writer.publish(observed_at.clone()));
+
+        for reader in &mut readers {
+            assert!(reader.has_update());
+            let snapshot = reader.read();
+            assert_eq!(2, snapshot.sequence_number);
+            assert_eq!(Some(&observed_at), snapshot.observed_at.as_ref());
+            assert_eq!(&[Some(1), Some(2)], snapshot.values.as_slice());
+            assert!(!reader.has_update());
+        }
+    }
+
+    #[test]
+    fn read_consistent_snapshots_concurrently() {
+        const COUNT: u64 = 10_000;
+        let (mut writer, readers) = register_snapshot_cache::<u64>(4, 2);
+        let readers: Vec<_> = readers
+            .into_iter()
+            .map(|mut reader| {
+                thread::spawn(move || {
+                    let mut last_sequence_number = 0;
+                    while last_sequence_number < COUNT {
+                        let snapshot = reader.read();
+                        assert!(snapshot.sequence_number >= last_sequence_number);
+                        if snapshot.sequence_number > 0 {
+                            // Never torn
+                            assert!(snapshot
+                                .values
+                                .iter()
+                                .all(|value| *value == Some(snapshot.sequence_number)));
+                        }
+                        last_sequence_number = snapshot.sequence_number;
+                    }
+                })
+            })
+            .collect();
+        for sequence_number in 1..=COUNT {
+            for value in writer.values_mut() {
+                *value = Some(sequence_number);
+            }
+            assert_eq!(sequence_number, writer.publish(SystemInstant::now()));
+        }
+        for reader in readers {
+            reader.join().unwrap();
+        }
+    }
+}
@@ -12,12 +12,16 @@ use serde::{de::DeserializeOwned, Serialize};
 
 use crate::{
     fs::{
-        csv::RollingFileWriter,
+        csv::{
+            truncate_incomplete_trailing_record, RollingFileReader, RollingFileRecord,
+            RollingFileWriter,
+        },
         policy::{
-            FileInfoFilter, RollingFileConfig, RollingFileInfoWithSize, RollingFileLimits,
-            RollingFileNameTemplate, RollingFileStatus, RollingFileSystem, SystemTimeRange,
+            FileInfoFilter, RollingFileArchival, RollingFileConfig, RollingFileInfoWithSize,
+            RollingFileLimits, RollingFileNameTemplate, RollingFileStatus, RollingFileSystem,
+            SystemTimeRange,
         },
-        WriteResult,
+        DirLock, WriteResult,
     },
     storage::{
         CreatedAtOffset, MemorySize, ReadableRecordPrelude, RecordPreludeFilter, RecordStorageBase,
@@ -92,6 +96,8 @@ pub struct FileRecordStorage<RI, RO> {
 
     writing_status: Option<WritingStatus>,
 
+    _dir_lock: DirLock,
+
     _record_in_phantom: std::marker::PhantomData<RI>,
 
     _record_out_phantom: std::marker::PhantomData<RO>,
@@ -114,6 +120,26 @@ impl<RI, RO> FileRecordStorage<RI, RO> {
             .read_all_dir_entries_filtered_chronologically(filter)
     }
 
+    /// Replace the archival policy
+    ///
+    /// Old files are deleted instead of being archived by default.
+    pub fn replace_archival(
+        &mut self,
+        archival: Option<RollingFileArchival>,
+    ) -> Option<RollingFileArchival> {
+        std::mem::replace(&mut self.rolling_file_config.archival, archival)
+    }
+
+    /// Read the index of all archived files
+    pub fn read_archive_index(&self) -> IoResult<Vec<RollingFileInfoWithSize>> {
+        let Some(archival) = &self.rolling_file_config.archival else {
+            return Ok(Vec::new());
+        };
+        self.rolling_file_config
+            .system
+            .read_archive_index(&archival.archive_path)
+    }
+
     pub fn try_new(
         binary_data_format: BinaryDataFormat,
         config: StorageConfig,
@@ -121,9 +147,20 @@ impl<RI, RO> FileRecordStorage<RI, RO> {
         file_name_template: RollingFileNameTemplate,
         custom_header: Option<CsvStringRecord>,
     ) -> Result<Self> {
+        // Exclude other processes from writing into the same directory
+        let dir_lock = DirLock::try_acquire(&base_path)?;
+        let rolling_file_system = RollingFileSystem {
+            base_path,
+            file_name_template,
+        };
+        // Only the most recent file could have been written when
+        // the system crashed
+        if let Some(file_info) = rolling_file_system.read_most_recent_dir_entry()? {
+            truncate_incomplete_trailing_record(&file_info.path)?;
+        }
         let descriptor = StorageDescriptor {
             kind: "csv-file".to_string(),
-            base_path: Some(base_path.clone()),
+            base_path: Some(rolling_file_system.base_path.clone()),
             binary_data_format,
         };
         let StorageConfig {
@@ -132,6 +169,7 @@ impl<RI, RO> FileRecordStorage<RI, RO> {
                     time_interval: segment_time_interval,
                     size_limit: segment_size_limit,
                 },
+            durability,
             ..
         } = config;
         Ok(Self {
@@ -139,10 +177,7 @@ impl<RI, RO> FileRecordStorage<RI, RO> {
             descriptor,
             custom_header,
             rolling_file_config: RollingFileConfig {
-                system: RollingFileSystem {
-                    base_path,
-                    file_name_template,
-                },
+                system: rolling_file_system,
                 limits: RollingFileLimits {
                     max_bytes_written: Some(match segment_size_limit {
                         MemorySize::Bytes(bytes) => bytes.get(),
@@ -150,8 +185,11 @@ impl<RI, RO> FileRecordStorage<RI, RO> {
                     interval: Some(segment_time_interval.into()),
                     ..Default::default()
                 },
+                durability,
+                archival: None,
             },
             writing_status: None,
+            _dir_lock: dir_lock,
             _record_in_phantom: Default::default(),
             _record_out_phantom: Default::default(),
         })
@@ -267,10 +305,23 @@ impl<RI, RO> RecordStorageBase for FileRecordStorage<RI, RO> {
     }
 
     fn replace_config(&mut self, new_config: StorageConfig) -> StorageConfig {
+        self.rolling_file_config.durability = new_config.durability;
+        if let Some(writing_status) = self.writing_status.as_mut() {
+            writing_status.writer.set_durability(new_config.durability);
+        }
         std::mem::replace(&mut self.config, new_config)
     }
 
     fn perform_housekeeping(&mut self) -> Result<()> {
+        if self.rolling_file_config.archival.is_some() {
+            self.flush_before_reading()?;
+        }
+        if let Some(archival) = &self.rolling_file_config.archival {
+            let created_before = archival.archive_created_before(SystemTime::now());
+            self.rolling_file_config
+                .system
+                .archive_files_created_before(&archival.archive_path, created_before)?;
+        }
         let created_since =
             Interval::from(self.config.retention_time).system_time_before(SystemTime::now());
         self.retain_all_records_created_since(created_since)
@@ -489,6 +540,17 @@ impl<D, T> FileRecordStorageWithDeserializer<D, T> {
         self.inner.flush_before_reading()
     }
 
+    pub fn replace_archival(
+        &mut self,
+        archival: Option<RollingFileArchival>,
+    ) -> Option<RollingFileArchival> {
+        self.inner.replace_archival(archival)
+    }
+
+    pub fn read_archive_index(&self) -> IoResult<Vec<RollingFileInfoWithSize>> {
+        self.inner.read_archive_index()
+    }
+
     pub fn read_all_dir_entries_filtered_chronologically(
         &self,
         filter: &FileInfoFilter,
@@ -602,40 +664,24 @@ where
     ) -> Result<Vec<(SystemTime, T)>> {
         self.inner.flush_before_reading()?;
         let limit = limit.get().min(MAX_PREALLOCATED_CAPACITY_LIMIT);
+        let RecordPreludeFilter {
+            since_created_at,
+            until_created_at,
+        } = *filter;
         let mut records = Vec::with_capacity(limit);
-        let mut record = CsvStringRecord::new();
-        for file_info in self
-            .inner
-            .rolling_file_config
-            .system
-            .read_all_dir_entries_filtered_chronologically(
-                &file_info_filter_from_record_prelude_filter(filter),
-            )?
-        {
+        let reader = RollingFileReader::new(self.inner.rolling_file_config.system.clone());
+        for file_record in reader.read_records(since_created_at, until_created_at)? {
             if limit <= records.len() {
                 break;
             }
-            let mut reader = create_file_reader(&file_info.path)?;
-            while let Some(filtered_record) = read_next_from_string_record_filtered(
-                &mut reader,
-                &mut record,
-                &self.deserializer,
-                file_info.created_at.into(),
-                filter,
-            )? {
-                if limit <= records.len() {
-                    break;
-                }
-                match filtered_record {
-                    FilteredRecord::Match(record) => {
-                        records.push((file_info.created_at.into(), record));
-                    }
-                    FilteredRecord::MismatchCreatedAfter => {
-                        return Ok(records);
-                    }
-                }
-            }
+            let RollingFileRecord {
+                file_created_at,
+                record,
+                ..
+            } = file_record?;
+            let record = self.deserializer.deserialize_string_record(&record)?;
+            records.push((file_created_at, record));
         }
-        Ok(records.into_iter().collect())
+        Ok(records)
     }
 }
@@ -0,0 +1,247 @@
+//! Monitoring the available disk space of storages
+
+use std::{
+    io::Result as IoResult,
+    path::PathBuf,
+    time::{Duration, Instant},
+};
+
+use crate::fs::{disk_space, DiskSpace};
+
+use super::{MemorySize, RecordStorageBase, Result};
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Ord, PartialOrd)]
+pub enum DiskSpaceLevel {
+    Sufficient,
+    Low,
+    Critical,
+}
+
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DiskSpaceMonitorConfig {
+    /// Minimum time between two subsequent checks
+    pub check_interval: Duration,
+
+    /// The available space is low below this limit
+    pub low_below: MemorySize,
+
+    /// The available space is critical below this limit
+    ///
+    /// Should be less than [`Self::low_below`].
+    pub critical_below: MemorySize,
+}
+
+const fn memory_size_bytes(memory_size: MemorySize) -> u64 {
+    match memory_size {
+        MemorySize::Bytes(bytes) => bytes.get(),
+    }
+}
+
+impl DiskSpaceMonitorConfig {
+    #[must_use]
+    pub const fn level(&self, available_bytes: u64) -> DiskSpaceLevel {
+        if available_bytes < memory_size_bytes(self.critical_below) {
+            DiskSpaceLevel::Critical
+        } else if available_bytes < memory_size_bytes(self.low_below) {
+            DiskSpaceLevel::Low
+        } else {
+            DiskSpaceLevel::Sufficient
+        }
+    }
+}
+
+/// The level of the available disk space has changed
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct DiskSpaceEvent {
+    pub previous_level: DiskSpaceLevel,
+    pub level: DiskSpaceLevel,
+    pub disk_space: DiskSpace,
+}
+
+/// Periodically checks the available space of a data directory
+///
+/// Supposed to be invoked by the owner of a storage before writing,
+/// who is also responsible for publishing the returned events.
+#[derive(Debug)]
+pub struct DiskSpaceMonitor {
+    path: PathBuf,
+    config: DiskSpaceMonitorConfig,
+    level: DiskSpaceLevel,
+    last_checked_at: Option<Instant>,
+    last_disk_space: Option<DiskSpace>,
+}
+
+impl DiskSpaceMonitor {
+    #[must_use]
+    pub const fn new(path: PathBuf, config: DiskSpaceMonitorConfig) -> Self {
+        Self {
+            path,
+            config,
+            level: DiskSpaceLevel::Sufficient,
+            last_checked_at: None,
+            last_disk_space: None,
+        }
+    }
+
+    #[must_use]
+    pub const fn config(&self) -> &DiskSpaceMonitorConfig {
+        &self.config
+    }
+
+    /// The level of the last check
+    #[must_use]
+    pub const fn level(&self) -> DiskSpaceLevel {
+        self.level
+    }
+
+    /// The disk space of the last check
+    #[must_use]
+    pub const fn last_disk_space(&self) -> Option<DiskSpace> {
+        self.last_disk_space
+    }
+
+    fn is_check_due(&self, now: Instant) -> bool {
+        self.last_checked_at.map_or(true, |last_checked_at| {
+            now.saturating_duration_since(last_checked_at) >= self.config.check_interval
+        })
+    }
+
+    fn query(&mut self, now: Instant) -> IoResult<DiskSpace> {
+        let disk_space = disk_space(&self.path)?;
+        self.level = self.config.level(disk_space.available_bytes);
+        self.last_checked_at = Some(now);
+        self.last_disk_space = Some(disk_space);
+        Ok(disk_space)
+    }
+
+    fn event_since(
+        &self,
+        previous_level: DiskSpaceLevel,
+        disk_space: DiskSpace,
+    ) -> Option<DiskSpaceEvent> {
+        if previous_level == self.level {
+            return None;
+        }
+        let DiskSpace {
+            available_bytes,
+            total_bytes,
+        } = disk_space;
+        let path = self.path.display();
+        match self.level {
+            DiskSpaceLevel::Sufficient => {
+                log::info!("Sufficient disk space available for {path}: {available_bytes} of {total_bytes} byte(s)");
+            }
+            DiskSpaceLevel::Low => {
+                log::warn!("Low disk space available for {path}: {available_bytes} of {total_bytes} byte(s)");
+            }
+            DiskSpaceLevel::Critical => {
+                log::error!("Critical disk space available for {path}: {available_bytes} of {total_bytes} byte(s)");
+            }
+        }
+        Some(DiskSpaceEvent {
+            previous_level,
+            level: self.level,
+            disk_space,
+        })
+    }
+
+    /// Check the available disk space if due
+    ///
+    /// Returns an event if the level has changed since the last check.
+    pub fn check(&mut self, now: Instant) -> IoResult<Option<DiskSpaceEvent>> {
+        if !self.is_check_due(now) {
+            return Ok(None);
+        }
+        let previous_level = self.level;
+        let disk_space = self.query(now)?;
+        Ok(self.event_since(previous_level, disk_space))
+    }
+
+    /// Check the available disk space and free up space if needed
+    ///
+    /// Like [`Self::check()`], but performs housekeeping of the storage
+    /// early if the available space is not sufficient. The disk space
+    /// is checked again after housekeeping.
+    pub fn check_storage<S>(
+        &mut self,
+        now: Instant,
+        storage: &mut S,
+    ) -> Result<Option<DiskSpaceEvent>>
+    where
+        S: RecordStorageBase + ?Sized,
+    {
+        if !self.is_check_due(now) {
+            return Ok(None);
+        }
+        let previous_level = self.level;
+        let mut disk_space = self.query(now)?;
+        if self.level > DiskSpaceLevel::Sufficient {
+            log::info!("Performing housekeeping for {} early", self.path.display());
+            storage.perform_housekeeping()?;
+            disk_space = self.query(now)?;
+        }
+        Ok(self.event_since(previous_level, disk_space))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::num::NonZeroU64;
+
+    use super::*;
+
+    fn config(low_below: u64, critical_below: u64) -> DiskSpaceMonitorConfig {
+        DiskSpaceMonitorConfig {
+            check_interval: Duration::from_secs(60),
+            low_below: MemorySize::Bytes(NonZeroU64::new(low_below).unwrap()),
+            critical_below: MemorySize::Bytes(NonZeroU64::new(critical_below).unwrap()),
+        }
+    }
+
+    #[test]
+    fn level_of_available_space() {
+        let config = config(1_000, 100);
+        assert_eq!(DiskSpaceLevel::Critical, config.level(0));
+        assert_eq!(DiskSpaceLevel::Critical, config.level(99));
+        assert_eq!(DiskSpaceLevel::Low, config.level(100));
+        assert_eq!(DiskSpaceLevel::Low, config.level(999));
+        assert_eq!(DiskSpaceLevel::Sufficient, config.level(1_000));
+    }
+
+    #[test]
+    fn report_level_changes_periodically() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        // No disk is that large
+        let mut monitor =
+            DiskSpaceMonitor::new(temp_dir.path().to_path_buf(), config(u64::MAX, u64::MAX));
+        assert_eq!(DiskSpaceLevel::Sufficient, monitor.level());
+        assert!(monitor.last_disk_space().is_none());
+
+        let now = Instant::now();
+        let event = monitor.check(now).unwrap().unwrap();
+        assert_eq!(DiskSpaceLevel::Sufficient, event.previous_level);
+        assert_eq!(DiskSpaceLevel::Critical, event.level);
+        assert_eq!(Some(event.disk_space), monitor.last_disk_space());
+
+        // Not due yet
+        monitor.config.critical_below = MemorySize::Bytes(NonZeroU64::MIN);
+        assert!(monitor
+            .check(now + Duration::from_secs(59))
+            .unwrap()
+            .is_none());
+        assert_eq!(DiskSpaceLevel::Critical, monitor.level());
+
+        let event = monitor
+            .check(now + Duration::from_secs(60))
+            .unwrap()
+            .unwrap();
+        assert_eq!(DiskSpaceLevel::Critical, event.previous_level);
+        assert_eq!(DiskSpaceLevel::Low, event.level);
+
+        // Unchanged
+        assert!(monitor
+            .check(now + Duration::from_secs(120))
+            .unwrap()
+            .is_none());
+    }
+}
@@ -17,6 +17,10 @@ use crate::{
     time::{Interval, SystemInstant},
 };
 
+pub use crate::fs::policy::DurabilityPolicy;
+
+pub mod disk_space;
+
 // TODO: Currently unused
 pub mod field;
 
@@ -34,6 +38,19 @@ pub enum Error {
 
     #[error(transparent)]
     Other(#[from] anyhow::Error),
+
+    #[error(transparent)]
+    LockDir(crate::fs::LockDirError),
+}
+
+impl From<crate::fs::LockDirError> for Error {
+    fn from(err: crate::fs::LockDirError) -> Self {
+        use crate::fs::LockDirError::*;
+        match err {
+            Io(err) => Error::Io(err),
+            err @ InUse { .. } => Error::LockDir(err),
+        }
+    }
 }
 
 #[cfg(feature = "csv-storage")]
@@ -93,6 +110,7 @@ pub enum MemorySize {
 pub struct StorageConfig {
     pub retention_time: TimeInterval,
     pub segmentation: StorageSegmentConfig,
+    pub durability: DurabilityPolicy,
 }
 
 #[derive(Debug, Clone, Eq, PartialEq)]
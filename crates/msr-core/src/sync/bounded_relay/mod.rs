@@ -0,0 +1,185 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use crate::sync::{Condvar, Mutex};
+
+/// How to handle new values if a [`BoundedRelay`] is full
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverflowPolicy {
+    /// Drop the oldest value to make room for the new value
+    Overwrite,
+
+    /// Reject the new value
+    Reject,
+}
+
+/// Move multiple values between threads
+///
+/// In contrast to a [`Relay`](super::Relay) that holds only a single
+/// value, up to a fixed number of values are buffered in order until
+/// consumers are ready to take them. The [`OverflowPolicy`] decides
+/// what happens if producers keep up faster than consumers.
+///
+/// Each value can be consumed at most once.
+#[derive(Debug)]
+pub struct BoundedRelay<T> {
+    mutex: Mutex<VecDeque<T>>,
+    condvar: Condvar,
+    capacity: usize,
+    overflow_policy: OverflowPolicy,
+}
+
+impl<T> BoundedRelay<T> {
+    /// Create a new, empty relay
+    ///
+    /// # Panics
+    ///
+    /// Panics if the capacity is 0.
+    #[must_use]
+    pub fn new(capacity: usize, overflow_policy: OverflowPolicy) -> Self {
+        assert!(capacity > 0, "zero capacity");
+        Self {
+            mutex: Mutex::new(VecDeque::with_capacity(capacity)),
+            condvar: Condvar::new(),
+            capacity,
+            overflow_policy,
+        }
+    }
+
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    #[must_use]
+    pub const fn overflow_policy(&self) -> OverflowPolicy {
+        self.overflow_policy
+    }
+
+    /// The number of buffered values
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn len(&self) -> usize {
+        self.mutex.lock().expect("not poisoned").len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn push(&self, value: T) -> Result<Option<T>, T> {
+        let mut guard = self.mutex.lock().expect("not poisoned");
+        let overwritten = if guard.len() < self.capacity {
+            None
+        } else {
+            match self.overflow_policy {
+                OverflowPolicy::Overwrite => guard.pop_front(),
+                OverflowPolicy::Reject => return Err(value),
+            }
+        };
+        guard.push_back(value);
+        Ok(overwritten)
+    }
+
+    /// Append a value and notify a single waiting consumer
+    ///
+    /// Returns the oldest value if it has been overwritten or
+    /// the new value as an error if it has been rejected.
+    pub fn push_notify_one(&self, value: T) -> Result<Option<T>, T> {
+        let overwritten = self.push(value)?;
+        self.condvar.notify_one();
+        Ok(overwritten)
+    }
+
+    /// Append a value and notify all waiting consumers
+    ///
+    /// Returns the oldest value if it has been overwritten or
+    /// the new value as an error if it has been rejected.
+    pub fn push_notify_all(&self, value: T) -> Result<Option<T>, T> {
+        let overwritten = self.push(value)?;
+        self.condvar.notify_all();
+        Ok(overwritten)
+    }
+
+    /// Take the oldest value immediately
+    ///
+    /// Returns the oldest value or `None`.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn take(&self) -> Option<T> {
+        self.mutex.lock().expect("not poisoned").pop_front()
+    }
+
+    /// Take all values immediately
+    ///
+    /// Returns all values in order, starting with the oldest value.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn take_all(&self) -> Vec<T> {
+        self.mutex.lock().expect("not poisoned").drain(..).collect()
+    }
+
+    /// Wait for a value and then take it
+    ///
+    /// Returns the oldest value.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn wait(&self) -> T {
+        let mut guard = self.mutex.lock().expect("not poisoned");
+        // The loop is required to handle spurious wakeups
+        loop {
+            if let Some(value) = guard.pop_front() {
+                return value;
+            }
+            guard = self.condvar.wait(guard).expect("not poisoned");
+        }
+    }
+
+    /// Wait for a value with a timeout and then take it
+    ///
+    /// Returns the oldest value if available or `None` if the timeout
+    /// expired.
+    pub fn wait_for(&self, timeout: Duration) -> Option<T> {
+        // Handle edge case separately
+        if timeout.is_zero() {
+            return self.take();
+        }
+        if let Some(deadline) = Instant::now().checked_add(timeout) {
+            self.wait_until(deadline)
+        } else {
+            // Wait without a deadline if the result cannot be represented
+            // by an Instant
+            Some(self.wait())
+        }
+    }
+
+    /// Wait for a value until a deadline and then take it
+    ///
+    /// Returns the oldest value if available or `None` if the deadline
+    /// expired.
+    #[allow(clippy::missing_panics_doc)]
+    pub fn wait_until(&self, deadline: Instant) -> Option<T> {
+        let mut guard = self.mutex.lock().expect("not poisoned");
+        // The loop is required to handle spurious wakeups
+        while guard.is_empty() {
+            let now = Instant::now();
+            if now >= deadline {
+                break;
+            }
+            let timeout = deadline.duration_since(now);
+            let (replaced_guard, wait_result) = self
+                .condvar
+                .wait_timeout(guard, timeout)
+                .expect("not poisoned");
+            guard = replaced_guard;
+            if wait_result.timed_out() {
+                break;
+            }
+            // Continue on spurious wakeup
+        }
+        guard.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests;
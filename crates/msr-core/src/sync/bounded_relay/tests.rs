@@ -0,0 +1,54 @@
+use std::thread;
+
+use crate::sync::Arc;
+
+use super::*;
+
+#[test]
+fn overwrite_oldest_values() {
+    let relay = BoundedRelay::new(2, OverflowPolicy::Overwrite);
+    assert_eq!(Ok(None), relay.push_notify_one(1));
+    assert_eq!(Ok(None), relay.push_notify_one(2));
+    assert_eq!(Ok(Some(1)), relay.push_notify_one(3));
+    assert_eq!(Ok(Some(2)), relay.push_notify_all(4));
+    assert_eq!(2, relay.len());
+    assert_eq!(Some(3), relay.take());
+    assert_eq!(Some(4), relay.wait_for(Duration::ZERO));
+    assert!(relay.is_empty());
+}
+
+#[test]
+fn reject_new_values() {
+    let relay = BoundedRelay::new(2, OverflowPolicy::Reject);
+    assert_eq!(Ok(None), relay.push_notify_one(1));
+    assert_eq!(Ok(None), relay.push_notify_one(2));
+    assert_eq!(Err(3), relay.push_notify_one(3));
+    assert_eq!(vec![1, 2], relay.take_all());
+    assert!(relay.take_all().is_empty());
+}
+
+#[test]
+fn wait_for_timeout_empty() {
+    let relay = BoundedRelay::<()>::new(1, OverflowPolicy::Reject);
+    assert_eq!(None, relay.wait_for(Duration::ZERO));
+    assert_eq!(None, relay.wait_for(Duration::from_millis(1)));
+    assert_eq!(None, relay.wait_until(Instant::now()));
+}
+
+#[test]
+fn transfer_all_values_between_threads() {
+    const COUNT: usize = 1_000;
+    let relay = Arc::new(BoundedRelay::new(8, OverflowPolicy::Reject));
+    let consumer = thread::spawn({
+        let relay = Arc::clone(&relay);
+        move || (0..COUNT).map(|_| relay.wait()).collect::<Vec<_>>()
+    });
+    for value in 0..COUNT {
+        let mut value = value;
+        while let Err(rejected) = relay.push_notify_one(value) {
+            value = rejected;
+            thread::yield_now();
+        }
+    }
+    assert_eq!((0..COUNT).collect::<Vec<_>>(), consumer.join().unwrap());
+}
@@ -1,8 +1,20 @@
 pub mod atomic;
 
+pub mod bounded_relay;
+pub use self::bounded_relay::{BoundedRelay, OverflowPolicy};
+
 pub mod relay;
 pub use self::relay::Relay;
 
+pub mod ring_buffer;
+pub use self::ring_buffer::{ring_buffer, RingBufferConsumer, RingBufferProducer};
+
+pub mod triple_buffer;
+pub use self::triple_buffer::{triple_buffer, TripleBufferReader, TripleBufferWriter};
+
+pub mod wait_group;
+pub use self::wait_group::WaitGroup;
+
 // loom doesn't provide a drop-in replacement for std::sync::Weak,
 // only for std::sync::Arc. Unfortunately, both are needed.
 #[allow(unused_imports)]
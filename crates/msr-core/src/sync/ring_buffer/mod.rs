@@ -0,0 +1,271 @@
+use std::{cell::UnsafeCell, mem::MaybeUninit};
+
+use crate::sync::{
+    atomic::{AtomicU64, AtomicUsize, Ordering},
+    Arc,
+};
+
+/// Storage shared by producer and consumer
+///
+/// The positions are increasing monotonically and wrap around on
+/// overflow. The slot of a position is determined by masking the
+/// lower bits, which requires that the capacity is a power of 2.
+#[derive(Debug)]
+struct Shared<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+
+    /// The position of the next slot to read, only written by the consumer
+    head: AtomicUsize,
+
+    /// The position of the next slot to write, only written by the producer
+    tail: AtomicUsize,
+
+    overflow_count: AtomicU64,
+}
+
+// SAFETY: Each slot is accessed by either the producer or the consumer,
+// never by both at the same time. The ownership of a slot is handed over
+// by the acquire/release semantics of the head and tail positions.
+#[allow(unsafe_code)]
+unsafe impl<T: Send> Send for Shared<T> {}
+
+// SAFETY: See above
+#[allow(unsafe_code)]
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+impl<T> Shared<T> {
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn slot(&self, pos: usize) -> &UnsafeCell<MaybeUninit<T>> {
+        &self.slots[pos & (self.capacity() - 1)]
+    }
+
+    fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    fn overflow_count(&self) -> u64 {
+        self.overflow_count.load(Ordering::Relaxed)
+    }
+
+    fn add_overflow_count(&self, count: usize) {
+        if count > 0 {
+            self.overflow_count
+                .fetch_add(count as u64, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<T> Drop for Shared<T> {
+    #[allow(unsafe_code)]
+    fn drop(&mut self) {
+        if !std::mem::needs_drop::<T>() {
+            return;
+        }
+        let tail = self.tail.load(Ordering::Acquire);
+        let mut pos = self.head.load(Ordering::Acquire);
+        while pos != tail {
+            // SAFETY: All slots between head and tail are initialized
+            unsafe { self.slot(pos).get().cast::<T>().drop_in_place() };
+            pos = pos.wrapping_add(1);
+        }
+    }
+}
+
+/// Create a single-producer/single-consumer ring buffer
+///
+/// All memory is allocated once upfront. Neither pushing nor
+/// popping values allocates or blocks and both could safely be
+/// invoked in a real-time context.
+///
+/// Values that don't fit into the buffer are rejected and counted
+/// as overflows.
+///
+/// The capacity is rounded up to the next power of 2.
+///
+/// # Panics
+///
+/// Panics if the capacity is 0 or too large.
+#[must_use]
+pub fn ring_buffer<T>(min_capacity: usize) -> (RingBufferProducer<T>, RingBufferConsumer<T>) {
+    assert!(min_capacity > 0, "zero capacity");
+    let capacity = min_capacity
+        .checked_next_power_of_two()
+        .expect("capacity too large");
+    let slots = std::iter::repeat_with(|| UnsafeCell::new(MaybeUninit::uninit()))
+        .take(capacity)
+        .collect();
+    let shared = Arc::new(Shared {
+        slots,
+        head: AtomicUsize::new(0),
+        tail: AtomicUsize::new(0),
+        overflow_count: AtomicU64::new(0),
+    });
+    let producer = RingBufferProducer {
+        shared: Arc::clone(&shared),
+    };
+    let consumer = RingBufferConsumer { shared };
+    (producer, consumer)
+}
+
+/// Writing side of a ring buffer (lock-free)
+#[derive(Debug)]
+pub struct RingBufferProducer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> RingBufferProducer<T> {
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// The number of values that are ready to be popped
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of values that have been rejected
+    #[must_use]
+    pub fn overflow_count(&self) -> u64 {
+        self.shared.overflow_count()
+    }
+
+    fn vacant_len(&self, tail: usize) -> usize {
+        let head = self.shared.head.load(Ordering::Acquire);
+        self.capacity() - tail.wrapping_sub(head)
+    }
+
+    /// Write a value into the next slot
+    ///
+    /// Returns the value back if the buffer is full.
+    #[allow(unsafe_code)]
+    pub fn try_push(&mut self, value: T) -> Result<(), T> {
+        let tail = self.shared.tail.load(Ordering::Relaxed);
+        if self.vacant_len(tail) == 0 {
+            self.shared.add_overflow_count(1);
+            return Err(value);
+        }
+        // SAFETY: The slot is vacant and not accessed by the consumer
+        unsafe { (*self.shared.slot(tail).get()).write(value) };
+        self.shared
+            .tail
+            .store(tail.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Write as many values as possible
+    ///
+    /// Returns the number of values that have been written. All
+    /// remaining values are rejected.
+    #[allow(unsafe_code)]
+    pub fn push_slice(&mut self, values: &[T]) -> usize
+    where
+        T: Copy,
+    {
+        let tail = self.shared.tail.load(Ordering::Relaxed);
+        let count = values.len().min(self.vacant_len(tail));
+        for (offset, value) in values[..count].iter().enumerate() {
+            // SAFETY: The slot is vacant and not accessed by the consumer
+            unsafe { (*self.shared.slot(tail.wrapping_add(offset)).get()).write(*value) };
+        }
+        self.shared
+            .tail
+            .store(tail.wrapping_add(count), Ordering::Release);
+        self.shared.add_overflow_count(values.len() - count);
+        count
+    }
+}
+
+/// Reading side of a ring buffer (lock-free)
+#[derive(Debug)]
+pub struct RingBufferConsumer<T> {
+    shared: Arc<Shared<T>>,
+}
+
+impl<T> RingBufferConsumer<T> {
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.shared.capacity()
+    }
+
+    /// The number of values that are ready to be popped
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.shared.len()
+    }
+
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of values that have been rejected
+    #[must_use]
+    pub fn overflow_count(&self) -> u64 {
+        self.shared.overflow_count()
+    }
+
+    /// Reset the number of rejected values
+    ///
+    /// Returns the number of values that have been rejected since
+    /// the last reset.
+    pub fn reset_overflow_count(&mut self) -> u64 {
+        self.shared.overflow_count.swap(0, Ordering::Relaxed)
+    }
+
+    fn occupied_len(&self, head: usize) -> usize {
+        let tail = self.shared.tail.load(Ordering::Acquire);
+        tail.wrapping_sub(head)
+    }
+
+    /// Take the value from the next slot
+    #[allow(unsafe_code)]
+    pub fn pop(&mut self) -> Option<T> {
+        let head = self.shared.head.load(Ordering::Relaxed);
+        if self.occupied_len(head) == 0 {
+            return None;
+        }
+        // SAFETY: The slot is occupied and not accessed by the producer
+        let value = unsafe { (*self.shared.slot(head).get()).assume_init_read() };
+        self.shared
+            .head
+            .store(head.wrapping_add(1), Ordering::Release);
+        Some(value)
+    }
+
+    /// Take as many values as possible
+    ///
+    /// Returns the number of values that have been copied into the
+    /// beginning of the buffer.
+    #[allow(unsafe_code)]
+    pub fn pop_slice(&mut self, buffer: &mut [T]) -> usize
+    where
+        T: Copy,
+    {
+        let head = self.shared.head.load(Ordering::Relaxed);
+        let count = buffer.len().min(self.occupied_len(head));
+        for (offset, value) in buffer[..count].iter_mut().enumerate() {
+            // SAFETY: The slot is occupied and not accessed by the producer
+            *value =
+                unsafe { (*self.shared.slot(head.wrapping_add(offset)).get()).assume_init_read() };
+        }
+        self.shared
+            .head
+            .store(head.wrapping_add(count), Ordering::Release);
+        count
+    }
+}
+
+#[cfg(test)]
+mod tests;
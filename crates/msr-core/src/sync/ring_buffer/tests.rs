@@ -0,0 +1,115 @@
+use std::thread;
+
+use super::*;
+
+#[test]
+fn push_pop_with_wrap_around() {
+    let (mut tx, mut rx) = ring_buffer(3);
+    assert_eq!(4, tx.capacity());
+    assert!(rx.is_empty());
+    assert_eq!(None, rx.pop());
+    for value in 0..10 {
+        assert_eq!(Ok(()), tx.try_push(value));
+        assert_eq!(Ok(()), tx.try_push(value + 100));
+        assert_eq!(Ok(()), tx.try_push(value + 200));
+        assert_eq!(3, rx.len());
+        assert_eq!(Some(value), rx.pop());
+        assert_eq!(Some(value + 100), rx.pop());
+        assert_eq!(Some(value + 200), rx.pop());
+        assert!(tx.is_empty());
+    }
+    assert_eq!(0, rx.overflow_count());
+}
+
+#[test]
+fn count_overflows() {
+    let (mut tx, mut rx) = ring_buffer(2);
+    assert_eq!(Ok(()), tx.try_push(1));
+    assert_eq!(Ok(()), tx.try_push(2));
+    assert_eq!(Err(3), tx.try_push(3));
+    assert_eq!(1, tx.overflow_count());
+    assert_eq!(Some(1), rx.pop());
+    assert_eq!(Ok(()), tx.try_push(4));
+    assert_eq!(Some(2), rx.pop());
+    assert_eq!(Some(4), rx.pop());
+    assert_eq!(1, rx.reset_overflow_count());
+    assert_eq!(0, tx.overflow_count());
+}
+
+#[test]
+fn push_pop_slices() {
+    let (mut tx, mut rx) = ring_buffer(4);
+    assert_eq!(3, tx.push_slice(&[1, 2, 3]));
+    assert_eq!(1, tx.push_slice(&[4, 5, 6]));
+    assert_eq!(2, tx.overflow_count());
+
+    let mut buffer = [0; 3];
+    assert_eq!(3, rx.pop_slice(&mut buffer));
+    assert_eq!([1, 2, 3], buffer);
+
+    // Wrap around
+    assert_eq!(3, tx.push_slice(&[7, 8, 9]));
+    let mut buffer = [0; 8];
+    assert_eq!(4, rx.pop_slice(&mut buffer));
+    assert_eq!([4, 7, 8, 9], buffer[..4]);
+    assert_eq!(0, rx.pop_slice(&mut buffer));
+}
+
+#[test]
+fn wrap_around_positions() {
+    let (mut tx, mut rx) = ring_buffer(2);
+    let start = usize::MAX - 2;
+    tx.shared.head.store(start, Ordering::Relaxed);
+    tx.shared.tail.store(start, Ordering::Relaxed);
+    for value in 0..5 {
+        assert_eq!(Ok(()), tx.try_push(value));
+        assert_eq!(Ok(()), tx.try_push(value + 100));
+        assert_eq!(Err(0), tx.try_push(0));
+        assert_eq!(Some(value), rx.pop());
+        assert_eq!(Some(value + 100), rx.pop());
+        assert_eq!(None, rx.pop());
+    }
+}
+
+#[test]
+fn drop_remaining_values() {
+    let value = Arc::new(());
+    let (mut tx, mut rx) = ring_buffer(4);
+    for _ in 0..3 {
+        assert!(tx.try_push(Arc::clone(&value)).is_ok());
+    }
+    drop(rx.pop());
+    assert_eq!(3, Arc::strong_count(&value));
+    drop(tx);
+    drop(rx);
+    assert_eq!(1, Arc::strong_count(&value));
+}
+
+#[test]
+fn transfer_between_threads() {
+    const COUNT: u64 = 100_000;
+    let (mut tx, mut rx) = ring_buffer(16);
+    let producer = thread::spawn(move || {
+        for value in 0..COUNT {
+            let mut value = value;
+            while let Err(rejected) = tx.try_push(value) {
+                value = rejected;
+                thread::yield_now();
+            }
+        }
+        tx.overflow_count()
+    });
+    let mut expected = 0;
+    while expected < COUNT {
+        match rx.pop() {
+            Some(value) => {
+                assert_eq!(expected, value);
+                expected += 1;
+            }
+            None => thread::yield_now(),
+        }
+    }
+    let overflow_count = producer.join().unwrap();
+    assert_eq!(overflow_count, rx.overflow_count());
+    assert!(rx.is_empty());
+}
@@ -0,0 +1,149 @@
+use std::cell::UnsafeCell;
+
+use crate::sync::{
+    atomic::{AtomicU8, Ordering},
+    Arc,
+};
+
+/// Flags the back buffer as containing a value that has not been read yet
+const DIRTY_FLAG: u8 = 0b100;
+
+const INDEX_MASK: u8 = 0b011;
+
+/// Storage shared by writer and reader
+///
+/// The writer and the reader each own one of the three buffers
+/// exclusively. The remaining back buffer is exchanged atomically.
+#[derive(Debug)]
+struct Shared<T> {
+    buffers: [UnsafeCell<T>; 3],
+
+    /// The index of the back buffer combined with the [`DIRTY_FLAG`]
+    back: AtomicU8,
+}
+
+// SAFETY: Each buffer is owned by either the writer or the reader
+// or is the back buffer that is not accessed at all. The ownership
+// is handed over by swapping the back buffer index atomically.
+#[allow(unsafe_code)]
+unsafe impl<T: Send> Send for Shared<T> {}
+
+// SAFETY: See above
+#[allow(unsafe_code)]
+unsafe impl<T: Send> Sync for Shared<T> {}
+
+/// Create a triple buffer for handing over the latest value
+///
+/// A single writer publishes values that are picked up by a single
+/// reader. Intermediate values are overwritten if the reader doesn't
+/// keep up with the writer.
+///
+/// Both writing and reading are wait-free, i.e. neither blocks nor
+/// allocates, and could safely be invoked in a real-time context.
+/// In contrast to a [`Relay`](super::Relay) the writer never needs
+/// to acquire a lock, but the reader could not wait for new values.
+#[must_use]
+pub fn triple_buffer<T>(initial_value: T) -> (TripleBufferWriter<T>, TripleBufferReader<T>)
+where
+    T: Clone,
+{
+    let shared = Arc::new(Shared {
+        buffers: [
+            UnsafeCell::new(initial_value.clone()),
+            UnsafeCell::new(initial_value.clone()),
+            UnsafeCell::new(initial_value),
+        ],
+        back: AtomicU8::new(2),
+    });
+    let writer = TripleBufferWriter {
+        shared: Arc::clone(&shared),
+        index: 0,
+    };
+    let reader = TripleBufferReader { shared, index: 1 };
+    (writer, reader)
+}
+
+/// Writing side of a triple buffer (wait-free)
+#[derive(Debug)]
+pub struct TripleBufferWriter<T> {
+    shared: Arc<Shared<T>>,
+    index: u8,
+}
+
+impl<T> TripleBufferWriter<T> {
+    /// Access the input buffer for updating it in place
+    ///
+    /// The buffer contains the value that has been published before
+    /// the previous one. Modifications are only visible to the reader
+    /// after publishing them.
+    #[allow(unsafe_code)]
+    pub fn input_mut(&mut self) -> &mut T {
+        // SAFETY: The input buffer is owned by the writer
+        unsafe { &mut *self.shared.buffers[usize::from(self.index)].get() }
+    }
+
+    /// Publish the input buffer
+    pub fn publish(&mut self) {
+        let back = self
+            .shared
+            .back
+            .swap(self.index | DIRTY_FLAG, Ordering::AcqRel);
+        self.index = back & INDEX_MASK;
+    }
+
+    /// Replace the input buffer and publish it
+    pub fn write(&mut self, value: T) {
+        *self.input_mut() = value;
+        self.publish();
+    }
+
+    /// Check if the last published value has been picked up by the reader
+    #[must_use]
+    pub fn is_consumed(&self) -> bool {
+        self.shared.back.load(Ordering::Relaxed) & DIRTY_FLAG == 0
+    }
+}
+
+/// Reading side of a triple buffer (wait-free)
+#[derive(Debug)]
+pub struct TripleBufferReader<T> {
+    shared: Arc<Shared<T>>,
+    index: u8,
+}
+
+impl<T> TripleBufferReader<T> {
+    /// Check for a new value that has not been read yet
+    #[must_use]
+    pub fn has_update(&self) -> bool {
+        self.shared.back.load(Ordering::Relaxed) & DIRTY_FLAG != 0
+    }
+
+    /// Pick up the latest published value
+    ///
+    /// Returns `true` if a new value is available.
+    pub fn update(&mut self) -> bool {
+        if !self.has_update() {
+            return false;
+        }
+        let back = self.shared.back.swap(self.index, Ordering::AcqRel);
+        self.index = back & INDEX_MASK;
+        true
+    }
+
+    /// Access the value that has been picked up last
+    #[must_use]
+    #[allow(unsafe_code)]
+    pub fn output(&self) -> &T {
+        // SAFETY: The output buffer is owned by the reader
+        unsafe { &*self.shared.buffers[usize::from(self.index)].get() }
+    }
+
+    /// Pick up and access the latest published value
+    pub fn read(&mut self) -> &T {
+        self.update();
+        self.output()
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,65 @@
+use std::thread;
+
+use super::*;
+
+#[test]
+fn read_initial_value() {
+    let (writer, mut reader) = triple_buffer(1);
+    assert!(writer.is_consumed());
+    assert!(!reader.has_update());
+    assert_eq!(1, *reader.read());
+}
+
+#[test]
+fn read_latest_value() {
+    let (mut writer, mut reader) = triple_buffer(0);
+    writer.write(1);
+    assert!(!writer.is_consumed());
+    assert!(reader.has_update());
+    writer.write(2);
+    writer.write(3);
+    assert_eq!(3, *reader.read());
+    assert!(writer.is_consumed());
+    assert!(!reader.update());
+    assert_eq!(3, *reader.output());
+
+    // The reader keeps its value while the writer continues
+    writer.write(4);
+    assert_eq!(3, *reader.output());
+    assert_eq!(4, *reader.read());
+}
+
+#[test]
+fn update_in_place() {
+    let (mut writer, mut reader) = triple_buffer(vec![0; 3]);
+    writer.input_mut()[0] = 1;
+    writer.publish();
+    assert_eq!(&[1, 0, 0], reader.read().as_slice());
+
+    // The input buffer contains an outdated value
+    let input = writer.input_mut();
+    input.clear();
+    input.extend([2, 2]);
+    writer.publish();
+    assert_eq!(&[2, 2], reader.read().as_slice());
+}
+
+#[test]
+fn read_consistent_values_concurrently() {
+    const COUNT: u64 = 10_000;
+    let (mut writer, mut reader) = triple_buffer([0u64; 8]);
+    let writer = thread::spawn(move || {
+        for value in 1..=COUNT {
+            writer.write([value; 8]);
+        }
+    });
+    let mut last_value = 0;
+    while last_value < COUNT {
+        let values = *reader.read();
+        // Never torn and never outdated
+        assert!(values.iter().all(|value| *value == values[0]));
+        assert!(values[0] >= last_value);
+        last_value = values[0];
+    }
+    writer.join().unwrap();
+}
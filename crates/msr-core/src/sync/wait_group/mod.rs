@@ -0,0 +1,122 @@
+use std::time::{Duration, Instant};
+
+use crate::sync::{Arc, Condvar, Mutex};
+
+#[derive(Debug, Default)]
+struct Inner {
+    count: Mutex<usize>,
+    condvar: Condvar,
+}
+
+/// Wait until a number of participants have signaled
+///
+/// A count-down latch, e.g. for blocking a startup sequence until
+/// all workers or plugins have signaled their readiness. Clones
+/// share the same counter.
+///
+/// The counter could be increased again after it has reached zero,
+/// which allows reusing the wait group.
+#[derive(Debug, Clone, Default)]
+pub struct WaitGroup {
+    inner: Arc<Inner>,
+}
+
+impl WaitGroup {
+    /// Create a new wait group for the given number of participants
+    #[must_use]
+    pub fn new(count: usize) -> Self {
+        let wait_group = Self::default();
+        wait_group.add(count);
+        wait_group
+    }
+
+    /// The number of pending participants
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn count(&self) -> usize {
+        *self.inner.count.lock().expect("not poisoned")
+    }
+
+    /// Add more participants
+    #[allow(clippy::missing_panics_doc)]
+    pub fn add(&self, count: usize) {
+        let mut guard = self.inner.count.lock().expect("not poisoned");
+        *guard = guard.saturating_add(count);
+    }
+
+    /// Signal that a participant is done
+    ///
+    /// Notifies all waiting threads when the last participant is done.
+    /// Surplus signals are ignored.
+    ///
+    /// Returns the number of pending participants.
+    #[allow(clippy::must_use_candidate)]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn done(&self) -> usize {
+        let mut guard = self.inner.count.lock().expect("not poisoned");
+        if *guard == 0 {
+            return 0;
+        }
+        *guard -= 1;
+        let count = *guard;
+        drop(guard);
+        if count == 0 {
+            self.inner.condvar.notify_all();
+        }
+        count
+    }
+
+    /// Wait until all participants are done (blocking)
+    #[allow(clippy::missing_panics_doc)]
+    pub fn wait(&self) {
+        let mut guard = self.inner.count.lock().expect("not poisoned");
+        // The loop is required to handle spurious wakeups
+        while *guard > 0 {
+            guard = self.inner.condvar.wait(guard).expect("not poisoned");
+        }
+    }
+
+    /// Wait until all participants are done with a timeout (blocking)
+    ///
+    /// Returns `true` if all participants are done or `false` if
+    /// the timeout expired.
+    #[must_use]
+    pub fn wait_for(&self, timeout: Duration) -> bool {
+        if let Some(deadline) = Instant::now().checked_add(timeout) {
+            self.wait_until(deadline)
+        } else {
+            // Wait without a deadline if the result cannot be represented
+            // by an Instant
+            self.wait();
+            true
+        }
+    }
+
+    /// Wait until all participants are done with a deadline (blocking)
+    ///
+    /// Returns `true` if all participants are done or `false` if
+    /// the deadline expired.
+    #[must_use]
+    #[allow(clippy::missing_panics_doc)]
+    pub fn wait_until(&self, deadline: Instant) -> bool {
+        let mut guard = self.inner.count.lock().expect("not poisoned");
+        // The loop is required to handle spurious wakeups
+        while *guard > 0 {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            let timeout = deadline.duration_since(now);
+            let (replaced_guard, _) = self
+                .inner
+                .condvar
+                .wait_timeout(guard, timeout)
+                .expect("not poisoned");
+            guard = replaced_guard;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,50 @@
+use std::thread;
+
+use super::*;
+
+#[test]
+fn count_down() {
+    let wait_group = WaitGroup::new(2);
+    assert_eq!(2, wait_group.count());
+    assert!(!wait_group.wait_for(Duration::ZERO));
+    assert_eq!(1, wait_group.done());
+    assert!(!wait_group.wait_until(Instant::now()));
+    assert_eq!(0, wait_group.clone().done());
+    assert!(wait_group.wait_for(Duration::ZERO));
+    wait_group.wait();
+
+    // Surplus signals are ignored
+    assert_eq!(0, wait_group.done());
+    assert_eq!(0, wait_group.count());
+
+    // Reuse
+    wait_group.add(1);
+    assert!(!wait_group.wait_for(Duration::ZERO));
+}
+
+#[test]
+fn empty_wait_group_does_not_block() {
+    let wait_group = WaitGroup::default();
+    wait_group.wait();
+    assert!(wait_group.wait_for(Duration::MAX));
+}
+
+#[test]
+fn wait_for_threads() {
+    const THREADS: usize = 4;
+    let wait_group = WaitGroup::new(THREADS);
+    let threads: Vec<_> = (0..THREADS)
+        .map(|_| {
+            let wait_group = wait_group.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(1));
+                wait_group.done();
+            })
+        })
+        .collect();
+    assert!(wait_group.wait_for(Duration::from_secs(10)));
+    assert_eq!(0, wait_group.count());
+    for thread in threads {
+        thread.join().unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+use std::time::{Duration, Instant};
+
+use crate::thread;
+
+/// A single tick of a [`CyclicTimer`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CyclicTick {
+    /// The number of the tick, i.e. `n` in `start + n * period`
+    pub index: u64,
+
+    /// The nominal time of the tick
+    pub deadline: Instant,
+
+    /// The time when the tick has been observed
+    pub actual: Instant,
+
+    /// The number of ticks that have been missed since the previous tick
+    pub missed: u64,
+}
+
+impl CyclicTick {
+    /// Deviation of the actual from the nominal time of the tick
+    ///
+    /// The jitter is always less than the period of the timer,
+    /// because missed ticks are skipped.
+    #[must_use]
+    pub fn jitter(&self) -> Duration {
+        self.actual.duration_since(self.deadline)
+    }
+}
+
+/// Statistics of a [`CyclicTimer`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CyclicTimerStats {
+    /// Number of observed ticks
+    pub ticks: u64,
+
+    /// Total number of missed ticks
+    pub missed_ticks: u64,
+
+    /// Jitter of the most recent tick
+    pub last_jitter: Duration,
+
+    /// Maximum jitter of all ticks
+    pub max_jitter: Duration,
+
+    /// Sum of the jitter of all ticks
+    pub jitter_sum: Duration,
+}
+
+impl CyclicTimerStats {
+    /// Average jitter of all ticks
+    #[must_use]
+    pub fn avg_jitter(&self) -> Option<Duration> {
+        if self.ticks == 0 {
+            return None;
+        }
+        let avg_nanos = self.jitter_sum.as_nanos() / u128::from(self.ticks);
+        Some(Duration::from_nanos(avg_nanos as u64))
+    }
+
+    fn update(&mut self, tick: &CyclicTick) {
+        let jitter = tick.jitter();
+        self.ticks += 1;
+        self.missed_ticks += tick.missed;
+        self.last_jitter = jitter;
+        self.max_jitter = self.max_jitter.max(jitter);
+        self.jitter_sum += jitter;
+    }
+}
+
+/// Drift-compensated cyclic timer
+///
+/// The ticks are scheduled at absolute times `start + n * period`
+/// instead of sleeping for a relative duration after each cycle.
+/// Delays of individual ticks don't accumulate and the timer never
+/// drifts from its nominal schedule.
+///
+/// Ticks that have been missed entirely, i.e. if a cycle took
+/// longer than the period, are skipped and counted.
+#[derive(Debug, Clone)]
+pub struct CyclicTimer {
+    start: Instant,
+    period: Duration,
+    next_index: u64,
+    stats: CyclicTimerStats,
+}
+
+impl CyclicTimer {
+    /// Create a new timer with the first tick at `start`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the period is zero.
+    #[must_use]
+    pub fn new(start: Instant, period: Duration) -> Self {
+        assert!(period > Duration::ZERO, "period must not be zero");
+        Self {
+            start,
+            period,
+            next_index: 0,
+            stats: Default::default(),
+        }
+    }
+
+    /// Create a new timer with the first tick now
+    #[must_use]
+    pub fn start_now(period: Duration) -> Self {
+        Self::new(Instant::now(), period)
+    }
+
+    #[must_use]
+    pub const fn start(&self) -> Instant {
+        self.start
+    }
+
+    #[must_use]
+    pub const fn period(&self) -> Duration {
+        self.period
+    }
+
+    #[must_use]
+    pub const fn stats(&self) -> &CyclicTimerStats {
+        &self.stats
+    }
+
+    /// The nominal time of the tick with the given index
+    #[must_use]
+    pub fn deadline(&self, index: u64) -> Instant {
+        let offset_nanos = self.period.as_nanos().saturating_mul(u128::from(index));
+        let offset = Duration::new(
+            (offset_nanos / 1_000_000_000).min(u128::from(u64::MAX)) as u64,
+            (offset_nanos % 1_000_000_000) as u32,
+        );
+        self.start + offset
+    }
+
+    /// The nominal time of the next tick
+    #[must_use]
+    pub fn next_deadline(&self) -> Instant {
+        self.deadline(self.next_index)
+    }
+
+    /// Restart the timer with the first tick at `start`
+    ///
+    /// The statistics are reset.
+    pub fn restart(&mut self, start: Instant) {
+        *self = Self::new(start, self.period);
+    }
+
+    /// Observe the next tick
+    ///
+    /// Returns `None` if the next deadline has not been reached yet.
+    /// Otherwise the most recent tick is returned and all ticks
+    /// before it are skipped.
+    pub fn poll_tick(&mut self, now: Instant) -> Option<CyclicTick> {
+        if now < self.next_deadline() {
+            return None;
+        }
+        let elapsed_nanos = now.duration_since(self.start).as_nanos();
+        let elapsed_ticks = elapsed_nanos / self.period.as_nanos();
+        let index = elapsed_ticks.min(u128::from(u64::MAX)) as u64;
+        debug_assert!(index >= self.next_index);
+        let tick = CyclicTick {
+            index,
+            deadline: self.deadline(index),
+            actual: now,
+            missed: index - self.next_index,
+        };
+        self.next_index = index.saturating_add(1);
+        self.stats.update(&tick);
+        Some(tick)
+    }
+
+    /// Sleep until the next tick
+    ///
+    /// Puts the current thread to sleep until the next deadline
+    /// and then observes the tick.
+    pub fn wait_tick(&mut self) -> CyclicTick {
+        loop {
+            thread::sleep_until(self.next_deadline());
+            if let Some(tick) = self.poll_tick(Instant::now()) {
+                return tick;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,73 @@
+use super::*;
+
+const PERIOD: Duration = Duration::from_millis(10);
+
+#[test]
+fn deadlines_are_absolute() {
+    let start = Instant::now();
+    let timer = CyclicTimer::new(start, PERIOD);
+    assert_eq!(start, timer.next_deadline());
+    assert_eq!(start + 1_000 * PERIOD, timer.deadline(1_000));
+    let timer = CyclicTimer::new(start, Duration::from_nanos(333_333_333));
+    assert_eq!(
+        start + Duration::from_secs(333_333_333),
+        timer.deadline(1_000_000_000)
+    );
+}
+
+#[test]
+fn poll_ticks_without_drift() {
+    let start = Instant::now();
+    let mut timer = CyclicTimer::new(start, PERIOD);
+
+    let tick = timer.poll_tick(start + PERIOD / 2).unwrap();
+    assert_eq!(0, tick.index);
+    assert_eq!(0, tick.missed);
+    assert_eq!(PERIOD / 2, tick.jitter());
+
+    // The delay of the previous tick doesn't shift the next deadline
+    assert_eq!(start + PERIOD, timer.next_deadline());
+    assert!(timer.poll_tick(start + PERIOD * 99 / 100).is_none());
+
+    let tick = timer.poll_tick(start + PERIOD).unwrap();
+    assert_eq!(1, tick.index);
+    assert_eq!(Duration::ZERO, tick.jitter());
+
+    let stats = timer.stats();
+    assert_eq!(2, stats.ticks);
+    assert_eq!(0, stats.missed_ticks);
+    assert_eq!(Duration::ZERO, stats.last_jitter);
+    assert_eq!(PERIOD / 2, stats.max_jitter);
+    assert_eq!(Some(PERIOD / 4), stats.avg_jitter());
+}
+
+#[test]
+fn skip_missed_ticks() {
+    let start = Instant::now();
+    let mut timer = CyclicTimer::new(start, PERIOD);
+    assert!(timer.poll_tick(start).is_some());
+
+    let tick = timer.poll_tick(start + 3 * PERIOD + PERIOD / 4).unwrap();
+    assert_eq!(3, tick.index);
+    assert_eq!(2, tick.missed);
+    assert_eq!(start + 3 * PERIOD, tick.deadline);
+    assert_eq!(PERIOD / 4, tick.jitter());
+    assert_eq!(start + 4 * PERIOD, timer.next_deadline());
+    assert_eq!(2, timer.stats().missed_ticks);
+
+    timer.restart(start);
+    assert_eq!(start, timer.next_deadline());
+    assert_eq!(&CyclicTimerStats::default(), timer.stats());
+    assert_eq!(None, CyclicTimerStats::default().avg_jitter());
+}
+
+#[test]
+fn wait_ticks() {
+    let mut timer = CyclicTimer::start_now(Duration::from_millis(1));
+    for index in 0..3 {
+        let tick = timer.wait_tick();
+        assert!(tick.actual >= tick.deadline);
+        assert!(tick.index >= index);
+    }
+    assert_eq!(3, timer.stats().ticks);
+}
@@ -3,6 +3,7 @@
 use std::{
     fmt,
     ops::{Add, AddAssign, Deref, DerefMut, Sub, SubAssign},
+    str::FromStr,
     time::{Duration, Instant, SystemTime},
 };
 
@@ -10,6 +11,9 @@ use time::{
     error::IndeterminateOffset, format_description::well_known::Rfc3339, OffsetDateTime, UtcOffset,
 };
 
+mod cyclic;
+pub use self::cyclic::{CyclicTick, CyclicTimer, CyclicTimerStats};
+
 /// A system time with the corresponding instant.
 ///
 /// This should only be used for anchoring values of Instant
@@ -143,10 +147,49 @@ impl Timestamp {
         TimestampInner::now_utc().into()
     }
 
+    /// Convert into the given time zone offset
+    ///
+    /// The point in time is preserved.
+    #[must_use]
+    pub const fn to_offset(self, offset: UtcOffset) -> Self {
+        Self(self.to_inner().to_offset(offset))
+    }
+
+    /// Convert into the local time zone offset
+    ///
+    /// Returns the unmodified timestamp if the local time zone
+    /// offset is unknown or could not be determined.
+    #[must_use]
+    pub fn to_local(self) -> Self {
+        UtcOffset::local_offset_at(self.to_inner()).map_or(self, |offset| self.to_offset(offset))
+    }
+
+    /// Parse an RFC 3339 string, e.g. `2023-09-10T12:34:56.789+02:00`
+    ///
+    /// The time zone offset of the input is preserved.
     pub fn parse_rfc3339(input: &str) -> Result<Self, time::error::Parse> {
         TimestampInner::parse(input, &Rfc3339).map(Self::new)
     }
 
+    /// Parse an RFC 3339 string and convert it into UTC
+    pub fn parse_rfc3339_utc(input: &str) -> Result<Self, time::error::Parse> {
+        Self::parse_rfc3339(input).map(Self::to_utc)
+    }
+
+    /// Format as an RFC 3339 string with the time zone offset
+    /// of the timestamp
+    ///
+    /// Formatting fails for years outside of 0..=9999 and for
+    /// offsets with a seconds component.
+    pub fn to_rfc3339(&self) -> Result<String, time::error::Format> {
+        self.format_rfc3339()
+    }
+
+    /// Format as an RFC 3339 string in UTC, e.g. `2023-09-10T10:34:56.789Z`
+    pub fn to_rfc3339_utc(&self) -> Result<String, time::error::Format> {
+        self.to_utc().format_rfc3339()
+    }
+
     pub fn format_rfc3339(&self) -> Result<String, time::error::Format> {
         self.0.format(&Rfc3339)
     }
@@ -159,6 +202,14 @@ impl Timestamp {
     }
 }
 
+impl FromStr for Timestamp {
+    type Err = time::error::Parse;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_rfc3339(s)
+    }
+}
+
 impl From<TimestampInner> for Timestamp {
     fn from(inner: TimestampInner) -> Self {
         Self::new(inner)
@@ -294,3 +345,6 @@ impl SubAssign<Interval> for Timestamp {
         *self = *self - interval;
     }
 }
+
+#[cfg(test)]
+mod tests;
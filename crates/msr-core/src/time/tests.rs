@@ -0,0 +1,44 @@
+use time::macros::{datetime, offset};
+
+use super::*;
+
+#[test]
+fn parse_and_format_rfc3339() {
+    let ts = Timestamp::parse_rfc3339("2023-09-10T12:34:56.789+02:00").unwrap();
+    assert_eq!(datetime!(2023-09-10 12:34:56.789 +2), ts.to_inner());
+    assert_eq!(offset!(+2), ts.offset());
+    assert_eq!("2023-09-10T12:34:56.789+02:00", ts.to_rfc3339().unwrap());
+    assert_eq!("2023-09-10T10:34:56.789Z", ts.to_rfc3339_utc().unwrap());
+    assert_eq!(ts, "2023-09-10T12:34:56.789+02:00".parse().unwrap());
+    assert!("2023-09-10 12:34:56".parse::<Timestamp>().is_err());
+}
+
+#[test]
+fn offset_handling() {
+    let ts = Timestamp::parse_rfc3339_utc("2023-09-10T12:34:56+02:00").unwrap();
+    assert_eq!(UtcOffset::UTC, ts.offset());
+    assert_eq!(datetime!(2023-09-10 10:34:56 UTC), ts.to_inner());
+    let converted = ts.to_offset(offset!(-5));
+    assert_eq!(offset!(-5), converted.offset());
+    assert_eq!("2023-09-10T05:34:56-05:00", converted.to_rfc3339().unwrap());
+    // Conversions preserve the point in time
+    assert_eq!(ts, converted);
+    assert_eq!(ts, ts.to_local());
+}
+
+#[test]
+fn format_out_of_range() {
+    let ts = Timestamp::new(datetime!(2023-09-10 12:34:56 +01:02:03));
+    assert!(ts.to_rfc3339().is_err());
+    assert_eq!("2023-09-10T11:32:53Z", ts.to_rfc3339_utc().unwrap());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serde_rfc3339() {
+    let ts = Timestamp::parse_rfc3339("2023-09-10T12:34:56.789+02:00").unwrap();
+    let json = serde_json::to_string(&ts).unwrap();
+    assert_eq!("\"2023-09-10T12:34:56.789+02:00\"", json);
+    assert_eq!(ts, serde_json::from_str::<Timestamp>(&json).unwrap());
+    assert!(serde_json::from_str::<Timestamp>("\"2023-09-10\"").is_err());
+}
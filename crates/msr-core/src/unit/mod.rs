@@ -0,0 +1,308 @@
+//! Engineering units
+
+use std::fmt;
+
+use thiserror::Error;
+
+/// The physical dimension of a unit
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Dimension {
+    Dimensionless,
+    Length,
+    Mass,
+    Time,
+    ElectricCurrent,
+    Temperature,
+    AmountOfSubstance,
+    LuminousIntensity,
+    Pressure,
+    Voltage,
+    Power,
+    Energy,
+    Frequency,
+    Volume,
+    VolumeFlow,
+}
+
+/// Enumeration of engineering units
+///
+/// Contains the SI base units and common process units.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum Unit {
+    /// Ratio without a unit
+    One,
+    Percent,
+
+    Meter,
+    Millimeter,
+
+    Kilogram,
+    Gram,
+    Tonne,
+
+    Second,
+    Millisecond,
+    Minute,
+    Hour,
+
+    Ampere,
+    Milliampere,
+
+    Kelvin,
+    DegreeCelsius,
+    DegreeFahrenheit,
+
+    Mole,
+
+    Candela,
+
+    Pascal,
+    Hectopascal,
+    Kilopascal,
+    Millibar,
+    Bar,
+
+    Volt,
+    Millivolt,
+
+    Watt,
+    Kilowatt,
+
+    Joule,
+    KilowattHour,
+
+    Hertz,
+    RevolutionsPerMinute,
+
+    CubicMeter,
+    Liter,
+
+    CubicMeterPerHour,
+    LiterPerMinute,
+}
+
+/// All units
+pub const UNITS: &[Unit] = &[
+    Unit::One,
+    Unit::Percent,
+    Unit::Meter,
+    Unit::Millimeter,
+    Unit::Kilogram,
+    Unit::Gram,
+    Unit::Tonne,
+    Unit::Second,
+    Unit::Millisecond,
+    Unit::Minute,
+    Unit::Hour,
+    Unit::Ampere,
+    Unit::Milliampere,
+    Unit::Kelvin,
+    Unit::DegreeCelsius,
+    Unit::DegreeFahrenheit,
+    Unit::Mole,
+    Unit::Candela,
+    Unit::Pascal,
+    Unit::Hectopascal,
+    Unit::Kilopascal,
+    Unit::Millibar,
+    Unit::Bar,
+    Unit::Volt,
+    Unit::Millivolt,
+    Unit::Watt,
+    Unit::Kilowatt,
+    Unit::Joule,
+    Unit::KilowattHour,
+    Unit::Hertz,
+    Unit::RevolutionsPerMinute,
+    Unit::CubicMeter,
+    Unit::Liter,
+    Unit::CubicMeterPerHour,
+    Unit::LiterPerMinute,
+];
+
+/// Linear conversion into the coherent SI unit
+struct Definition {
+    symbol: &'static str,
+    dimension: Dimension,
+    factor: f64,
+    offset: f64,
+}
+
+const fn def(symbol: &'static str, dimension: Dimension, factor: f64) -> Definition {
+    Definition {
+        symbol,
+        dimension,
+        factor,
+        offset: 0.0,
+    }
+}
+
+impl Unit {
+    const fn definition(self) -> Definition {
+        use Dimension::*;
+        use Unit::*;
+        match self {
+            One => def("", Dimensionless, 1.0),
+            Percent => def("%", Dimensionless, 0.01),
+            Meter => def("m", Length, 1.0),
+            Millimeter => def("mm", Length, 1e-3),
+            Kilogram => def("kg", Mass, 1.0),
+            Gram => def("g", Mass, 1e-3),
+            Tonne => def("t", Mass, 1e3),
+            Second => def("s", Time, 1.0),
+            Millisecond => def("ms", Time, 1e-3),
+            Minute => def("min", Time, 60.0),
+            Hour => def("h", Time, 3600.0),
+            Ampere => def("A", ElectricCurrent, 1.0),
+            Milliampere => def("mA", ElectricCurrent, 1e-3),
+            Kelvin => def("K", Temperature, 1.0),
+            DegreeCelsius => Definition {
+                symbol: "°C",
+                dimension: Temperature,
+                factor: 1.0,
+                offset: 273.15,
+            },
+            DegreeFahrenheit => Definition {
+                symbol: "°F",
+                dimension: Temperature,
+                factor: 5.0 / 9.0,
+                offset: 273.15 - 32.0 * 5.0 / 9.0,
+            },
+            Mole => def("mol", AmountOfSubstance, 1.0),
+            Candela => def("cd", LuminousIntensity, 1.0),
+            Pascal => def("Pa", Pressure, 1.0),
+            Hectopascal => def("hPa", Pressure, 1e2),
+            Kilopascal => def("kPa", Pressure, 1e3),
+            Millibar => def("mbar", Pressure, 1e2),
+            Bar => def("bar", Pressure, 1e5),
+            Volt => def("V", Voltage, 1.0),
+            Millivolt => def("mV", Voltage, 1e-3),
+            Watt => def("W", Power, 1.0),
+            Kilowatt => def("kW", Power, 1e3),
+            Joule => def("J", Energy, 1.0),
+            KilowattHour => def("kWh", Energy, 3.6e6),
+            Hertz => def("Hz", Frequency, 1.0),
+            RevolutionsPerMinute => def("rpm", Frequency, 1.0 / 60.0),
+            CubicMeter => def("m³", Volume, 1.0),
+            Liter => def("l", Volume, 1e-3),
+            CubicMeterPerHour => def("m³/h", VolumeFlow, 1.0 / 3600.0),
+            LiterPerMinute => def("l/min", VolumeFlow, 1e-3 / 60.0),
+        }
+    }
+
+    /// The symbol, e.g. `°C`
+    #[must_use]
+    pub const fn symbol(self) -> &'static str {
+        self.definition().symbol
+    }
+
+    #[must_use]
+    pub const fn dimension(self) -> Dimension {
+        self.definition().dimension
+    }
+
+    #[must_use]
+    pub fn try_from_symbol(symbol: &str) -> Option<Self> {
+        UNITS.iter().copied().find(|unit| unit.symbol() == symbol)
+    }
+
+    /// Convert a value into the coherent SI unit of the dimension,
+    /// e.g. °C into K or bar into Pa.
+    #[must_use]
+    pub fn to_si(self, value: f64) -> f64 {
+        let Definition { factor, offset, .. } = self.definition();
+        value * factor + offset
+    }
+
+    /// Convert a value from the coherent SI unit of the dimension.
+    #[must_use]
+    pub fn from_si(self, value: f64) -> f64 {
+        let Definition { factor, offset, .. } = self.definition();
+        (value - offset) / factor
+    }
+
+    /// Convert a value into another unit of the same dimension.
+    pub fn convert(self, value: f64, to: Unit) -> Result<f64, IncompatibleUnitsError> {
+        if self.dimension() != to.dimension() {
+            return Err(IncompatibleUnitsError { from: self, to });
+        }
+        if self == to {
+            return Ok(value);
+        }
+        Ok(to.from_si(self.to_si(value)))
+    }
+}
+
+impl fmt::Display for Unit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.symbol())
+    }
+}
+
+/// Units are serialized by their symbol.
+#[cfg(feature = "serde")]
+impl serde::Serialize for Unit {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.symbol())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Unit {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let symbol = <String as serde::Deserialize<'de>>::deserialize(deserializer)?;
+        Self::try_from_symbol(&symbol).ok_or_else(|| {
+            serde::de::Error::invalid_value(serde::de::Unexpected::Str(&symbol), &"a unit symbol")
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("incompatible units: from = {from:?}, to = {to:?}")]
+pub struct IncompatibleUnitsError {
+    pub from: Unit,
+    pub to: Unit,
+}
+
+/// A numeric value with a unit
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Quantity {
+    pub value: f64,
+    pub unit: Unit,
+}
+
+impl Quantity {
+    #[must_use]
+    pub const fn new(value: f64, unit: Unit) -> Self {
+        Self { value, unit }
+    }
+
+    /// Convert the value into another unit of the same dimension.
+    pub fn convert(self, to: Unit) -> Result<Self, IncompatibleUnitsError> {
+        self.unit
+            .convert(self.value, to)
+            .map(|value| Self::new(value, to))
+    }
+}
+
+impl fmt::Display for Quantity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { value, unit } = self;
+        match unit {
+            Unit::One => write!(f, "{value}"),
+            Unit::Percent => write!(f, "{value}{unit}"),
+            _ => write!(f, "{value} {unit}"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
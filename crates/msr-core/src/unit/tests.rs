@@ -0,0 +1,97 @@
+use super::*;
+
+fn assert_approx_eq(expected: f64, actual: f64) {
+    assert!(
+        (expected - actual).abs() < 1e-9,
+        "expected = {expected}, actual = {actual}"
+    );
+}
+
+#[test]
+fn unique_symbols() {
+    for unit in UNITS {
+        assert_eq!(Some(*unit), Unit::try_from_symbol(unit.symbol()));
+    }
+    assert_eq!(None, Unit::try_from_symbol("furlong"));
+}
+
+#[test]
+fn convert_temperatures() {
+    assert_approx_eq(
+        293.15,
+        Unit::DegreeCelsius.convert(20.0, Unit::Kelvin).unwrap(),
+    );
+    assert_approx_eq(
+        -273.15,
+        Unit::Kelvin.convert(0.0, Unit::DegreeCelsius).unwrap(),
+    );
+    assert_approx_eq(
+        212.0,
+        Unit::DegreeCelsius
+            .convert(100.0, Unit::DegreeFahrenheit)
+            .unwrap(),
+    );
+    assert_approx_eq(
+        -40.0,
+        Unit::DegreeFahrenheit
+            .convert(-40.0, Unit::DegreeCelsius)
+            .unwrap(),
+    );
+}
+
+#[test]
+fn convert_pressures() {
+    assert_approx_eq(250_000.0, Unit::Bar.convert(2.5, Unit::Pascal).unwrap());
+    assert_approx_eq(
+        1.013_25,
+        Unit::Hectopascal.convert(1013.25, Unit::Bar).unwrap(),
+    );
+    assert_approx_eq(1.0, Unit::Millibar.convert(1.0, Unit::Hectopascal).unwrap());
+}
+
+#[test]
+fn convert_flows() {
+    assert_approx_eq(
+        1.2,
+        Unit::LiterPerMinute
+            .convert(20.0, Unit::CubicMeterPerHour)
+            .unwrap(),
+    );
+    assert_approx_eq(0.5, Unit::Percent.convert(50.0, Unit::One).unwrap());
+}
+
+#[test]
+fn reject_incompatible_units() {
+    assert_eq!(
+        Err(IncompatibleUnitsError {
+            from: Unit::Bar,
+            to: Unit::Kelvin,
+        }),
+        Unit::Bar.convert(1.0, Unit::Kelvin)
+    );
+}
+
+#[test]
+fn display_quantities() {
+    assert_eq!(
+        "21.5 °C",
+        Quantity::new(21.5, Unit::DegreeCelsius).to_string()
+    );
+    assert_eq!("80%", Quantity::new(80.0, Unit::Percent).to_string());
+    assert_eq!("2 bar", Quantity::new(2.0, Unit::Bar).to_string());
+    assert_eq!("0.5", Quantity::new(0.5, Unit::One).to_string());
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn serialize_units() {
+    assert_eq!(
+        r#""°C""#,
+        serde_json::to_string(&Unit::DegreeCelsius).unwrap()
+    );
+    assert_eq!(
+        Unit::CubicMeterPerHour,
+        serde_json::from_str::<Unit>(r#""m³/h""#).unwrap()
+    );
+    assert!(serde_json::from_str::<Unit>(r#""furlong""#).is_err());
+}
@@ -0,0 +1,234 @@
+use std::collections::BTreeMap;
+
+use super::{ScalarType, ScalarValue, Value};
+
+/// The backing integer type of a [`Bitfield`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BitfieldWidth {
+    U16,
+    U32,
+}
+
+impl BitfieldWidth {
+    /// The number of bits
+    #[must_use]
+    pub const fn bits(self) -> u8 {
+        match self {
+            Self::U16 => 16,
+            Self::U32 => 32,
+        }
+    }
+
+    #[must_use]
+    pub const fn to_scalar_type(self) -> ScalarType {
+        match self {
+            Self::U16 => ScalarType::U16,
+            Self::U32 => ScalarType::U32,
+        }
+    }
+}
+
+/// A word of individually addressable bits, e.g. a device status word
+///
+/// Bits are indexed starting with the least significant bit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Bitfield {
+    bits: u32,
+    width: BitfieldWidth,
+}
+
+impl Bitfield {
+    #[must_use]
+    pub const fn from_u16(bits: u16) -> Self {
+        Self {
+            bits: bits as u32,
+            width: BitfieldWidth::U16,
+        }
+    }
+
+    #[must_use]
+    pub const fn from_u32(bits: u32) -> Self {
+        Self {
+            bits,
+            width: BitfieldWidth::U32,
+        }
+    }
+
+    #[must_use]
+    pub const fn width(self) -> BitfieldWidth {
+        self.width
+    }
+
+    /// The raw bits
+    #[must_use]
+    pub const fn bits(self) -> u32 {
+        self.bits
+    }
+
+    /// Read a single bit.
+    ///
+    /// Returns `None` if the index exceeds the width.
+    #[must_use]
+    pub const fn bit(self, index: u8) -> Option<bool> {
+        if index >= self.width.bits() {
+            return None;
+        }
+        Some(self.bits & (1 << index) != 0)
+    }
+
+    /// Replace a single bit, e.g. of a control word.
+    ///
+    /// Returns `None` if the index exceeds the width.
+    #[must_use]
+    pub const fn with_bit(self, index: u8, value: bool) -> Option<Self> {
+        if index >= self.width.bits() {
+            return None;
+        }
+        let bits = if value {
+            self.bits | (1 << index)
+        } else {
+            self.bits & !(1 << index)
+        };
+        Some(Self { bits, ..self })
+    }
+
+    /// The indexes of all bits that differ from a previous value
+    pub fn changed_bits(self, previous: Self) -> impl Iterator<Item = u8> {
+        let changed = self.bits ^ previous.bits;
+        (0..self.width.bits()).filter(move |index| changed & (1 << index) != 0)
+    }
+}
+
+impl From<u16> for Bitfield {
+    fn from(from: u16) -> Self {
+        Self::from_u16(from)
+    }
+}
+
+impl From<u32> for Bitfield {
+    fn from(from: u32) -> Self {
+        Self::from_u32(from)
+    }
+}
+
+impl From<Bitfield> for ScalarValue {
+    fn from(from: Bitfield) -> Self {
+        match from.width {
+            // The bits are always within the width
+            #[allow(clippy::cast_possible_truncation)]
+            BitfieldWidth::U16 => Self::U16(from.bits as u16),
+            BitfieldWidth::U32 => Self::U32(from.bits),
+        }
+    }
+}
+
+impl ScalarValue {
+    /// Interpret an unsigned integer value as a bitfield.
+    #[must_use]
+    pub const fn to_bitfield(self) -> Option<Bitfield> {
+        match self {
+            Self::U16(val) => Some(Bitfield::from_u16(val)),
+            Self::U32(val) => Some(Bitfield::from_u32(val)),
+            _ => None,
+        }
+    }
+}
+
+/// A named bit
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitDefinition {
+    pub index: u8,
+    pub name: String,
+}
+
+/// A changed bit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BitChange<'a> {
+    pub index: u8,
+    pub name: &'a str,
+    pub value: bool,
+}
+
+/// The named bits of a bitfield
+///
+/// Bits without a name are ignored when interpreting
+/// a bitfield.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BitfieldDefinition {
+    pub width: BitfieldWidth,
+    pub bits: Vec<BitDefinition>,
+}
+
+impl BitfieldDefinition {
+    #[must_use]
+    pub const fn new(width: BitfieldWidth) -> Self {
+        Self {
+            width,
+            bits: Vec::new(),
+        }
+    }
+
+    /// Add a named bit.
+    #[must_use]
+    pub fn with_bit(mut self, index: u8, name: impl Into<String>) -> Self {
+        self.bits.push(BitDefinition {
+            index,
+            name: name.into(),
+        });
+        self
+    }
+
+    /// Find the index of a named bit.
+    #[must_use]
+    pub fn index_of(&self, name: &str) -> Option<u8> {
+        self.bits
+            .iter()
+            .find(|bit| bit.name == name)
+            .map(|bit| bit.index)
+    }
+
+    /// Read a named bit.
+    #[must_use]
+    pub fn read(&self, bitfield: Bitfield, name: &str) -> Option<bool> {
+        self.index_of(name).and_then(|index| bitfield.bit(index))
+    }
+
+    /// Read all named bits.
+    pub fn named_bits(&self, bitfield: Bitfield) -> impl Iterator<Item = (&str, bool)> {
+        self.bits.iter().filter_map(move |bit| {
+            bitfield
+                .bit(bit.index)
+                .map(|value| (bit.name.as_str(), value))
+        })
+    }
+
+    /// All named bits that differ from a previous value
+    pub fn changes(
+        &self,
+        previous: Bitfield,
+        current: Bitfield,
+    ) -> impl Iterator<Item = BitChange<'_>> {
+        self.bits.iter().filter_map(move |bit| {
+            let value = current.bit(bit.index)?;
+            (previous.bit(bit.index) != Some(value)).then_some(BitChange {
+                index: bit.index,
+                name: &bit.name,
+                value,
+            })
+        })
+    }
+
+    /// Map the named bits to boolean values, e.g. for recording
+    /// them individually.
+    #[must_use]
+    pub fn to_value(&self, bitfield: Bitfield) -> Value {
+        Value::Map(
+            self.named_bits(bitfield)
+                .map(|(name, value)| (name.to_owned(), Value::from(value)))
+                .collect::<BTreeMap<_, _>>(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,103 @@
+use super::*;
+
+fn status_word() -> BitfieldDefinition {
+    BitfieldDefinition::new(BitfieldWidth::U16)
+        .with_bit(0, "ready")
+        .with_bit(3, "fault")
+        .with_bit(15, "remote")
+        .with_bit(16, "invalid")
+}
+
+#[test]
+fn read_bits() {
+    let bitfield = Bitfield::from_u16(0b1000_0000_0000_1001);
+    assert_eq!(Some(true), bitfield.bit(0));
+    assert_eq!(Some(false), bitfield.bit(1));
+    assert_eq!(Some(true), bitfield.bit(15));
+    assert_eq!(None, bitfield.bit(16));
+    assert_eq!(Some(false), Bitfield::from_u32(0).bit(31));
+}
+
+#[test]
+fn write_bits() {
+    let bitfield = Bitfield::from_u16(0b1001);
+    assert_eq!(Some(Bitfield::from_u16(0b1011)), bitfield.with_bit(1, true));
+    assert_eq!(
+        Some(Bitfield::from_u16(0b1000)),
+        bitfield.with_bit(0, false)
+    );
+    assert_eq!(None, bitfield.with_bit(16, true));
+}
+
+#[test]
+fn changed_bits() {
+    let previous = Bitfield::from_u32(0b0101);
+    let current = Bitfield::from_u32(0b1100 | 1 << 31);
+    assert_eq!(
+        vec![0, 3, 31],
+        current.changed_bits(previous).collect::<Vec<_>>()
+    );
+    assert_eq!(0, current.changed_bits(current).count());
+}
+
+#[test]
+fn scalar_value_conversion() {
+    let bitfield = Bitfield::from_u16(0xABCD);
+    assert_eq!(ScalarValue::U16(0xABCD), ScalarValue::from(bitfield));
+    assert_eq!(Some(bitfield), ScalarValue::U16(0xABCD).to_bitfield());
+    assert_eq!(
+        Some(Bitfield::from_u32(7)),
+        ScalarValue::U32(7).to_bitfield()
+    );
+    assert_eq!(None, ScalarValue::I16(7).to_bitfield());
+    assert_eq!(ScalarType::U32, BitfieldWidth::U32.to_scalar_type());
+}
+
+#[test]
+fn read_named_bits() {
+    let definition = status_word();
+    let bitfield = Bitfield::from_u16(0b1001);
+    assert_eq!(Some(true), definition.read(bitfield, "fault"));
+    assert_eq!(Some(false), definition.read(bitfield, "remote"));
+    assert_eq!(None, definition.read(bitfield, "invalid"));
+    assert_eq!(None, definition.read(bitfield, "unknown"));
+    assert_eq!(
+        vec![("ready", true), ("fault", true), ("remote", false)],
+        definition.named_bits(bitfield).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn detect_changes_of_named_bits() {
+    let definition = status_word();
+    let previous = Bitfield::from_u16(0b0000_0000_0000_0011);
+    let current = Bitfield::from_u16(0b1000_0000_0000_1001);
+    assert_eq!(
+        vec![
+            BitChange {
+                index: 3,
+                name: "fault",
+                value: true,
+            },
+            BitChange {
+                index: 15,
+                name: "remote",
+                value: true,
+            },
+        ],
+        definition.changes(previous, current).collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn map_named_bits_to_value() {
+    let value = status_word().to_value(Bitfield::from_u16(0b1000));
+    assert_eq!(
+        Value::Map(BTreeMap::from_iter([
+            ("fault".to_owned(), Value::from(true)),
+            ("ready".to_owned(), Value::from(false)),
+            ("remote".to_owned(), Value::from(false)),
+        ])),
+        value
+    );
+}
@@ -0,0 +1,289 @@
+//! Conversions between value types
+
+use thiserror::Error;
+
+use super::{
+    ops::{from_i128, to_i128},
+    Decimal, ScalarType, ScalarValue, Value, ValueType,
+};
+
+/// A value could not be cast into another type
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum CastError {
+    /// The types are not convertible
+    #[error("unsupported cast: from = {from}, to = {to}")]
+    Unsupported { from: ValueType, to: ValueType },
+
+    /// The value is out of the range of the target type
+    #[error("value out of range: to = {to}")]
+    OutOfRange { to: ValueType },
+
+    /// The cast would not be lossless
+    #[error("loss of precision: to = {to}")]
+    LossOfPrecision { to: ValueType },
+
+    /// The value could not be parsed or decoded
+    #[error("invalid value: to = {to}")]
+    Invalid { to: ValueType },
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Number {
+    Integer(i128),
+    Float(f64),
+}
+
+impl Number {
+    fn from_scalar(value: ScalarValue, to: ScalarType, lossy: bool) -> Result<Self, CastError> {
+        let number = match value {
+            ScalarValue::Bool(val) => Self::Integer(val.into()),
+            ScalarValue::F32(val) => Self::Float(val.into()),
+            ScalarValue::F64(val) => Self::Float(val),
+            ScalarValue::Decimal(val) => Self::from_decimal(val, to, lossy)?,
+            _ => Self::Integer(to_i128(value).expect("integer")),
+        };
+        Ok(number)
+    }
+
+    fn from_decimal(val: Decimal, to: ScalarType, lossy: bool) -> Result<Self, CastError> {
+        let val = val.normalize();
+        if val.scale() == 0 {
+            return Ok(Self::Integer(val.mantissa().into()));
+        }
+        match to {
+            ScalarType::F32 | ScalarType::F64 => {
+                let cast = val.to_f64();
+                if !lossy && decimal_from_f64(cast) != Some(val) {
+                    return Err(CastError::LossOfPrecision { to: to.into() });
+                }
+                Ok(Self::Float(cast))
+            }
+            ScalarType::Bool => Err(CastError::OutOfRange { to: to.into() }),
+            _ => {
+                if !lossy {
+                    return Err(CastError::LossOfPrecision { to: to.into() });
+                }
+                let rounded = val
+                    .rescale(0)
+                    .ok_or(CastError::OutOfRange { to: to.into() })?;
+                Ok(Self::Integer(rounded.mantissa().into()))
+            }
+        }
+    }
+}
+
+/// The shortest decimal representation of a floating-point number
+fn decimal_from_f64(val: f64) -> Option<Decimal> {
+    val.to_string().parse().ok()
+}
+
+/// Rounds to the greatest possible scale
+#[allow(clippy::cast_possible_truncation)] // checked
+fn decimal_from_f64_lossy(val: f64) -> Option<Decimal> {
+    (0..=Decimal::MAX_SCALE).rev().find_map(|scale| {
+        let mantissa = (val * 10_f64.powi(scale.into())).round();
+        if mantissa.abs() < 9.2e18 {
+            Decimal::new(mantissa as i64, scale)
+        } else {
+            None
+        }
+    })
+}
+
+/// Floating-point numbers are converted into their shortest
+/// decimal representation that round-trips.
+fn float_to_decimal(val: f64, shortest: &str, lossy: bool) -> Result<Decimal, CastError> {
+    let to = ScalarType::Decimal.into();
+    if !val.is_finite() || val.abs() >= 9.2e18 {
+        return Err(CastError::OutOfRange { to });
+    }
+    if let Ok(val) = shortest.parse() {
+        return Ok(val);
+    }
+    if !lossy {
+        return Err(CastError::LossOfPrecision { to });
+    }
+    decimal_from_f64_lossy(val).ok_or(CastError::OutOfRange { to })
+}
+
+fn cast_to_decimal(value: ScalarValue, lossy: bool) -> Result<ScalarValue, CastError> {
+    let to = ScalarType::Decimal.into();
+    let val = match value {
+        ScalarValue::F32(val) => Some(float_to_decimal(val.into(), &val.to_string(), lossy)?),
+        ScalarValue::F64(val) => Some(float_to_decimal(val, &val.to_string(), lossy)?),
+        ScalarValue::Bool(val) => Some(i64::from(val).into()),
+        _ => {
+            let val = to_i128(value).expect("integer");
+            let val = i64::try_from(val).map_err(|_| CastError::OutOfRange { to })?;
+            Some(val.into())
+        }
+    };
+    val.map(ScalarValue::Decimal)
+        .ok_or(CastError::OutOfRange { to })
+}
+
+/// The largest float magnitude that is safely convertible into `i128`
+const MAX_INTEGRAL_FLOAT: f64 = 1e36;
+
+#[allow(clippy::cast_precision_loss)] // checked by the reverse conversion
+#[allow(clippy::float_cmp)] // exact comparisons are intended
+fn cast_scalar(value: ScalarValue, to: ScalarType, lossy: bool) -> Result<ScalarValue, CastError> {
+    if value.to_type() == to {
+        return Ok(value);
+    }
+    let out_of_range = CastError::OutOfRange { to: to.into() };
+    let loss_of_precision = CastError::LossOfPrecision { to: to.into() };
+    if to == ScalarType::Decimal {
+        return cast_to_decimal(value, lossy);
+    }
+    let number = Number::from_scalar(value, to, lossy)?;
+    match to {
+        ScalarType::Bool => match number {
+            Number::Integer(0) | Number::Float(0.0) => Ok(false.into()),
+            Number::Integer(1) | Number::Float(1.0) => Ok(true.into()),
+            _ => Err(out_of_range),
+        },
+        ScalarType::F32 => {
+            let (cast, lossless) = match number {
+                Number::Integer(val) => {
+                    let cast = val as f32;
+                    (cast, cast as i128 == val)
+                }
+                Number::Float(val) => {
+                    let cast = val as f32;
+                    if cast.is_infinite() && val.is_finite() {
+                        return Err(out_of_range);
+                    }
+                    (cast, val.is_nan() || f64::from(cast) == val)
+                }
+            };
+            if !lossless && !lossy {
+                return Err(loss_of_precision);
+            }
+            Ok(cast.into())
+        }
+        ScalarType::F64 => {
+            let cast = match number {
+                Number::Integer(val) => {
+                    let cast = val as f64;
+                    if cast as i128 != val && !lossy {
+                        return Err(loss_of_precision);
+                    }
+                    cast
+                }
+                Number::Float(val) => val,
+            };
+            Ok(cast.into())
+        }
+        _ => {
+            let val = match number {
+                Number::Integer(val) => val,
+                Number::Float(val) => {
+                    if !val.is_finite() || val.abs() > MAX_INTEGRAL_FLOAT {
+                        return Err(out_of_range);
+                    }
+                    let rounded = val.round();
+                    if rounded != val && !lossy {
+                        return Err(loss_of_precision);
+                    }
+                    rounded as i128
+                }
+            };
+            from_i128(to, val).ok_or(out_of_range)
+        }
+    }
+}
+
+fn parse_scalar(s: &str, to: ScalarType) -> Option<ScalarValue> {
+    use ScalarType::*;
+    let val = match to {
+        Bool => s.parse::<bool>().ok()?.into(),
+        I8 => s.parse::<i8>().ok()?.into(),
+        U8 => s.parse::<u8>().ok()?.into(),
+        I16 => s.parse::<i16>().ok()?.into(),
+        U16 => s.parse::<u16>().ok()?.into(),
+        I32 => s.parse::<i32>().ok()?.into(),
+        U32 => s.parse::<u32>().ok()?.into(),
+        F32 => s.parse::<f32>().ok()?.into(),
+        I64 => s.parse::<i64>().ok()?.into(),
+        U64 => s.parse::<u64>().ok()?.into(),
+        F64 => s.parse::<f64>().ok()?.into(),
+        Decimal => s.parse::<super::Decimal>().ok()?.into(),
+    };
+    Some(val)
+}
+
+impl ScalarValue {
+    /// Cast the value into another scalar type without any loss.
+    ///
+    /// See [`Value::try_cast()`] for the supported conversions.
+    pub fn try_cast(self, to: ScalarType) -> Result<Self, CastError> {
+        cast_scalar(self, to, false)
+    }
+
+    /// Cast the value into another scalar type, permitting
+    /// the loss of precision.
+    pub fn try_cast_lossy(self, to: ScalarType) -> Result<Self, CastError> {
+        cast_scalar(self, to, true)
+    }
+}
+
+impl Value {
+    fn cast(&self, to: ValueType, lossy: bool) -> Result<Self, CastError> {
+        if self.to_type() == to {
+            return Ok(self.clone());
+        }
+        match (self, to) {
+            (Self::Scalar(val), ValueType::Scalar(to)) => {
+                cast_scalar(*val, to, lossy).map(Self::Scalar)
+            }
+            (Self::Scalar(val), ValueType::String) => Ok(Self::String(val.to_string())),
+            (Self::String(val), ValueType::Scalar(scalar_type)) => parse_scalar(val, scalar_type)
+                .map(Self::Scalar)
+                .ok_or(CastError::Invalid { to }),
+            (Self::String(val), ValueType::Bytes) => Ok(Self::Bytes(val.as_bytes().to_vec())),
+            (Self::Bytes(val), ValueType::String) => {
+                if lossy {
+                    return Ok(Self::String(String::from_utf8_lossy(val).into_owned()));
+                }
+                String::from_utf8(val.clone())
+                    .map(Self::String)
+                    .map_err(|_| CastError::Invalid { to })
+            }
+            _ => Err(CastError::Unsupported {
+                from: self.to_type(),
+                to,
+            }),
+        }
+    }
+
+    /// Cast the value into another type without any loss.
+    ///
+    /// | From                  | To                    | Lossless                          | Lossy (opt-in)              |
+    /// |-----------------------|-----------------------|-----------------------------------|-----------------------------|
+    /// | integer               | integer               | value within range                | –                           |
+    /// | integer, float        | float                 | value exactly representable       | rounded to nearest          |
+    /// | float                 | integer               | integral value within range       | rounded to nearest integer  |
+    /// | bool                  | integer, float        | `false` → 0, `true` → 1           | –                           |
+    /// | integer, float        | bool                  | 0 → `false`, 1 → `true`           | –                           |
+    /// | scalar                | string                | formatted with [`std::fmt::Display`] | –                        |
+    /// | string                | scalar                | parsed with [`std::str::FromStr`] | –                           |
+    /// | string                | bytes                 | UTF-8 encoded                     | –                           |
+    /// | bytes                 | string                | valid UTF-8                       | invalid sequences replaced  |
+    ///
+    /// Values are never clamped, i.e. values that are out of range
+    /// are rejected even if lossy casts are permitted. All other
+    /// combinations of different types are unsupported.
+    pub fn try_cast(&self, to: ValueType) -> Result<Self, CastError> {
+        self.cast(to, false)
+    }
+
+    /// Cast the value into another type, permitting the loss
+    /// of precision and the replacement of invalid UTF-8 sequences.
+    pub fn try_cast_lossy(&self, to: ValueType) -> Result<Self, CastError> {
+        self.cast(to, true)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,210 @@
+use super::*;
+
+#[test]
+fn integers() {
+    assert_eq!(
+        Ok(ScalarValue::U8(200)),
+        ScalarValue::I32(200).try_cast(ScalarType::U8)
+    );
+    assert_eq!(
+        Err(CastError::OutOfRange {
+            to: ScalarType::U8.into()
+        }),
+        ScalarValue::I32(-1).try_cast(ScalarType::U8)
+    );
+    assert_eq!(
+        Err(CastError::OutOfRange {
+            to: ScalarType::I64.into()
+        }),
+        ScalarValue::U64(u64::MAX).try_cast_lossy(ScalarType::I64)
+    );
+}
+
+#[test]
+fn floats() {
+    assert_eq!(
+        Ok(ScalarValue::I16(-3)),
+        ScalarValue::F64(-3.0).try_cast(ScalarType::I16)
+    );
+    assert_eq!(
+        Err(CastError::LossOfPrecision {
+            to: ScalarType::I16.into()
+        }),
+        ScalarValue::F64(2.5).try_cast(ScalarType::I16)
+    );
+    assert_eq!(
+        Ok(ScalarValue::I16(3)),
+        ScalarValue::F64(2.5).try_cast_lossy(ScalarType::I16)
+    );
+    assert_eq!(
+        Err(CastError::OutOfRange {
+            to: ScalarType::U32.into()
+        }),
+        ScalarValue::F32(f32::NAN).try_cast_lossy(ScalarType::U32)
+    );
+    assert_eq!(
+        Ok(ScalarValue::F32(0.5)),
+        ScalarValue::F64(0.5).try_cast(ScalarType::F32)
+    );
+    assert!(ScalarValue::F64(0.1).try_cast(ScalarType::F32).is_err());
+    assert_eq!(
+        Ok(ScalarValue::F32(0.1)),
+        ScalarValue::F64(0.1).try_cast_lossy(ScalarType::F32)
+    );
+    assert_eq!(
+        Err(CastError::OutOfRange {
+            to: ScalarType::F32.into()
+        }),
+        ScalarValue::F64(1e300).try_cast_lossy(ScalarType::F32)
+    );
+    assert_eq!(
+        Ok(ScalarValue::F64(16_777_216.0)),
+        ScalarValue::U32(16_777_216).try_cast(ScalarType::F64)
+    );
+    assert!(ScalarValue::U32(16_777_217)
+        .try_cast(ScalarType::F32)
+        .is_err());
+    assert!(ScalarValue::U64(u64::MAX)
+        .try_cast(ScalarType::F64)
+        .is_err());
+    assert!(ScalarValue::U64(u64::MAX)
+        .try_cast_lossy(ScalarType::F64)
+        .is_ok());
+}
+
+#[test]
+fn booleans() {
+    assert_eq!(
+        Ok(ScalarValue::U16(1)),
+        ScalarValue::Bool(true).try_cast(ScalarType::U16)
+    );
+    assert_eq!(
+        Ok(ScalarValue::Bool(false)),
+        ScalarValue::F32(0.0).try_cast(ScalarType::Bool)
+    );
+    assert!(ScalarValue::I8(2).try_cast_lossy(ScalarType::Bool).is_err());
+}
+
+#[test]
+fn strings_and_bytes() {
+    let string = |s: &str| Value::String(s.to_owned());
+    assert_eq!(
+        Ok(string("-1.5")),
+        Value::from(-1.5).try_cast(ValueType::String)
+    );
+    assert_eq!(
+        Ok(Value::from(42u16)),
+        string("42").try_cast(ScalarType::U16.into())
+    );
+    assert_eq!(
+        Err(CastError::Invalid {
+            to: ScalarType::U8.into()
+        }),
+        string("256").try_cast(ScalarType::U8.into())
+    );
+    assert_eq!(
+        Ok(Value::Bytes(b"abc".to_vec())),
+        string("abc").try_cast(ValueType::Bytes)
+    );
+    let invalid = Value::Bytes(vec![b'a', 0xff]);
+    assert_eq!(
+        Err(CastError::Invalid {
+            to: ValueType::String
+        }),
+        invalid.try_cast(ValueType::String)
+    );
+    assert_eq!(
+        Ok(string("a\u{fffd}")),
+        invalid.try_cast_lossy(ValueType::String)
+    );
+}
+
+#[test]
+fn unsupported() {
+    assert_eq!(
+        Err(CastError::Unsupported {
+            from: ValueType::Duration,
+            to: ScalarType::U64.into()
+        }),
+        Value::Duration(std::time::Duration::ZERO).try_cast(ScalarType::U64.into())
+    );
+    assert_eq!(
+        Ok(Value::Array(vec![])),
+        Value::Array(vec![]).try_cast(ValueType::Array)
+    );
+}
+
+#[test]
+fn decimals() {
+    let dec = |s: &str| ScalarValue::Decimal(s.parse().unwrap());
+    assert_eq!(
+        Ok(dec("0.1")),
+        ScalarValue::F64(0.1).try_cast(ScalarType::Decimal)
+    );
+    assert_eq!(
+        Ok(dec("0.1")),
+        ScalarValue::F32(0.1).try_cast(ScalarType::Decimal)
+    );
+    assert_eq!(
+        Ok(dec("-42")),
+        ScalarValue::I16(-42).try_cast(ScalarType::Decimal)
+    );
+    assert_eq!(
+        Err(CastError::OutOfRange {
+            to: ScalarType::Decimal.into()
+        }),
+        ScalarValue::U64(u64::MAX).try_cast(ScalarType::Decimal)
+    );
+    assert_eq!(
+        Err(CastError::LossOfPrecision {
+            to: ScalarType::Decimal.into()
+        }),
+        ScalarValue::F64(1e-30).try_cast(ScalarType::Decimal)
+    );
+    assert_eq!(
+        Ok(dec("0")),
+        ScalarValue::F64(1e-30).try_cast_lossy(ScalarType::Decimal)
+    );
+    assert_eq!(
+        Ok(dec("0.3333333333333333")),
+        ScalarValue::F64(1.0 / 3.0).try_cast(ScalarType::Decimal)
+    );
+    assert_eq!(
+        Ok(dec("0.000000000000000001")),
+        ScalarValue::F64(1.25e-18).try_cast_lossy(ScalarType::Decimal)
+    );
+    assert!(ScalarValue::F64(f64::NAN)
+        .try_cast_lossy(ScalarType::Decimal)
+        .is_err());
+
+    assert_eq!(
+        Ok(ScalarValue::U8(12)),
+        dec("12.00").try_cast(ScalarType::U8)
+    );
+    assert_eq!(
+        Err(CastError::LossOfPrecision {
+            to: ScalarType::U8.into()
+        }),
+        dec("12.5").try_cast(ScalarType::U8)
+    );
+    assert_eq!(
+        Ok(ScalarValue::U8(13)),
+        dec("12.5").try_cast_lossy(ScalarType::U8)
+    );
+    assert_eq!(
+        Ok(ScalarValue::F64(12.5)),
+        dec("12.5").try_cast(ScalarType::F64)
+    );
+    assert_eq!(
+        Ok(ScalarValue::Bool(true)),
+        dec("1.0").try_cast(ScalarType::Bool)
+    );
+    assert_eq!(
+        Ok(Value::from(dec("-0.50"))),
+        Value::from("-0.50".to_owned()).try_cast(ScalarType::Decimal.into())
+    );
+    assert_eq!(
+        Ok(Value::from("-0.50".to_owned())),
+        Value::from(dec("-0.50")).try_cast(ValueType::String)
+    );
+}
@@ -0,0 +1,256 @@
+use std::{cmp::Ordering, fmt, str::FromStr};
+
+use thiserror::Error;
+
+/// Fixed-point decimal number
+///
+/// The value is represented by a 64-bit signed integer mantissa
+/// and a decimal scale, i.e. `mantissa * 10^-scale`. Decimal
+/// fractions like `0.1` are represented exactly and don't
+/// accumulate errors like binary floating-point numbers.
+///
+/// Values with different scales are equal if they represent
+/// the same number, e.g. `1.0 == 1.00`.
+#[derive(Debug, Clone, Copy)]
+pub struct Decimal {
+    mantissa: i64,
+    scale: u8,
+}
+
+fn pow10(exp: u8) -> i128 {
+    10_i128.pow(exp.into())
+}
+
+/// Integer division that rounds half away from zero
+fn div_round(numerator: i128, denominator: i128) -> i128 {
+    let quotient = numerator / denominator;
+    let remainder = numerator % denominator;
+    if remainder.abs() * 2 >= denominator.abs() {
+        quotient + numerator.signum() * denominator.signum()
+    } else {
+        quotient
+    }
+}
+
+impl Decimal {
+    /// The maximum number of fractional digits
+    pub const MAX_SCALE: u8 = 18;
+
+    pub const ZERO: Self = Self {
+        mantissa: 0,
+        scale: 0,
+    };
+
+    /// Create a new value `mantissa * 10^-scale`.
+    ///
+    /// Returns `None` if the scale exceeds [`Self::MAX_SCALE`].
+    #[must_use]
+    pub const fn new(mantissa: i64, scale: u8) -> Option<Self> {
+        if scale > Self::MAX_SCALE {
+            return None;
+        }
+        Some(Self { mantissa, scale })
+    }
+
+    #[must_use]
+    pub const fn from_i64(val: i64) -> Self {
+        Self {
+            mantissa: val,
+            scale: 0,
+        }
+    }
+
+    #[must_use]
+    pub const fn mantissa(self) -> i64 {
+        self.mantissa
+    }
+
+    #[must_use]
+    pub const fn scale(self) -> u8 {
+        self.scale
+    }
+
+    fn from_i128(mantissa: i128, scale: u8) -> Option<Self> {
+        let mantissa = i64::try_from(mantissa).ok()?;
+        Self::new(mantissa, scale)
+    }
+
+    /// The mantissa for a scale that is not less than the current scale
+    pub(super) fn scaled_mantissa(self, scale: u8) -> i128 {
+        debug_assert!(scale >= self.scale);
+        i128::from(self.mantissa) * pow10(scale - self.scale)
+    }
+
+    /// Change the number of fractional digits.
+    ///
+    /// Rounds half away from zero when decreasing the scale.
+    #[must_use]
+    pub fn rescale(self, scale: u8) -> Option<Self> {
+        if scale >= self.scale {
+            return Self::from_i128(self.scaled_mantissa(scale), scale);
+        }
+        let mantissa = div_round(self.mantissa.into(), pow10(self.scale - scale));
+        Self::from_i128(mantissa, scale)
+    }
+
+    /// Remove trailing zeros from the fractional digits.
+    #[must_use]
+    pub const fn normalize(self) -> Self {
+        let Self {
+            mut mantissa,
+            mut scale,
+        } = self;
+        while scale > 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        Self { mantissa, scale }
+    }
+
+    /// Add two values.
+    ///
+    /// The result has the greater scale of both operands.
+    #[must_use]
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        let scale = self.scale.max(rhs.scale);
+        Self::from_i128(
+            self.scaled_mantissa(scale) + rhs.scaled_mantissa(scale),
+            scale,
+        )
+    }
+
+    /// Subtract two values.
+    ///
+    /// The result has the greater scale of both operands.
+    #[must_use]
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        let scale = self.scale.max(rhs.scale);
+        Self::from_i128(
+            self.scaled_mantissa(scale) - rhs.scaled_mantissa(scale),
+            scale,
+        )
+    }
+
+    /// Multiply two values.
+    ///
+    /// The result has the sum of the scales of both operands,
+    /// rounded to at most [`Self::MAX_SCALE`] fractional digits.
+    #[must_use]
+    pub fn checked_mul(self, rhs: Self) -> Option<Self> {
+        let mut mantissa = i128::from(self.mantissa) * i128::from(rhs.mantissa);
+        let mut scale = self.scale + rhs.scale;
+        if scale > Self::MAX_SCALE {
+            mantissa = div_round(mantissa, pow10(scale - Self::MAX_SCALE));
+            scale = Self::MAX_SCALE;
+        }
+        while i64::try_from(mantissa).is_err() && scale > 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        Self::from_i128(mantissa, scale)
+    }
+
+    /// Divide two values.
+    ///
+    /// The result has the greater scale of both operands
+    /// and is rounded half away from zero.
+    ///
+    /// Returns `None` on division by zero.
+    #[must_use]
+    pub fn checked_div(self, rhs: Self) -> Option<Self> {
+        if rhs.mantissa == 0 {
+            return None;
+        }
+        let scale = self.scale.max(rhs.scale);
+        let numerator =
+            i128::from(self.mantissa).checked_mul(pow10(scale + rhs.scale - self.scale))?;
+        Self::from_i128(div_round(numerator, rhs.mantissa.into()), scale)
+    }
+
+    /// The nearest floating-point number.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)] // intended
+    pub fn to_f64(self) -> f64 {
+        self.mantissa as f64 / 10_f64.powi(self.scale.into())
+    }
+}
+
+impl From<i64> for Decimal {
+    fn from(from: i64) -> Self {
+        Self::from_i64(from)
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let scale = self.scale.max(other.scale);
+        self.scaled_mantissa(scale)
+            .cmp(&other.scaled_mantissa(scale))
+    }
+}
+
+/// Formatted with all fractional digits, e.g. `-0.050`.
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Self { mantissa, scale } = *self;
+        if mantissa < 0 {
+            f.write_str("-")?;
+        }
+        let digits = mantissa.unsigned_abs().to_string();
+        let scale = usize::from(scale);
+        if scale == 0 {
+            return f.write_str(&digits);
+        }
+        let digits = format!("{digits:0>width$}", width = scale + 1);
+        let (integral, fractional) = digits.split_at(digits.len() - scale);
+        write!(f, "{integral}.{fractional}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("invalid decimal number")]
+pub struct ParseDecimalError;
+
+/// Parses numbers like `-12.50`.
+///
+/// The scale is defined by the number of fractional digits.
+impl FromStr for Decimal {
+    type Err = ParseDecimalError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (negative, unsigned) = match s.as_bytes().first() {
+            Some(b'-') => (true, &s[1..]),
+            Some(b'+') => (false, &s[1..]),
+            _ => (false, s),
+        };
+        let (integral, fractional) = unsigned.split_once('.').unwrap_or((unsigned, ""));
+        if integral.is_empty()
+            || !integral.bytes().all(|b| b.is_ascii_digit())
+            || !fractional.bytes().all(|b| b.is_ascii_digit())
+            || unsigned.ends_with('.')
+        {
+            return Err(ParseDecimalError);
+        }
+        let scale = u8::try_from(fractional.len()).map_err(|_| ParseDecimalError)?;
+        let digits = format!("{}{integral}{fractional}", if negative { "-" } else { "" });
+        let mantissa = digits.parse::<i64>().map_err(|_| ParseDecimalError)?;
+        Self::new(mantissa, scale).ok_or(ParseDecimalError)
+    }
+}
+
+#[cfg(test)]
+mod tests;
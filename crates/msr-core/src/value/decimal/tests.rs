@@ -0,0 +1,94 @@
+use super::*;
+
+fn dec(s: &str) -> Decimal {
+    s.parse().unwrap()
+}
+
+#[test]
+fn parse_and_format() {
+    for s in ["0", "-1", "12.345", "-0.050", "0.000000000000000001"] {
+        assert_eq!(s, dec(s).to_string());
+    }
+    assert_eq!(Decimal::new(-5, 2), Some(dec("-0.05")));
+    assert_eq!("1.5", dec("+1.5").to_string());
+    for s in [
+        "",
+        "-",
+        ".5",
+        "1.",
+        "1.2.3",
+        "1e3",
+        "0x1",
+        " 1",
+        "1.0000000000000000000",
+    ] {
+        assert_eq!(Err(ParseDecimalError), s.parse::<Decimal>(), "{s}");
+    }
+    assert!(Decimal::new(1, Decimal::MAX_SCALE + 1).is_none());
+}
+
+#[test]
+fn compare_numerically() {
+    assert_eq!(dec("1.0"), dec("1.00"));
+    assert_eq!(dec("1.0"), Decimal::from(1));
+    assert!(dec("-0.1") < dec("0.01"));
+    assert!(dec("2") > dec("1.999"));
+    assert_eq!(dec("1.50").normalize().scale(), 1);
+}
+
+#[test]
+fn arithmetic() {
+    // 0.1 + 0.2 is exactly 0.3
+    assert_eq!(
+        "0.3",
+        dec("0.1").checked_add(dec("0.2")).unwrap().to_string()
+    );
+    assert_eq!(
+        "-1.05",
+        dec("0.2").checked_sub(dec("1.25")).unwrap().to_string()
+    );
+    assert_eq!(
+        "0.375",
+        dec("1.5").checked_mul(dec("0.25")).unwrap().to_string()
+    );
+    assert_eq!(
+        "0.67",
+        dec("2.00").checked_div(dec("3")).unwrap().to_string()
+    );
+    assert_eq!(
+        "-0.7",
+        dec("-2").checked_div(dec("3.0")).unwrap().to_string()
+    );
+    assert_eq!(None, dec("1").checked_div(Decimal::ZERO));
+    assert_eq!(None, Decimal::from(i64::MAX).checked_add(dec("1")));
+    assert_eq!(
+        "1.000000000",
+        dec("0.000000001")
+            .checked_mul(dec("1000000000"))
+            .unwrap()
+            .to_string()
+    );
+    // Rounded to the maximum scale
+    assert_eq!(
+        "0.000000000000000001",
+        dec("0.0000000005")
+            .checked_mul(dec("0.000000001"))
+            .unwrap()
+            .to_string()
+    );
+}
+
+#[test]
+fn rescale() {
+    assert_eq!("1.25", dec("1.245").rescale(2).unwrap().to_string());
+    assert_eq!("-1.25", dec("-1.245").rescale(2).unwrap().to_string());
+    assert_eq!("1.2400", dec("1.24").rescale(4).unwrap().to_string());
+    assert!(Decimal::from(i64::MAX).rescale(1).is_none());
+}
+
+#[test]
+#[allow(clippy::float_cmp)]
+fn to_f64() {
+    assert_eq!(0.1, dec("0.1").to_f64());
+    assert_eq!(-12.345, dec("-12.345").to_f64());
+}
@@ -1,15 +1,34 @@
-use std::{fmt, time::Duration};
+use std::{collections::BTreeMap, fmt, time::Duration};
 
 // TODO: Make `scalar` module public instead of renaming and re-exporting all types?
 mod scalar;
 pub use self::scalar::{Type as ScalarType, Value as ScalarValue};
 
+mod decimal;
+pub use self::decimal::{Decimal, ParseDecimalError};
+
+mod bitfield;
+pub use self::bitfield::{BitChange, BitDefinition, Bitfield, BitfieldDefinition, BitfieldWidth};
+
+mod cast;
+pub use self::cast::CastError;
+
+mod non_finite;
+pub use self::non_finite::{NonFinite, NonFiniteError, NonFinitePolicy};
+
+mod ops;
+pub use self::ops::OperationError;
+
+#[cfg(feature = "serde")]
+mod serde;
+
 pub trait ToValueType {
     fn to_value_type(&self) -> ValueType;
 }
 
 /// Enumeration of value types
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+#[non_exhaustive]
 pub enum ValueType {
     /// Scalar type
     Scalar(ScalarType),
@@ -22,12 +41,20 @@ pub enum ValueType {
 
     /// Binary data
     Bytes,
+
+    /// Sequence of values
+    Array,
+
+    /// Values with named keys
+    Map,
 }
 
 // TODO: Use short identifiers?
 const TYPE_STR_DURATION: &str = "duration";
 const TYPE_STR_STRING: &str = "string";
 const TYPE_STR_BYTES: &str = "bytes";
+const TYPE_STR_ARRAY: &str = "array";
+const TYPE_STR_MAP: &str = "map";
 
 impl ValueType {
     #[must_use]
@@ -54,6 +81,8 @@ impl ValueType {
             Self::Duration => TYPE_STR_DURATION,
             Self::String => TYPE_STR_STRING,
             Self::Bytes => TYPE_STR_BYTES,
+            Self::Array => TYPE_STR_ARRAY,
+            Self::Map => TYPE_STR_MAP,
         }
     }
 
@@ -63,6 +92,8 @@ impl ValueType {
             TYPE_STR_DURATION => Some(Self::Duration),
             TYPE_STR_STRING => Some(Self::String),
             TYPE_STR_BYTES => Some(Self::Bytes),
+            TYPE_STR_ARRAY => Some(Self::Array),
+            TYPE_STR_MAP => Some(Self::Map),
             _ => None,
         })
     }
@@ -86,6 +117,7 @@ impl fmt::Display for ValueType {
 /// an enclosing type that includes the complex, non-real-time-safe
 /// values?
 #[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
 pub enum Value {
     /// Scalar value (real-time safe)
     ///
@@ -106,6 +138,18 @@ pub enum Value {
     ///
     /// This variant must not be used in real-time contexts.
     Bytes(Vec<u8>),
+
+    /// Variable-size sequence of values, e.g. a spectrum
+    ///
+    /// The elements may have different types.
+    ///
+    /// This variant must not be used in real-time contexts.
+    Array(Vec<Value>),
+
+    /// Values with named keys, e.g. a parameter set
+    ///
+    /// This variant must not be used in real-time contexts.
+    Map(BTreeMap<String, Value>),
 }
 
 impl From<Duration> for Value {
@@ -126,6 +170,18 @@ impl From<Vec<u8>> for Value {
     }
 }
 
+impl From<Vec<Value>> for Value {
+    fn from(from: Vec<Value>) -> Value {
+        Self::Array(from)
+    }
+}
+
+impl From<BTreeMap<String, Value>> for Value {
+    fn from(from: BTreeMap<String, Value>) -> Value {
+        Self::Map(from)
+    }
+}
+
 impl Value {
     #[must_use]
     pub const fn to_type(&self) -> ValueType {
@@ -134,6 +190,8 @@ impl Value {
             Self::Duration(_) => ValueType::Duration,
             Self::String(_) => ValueType::String,
             Self::Bytes(_) => ValueType::Bytes,
+            Self::Array(_) => ValueType::Array,
+            Self::Map(_) => ValueType::Map,
         }
     }
 
@@ -0,0 +1,167 @@
+use std::cmp::Ordering;
+
+use thiserror::Error;
+
+use super::{OperationError, ScalarValue, Value};
+
+/// Classification of non-finite floating-point values
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NonFinite {
+    NaN,
+    PositiveInfinity,
+    NegativeInfinity,
+}
+
+impl NonFinite {
+    #[must_use]
+    pub fn classify(val: f64) -> Option<Self> {
+        if val.is_nan() {
+            Some(Self::NaN)
+        } else if val == f64::INFINITY {
+            Some(Self::PositiveInfinity)
+        } else if val == f64::NEG_INFINITY {
+            Some(Self::NegativeInfinity)
+        } else {
+            None
+        }
+    }
+}
+
+/// A non-finite value has been rejected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("non-finite value: {0:?}")]
+pub struct NonFiniteError(pub NonFinite);
+
+/// Handling of NaN and infinite floating-point values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NonFinitePolicy {
+    /// Keep the values (IEEE 754 semantics)
+    #[default]
+    Propagate,
+
+    /// Fail with a [`NonFiniteError`]
+    Reject,
+
+    /// Treat the values as missing
+    Missing,
+
+    /// Replace infinite values by the greatest or least finite
+    /// value of the type and treat NaN as missing
+    Clamp,
+}
+
+impl ScalarValue {
+    /// Check if the value is a NaN or infinite floating-point number.
+    #[must_use]
+    pub fn non_finite(self) -> Option<NonFinite> {
+        match self {
+            Self::F32(val) => NonFinite::classify(val.into()),
+            Self::F64(val) => NonFinite::classify(val),
+            _ => None,
+        }
+    }
+
+    /// Apply the policy if the value is not finite.
+    ///
+    /// Returns `None` if the value has to be treated as missing.
+    pub fn apply_non_finite_policy(
+        self,
+        policy: NonFinitePolicy,
+    ) -> Result<Option<Self>, NonFiniteError> {
+        let Some(non_finite) = self.non_finite() else {
+            return Ok(Some(self));
+        };
+        match (policy, non_finite) {
+            (NonFinitePolicy::Propagate, _) => Ok(Some(self)),
+            (NonFinitePolicy::Reject, _) => Err(NonFiniteError(non_finite)),
+            (NonFinitePolicy::Missing, _) | (NonFinitePolicy::Clamp, NonFinite::NaN) => Ok(None),
+            (NonFinitePolicy::Clamp, _) => {
+                let positive = non_finite == NonFinite::PositiveInfinity;
+                let clamped = match (self, positive) {
+                    (Self::F32(_), true) => Self::F32(f32::MAX),
+                    (Self::F32(_), false) => Self::F32(f32::MIN),
+                    (_, true) => Self::F64(f64::MAX),
+                    (_, false) => Self::F64(f64::MIN),
+                };
+                Ok(Some(clamped))
+            }
+        }
+    }
+
+    /// Compare two values after applying the policy.
+    ///
+    /// Missing values are unordered.
+    pub fn try_compare_with(
+        self,
+        rhs: Self,
+        policy: NonFinitePolicy,
+    ) -> Result<Option<Ordering>, OperationError> {
+        let ordering = self.try_compare(rhs)?;
+        if self.non_finite().is_none() && rhs.non_finite().is_none() {
+            return Ok(ordering);
+        }
+        match (
+            self.apply_non_finite_policy(policy)?,
+            rhs.apply_non_finite_policy(policy)?,
+        ) {
+            (Some(lhs), Some(rhs)) => lhs.try_compare(rhs),
+            _ => Ok(None),
+        }
+    }
+}
+
+impl Value {
+    /// Compare two values after applying the policy.
+    ///
+    /// Missing values are unordered.
+    pub fn try_compare_with(
+        &self,
+        rhs: &Self,
+        policy: NonFinitePolicy,
+    ) -> Result<Option<Ordering>, OperationError> {
+        let ordering = self.try_compare(rhs)?;
+        if policy == NonFinitePolicy::Propagate {
+            return Ok(ordering);
+        }
+        match (
+            self.clone().apply_non_finite_policy(policy)?,
+            rhs.clone().apply_non_finite_policy(policy)?,
+        ) {
+            (Some(lhs), Some(rhs)) => lhs.try_compare(&rhs),
+            _ => Ok(None),
+        }
+    }
+
+    /// Apply the policy to all scalar values.
+    ///
+    /// Arrays and maps are treated as missing if any
+    /// of their elements is missing.
+    pub fn apply_non_finite_policy(
+        self,
+        policy: NonFinitePolicy,
+    ) -> Result<Option<Self>, NonFiniteError> {
+        if policy == NonFinitePolicy::Propagate {
+            return Ok(Some(self));
+        }
+        match self {
+            Self::Scalar(val) => Ok(val.apply_non_finite_policy(policy)?.map(Self::Scalar)),
+            Self::Array(values) => values
+                .into_iter()
+                .map(|val| val.apply_non_finite_policy(policy))
+                .collect::<Result<Option<Vec<_>>, _>>()
+                .map(|values| values.map(Self::Array)),
+            Self::Map(values) => values
+                .into_iter()
+                .map(|(key, val)| {
+                    val.apply_non_finite_policy(policy)
+                        .map(|val| val.map(|val| (key, val)))
+                })
+                .collect::<Result<Option<_>, _>>()
+                .map(|values| values.map(Self::Map)),
+            Self::Duration(_) | Self::String(_) | Self::Bytes(_) => Ok(Some(self)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,109 @@
+use std::collections::BTreeMap;
+
+use super::*;
+
+#[test]
+fn classify() {
+    assert_eq!(None, ScalarValue::F64(1.0).non_finite());
+    assert_eq!(
+        Some(NonFinite::NaN),
+        ScalarValue::F32(f32::NAN).non_finite()
+    );
+    assert_eq!(
+        Some(NonFinite::NegativeInfinity),
+        ScalarValue::F64(f64::NEG_INFINITY).non_finite()
+    );
+    assert_eq!(None, ScalarValue::I64(i64::MAX).non_finite());
+}
+
+#[test]
+fn apply_policies() {
+    let inf = ScalarValue::F32(f32::INFINITY);
+    let nan = ScalarValue::F64(f64::NAN);
+    assert_eq!(
+        Ok(Some(inf)),
+        inf.apply_non_finite_policy(NonFinitePolicy::Propagate)
+    );
+    assert_eq!(
+        Err(NonFiniteError(NonFinite::PositiveInfinity)),
+        inf.apply_non_finite_policy(NonFinitePolicy::Reject)
+    );
+    assert_eq!(
+        Ok(None),
+        inf.apply_non_finite_policy(NonFinitePolicy::Missing)
+    );
+    assert_eq!(
+        Ok(Some(ScalarValue::F32(f32::MAX))),
+        inf.apply_non_finite_policy(NonFinitePolicy::Clamp)
+    );
+    assert_eq!(
+        Ok(Some(ScalarValue::F64(f64::MIN))),
+        ScalarValue::F64(f64::NEG_INFINITY).apply_non_finite_policy(NonFinitePolicy::Clamp)
+    );
+    assert_eq!(
+        Ok(None),
+        nan.apply_non_finite_policy(NonFinitePolicy::Clamp)
+    );
+    assert_eq!(
+        Ok(Some(ScalarValue::F64(1.5))),
+        ScalarValue::F64(1.5).apply_non_finite_policy(NonFinitePolicy::Reject)
+    );
+}
+
+#[test]
+fn apply_policies_recursively() {
+    let array = Value::Array(vec![1.0.into(), f64::INFINITY.into()]);
+    assert_eq!(
+        Ok(Some(Value::Array(vec![1.0.into(), f64::MAX.into()]))),
+        array
+            .clone()
+            .apply_non_finite_policy(NonFinitePolicy::Clamp)
+    );
+    assert_eq!(
+        Ok(None),
+        array
+            .clone()
+            .apply_non_finite_policy(NonFinitePolicy::Missing)
+    );
+    let map = Value::Map(BTreeMap::from_iter([("a".to_owned(), array)]));
+    assert!(map
+        .apply_non_finite_policy(NonFinitePolicy::Reject)
+        .is_err());
+}
+
+#[test]
+fn compare_with_policies() {
+    let nan = ScalarValue::F64(f64::NAN);
+    let inf = ScalarValue::F64(f64::INFINITY);
+    assert_eq!(
+        Ok(None),
+        nan.try_compare_with(1.0.into(), NonFinitePolicy::Propagate)
+    );
+    assert_eq!(
+        Err(OperationError::NonFinite(NonFiniteError(NonFinite::NaN))),
+        nan.try_compare_with(1.0.into(), NonFinitePolicy::Reject)
+    );
+    assert_eq!(
+        Ok(Some(Ordering::Greater)),
+        inf.try_compare_with(f64::MAX.into(), NonFinitePolicy::Propagate)
+    );
+    assert_eq!(
+        Ok(Some(Ordering::Equal)),
+        inf.try_compare_with(f64::MAX.into(), NonFinitePolicy::Clamp)
+    );
+    assert_eq!(
+        Ok(None),
+        inf.try_compare_with(1.into(), NonFinitePolicy::Missing)
+    );
+    // Incompatible types are always rejected
+    assert!(nan
+        .try_compare_with(true.into(), NonFinitePolicy::Missing)
+        .is_err());
+    assert_eq!(
+        Ok(Some(Ordering::Less)),
+        Value::from(1.0).try_compare_with(&Value::from(f64::INFINITY), NonFinitePolicy::Propagate)
+    );
+    assert!(Value::from(1.0)
+        .try_compare_with(&Value::from(f64::INFINITY), NonFinitePolicy::Reject)
+        .is_err());
+}
@@ -0,0 +1,417 @@
+//! Arithmetic and comparison operations
+//!
+//! Operands of different scalar types are promoted to a common type
+//! before the operation is applied:
+//!
+//! - Booleans are only comparable with booleans and don't support
+//!   any arithmetic.
+//! - Integers with the same signedness are promoted to the wider type.
+//! - Signed and unsigned integers are promoted to a signed type that is
+//!   able to represent both operands, i.e. `u8` → `i16`, `u16` → `i32`,
+//!   `u32` → `i64`. The widest signed type is `i64` and unsigned 64-bit
+//!   values that exceed its range cause an overflow.
+//! - Decimals and integers are promoted to decimals. Unsigned 64-bit
+//!   values that exceed the range of the mantissa cause an overflow.
+//! - If any operand is a floating-point number both operands are
+//!   promoted to `f32` if they could be converted losslessly and
+//!   to `f64` otherwise.
+//!
+//! Integer and decimal operations are checked and fail if the result
+//! does not fit into the promoted type. See [`Decimal`] for the scale
+//! of decimal results. Floating-point operations follow IEEE 754.
+
+use std::cmp::Ordering;
+
+use thiserror::Error;
+
+use super::{Decimal, NonFiniteError, ScalarType, ScalarValue, Value, ValueType};
+
+/// An operation that could not be applied
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+pub enum OperationError {
+    /// The types of the operands are not compatible
+    #[error("incompatible types: lhs = {lhs}, rhs = {rhs}")]
+    IncompatibleTypes { lhs: ValueType, rhs: ValueType },
+
+    /// The result exceeds the range of the promoted type
+    #[error("arithmetic overflow")]
+    Overflow,
+
+    /// Integer division by zero
+    #[error("division by zero")]
+    DivisionByZero,
+
+    /// A non-finite operand has been rejected
+    #[error(transparent)]
+    NonFinite(#[from] NonFiniteError),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+/// Signedness and number of bits of an integer type
+const fn integer_bits(ty: ScalarType) -> Option<(bool, u32)> {
+    use ScalarType::*;
+    match ty {
+        I8 => Some((true, 8)),
+        U8 => Some((false, 8)),
+        I16 => Some((true, 16)),
+        U16 => Some((false, 16)),
+        I32 => Some((true, 32)),
+        U32 => Some((false, 32)),
+        I64 => Some((true, 64)),
+        U64 => Some((false, 64)),
+        _ => None,
+    }
+}
+
+const fn integer_type(signed: bool, bits: u32) -> ScalarType {
+    use ScalarType::*;
+    match (signed, bits) {
+        (true, 8) => I8,
+        (false, 8) => U8,
+        (true, 16) => I16,
+        (false, 16) => U16,
+        (true, 32) => I32,
+        (false, 32) => U32,
+        (true, _) => I64,
+        (false, _) => U64,
+    }
+}
+
+fn promote(lhs: ScalarType, rhs: ScalarType) -> Option<ScalarType> {
+    use ScalarType::*;
+    if lhs == Bool || rhs == Bool {
+        return None;
+    }
+    let is_float = |ty| matches!(ty, F32 | F64);
+    if (lhs == Decimal || rhs == Decimal) && !is_float(lhs) && !is_float(rhs) {
+        return Some(Decimal);
+    }
+    if let (Some((lhs_signed, lhs_bits)), Some((rhs_signed, rhs_bits))) =
+        (integer_bits(lhs), integer_bits(rhs))
+    {
+        if lhs_signed == rhs_signed {
+            return Some(integer_type(lhs_signed, lhs_bits.max(rhs_bits)));
+        }
+        let (signed_bits, unsigned_bits) = if lhs_signed {
+            (lhs_bits, rhs_bits)
+        } else {
+            (rhs_bits, lhs_bits)
+        };
+        return Some(integer_type(true, signed_bits.max(unsigned_bits * 2)));
+    }
+    let is_f32_compatible = |ty| matches!(ty, F32 | I8 | U8 | I16 | U16);
+    if is_f32_compatible(lhs) && is_f32_compatible(rhs) {
+        Some(F32)
+    } else {
+        Some(F64)
+    }
+}
+
+pub(super) fn to_i128(value: ScalarValue) -> Option<i128> {
+    use ScalarValue::*;
+    match value {
+        I8(val) => Some(val.into()),
+        U8(val) => Some(val.into()),
+        I16(val) => Some(val.into()),
+        U16(val) => Some(val.into()),
+        I32(val) => Some(val.into()),
+        U32(val) => Some(val.into()),
+        I64(val) => Some(val.into()),
+        U64(val) => Some(val.into()),
+        _ => None,
+    }
+}
+
+pub(super) fn from_i128(ty: ScalarType, val: i128) -> Option<ScalarValue> {
+    match ty {
+        ScalarType::I8 => i8::try_from(val).ok().map(ScalarValue::I8),
+        ScalarType::U8 => u8::try_from(val).ok().map(ScalarValue::U8),
+        ScalarType::I16 => i16::try_from(val).ok().map(ScalarValue::I16),
+        ScalarType::U16 => u16::try_from(val).ok().map(ScalarValue::U16),
+        ScalarType::I32 => i32::try_from(val).ok().map(ScalarValue::I32),
+        ScalarType::U32 => u32::try_from(val).ok().map(ScalarValue::U32),
+        ScalarType::I64 => i64::try_from(val).ok().map(ScalarValue::I64),
+        ScalarType::U64 => u64::try_from(val).ok().map(ScalarValue::U64),
+        _ => None,
+    }
+}
+
+#[allow(clippy::cast_precision_loss)] // promotion of 64-bit integers
+fn to_f64(value: ScalarValue) -> Option<f64> {
+    match value {
+        ScalarValue::Decimal(val) => Some(val.to_f64()),
+        ScalarValue::I64(val) => Some(val as f64),
+        ScalarValue::U64(val) => Some(val as f64),
+        _ => value.to_f64(),
+    }
+}
+
+const fn incompatible_scalars(lhs: ScalarValue, rhs: ScalarValue) -> OperationError {
+    OperationError::IncompatibleTypes {
+        lhs: ValueType::Scalar(lhs.to_type()),
+        rhs: ValueType::Scalar(rhs.to_type()),
+    }
+}
+
+impl ScalarType {
+    /// The common type of two scalar types for arithmetic operations.
+    #[must_use]
+    pub fn promote(self, rhs: Self) -> Option<Self> {
+        promote(self, rhs)
+    }
+}
+
+/// Integers and decimals with the given scale
+fn to_scaled_i128(value: ScalarValue, scale: u8) -> Option<i128> {
+    match value {
+        ScalarValue::Decimal(val) => Some(val.scaled_mantissa(scale)),
+        _ => to_i128(value).map(|val| val * 10_i128.pow(scale.into())),
+    }
+}
+
+fn promote_to_decimal(value: ScalarValue) -> Option<Decimal> {
+    match value {
+        ScalarValue::Decimal(val) => Some(val),
+        _ => to_i128(value)
+            .and_then(|val| i64::try_from(val).ok())
+            .map(Decimal::from_i64),
+    }
+}
+
+impl ScalarValue {
+    fn apply(self, op: Operator, rhs: Self) -> Result<Self, OperationError> {
+        let ty = promote(self.to_type(), rhs.to_type()).ok_or(incompatible_scalars(self, rhs))?;
+        if ty == ScalarType::Decimal {
+            let (lhs, rhs) = promote_to_decimal(self)
+                .zip(promote_to_decimal(rhs))
+                .ok_or(OperationError::Overflow)?;
+            let res = match op {
+                Operator::Add => lhs.checked_add(rhs),
+                Operator::Sub => lhs.checked_sub(rhs),
+                Operator::Mul => lhs.checked_mul(rhs),
+                Operator::Div => {
+                    if rhs.mantissa() == 0 {
+                        return Err(OperationError::DivisionByZero);
+                    }
+                    lhs.checked_div(rhs)
+                }
+            };
+            return res.map(Self::Decimal).ok_or(OperationError::Overflow);
+        }
+        if integer_bits(ty).is_some() {
+            let (lhs, rhs) = to_i128(self).zip(to_i128(rhs)).expect("integers");
+            let res = match op {
+                Operator::Add => lhs.checked_add(rhs),
+                Operator::Sub => lhs.checked_sub(rhs),
+                Operator::Mul => lhs.checked_mul(rhs),
+                Operator::Div => {
+                    if rhs == 0 {
+                        return Err(OperationError::DivisionByZero);
+                    }
+                    lhs.checked_div(rhs)
+                }
+            };
+            return res
+                .and_then(|res| from_i128(ty, res))
+                .ok_or(OperationError::Overflow);
+        }
+        if ty == ScalarType::F32 {
+            let (lhs, rhs) = self.to_f32().zip(rhs.to_f32()).expect("f32 compatible");
+            let res = match op {
+                Operator::Add => lhs + rhs,
+                Operator::Sub => lhs - rhs,
+                Operator::Mul => lhs * rhs,
+                Operator::Div => lhs / rhs,
+            };
+            return Ok(Self::F32(res));
+        }
+        let (lhs, rhs) = to_f64(self).zip(to_f64(rhs)).expect("numbers");
+        let res = match op {
+            Operator::Add => lhs + rhs,
+            Operator::Sub => lhs - rhs,
+            Operator::Mul => lhs * rhs,
+            Operator::Div => lhs / rhs,
+        };
+        Ok(Self::F64(res))
+    }
+
+    /// Add two values.
+    pub fn checked_add(self, rhs: Self) -> Result<Self, OperationError> {
+        self.apply(Operator::Add, rhs)
+    }
+
+    /// Subtract two values.
+    pub fn checked_sub(self, rhs: Self) -> Result<Self, OperationError> {
+        self.apply(Operator::Sub, rhs)
+    }
+
+    /// Multiply two values.
+    pub fn checked_mul(self, rhs: Self) -> Result<Self, OperationError> {
+        self.apply(Operator::Mul, rhs)
+    }
+
+    /// Divide two values.
+    ///
+    /// Integer divisions are truncated towards zero.
+    pub fn checked_div(self, rhs: Self) -> Result<Self, OperationError> {
+        self.apply(Operator::Div, rhs)
+    }
+
+    /// Compare two values.
+    ///
+    /// Integers and decimals are compared exactly without any promotion.
+    /// Returns `None` if any floating-point operand is NaN.
+    pub fn try_compare(self, rhs: Self) -> Result<Option<Ordering>, OperationError> {
+        if let (Some(lhs), Some(rhs)) = (self.to_bool(), rhs.to_bool()) {
+            return Ok(Some(lhs.cmp(&rhs)));
+        }
+        let scale = |value| match value {
+            ScalarValue::Decimal(val) => val.scale(),
+            _ => 0,
+        };
+        let scale = scale(self).max(scale(rhs));
+        if let (Some(lhs), Some(rhs)) = (to_scaled_i128(self, scale), to_scaled_i128(rhs, scale)) {
+            return Ok(Some(lhs.cmp(&rhs)));
+        }
+        match (to_f64(self), to_f64(rhs)) {
+            (Some(lhs), Some(rhs)) => Ok(lhs.partial_cmp(&rhs)),
+            _ => Err(incompatible_scalars(self, rhs)),
+        }
+    }
+
+    /// Check if two values are equal after promotion.
+    ///
+    /// In contrast to `==` values of different types
+    /// are equal if they represent the same number.
+    pub fn try_eq(self, rhs: Self) -> Result<bool, OperationError> {
+        self.try_compare(rhs)
+            .map(|ordering| ordering == Some(Ordering::Equal))
+    }
+}
+
+const fn incompatible_values(lhs: &Value, rhs: &Value) -> OperationError {
+    OperationError::IncompatibleTypes {
+        lhs: lhs.to_type(),
+        rhs: rhs.to_type(),
+    }
+}
+
+impl Value {
+    /// Add two values.
+    ///
+    /// Supports scalars and durations.
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self, OperationError> {
+        match (self, rhs) {
+            (Self::Scalar(lhs), Self::Scalar(rhs)) => lhs.checked_add(*rhs).map(Self::Scalar),
+            (Self::Duration(lhs), Self::Duration(rhs)) => lhs
+                .checked_add(*rhs)
+                .map(Self::Duration)
+                .ok_or(OperationError::Overflow),
+            _ => Err(incompatible_values(self, rhs)),
+        }
+    }
+
+    /// Subtract two values.
+    ///
+    /// Supports scalars and durations. Negative durations
+    /// are reported as an overflow.
+    pub fn checked_sub(&self, rhs: &Self) -> Result<Self, OperationError> {
+        match (self, rhs) {
+            (Self::Scalar(lhs), Self::Scalar(rhs)) => lhs.checked_sub(*rhs).map(Self::Scalar),
+            (Self::Duration(lhs), Self::Duration(rhs)) => lhs
+                .checked_sub(*rhs)
+                .map(Self::Duration)
+                .ok_or(OperationError::Overflow),
+            _ => Err(incompatible_values(self, rhs)),
+        }
+    }
+
+    /// Multiply two values.
+    ///
+    /// Supports scalars and durations multiplied by
+    /// unsigned integers.
+    pub fn checked_mul(&self, rhs: &Self) -> Result<Self, OperationError> {
+        match (self, rhs) {
+            (Self::Scalar(lhs), Self::Scalar(rhs)) => lhs.checked_mul(*rhs).map(Self::Scalar),
+            (Self::Duration(lhs), Self::Scalar(factor)) => {
+                let factor = factor
+                    .to_u32()
+                    .ok_or_else(|| incompatible_values(self, rhs))?;
+                lhs.checked_mul(factor)
+                    .map(Self::Duration)
+                    .ok_or(OperationError::Overflow)
+            }
+            _ => Err(incompatible_values(self, rhs)),
+        }
+    }
+
+    /// Divide two values.
+    ///
+    /// Supports scalars and durations divided by
+    /// unsigned integers.
+    pub fn checked_div(&self, rhs: &Self) -> Result<Self, OperationError> {
+        match (self, rhs) {
+            (Self::Scalar(lhs), Self::Scalar(rhs)) => lhs.checked_div(*rhs).map(Self::Scalar),
+            (Self::Duration(lhs), Self::Scalar(divisor)) => {
+                let divisor = divisor
+                    .to_u32()
+                    .ok_or_else(|| incompatible_values(self, rhs))?;
+                lhs.checked_div(divisor)
+                    .map(Self::Duration)
+                    .ok_or(OperationError::DivisionByZero)
+            }
+            _ => Err(incompatible_values(self, rhs)),
+        }
+    }
+
+    /// Compare two values of compatible types.
+    ///
+    /// Scalars are compared as defined by [`ScalarValue::try_compare()`],
+    /// strings, bytes and arrays lexicographically. Maps are either
+    /// equal or unordered.
+    pub fn try_compare(&self, rhs: &Self) -> Result<Option<Ordering>, OperationError> {
+        match (self, rhs) {
+            (Self::Scalar(lhs), Self::Scalar(rhs)) => lhs.try_compare(*rhs),
+            (Self::Duration(lhs), Self::Duration(rhs)) => Ok(Some(lhs.cmp(rhs))),
+            (Self::String(lhs), Self::String(rhs)) => Ok(Some(lhs.cmp(rhs))),
+            (Self::Bytes(lhs), Self::Bytes(rhs)) => Ok(Some(lhs.cmp(rhs))),
+            (Self::Array(lhs), Self::Array(rhs)) => {
+                for (lhs, rhs) in lhs.iter().zip(rhs) {
+                    match lhs.try_compare(rhs)? {
+                        Some(Ordering::Equal) => {}
+                        ordering => return Ok(ordering),
+                    }
+                }
+                Ok(Some(lhs.len().cmp(&rhs.len())))
+            }
+            (Self::Map(lhs), Self::Map(rhs)) => {
+                if lhs.len() != rhs.len() {
+                    return Ok(None);
+                }
+                for ((lhs_key, lhs), (rhs_key, rhs)) in lhs.iter().zip(rhs) {
+                    if lhs_key != rhs_key || !lhs.try_eq(rhs)? {
+                        return Ok(None);
+                    }
+                }
+                Ok(Some(Ordering::Equal))
+            }
+            _ => Err(incompatible_values(self, rhs)),
+        }
+    }
+
+    /// Check if two values of compatible types are equal.
+    pub fn try_eq(&self, rhs: &Self) -> Result<bool, OperationError> {
+        self.try_compare(rhs)
+            .map(|ordering| ordering == Some(Ordering::Equal))
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,214 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use super::*;
+
+#[test]
+fn promote_types() {
+    use ScalarType::*;
+    assert_eq!(Some(I32), I8.promote(I32));
+    assert_eq!(Some(U64), U64.promote(U16));
+    assert_eq!(Some(I16), I8.promote(U8));
+    assert_eq!(Some(I32), U16.promote(I8));
+    assert_eq!(Some(I64), U32.promote(I32));
+    assert_eq!(Some(I64), I8.promote(U64));
+    assert_eq!(Some(F32), F32.promote(U16));
+    assert_eq!(Some(F64), F32.promote(I32));
+    assert_eq!(Some(F64), U64.promote(F64));
+    assert_eq!(None, Bool.promote(I8));
+    assert_eq!(None, F64.promote(Bool));
+}
+
+#[test]
+fn integer_arithmetic() {
+    let add = |lhs: ScalarValue, rhs: ScalarValue| lhs.checked_add(rhs);
+    assert_eq!(
+        Ok(ScalarValue::I16(-1)),
+        add(255u8.into(), (-256i16).into())
+    );
+    assert_eq!(Ok(ScalarValue::I16(0)), add(128u8.into(), (-128i8).into()));
+    assert_eq!(
+        Ok(ScalarValue::U8(2)),
+        ScalarValue::U8(7).checked_div(3u8.into())
+    );
+    assert_eq!(
+        Ok(ScalarValue::I32(-2)),
+        ScalarValue::I32(-7).checked_div(3i8.into())
+    );
+    assert_eq!(Err(OperationError::Overflow), add(255u8.into(), 1u8.into()));
+    assert_eq!(
+        Err(OperationError::Overflow),
+        ScalarValue::U8(1).checked_sub(2u8.into())
+    );
+    assert_eq!(
+        Err(OperationError::Overflow),
+        ScalarValue::U64(u64::MAX).checked_mul(1i8.into())
+    );
+    assert_eq!(
+        Err(OperationError::DivisionByZero),
+        ScalarValue::I64(1).checked_div(0u8.into())
+    );
+}
+
+#[test]
+fn floating_point_arithmetic() {
+    assert_eq!(
+        Ok(ScalarValue::F32(2.5)),
+        ScalarValue::F32(0.5).checked_add(2u8.into())
+    );
+    assert_eq!(
+        Ok(ScalarValue::F64(-1.5)),
+        ScalarValue::I32(-3).checked_mul(0.5_f32.into())
+    );
+    assert_eq!(
+        Ok(ScalarValue::F64(f64::INFINITY)),
+        ScalarValue::F64(1.0).checked_div(0u8.into())
+    );
+}
+
+#[test]
+fn incompatible_scalars() {
+    assert_eq!(
+        Err(OperationError::IncompatibleTypes {
+            lhs: ScalarType::Bool.into(),
+            rhs: ScalarType::I8.into(),
+        }),
+        ScalarValue::Bool(true).checked_add(1i8.into())
+    );
+    assert!(ScalarValue::Bool(true).try_compare(1.0.into()).is_err());
+}
+
+#[test]
+fn compare_scalars() {
+    assert_eq!(
+        Ok(Some(Ordering::Greater)),
+        ScalarValue::U64(u64::MAX).try_compare((-1i64).into())
+    );
+    assert_eq!(Ok(true), ScalarValue::U8(1).try_eq(1.0_f32.into()));
+    assert_eq!(Ok(true), ScalarValue::I16(2).try_eq(2u32.into()));
+    assert_eq!(
+        Ok(Some(Ordering::Less)),
+        ScalarValue::Bool(false).try_compare(true.into())
+    );
+    assert_eq!(Ok(None), ScalarValue::F64(f64::NAN).try_compare(1.into()));
+    assert_eq!(
+        Ok(false),
+        ScalarValue::F64(f64::NAN).try_eq(f64::NAN.into())
+    );
+}
+
+#[test]
+fn durations() {
+    let sec = Value::Duration(Duration::from_secs(1));
+    assert_eq!(
+        Ok(Value::Duration(Duration::from_secs(2))),
+        sec.checked_add(&sec)
+    );
+    assert_eq!(
+        Err(OperationError::Overflow),
+        Value::Duration(Duration::ZERO).checked_sub(&sec)
+    );
+    assert_eq!(
+        Ok(Value::Duration(Duration::from_secs(3))),
+        sec.checked_mul(&3u8.into())
+    );
+    assert_eq!(
+        Ok(Value::Duration(Duration::from_millis(500))),
+        sec.checked_div(&2u32.into())
+    );
+    assert_eq!(
+        Err(OperationError::DivisionByZero),
+        sec.checked_div(&0u32.into())
+    );
+    assert!(sec.checked_mul(&(-1i32).into()).is_err());
+    assert_eq!(
+        Ok(Some(Ordering::Greater)),
+        sec.try_compare(&Duration::ZERO.into())
+    );
+}
+
+#[test]
+fn compare_values() {
+    assert_eq!(
+        Ok(Some(Ordering::Less)),
+        Value::from("a".to_owned()).try_compare(&"b".to_owned().into())
+    );
+    assert_eq!(Ok(true), Value::from(3u8).try_eq(&3.0.into()));
+    assert_eq!(
+        Err(OperationError::IncompatibleTypes {
+            lhs: ValueType::String,
+            rhs: ValueType::Bytes,
+        }),
+        Value::from(String::new()).try_compare(&Vec::<u8>::new().into())
+    );
+    assert_eq!(
+        Ok(Value::Scalar(ScalarValue::I32(-1))),
+        Value::from(1u16).checked_sub(&2i8.into())
+    );
+}
+
+#[test]
+fn compare_arrays_and_maps() {
+    let array = |values: &[i32]| Value::Array(values.iter().copied().map(Value::from).collect());
+    assert_eq!(
+        Ok(Some(Ordering::Less)),
+        array(&[1, 2]).try_compare(&array(&[1, 3]))
+    );
+    assert_eq!(
+        Ok(Some(Ordering::Greater)),
+        array(&[1, 2]).try_compare(&array(&[1]))
+    );
+    assert_eq!(
+        Ok(true),
+        array(&[1]).try_eq(&Value::Array(vec![Value::from(1.0)]))
+    );
+    assert!(array(&[1])
+        .try_compare(&Value::Array(vec![Value::from(String::new())]))
+        .is_err());
+
+    let map = |value: f64| {
+        Value::Map(BTreeMap::from_iter([
+            ("a".to_owned(), Value::from(1u8)),
+            ("b".to_owned(), Value::from(value)),
+        ]))
+    };
+    assert_eq!(Ok(true), map(2.0).try_eq(&map(2.0)));
+    assert_eq!(Ok(None), map(2.0).try_compare(&map(3.0)));
+    assert_eq!(Ok(None), map(2.0).try_compare(&Value::Map(BTreeMap::new())));
+}
+
+#[test]
+fn decimal_arithmetic() {
+    let dec = |s: &str| ScalarValue::Decimal(s.parse().unwrap());
+    assert_eq!(
+        Some(ScalarType::Decimal),
+        ScalarType::Decimal.promote(ScalarType::U64)
+    );
+    assert_eq!(
+        Some(ScalarType::F64),
+        ScalarType::F32.promote(ScalarType::Decimal)
+    );
+    assert_eq!(None, ScalarType::Decimal.promote(ScalarType::Bool));
+    assert_eq!(Ok(dec("0.3")), dec("0.1").checked_add(dec("0.2")));
+    assert_eq!(Ok(dec("2.5")), dec("0.5").checked_mul(5u8.into()));
+    assert_eq!(
+        Ok(ScalarValue::F64(0.75)),
+        dec("0.5").checked_add(0.25.into())
+    );
+    assert_eq!(
+        Err(OperationError::DivisionByZero),
+        dec("1.5").checked_div(0i32.into())
+    );
+    assert_eq!(
+        Err(OperationError::Overflow),
+        dec("1").checked_add(u64::MAX.into())
+    );
+    assert_eq!(
+        Ok(Some(Ordering::Less)),
+        dec("922337203685477580.7").try_compare(922_337_203_685_477_581_u64.into())
+    );
+    assert_eq!(Ok(true), dec("2.00").try_eq(2u8.into()));
+    assert_eq!(
+        Ok(Some(Ordering::Greater)),
+        dec("0.3").try_compare(0.25.into())
+    );
+}
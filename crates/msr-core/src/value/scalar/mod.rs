@@ -1,5 +1,7 @@
 use std::fmt;
 
+use super::Decimal;
+
 /// Enumeration of scalar value types
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
 #[non_exhaustive]
@@ -15,6 +17,7 @@ pub enum Type {
     I64,
     U64,
     F64,
+    Decimal,
 }
 
 const TYPE_STR_BOOL: &str = "bool";
@@ -28,6 +31,7 @@ const TYPE_STR_F32: &str = "f32";
 const TYPE_STR_I64: &str = "i64";
 const TYPE_STR_U64: &str = "u64";
 const TYPE_STR_F64: &str = "f64";
+const TYPE_STR_DECIMAL: &str = "decimal";
 
 impl Type {
     #[must_use]
@@ -45,6 +49,7 @@ impl Type {
             I64 => TYPE_STR_I64,
             U64 => TYPE_STR_U64,
             F64 => TYPE_STR_F64,
+            Decimal => TYPE_STR_DECIMAL,
         }
     }
 
@@ -63,6 +68,7 @@ impl Type {
             TYPE_STR_I64 => Some(Type::I64),
             TYPE_STR_U64 => Some(Type::U64),
             TYPE_STR_F64 => Some(Type::F64),
+            TYPE_STR_DECIMAL => Some(Type::Decimal),
             _ => None,
         }
     }
@@ -108,6 +114,8 @@ pub enum Value {
     U64(u64),
     /// 64-bit floating-point number (double precision)
     F64(f64),
+    /// Fixed-point decimal number
+    Decimal(Decimal),
 }
 
 impl Value {
@@ -166,6 +174,11 @@ impl Value {
         Self::F64(val)
     }
 
+    #[must_use]
+    pub const fn from_decimal(val: Decimal) -> Self {
+        Self::Decimal(val)
+    }
+
     #[must_use]
     pub const fn to_bool(self) -> Option<bool> {
         match self {
@@ -282,6 +295,21 @@ impl Value {
             _ => None,
         }
     }
+
+    #[must_use]
+    pub fn to_decimal(self) -> Option<Decimal> {
+        match self {
+            Self::I8(val) => Some(i64::from(val).into()),
+            Self::U8(val) => Some(i64::from(val).into()),
+            Self::I16(val) => Some(i64::from(val).into()),
+            Self::U16(val) => Some(i64::from(val).into()),
+            Self::I32(val) => Some(i64::from(val).into()),
+            Self::U32(val) => Some(i64::from(val).into()),
+            Self::I64(val) => Some(val.into()),
+            Self::Decimal(val) => Some(val),
+            _ => None,
+        }
+    }
 }
 
 impl fmt::Display for Value {
@@ -299,6 +327,7 @@ impl fmt::Display for Value {
             I64(val) => write!(f, "{val}"),
             U64(val) => write!(f, "{val}"),
             F64(val) => write!(f, "{val}"),
+            Decimal(val) => write!(f, "{val}"),
         }
     }
 }
@@ -319,6 +348,7 @@ impl Value {
             I64(_) => Type::I64,
             U64(_) => Type::U64,
             F64(_) => Type::F64,
+            Decimal(_) => Type::Decimal,
         }
     }
 }
@@ -395,5 +425,11 @@ impl From<f64> for Value {
     }
 }
 
+impl From<Decimal> for Value {
+    fn from(from: Decimal) -> Self {
+        Self::from_decimal(from)
+    }
+}
+
 #[cfg(test)]
 mod tests;
@@ -14,6 +14,7 @@ fn value_types() {
     assert_eq!(Type::I64, Value::from(-123i64).to_type());
     assert_eq!(Type::U64, Value::from(123u64).to_type());
     assert_eq!(Type::F64, Value::from(1.234).to_type());
+    assert_eq!(Type::Decimal, Value::from(Decimal::from(1)).to_type());
 }
 
 #[test]
@@ -29,4 +30,5 @@ fn try_type_from_str() {
     assert_eq!(Some(Type::I64), Type::try_from_str(TYPE_STR_I64));
     assert_eq!(Some(Type::U64), Type::try_from_str(TYPE_STR_U64));
     assert_eq!(Some(Type::F64), Type::try_from_str(TYPE_STR_F64));
+    assert_eq!(Some(Type::Decimal), Type::try_from_str(TYPE_STR_DECIMAL));
 }
@@ -0,0 +1,250 @@
+//! Serialization of values
+//!
+//! Types are represented by their names. Values are represented
+//! by a map with a single entry, i.e. the name of the type and
+//! the actual value. This keeps the exact type of each value:
+//!
+//! | Type                           | JSON                                            |
+//! |--------------------------------|-------------------------------------------------|
+//! | `ValueType`, `ScalarType`      | `"u16"`, `"duration"`                           |
+//! | `ScalarValue`, `Value::Scalar` | `{"u16":42}`, `{"f64":1.5}`, `{"bool":true}`    |
+//! | `ScalarValue::Decimal`         | `{"decimal":"-0.50"}`                           |
+//! | `Decimal`                      | `"-0.50"`                                       |
+//! | `Value::Duration`              | `{"duration":{"secs":1,"nanos":500000000}}`     |
+//! | `Value::String`                | `{"string":"text"}`                             |
+//! | `Value::Bytes`                 | `{"bytes":"AAEC"}` (standard Base64 encoding)   |
+//! | `Value::Array`                 | `{"array":[{"u8":1},{"string":"a"}]}`           |
+//! | `Value::Map`                   | `{"map":{"a":{"bool":false}}}`                  |
+
+use std::fmt;
+
+use ::serde::{
+    de::{self, IgnoredAny, MapAccess, Unexpected, Visitor},
+    ser::SerializeMap as _,
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine as _};
+
+use super::{
+    Decimal, ScalarType, ScalarValue, Value, ValueType, TYPE_STR_ARRAY, TYPE_STR_BYTES,
+    TYPE_STR_DURATION, TYPE_STR_MAP, TYPE_STR_STRING,
+};
+
+const SCALAR_TYPES: &[&str] = &[
+    ScalarType::Bool.as_str(),
+    ScalarType::I8.as_str(),
+    ScalarType::U8.as_str(),
+    ScalarType::I16.as_str(),
+    ScalarType::U16.as_str(),
+    ScalarType::I32.as_str(),
+    ScalarType::U32.as_str(),
+    ScalarType::F32.as_str(),
+    ScalarType::I64.as_str(),
+    ScalarType::U64.as_str(),
+    ScalarType::F64.as_str(),
+    ScalarType::Decimal.as_str(),
+];
+
+const VALUE_TYPES: &[&str] = &[
+    ScalarType::Bool.as_str(),
+    ScalarType::I8.as_str(),
+    ScalarType::U8.as_str(),
+    ScalarType::I16.as_str(),
+    ScalarType::U16.as_str(),
+    ScalarType::I32.as_str(),
+    ScalarType::U32.as_str(),
+    ScalarType::F32.as_str(),
+    ScalarType::I64.as_str(),
+    ScalarType::U64.as_str(),
+    ScalarType::F64.as_str(),
+    ScalarType::Decimal.as_str(),
+    TYPE_STR_DURATION,
+    TYPE_STR_STRING,
+    TYPE_STR_BYTES,
+    TYPE_STR_ARRAY,
+    TYPE_STR_MAP,
+];
+
+impl Serialize for ScalarType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ScalarType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::try_from_str(&s).ok_or_else(|| de::Error::unknown_variant(&s, SCALAR_TYPES))
+    }
+}
+
+impl Serialize for ValueType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for ValueType {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::try_from_str(&s).ok_or_else(|| de::Error::unknown_variant(&s, VALUE_TYPES))
+    }
+}
+
+/// Decimals are serialized as strings to keep all digits.
+impl Serialize for Decimal {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Decimal {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse()
+            .map_err(|_| de::Error::invalid_value(Unexpected::Str(&s), &"a decimal number"))
+    }
+}
+
+impl Serialize for ScalarValue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use ScalarValue::*;
+        let mut map = serializer.serialize_map(Some(1))?;
+        let key = self.to_type().as_str();
+        match self {
+            Bool(val) => map.serialize_entry(key, val)?,
+            I8(val) => map.serialize_entry(key, val)?,
+            U8(val) => map.serialize_entry(key, val)?,
+            I16(val) => map.serialize_entry(key, val)?,
+            U16(val) => map.serialize_entry(key, val)?,
+            I32(val) => map.serialize_entry(key, val)?,
+            U32(val) => map.serialize_entry(key, val)?,
+            F32(val) => map.serialize_entry(key, val)?,
+            I64(val) => map.serialize_entry(key, val)?,
+            U64(val) => map.serialize_entry(key, val)?,
+            F64(val) => map.serialize_entry(key, val)?,
+            Decimal(val) => map.serialize_entry(key, val)?,
+        }
+        map.end()
+    }
+}
+
+impl Serialize for Value {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if let Self::Scalar(val) = self {
+            return val.serialize(serializer);
+        }
+        let mut map = serializer.serialize_map(Some(1))?;
+        let key = self.to_type().as_str();
+        match self {
+            Self::Scalar(_) => unreachable!(),
+            Self::Duration(val) => map.serialize_entry(key, val)?,
+            Self::String(val) => map.serialize_entry(key, val)?,
+            Self::Bytes(val) => map.serialize_entry(key, &BASE64.encode(val))?,
+            Self::Array(val) => map.serialize_entry(key, val)?,
+            Self::Map(val) => map.serialize_entry(key, val)?,
+        }
+        map.end()
+    }
+}
+
+fn next_scalar_value<'de, A: MapAccess<'de>>(
+    map: &mut A,
+    scalar_type: ScalarType,
+) -> Result<ScalarValue, A::Error> {
+    use ScalarType::*;
+    let val = match scalar_type {
+        Bool => map.next_value::<bool>()?.into(),
+        I8 => map.next_value::<i8>()?.into(),
+        U8 => map.next_value::<u8>()?.into(),
+        I16 => map.next_value::<i16>()?.into(),
+        U16 => map.next_value::<u16>()?.into(),
+        I32 => map.next_value::<i32>()?.into(),
+        U32 => map.next_value::<u32>()?.into(),
+        F32 => map.next_value::<f32>()?.into(),
+        I64 => map.next_value::<i64>()?.into(),
+        U64 => map.next_value::<u64>()?.into(),
+        F64 => map.next_value::<f64>()?.into(),
+        Decimal => map.next_value::<super::Decimal>()?.into(),
+    };
+    Ok(val)
+}
+
+fn end_of_map<'de, A: MapAccess<'de>>(map: &mut A) -> Result<(), A::Error> {
+    if map.next_key::<IgnoredAny>()?.is_some() {
+        return Err(de::Error::custom("expected a single entry"));
+    }
+    Ok(())
+}
+
+struct ScalarValueVisitor;
+
+impl<'de> Visitor<'de> for ScalarValueVisitor {
+    type Value = ScalarValue;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a scalar value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let key = map
+            .next_key::<String>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let scalar_type = ScalarType::try_from_str(&key)
+            .ok_or_else(|| de::Error::unknown_variant(&key, SCALAR_TYPES))?;
+        let val = next_scalar_value(&mut map, scalar_type)?;
+        end_of_map(&mut map)?;
+        Ok(val)
+    }
+}
+
+impl<'de> Deserialize<'de> for ScalarValue {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ScalarValueVisitor)
+    }
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a value")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let key = map
+            .next_key::<String>()?
+            .ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let value_type = ValueType::try_from_str(&key)
+            .ok_or_else(|| de::Error::unknown_variant(&key, VALUE_TYPES))?;
+        let val = match value_type {
+            ValueType::Scalar(scalar_type) => {
+                Value::Scalar(next_scalar_value(&mut map, scalar_type)?)
+            }
+            ValueType::Duration => Value::Duration(map.next_value()?),
+            ValueType::String => Value::String(map.next_value()?),
+            ValueType::Bytes => {
+                let encoded = map.next_value::<String>()?;
+                let decoded = BASE64.decode(&encoded).map_err(|_| {
+                    de::Error::invalid_value(Unexpected::Str(&encoded), &"Base64 encoded bytes")
+                })?;
+                Value::Bytes(decoded)
+            }
+            ValueType::Array => Value::Array(map.next_value()?),
+            ValueType::Map => Value::Map(map.next_value()?),
+        };
+        end_of_map(&mut map)?;
+        Ok(val)
+    }
+}
+
+impl<'de> Deserialize<'de> for Value {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(ValueVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,82 @@
+use std::{collections::BTreeMap, time::Duration};
+
+use super::*;
+
+fn roundtrip(value: &Value, json: &str) {
+    assert_eq!(json, serde_json::to_string(value).unwrap());
+    assert_eq!(*value, serde_json::from_str::<Value>(json).unwrap());
+}
+
+#[test]
+fn types() {
+    assert_eq!(
+        r#"["u16","duration","array"]"#,
+        serde_json::to_string(&[
+            ValueType::Scalar(ScalarType::U16),
+            ValueType::Duration,
+            ValueType::Array
+        ])
+        .unwrap()
+    );
+    assert_eq!(
+        ValueType::Bytes,
+        serde_json::from_str::<ValueType>(r#""bytes""#).unwrap()
+    );
+    assert_eq!(
+        ScalarType::F32,
+        serde_json::from_str::<ScalarType>(r#""f32""#).unwrap()
+    );
+    assert!(serde_json::from_str::<ScalarType>(r#""string""#).is_err());
+}
+
+#[test]
+fn scalar_values() {
+    roundtrip(&Value::from(42u16), r#"{"u16":42}"#);
+    roundtrip(&Value::from(-1i8), r#"{"i8":-1}"#);
+    roundtrip(&Value::from(1.5), r#"{"f64":1.5}"#);
+    roundtrip(&Value::from(true), r#"{"bool":true}"#);
+    roundtrip(
+        &Value::from("-0.50".parse::<Decimal>().unwrap()),
+        r#"{"decimal":"-0.50"}"#,
+    );
+    assert!(serde_json::from_str::<Value>(r#"{"decimal":"0.5.0"}"#).is_err());
+    assert!(serde_json::from_str::<Value>(r#"{"decimal":0.5}"#).is_err());
+    assert_eq!(
+        ScalarValue::U64(7),
+        serde_json::from_str::<ScalarValue>(r#"{"u64":7}"#).unwrap()
+    );
+    assert_eq!(
+        r#"{"i32":-7}"#,
+        serde_json::to_string(&ScalarValue::I32(-7)).unwrap()
+    );
+    // Out of range
+    assert!(serde_json::from_str::<Value>(r#"{"u8":256}"#).is_err());
+    assert!(serde_json::from_str::<ScalarValue>(r#"{"string":""}"#).is_err());
+}
+
+#[test]
+fn complex_values() {
+    roundtrip(
+        &Value::Duration(Duration::from_millis(1500)),
+        r#"{"duration":{"secs":1,"nanos":500000000}}"#,
+    );
+    roundtrip(&Value::from("text".to_owned()), r#"{"string":"text"}"#);
+    roundtrip(&Value::Bytes(vec![0, 1, 2]), r#"{"bytes":"AAEC"}"#);
+    roundtrip(
+        &Value::Array(vec![1u8.into(), "a".to_owned().into()]),
+        r#"{"array":[{"u8":1},{"string":"a"}]}"#,
+    );
+    roundtrip(
+        &Value::Map(BTreeMap::from_iter([("a".to_owned(), false.into())])),
+        r#"{"map":{"a":{"bool":false}}}"#,
+    );
+}
+
+#[test]
+fn invalid_values() {
+    assert!(serde_json::from_str::<Value>("{}").is_err());
+    assert!(serde_json::from_str::<Value>(r#"{"u8":1,"u16":2}"#).is_err());
+    assert!(serde_json::from_str::<Value>(r#"{"bytes":"*"}"#).is_err());
+    assert!(serde_json::from_str::<Value>(r#"{"unknown":1}"#).is_err());
+    assert!(serde_json::from_str::<Value>("1").is_err());
+}
@@ -162,6 +162,9 @@ impl Worker for CyclicWorker {
                         ProgressHint::Finish => {
                             return Ok(CompletionStatus::Finishing);
                         }
+                        ProgressHint::Abort => {
+                            return Ok(CompletionStatus::Aborting);
+                        }
                     };
                 }
                 CyclicWorkerTiming::Waiting => {
@@ -174,6 +177,9 @@ impl Worker for CyclicWorker {
                             ProgressHint::Finish => {
                                 return Ok(CompletionStatus::Finishing);
                             }
+                            ProgressHint::Abort => {
+                                return Ok(CompletionStatus::Aborting);
+                            }
                         };
                     }
                 }
@@ -5,6 +5,7 @@
 //! let cfg = BangBangConfig {
 //!     default_threshold: 5.8,
 //!     hysteresis: 0.1,
+//!     ..Default::default()
 //! };
 //! let mut c = BangBang::new(cfg);
 //!
@@ -14,8 +15,29 @@
 //! assert!(c.next(5.71));
 //! assert!(!c.next(5.69));
 //! ```
+//!
+//! Minimum on/off times and the minimum cycle time
+//! require the time step variant:
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, bang_bang::*};
+//!
+//! let cfg = BangBangConfig {
+//!     default_threshold: 5.8,
+//!     min_on_time: Duration::from_secs(60),
+//!     ..Default::default()
+//! };
+//! let mut c = BangBang::new(cfg);
+//! let delta_t = Duration::from_secs(10);
+//!
+//! assert!(c.next(6.0, &delta_t));
+//! assert!(c.next(5.0, &delta_t)); // locked for another 50 seconds
+//! assert_eq!(c.state().lockout, Duration::from_secs(50));
+//! ```
 
 use super::{Controller, PureController};
+use std::time::Duration;
 
 /// A Bang-bang controller implementation
 #[derive(Debug, Clone)]
@@ -27,15 +49,30 @@ pub struct BangBang {
 /// Bang-bang controller configuration
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct BangBangConfig {
     pub default_threshold: f64,
     pub hysteresis: f64,
+    /// Minimum time the output stays switched on
+    pub min_on_time: Duration,
+    /// Minimum time the output stays switched off
+    pub min_off_time: Duration,
+    /// Minimum time between two switch-on events
+    /// (i.e. the maximum switching frequency)
+    pub min_cycle_time: Duration,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BangBangState {
     pub current: bool,
     pub threshold: f64,
+    /// Remaining time until the output may be switched again
+    pub lockout: Duration,
+    /// Time since the last switching event
+    pub last_switch: Option<Duration>,
+    /// Time since the last switch-on event
+    pub last_switch_on: Option<Duration>,
 }
 
 impl Default for BangBangState {
@@ -43,6 +80,9 @@ impl Default for BangBangState {
         BangBangState {
             current: false,
             threshold: 0.0,
+            lockout: Duration::ZERO,
+            last_switch: None,
+            last_switch_on: None,
         }
     }
 }
@@ -52,6 +92,9 @@ impl Default for BangBangConfig {
         BangBangConfig {
             default_threshold: 0.0,
             hysteresis: 0.0,
+            min_on_time: Duration::ZERO,
+            min_off_time: Duration::ZERO,
+            min_cycle_time: Duration::ZERO,
         }
     }
 }
@@ -65,6 +108,25 @@ impl BangBang {
         };
         BangBang { cfg, state }
     }
+    /// The current controller state.
+    pub fn state(&self) -> &BangBangState {
+        &self.state
+    }
+}
+
+impl BangBangConfig {
+    /// Remaining time until the current output may be switched.
+    fn lockout(&self, state: &BangBangState) -> Duration {
+        let since = |t: Option<Duration>, min: Duration| {
+            t.map(|t| min.saturating_sub(t)).unwrap_or(Duration::ZERO)
+        };
+        if state.current {
+            since(state.last_switch, self.min_on_time)
+        } else {
+            since(state.last_switch, self.min_off_time)
+                .max(since(state.last_switch_on, self.min_cycle_time))
+        }
+    }
 }
 
 impl Controller<f64, bool> for BangBang {
@@ -86,6 +148,36 @@ impl PureController<(BangBangState, f64), BangBangState> for BangBangConfig {
     }
 }
 
+impl Controller<(f64, &Duration), bool> for BangBang {
+    fn next(&mut self, input: (f64, &Duration)) -> bool {
+        let (actual, duration) = input;
+        self.state = self.cfg.next((self.state, actual, duration));
+        self.state.current
+    }
+}
+
+/// The output is only switched if the minimum on/off times
+/// and the minimum cycle time have elapsed.
+impl PureController<(BangBangState, f64, &Duration), BangBangState> for BangBangConfig {
+    fn next(&self, input: (BangBangState, f64, &Duration)) -> BangBangState {
+        let (mut state, actual, duration) = input;
+        state.last_switch = state.last_switch.map(|t| t + *duration);
+        state.last_switch_on = state.last_switch_on.map(|t| t + *duration);
+
+        let previous = state.current;
+        let desired = self.next((state, actual)).current;
+        if desired != previous && self.lockout(&state).is_zero() {
+            state.current = desired;
+            state.last_switch = Some(Duration::ZERO);
+            if desired {
+                state.last_switch_on = Some(Duration::ZERO);
+            }
+        }
+        state.lockout = self.lockout(&state);
+        state
+    }
+}
+
 #[cfg(test)]
 #[allow(clippy::float_cmp)]
 mod tests {
@@ -188,4 +280,56 @@ mod tests {
         assert!(!bb.next(-0.6));
         assert!(!bb.next(NAN));
     }
+
+    #[test]
+    fn keep_minimum_on_and_off_times() {
+        let cfg = BangBangConfig {
+            min_on_time: Duration::from_secs(3),
+            min_off_time: Duration::from_secs(2),
+            ..Default::default()
+        };
+        let mut bb = BangBang::new(cfg);
+        let dt = Duration::from_secs(1);
+        let states = vec![
+            (1.0, true),
+            (-1.0, true),
+            (-1.0, true),
+            (-1.0, false),
+            (1.0, false),
+            (1.0, true),
+            (-1.0, true),
+        ];
+        for (input, output) in states {
+            assert_eq!(bb.next((input, &dt)), output);
+        }
+        assert_eq!(bb.state().lockout, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn limit_switching_frequency() {
+        let cfg = BangBangConfig {
+            min_cycle_time: Duration::from_secs(4),
+            ..Default::default()
+        };
+        let mut bb = BangBang::new(cfg);
+        let dt = Duration::from_secs(1);
+        assert!(bb.next((1.0, &dt)));
+        assert_eq!(bb.state().lockout, Duration::ZERO);
+        assert!(!bb.next((-1.0, &dt)));
+        assert_eq!(bb.state().lockout, Duration::from_secs(3));
+        assert!(!bb.next((1.0, &dt)));
+        assert!(!bb.next((1.0, &dt)));
+        assert!(bb.next((1.0, &dt)));
+    }
+
+    #[test]
+    fn ignore_guards_without_time_steps() {
+        let cfg = BangBangConfig {
+            min_on_time: Duration::from_secs(3),
+            ..Default::default()
+        };
+        let mut bb = BangBang::new(cfg);
+        assert!(bb.next(1.0));
+        assert!(!bb.next(-1.0));
+    }
 }
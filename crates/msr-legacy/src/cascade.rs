@@ -0,0 +1,221 @@
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, cascade::*, pid::PidConfig};
+//!
+//! // The outer loop controls the temperature and
+//! // calculates the setpoint for the inner flow loop.
+//! let cfg = CascadeConfig {
+//!     outer: PidConfig {
+//!         k_p: 2.0,
+//!         k_i: 0.1,
+//!         default_target: 80.0,
+//!         min: Some(0.0),  // minimum flow setpoint
+//!         max: Some(12.0), // maximum flow setpoint
+//!         ..Default::default()
+//!     },
+//!     inner: PidConfig {
+//!         k_p: 0.5,
+//!         k_i: 1.0,
+//!         min: Some(0.0),   // valve closed
+//!         max: Some(100.0), // valve open
+//!         ..Default::default()
+//!     },
+//! };
+//! let mut c = Cascade::new(cfg);
+//!
+//! let delta_t = Duration::from_millis(500);
+//! let temperature = 65.0;
+//! let flow = 4.0;
+//! let valve = c.next((temperature, flow), &delta_t);
+//! ```
+
+use super::{pid::*, Controller, PureController};
+use std::time::Duration;
+
+/// Cascade of two PID controllers
+///
+/// The output of the outer controller is used as the target of
+/// the inner controller. The output limits of the outer controller
+/// constrain the inner setpoint and the output limits of the inner
+/// controller constrain the actuator value.
+///
+/// The integral portion of the outer controller is frozen while the
+/// inner controller (or the outer controller itself) is saturated in
+/// the direction the outer controller is pushing (anti-windup).
+#[derive(Debug, Clone)]
+pub struct Cascade {
+    cfg: CascadeConfig,
+    /// Current cascade state
+    pub state: CascadeState,
+}
+
+/// Cascade configuration
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CascadeConfig {
+    /// The outer (master) controller
+    pub outer: PidConfig,
+    /// The inner (slave) controller
+    pub inner: PidConfig,
+}
+
+/// Internal cascade state
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CascadeState {
+    /// State of the outer controller
+    pub outer: PidState,
+    /// State of the inner controller
+    pub inner: PidState,
+}
+
+impl Cascade {
+    /// Create a new cascade controller instance.
+    pub fn new(cfg: CascadeConfig) -> Self {
+        let state = CascadeState {
+            outer: PidState {
+                target: cfg.outer.default_target,
+                ..Default::default()
+            },
+            inner: PidState {
+                target: cfg.inner.default_target,
+                ..Default::default()
+            },
+        };
+        Cascade { cfg, state }
+    }
+    /// Set the target of the outer controller.
+    pub fn set_target(&mut self, target: f64) {
+        self.state.outer.target = target;
+    }
+    /// Reset the internal controller states.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.cfg.clone());
+    }
+}
+
+/// The input consists of the actual values of the outer and the inner loop.
+impl Controller<((f64, f64), &Duration), f64> for Cascade {
+    fn next(&mut self, input: ((f64, f64), &Duration)) -> f64 {
+        let ((outer_actual, inner_actual), duration) = input;
+        let (state, result) = self
+            .cfg
+            .next((self.state, outer_actual, inner_actual, duration));
+        self.state = state;
+        result
+    }
+}
+
+impl PureController<(CascadeState, f64, f64, &Duration), (CascadeState, f64)> for CascadeConfig {
+    fn next(&self, input: (CascadeState, f64, f64, &Duration)) -> (CascadeState, f64) {
+        let (state, outer_actual, inner_actual, duration) = input;
+
+        let (mut outer, inner_target) = self.outer.next((state.outer, outer_actual, duration));
+
+        let inner = PidState {
+            target: inner_target,
+            ..state.inner
+        };
+        let (inner, result) = self.inner.next((inner, inner_actual, duration));
+
+        let err = outer.target - outer_actual;
+        let saturated = |value: f64, cfg: &PidConfig| {
+            (err > 0.0 && cfg.max.map(|max| value >= max).unwrap_or(false))
+                || (err < 0.0 && cfg.min.map(|min| value <= min).unwrap_or(false))
+        };
+        if saturated(inner_target, &self.outer) || saturated(result, &self.inner) {
+            outer.i = state.outer.i;
+        }
+
+        (CascadeState { outer, inner }, result)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn outer_output_is_inner_target() {
+        let cfg = CascadeConfig {
+            outer: PidConfig {
+                k_p: 2.0,
+                default_target: 10.0,
+                ..Default::default()
+            },
+            inner: PidConfig {
+                k_p: 3.0,
+                ..Default::default()
+            },
+        };
+        let mut c = Cascade::new(cfg);
+        let dt = Duration::from_secs(1);
+        assert_eq!(c.next(((8.0, 1.0), &dt)), 9.0);
+        assert_eq!(c.state.inner.target, 4.0);
+        c.set_target(9.0);
+        assert_eq!(c.next(((8.0, 1.0), &dt)), 3.0);
+        assert_eq!(c.state.inner.target, 2.0);
+    }
+
+    #[test]
+    fn limit_inner_target_and_output() {
+        let cfg = CascadeConfig {
+            outer: PidConfig {
+                k_p: 10.0,
+                default_target: 10.0,
+                max: Some(5.0),
+                ..Default::default()
+            },
+            inner: PidConfig {
+                k_p: 10.0,
+                max: Some(20.0),
+                ..Default::default()
+            },
+        };
+        let mut c = Cascade::new(cfg);
+        let dt = Duration::from_secs(1);
+        assert_eq!(c.next(((0.0, 4.0), &dt)), 10.0);
+        assert_eq!(c.state.inner.target, 5.0);
+        assert_eq!(c.next(((0.0, 0.0), &dt)), 20.0);
+    }
+
+    #[test]
+    fn freeze_outer_integral_while_inner_is_saturated() {
+        let cfg = CascadeConfig {
+            outer: PidConfig {
+                k_p: 0.0,
+                k_i: 1.0,
+                default_target: 10.0,
+                ..Default::default()
+            },
+            inner: PidConfig {
+                k_p: 1.0,
+                max: Some(3.0),
+                ..Default::default()
+            },
+        };
+        let mut c = Cascade::new(cfg);
+        let dt = Duration::from_secs(1);
+        // inner output is not saturated
+        assert_eq!(c.next(((9.0, 0.0), &dt)), 1.0);
+        assert_eq!(c.state.outer.i, 1.0);
+        assert_eq!(c.next(((9.0, 0.0), &dt)), 2.0);
+        assert_eq!(c.state.outer.i, 2.0);
+        // inner output is saturated
+        assert_eq!(c.next(((9.0, 0.0), &dt)), 3.0);
+        assert_eq!(c.state.outer.i, 2.0);
+        assert_eq!(c.next(((9.0, 0.0), &dt)), 3.0);
+        assert_eq!(c.state.outer.i, 2.0);
+        // the outer controller is now reducing its output
+        assert_eq!(c.next(((11.0, 0.0), &dt)), 1.0);
+        assert_eq!(c.state.outer.i, 1.0);
+        c.reset();
+        assert_eq!(c.state.outer.i, 0.0);
+        assert_eq!(c.state.outer.target, 10.0);
+    }
+}
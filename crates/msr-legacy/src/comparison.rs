@@ -1,11 +1,52 @@
 use super::*;
 use std::{
     f64::EPSILON,
-    io::{Error, Result},
+    io::{Error, ErrorKind, Result},
 };
 
+/// The tolerance of an approximate comparison
+///
+/// Tolerances are equal if their values are bitwise equal.
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Tolerance {
+    /// `|a - b| <= epsilon`
+    Absolute(f64),
+    /// `|a - b| <= percent / 100 * max(|a|, |b|)`
+    Percent(f64),
+}
+
+impl PartialEq for Tolerance {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Tolerance::Absolute(a), Tolerance::Absolute(b))
+            | (Tolerance::Percent(a), Tolerance::Percent(b)) => a.to_bits() == b.to_bits(),
+            _ => false,
+        }
+    }
+}
+
+impl Eq for Tolerance {}
+
+impl Tolerance {
+    /// Check if two values are equal within the tolerance.
+    ///
+    /// NaN is never approximately equal to any value.
+    pub fn is_approx_eq(self, a: f64, b: f64) -> bool {
+        let epsilon = match self {
+            Tolerance::Absolute(epsilon) => epsilon,
+            Tolerance::Percent(percent) => percent / 100.0 * a.abs().max(b.abs()),
+        };
+        // Infinite values only match themselves
+        #[allow(clippy::float_cmp)]
+        let identical = a == b;
+        identical || (a - b).abs() <= epsilon
+    }
+}
+
 /// Comperators
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Comparator {
     /// `<` or `LT` (Less Than)
     Less,
@@ -19,10 +60,18 @@ pub enum Comparator {
     Equal,
     /// `!=` or `NE` (Not Equal)
     NotEqual,
+    /// `~=` (Approximately Equal)
+    ///
+    /// Avoids flapping of conditions on analog values
+    /// due to noise or rounding errors.
+    ApproxEqual(Tolerance),
+    /// `!~=` (Not Approximately Equal)
+    NotApproxEqual(Tolerance),
 }
 
 /// A comparison between two data sources
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Comparison {
     pub(crate) left: Source,
     pub(crate) cmp: Comparator,
@@ -32,198 +81,179 @@ pub struct Comparison {
 impl Evaluation<SystemState> for Comparison {
     type Output = bool;
     fn eval(&self, state: &SystemState) -> Result<bool> {
-        use crate::Comparator::*;
-        use crate::ErrorKind::*;
-        use crate::Value::*;
         let left = get_val(&self.left, state)?;
         let right = get_val(&self.right, state)?;
-        let res = match left {
-            Bit(a) => match right {
-                Bit(b) => match self.cmp {
-                    Equal => a == b,
-                    NotEqual => a != b,
-                    _ => {
-                        return Err(Error::new(
-                            InvalidInput,
-                            format!("Bits can't be compared with a '{:?}' comparator", self.cmp),
-                        ));
-                    }
-                },
-                Timeout(t) => {
-                    let timed_out = *t == Duration::new(0, 0);
-                    match self.cmp {
-                        Equal => *a == timed_out,
-                        NotEqual => *a != timed_out,
-                        _ => {
-                            return Err(Error::new(
-                                InvalidInput,
-                                format!(
-                                    "Bits can't be compared with a '{:?}' comparator",
-                                    self.cmp
-                                ),
-                            ));
-                        }
-                    }
-                }
-                _ => {
-                    return Err(Error::new(
-                        InvalidInput,
-                        "Bits can only compared with other bits or timeouts",
-                    ));
-                }
-            },
-            Decimal(a) => {
-                if let Decimal(b) = right {
-                    match self.cmp {
-                        Less => a < b,
-                        LessOrEqual => a <= b,
-                        Greater => a > b,
-                        GreaterOrEqual => a >= b,
-                        Equal => (a - b).abs() < EPSILON,
-                        NotEqual => (a - b).abs() > EPSILON,
-                    }
-                } else {
-                    return Err(Error::new(
-                        InvalidInput,
-                        "Decimal values can only compared with other decimals",
-                    ));
-                }
-            }
-            Integer(a) => {
-                if let Integer(b) = right {
-                    match self.cmp {
-                        Less => a < b,
-                        LessOrEqual => a <= b,
-                        Greater => a > b,
-                        GreaterOrEqual => a >= b,
-                        Equal => a == b,
-                        NotEqual => a != b,
-                    }
-                } else {
-                    return Err(Error::new(
-                        InvalidInput,
-                        "Integer values can only compared with other integers",
-                    ));
-                }
-            }
+        compare(left.as_ref(), self.cmp, right.as_ref())
+    }
+}
 
-            Text(a) => {
-                if let Text(b) = right {
-                    match self.cmp {
-                        Equal => a == b,
-                        NotEqual => a != b,
-                        _ => {
-                            return Err(Error::new(
-                                InvalidInput,
-                                format!(
-                                    "Text values can't be compared with a '{:?}' comparator",
-                                    self.cmp
-                                ),
-                            ));
-                        }
-                    }
-                } else {
-                    return Err(Error::new(
-                        InvalidInput,
-                        "Text values can only compared with other text",
-                    ));
-                }
+/// Compare two values.
+///
+/// | left \ right | Bit           | Decimal | Integer | Text       | Bin        | Timeout       |
+/// |--------------|---------------|---------|---------|------------|------------|---------------|
+/// | Bit          | `==` `!=`     | error   | error   | error      | error      | `==` `!=` (1) |
+/// | Decimal      | error         | all     | all (2) | error      | error      | error         |
+/// | Integer      | error         | all (2) | all     | error      | error      | error         |
+/// | Text         | error         | error   | error   | `==` `!=`  | error      | error         |
+/// | Bin          | error         | error   | error   | error      | `==` `!=`  | error         |
+/// | Timeout      | `==` `!=` (1) | error   | error   | error      | error      | all but `~=`  |
+///
+/// 1. A timeout is equal to `true` if it has elapsed.
+/// 2. The integer is promoted to a decimal. Integers with
+///    a magnitude above 2^53 might lose precision.
+///
+/// Decimals are considered to be equal if they differ by less
+/// than [`f64::EPSILON`]. Comparisons with NaN are always `false`,
+/// except for `!=` and `!~=`.
+///
+/// All other combinations, e.g. bits and numbers, are rejected
+/// with an [`ErrorKind::InvalidInput`](std::io::ErrorKind::InvalidInput) error.
+pub fn compare(left: &Value, cmp: Comparator, right: &Value) -> Result<bool> {
+    use crate::Value::*;
+    match (left, right) {
+        (Decimal(a), Decimal(b)) => Ok(compare_decimals(*a, cmp, *b)),
+        (Decimal(a), Integer(b)) => Ok(compare_decimals(*a, cmp, *b as f64)),
+        (Integer(a), Decimal(b)) => Ok(compare_decimals(*a as f64, cmp, *b)),
+        (Integer(a), Integer(b)) => Ok(match cmp {
+            Comparator::ApproxEqual(tol) => tol.is_approx_eq(*a as f64, *b as f64),
+            Comparator::NotApproxEqual(tol) => !tol.is_approx_eq(*a as f64, *b as f64),
+            cmp => compare_ordered(a, cmp, b),
+        }),
+        (Timeout(a), Timeout(b)) => match cmp {
+            Comparator::ApproxEqual(_) | Comparator::NotApproxEqual(_) => {
+                Err(unsupported_comparator(left, cmp))
             }
+            cmp => Ok(compare_ordered(a, cmp, b)),
+        },
+        (Bit(a), Bit(b)) => compare_equality(left, a == b, cmp),
+        (Bit(a), Timeout(t)) | (Timeout(t), Bit(a)) => {
+            compare_equality(left, *a == t.is_zero(), cmp)
+        }
+        (Text(a), Text(b)) => compare_equality(left, a == b, cmp),
+        (Bin(a), Bin(b)) => compare_equality(left, a == b, cmp),
+        _ => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!(
+                "{} can't be compared with {}",
+                type_name(left),
+                type_name(right).to_lowercase()
+            ),
+        )),
+    }
+}
 
-            Bin(a) => {
-                if let Bin(b) = right {
-                    match self.cmp {
-                        Equal => a == b,
-                        NotEqual => a != b,
-                        _ => {
-                            return Err(Error::new(
-                                InvalidInput,
-                                format!(
-                                    "Binary data can't be compared with a '{:?}' comparator",
-                                    self.cmp
-                                ),
-                            ));
-                        }
-                    }
-                } else {
-                    return Err(Error::new(
-                        InvalidInput,
-                        "Binary data can only compared with other binary data",
-                    ));
-                }
-            }
-            Timeout(a) => match right {
-                Timeout(b) => match self.cmp {
-                    Less => a < b,
-                    LessOrEqual => a <= b,
-                    Greater => a > b,
-                    GreaterOrEqual => a >= b,
-                    Equal => a == b,
-                    NotEqual => a != b,
-                },
-                Bit(b) => {
-                    let timed_out = *a == Duration::new(0, 0);
-                    match self.cmp {
-                        Equal => timed_out == *b,
-                        NotEqual => timed_out != *b,
-                        _ => {
-                            return Err(Error::new(
-                                InvalidInput,
-                                format!(
-                                    "Binary data can't be compared with a '{:?}' comparator",
-                                    self.cmp
-                                ),
-                            ));
-                        }
-                    }
-                }
-                _ => {
-                    return Err(Error::new(
-                        InvalidInput,
-                        "Timeouts can only compared with other timeouts or boolan",
-                    ));
-                }
-            },
-        };
-        Ok(res)
+fn compare_decimals(a: f64, cmp: Comparator, b: f64) -> bool {
+    use crate::Comparator::*;
+    match cmp {
+        Equal => (a - b).abs() < EPSILON,
+        NotEqual => !compare_decimals(a, Equal, b),
+        ApproxEqual(tol) => tol.is_approx_eq(a, b),
+        NotApproxEqual(tol) => !tol.is_approx_eq(a, b),
+        cmp => compare_ordered(&a, cmp, &b),
+    }
+}
+
+fn compare_ordered<T: PartialOrd>(a: &T, cmp: Comparator, b: &T) -> bool {
+    use crate::Comparator::*;
+    match cmp {
+        Less => a < b,
+        LessOrEqual => a <= b,
+        Greater => a > b,
+        GreaterOrEqual => a >= b,
+        Equal => a == b,
+        NotEqual => a != b,
+        ApproxEqual(_) | NotApproxEqual(_) => unreachable!(),
+    }
+}
+
+fn compare_equality(left: &Value, eq: bool, cmp: Comparator) -> Result<bool> {
+    match cmp {
+        Comparator::Equal => Ok(eq),
+        Comparator::NotEqual => Ok(!eq),
+        cmp => Err(unsupported_comparator(left, cmp)),
     }
 }
 
-fn get_val<'a>(src: &'a Source, state: &'a SystemState) -> Result<&'a Value> {
+fn unsupported_comparator(left: &Value, cmp: Comparator) -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        format!(
+            "{} can't be compared with a '{cmp:?}' comparator",
+            type_name(left)
+        ),
+    )
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Bit(_) => "Bits",
+        Value::Decimal(_) => "Decimal values",
+        Value::Integer(_) => "Integer values",
+        Value::Text(_) => "Text values",
+        Value::Bin(_) => "Binary data",
+        Value::Timeout(_) => "Timeouts",
+    }
+}
+
+pub(crate) fn get_val<'a>(src: &'a Source, state: &'a SystemState) -> Result<Cow<'a, Value>> {
     use crate::ErrorKind::*;
     use crate::Source::*;
     match src {
-        In(ref id) => state.io.inputs.get(id).ok_or_else(|| {
-            Error::new(
-                NotFound,
-                format!("The state of input '{id}' does not exist"),
-            )
-        }),
-        Out(ref id) => state.io.outputs.get(id).ok_or_else(|| {
-            Error::new(
-                NotFound,
-                format!("The state of output '{id}' does not exist"),
-            )
-        }),
-        Mem(ref id) => state.io.mem.get(id).ok_or_else(|| {
-            Error::new(
-                NotFound,
-                format!("The state of memory '{id}' does not exist"),
-            )
-        }),
-        Setpoint(ref id) => state.setpoints.get(id).ok_or_else(|| {
-            Error::new(
-                NotFound,
-                format!("The state of setpoint '{id}' does not exist"),
-            )
-        }),
-        Timeout(ref id) => state.timeouts.get(id).ok_or_else(|| {
-            Error::new(
-                NotFound,
-                format!("The state of timeout '{id}' does not exist"),
-            )
-        }),
-        Const(ref v) => Ok(v),
+        In(ref id) => state
+            .io
+            .inputs
+            .get(id)
+            .ok_or_else(|| {
+                Error::new(
+                    NotFound,
+                    format!("The state of input '{id}' does not exist"),
+                )
+            })
+            .map(Cow::Borrowed),
+        Out(ref id) => state
+            .io
+            .outputs
+            .get(id)
+            .ok_or_else(|| {
+                Error::new(
+                    NotFound,
+                    format!("The state of output '{id}' does not exist"),
+                )
+            })
+            .map(Cow::Borrowed),
+        Mem(ref id) => state
+            .io
+            .mem
+            .get(id)
+            .ok_or_else(|| {
+                Error::new(
+                    NotFound,
+                    format!("The state of memory '{id}' does not exist"),
+                )
+            })
+            .map(Cow::Borrowed),
+        Setpoint(ref id) => state
+            .setpoints
+            .get(id)
+            .ok_or_else(|| {
+                Error::new(
+                    NotFound,
+                    format!("The state of setpoint '{id}' does not exist"),
+                )
+            })
+            .map(Cow::Borrowed),
+        Timeout(ref id) => state
+            .timeouts
+            .get(id)
+            .ok_or_else(|| {
+                Error::new(
+                    NotFound,
+                    format!("The state of timeout '{id}' does not exist"),
+                )
+            })
+            .map(Cow::Borrowed),
+        Const(ref v) => Ok(Cow::Borrowed(v)),
+        Expr(ref e) => e.eval(state).map(Cow::Owned),
     }
 }
 
@@ -268,6 +298,12 @@ mod tests {
         assert_eq!(gt.left, input);
         assert_eq!(gt.right, val);
         assert_eq!(gt.cmp, Greater);
+
+        let tol = Tolerance::Absolute(0.5);
+        let approx_eq = input.clone().cmp_approx_eq(val.clone(), tol);
+        assert_eq!(approx_eq.cmp, ApproxEqual(tol));
+        let approx_ne = input.cmp_approx_ne(val, tol);
+        assert_eq!(approx_ne.cmp, NotApproxEqual(tol));
     }
 
     #[test]
@@ -295,12 +331,73 @@ mod tests {
             (5.4.into(), Less, 6.0.into(), true),
             (5.4.into(), Equal, 5.4.into(), true),
             (5.4.into(), LessOrEqual, 6.0.into(), true),
+            (5.0.into(), Equal, 5.into(), true),
+            (5.5.into(), Greater, 5.into(), true),
+            (f64::NAN.into(), NotEqual, f64::NAN.into(), true),
+            (f64::NAN.into(), Equal, f64::NAN.into(), false),
+            (f64::NAN.into(), LessOrEqual, 5.into(), false),
         ];
         let err_tests: Vec<(Value, Comparator, Value)> = vec![
             (5.0.into(), Equal, true.into()),
             (5.0.into(), Equal, "5.0".to_string().into()),
             (5.0.into(), Equal, vec![0x05_u8].into()),
-            (5.0.into(), Equal, 5.into()),
+            (5.0.into(), Equal, Duration::from_secs(5).into()),
+        ];
+        run_cmp_ok_tests(ok_tests);
+        run_cmp_err_tests(err_tests);
+    }
+
+    #[test]
+    fn compare_tolerances() {
+        fn assert_eq_impl<T: Eq>() {}
+        assert_eq_impl::<Comparator>();
+        assert_eq!(Tolerance::Absolute(0.1), Tolerance::Absolute(0.1));
+        assert_ne!(Tolerance::Absolute(0.1), Tolerance::Percent(0.1));
+        assert_ne!(Tolerance::Absolute(0.1), Tolerance::Absolute(0.2));
+        assert_eq!(Tolerance::Percent(f64::NAN), Tolerance::Percent(f64::NAN));
+    }
+
+    #[test]
+    fn evaluate_approximate_comparison() {
+        let abs = Tolerance::Absolute(0.1);
+        let pct = Tolerance::Percent(1.0);
+        let ok_tests: Vec<(Value, Comparator, Value, bool)> = vec![
+            (5.0.into(), ApproxEqual(abs), 5.05.into(), true),
+            (5.0.into(), ApproxEqual(abs), 4.95.into(), true),
+            (5.0.into(), ApproxEqual(abs), 5.2.into(), false),
+            (5.0.into(), NotApproxEqual(abs), 5.2.into(), true),
+            (5.0.into(), NotApproxEqual(abs), 5.0.into(), false),
+            (200.0.into(), ApproxEqual(pct), 198.5.into(), true),
+            (200.0.into(), ApproxEqual(pct), 197.0.into(), false),
+            (0.0.into(), ApproxEqual(pct), 0.0.into(), true),
+            (100.into(), ApproxEqual(pct), 101.into(), true),
+            (
+                100.into(),
+                NotApproxEqual(Tolerance::Absolute(0.5)),
+                101.into(),
+                true,
+            ),
+            (f64::NAN.into(), ApproxEqual(abs), f64::NAN.into(), false),
+            (f64::NAN.into(), NotApproxEqual(abs), 1.0.into(), true),
+            (
+                f64::INFINITY.into(),
+                ApproxEqual(abs),
+                f64::INFINITY.into(),
+                true,
+            ),
+        ];
+        let err_tests: Vec<(Value, Comparator, Value)> = vec![
+            (true.into(), ApproxEqual(abs), true.into()),
+            (
+                "a".to_string().into(),
+                ApproxEqual(abs),
+                "a".to_string().into(),
+            ),
+            (
+                Duration::from_millis(5).into(),
+                ApproxEqual(abs),
+                Duration::from_millis(5).into(),
+            ),
         ];
         run_cmp_ok_tests(ok_tests);
         run_cmp_err_tests(err_tests);
@@ -397,9 +494,13 @@ mod tests {
             (6.into(), GreaterOrEqual, 5.into(), true),
             (5.into(), Less, 5.into(), false),
             (4.into(), Equal, 4.into(), true),
+            (5.into(), Equal, 5.0.into(), true),
+            (5.into(), Less, 5.1.into(), true),
+            (5.into(), NotEqual, 5.1.into(), true),
+            (i64::MAX.into(), Greater, i64::MAX.into(), false),
         ];
         let err_tests: Vec<(Value, Comparator, Value)> = vec![
-            (5.into(), Equal, 5.0.into()),
+            (5.into(), Equal, Duration::from_secs(5).into()),
             (5.into(), Equal, "5".to_string().into()),
             (5.into(), Equal, vec![0x05_u8].into()),
             (1.into(), Equal, true.into()),
@@ -459,6 +560,67 @@ mod tests {
         run_cmp_err_tests(err_tests);
     }
 
+    #[test]
+    fn comparison_matrix() {
+        use crate::Value as V;
+        let values = [
+            V::Bit(true),
+            V::Decimal(1.0),
+            V::Integer(1),
+            V::Text("1".into()),
+            V::Bin(vec![1]),
+            V::Timeout(Duration::ZERO),
+        ];
+        let tol = Tolerance::Absolute(0.1);
+        let comparators = [
+            Less,
+            LessOrEqual,
+            Greater,
+            GreaterOrEqual,
+            Equal,
+            NotEqual,
+            ApproxEqual(tol),
+            NotApproxEqual(tol),
+        ];
+        // Supported comparators (by index) for each pair of types
+        let eq: &[usize] = &[4, 5];
+        let all: &[usize] = &[0, 1, 2, 3, 4, 5, 6, 7];
+        let ordered: &[usize] = &[0, 1, 2, 3, 4, 5];
+        let none: &[usize] = &[];
+        let matrix = [
+            [eq, none, none, none, none, eq],
+            [none, all, all, none, none, none],
+            [none, all, all, none, none, none],
+            [none, none, none, eq, none, none],
+            [none, none, none, none, eq, none],
+            [eq, none, none, none, none, ordered],
+        ];
+        for (row, left) in values.iter().enumerate() {
+            for (col, right) in values.iter().enumerate() {
+                for (i, cmp) in comparators.iter().enumerate() {
+                    let res = compare(left, *cmp, right);
+                    assert_eq!(
+                        res.is_ok(),
+                        matrix[row][col].contains(&i),
+                        "{left:?} {cmp:?} {right:?}: {res:?}"
+                    );
+                    if let Err(err) = res {
+                        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+                    }
+                }
+            }
+        }
+        // All supported combinations of these values are equal
+        assert!(compare(&V::Decimal(1.0), Equal, &V::Integer(1)).unwrap());
+        assert!(compare(&V::Bit(true), Equal, &V::Timeout(Duration::ZERO)).unwrap());
+        assert_eq!(
+            compare(&V::Bit(true), Equal, &V::Integer(1))
+                .unwrap_err()
+                .to_string(),
+            "Bits can't be compared with integer values"
+        );
+    }
+
     fn run_cmp_ok_tests(ok_tests: Vec<(Value, Comparator, Value, bool)>) {
         let mut state = SystemState::default();
         let left = In("x".into());
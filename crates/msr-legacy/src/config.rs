@@ -0,0 +1,623 @@
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(feature = "toml")]
+//! # {
+//! use msr_legacy::config::RuntimeConfig;
+//!
+//! let cfg = RuntimeConfig::from_toml(
+//!     r#"
+//! inputs = ["temperature"]
+//!
+//! [[rules]]
+//! id = "overheated"
+//! actions = ["alarm"]
+//! condition.Eval = { left.In = "temperature", cmp = "Greater", right.Const = 80.0 }
+//!
+//! [[actions]]
+//! id = "alarm"
+//! outputs.horn.Const = true
+//! "#,
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(cfg.runtime.rules[0].actions, vec!["alarm"]);
+//! # }
+//! ```
+
+use super::{io_table::IoRegistry, runtime::SyncRuntime, BoolExpr, Comparison, Source, Sources};
+use std::{collections::HashSet, fmt, io, sync::Arc};
+
+/// A runtime configuration
+///
+/// Beside the runtime itself the configuration declares the values
+/// that are provided from outside of the runtime, so that all
+/// sources can be checked.
+#[derive(Debug, Default)]
+pub struct RuntimeConfig {
+    /// Inputs that are provided by the I/O system
+    /// (the inputs of the loops are added implicitly)
+    pub inputs: Vec<String>,
+    /// Memory values that are written by the application
+    pub memory: Vec<String>,
+    /// The runtime
+    pub runtime: SyncRuntime,
+}
+
+/// A configuration error
+#[derive(Debug)]
+pub enum Error {
+    /// The configuration could not be parsed
+    Parse {
+        /// Description of the error
+        message: String,
+        /// Line of the error (starting at 1)
+        line: Option<usize>,
+        /// Column of the error (starting at 1)
+        column: Option<usize>,
+    },
+    /// The configuration is inconsistent
+    Invalid(Vec<String>),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse {
+                message,
+                line: Some(line),
+                column,
+            } => {
+                write!(f, "Invalid configuration at line {line}")?;
+                if let Some(column) = column {
+                    write!(f, ", column {column}")?;
+                }
+                write!(f, ": {message}")
+            }
+            Error::Parse { message, .. } => write!(f, "Invalid configuration: {message}"),
+            Error::Invalid(problems) => {
+                write!(f, "Inconsistent configuration: {}", problems.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<Error> for io::Error {
+    fn from(err: Error) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+#[cfg(any(feature = "toml", feature = "yaml"))]
+#[derive(serde::Deserialize)]
+struct Declarations {
+    #[serde(default)]
+    inputs: Vec<String>,
+    #[serde(default)]
+    memory: Vec<String>,
+}
+
+impl RuntimeConfig {
+    /// Load and validate a TOML configuration.
+    ///
+    /// The conditions are optimized (see [crate::optimizer])
+    /// and the IDs of the I/O values are resolved
+    /// (see [RuntimeConfig::io_registry]).
+    #[cfg(feature = "toml")]
+    pub fn from_toml(s: &str) -> Result<Self, Error> {
+        let parse_err = |err: toml::de::Error| {
+            let (line, column) = match err.span() {
+                Some(span) => {
+                    let (line, column) = position(s, span.start);
+                    (Some(line), Some(column))
+                }
+                None => (None, None),
+            };
+            Error::Parse {
+                message: err.message().trim().to_string(),
+                line,
+                column,
+            }
+        };
+        let runtime = toml::from_str(s).map_err(parse_err)?;
+        let Declarations { inputs, memory } = toml::from_str(s).map_err(parse_err)?;
+        let mut cfg = RuntimeConfig {
+            inputs,
+            memory,
+            runtime,
+        };
+        cfg.validate()?;
+        cfg.runtime.optimize_conditions();
+        cfg.runtime.io_registry = Arc::new(cfg.io_registry());
+        Ok(cfg)
+    }
+
+    /// Load and validate a YAML configuration.
+    ///
+    /// The conditions are optimized (see [crate::optimizer])
+    /// and the IDs of the I/O values are resolved
+    /// (see [RuntimeConfig::io_registry]).
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(s: &str) -> Result<Self, Error> {
+        let parse_err = |err: serde_yaml::Error| Error::Parse {
+            message: err.to_string(),
+            line: err.location().map(|l| l.line()),
+            column: err.location().map(|l| l.column()),
+        };
+        let runtime = serde_yaml::from_str(s).map_err(parse_err)?;
+        let Declarations { inputs, memory } = serde_yaml::from_str(s).map_err(parse_err)?;
+        let mut cfg = RuntimeConfig {
+            inputs,
+            memory,
+            runtime,
+        };
+        cfg.validate()?;
+        cfg.runtime.optimize_conditions();
+        cfg.runtime.io_registry = Arc::new(cfg.io_registry());
+        Ok(cfg)
+    }
+
+    /// Check for duplicate IDs, unknown actions, timers and sources,
+    /// invalid scalings and invalid MPC models.
+    pub fn validate(&self) -> Result<(), Error> {
+        let rt = &self.runtime;
+        let mut problems = vec![];
+
+        let mut check_ids = |kind: &str, ids: Vec<&String>| {
+            let mut known = HashSet::new();
+            for id in ids {
+                if !known.insert(id) {
+                    problems.push(format!("Duplicate {kind} ID '{id}'"));
+                }
+            }
+        };
+        check_ids("loop", rt.loops.iter().map(|x| &x.id).collect());
+        check_ids("rule", rt.rules.iter().map(|x| &x.id).collect());
+        check_ids("action", rt.actions.iter().map(|x| &x.id).collect());
+        check_ids("graph", rt.graphs.iter().map(|x| &x.id).collect());
+        check_ids("block", self.block_ids().collect());
+
+        let actions = rt.actions.iter().map(|a| &a.id).collect::<HashSet<_>>();
+        let mut check_actions = |owner: String, ids: &[String]| {
+            for id in ids.iter().filter(|id| !actions.contains(id)) {
+                problems.push(format!("{owner}: unknown action '{id}'"));
+            }
+        };
+        for r in &rt.rules {
+            check_actions(format!("Rule '{}'", r.id), &r.actions);
+        }
+        for a in &rt.actions {
+            check_actions(format!("Action '{}'", a.id), &a.release);
+        }
+        for (m_id, m) in &rt.state_machines {
+            for t in &m.transitions {
+                let owner = format!("State machine '{m_id}' ('{}' → '{}')", t.from, t.to);
+                check_actions(owner, &t.actions);
+            }
+            for s in &m.states {
+                let owner = format!("State machine '{m_id}' ('{}')", s.id);
+                check_actions(owner.clone(), &s.entry);
+                check_actions(owner.clone(), &s.exit);
+                check_actions(owner, &s.during);
+            }
+        }
+
+        for a in &rt.actions {
+            for id in a.timers.keys() {
+                if !rt.timers.iter().any(|t| t.id == *id) {
+                    problems.push(format!("Action '{}': unknown timer '{id}'", a.id));
+                }
+            }
+        }
+
+        for l in &rt.loops {
+            for (id, scaling) in &l.output_scaling {
+                if !l.outputs.contains(id) {
+                    problems.push(format!("Loop '{}': scaling of unknown output '{id}'", l.id));
+                } else if let Err(err) = scaling.validate() {
+                    problems.push(format!("Loop '{}': output '{id}': {err}", l.id));
+                }
+            }
+            if let crate::ControllerConfig::Mpc(ref cfg) = l.controller {
+                if let Err(err) = cfg.validate() {
+                    problems.push(format!("Loop '{}': {err}", l.id));
+                }
+            }
+            for step in &l.preprocessing {
+                if let crate::preprocessing::Preprocessing::Scaling(scaling) = step {
+                    if let Err(err) = scaling.validate() {
+                        problems.push(format!("Loop '{}': preprocessing: {err}", l.id));
+                    }
+                }
+            }
+        }
+
+        for (owner, src) in self.sources() {
+            if !self.is_known(&src) {
+                problems.push(format!("{owner}: unknown source {src:?}"));
+            }
+        }
+
+        for g in &rt.graphs {
+            if let Err(err) = g.execution_order() {
+                problems.push(format!("Graph '{}': {err}", g.id));
+            }
+        }
+
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            Err(Error::Invalid(problems))
+        }
+    }
+
+    /// Register the IDs of all inputs, outputs and memory values
+    /// that are declared or used by the runtime.
+    ///
+    /// The registry is used by the loaded runtime and can be used
+    /// to resolve the keys of the values once, e.g. in an I/O driver.
+    pub fn io_registry(&self) -> IoRegistry {
+        let rt = &self.runtime;
+        let mut registry = IoRegistry::default();
+        let mut ids = self.inputs.iter().chain(&self.memory).collect::<Vec<_>>();
+        for l in &rt.loops {
+            ids.extend(&l.inputs);
+            ids.extend(&l.outputs);
+            ids.extend(&l.feedforward);
+            ids.extend(&l.tracking);
+        }
+        for a in &rt.actions {
+            ids.extend(a.outputs.keys().chain(a.memory.keys()));
+        }
+        for g in &rt.graphs {
+            ids.extend(&g.outputs);
+        }
+        ids.extend(self.block_ids());
+        for id in ids {
+            registry.intern(id);
+        }
+        for (_, src) in self.sources() {
+            if let Source::In(id) | Source::Out(id) | Source::Mem(id) = src {
+                registry.intern(&id);
+            }
+        }
+        registry
+    }
+
+    /// The IDs of all blocks that write to the memory.
+    fn block_ids(&self) -> impl Iterator<Item = &String> {
+        let rt = &self.runtime;
+        rt.hysteresis_blocks
+            .iter()
+            .map(|b| &b.id)
+            .chain(rt.counters.iter().map(|b| &b.id))
+            .chain(rt.timers.iter().map(|b| &b.id))
+            .chain(rt.statistics.iter().map(|b| &b.id))
+            .chain(rt.spc.iter().map(|b| &b.id))
+            .chain(rt.plausibility_checks.iter().map(|b| &b.id))
+            .chain(rt.trends.iter().map(|b| &b.id))
+            .chain(rt.scalings.iter().map(|b| &b.id))
+            .chain(rt.selectors.iter().map(|b| &b.id))
+            .chain(rt.limiters.iter().map(|b| &b.id))
+    }
+
+    /// All sources that are read by the runtime with a description of the reader.
+    fn sources(&self) -> Vec<(String, Source)> {
+        let rt = &self.runtime;
+        let mut sources = vec![];
+        let condition = |owner: String, c: &BoolExpr<Comparison>| {
+            c.sources().into_iter().map(move |s| (owner.clone(), s))
+        };
+        for r in &rt.rules {
+            sources.extend(condition(format!("Rule '{}'", r.id), &r.condition));
+        }
+        for (m_id, m) in &rt.state_machines {
+            for t in &m.transitions {
+                let owner = format!("State machine '{m_id}' ('{}' → '{}')", t.from, t.to);
+                sources.extend(condition(owner, &t.condition));
+            }
+        }
+        for a in &rt.actions {
+            for src in a
+                .outputs
+                .values()
+                .chain(a.memory.values())
+                .chain(a.setpoints.values())
+            {
+                sources.push((format!("Action '{}'", a.id), src.clone()));
+            }
+        }
+        let blocks = rt
+            .hysteresis_blocks
+            .iter()
+            .map(|b| (&b.id, &b.input))
+            .chain(rt.counters.iter().map(|b| (&b.id, &b.input)))
+            .chain(rt.statistics.iter().map(|b| (&b.id, &b.input)))
+            .chain(rt.spc.iter().map(|b| (&b.id, &b.input)))
+            .chain(rt.plausibility_checks.iter().map(|b| (&b.id, &b.input)))
+            .chain(rt.trends.iter().map(|b| (&b.id, &b.input)))
+            .chain(rt.scalings.iter().map(|b| (&b.id, &b.input)))
+            .chain(rt.limiters.iter().map(|b| (&b.id, &b.input)))
+            .chain(
+                rt.selectors
+                    .iter()
+                    .flat_map(|b| b.inputs.iter().map(move |i| (&b.id, i))),
+            );
+        for (id, src) in blocks {
+            sources.push((format!("Block '{id}'"), src.clone()));
+        }
+        sources
+    }
+
+    fn is_known(&self, src: &Source) -> bool {
+        let rt = &self.runtime;
+        match src {
+            Source::In(id) => {
+                self.inputs.contains(id)
+                    || rt.loops.iter().any(|l| {
+                        l.inputs.contains(id)
+                            || l.feedforward.as_ref() == Some(id)
+                            || l.tracking.as_ref() == Some(id)
+                    })
+            }
+            Source::Out(id) => {
+                rt.loops.iter().any(|l| l.outputs.contains(id))
+                    || rt.actions.iter().any(|a| a.outputs.contains_key(id))
+                    || rt.graphs.iter().any(|g| g.outputs.contains(id))
+            }
+            Source::Mem(id) => {
+                self.memory.contains(id)
+                    || rt.actions.iter().any(|a| a.memory.contains_key(id))
+                    || self.block_ids().any(|b| {
+                        id == b
+                            || id
+                                .strip_prefix(b.as_str())
+                                .map(|x| x.starts_with('.'))
+                                .unwrap_or(false)
+                    })
+            }
+            Source::Setpoint(id) => {
+                rt.loops.iter().any(|l| l.id == *id)
+                    || rt.actions.iter().any(|a| a.setpoints.contains_key(id))
+            }
+            Source::Timeout(id) => rt.actions.iter().any(|a| a.timeouts.contains_key(id)),
+            Source::Const(_) => true,
+            Source::Expr(e) => e.sources().iter().all(|s| self.is_known(s)),
+        }
+    }
+}
+
+/// Line and column (both starting at 1) of a byte offset.
+#[cfg(feature = "toml")]
+fn position(s: &str, offset: usize) -> (usize, usize) {
+    let before = &s[..offset.min(s.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|c| *c != '\n').count() + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const TOML: &str = r#"
+inputs = ["x"]
+
+[[loops]]
+id = "ctrl"
+inputs = ["temperature"]
+outputs = ["heater"]
+controller.Pid = { k_p = 2.0 }
+
+[[rules]]
+id = "high"
+condition.Eval = { left.In = "temperature", cmp = "Greater", right.Const = 80 }
+actions = ["stop"]
+
+[[actions]]
+id = "stop"
+outputs.heater.Const = 0.0
+controllers.ctrl = { active = false }
+
+[state_machines.fsm]
+initial = "idle"
+
+[[state_machines.fsm.transitions]]
+from = "idle"
+to = "busy"
+condition.Eval = { left.In = "x", cmp = "Equal", right.Const = true }
+after = { secs = 5, nanos = 0 }
+
+[[hysteresis_blocks]]
+id = "warm"
+input.In = "temperature"
+hysteresis = { on = 60.0, off = 55.0 }
+"#;
+
+    #[test]
+    fn load_toml() {
+        let cfg = RuntimeConfig::from_toml(TOML).unwrap();
+        let rt = cfg.runtime;
+        assert_eq!(cfg.inputs, vec!["x"]);
+        assert_eq!(rt.loops[0].outputs, vec!["heater"]);
+        assert_eq!(rt.rules[0].actions, vec!["stop"]);
+        assert_eq!(rt.actions[0].controllers["ctrl"].active, Some(false));
+        let fsm = &rt.state_machines["fsm"];
+        assert_eq!(
+            fsm.transitions[0].after,
+            Some(std::time::Duration::from_secs(5))
+        );
+        assert!(fsm.transitions[0].actions.is_empty());
+        assert_eq!(rt.hysteresis_blocks[0].id, "warm");
+    }
+
+    #[test]
+    fn load_yaml() {
+        let yaml = r#"
+loops:
+  - id: ctrl
+    inputs: [temperature]
+    outputs: [heater]
+    controller: !Pid
+      k_p: 2.0
+rules:
+  - id: high
+    condition: !Eval
+      left: !In temperature
+      cmp: Greater
+      right: !Const 80
+    actions: [stop]
+actions:
+  - id: stop
+    outputs:
+      heater: !Const 0
+"#;
+        let rt = RuntimeConfig::from_yaml(yaml).unwrap().runtime;
+        assert_eq!(rt.loops[0].id, "ctrl");
+        assert_eq!(
+            rt.rules[0].condition,
+            BoolExpr::Eval(Source::In("temperature".into()).cmp_gt(Source::Const(80.into())))
+        );
+    }
+
+    #[test]
+    fn report_parse_error_positions() {
+        let err = RuntimeConfig::from_toml("[[rules]]\nid = \"a\"\nactions = 5\n").unwrap_err();
+        match err {
+            Error::Parse { line, .. } => assert_eq!(line, Some(3)),
+            _ => panic!("unexpected error: {err}"),
+        }
+        let err = RuntimeConfig::from_yaml("rules:\n  - id: a\n    actions: 5\n").unwrap_err();
+        match err {
+            Error::Parse { line, .. } => assert_eq!(line, Some(3)),
+            _ => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn optimize_conditions() {
+        let toml = r#"
+inputs = ["x"]
+
+[[rules]]
+id = "a"
+condition = "in.x > 2 * 5 && !false"
+"#;
+        let cfg = RuntimeConfig::from_toml(toml).unwrap();
+        assert_eq!(cfg.runtime.rules[0].condition.to_string(), "in.x > 10");
+    }
+
+    #[test]
+    fn detect_inconsistencies() {
+        let toml = r#"
+[[rules]]
+id = "a"
+condition.Eval = { left.In = "x", cmp = "Equal", right.Mem = "y.value" }
+actions = ["foo"]
+
+[[rules]]
+id = "a"
+condition = "True"
+
+[[actions]]
+id = "bar"
+outputs.z.Setpoint = "ctrl"
+"#;
+        match RuntimeConfig::from_toml(toml).unwrap_err() {
+            Error::Invalid(problems) => assert_eq!(
+                problems,
+                vec![
+                    "Duplicate rule ID 'a'",
+                    "Rule 'a': unknown action 'foo'",
+                    "Rule 'a': unknown source In(\"x\")",
+                    "Rule 'a': unknown source Mem(\"y.value\")",
+                    "Action 'bar': unknown source Setpoint(\"ctrl\")",
+                ]
+            ),
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn accept_block_outputs_and_declared_values() {
+        let mut cfg = RuntimeConfig::default();
+        cfg.runtime.counters.push(crate::CounterBlock {
+            id: "pump".into(),
+            input: Source::Mem("running".into()),
+            counter: Default::default(),
+        });
+        assert!(cfg.validate().is_err());
+        cfg.memory.push("running".into());
+        assert!(cfg.validate().is_ok());
+        cfg.runtime.rules.push(crate::Rule {
+            id: "service".into(),
+            condition: BoolExpr::Eval(
+                Source::Mem("pump.service".into()).cmp_eq(Source::Const(true.into())),
+            ),
+            actions: vec![],
+            trigger: crate::Trigger::Level,
+        });
+        assert!(cfg.validate().is_ok());
+        cfg.runtime.rules[0].condition =
+            BoolExpr::Eval(Source::Mem("pumps".into()).cmp_eq(Source::Const(true.into())));
+        assert!(cfg.validate().is_err());
+    }
+
+    #[test]
+    fn check_output_scalings() {
+        let mut cfg = RuntimeConfig::default();
+        let mut l = crate::Loop {
+            id: "ctrl".into(),
+            inputs: vec!["t1".into(), "t2".into()],
+            outputs: vec!["heater".into()],
+            combiner: Some(crate::selector::Selector::Average),
+            ..Default::default()
+        };
+        l.output_scaling.insert("heater".into(), Default::default());
+        cfg.runtime.loops.push(l);
+        assert!(cfg.validate().is_ok());
+        cfg.runtime.loops[0]
+            .output_scaling
+            .insert("cooler".into(), Default::default());
+        match cfg.validate().unwrap_err() {
+            Error::Invalid(problems) => assert_eq!(
+                problems,
+                vec!["Loop 'ctrl': scaling of unknown output 'cooler'"]
+            ),
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn check_mpc_models() {
+        let mut cfg = RuntimeConfig::default();
+        cfg.runtime.loops.push(crate::Loop {
+            id: "mpc".into(),
+            inputs: vec!["t".into()],
+            outputs: vec!["heater".into()],
+            controller: crate::ControllerConfig::Mpc(crate::mpc::MpcConfig::default()),
+            ..Default::default()
+        });
+        match cfg.validate().unwrap_err() {
+            Error::Invalid(problems) => assert_eq!(
+                problems,
+                vec!["Loop 'mpc': At least one step response is required"]
+            ),
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn register_io_ids() {
+        let cfg = RuntimeConfig::from_toml(TOML).unwrap();
+        let registry = cfg.io_registry();
+        for id in ["x", "temperature", "heater", "warm"] {
+            assert!(registry.key(id).is_some(), "{id} is not registered");
+        }
+        assert_eq!(registry.len(), 4);
+        assert_eq!(*cfg.runtime.io_registry, registry);
+    }
+}
@@ -0,0 +1,188 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, counter::*};
+//!
+//! let cfg = CounterConfig {
+//!     service_cycles: Some(2),
+//!     ..Default::default()
+//! };
+//! let mut pump = Counter::new(cfg);
+//! let delta_t = Duration::from_secs(1800);
+//!
+//! for running in [false, true, true, false, true] {
+//!     pump.next(running, &delta_t);
+//! }
+//! assert_eq!(pump.state.hours(), 1.5);
+//! assert_eq!(pump.state.cycles, 2);
+//! assert!(pump.service_due());
+//! ```
+
+use super::{Controller, PureController};
+use std::time::Duration;
+
+/// Operating-hours and switching-cycle counter
+///
+/// The run time is accumulated while the input is on and
+/// each switch from off to on is counted as a cycle.
+#[derive(Debug, Clone)]
+pub struct Counter {
+    cfg: CounterConfig,
+    /// Current state
+    pub state: CounterState,
+}
+
+/// Counter configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CounterConfig {
+    /// Run time after which a service is due
+    pub service_interval: Option<Duration>,
+    /// Number of cycles after which a service is due
+    pub service_cycles: Option<u64>,
+}
+
+/// Internal counter state
+///
+/// The state can be stored to keep the counters across restarts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CounterState {
+    /// Accumulated run time
+    pub run_time: Duration,
+    /// Number of switching cycles
+    pub cycles: u64,
+    /// The input of the previous step
+    pub prev_input: Option<bool>,
+}
+
+impl CounterState {
+    /// The run time in hours.
+    pub fn hours(&self) -> f64 {
+        self.run_time.as_secs_f64() / 3600.0
+    }
+}
+
+impl CounterConfig {
+    /// Check whether one of the service limits has been reached.
+    pub fn service_due(&self, state: &CounterState) -> bool {
+        self.service_interval
+            .map(|limit| state.run_time >= limit)
+            .unwrap_or(false)
+            || self
+                .service_cycles
+                .map(|limit| state.cycles >= limit)
+                .unwrap_or(false)
+    }
+}
+
+impl Counter {
+    /// Create a new counter instance.
+    pub fn new(cfg: CounterConfig) -> Self {
+        Self::with_state(cfg, CounterState::default())
+    }
+    /// Create a counter that continues with a stored state.
+    pub fn with_state(cfg: CounterConfig, state: CounterState) -> Self {
+        Counter { cfg, state }
+    }
+    /// Reset the run time and the cycles.
+    pub fn reset(&mut self) {
+        self.preset(Duration::ZERO, 0);
+    }
+    /// Set the run time and the cycles.
+    pub fn preset(&mut self, run_time: Duration, cycles: u64) {
+        self.state.run_time = run_time;
+        self.state.cycles = cycles;
+    }
+    /// Check whether one of the service limits has been reached.
+    pub fn service_due(&self) -> bool {
+        self.cfg.service_due(&self.state)
+    }
+}
+
+impl Controller<(bool, &Duration), CounterState> for Counter {
+    fn next(&mut self, input: (bool, &Duration)) -> CounterState {
+        let (on, duration) = input;
+        self.state = self.cfg.next((self.state, on, duration));
+        self.state
+    }
+}
+
+impl PureController<(CounterState, bool, &Duration), CounterState> for CounterConfig {
+    fn next(&self, input: (CounterState, bool, &Duration)) -> CounterState {
+        let (mut state, on, duration) = input;
+        if on {
+            state.run_time += *duration;
+            if state.prev_input == Some(false) {
+                state.cycles += 1;
+            }
+        }
+        state.prev_input = Some(on);
+        state
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn count_run_time_and_cycles() {
+        let mut c = Counter::new(CounterConfig::default());
+        let dt = Duration::from_secs(10);
+        // The initial state is not counted as a cycle
+        c.next((true, &dt));
+        assert_eq!(c.state.run_time, dt);
+        assert_eq!(c.state.cycles, 0);
+        c.next((false, &dt));
+        c.next((true, &dt));
+        c.next((true, &dt));
+        c.next((false, &dt));
+        c.next((true, &dt));
+        assert_eq!(c.state.run_time, Duration::from_secs(40));
+        assert_eq!(c.state.cycles, 2);
+    }
+
+    #[test]
+    fn reset_and_preset() {
+        let mut c = Counter::new(CounterConfig::default());
+        let dt = Duration::from_secs(3600);
+        c.preset(Duration::from_secs(7200), 5);
+        c.next((false, &dt));
+        c.next((true, &dt));
+        assert_eq!(c.state.hours(), 3.0);
+        assert_eq!(c.state.cycles, 6);
+        c.reset();
+        assert_eq!(c.state.run_time, Duration::ZERO);
+        assert_eq!(c.state.cycles, 0);
+        // The previous input is kept
+        c.next((true, &dt));
+        assert_eq!(c.state.cycles, 0);
+    }
+
+    #[test]
+    fn signal_service() {
+        let cfg = CounterConfig {
+            service_interval: Some(Duration::from_secs(100)),
+            service_cycles: Some(3),
+        };
+        let mut c = Counter::new(cfg.clone());
+        let dt = Duration::from_secs(50);
+        c.next((true, &dt));
+        assert!(!c.service_due());
+        c.next((true, &dt));
+        assert!(c.service_due());
+        let c = Counter::with_state(
+            cfg,
+            CounterState {
+                cycles: 3,
+                ..Default::default()
+            },
+        );
+        assert!(c.service_due());
+    }
+}
@@ -107,7 +107,9 @@ impl<'a> From<&'a str> for IoGate {
 }
 
 /// A loop continuously triggers a controller again and again.
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Loop {
     /// The unique ID of the rule
     pub id: String,
@@ -117,6 +119,181 @@ pub struct Loop {
     pub outputs: Vec<String>,
     /// The controller configuration
     pub controller: ControllerConfig,
+    /// Combines several inputs to the process value
+    ///
+    /// All inputs except the ones that are additionally
+    /// required by the controller (e.g. the inner actual
+    /// value of a cascade, the last input) are combined.
+    pub combiner: Option<selector::Selector>,
+    /// Individual scaling of the outputs
+    ///
+    /// The raw range is the range of the controller output,
+    /// the engineering range is the range of the actuator.
+    pub output_scaling: HashMap<String, scaling::Scaling>,
+    /// Input of a measured disturbance that is fed forward
+    /// (see [pid::FeedforwardConfig])
+    pub feedforward: Option<String>,
+    /// Split the controller output onto two actuator outputs
+    pub split_range: Option<split_range::SplitRangeConfig>,
+    /// Steps that condition the input before the controller runs
+    pub preprocessing: Vec<preprocessing::Preprocessing>,
+    /// Filters that are applied to the input after the preprocessing
+    pub filters: Vec<filter::FilterConfig>,
+    /// Limits the change of the outputs
+    pub output_rate_limit: Option<rate_limiter::RateLimiterConfig>,
+    /// Limits the change of the target if a new setpoint is applied
+    pub setpoint_rate_limit: Option<rate_limiter::RateLimiterConfig>,
+    /// Input that is followed by the output in tracking mode
+    pub tracking: Option<String>,
+}
+
+/// A hysteresis block compares a value and
+/// writes the boolean result to the memory.
+///
+/// The result can be used in rules and state machines
+/// by referring to `Source::Mem(id)`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HysteresisBlock {
+    /// The unique ID of the block and its memory value
+    pub id: String,
+    /// The compared value
+    pub input: Source,
+    /// The hysteresis configuration
+    pub hysteresis: hysteresis::HysteresisConfig,
+}
+
+/// A counter block accumulates the run time and the switching
+/// cycles of a boolean value.
+///
+/// The results are written to the memory values
+/// `<id>.hours`, `<id>.cycles` and `<id>.service`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CounterBlock {
+    /// The unique ID of the block
+    pub id: String,
+    /// The observed value
+    pub input: Source,
+    /// The counter configuration
+    pub counter: counter::CounterConfig,
+}
+
+/// A timer block is started, stopped and reset by actions.
+///
+/// The output is written to the memory value `<id>`
+/// and the elapsed time (in seconds) to `<id>.elapsed`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TimerBlock {
+    /// The unique ID of the block
+    pub id: String,
+    /// The timer configuration
+    pub timer: timer::TimerConfig,
+}
+
+/// A statistics block summarizes a numeric value.
+///
+/// The results are written to the memory values `<id>.mean`,
+/// `<id>.stddev`, `<id>.min`, `<id>.max`, `<id>.ewma` and `<id>.ewm_stddev`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatisticsBlock {
+    /// The unique ID of the block
+    pub id: String,
+    /// The observed value
+    pub input: Source,
+    /// The statistics configuration
+    pub statistics: statistics::StatisticsConfig,
+}
+
+/// A SPC block checks a numeric value against statistical process control rules.
+///
+/// The memory value `<id>` is set while a rule is violated.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpcBlock {
+    /// The unique ID of the block
+    pub id: String,
+    /// The observed value
+    pub input: Source,
+    /// The SPC configuration
+    pub spc: spc::SpcConfig,
+}
+
+/// A trend block estimates the slope of a numeric value.
+///
+/// The results are written to the memory values `<id>.slope`,
+/// `<id>.rising` and `<id>.falling`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrendBlock {
+    /// The unique ID of the block
+    pub id: String,
+    /// The observed value
+    pub input: Source,
+    /// The trend detection configuration
+    pub trend: trend::TrendConfig,
+}
+
+/// A selector block selects one of several numeric values.
+///
+/// The selected value is written to the memory value `<id>`
+/// and the index of the selected input to `<id>.selected`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectorBlock {
+    /// The unique ID of the block
+    pub id: String,
+    /// The values to select from
+    pub inputs: Vec<Source>,
+    /// The selection
+    pub selector: selector::Selector,
+}
+
+/// A limiter block limits a numeric value.
+///
+/// The limited value is written to the memory value `<id>`
+/// and the active limit to `<id>.low` and `<id>.high`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LimiterBlock {
+    /// The unique ID of the block
+    pub id: String,
+    /// The unlimited value
+    pub input: Source,
+    /// The limiter configuration
+    pub limiter: limiter::LimiterConfig,
+}
+
+/// A scaling block converts a raw value to an engineering value.
+///
+/// The result is written to the memory value `<id>` and the
+/// out-of-range flag to `<id>.out_of_range`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ScalingBlock {
+    /// The unique ID of the block
+    pub id: String,
+    /// The raw value
+    pub input: Source,
+    /// The scaling configuration
+    pub scaling: scaling::Scaling,
+}
+
+/// A plausibility block checks a numeric sensor value.
+///
+/// The quality flag is written to the memory value `<id>` and the
+/// detected faults to `<id>.stuck` and `<id>.spike`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlausibilityBlock {
+    /// The unique ID of the block
+    pub id: String,
+    /// The checked value
+    pub input: Source,
+    /// The plausibility check configuration
+    pub plausibility: plausibility::PlausibilityConfig,
 }
 
 /// A periodic interval with a fixed duration
@@ -130,17 +307,55 @@ pub struct Interval {
 
 /// A Rule connects a condition with a list of actions.
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Rule {
     /// The unique ID of the rule
     pub id: String,
     /// The condition
+    #[cfg_attr(feature = "serde", serde(with = "crate::parser::condition_text"))]
     pub condition: BoolExpr<Comparison>,
     /// Actions that should be triggered
+    #[cfg_attr(feature = "serde", serde(default))]
     pub actions: Vec<String>,
+    /// When the actions are triggered
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub trigger: Trigger,
+}
+
+/// Trigger of the actions of a [Rule]
+///
+/// The edges are detected by comparing the condition with
+/// its state of the previous step. A missing state counts as `false`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trigger {
+    /// On each step while the condition is true
+    #[default]
+    Level,
+    /// Once when the condition becomes true
+    Rising,
+    /// Once when the condition becomes false
+    Falling,
+    /// Once whenever the condition changes
+    Changed,
+}
+
+impl Trigger {
+    /// Check if the actions are triggered.
+    pub fn is_triggered(&self, previous: bool, current: bool) -> bool {
+        match self {
+            Trigger::Level => current,
+            Trigger::Rising => !previous && current,
+            Trigger::Falling => previous && !current,
+            Trigger::Changed => previous != current,
+        }
+    }
 }
 
 /// An action can modify outputs and setpoints.
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct Action {
     /// The unique ID of the action
     pub id: String,
@@ -153,16 +368,125 @@ pub struct Action {
     /// Define controller states
     pub controllers: HashMap<String, ControllerAction>,
     /// Define timeouts
+    ///
+    /// Timer blocks (see [TimerBlock]) offer on-delay,
+    /// off-delay and pulse semantics instead.
     pub timeouts: HashMap<String, Option<Duration>>,
+    /// Modify counter blocks
+    pub counters: HashMap<String, CounterAction>,
+    /// Control timer blocks
+    pub timers: HashMap<String, TimerAction>,
+    /// Apply the action only after it was requested for this duration
+    pub on_delay: Option<Duration>,
+    /// Keep applying the action for this duration after it is no longer requested
+    pub off_delay: Option<Duration>,
+    /// Apply the action for exactly this duration after it was requested
+    /// (the `off_delay` is ignored)
+    pub pulse: Option<Duration>,
+    /// Actions that are applied once when the timed action ends
+    pub release: Vec<String>,
 }
 
-/// An action to modify the state or behaviour of a controller.
+impl Action {
+    /// Check if the action is delayed or pulsed.
+    pub fn is_timed(&self) -> bool {
+        self.on_delay.is_some() || self.off_delay.is_some() || self.pulse.is_some()
+    }
+}
+
+/// Timer state of a delayed or pulsed [Action]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ActionState {
+    /// Duration since the action is requested
+    pub requested: Option<Duration>,
+    /// The on-delay has elapsed
+    pub on: bool,
+    /// Duration of the running pulse or off-delay
+    pub timer: Option<Duration>,
+    /// The action is applied
+    pub active: bool,
+}
+
+impl ActionState {
+    /// Update the timers and return whether the action is active.
+    pub fn update(&mut self, action: &Action, requested: bool, dt: &Duration) -> bool {
+        self.requested = if requested {
+            Some(self.requested.map(|t| t + *dt).unwrap_or_default())
+        } else {
+            None
+        };
+        let was_on = self.on;
+        self.on = self.requested >= Some(action.on_delay.unwrap_or_default());
+        self.active = match action.pulse {
+            Some(pulse) => {
+                match self.timer.as_mut() {
+                    Some(t) => *t += *dt,
+                    None if self.on && !was_on => self.timer = Some(Duration::ZERO),
+                    None => {}
+                }
+                let running = self.timer.map(|t| t < pulse).unwrap_or(false);
+                if !running && !self.on {
+                    // Allow to trigger the next pulse
+                    self.timer = None;
+                }
+                running
+            }
+            None if self.on => {
+                self.timer = None;
+                true
+            }
+            None if self.active || self.timer.is_some() => {
+                let t = self.timer.map(|t| t + *dt).unwrap_or_default();
+                let delayed = t < action.off_delay.unwrap_or_default();
+                self.timer = delayed.then_some(t);
+                delayed
+            }
+            None => false,
+        };
+        self.active
+    }
+}
+
+/// An action to modify a counter block.
 #[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CounterAction {
+    /// Set the run time and the cycles to zero
+    Reset,
+    /// Set the run time and the cycles
+    Preset {
+        /// Accumulated run time
+        run_time: Duration,
+        /// Number of switching cycles
+        cycles: u64,
+    },
+}
+
+/// An action to control a [TimerBlock].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimerAction {
+    /// Switch the timer input on
+    Start,
+    /// Switch the timer input off
+    Stop,
+    /// Switch the timer input and output off and clear the elapsed time
+    Reset,
+}
+
+/// An action to modify the state or behaviour of a controller.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct ControllerAction {
     /// Reset controlle state
     pub reset: bool,
     /// Start/Stop a controller
     pub active: Option<bool>,
+    /// Switch the operating mode
+    pub mode: Option<OperatingMode>,
 }
 
 #[cfg(test)]
@@ -201,4 +525,72 @@ mod tests {
         assert_eq!(cropping.crop(3.0), 3.0);
         assert_eq!(cropping.crop(3.1), 3.0);
     }
+
+    #[test]
+    fn trigger_actions() {
+        let steps = [(false, false), (false, true), (true, true), (true, false)];
+        let triggered = |t: Trigger| {
+            steps
+                .iter()
+                .map(|(prev, cur)| t.is_triggered(*prev, *cur))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(triggered(Trigger::Level), [false, true, true, false]);
+        assert_eq!(triggered(Trigger::Rising), [false, true, false, false]);
+        assert_eq!(triggered(Trigger::Falling), [false, false, false, true]);
+        assert_eq!(triggered(Trigger::Changed), [false, true, false, true]);
+    }
+
+    fn run_timer(action: &Action, requests: &[bool]) -> Vec<bool> {
+        let mut s = ActionState::default();
+        let dt = Duration::from_secs(1);
+        requests.iter().map(|r| s.update(action, *r, &dt)).collect()
+    }
+
+    #[test]
+    fn delay_actions() {
+        let action = Action {
+            on_delay: Some(Duration::from_secs(2)),
+            ..Default::default()
+        };
+        assert!(action.is_timed());
+        assert_eq!(
+            run_timer(&action, &[true, true, true, true, false, true]),
+            [false, false, true, true, false, false]
+        );
+        let action = Action {
+            off_delay: Some(Duration::from_secs(2)),
+            ..Default::default()
+        };
+        assert_eq!(
+            run_timer(&action, &[true, false, false, false, true, false, true]),
+            [true, true, true, false, true, true, true]
+        );
+        assert!(!Action::default().is_timed());
+    }
+
+    #[test]
+    fn pulse_actions() {
+        let action = Action {
+            pulse: Some(Duration::from_secs(2)),
+            ..Default::default()
+        };
+        // The pulse is not shortened or extended by the request
+        assert_eq!(
+            run_timer(
+                &action,
+                &[true, false, false, true, true, true, false, true]
+            ),
+            [true, true, false, true, true, false, false, true]
+        );
+        let action = Action {
+            on_delay: Some(Duration::from_secs(1)),
+            pulse: Some(Duration::from_secs(1)),
+            ..Default::default()
+        };
+        assert_eq!(
+            run_timer(&action, &[true, true, true, false]),
+            [false, true, false, false]
+        );
+    }
 }
@@ -0,0 +1,307 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{executor::*, *};
+//!
+//! # #[tokio::main(flavor = "current_thread")]
+//! # async fn main() {
+//! let runtime = SyncRuntime {
+//!     rules: vec![Rule {
+//!         id: "start".into(),
+//!         condition: "in.start".parse().unwrap(),
+//!         actions: vec!["pump".into()],
+//!         trigger: Trigger::Level,
+//!     }],
+//!     actions: vec![Action {
+//!         id: "pump".into(),
+//!         outputs: [("pump".into(), true.into())].into_iter().collect(),
+//!         ..Default::default()
+//!     }],
+//!     ..Default::default()
+//! };
+//! let executor = Executor::new(runtime, SystemState::default(), Duration::from_millis(10));
+//!
+//! // An I/O provider writes the inputs and reads the outputs
+//! let inputs = executor.input_sender();
+//! let mut io = executor.subscribe_io();
+//! let commands = executor.command_sender();
+//!
+//! let task = tokio::spawn(executor.run());
+//! inputs.send([("start".into(), true.into())].into()).await.unwrap();
+//! io.wait_for(|io| io.outputs.contains_key("pump")).await.unwrap();
+//!
+//! commands.send(Command::Stop).await.unwrap();
+//! let state = task.await.unwrap();
+//! assert_eq!(state.io.outputs["pump"], Value::Bit(true));
+//! # }
+//! ```
+
+use super::{IoState, PureController, SyncRuntime, SystemState, Value};
+use std::{collections::HashMap, sync::Arc, time::Duration};
+use tokio::{
+    sync::{broadcast, mpsc, mpsc::error::TryRecvError, watch},
+    time::{self, Instant, MissedTickBehavior},
+};
+
+/// Input values that are sent by an I/O provider
+pub type Inputs = HashMap<String, Value>;
+
+/// A command to control the executor
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    /// Stop the execution after the current step
+    Stop,
+    /// Set a setpoint
+    Setpoint(String, Value),
+    /// Set a memory value
+    Memory(String, Value),
+}
+
+/// An event published by the executor
+#[derive(Debug, Clone)]
+pub enum Event {
+    /// The state after a step
+    Snapshot(Arc<SystemState>),
+    /// The errors of a step
+    Errors(Vec<String>),
+    /// The execution has been stopped
+    Stopped,
+}
+
+/// Capacity of the input and command channels
+const CHANNEL_CAPACITY: usize = 100;
+
+/// Capacity of the event channel
+const EVENT_CAPACITY: usize = 16;
+
+/// Executes a [SyncRuntime] cyclically.
+///
+/// Before each step the received inputs and commands are applied.
+/// The time since the previous step is used as `delta_t`
+/// (the cycle time for the first step).
+#[derive(Debug)]
+pub struct Executor {
+    runtime: SyncRuntime,
+    state: SystemState,
+    cycle_time: Duration,
+    input_tx: mpsc::Sender<Inputs>,
+    input_rx: mpsc::Receiver<Inputs>,
+    command_tx: mpsc::Sender<Command>,
+    command_rx: mpsc::Receiver<Command>,
+    io_tx: watch::Sender<IoState>,
+    event_tx: broadcast::Sender<Event>,
+}
+
+impl Executor {
+    /// Create a new executor with an initial state.
+    pub fn new(runtime: SyncRuntime, state: SystemState, cycle_time: Duration) -> Self {
+        let (input_tx, input_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let (command_tx, command_rx) = mpsc::channel(CHANNEL_CAPACITY);
+        let (io_tx, _) = watch::channel(state.io.clone());
+        let (event_tx, _) = broadcast::channel(EVENT_CAPACITY);
+        Self {
+            runtime,
+            state,
+            cycle_time,
+            input_tx,
+            input_rx,
+            command_tx,
+            command_rx,
+            io_tx,
+            event_tx,
+        }
+    }
+
+    /// Endpoint for sending input values
+    pub fn input_sender(&self) -> mpsc::Sender<Inputs> {
+        self.input_tx.clone()
+    }
+
+    /// Endpoint for sending commands
+    ///
+    /// The execution stops when all command senders have been dropped.
+    pub fn command_sender(&self) -> mpsc::Sender<Command> {
+        self.command_tx.clone()
+    }
+
+    /// Receive the I/O state after each step.
+    pub fn subscribe_io(&self) -> watch::Receiver<IoState> {
+        self.io_tx.subscribe()
+    }
+
+    /// Subscribe to the published events.
+    pub fn subscribe_events(&self) -> broadcast::Receiver<Event> {
+        self.event_tx.subscribe()
+    }
+
+    /// Run the cyclic execution until [Command::Stop] is received
+    /// or all command senders have been dropped.
+    ///
+    /// Returns the final state.
+    pub async fn run(self) -> SystemState {
+        let Self {
+            runtime,
+            mut state,
+            cycle_time,
+            input_tx,
+            mut input_rx,
+            command_tx,
+            mut command_rx,
+            io_tx,
+            event_tx,
+        } = self;
+        // Only the external senders keep the channels open
+        drop(input_tx);
+        drop(command_tx);
+
+        let mut interval = time::interval(cycle_time);
+        interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        let mut last_step: Option<Instant> = None;
+        let mut stop = false;
+        while !stop {
+            let now = interval.tick().await;
+            while let Ok(inputs) = input_rx.try_recv() {
+                state.io.inputs.extend(inputs);
+            }
+            loop {
+                let cmd = match command_rx.try_recv() {
+                    Ok(cmd) => cmd,
+                    Err(TryRecvError::Empty) => break,
+                    // Nobody is able to stop the execution anymore
+                    Err(TryRecvError::Disconnected) => {
+                        stop = true;
+                        break;
+                    }
+                };
+                match cmd {
+                    Command::Stop => stop = true,
+                    Command::Setpoint(id, v) => {
+                        state.setpoints.insert(id, v);
+                    }
+                    Command::Memory(id, v) => {
+                        state.io.mem.insert(id, v);
+                    }
+                }
+            }
+            let dt = last_step.map(|t| now - t).unwrap_or(cycle_time);
+            last_step = Some(now);
+            state = match runtime.next((&state, &dt)) {
+                Ok(state) => state,
+                Err(err) => {
+                    let errors = err.causes.iter().map(ToString::to_string).collect();
+                    // Nobody might be listening
+                    let _ = event_tx.send(Event::Errors(errors));
+                    err.state
+                }
+            };
+            io_tx.send_replace(state.io.clone());
+            let _ = event_tx.send(Event::Snapshot(Arc::new(state.clone())));
+        }
+        let _ = event_tx.send(Event::Stopped);
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::{super::*, *};
+
+    fn runtime() -> SyncRuntime {
+        SyncRuntime {
+            rules: vec![Rule {
+                id: "alarm".into(),
+                condition: "in.temp > mem.limit".parse().unwrap(),
+                actions: vec!["horn".into()],
+                trigger: Trigger::Level,
+            }],
+            actions: vec![Action {
+                id: "horn".into(),
+                outputs: [("horn".into(), true.into())].into_iter().collect(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn execute_cyclically() {
+        let mut state = SystemState::default();
+        state.io.mem.insert("limit".into(), 80.0.into());
+        state.io.inputs.insert("temp".into(), 20.0.into());
+        state
+            .timeouts
+            .insert("t".into(), Duration::from_secs(1).into());
+        let executor = Executor::new(runtime(), state, Duration::from_millis(100));
+        let inputs = executor.input_sender();
+        let commands = executor.command_sender();
+        let mut io = executor.subscribe_io();
+        let mut events = executor.subscribe_events();
+        let task = tokio::spawn(executor.run());
+
+        match events.recv().await.unwrap() {
+            Event::Snapshot(s) => assert!(s.io.outputs.is_empty()),
+            ev => panic!("unexpected event {ev:?}"),
+        }
+        inputs
+            .send([("temp".into(), 90.0.into())].into())
+            .await
+            .unwrap();
+        io.wait_for(|io| io.outputs.contains_key("horn"))
+            .await
+            .unwrap();
+
+        commands
+            .send(Command::Memory("limit".into(), 95.0.into()))
+            .await
+            .unwrap();
+        commands.send(Command::Stop).await.unwrap();
+        let state = task.await.unwrap();
+        assert_eq!(*state.io.inputs.get("temp").unwrap(), Value::Decimal(90.0));
+        assert_eq!(*state.io.mem.get("limit").unwrap(), Value::Decimal(95.0));
+        assert!(!*state.rules.get("alarm").unwrap());
+        // The measured delta_t is applied
+        let Value::Timeout(t) = state.timeouts.get("t").unwrap() else {
+            panic!("timeout expected");
+        };
+        assert!(*t < Duration::from_millis(900));
+
+        let mut last = None;
+        while let Ok(ev) = events.try_recv() {
+            last = Some(ev);
+        }
+        assert!(matches!(last, Some(Event::Stopped)));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn publish_errors() {
+        let executor = Executor::new(runtime(), SystemState::default(), Duration::from_millis(10));
+        let commands = executor.command_sender();
+        let mut events = executor.subscribe_events();
+        let task = tokio::spawn(executor.run());
+        match events.recv().await.unwrap() {
+            Event::Errors(errors) => assert_eq!(errors.len(), 1),
+            ev => panic!("unexpected event {ev:?}"),
+        }
+        assert!(matches!(events.recv().await.unwrap(), Event::Snapshot(_)));
+        commands.send(Command::Stop).await.unwrap();
+        task.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stop_when_command_senders_are_dropped() {
+        let executor = Executor::new(runtime(), SystemState::default(), Duration::from_millis(10));
+        let commands = executor.command_sender();
+        let mut events = executor.subscribe_events();
+        let task = tokio::spawn(executor.run());
+        assert!(matches!(events.recv().await.unwrap(), Event::Errors(_)));
+        drop(commands);
+        task.await.unwrap();
+        let mut last = None;
+        while let Ok(ev) = events.try_recv() {
+            last = Some(ev);
+        }
+        assert!(matches!(last, Some(Event::Stopped)));
+    }
+}
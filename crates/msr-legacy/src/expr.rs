@@ -0,0 +1,218 @@
+use super::{comparison::get_val, *};
+
+/// An arithmetic expression over data sources
+///
+/// Expressions are used as [Source::Expr], e.g. to compare
+/// the difference of two inputs or to compute a setpoint.
+///
+/// # Example
+///
+/// ```rust
+/// use msr_legacy::*;
+///
+/// let mut state = SystemState::default();
+/// state.io.inputs.insert("a".into(), 12.0.into());
+/// state.io.inputs.insert("b".into(), 5.0.into());
+///
+/// let diff = Source::from(Expr::Sub(Source::In("a".into()), Source::In("b".into())));
+/// let rule = BoolExpr::Eval(diff.cmp_gt(5.0.into()));
+/// assert!(rule.eval(&state).unwrap());
+/// ```
+#[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Expr {
+    /// `a + b`
+    Add(Source, Source),
+    /// `a - b`
+    Sub(Source, Source),
+    /// `a * b`
+    Mul(Source, Source),
+    /// `a / b`
+    Div(Source, Source),
+    /// `min(a, b)`
+    Min(Source, Source),
+    /// `max(a, b)`
+    Max(Source, Source),
+    /// `abs(a)`
+    Abs(Source),
+}
+
+impl From<Expr> for Source {
+    fn from(e: Expr) -> Source {
+        Source::Expr(Box::new(e))
+    }
+}
+
+impl Evaluation<SystemState> for Expr {
+    type Output = Value;
+    /// Calculate the value of the expression.
+    ///
+    /// Integers stay integers, except for divisions.
+    /// If one of the operands is a decimal, the result is a decimal.
+    fn eval(&self, state: &SystemState) -> Result<Value> {
+        use crate::Expr::*;
+        match self {
+            Add(a, b) | Sub(a, b) | Mul(a, b) | Div(a, b) | Min(a, b) | Max(a, b) => binary_op(
+                self,
+                get_val(a, state)?.as_ref(),
+                get_val(b, state)?.as_ref(),
+            ),
+            Abs(a) => match get_val(a, state)?.as_ref() {
+                Value::Integer(x) => x.checked_abs().map(Value::Integer).ok_or_else(overflow),
+                Value::Decimal(x) => Ok(Value::Decimal(x.abs())),
+                _ => Err(not_numeric()),
+            },
+        }
+    }
+}
+
+impl Sources for Expr {
+    fn sources(&self) -> Vec<Source> {
+        use crate::Expr::*;
+        let leafs = |src: &Source| match src {
+            Source::Expr(e) => e.sources(),
+            src => vec![src.clone()],
+        };
+        match self {
+            Add(a, b) | Sub(a, b) | Mul(a, b) | Div(a, b) | Min(a, b) | Max(a, b) => {
+                let mut srcs = leafs(a);
+                srcs.append(&mut leafs(b));
+                srcs
+            }
+            Abs(a) => leafs(a),
+        }
+    }
+}
+
+fn binary_op(op: &Expr, a: &Value, b: &Value) -> Result<Value> {
+    use crate::Expr::*;
+    match (a, b) {
+        (Value::Integer(a), Value::Integer(b)) => {
+            let (a, b) = (*a, *b);
+            let res = match op {
+                Add(..) => a.checked_add(b),
+                Sub(..) => a.checked_sub(b),
+                Mul(..) => a.checked_mul(b),
+                Div(..) => return Ok(Value::Decimal(a as f64 / b as f64)),
+                Min(..) => Some(a.min(b)),
+                Max(..) => Some(a.max(b)),
+                Abs(_) => unreachable!(),
+            };
+            res.map(Value::Integer).ok_or_else(overflow)
+        }
+        (Value::Integer(_) | Value::Decimal(_), Value::Integer(_) | Value::Decimal(_)) => {
+            let (a, b) = (as_f64(a), as_f64(b));
+            let res = match op {
+                Add(..) => a + b,
+                Sub(..) => a - b,
+                Mul(..) => a * b,
+                Div(..) => a / b,
+                Min(..) => a.min(b),
+                Max(..) => a.max(b),
+                Abs(_) => unreachable!(),
+            };
+            Ok(Value::Decimal(res))
+        }
+        _ => Err(not_numeric()),
+    }
+}
+
+fn as_f64(v: &Value) -> f64 {
+    match v {
+        Value::Integer(x) => *x as f64,
+        Value::Decimal(x) => *x,
+        _ => f64::NAN,
+    }
+}
+
+fn overflow() -> Error {
+    Error::new(ErrorKind::InvalidData, "Integer overflow")
+}
+
+fn not_numeric() -> Error {
+    Error::new(
+        ErrorKind::InvalidInput,
+        "Arithmetic expressions require numeric values",
+    )
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    fn state() -> SystemState {
+        let mut state = SystemState::default();
+        state.io.inputs.insert("a".into(), 7.into());
+        state.io.inputs.insert("b".into(), 2.into());
+        state.io.inputs.insert("x".into(), 1.5.into());
+        state
+            .io
+            .inputs
+            .insert("t".into(), Value::Text("foo".into()));
+        state
+    }
+
+    fn a() -> Source {
+        Source::In("a".into())
+    }
+
+    fn b() -> Source {
+        Source::In("b".into())
+    }
+
+    fn x() -> Source {
+        Source::In("x".into())
+    }
+
+    #[test]
+    fn calculate_integers() {
+        let s = state();
+        assert_eq!(Expr::Add(a(), b()).eval(&s).unwrap(), Value::Integer(9));
+        assert_eq!(Expr::Sub(b(), a()).eval(&s).unwrap(), Value::Integer(-5));
+        assert_eq!(Expr::Mul(a(), b()).eval(&s).unwrap(), Value::Integer(14));
+        assert_eq!(Expr::Div(a(), b()).eval(&s).unwrap(), Value::Decimal(3.5));
+        assert_eq!(Expr::Min(a(), b()).eval(&s).unwrap(), Value::Integer(2));
+        assert_eq!(Expr::Max(a(), b()).eval(&s).unwrap(), Value::Integer(7));
+        assert_eq!(
+            Expr::Abs(Expr::Sub(b(), a()).into()).eval(&s).unwrap(),
+            Value::Integer(5)
+        );
+        let max = Source::Const(i64::MAX.into());
+        assert!(Expr::Add(max, a()).eval(&s).is_err());
+    }
+
+    #[test]
+    fn calculate_decimals() {
+        let s = state();
+        assert_eq!(Expr::Add(a(), x()).eval(&s).unwrap(), Value::Decimal(8.5));
+        assert_eq!(Expr::Mul(x(), b()).eval(&s).unwrap(), Value::Decimal(3.0));
+        assert_eq!(Expr::Min(a(), x()).eval(&s).unwrap(), Value::Decimal(1.5));
+        assert_eq!(
+            Expr::Abs(Source::Const((-0.5).into())).eval(&s).unwrap(),
+            Value::Decimal(0.5)
+        );
+    }
+
+    #[test]
+    fn reject_invalid_operands() {
+        let s = state();
+        assert!(Expr::Add(a(), Source::In("t".into())).eval(&s).is_err());
+        assert!(Expr::Abs(Source::Const(true.into())).eval(&s).is_err());
+        assert_eq!(
+            Expr::Add(a(), Source::In("missing".into()))
+                .eval(&s)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::NotFound
+        );
+    }
+
+    #[test]
+    fn nested_sources() {
+        let e = Expr::Max(Expr::Sub(a(), b()).into(), Source::Const(0.into()));
+        assert_eq!(e.sources(), vec![a(), b(), Source::Const(0.into())]);
+        assert_eq!(e.eval(&state()).unwrap(), Value::Integer(5));
+    }
+}
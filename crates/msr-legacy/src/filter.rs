@@ -0,0 +1,297 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, filter::*};
+//!
+//! let mut f = Filter::new(FilterConfig::Median(MedianConfig { window: 3 }));
+//! let delta_t = Duration::from_millis(100);
+//!
+//! assert_eq!(f.next(1.0, &delta_t), 1.0);
+//! assert_eq!(f.next(3.0, &delta_t), 2.0);
+//! assert_eq!(f.next(100.0, &delta_t), 3.0); // spike
+//! assert_eq!(f.next(2.0, &delta_t), 3.0);
+//! ```
+
+use super::{Controller, PureController};
+use std::{collections::VecDeque, time::Duration};
+
+/// First-order low-pass filter (PT1)
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Pt1Config {
+    /// Time constant of the filter
+    pub time_constant: Duration,
+}
+
+/// Internal PT1 filter state
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Pt1State {
+    /// The filtered value of the previous step
+    pub value: Option<f64>,
+}
+
+impl PureController<(Pt1State, f64, &Duration), (Pt1State, f64)> for Pt1Config {
+    fn next(&self, input: (Pt1State, f64, &Duration)) -> (Pt1State, f64) {
+        let (state, x, duration) = input;
+        let y = match state.value {
+            Some(prev) => {
+                let delta_t = duration.as_secs_f64();
+                let t = self.time_constant.as_secs_f64();
+                if t + delta_t > 0.0 {
+                    prev + delta_t / (t + delta_t) * (x - prev)
+                } else {
+                    x
+                }
+            }
+            None => x,
+        };
+        (Pt1State { value: Some(y) }, y)
+    }
+}
+
+/// Moving-average filter over the last `window` values
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MovingAverageConfig {
+    /// Number of averaged values
+    pub window: usize,
+}
+
+/// Median filter over the last `window` values
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MedianConfig {
+    /// Number of considered values
+    pub window: usize,
+}
+
+/// Internal state of filters over a window of values
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WindowState {
+    /// The most recent values (the oldest first)
+    pub values: VecDeque<f64>,
+}
+
+impl WindowState {
+    fn push(mut self, x: f64, window: usize) -> Self {
+        self.values.push_back(x);
+        while self.values.len() > window.max(1) {
+            self.values.pop_front();
+        }
+        self
+    }
+}
+
+impl PureController<(WindowState, f64), (WindowState, f64)> for MovingAverageConfig {
+    fn next(&self, input: (WindowState, f64)) -> (WindowState, f64) {
+        let (state, x) = input;
+        let state = state.push(x, self.window);
+        let y = state.values.iter().sum::<f64>() / state.values.len() as f64;
+        (state, y)
+    }
+}
+
+impl PureController<(WindowState, f64), (WindowState, f64)> for MedianConfig {
+    fn next(&self, input: (WindowState, f64)) -> (WindowState, f64) {
+        let (state, x) = input;
+        let state = state.push(x, self.window);
+        let mut values: Vec<_> = state.values.iter().copied().collect();
+        values.sort_by(|a, b| a.total_cmp(b));
+        let n = values.len();
+        let y = if n % 2 == 0 {
+            (values[n / 2 - 1] + values[n / 2]) / 2.0
+        } else {
+            values[n / 2]
+        };
+        (state, y)
+    }
+}
+
+/// Filter configuration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FilterConfig {
+    Pt1(Pt1Config),
+    MovingAverage(MovingAverageConfig),
+    Median(MedianConfig),
+}
+
+/// Filter state
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FilterState {
+    Pt1(Pt1State),
+    MovingAverage(WindowState),
+    Median(WindowState),
+}
+
+impl FilterConfig {
+    /// The state of a filter that has not received any value yet.
+    pub fn initial_state(&self) -> FilterState {
+        match self {
+            FilterConfig::Pt1(_) => FilterState::Pt1(Pt1State::default()),
+            FilterConfig::MovingAverage(_) => FilterState::MovingAverage(WindowState::default()),
+            FilterConfig::Median(_) => FilterState::Median(WindowState::default()),
+        }
+    }
+}
+
+/// A state that does not match the configuration is reinitialized.
+impl PureController<(FilterState, f64, &Duration), (FilterState, f64)> for FilterConfig {
+    fn next(&self, input: (FilterState, f64, &Duration)) -> (FilterState, f64) {
+        let (state, x, duration) = input;
+        match (self, state) {
+            (FilterConfig::Pt1(cfg), FilterState::Pt1(s)) => {
+                let (s, y) = cfg.next((s, x, duration));
+                (FilterState::Pt1(s), y)
+            }
+            (FilterConfig::MovingAverage(cfg), FilterState::MovingAverage(s)) => {
+                let (s, y) = cfg.next((s, x));
+                (FilterState::MovingAverage(s), y)
+            }
+            (FilterConfig::Median(cfg), FilterState::Median(s)) => {
+                let (s, y) = cfg.next((s, x));
+                (FilterState::Median(s), y)
+            }
+            (cfg, _) => cfg.next((cfg.initial_state(), x, duration)),
+        }
+    }
+}
+
+/// A chain of filters that are applied one after another.
+impl PureController<(Vec<FilterState>, f64, &Duration), (Vec<FilterState>, f64)>
+    for [FilterConfig]
+{
+    fn next(&self, input: (Vec<FilterState>, f64, &Duration)) -> (Vec<FilterState>, f64) {
+        let (states, x, duration) = input;
+        let mut states = states.into_iter();
+        let mut y = x;
+        let states = self
+            .iter()
+            .map(|cfg| {
+                let state = states.next().unwrap_or_else(|| cfg.initial_state());
+                let (state, filtered) = cfg.next((state, y, duration));
+                y = filtered;
+                state
+            })
+            .collect();
+        (states, y)
+    }
+}
+
+/// A stateful signal filter
+#[derive(Debug, Clone)]
+pub struct Filter {
+    cfg: FilterConfig,
+    /// Current filter state
+    pub state: FilterState,
+}
+
+impl Filter {
+    /// Create a new filter instance.
+    pub fn new(cfg: FilterConfig) -> Self {
+        let state = cfg.initial_state();
+        Filter { cfg, state }
+    }
+    /// Reset the internal filter state.
+    pub fn reset(&mut self) {
+        self.state = self.cfg.initial_state();
+    }
+}
+
+impl Controller<(f64, &Duration), f64> for Filter {
+    fn next(&mut self, input: (f64, &Duration)) -> f64 {
+        let (x, duration) = input;
+        let state = std::mem::replace(&mut self.state, self.cfg.initial_state());
+        let (state, y) = self.cfg.next((state, x, duration));
+        self.state = state;
+        y
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn pt1_low_pass() {
+        let cfg = Pt1Config {
+            time_constant: Duration::from_secs(3),
+        };
+        let dt = Duration::from_secs(1);
+        let (s, y) = cfg.next((Pt1State::default(), 10.0, &dt));
+        assert_eq!(y, 10.0);
+        let (s, y) = cfg.next((s, 14.0, &dt));
+        assert_eq!(y, 11.0);
+        let (_, y) = cfg.next((s, 11.0, &Duration::ZERO));
+        assert_eq!(y, 11.0);
+        let cfg = Pt1Config::default();
+        let (_, y) = cfg.next((Pt1State { value: Some(3.0) }, 5.0, &dt));
+        assert_eq!(y, 5.0);
+    }
+
+    #[test]
+    fn moving_average() {
+        let mut f = Filter::new(FilterConfig::MovingAverage(MovingAverageConfig {
+            window: 3,
+        }));
+        let dt = Duration::from_secs(1);
+        assert_eq!(f.next((3.0, &dt)), 3.0);
+        assert_eq!(f.next((6.0, &dt)), 4.5);
+        assert_eq!(f.next((9.0, &dt)), 6.0);
+        assert_eq!(f.next((0.0, &dt)), 5.0);
+        f.reset();
+        assert_eq!(f.next((1.0, &dt)), 1.0);
+    }
+
+    #[test]
+    fn median() {
+        let cfg = MedianConfig { window: 4 };
+        let (s, y) = cfg.next((WindowState::default(), 5.0));
+        assert_eq!(y, 5.0);
+        let (s, y) = cfg.next((s, -1.0));
+        assert_eq!(y, 2.0);
+        let (s, y) = cfg.next((s, 1000.0));
+        assert_eq!(y, 5.0);
+        let (s, _) = cfg.next((s, 7.0));
+        let (s, y) = cfg.next((s, 6.0));
+        assert_eq!(s.values, vec![-1.0, 1000.0, 7.0, 6.0]);
+        assert_eq!(y, 6.5);
+    }
+
+    #[test]
+    fn reinitialize_mismatching_state() {
+        let cfg = FilterConfig::MovingAverage(MovingAverageConfig { window: 2 });
+        let dt = Duration::from_secs(1);
+        let (s, y) = cfg.next((FilterState::Pt1(Pt1State::default()), 2.0, &dt));
+        assert_eq!(y, 2.0);
+        assert_eq!(
+            s,
+            FilterState::MovingAverage(WindowState {
+                values: vec![2.0].into()
+            })
+        );
+    }
+
+    #[test]
+    fn filter_chain() {
+        let chain = [
+            FilterConfig::Median(MedianConfig { window: 3 }),
+            FilterConfig::MovingAverage(MovingAverageConfig { window: 2 }),
+        ];
+        let dt = Duration::from_secs(1);
+        let (s, y) = chain.next((vec![], 2.0, &dt));
+        assert_eq!(s.len(), 2);
+        assert_eq!(y, 2.0);
+        let (s, y) = chain.next((s, 4.0, &dt));
+        assert_eq!(y, 2.5);
+        let (_, y) = chain.next((s, 100.0, &dt));
+        assert_eq!(y, 3.5);
+    }
+}
@@ -2,21 +2,197 @@
 use super::*;
 
 /// Finit State Machine
-#[derive(Debug, Clone)]
+///
+/// States can be nested: A transition of a state also applies to all of
+/// its sub states and entering a state with sub states enters its initial
+/// sub state or, with history, the sub state that was active before.
+/// The active state is always a leaf state.
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct StateMachine {
     /// Initial state
     pub initial: String,
     /// Transitions
     pub transitions: Vec<Transition>,
+    /// Nested states and states with actions
+    /// (states that are not listed here are top-level states without actions)
+    pub states: Vec<State>,
+}
+
+/// A state with sub states or actions
+#[derive(Debug, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct State {
+    pub id: String,
+    /// The enclosing state
+    pub parent: Option<String>,
+    /// The sub state that is entered first (defaults to the first sub state)
+    pub initial: Option<String>,
+    /// The sub state that is entered when re-entering the state
+    pub history: History,
+    /// Actions that are applied when the state is entered
+    pub entry: Vec<String>,
+    /// Actions that are applied when the state is left
+    pub exit: Vec<String>,
+    /// Actions that are applied on each step while the state is active
+    pub during: Vec<String>,
+}
+
+/// History of a state with sub states
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum History {
+    /// Always enter the initial sub state
+    #[default]
+    None,
+    /// Enter the last active direct sub state
+    Shallow,
+    /// Enter the last active leaf state
+    Deep,
+}
+
+/// The last active leaf states of the states with sub states
+pub type StateHistory = HashMap<String, String>;
+
+/// Internal state of a state machine beside the active state
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateMachineData {
+    /// History of the states with sub states
+    pub history: StateHistory,
+    /// How long the active state and its enclosing states have been active
+    pub elapsed: HashMap<String, Duration>,
 }
 
 /// A State Transition
 #[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Transition {
+    #[cfg_attr(feature = "serde", serde(with = "crate::parser::condition_text"))]
     pub condition: BoolExpr<Comparison>,
     pub from: String,
     pub to: String,
+    #[cfg_attr(feature = "serde", serde(default))]
     pub actions: Vec<String>,
+    /// The transition fires not until the `from` state
+    /// has been active for this duration
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub after: Option<Duration>,
+}
+
+impl StateMachine {
+    fn parent(&self, state: &str) -> Option<&str> {
+        self.states
+            .iter()
+            .find(|s| s.id == state)
+            .and_then(|s| s.parent.as_deref())
+    }
+
+    /// The state and all its enclosing states (innermost first).
+    pub fn path<'a>(&'a self, state: &'a str) -> Vec<&'a str> {
+        let mut path = vec![state];
+        while let Some(parent) = path.last().and_then(|s| self.parent(s)) {
+            if path.contains(&parent) {
+                break;
+            }
+            path.push(parent);
+        }
+        path
+    }
+
+    /// The leaf state that becomes active when entering a state.
+    pub fn enter(&self, state: &str, history: &StateHistory) -> String {
+        let mut state = state.to_string();
+        let mut visited = vec![];
+        while !visited.contains(&state) {
+            let sub_states = self
+                .states
+                .iter()
+                .filter(|s| s.parent.as_deref() == Some(&state))
+                .collect::<Vec<_>>();
+            if sub_states.is_empty() {
+                break;
+            }
+            let mode = self
+                .states
+                .iter()
+                .find(|s| s.id == state)
+                .map(|s| s.history)
+                .unwrap_or_default();
+            let last = history.get(&state);
+            let next = match (mode, last) {
+                (History::Deep, Some(last)) => return last.clone(),
+                (History::Shallow, Some(last)) => self
+                    .path(last)
+                    .into_iter()
+                    .find(|s| sub_states.iter().any(|sub| sub.id == *s))
+                    .map(ToString::to_string),
+                _ => None,
+            };
+            let next = next
+                .or_else(|| {
+                    self.states
+                        .iter()
+                        .find(|s| s.id == state)
+                        .and_then(|s| s.initial.clone())
+                })
+                .unwrap_or_else(|| sub_states[0].id.clone());
+            visited.push(std::mem::replace(&mut state, next));
+        }
+        state
+    }
+
+    /// Advance the time of the active state and its enclosing states.
+    pub fn tick(&self, state: &str, data: &mut StateMachineData, dt: &Duration) {
+        let path = self.path(state);
+        data.elapsed.retain(|s, _| path.contains(&s.as_str()));
+        for s in path {
+            *data.elapsed.entry(s.to_string()).or_default() += *dt;
+        }
+    }
+
+    /// Update the history and the times on a transition between two leaf states.
+    pub fn transit(&self, from: &str, to: &str, data: &mut StateMachineData) {
+        self.leave(from, &mut data.history);
+        if from == to {
+            data.elapsed.clear();
+        } else {
+            let path = self.path(to);
+            data.elapsed.retain(|s, _| path.contains(&s.as_str()));
+        }
+    }
+
+    fn actions<'a>(
+        &'a self,
+        states: impl Iterator<Item = &'a str>,
+        actions: impl Fn(&'a State) -> &'a Vec<String>,
+    ) -> Vec<String> {
+        states
+            .filter_map(|id| self.states.iter().find(|s| s.id == id))
+            .flat_map(|s| actions(s).iter().cloned())
+            .collect()
+    }
+
+    /// The entry actions of a leaf state and its enclosing states (outermost first).
+    pub fn entry_actions(&self, state: &str) -> Vec<String> {
+        self.actions(self.path(state).into_iter().rev(), |s| &s.entry)
+    }
+
+    /// The actions of a leaf state and its enclosing states that are
+    /// applied while they are active (outermost first).
+    pub fn during_actions(&self, state: &str) -> Vec<String> {
+        self.actions(self.path(state).into_iter().rev(), |s| &s.during)
+    }
+
+    /// Remember a leaf state that is left in the history of
+    /// all its enclosing states.
+    pub fn leave(&self, state: &str, history: &mut StateHistory) {
+        for s in self.path(state).into_iter().skip(1) {
+            history.insert(s.to_string(), state.to_string());
+        }
+    }
 }
 
 impl<'a> PureController<(Option<&'a str>, &'a SystemState), Option<(String, Vec<String>)>>
@@ -24,12 +200,58 @@ impl<'a> PureController<(Option<&'a str>, &'a SystemState), Option<(String, Vec<
 {
     fn next(&self, input: (Option<&str>, &SystemState)) -> Option<(String, Vec<String>)> {
         let (fsm_state, state) = input;
+        self.next((fsm_state, &StateMachineData::default(), state))
+    }
+}
 
-        for t in &self.transitions {
-            if t.from == fsm_state.unwrap_or(&self.initial) {
+impl<'a>
+    PureController<
+        (Option<&'a str>, &'a StateMachineData, &'a SystemState),
+        Option<(String, Vec<String>)>,
+    > for StateMachine
+{
+    fn next(
+        &self,
+        input: (Option<&str>, &StateMachineData, &SystemState),
+    ) -> Option<(String, Vec<String>)> {
+        // The actions are ordered: exit actions (innermost first),
+        // transition actions and entry actions (outermost first).
+        let (fsm_state, data, state) = input;
+        let history = &data.history;
+        let current = match fsm_state {
+            Some(s) => s.to_string(),
+            None => self.enter(&self.initial, history),
+        };
+        // Transitions of inner states take precedence
+        for s in self.path(&current) {
+            for t in self.transitions.iter().filter(|t| t.from == s) {
+                if let Some(after) = t.after {
+                    if data.elapsed.get(s).copied().unwrap_or_default() < after {
+                        continue;
+                    }
+                }
                 if let Ok(active) = t.condition.eval(state) {
                     if active {
-                        return Some((t.to.clone(), t.actions.clone()));
+                        let mut history = history.clone();
+                        self.leave(&current, &mut history);
+                        let next = self.enter(&t.to, &history);
+                        let old_path = self.path(&current);
+                        let new_path = self.path(&next);
+                        // A self-transition leaves and re-enters the state
+                        let reentered = if t.from == t.to {
+                            &old_path[..=old_path.iter().position(|s| *s == t.from).unwrap_or(0)]
+                        } else {
+                            &[]
+                        };
+                        let kept = |s: &&str| {
+                            !reentered.contains(s) && old_path.contains(s) && new_path.contains(s)
+                        };
+                        let exited = old_path.iter().copied().filter(|s| !kept(s));
+                        let entered = new_path.iter().rev().copied().filter(|s| !kept(s));
+                        let mut actions = self.actions(exited, |s| &s.exit);
+                        actions.extend(t.actions.iter().cloned());
+                        actions.extend(self.actions(entered, |s| &s.entry));
+                        return Some((next, actions));
                     }
                 }
             }
@@ -56,6 +278,7 @@ mod tests {
                     from: "start".into(),
                     to: "step-one".into(),
                     actions: vec![],
+                    after: None,
                 },
                 Transition {
                     condition: BoolExpr::Eval(
@@ -64,8 +287,10 @@ mod tests {
                     from: "step-one".into(),
                     to: "step-two".into(),
                     actions: vec![],
+                    after: None,
                 },
             ],
+            ..Default::default()
         };
         assert_eq!(machine.next((None, &state)), None);
         state.io.inputs.insert("x".into(), Value::Decimal(5.1));
@@ -80,4 +305,204 @@ mod tests {
             Some(("step-two".into(), vec![]))
         );
     }
+
+    fn cond(id: &str) -> BoolExpr<Comparison> {
+        BoolExpr::Eval(Source::In(id.into()).cmp_eq(Source::Const(true.into())))
+    }
+
+    fn transition(from: &str, to: &str, condition: &str) -> Transition {
+        Transition {
+            condition: cond(condition),
+            from: from.into(),
+            to: to.into(),
+            actions: vec![],
+            after: None,
+        }
+    }
+
+    fn nested(id: &str, parent: Option<&str>, history: History) -> State {
+        State {
+            id: id.into(),
+            parent: parent.map(Into::into),
+            history,
+            ..Default::default()
+        }
+    }
+
+    fn machine(history: History) -> StateMachine {
+        StateMachine {
+            initial: "auto".into(),
+            transitions: vec![
+                transition("filling", "heating", "full"),
+                transition("heating", "draining", "hot"),
+                transition("auto", "stopped", "stop"),
+                transition("stopped", "auto", "start"),
+            ],
+            states: vec![
+                nested("auto", None, history),
+                nested("filling", Some("auto"), History::None),
+                nested("heating", Some("auto"), History::None),
+                nested("draining", Some("auto"), History::None),
+            ],
+        }
+    }
+
+    #[test]
+    fn enter_nested_states() {
+        let m = machine(History::None);
+        let history = StateHistory::new();
+        assert_eq!(m.enter("auto", &history), "filling");
+        assert_eq!(m.enter("heating", &history), "heating");
+        assert_eq!(m.path("heating"), vec!["heating", "auto"]);
+        let mut state = SystemState::default();
+        state.io.inputs.insert("full".into(), true.into());
+        // The initial state is resolved to a leaf state
+        assert_eq!(m.next((None, &state)), Some(("heating".into(), vec![])));
+    }
+
+    #[test]
+    fn inherit_transitions() {
+        let m = machine(History::None);
+        let mut state = SystemState::default();
+        state.io.inputs.insert("stop".into(), true.into());
+        for s in ["filling", "heating", "draining"] {
+            assert_eq!(m.next((Some(s), &state)), Some(("stopped".into(), vec![])));
+        }
+        // Transitions of the inner state take precedence
+        state.io.inputs.insert("hot".into(), true.into());
+        assert_eq!(
+            m.next((Some("heating"), &state)),
+            Some(("draining".into(), vec![]))
+        );
+    }
+
+    #[test]
+    fn reenter_with_history() {
+        let mut state = SystemState::default();
+        state.io.inputs.insert("start".into(), true.into());
+        let m = machine(History::None);
+        let mut data = StateMachineData::default();
+        m.leave("heating", &mut data.history);
+        assert_eq!(data.history.get("auto").unwrap(), "heating");
+        assert_eq!(
+            m.next((Some("stopped"), &data, &state)),
+            Some(("filling".into(), vec![]))
+        );
+        let m = machine(History::Shallow);
+        assert_eq!(
+            m.next((Some("stopped"), &data, &state)),
+            Some(("heating".into(), vec![]))
+        );
+    }
+
+    #[test]
+    fn distinguish_shallow_and_deep_history() {
+        let mut m = machine(History::Shallow);
+        m.states
+            .push(nested("burner-on", Some("heating"), History::None));
+        m.states
+            .push(nested("burner-off", Some("heating"), History::None));
+        let mut history = StateHistory::new();
+        m.leave("burner-off", &mut history);
+        assert_eq!(m.enter("auto", &history), "burner-on");
+        m.states[0].history = History::Deep;
+        assert_eq!(m.enter("auto", &history), "burner-off");
+    }
+
+    #[test]
+    fn fire_timed_transitions() {
+        let mut m = machine(History::None);
+        m.transitions[0].condition = BoolExpr::True;
+        m.transitions[0].after = Some(Duration::from_secs(10));
+        let state = SystemState::default();
+        let mut data = StateMachineData::default();
+        let dt = Duration::from_secs(4);
+        for _ in 0..2 {
+            m.tick("filling", &mut data, &dt);
+            assert_eq!(m.next((Some("filling"), &data, &state)), None);
+        }
+        m.tick("filling", &mut data, &dt);
+        assert_eq!(data.elapsed["filling"], Duration::from_secs(12));
+        assert_eq!(
+            m.next((Some("filling"), &data, &state)),
+            Some(("heating".into(), vec![]))
+        );
+        // Timed transitions never fire without elapsed times
+        assert_eq!(m.next((Some("filling"), &state)), None);
+    }
+
+    #[test]
+    fn reset_times_of_exited_states() {
+        let m = machine(History::None);
+        let mut data = StateMachineData::default();
+        let dt = Duration::from_secs(1);
+        m.tick("filling", &mut data, &dt);
+        m.transit("filling", "heating", &mut data);
+        m.tick("heating", &mut data, &dt);
+        assert_eq!(data.elapsed["auto"], Duration::from_secs(2));
+        assert_eq!(data.elapsed["heating"], Duration::from_secs(1));
+        assert!(!data.elapsed.contains_key("filling"));
+        m.transit("heating", "stopped", &mut data);
+        assert!(data.elapsed.is_empty());
+        m.tick("stopped", &mut data, &dt);
+        m.transit("stopped", "stopped", &mut data);
+        assert!(data.elapsed.is_empty());
+    }
+
+    #[test]
+    fn order_entry_and_exit_actions() {
+        let mut m = machine(History::None);
+        let with_actions = |mut s: State| {
+            s.entry = vec![format!("enter-{}", s.id)];
+            s.exit = vec![format!("exit-{}", s.id)];
+            s.during = vec![format!("in-{}", s.id)];
+            s
+        };
+        m.states = m.states.into_iter().map(with_actions).collect();
+        m.states
+            .push(with_actions(nested("stopped", None, History::None)));
+        m.transitions[2].actions = vec!["stop".into()];
+        m.transitions.push(transition("auto", "auto", "restart"));
+        let mut state = SystemState::default();
+        state.io.inputs.insert("stop".into(), true.into());
+        assert_eq!(
+            m.next((Some("heating"), &state)),
+            Some((
+                "stopped".into(),
+                vec![
+                    "exit-heating".into(),
+                    "exit-auto".into(),
+                    "stop".into(),
+                    "enter-stopped".into()
+                ]
+            ))
+        );
+        state.io.inputs.insert("full".into(), true.into());
+        assert_eq!(
+            m.next((Some("filling"), &state)),
+            Some((
+                "heating".into(),
+                vec!["exit-filling".into(), "enter-heating".into()]
+            ))
+        );
+        let mut state = SystemState::default();
+        state.io.inputs.insert("restart".into(), true.into());
+        assert_eq!(
+            m.next((Some("heating"), &state)),
+            Some((
+                "filling".into(),
+                vec![
+                    "exit-heating".into(),
+                    "exit-auto".into(),
+                    "enter-auto".into(),
+                    "enter-filling".into()
+                ]
+            ))
+        );
+        assert_eq!(
+            m.entry_actions("filling"),
+            vec!["enter-auto", "enter-filling"]
+        );
+        assert_eq!(m.during_actions("heating"), vec!["in-auto", "in-heating"]);
+    }
 }
@@ -0,0 +1,481 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, fuzzy::*};
+//!
+//! let set = |name: &str, left, peak, right| FuzzySet {
+//!     name: name.into(),
+//!     membership: Membership::Triangle { left, peak, right },
+//! };
+//! let cfg = FuzzyConfig {
+//!     default_target: 20.0,
+//!     error: FuzzyVariable {
+//!         min: -10.0,
+//!         max: 10.0,
+//!         sets: vec![
+//!             set("negative", -20.0, -10.0, 0.0),
+//!             set("zero", -10.0, 0.0, 10.0),
+//!             set("positive", 0.0, 10.0, 20.0),
+//!         ],
+//!     },
+//!     output: FuzzyVariable {
+//!         min: 0.0,
+//!         max: 100.0,
+//!         sets: vec![
+//!             set("low", -50.0, 0.0, 50.0),
+//!             set("medium", 0.0, 50.0, 100.0),
+//!             set("high", 50.0, 100.0, 150.0),
+//!         ],
+//!     },
+//!     rules: vec![
+//!         FuzzyRule::new(FuzzyCondition::is(FuzzyInput::Error, "negative"), "low"),
+//!         FuzzyRule::new(FuzzyCondition::is(FuzzyInput::Error, "zero"), "medium"),
+//!         FuzzyRule::new(FuzzyCondition::is(FuzzyInput::Error, "positive"), "high"),
+//!     ],
+//!     ..Default::default()
+//! };
+//! let mut c = Fuzzy::new(cfg);
+//!
+//! let delta_t = Duration::from_secs(1);
+//! let heating = c.next(20.0, &delta_t);
+//! assert!((heating - 50.0).abs() < 1e-9);
+//! ```
+
+use super::{util, Controller, PureController};
+use std::time::Duration;
+
+/// Fuzzy inference controller
+///
+/// The control error (`target - actual`) and its rate of change
+/// are fuzzified by the configured membership functions.
+/// Each rule clips its output set by the truth value of its
+/// condition (`AND` = minimum, `OR` = maximum, `NOT` = complement).
+/// The clipped sets are aggregated by their maximum and the
+/// result is defuzzified by calculating the centroid.
+#[derive(Debug, Clone)]
+pub struct Fuzzy {
+    cfg: FuzzyConfig,
+    /// Current controller state
+    pub state: FuzzyState,
+}
+
+/// Fuzzy controller configuration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuzzyConfig {
+    /// Target that is used after initialization
+    pub default_target: f64,
+    /// Linguistic terms of the control error
+    pub error: FuzzyVariable,
+    /// Linguistic terms of the change of the control error per second
+    pub error_rate: FuzzyVariable,
+    /// Linguistic terms of the controller output
+    pub output: FuzzyVariable,
+    /// The rule base
+    pub rules: Vec<FuzzyRule>,
+    /// Number of samples of the output range for the defuzzification
+    pub resolution: usize,
+}
+
+impl Default for FuzzyConfig {
+    fn default() -> Self {
+        FuzzyConfig {
+            default_target: 0.0,
+            error: FuzzyVariable::default(),
+            error_rate: FuzzyVariable::default(),
+            output: FuzzyVariable::default(),
+            rules: vec![],
+            resolution: 101,
+        }
+    }
+}
+
+/// A linguistic variable
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuzzyVariable {
+    /// Lower bound of the universe of discourse
+    pub min: f64,
+    /// Upper bound of the universe of discourse
+    pub max: f64,
+    /// The linguistic terms
+    pub sets: Vec<FuzzySet>,
+}
+
+impl FuzzyVariable {
+    /// The degree of membership of `x` in the set with the given name.
+    ///
+    /// `x` is limited to the bounds of the variable and
+    /// unknown set names have a degree of `0.0`.
+    pub fn degree(&self, set: &str, x: f64) -> f64 {
+        let x = util::limit(Some(self.min), Some(self.max), x);
+        self.sets
+            .iter()
+            .find(|s| s.name == set)
+            .map(|s| s.membership.degree(x))
+            .unwrap_or(0.0)
+    }
+}
+
+/// A linguistic term
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuzzySet {
+    /// The name that is referenced by the rules
+    pub name: String,
+    /// The membership function
+    pub membership: Membership,
+}
+
+/// Membership function
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Membership {
+    /// Rises from `left` to `peak` and falls to `right`
+    Triangle { left: f64, peak: f64, right: f64 },
+    /// Rises from `left` to `left_top`, is `1.0` up to `right_top`
+    /// and falls to `right`
+    Trapezoid {
+        left: f64,
+        left_top: f64,
+        right_top: f64,
+        right: f64,
+    },
+    /// Bell curve around `mean`
+    Gaussian { mean: f64, sigma: f64 },
+}
+
+impl Membership {
+    /// The degree of membership (`0.0 ..= 1.0`) of `x`.
+    pub fn degree(&self, x: f64) -> f64 {
+        match *self {
+            Membership::Triangle { left, peak, right } => trapezoid(left, peak, peak, right, x),
+            Membership::Trapezoid {
+                left,
+                left_top,
+                right_top,
+                right,
+            } => trapezoid(left, left_top, right_top, right, x),
+            Membership::Gaussian { mean, sigma } => {
+                if sigma == 0.0 {
+                    return if x == mean { 1.0 } else { 0.0 };
+                }
+                (-(x - mean).powi(2) / (2.0 * sigma.powi(2))).exp()
+            }
+        }
+    }
+}
+
+fn trapezoid(left: f64, left_top: f64, right_top: f64, right: f64, x: f64) -> f64 {
+    if x < left || x > right {
+        0.0
+    } else if x < left_top {
+        (x - left) / (left_top - left)
+    } else if x <= right_top {
+        1.0
+    } else {
+        (right - x) / (right - right_top)
+    }
+}
+
+/// Input of the fuzzy controller
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FuzzyInput {
+    /// The control error
+    Error,
+    /// The change of the control error per second
+    ErrorRate,
+}
+
+/// Condition of a fuzzy rule
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum FuzzyCondition {
+    /// The input is member of the named set
+    Is(FuzzyInput, String),
+    /// Minimum of all conditions
+    And(Vec<FuzzyCondition>),
+    /// Maximum of all conditions
+    Or(Vec<FuzzyCondition>),
+    /// Complement of the condition
+    Not(Box<FuzzyCondition>),
+}
+
+impl FuzzyCondition {
+    /// Create an `Is` condition.
+    pub fn is(input: FuzzyInput, set: &str) -> Self {
+        FuzzyCondition::Is(input, set.into())
+    }
+
+    fn truth(&self, cfg: &FuzzyConfig, err: f64, err_rate: f64) -> f64 {
+        match self {
+            FuzzyCondition::Is(FuzzyInput::Error, set) => cfg.error.degree(set, err),
+            FuzzyCondition::Is(FuzzyInput::ErrorRate, set) => cfg.error_rate.degree(set, err_rate),
+            FuzzyCondition::And(conditions) => conditions
+                .iter()
+                .map(|c| c.truth(cfg, err, err_rate))
+                .fold(1.0, f64::min),
+            FuzzyCondition::Or(conditions) => conditions
+                .iter()
+                .map(|c| c.truth(cfg, err, err_rate))
+                .fold(0.0, f64::max),
+            FuzzyCondition::Not(condition) => 1.0 - condition.truth(cfg, err, err_rate),
+        }
+    }
+}
+
+/// A fuzzy rule (`IF condition THEN output IS set`)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuzzyRule {
+    /// The condition
+    pub condition: FuzzyCondition,
+    /// Name of the output set
+    pub output: String,
+}
+
+impl FuzzyRule {
+    /// Create a new rule.
+    pub fn new(condition: FuzzyCondition, output: &str) -> Self {
+        FuzzyRule {
+            condition,
+            output: output.into(),
+        }
+    }
+}
+
+/// Internal fuzzy controller state
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct FuzzyState {
+    /// Current target
+    pub target: f64,
+    /// Control error of the previous step
+    pub prev_err: Option<f64>,
+    /// Output of the previous step
+    ///
+    /// The output is kept if no rule applies.
+    pub output: f64,
+}
+
+impl Fuzzy {
+    /// Create a new controller instance.
+    pub fn new(cfg: FuzzyConfig) -> Self {
+        let state = FuzzyState {
+            target: cfg.default_target,
+            ..Default::default()
+        };
+        Fuzzy { cfg, state }
+    }
+    /// Set target value.
+    pub fn set_target(&mut self, target: f64) {
+        self.state.target = target;
+    }
+    /// Reset the internal controller state.
+    pub fn reset(&mut self) {
+        *self = Self::new(self.cfg.clone());
+    }
+}
+
+impl Controller<(f64, &Duration), f64> for Fuzzy {
+    fn next(&mut self, input: (f64, &Duration)) -> f64 {
+        let (actual, duration) = input;
+        let (state, result) = self.cfg.next((self.state, actual, duration));
+        self.state = state;
+        result
+    }
+}
+
+impl PureController<(FuzzyState, f64, &Duration), (FuzzyState, f64)> for FuzzyConfig {
+    fn next(&self, input: (FuzzyState, f64, &Duration)) -> (FuzzyState, f64) {
+        let (mut state, actual, duration) = input;
+        let delta_t = duration.as_secs_f64();
+
+        let err = state.target - actual;
+        let err_rate = match state.prev_err {
+            Some(prev_err) if delta_t > 0.0 => (err - prev_err) / delta_t,
+            _ => 0.0,
+        };
+        state.prev_err = Some(err);
+
+        let strengths: Vec<_> = self
+            .rules
+            .iter()
+            .map(|r| (r.condition.truth(self, err, err_rate), &r.output))
+            .filter(|(strength, _)| *strength > 0.0)
+            .collect();
+
+        let samples = self.resolution.max(2);
+        let step = (self.output.max - self.output.min) / (samples - 1) as f64;
+        let mut sum = 0.0;
+        let mut weighted_sum = 0.0;
+        for i in 0..samples {
+            let x = self.output.min + i as f64 * step;
+            let mu = strengths
+                .iter()
+                .map(|(strength, set)| strength.min(self.output.degree(set, x)))
+                .fold(0.0, f64::max);
+            sum += mu;
+            weighted_sum += mu * x;
+        }
+        if sum > 0.0 {
+            state.output = weighted_sum / sum;
+        }
+        (state, state.output)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    fn triangle(name: &str, left: f64, peak: f64, right: f64) -> FuzzySet {
+        FuzzySet {
+            name: name.into(),
+            membership: Membership::Triangle { left, peak, right },
+        }
+    }
+
+    fn cfg() -> FuzzyConfig {
+        FuzzyConfig {
+            error: FuzzyVariable {
+                min: -10.0,
+                max: 10.0,
+                sets: vec![
+                    triangle("negative", -20.0, -10.0, 0.0),
+                    triangle("zero", -10.0, 0.0, 10.0),
+                    triangle("positive", 0.0, 10.0, 20.0),
+                ],
+            },
+            error_rate: FuzzyVariable {
+                min: -1.0,
+                max: 1.0,
+                sets: vec![
+                    triangle("falling", -2.0, -1.0, 0.0),
+                    triangle("rising", 0.0, 1.0, 2.0),
+                ],
+            },
+            output: FuzzyVariable {
+                min: -10.0,
+                max: 10.0,
+                sets: vec![
+                    triangle("low", -20.0, -10.0, 0.0),
+                    triangle("medium", -10.0, 0.0, 10.0),
+                    triangle("high", 0.0, 10.0, 20.0),
+                ],
+            },
+            rules: vec![
+                FuzzyRule::new(FuzzyCondition::is(FuzzyInput::Error, "negative"), "low"),
+                FuzzyRule::new(FuzzyCondition::is(FuzzyInput::Error, "zero"), "medium"),
+                FuzzyRule::new(FuzzyCondition::is(FuzzyInput::Error, "positive"), "high"),
+            ],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn membership_degrees() {
+        let t = Membership::Triangle {
+            left: 0.0,
+            peak: 1.0,
+            right: 3.0,
+        };
+        assert_eq!(t.degree(-1.0), 0.0);
+        assert_eq!(t.degree(0.5), 0.5);
+        assert_eq!(t.degree(1.0), 1.0);
+        assert_eq!(t.degree(2.0), 0.5);
+        assert_eq!(t.degree(4.0), 0.0);
+        let t = Membership::Trapezoid {
+            left: 0.0,
+            left_top: 0.0,
+            right_top: 2.0,
+            right: 4.0,
+        };
+        assert_eq!(t.degree(0.0), 1.0);
+        assert_eq!(t.degree(2.0), 1.0);
+        assert_eq!(t.degree(3.0), 0.5);
+        let g = Membership::Gaussian {
+            mean: 1.0,
+            sigma: 2.0,
+        };
+        assert_eq!(g.degree(1.0), 1.0);
+        assert!((g.degree(3.0) - (-0.5_f64).exp()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn degree_of_unknown_set_and_limited_input() {
+        let cfg = cfg();
+        assert_eq!(cfg.error.degree("foo", 0.0), 0.0);
+        assert_eq!(cfg.error.degree("positive", 100.0), 1.0);
+    }
+
+    #[test]
+    fn defuzzify_by_centroid() {
+        let mut c = Fuzzy::new(cfg());
+        let dt = Duration::from_secs(1);
+        assert!(c.next((0.0, &dt)).abs() < 1e-9);
+        // The result is symmetric
+        let y = c.next((-5.0, &dt));
+        assert!(y > 0.0 && y < 10.0);
+        c.reset();
+        assert!((c.next((5.0, &dt)) + y).abs() < 1e-9);
+        c.set_target(100.0);
+        assert!(c.next((0.0, &dt)) > 5.0);
+    }
+
+    #[test]
+    fn combine_conditions() {
+        let mut cfg = cfg();
+        cfg.rules = vec![
+            FuzzyRule::new(
+                FuzzyCondition::And(vec![
+                    FuzzyCondition::is(FuzzyInput::Error, "zero"),
+                    FuzzyCondition::is(FuzzyInput::ErrorRate, "rising"),
+                ]),
+                "high",
+            ),
+            FuzzyRule::new(
+                FuzzyCondition::Or(vec![
+                    FuzzyCondition::is(FuzzyInput::Error, "negative"),
+                    FuzzyCondition::is(FuzzyInput::ErrorRate, "falling"),
+                ]),
+                "low",
+            ),
+        ];
+        let dt = Duration::from_secs(1);
+        let state = FuzzyState::default();
+        // error rate is zero
+        let (state, y) = cfg.next((state, 0.0, &dt));
+        assert_eq!(y, 0.0);
+        // error is zero and rising
+        let (state, y) = cfg.next((state, -0.5, &dt));
+        assert!(y > 0.0);
+        // error is falling
+        let (_, y) = cfg.next((state, 0.0, &dt));
+        assert!(y < 0.0);
+
+        cfg.rules = vec![FuzzyRule::new(
+            FuzzyCondition::Not(Box::new(FuzzyCondition::is(FuzzyInput::Error, "zero"))),
+            "high",
+        )];
+        let (_, y) = cfg.next((FuzzyState::default(), 0.0, &dt));
+        assert_eq!(y, 0.0);
+        let (_, y) = cfg.next((FuzzyState::default(), -10.0, &dt));
+        assert!(y > 5.0);
+    }
+
+    #[test]
+    fn keep_output_if_no_rule_applies() {
+        let mut cfg = cfg();
+        cfg.rules.truncate(1);
+        let dt = Duration::from_secs(1);
+        let (state, y) = cfg.next((FuzzyState::default(), 5.0, &dt));
+        assert!(y < 0.0);
+        let (_, y2) = cfg.next((state, 0.0, &dt));
+        assert_eq!(y, y2);
+    }
+}
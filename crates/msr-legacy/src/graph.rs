@@ -0,0 +1,708 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{graph::*, pid::PidConfig, IoState, PureController, Value};
+//!
+//! // Cascade: The outer PID sets the target of the inner PID
+//! let graph = GraphConfig {
+//!     id: "cascade".into(),
+//!     nodes: vec![
+//!         Node::new(
+//!             "outer",
+//!             BlockConfig::Pid(PidConfig::default()),
+//!             &["temperature", "setpoint"],
+//!             "flow_target",
+//!         ),
+//!         Node::new(
+//!             "inner",
+//!             BlockConfig::Pid(PidConfig::default()),
+//!             &["flow", "flow_target"],
+//!             "valve",
+//!         ),
+//!     ],
+//!     outputs: vec!["valve".into()],
+//! };
+//!
+//! let mut io = IoState::default();
+//! io.inputs.insert("temperature".into(), 18.0.into());
+//! io.inputs.insert("flow".into(), 1.0.into());
+//! io.mem.insert("setpoint".into(), 20.0.into());
+//!
+//! let state = graph.initial_state();
+//! let (state, io) = graph.next((&state, &io, &Duration::from_secs(1))).unwrap();
+//!
+//! assert_eq!(state.signals["flow_target"], Value::Decimal(2.0));
+//! assert_eq!(io.outputs["valve"], Value::Decimal(1.0));
+//! ```
+
+use super::{
+    filter, hysteresis, limiter, lookup_table, pid, rate_limiter, scaling, totalizer, IoState,
+    PureController, Value,
+};
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind, Result},
+    time::Duration,
+};
+
+pub use super::selector::Selector;
+
+/// A directed acyclic graph of function blocks
+///
+/// Blocks are connected by named signals. A signal is either the output
+/// of a block or, if no block drives it, an input or a memory value of
+/// the [IoState]. The blocks are sorted topologically and executed in
+/// that order on each step, so a block always sees the current outputs
+/// of its predecessors. Cycles are rejected as algebraic loops unless
+/// they are broken by a [BlockConfig::UnitDelay].
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphConfig {
+    /// The unique ID of the graph
+    pub id: String,
+    /// Function blocks
+    pub nodes: Vec<Node>,
+    /// Signals that are written to the outputs with the same ID
+    pub outputs: Vec<String>,
+}
+
+/// A function block within a graph
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Node {
+    /// The unique ID of the block
+    pub id: String,
+    /// The block configuration
+    pub block: BlockConfig,
+    /// Input signals
+    pub inputs: Vec<String>,
+    /// Output signal
+    pub output: String,
+}
+
+impl Node {
+    /// Create a new node.
+    pub fn new(id: &str, block: BlockConfig, inputs: &[&str], output: &str) -> Self {
+        Node {
+            id: id.into(),
+            block,
+            inputs: inputs.iter().map(|x| x.to_string()).collect(),
+            output: output.into(),
+        }
+    }
+}
+
+/// Function block configuration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlockConfig {
+    /// A constant value (no inputs)
+    Const(f64),
+    /// PID controller (inputs: actual value and an optional target)
+    Pid(pid::PidConfig),
+    /// Signal filter (input: raw value)
+    Filter(filter::FilterConfig),
+    /// Rate limiter (input: unlimited value)
+    RateLimiter(rate_limiter::RateLimiterConfig),
+    /// Hysteresis (input: compared value, output: bit)
+    Hysteresis(hysteresis::HysteresisConfig),
+    /// Arithmetic operation
+    Math(MathOp),
+    /// Select one of the inputs
+    Select(Selector),
+    /// Limiter (input: unlimited value)
+    Limit(limiter::LimiterConfig),
+    /// Linear scaling (input: raw value)
+    Scale(scaling::Scaling),
+    /// Totalizer (inputs: rate and an optional reset signal)
+    ///
+    /// The total is reset while the reset signal is not zero.
+    Totalizer(totalizer::TotalizerConfig),
+    /// Characteristic curve (input: x)
+    Lookup(lookup_table::LookupTable),
+    /// Characteristic map (inputs: x and y)
+    Lookup2d(lookup_table::LookupTable2d),
+    /// The value of the input signal of the previous step
+    ///
+    /// The given value is used in the first step.
+    UnitDelay(f64),
+}
+
+/// Arithmetic operation of a [BlockConfig::Math] block
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum MathOp {
+    /// Sum of all inputs
+    Add,
+    /// First input minus the second input
+    Sub,
+    /// Product of all inputs
+    Mul,
+    /// First input divided by the second input
+    Div,
+    /// Negated input
+    Neg,
+    /// Absolute value of the input
+    Abs,
+}
+
+/// Internal state of a function block
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlockState {
+    Pid(pid::PidState),
+    Filter(filter::FilterState),
+    RateLimiter(rate_limiter::RateLimiterState),
+    Hysteresis(hysteresis::HysteresisState),
+    Totalizer(totalizer::TotalizerState),
+}
+
+/// Internal graph state
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GraphState {
+    /// States of the stateful blocks
+    pub blocks: HashMap<String, BlockState>,
+    /// All signals of the previous step
+    pub signals: HashMap<String, Value>,
+}
+
+impl BlockConfig {
+    /// The state of a block that has not been executed yet
+    /// (none for stateless blocks).
+    pub fn initial_state(&self) -> Option<BlockState> {
+        match self {
+            BlockConfig::Pid(cfg) => Some(BlockState::Pid(pid::PidState {
+                target: cfg.default_target,
+                ..Default::default()
+            })),
+            BlockConfig::Filter(cfg) => Some(BlockState::Filter(cfg.initial_state())),
+            BlockConfig::RateLimiter(_) => Some(BlockState::RateLimiter(Default::default())),
+            BlockConfig::Hysteresis(_) => Some(BlockState::Hysteresis(Default::default())),
+            BlockConfig::Totalizer(_) => Some(BlockState::Totalizer(Default::default())),
+            BlockConfig::Const(_)
+            | BlockConfig::Math(_)
+            | BlockConfig::Select(_)
+            | BlockConfig::Limit(_)
+            | BlockConfig::Scale(_)
+            | BlockConfig::Lookup(_)
+            | BlockConfig::Lookup2d(_)
+            | BlockConfig::UnitDelay(_) => None,
+        }
+    }
+
+    fn required_inputs(&self) -> (usize, Option<usize>) {
+        match self {
+            BlockConfig::Const(_) => (0, Some(0)),
+            BlockConfig::Pid(_) | BlockConfig::Totalizer(_) => (1, Some(2)),
+            BlockConfig::Filter(_)
+            | BlockConfig::RateLimiter(_)
+            | BlockConfig::Hysteresis(_)
+            | BlockConfig::Limit(_)
+            | BlockConfig::Scale(_)
+            | BlockConfig::Lookup(_)
+            | BlockConfig::UnitDelay(_) => (1, Some(1)),
+            BlockConfig::Lookup2d(_) => (2, Some(2)),
+            BlockConfig::Math(MathOp::Sub | MathOp::Div) => (2, Some(2)),
+            BlockConfig::Math(MathOp::Neg | MathOp::Abs) => (1, Some(1)),
+            BlockConfig::Math(MathOp::Add | MathOp::Mul) | BlockConfig::Select(_) => (1, None),
+        }
+    }
+}
+
+impl GraphConfig {
+    /// The state of a graph that has not been executed yet.
+    pub fn initial_state(&self) -> GraphState {
+        GraphState {
+            blocks: self
+                .nodes
+                .iter()
+                .filter_map(|n| n.block.initial_state().map(|s| (n.id.clone(), s)))
+                .collect(),
+            signals: HashMap::new(),
+        }
+    }
+
+    /// Sort the blocks topologically.
+    ///
+    /// Returns the indices of the nodes in execution order or
+    /// an error if the graph is invalid or contains an algebraic loop.
+    pub fn execution_order(&self) -> Result<Vec<usize>> {
+        let mut drivers = HashMap::new();
+        for (idx, n) in self.nodes.iter().enumerate() {
+            let (min, max) = n.block.required_inputs();
+            if n.inputs.len() < min || max.map(|max| n.inputs.len() > max).unwrap_or(false) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Invalid number of inputs of block '{}'", n.id),
+                ));
+            }
+            match &n.block {
+                BlockConfig::Lookup(table) => table.validate()?,
+                BlockConfig::Lookup2d(table) => table.validate()?,
+                BlockConfig::Scale(scaling) => scaling.validate()?,
+                _ => {}
+            }
+            if self.nodes[..idx].iter().any(|x| x.id == n.id) {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Duplicate block ID '{}'", n.id),
+                ));
+            }
+            if drivers.insert(n.output.as_str(), idx).is_some() {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("Signal '{}' is driven by multiple blocks", n.output),
+                ));
+            }
+        }
+
+        // Kahn's algorithm
+        let mut successors = vec![vec![]; self.nodes.len()];
+        let mut predecessors = vec![0; self.nodes.len()];
+        for (idx, n) in self.nodes.iter().enumerate() {
+            if let BlockConfig::UnitDelay(_) = n.block {
+                // The delay breaks the dependency
+                continue;
+            }
+            for input in &n.inputs {
+                if let Some(driver) = drivers.get(input.as_str()) {
+                    successors[*driver].push(idx);
+                    predecessors[idx] += 1;
+                }
+            }
+        }
+        let mut ready: Vec<_> = (0..self.nodes.len())
+            .rev()
+            .filter(|idx| predecessors[*idx] == 0)
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(idx) = ready.pop() {
+            order.push(idx);
+            for s in successors[idx].iter().rev() {
+                predecessors[*s] -= 1;
+                if predecessors[*s] == 0 {
+                    ready.push(*s);
+                }
+            }
+        }
+        if order.len() < self.nodes.len() {
+            let blocks = (0..self.nodes.len())
+                .filter(|idx| predecessors[*idx] > 0)
+                .map(|idx| self.nodes[idx].id.as_str())
+                .collect::<Vec<_>>();
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Algebraic loop detected: {}", blocks.join(", ")),
+            ));
+        }
+        Ok(order)
+    }
+}
+
+fn decimal(signal: &str, v: &Value) -> Result<f64> {
+    match v {
+        Value::Decimal(x) => Ok(*x),
+        Value::Integer(x) => Ok(*x as f64),
+        _ => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Invalid data type of signal '{signal}': a numeric value is required"),
+        )),
+    }
+}
+
+/// A state that does not match the configuration is reinitialized.
+impl PureController<(Option<BlockState>, &[f64], &Duration), (Option<BlockState>, Value)>
+    for BlockConfig
+{
+    fn next(&self, input: (Option<BlockState>, &[f64], &Duration)) -> (Option<BlockState>, Value) {
+        let (state, x, dt) = input;
+        let state = match (self, state) {
+            (BlockConfig::Pid(_), Some(s @ BlockState::Pid(_)))
+            | (BlockConfig::Filter(_), Some(s @ BlockState::Filter(_)))
+            | (BlockConfig::RateLimiter(_), Some(s @ BlockState::RateLimiter(_)))
+            | (BlockConfig::Hysteresis(_), Some(s @ BlockState::Hysteresis(_)))
+            | (BlockConfig::Totalizer(_), Some(s @ BlockState::Totalizer(_))) => Some(s),
+            _ => self.initial_state(),
+        };
+        match (self, state) {
+            (BlockConfig::Pid(cfg), Some(BlockState::Pid(mut s))) => {
+                if let Some(target) = x.get(1) {
+                    s.target = *target;
+                }
+                let (s, y) = cfg.next((s, x[0], dt));
+                (Some(BlockState::Pid(s)), y.into())
+            }
+            (BlockConfig::Filter(cfg), Some(BlockState::Filter(s))) => {
+                let (s, y) = cfg.next((s, x[0], dt));
+                (Some(BlockState::Filter(s)), y.into())
+            }
+            (BlockConfig::RateLimiter(cfg), Some(BlockState::RateLimiter(s))) => {
+                let (s, y) = cfg.next((s, x[0], dt));
+                (Some(BlockState::RateLimiter(s)), y.into())
+            }
+            (BlockConfig::Hysteresis(cfg), Some(BlockState::Hysteresis(s))) => {
+                let s = cfg.next((s, x[0], dt));
+                (Some(BlockState::Hysteresis(s)), s.output.into())
+            }
+            (BlockConfig::Totalizer(cfg), Some(BlockState::Totalizer(s))) => {
+                let s = match x.get(1) {
+                    Some(reset) if *reset != 0.0 => totalizer::TotalizerState::default(),
+                    _ => cfg.next((s, x[0], dt)),
+                };
+                (Some(BlockState::Totalizer(s)), s.total.into())
+            }
+            (BlockConfig::Const(v), _) => (None, (*v).into()),
+            (BlockConfig::UnitDelay(_), _) => (None, x[0].into()),
+            (BlockConfig::Lookup(table), _) => (None, table.next(x[0]).into()),
+            (BlockConfig::Lookup2d(table), _) => (None, table.next((x[0], x[1])).into()),
+            (BlockConfig::Math(op), _) => {
+                let y = match op {
+                    MathOp::Add => x.iter().sum(),
+                    MathOp::Sub => x[0] - x[1],
+                    MathOp::Mul => x.iter().product(),
+                    MathOp::Div => x[0] / x[1],
+                    MathOp::Neg => -x[0],
+                    MathOp::Abs => x[0].abs(),
+                };
+                (None, y.into())
+            }
+            (BlockConfig::Select(sel), _) => (None, sel.next(x).into()),
+            (BlockConfig::Limit(cfg), _) => (None, cfg.next(x[0]).0.into()),
+            (BlockConfig::Scale(scaling), _) => (None, scaling.next(x[0]).0.into()),
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a> PureController<(&'a GraphState, &'a IoState, &'a Duration), Result<(GraphState, IoState)>>
+    for GraphConfig
+{
+    fn next(&self, input: (&GraphState, &IoState, &Duration)) -> Result<(GraphState, IoState)> {
+        let (state, io, dt) = input;
+        let mut io = io.clone();
+        let state = self.run(state, &mut io, dt)?;
+        Ok((state, io))
+    }
+}
+
+impl GraphConfig {
+    /// Execute the blocks and write the outputs in place.
+    ///
+    /// Nothing is written if an error occurs.
+    pub(crate) fn run(
+        &self,
+        state: &GraphState,
+        io: &mut IoState,
+        dt: &Duration,
+    ) -> Result<GraphState> {
+        let order = self.execution_order()?;
+        let mut blocks = HashMap::new();
+        let mut signals = HashMap::new();
+
+        for idx in order {
+            let n = &self.nodes[idx];
+            let x = n
+                .inputs
+                .iter()
+                .map(|id| {
+                    let v = if let BlockConfig::UnitDelay(initial) = n.block {
+                        Some(state.signals.get(id).cloned().unwrap_or(initial.into()))
+                    } else {
+                        signals
+                            .get(id)
+                            .or_else(|| io.inputs.get(id))
+                            .or_else(|| io.mem.get(id))
+                            .cloned()
+                    };
+                    match v {
+                        Some(v) => decimal(id, &v),
+                        None => Err(Error::new(
+                            ErrorKind::InvalidInput,
+                            format!("Unknown signal '{id}'"),
+                        )),
+                    }
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let (s, y) = n.block.next((state.blocks.get(&n.id).cloned(), &x, dt));
+            if let Some(s) = s {
+                blocks.insert(n.id.clone(), s);
+            }
+            signals.insert(n.output.clone(), y);
+        }
+
+        // Delayed inputs and memory values are not driven by a block
+        // and need to be remembered for the next step
+        for n in &self.nodes {
+            if let BlockConfig::UnitDelay(_) = n.block {
+                for id in &n.inputs {
+                    if signals.contains_key(id) {
+                        continue;
+                    }
+                    if let Some(v) = io.inputs.get(id).or_else(|| io.mem.get(id)) {
+                        signals.insert(id.clone(), v.clone());
+                    }
+                }
+            }
+        }
+
+        if let Some(id) = self.outputs.iter().find(|id| !signals.contains_key(*id)) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Output '{id}' is not driven by a block"),
+            ));
+        }
+        for id in &self.outputs {
+            io.outputs.set(id, signals[id].clone());
+        }
+        Ok(GraphState { blocks, signals })
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    fn math(id: &str, op: MathOp, inputs: &[&str], output: &str) -> Node {
+        Node::new(id, BlockConfig::Math(op), inputs, output)
+    }
+
+    #[test]
+    fn sort_blocks_topologically() {
+        let graph = GraphConfig {
+            nodes: vec![
+                math("c", MathOp::Add, &["b", "a"], "c"),
+                math("b", MathOp::Neg, &["a"], "b"),
+                math("a", MathOp::Abs, &["x"], "a"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(graph.execution_order().unwrap(), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn detect_algebraic_loops() {
+        let mut graph = GraphConfig {
+            nodes: vec![
+                math("in", MathOp::Abs, &["x"], "a"),
+                math("sum", MathOp::Add, &["a", "c"], "b"),
+                math("gain", MathOp::Mul, &["b", "k"], "c"),
+            ],
+            ..Default::default()
+        };
+        let err = graph.execution_order().unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert_eq!(err.to_string(), "Algebraic loop detected: sum, gain");
+
+        // A unit delay breaks the loop
+        graph.nodes[1].inputs[1] = "c_prev".into();
+        graph.nodes.push(Node::new(
+            "delay",
+            BlockConfig::UnitDelay(0.0),
+            &["c"],
+            "c_prev",
+        ));
+        assert!(graph.execution_order().is_ok());
+    }
+
+    #[test]
+    fn check_graph_configuration() {
+        let mut graph = GraphConfig {
+            nodes: vec![
+                math("a", MathOp::Abs, &["x"], "y"),
+                math("b", MathOp::Neg, &["x"], "y"),
+            ],
+            ..Default::default()
+        };
+        assert!(graph.execution_order().is_err());
+        graph.nodes[1].output = "z".into();
+        assert!(graph.execution_order().is_ok());
+        graph.nodes[1].id = "a".into();
+        assert!(graph.execution_order().is_err());
+        graph.nodes[1].id = "b".into();
+        graph.nodes[1].inputs.push("x".into());
+        assert!(graph.execution_order().is_err());
+    }
+
+    #[test]
+    fn execute_graph() {
+        let graph = GraphConfig {
+            id: "g".into(),
+            nodes: vec![
+                Node::new("offset", BlockConfig::Const(1.0), &[], "offset"),
+                math("sum", MathOp::Add, &["x", "offset"], "sum"),
+                Node::new(
+                    "max",
+                    BlockConfig::Select(Selector::Max),
+                    &["sum", "y"],
+                    "max",
+                ),
+                Node::new(
+                    "limit",
+                    BlockConfig::RateLimiter(rate_limiter::RateLimiterConfig {
+                        rising: Some(1.0),
+                        ..Default::default()
+                    }),
+                    &["max"],
+                    "out",
+                ),
+                Node::new(
+                    "alarm",
+                    BlockConfig::Hysteresis(hysteresis::HysteresisConfig {
+                        on: 5.0,
+                        off: 4.0,
+                        ..Default::default()
+                    }),
+                    &["out"],
+                    "alarm",
+                ),
+            ],
+            outputs: vec!["out".into(), "alarm".into()],
+        };
+        let dt = Duration::from_secs(1);
+        let mut io = IoState::default();
+        io.inputs.insert("x".into(), 2.0.into());
+        io.mem.insert("y".into(), 1.into());
+        let state = graph.initial_state();
+        let (state, io) = graph.next((&state, &io, &dt)).unwrap();
+        assert_eq!(io.outputs["out"], Value::Decimal(3.0));
+        assert_eq!(io.outputs["alarm"], Value::Bit(false));
+        assert_eq!(state.signals["sum"], Value::Decimal(3.0));
+
+        let mut io = io;
+        io.mem.insert("y".into(), 9.0.into());
+        let (state, io) = graph.next((&state, &io, &dt)).unwrap();
+        assert_eq!(io.outputs["out"], Value::Decimal(4.0));
+        let (state, io) = graph.next((&state, &io, &dt)).unwrap();
+        assert_eq!(io.outputs["out"], Value::Decimal(5.0));
+        assert_eq!(io.outputs["alarm"], Value::Bit(true));
+        assert_eq!(
+            state.blocks["limit"],
+            BlockState::RateLimiter(rate_limiter::RateLimiterState { value: Some(5.0) })
+        );
+    }
+
+    #[test]
+    fn execute_graph_with_lookup_tables() {
+        let curve = lookup_table::LookupTable {
+            breakpoints: vec![0.0, 10.0],
+            values: vec![0.0, 100.0],
+            ..Default::default()
+        };
+        let mut graph = GraphConfig {
+            id: "g".into(),
+            nodes: vec![Node::new("curve", BlockConfig::Lookup(curve), &["x"], "y")],
+            outputs: vec!["y".into()],
+        };
+        let dt = Duration::from_secs(1);
+        let mut io = IoState::default();
+        io.inputs.insert("x".into(), 2.5.into());
+        let state = graph.initial_state();
+        let (_, new_io) = graph.next((&state, &io, &dt)).unwrap();
+        assert_eq!(new_io.outputs["y"], Value::Decimal(25.0));
+
+        // Invalid tables are rejected
+        if let BlockConfig::Lookup(ref mut t) = graph.nodes[0].block {
+            t.values.pop();
+        }
+        assert!(graph.next((&state, &io, &dt)).is_err());
+    }
+
+    #[test]
+    fn execute_graph_with_totalizer() {
+        let graph = GraphConfig {
+            id: "g".into(),
+            nodes: vec![Node::new(
+                "volume",
+                BlockConfig::Totalizer(Default::default()),
+                &["flow", "reset"],
+                "volume",
+            )],
+            outputs: vec!["volume".into()],
+        };
+        let dt = Duration::from_secs(1);
+        let mut io = IoState::default();
+        io.inputs.insert("flow".into(), 2.0.into());
+        io.mem.insert("reset".into(), 0.into());
+        let mut state = graph.initial_state();
+        for _ in 0..3 {
+            let (s, new_io) = graph.next((&state, &io, &dt)).unwrap();
+            state = s;
+            io = new_io;
+        }
+        assert_eq!(io.outputs["volume"], Value::Decimal(4.0));
+        io.mem.insert("reset".into(), 1.into());
+        let (_, io) = graph.next((&state, &io, &dt)).unwrap();
+        assert_eq!(io.outputs["volume"], Value::Decimal(0.0));
+    }
+
+    #[test]
+    fn execute_graph_with_unit_delay() {
+        // Discrete integrator: y = y_prev + x
+        let graph = GraphConfig {
+            id: "g".into(),
+            nodes: vec![
+                math("sum", MathOp::Add, &["x", "y_prev"], "y"),
+                Node::new("delay", BlockConfig::UnitDelay(10.0), &["y"], "y_prev"),
+            ],
+            outputs: vec!["y".into()],
+        };
+        let dt = Duration::from_secs(1);
+        let mut io = IoState::default();
+        io.inputs.insert("x".into(), 1.0.into());
+        let mut state = graph.initial_state();
+        for expected in [11.0, 12.0, 13.0] {
+            let (s, new_io) = graph.next((&state, &io, &dt)).unwrap();
+            assert_eq!(new_io.outputs["y"], Value::Decimal(expected));
+            state = s;
+            io = new_io;
+        }
+    }
+
+    #[test]
+    fn execute_graph_with_delayed_input() {
+        let graph = GraphConfig {
+            id: "g".into(),
+            nodes: vec![
+                Node::new("delay", BlockConfig::UnitDelay(-1.0), &["x"], "x_prev"),
+                Node::new("mem_delay", BlockConfig::UnitDelay(0.0), &["m"], "m_prev"),
+            ],
+            outputs: vec!["x_prev".into(), "m_prev".into()],
+        };
+        let dt = Duration::from_secs(1);
+        let mut io = IoState::default();
+        let mut state = graph.initial_state();
+        for (x, expected) in [(1.0, -1.0), (2.0, 1.0), (3.0, 2.0)] {
+            io.inputs.insert("x".into(), x.into());
+            io.mem.insert("m".into(), (x * 10.0).into());
+            let (s, new_io) = graph.next((&state, &io, &dt)).unwrap();
+            assert_eq!(new_io.outputs["x_prev"], Value::Decimal(expected));
+            assert_eq!(s.signals["x"], Value::Decimal(x));
+            state = s;
+            io = new_io;
+        }
+        assert_eq!(io.outputs["m_prev"], Value::Decimal(20.0));
+    }
+
+    #[test]
+    fn reject_invalid_signals() {
+        let graph = GraphConfig {
+            id: "g".into(),
+            nodes: vec![math("neg", MathOp::Neg, &["x"], "y")],
+            outputs: vec!["z".into()],
+        };
+        let dt = Duration::from_secs(1);
+        let mut io = IoState::default();
+        let state = graph.initial_state();
+        assert!(graph.next((&state, &io, &dt)).is_err());
+        io.inputs.insert("x".into(), true.into());
+        let err = graph.next((&state, &io, &dt)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        io.inputs.insert("x".into(), 1.0.into());
+        let err = graph.next((&state, &io, &dt)).unwrap_err();
+        assert_eq!(err.to_string(), "Output 'z' is not driven by a block");
+    }
+}
@@ -0,0 +1,198 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, hysteresis::*};
+//!
+//! // Pressure alarm
+//! let cfg = HysteresisConfig {
+//!     on: 8.0,
+//!     off: 7.5,
+//!     on_delay: Duration::from_secs(2),
+//!     ..Default::default()
+//! };
+//! let mut h = Hysteresis::new(cfg);
+//! let delta_t = Duration::from_secs(1);
+//!
+//! assert!(!h.next(8.1, &delta_t));
+//! assert!(h.next(8.2, &delta_t));
+//! assert!(h.next(7.6, &delta_t));
+//! assert!(!h.next(7.4, &delta_t));
+//! ```
+
+use super::{Controller, PureController};
+use std::time::Duration;
+
+/// Hysteresis (comparator) with on/off delays
+///
+/// If the on-threshold is greater than or equal to the off-threshold
+/// the output is switched on if the input reaches the on-threshold and
+/// switched off if it drops below the off-threshold. Otherwise the output
+/// is switched on if the input drops to the on-threshold and switched off
+/// if it exceeds the off-threshold.
+///
+/// A switching condition has to hold for the corresponding delay
+/// before the output is switched.
+#[derive(Debug, Clone)]
+pub struct Hysteresis {
+    cfg: HysteresisConfig,
+    /// Current state
+    pub state: HysteresisState,
+}
+
+/// Hysteresis configuration
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct HysteresisConfig {
+    /// On-threshold
+    pub on: f64,
+    /// Off-threshold
+    pub off: f64,
+    /// Time the switch-on condition has to hold
+    pub on_delay: Duration,
+    /// Time the switch-off condition has to hold
+    pub off_delay: Duration,
+}
+
+/// Internal hysteresis state
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct HysteresisState {
+    /// The current output
+    pub output: bool,
+    /// Time since the switching condition holds
+    pub pending: Option<Duration>,
+}
+
+impl Hysteresis {
+    /// Create a new hysteresis instance.
+    pub fn new(cfg: HysteresisConfig) -> Self {
+        Hysteresis {
+            cfg,
+            state: HysteresisState::default(),
+        }
+    }
+    /// Reset the internal state.
+    pub fn reset(&mut self) {
+        self.state = HysteresisState::default();
+    }
+}
+
+impl HysteresisConfig {
+    fn switch_on(&self, x: f64) -> bool {
+        if self.on >= self.off {
+            x >= self.on
+        } else {
+            x <= self.on
+        }
+    }
+    fn switch_off(&self, x: f64) -> bool {
+        if self.on >= self.off {
+            x < self.off
+        } else {
+            x > self.off
+        }
+    }
+}
+
+impl Controller<(f64, &Duration), bool> for Hysteresis {
+    fn next(&mut self, input: (f64, &Duration)) -> bool {
+        let (x, duration) = input;
+        self.state = self.cfg.next((self.state, x, duration));
+        self.state.output
+    }
+}
+
+impl PureController<(HysteresisState, f64, &Duration), HysteresisState> for HysteresisConfig {
+    fn next(&self, input: (HysteresisState, f64, &Duration)) -> HysteresisState {
+        let (mut state, x, duration) = input;
+        let (switching, delay) = if state.output {
+            (self.switch_off(x), self.off_delay)
+        } else {
+            (self.switch_on(x), self.on_delay)
+        };
+        if !switching {
+            state.pending = None;
+            return state;
+        }
+        let pending = state
+            .pending
+            .map(|pending| pending + *duration)
+            .unwrap_or(*duration);
+        if pending >= delay {
+            state.output = !state.output;
+            state.pending = None;
+        } else {
+            state.pending = Some(pending);
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn switch_without_delays() {
+        let cfg = HysteresisConfig {
+            on: 2.0,
+            off: 1.0,
+            ..Default::default()
+        };
+        let mut h = Hysteresis::new(cfg);
+        let dt = Duration::from_secs(1);
+        let states = vec![
+            (1.5, false),
+            (2.0, true),
+            (1.0, true),
+            (0.9, false),
+            (1.9, false),
+        ];
+        for (input, output) in states {
+            assert_eq!(h.next((input, &dt)), output);
+        }
+    }
+
+    #[test]
+    fn switch_inverted() {
+        let cfg = HysteresisConfig {
+            on: 1.0,
+            off: 2.0,
+            ..Default::default()
+        };
+        let mut h = Hysteresis::new(cfg);
+        let dt = Duration::from_secs(1);
+        let states = vec![(1.5, false), (1.0, true), (2.0, true), (2.1, false)];
+        for (input, output) in states {
+            assert_eq!(h.next((input, &dt)), output);
+        }
+    }
+
+    #[test]
+    fn switch_with_delays() {
+        let cfg = HysteresisConfig {
+            on: 2.0,
+            off: 1.0,
+            on_delay: Duration::from_secs(2),
+            off_delay: Duration::from_secs(2),
+        };
+        let mut h = Hysteresis::new(cfg);
+        let dt = Duration::from_secs(1);
+        let states = vec![
+            (3.0, false),
+            (1.5, false), // the delay restarts
+            (3.0, false),
+            (3.0, true),
+            (0.0, true),
+            (0.0, false),
+        ];
+        for (input, output) in states {
+            assert_eq!(h.next((input, &dt)), output);
+        }
+        h.reset();
+        assert_eq!(h.state, HysteresisState::default());
+    }
+}
@@ -0,0 +1,526 @@
+//! An I/O table with interned IDs
+//!
+//! The [SyncRuntime](crate::SyncRuntime) operates on an [IoTable]
+//! ([IoState] is an alias that is kept for compatibility).
+//! The IDs of the runtime are resolved to indices once when the
+//! configuration is loaded and each step only copies the values
+//! that are modified (copy on write).
+//!
+//! The values can still be accessed by their string IDs like in a
+//! `HashMap`. I/O drivers that access many values in every cycle
+//! should resolve the IDs once and access the values by their keys.
+//!
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{io_table::*, SyncIoSystem, Value};
+//!
+//! // Resolve the IDs once, e.g. after loading the configuration
+//! let mut registry = IoRegistry::default();
+//! let temperature = registry.intern("temperature");
+//! let heater = registry.intern("heater");
+//!
+//! let mut io = IoTable::new(registry);
+//! io.inputs.set_by_key(temperature, Value::Decimal(21.5));
+//!
+//! // Cloning is cheap, the values are copied on the first write
+//! let mut next = io.clone();
+//! next.outputs.set_by_key(heater, Value::Bit(true));
+//! assert_eq!(io.outputs.get_by_key(heater), None);
+//!
+//! // Compatibility with the string based API
+//! assert_eq!(next.read("temperature").unwrap(), Value::Decimal(21.5));
+//! assert_eq!(next.outputs["heater"], Value::Bit(true));
+//! ```
+
+use super::{SyncIoSystem, Value};
+use std::{
+    collections::HashMap,
+    fmt,
+    io::{Error, ErrorKind, Result},
+    iter::Enumerate,
+    ops::Index,
+    slice,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+};
+
+/// The interned ID of an I/O value
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct IoKey(u32);
+
+impl IoKey {
+    /// The index of the value within an [IoValues] table.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// Resolves string IDs to [IoKey]s
+///
+/// IDs are only appended, i.e. the keys stay valid if
+/// further IDs are registered.
+#[derive(Debug, Clone)]
+pub struct IoRegistry {
+    ids: Vec<String>,
+    keys: HashMap<String, IoKey>,
+    // Registries that are derived from the same registry
+    // share the keys of the common IDs
+    origin: u64,
+}
+
+impl Default for IoRegistry {
+    fn default() -> Self {
+        static NEXT_ORIGIN: AtomicU64 = AtomicU64::new(0);
+        IoRegistry {
+            ids: vec![],
+            keys: HashMap::new(),
+            origin: NEXT_ORIGIN.fetch_add(1, Ordering::Relaxed),
+        }
+    }
+}
+
+/// Registries are equal if they register the same IDs in the same order.
+impl PartialEq for IoRegistry {
+    fn eq(&self, other: &Self) -> bool {
+        self.ids == other.ids
+    }
+}
+
+impl Eq for IoRegistry {}
+
+impl IoRegistry {
+    /// Get the key of an ID and register it if it is unknown.
+    pub fn intern(&mut self, id: &str) -> IoKey {
+        if let Some(key) = self.keys.get(id) {
+            return *key;
+        }
+        let key = IoKey(u32::try_from(self.ids.len()).expect("Too many I/O IDs"));
+        self.ids.push(id.to_string());
+        self.keys.insert(id.to_string(), key);
+        key
+    }
+    /// The key of a registered ID.
+    pub fn key(&self, id: &str) -> Option<IoKey> {
+        self.keys.get(id).copied()
+    }
+    /// The ID of a key.
+    pub fn id(&self, key: IoKey) -> Option<&str> {
+        self.ids.get(key.index()).map(String::as_str)
+    }
+    /// The number of registered IDs.
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+    /// Check if no ID is registered.
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+}
+
+impl<'a> FromIterator<&'a str> for IoRegistry {
+    fn from_iter<I: IntoIterator<Item = &'a str>>(ids: I) -> Self {
+        let mut registry = IoRegistry::default();
+        for id in ids {
+            registry.intern(id);
+        }
+        registry
+    }
+}
+
+/// Values that are indexed by [IoKey]s
+///
+/// The values can be accessed by their keys or, like in
+/// a `HashMap`, by their IDs. Unknown IDs are registered
+/// when they are inserted.
+///
+/// Clones share the values until one of them is modified
+/// (copy on write).
+#[derive(Clone, Default)]
+pub struct IoValues {
+    registry: Arc<IoRegistry>,
+    values: Arc<Vec<Option<Value>>>,
+}
+
+impl IoValues {
+    /// Create empty values for the registered IDs.
+    pub fn new(registry: Arc<IoRegistry>) -> Self {
+        IoValues {
+            registry,
+            values: Arc::default(),
+        }
+    }
+    /// The registry of the IDs.
+    pub fn registry(&self) -> &IoRegistry {
+        &self.registry
+    }
+    /// The key of a registered ID.
+    pub fn key(&self, id: &str) -> Option<IoKey> {
+        self.registry.key(id)
+    }
+    /// Get the key of an ID and register it if it is unknown.
+    ///
+    /// Registering a new ID copies the registry if it is shared.
+    pub fn intern(&mut self, id: &str) -> IoKey {
+        match self.registry.key(id) {
+            Some(key) => key,
+            None => Arc::make_mut(&mut self.registry).intern(id),
+        }
+    }
+    /// Get a value by its key.
+    pub fn get_by_key(&self, key: IoKey) -> Option<&Value> {
+        self.values.get(key.index()).and_then(Option::as_ref)
+    }
+    /// Set a value by its key.
+    ///
+    /// The key must have been resolved by the registry
+    /// of the values or by a registry it is derived from.
+    pub fn set_by_key(&mut self, key: IoKey, value: Value) -> Option<Value> {
+        debug_assert!(key.index() < self.registry.len(), "Unknown I/O key");
+        let values = Arc::make_mut(&mut self.values);
+        if values.len() <= key.index() {
+            values.resize(key.index() + 1, None);
+        }
+        values[key.index()].replace(value)
+    }
+    /// Remove a value by its key.
+    pub fn remove_by_key(&mut self, key: IoKey) -> Option<Value> {
+        self.get_by_key(key)?;
+        Arc::make_mut(&mut self.values)[key.index()].take()
+    }
+    /// Get a value.
+    pub fn get(&self, id: &str) -> Option<&Value> {
+        self.key(id).and_then(|key| self.get_by_key(key))
+    }
+    /// Check if a value exists.
+    pub fn contains_key(&self, id: &str) -> bool {
+        self.get(id).is_some()
+    }
+    /// Set a value and return the previous one.
+    pub fn set(&mut self, id: &str, value: Value) -> Option<Value> {
+        let key = self.intern(id);
+        self.set_by_key(key, value)
+    }
+    /// Insert a value and return the previous one.
+    pub fn insert(&mut self, id: String, value: Value) -> Option<Value> {
+        self.set(&id, value)
+    }
+    /// Remove a value.
+    pub fn remove(&mut self, id: &str) -> Option<Value> {
+        self.key(id).and_then(|key| self.remove_by_key(key))
+    }
+    /// Remove all values.
+    ///
+    /// The IDs stay registered.
+    pub fn clear(&mut self) {
+        self.values = Arc::default();
+    }
+    /// The number of values.
+    pub fn len(&self) -> usize {
+        self.values.iter().flatten().count()
+    }
+    /// Check if there are no values.
+    pub fn is_empty(&self) -> bool {
+        self.values.iter().all(Option::is_none)
+    }
+    /// All values with their IDs.
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            registry: &self.registry,
+            values: self.values.iter().enumerate(),
+        }
+    }
+    /// All values with their keys.
+    pub fn iter_by_key(&self) -> impl Iterator<Item = (IoKey, &Value)> {
+        self.values
+            .iter()
+            .enumerate()
+            .filter_map(|(i, v)| v.as_ref().map(|v| (IoKey(i as u32), v)))
+    }
+    /// Use the keys of another registry.
+    ///
+    /// Nothing is copied if the values already use the
+    /// registry or a registry that is derived from it.
+    /// IDs that are not registered yet are added.
+    pub fn resolve(&mut self, registry: &Arc<IoRegistry>) {
+        if self.registry.origin == registry.origin {
+            return;
+        }
+        let mut resolved = IoValues::new(Arc::clone(registry));
+        for (id, v) in self.iter() {
+            resolved.set(id, v.clone());
+        }
+        *self = resolved;
+    }
+}
+
+/// Iterator over the values and their IDs (see [IoValues::iter])
+#[derive(Debug)]
+pub struct Iter<'a> {
+    registry: &'a IoRegistry,
+    values: Enumerate<slice::Iter<'a, Option<Value>>>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a str, &'a Value);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for (i, v) in self.values.by_ref() {
+            if let (Some(v), Some(id)) = (v, self.registry.ids.get(i)) {
+                return Some((id, v));
+            }
+        }
+        None
+    }
+}
+
+impl<'a> IntoIterator for &'a IoValues {
+    type Item = (&'a str, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl Index<&str> for IoValues {
+    type Output = Value;
+
+    fn index(&self, id: &str) -> &Value {
+        self.get(id).expect("No such I/O value")
+    }
+}
+
+/// Values are equal if they have the same IDs and values,
+/// regardless of their keys.
+impl PartialEq for IoValues {
+    fn eq(&self, other: &Self) -> bool {
+        if Arc::ptr_eq(&self.values, &other.values) && Arc::ptr_eq(&self.registry, &other.registry)
+        {
+            return true;
+        }
+        self.len() == other.len() && self.iter().all(|(id, v)| other.get(id) == Some(v))
+    }
+}
+
+impl fmt::Debug for IoValues {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl Extend<(String, Value)> for IoValues {
+    fn extend<I: IntoIterator<Item = (String, Value)>>(&mut self, values: I) {
+        for (id, v) in values {
+            self.set(&id, v);
+        }
+    }
+}
+
+impl FromIterator<(String, Value)> for IoValues {
+    fn from_iter<I: IntoIterator<Item = (String, Value)>>(values: I) -> Self {
+        let mut io = IoValues::default();
+        io.extend(values);
+        io
+    }
+}
+
+impl From<HashMap<String, Value>> for IoValues {
+    fn from(values: HashMap<String, Value>) -> Self {
+        values.into_iter().collect()
+    }
+}
+
+impl From<&IoValues> for HashMap<String, Value> {
+    fn from(values: &IoValues) -> Self {
+        values
+            .iter()
+            .map(|(id, v)| (id.to_string(), v.clone()))
+            .collect()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for IoValues {
+    fn serialize<S: serde::Serializer>(
+        &self,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for IoValues {
+    fn deserialize<D: serde::Deserializer<'de>>(
+        deserializer: D,
+    ) -> std::result::Result<Self, D::Error> {
+        HashMap::<String, Value>::deserialize(deserializer).map(Into::into)
+    }
+}
+
+/// The state of all inputs and outputs with interned IDs
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct IoTable {
+    /// Input gates (sensors)
+    pub inputs: IoValues,
+    /// Output gates (actuators)
+    pub outputs: IoValues,
+    /// Values that only live in memory
+    pub mem: IoValues,
+}
+
+impl IoTable {
+    /// Create an empty table for the registered IDs.
+    ///
+    /// Inputs, outputs and memory values share the keys.
+    pub fn new(registry: IoRegistry) -> Self {
+        let registry = Arc::new(registry);
+        IoTable {
+            inputs: IoValues::new(Arc::clone(&registry)),
+            outputs: IoValues::new(Arc::clone(&registry)),
+            mem: IoValues::new(registry),
+        }
+    }
+    /// Use the keys of another registry for all values
+    /// (see [IoValues::resolve]).
+    pub fn resolve(&mut self, registry: &Arc<IoRegistry>) {
+        self.inputs.resolve(registry);
+        self.outputs.resolve(registry);
+        self.mem.resolve(registry);
+    }
+}
+
+impl SyncIoSystem for IoTable {
+    fn read(&mut self, id: &str) -> Result<Value> {
+        self.inputs
+            .get(id)
+            .cloned()
+            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no such input"))
+    }
+
+    fn read_output(&mut self, id: &str) -> Result<Option<Value>> {
+        Ok(self.outputs.get(id).cloned())
+    }
+
+    fn write(&mut self, id: &str, v: &Value) -> Result<()> {
+        self.outputs.set(id, v.clone());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn intern_ids() {
+        let mut r: IoRegistry = ["a", "b"].into_iter().collect();
+        assert_eq!(r.len(), 2);
+        assert_eq!(r.intern("b"), r.key("b").unwrap());
+        let c = r.intern("c");
+        assert_eq!(c.index(), 2);
+        assert_eq!(r.id(c), Some("c"));
+        assert_eq!(r.key("d"), None);
+        assert!(IoRegistry::default().is_empty());
+    }
+
+    #[test]
+    fn copy_values_on_write() {
+        let mut r = IoRegistry::default();
+        let (a, b) = (r.intern("a"), r.intern("b"));
+        let mut values = IoValues::new(Arc::new(r));
+        values.set_by_key(b, 1.into());
+        let copy = values.clone();
+        assert!(Arc::ptr_eq(&values.values, &copy.values));
+        values.set_by_key(a, 2.into());
+        assert!(!Arc::ptr_eq(&values.values, &copy.values));
+        assert_eq!(copy.get_by_key(a), None);
+        assert_eq!(values.iter().count(), 2);
+
+        // Removing a missing value does not copy
+        let mut copy2 = copy.clone();
+        assert_eq!(copy2.remove_by_key(a), None);
+        assert!(Arc::ptr_eq(&copy.values, &copy2.values));
+        assert_eq!(copy2.remove("b"), Some(1.into()));
+        assert_eq!(copy.get("b"), Some(&1.into()));
+    }
+
+    #[test]
+    fn access_values_by_id() {
+        let mut values = IoValues::default();
+        assert_eq!(values.insert("x".into(), 1.into()), None);
+        assert_eq!(values.insert("x".into(), 2.into()), Some(1.into()));
+        values.extend([("y".to_string(), true.into())]);
+        assert_eq!(values["x"], Value::Integer(2));
+        assert!(values.contains_key("y"));
+        assert_eq!(values.len(), 2);
+        let map = HashMap::from(&values);
+        assert_eq!(IoValues::from(map), values);
+        values.clear();
+        assert!(values.is_empty());
+        assert!(values.key("x").is_some());
+    }
+
+    #[test]
+    fn compare_values_regardless_of_keys() {
+        let mut a = IoValues::default();
+        a.insert("x".into(), 1.into());
+        a.insert("y".into(), 2.into());
+        let mut b = IoValues::new(Arc::new(["z", "y", "x"].into_iter().collect()));
+        b.insert("y".into(), 2.into());
+        assert_ne!(a, b);
+        b.insert("x".into(), 1.into());
+        assert_eq!(a, b);
+    }
+
+    #[test]
+    fn resolve_keys_once() {
+        let registry = Arc::new(["x", "y"].into_iter().collect::<IoRegistry>());
+        let mut io = IoTable::default();
+        io.inputs.insert("y".into(), 1.into());
+        io.mem.insert("z".into(), 2.into());
+        io.resolve(&registry);
+        let y = registry.key("y").unwrap();
+        assert_eq!(io.inputs.get_by_key(y), Some(&1.into()));
+        assert!(Arc::ptr_eq(&io.inputs.registry, &registry));
+        assert_eq!(io.mem["z"], Value::Integer(2));
+
+        // Registering further IDs keeps the keys
+        io.outputs.insert("w".into(), 3.into());
+        let copy = io.clone();
+        io.resolve(&registry);
+        assert!(Arc::ptr_eq(&io.outputs.values, &copy.outputs.values));
+        assert_eq!(io.outputs.key("w").unwrap().index(), 2);
+    }
+
+    #[test]
+    fn access_by_string_ids() {
+        let mut table = IoTable::new(["x"].into_iter().collect());
+        assert_eq!(table.read("x").unwrap_err().kind(), ErrorKind::NotFound);
+        let x = table.inputs.key("x").unwrap();
+        table.inputs.set_by_key(x, 3.into());
+        assert_eq!(table.read("x").unwrap(), Value::Integer(3));
+        let shared = table.clone();
+        table.write("y", &true.into()).unwrap();
+        assert_eq!(table.read_output("y").unwrap(), Some(true.into()));
+        // The registry of the clone is not modified
+        assert_eq!(shared.outputs.key("y"), None);
+        assert_eq!(shared.outputs.registry().len(), 1);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_values_as_map() {
+        let mut io = IoTable::default();
+        io.inputs.insert("x".into(), 1.5.into());
+        let json = serde_json::to_string(&io).unwrap();
+        assert_eq!(json, r#"{"inputs":{"x":1.5},"outputs":{},"mem":{}}"#);
+        assert_eq!(serde_json::from_str::<IoTable>(&json).unwrap(), io);
+    }
+}
@@ -13,6 +13,7 @@
 #![warn(rustdoc::broken_intra_doc_links)]
 
 use std::{
+    borrow::Cow,
     collections::HashMap,
     io::{Error, ErrorKind, Result},
     ops::Not,
@@ -21,13 +22,17 @@ use std::{
 
 mod comparison;
 mod entities;
+mod expr;
 pub mod fsm;
-mod parser;
+pub mod optimizer;
+pub mod parser;
 mod runtime;
+pub mod tolerant;
+pub mod trace;
 pub mod util;
 mod value;
 
-pub use self::{comparison::*, entities::*, runtime::*, value::*};
+pub use self::{comparison::*, entities::*, expr::*, runtime::*, value::*};
 
 /// PID controller
 pub mod pid;
@@ -35,6 +40,86 @@ pub mod pid;
 /// Bang-bang controller
 pub mod bang_bang;
 
+/// Cascade controller
+pub mod cascade;
+
+/// Three-point step controller
+pub mod three_point;
+
+/// Split-range output mapping
+pub mod split_range;
+
+/// Fuzzy logic controller
+pub mod fuzzy;
+
+/// Model-predictive controller
+pub mod mpc;
+
+/// Signal filters
+pub mod filter;
+
+/// Rate limiter
+pub mod rate_limiter;
+
+/// Hysteresis block
+pub mod hysteresis;
+
+/// Process simulation
+pub mod plant;
+
+/// Lookup table interpolation
+pub mod lookup_table;
+
+/// Totalizer
+pub mod totalizer;
+
+/// Operating-hours and switching-cycle counter
+pub mod counter;
+
+/// IEC timers
+pub mod timer;
+
+/// Online statistics
+pub mod statistics;
+
+/// Statistical process control
+pub mod spc;
+
+/// Trend detection
+pub mod trend;
+
+/// Signal selector
+pub mod selector;
+
+/// Limiter
+pub mod limiter;
+
+/// Linear scaling
+pub mod scaling;
+
+/// Sensor plausibility checks
+pub mod plausibility;
+
+/// Input preprocessing
+pub mod preprocessing;
+
+/// Function-block graph
+pub mod graph;
+
+/// Index-based I/O table
+pub mod io_table;
+
+/// Warm restart
+pub mod persistence;
+
+/// Declarative runtime configuration
+#[cfg(feature = "serde")]
+pub mod config;
+
+/// Cyclic executor
+#[cfg(feature = "tokio")]
+pub mod executor;
+
 /// A generic stateful controller
 pub trait Controller<Input, Output> {
     /// Calculate the next state.
@@ -76,7 +161,13 @@ pub trait SyncIoSystem {
 #[derive(Debug, Clone)]
 pub enum ControllerType {
     Pid(pid::Pid),
+    ScheduledPid(pid::ScheduledPid),
     BangBang(bang_bang::BangBang),
+    RelayTuner(pid::RelayTuner),
+    Cascade(Box<cascade::Cascade>),
+    ThreePoint(three_point::ThreePoint),
+    Fuzzy(fuzzy::Fuzzy),
+    Mpc(mpc::Mpc),
 }
 
 /// Controller configuration
@@ -84,14 +175,45 @@ pub enum ControllerType {
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ControllerConfig {
     Pid(pid::PidConfig),
+    ScheduledPid(pid::ScheduledPidConfig),
     BangBang(bang_bang::BangBangConfig),
+    RelayTuner(pid::RelayTunerConfig),
+    Cascade(cascade::CascadeConfig),
+    ThreePoint(three_point::ThreePointConfig),
+    Fuzzy(fuzzy::FuzzyConfig),
+    Mpc(mpc::MpcConfig),
+}
+
+impl Default for ControllerConfig {
+    fn default() -> Self {
+        ControllerConfig::Pid(pid::PidConfig::default())
+    }
 }
 
 /// Controller state
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum ControllerState {
     Pid(pid::PidState),
     BangBang(bang_bang::BangBangState),
+    RelayTuner(pid::RelayTunerState),
+    Cascade(cascade::CascadeState),
+    ThreePoint(three_point::ThreePointState),
+    Fuzzy(fuzzy::FuzzyState),
+    Mpc(mpc::MpcState),
+}
+
+/// Operating mode of a loop
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OperatingMode {
+    /// The controller calculates the output
+    #[default]
+    Auto,
+    /// The output is set by the operator
+    Manual(f64),
+    /// The output follows the tracking input of the loop
+    Tracking,
 }
 
 impl<'a>
@@ -105,55 +227,443 @@ impl<'a>
         input: (&ControllerState, &IoState, &Duration),
     ) -> Result<(ControllerState, IoState)> {
         let (controller, io, dt) = input;
-        if self.inputs.len() != 1 || self.outputs.len() != 1 {
+        self.next((controller, OperatingMode::Auto, io, dt))
+    }
+}
+
+/// In manual and tracking mode the controller keeps on calculating
+/// but its output is replaced. The integral portion is re-initialized
+/// so that switching back to auto mode is bumpless.
+/// PID controllers in velocity form don't support these modes.
+impl<'a>
+    PureController<
+        (
+            &'a ControllerState,
+            OperatingMode,
+            &'a IoState,
+            &'a Duration,
+        ),
+        Result<(ControllerState, IoState)>,
+    > for Loop
+{
+    fn next(
+        &self,
+        input: (&ControllerState, OperatingMode, &IoState, &Duration),
+    ) -> Result<(ControllerState, IoState)> {
+        let (controller, mode, io, dt) = input;
+        let mut io = io.clone();
+        let controller = self.control((controller, mode, &mut io, dt), None)?;
+        Ok((controller, io))
+    }
+}
+
+impl Loop {
+    /// Run the controller of the loop and write its outputs in place.
+    ///
+    /// A given process value (e.g. the filtered one)
+    /// is used instead of the inputs. Nothing is written
+    /// if an error occurs.
+    pub(crate) fn control(
+        &self,
+        input: (&ControllerState, OperatingMode, &mut IoState, &Duration),
+        process_value: Option<f64>,
+    ) -> Result<ControllerState> {
+        let (controller, mode, io, dt) = input;
+        let required_inputs = match self.controller {
+            // The cascade reads the outer and the inner actual value
+            ControllerConfig::Cascade(_) => 2,
+            // The MPC reads each controlled output
+            ControllerConfig::Mpc(ref cfg) => cfg.output_count(),
+            _ => 1,
+        };
+        let required_outputs = match self.controller {
+            // The three-point controller writes the open and the close output
+            ControllerConfig::ThreePoint(_) => 2,
+            // The MPC writes each manipulated input
+            ControllerConfig::Mpc(ref cfg) => cfg.input_count(),
+            // The split-range block writes two actuator outputs
+            _ if self.split_range.is_some() => 2,
+            _ => 1,
+        };
+        let inputs_valid = match (&self.controller, &self.combiner) {
+            // The inputs of the MPC must not be combined
+            (ControllerConfig::Mpc(_), Some(_)) => false,
+            (_, Some(_)) => self.inputs.len() >= required_inputs,
+            (_, None) => self.inputs.len() == required_inputs,
+        };
+        let outputs_valid = match (&self.controller, required_outputs) {
+            (ControllerConfig::Mpc(_), n) => self.outputs.len() == n,
+            // Single-output controllers may drive several actuators
+            (_, 1) => !self.outputs.is_empty(),
+            (_, n) => self.outputs.len() == n,
+        };
+        if !inputs_valid || !outputs_valid {
             return Err(Error::new(
                 ErrorKind::Other,
                 "Loop has invalid length of inputs/outputs",
             ));
         }
 
-        let input_id = &self.inputs[0];
-
-        if let Some(Value::Decimal(v)) = io.inputs.get(input_id) {
-            let mut io = io.clone();
-            let output_id = self.outputs[0].clone();
+        if self.split_range.is_some() {
+            if let ControllerConfig::BangBang(_)
+            | ControllerConfig::ThreePoint(_)
+            | ControllerConfig::Mpc(_) = self.controller
+            {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Split-range is not supported by the controller",
+                ));
+            }
+        }
 
-            match self.controller {
-                ControllerConfig::Pid(ref cfg) => match controller {
-                    ControllerState::Pid(s) => {
-                        let (pid_state, y) = cfg.next((*s, *v, dt));
-                        io.outputs.insert(output_id, y.into());
-                        let controller = ControllerState::Pid(pid_state);
-                        Ok((controller, io))
+        let disturbance = match self.feedforward {
+            Some(ref id) => match self.controller {
+                ControllerConfig::BangBang(_)
+                | ControllerConfig::RelayTuner(_)
+                | ControllerConfig::ThreePoint(_)
+                | ControllerConfig::Fuzzy(_)
+                | ControllerConfig::Mpc(_) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "Feedforward is not supported by the controller",
+                    ));
+                }
+                _ => match io.inputs.get(id) {
+                    Some(Value::Decimal(x)) => Some(*x),
+                    _ => {
+                        return Err(Error::new(
+                            ErrorKind::InvalidData,
+                            "Invalid feedforward data type: a decimal value is required",
+                        ));
                     }
-                    _ => Err(Error::new(
-                        ErrorKind::InvalidData,
-                        "Invalid controller state: a PID state is is required",
-                    )),
                 },
-                ControllerConfig::BangBang(ref cfg) => match controller {
-                    ControllerState::BangBang(s) => {
-                        let bb_state = cfg.next((*s, *v));
-                        io.outputs.insert(output_id, bb_state.current.into());
-                        let controller = ControllerState::BangBang(bb_state);
-                        Ok((controller, io))
+            },
+            None => None,
+        };
+
+        let manual = match mode {
+            OperatingMode::Auto => None,
+            _ => match self.controller {
+                ControllerConfig::BangBang(_)
+                | ControllerConfig::RelayTuner(_)
+                | ControllerConfig::ThreePoint(_)
+                | ControllerConfig::Mpc(_) => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "Manual mode is not supported by the controller",
+                    ));
+                }
+                // The absolute actuator value is unknown in velocity form
+                ControllerConfig::Pid(ref cfg)
+                | ControllerConfig::ScheduledPid(pid::ScheduledPidConfig {
+                    pid: ref cfg, ..
+                })
+                | ControllerConfig::Cascade(cascade::CascadeConfig { inner: ref cfg, .. })
+                    if cfg.form == pid::PidForm::Velocity =>
+                {
+                    return Err(Error::new(
+                        ErrorKind::InvalidInput,
+                        "Manual mode is not supported in velocity form",
+                    ));
+                }
+                _ => Some(self.manual_output(mode, io)?),
+            },
+        };
+
+        let v = match process_value {
+            Some(v) => v,
+            None => self.process_value(io)?,
+        };
+
+        match self.controller {
+            ControllerConfig::Pid(ref cfg) => match controller {
+                ControllerState::Pid(s) => {
+                    let s = pid::PidState { disturbance, ..*s };
+                    let (mut pid_state, mut y) = cfg.next((s, v, dt));
+                    if let Some(manual) = manual {
+                        y = util::limit(cfg.min, cfg.max, manual);
+                        pid_state.track(y);
+                    }
+                    self.write_output(io, y, dt);
+                    let controller = ControllerState::Pid(pid_state);
+                    Ok(controller)
+                }
+                _ => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid controller state: a PID state is is required",
+                )),
+            },
+            ControllerConfig::ScheduledPid(ref cfg) => match controller {
+                ControllerState::Pid(s) => {
+                    let x = match cfg.scheduled_by {
+                        pid::ScheduleVariable::Target => s.target,
+                        pid::ScheduleVariable::Input(ref id) => match io.inputs.get(id) {
+                            Some(Value::Decimal(x)) => *x,
+                            _ => {
+                                return Err(Error::new(
+                                    ErrorKind::InvalidData,
+                                    "Invalid scheduling variable: a decimal value is required",
+                                ));
+                            }
+                        },
+                    };
+                    let s = pid::PidState { disturbance, ..*s };
+                    let (mut pid_state, mut y) = cfg.next((s, v, x, dt));
+                    if let Some(manual) = manual {
+                        y = util::limit(cfg.pid.min, cfg.pid.max, manual);
+                        pid_state.track(y);
+                    }
+                    self.write_output(io, y, dt);
+                    let controller = ControllerState::Pid(pid_state);
+                    Ok(controller)
+                }
+                _ => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid controller state: a PID state is required",
+                )),
+            },
+            ControllerConfig::BangBang(ref cfg) => match controller {
+                ControllerState::BangBang(s) => {
+                    let bb_state = cfg.next((*s, v, dt));
+                    for id in &self.outputs {
+                        io.outputs.set(id, bb_state.current.into());
+                    }
+                    let controller = ControllerState::BangBang(bb_state);
+                    Ok(controller)
+                }
+                _ => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid controller state: a BangBang state is is required",
+                )),
+            },
+            ControllerConfig::RelayTuner(ref cfg) => match controller {
+                ControllerState::RelayTuner(s) => {
+                    let (tuner_state, y) = cfg.next((*s, v, dt));
+                    self.write_output(io, y, dt);
+                    let controller = ControllerState::RelayTuner(tuner_state);
+                    Ok(controller)
+                }
+                _ => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid controller state: a RelayTuner state is required",
+                )),
+            },
+            ControllerConfig::Cascade(ref cfg) => match controller {
+                ControllerState::Cascade(s) => {
+                    // The inner actual value is the last input
+                    let inner = match self.inputs.last().and_then(|id| io.inputs.get(id)) {
+                        Some(Value::Decimal(inner)) => *inner,
+                        _ => {
+                            return Err(Error::new(
+                                ErrorKind::InvalidData,
+                                "Invalid input data type: a decimal value is required",
+                            ));
+                        }
+                    };
+                    let mut s = *s;
+                    s.inner.disturbance = disturbance;
+                    let (mut cascade_state, mut y) = cfg.next((s, v, inner, dt));
+                    if let Some(manual) = manual {
+                        y = util::limit(cfg.inner.min, cfg.inner.max, manual);
+                        cascade_state.inner.track(y);
+                        // The inner target follows the inner actual value
+                        let inner_target = util::limit(cfg.outer.min, cfg.outer.max, inner);
+                        cascade_state.outer.track(inner_target);
+                        cascade_state.inner.target = inner_target;
                     }
+                    self.write_output(io, y, dt);
+                    let controller = ControllerState::Cascade(cascade_state);
+                    Ok(controller)
+                }
+                _ => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid controller state: a Cascade state is required",
+                )),
+            },
+            ControllerConfig::ThreePoint(ref cfg) => match controller {
+                ControllerState::ThreePoint(s) => {
+                    let tp_state = cfg.next((*s, v, dt));
+                    io.outputs
+                        .set(&self.outputs[0], tp_state.motion.is_opening().into());
+                    io.outputs
+                        .set(&self.outputs[1], tp_state.motion.is_closing().into());
+                    let controller = ControllerState::ThreePoint(tp_state);
+                    Ok(controller)
+                }
+                _ => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid controller state: a ThreePoint state is required",
+                )),
+            },
+            ControllerConfig::Fuzzy(ref cfg) => match controller {
+                ControllerState::Fuzzy(s) => {
+                    let (mut fuzzy_state, mut y) = cfg.next((*s, v, dt));
+                    if let Some(manual) = manual {
+                        y = manual;
+                        fuzzy_state.output = y;
+                    }
+                    self.write_output(io, y, dt);
+                    let controller = ControllerState::Fuzzy(fuzzy_state);
+                    Ok(controller)
+                }
+                _ => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid controller state: a Fuzzy state is required",
+                )),
+            },
+            ControllerConfig::Mpc(ref cfg) => match controller {
+                ControllerState::Mpc(s) => {
+                    // The process value is the first controlled output
+                    let mut actual = vec![v];
+                    for id in &self.inputs[1..] {
+                        match io.inputs.get(id) {
+                            Some(Value::Decimal(x)) => actual.push(*x),
+                            _ => {
+                                return Err(Error::new(
+                                    ErrorKind::InvalidData,
+                                    "Invalid input data type: a decimal value is required",
+                                ));
+                            }
+                        }
+                    }
+                    let (mpc_state, y) = cfg.next((s.clone(), &actual[..], dt));
+                    for (id, y) in self.outputs.iter().zip(y) {
+                        self.write_single_output(io, id, y, dt);
+                    }
+                    let controller = ControllerState::Mpc(mpc_state);
+                    Ok(controller)
+                }
+                _ => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid controller state: a MPC state is required",
+                )),
+            },
+        }
+    }
+
+    /// The inputs that are combined to the process value.
+    ///
+    /// Without a combiner this is the first input.
+    pub fn process_inputs(&self) -> &[String] {
+        let additional = match self.controller {
+            ControllerConfig::Cascade(_) => 1,
+            _ => 0,
+        };
+        let n = match self.combiner {
+            Some(_) => self.inputs.len().saturating_sub(additional),
+            None => self.inputs.len().min(1),
+        };
+        &self.inputs[..n]
+    }
+
+    /// The process value, i.e. the first input or
+    /// the combination of the process inputs.
+    pub fn process_value(&self, io: &IoState) -> Result<f64> {
+        let values = self
+            .process_inputs()
+            .iter()
+            .map(|id| match io.inputs.get(id) {
+                Some(Value::Decimal(x)) => Ok(*x),
+                _ => Err(Error::new(
+                    ErrorKind::InvalidData,
+                    "Invalid input data type: a decimal value is required",
+                )),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        match self.combiner {
+            Some(ref combiner) if !values.is_empty() => Ok(combiner.next(&values)),
+            _ => values
+                .first()
+                .copied()
+                .ok_or_else(|| Error::new(ErrorKind::InvalidInput, "Loop has no process input")),
+        }
+    }
+
+    /// The output value in manual or tracking mode.
+    fn manual_output(&self, mode: OperatingMode, io: &IoState) -> Result<f64> {
+        match mode {
+            OperatingMode::Manual(y) => Ok(y),
+            OperatingMode::Tracking => match self.tracking {
+                Some(ref id) => match io.inputs.get(id) {
+                    Some(Value::Decimal(y)) => Ok(*y),
                     _ => Err(Error::new(
                         ErrorKind::InvalidData,
-                        "Invalid controller state: a BangBang state is is required",
+                        "Invalid tracking data type: a decimal value is required",
                     )),
                 },
+                None => Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "Tracking mode requires a tracking input",
+                )),
+            },
+            OperatingMode::Auto => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The output is calculated by the controller in auto mode",
+            )),
+        }
+    }
+
+    /// Write the controller output to all outputs or, if a
+    /// split-range block is configured, split it into two
+    /// actuator outputs.
+    ///
+    /// Each written output is scaled individually and its
+    /// change is limited by the output rate limit.
+    fn write_output(&self, io: &mut IoState, y: f64, dt: &Duration) {
+        match self.split_range {
+            Some(ref split_range) => {
+                let (first, second) = split_range.next(y);
+                self.write_single_output(io, &self.outputs[0], first, dt);
+                self.write_single_output(io, &self.outputs[1], second, dt);
+            }
+            None => {
+                for id in &self.outputs {
+                    self.write_single_output(io, id, y, dt);
+                }
             }
-        } else {
-            Err(Error::new(
-                ErrorKind::InvalidData,
-                "Invalid input data type: a decimal value is required",
-            ))
+        }
+    }
+
+    /// Scale and rate limit a single output before writing it.
+    fn write_single_output(&self, io: &mut IoState, id: &str, y: f64, dt: &Duration) {
+        let y = match self.output_scaling.get(id) {
+            Some(scaling) => scaling.next(y).0,
+            None => y,
+        };
+        let y = match self.output_rate_limit {
+            Some(ref cfg) => {
+                let value = match io.outputs.get(id) {
+                    Some(Value::Decimal(prev)) => Some(*prev),
+                    _ => None,
+                };
+                cfg.next((rate_limiter::RateLimiterState { value }, y, dt))
+                    .1
+            }
+            None => y,
+        };
+        io.outputs.set(id, y.into());
+    }
+
+    /// Move the target towards the setpoint
+    /// as fast as the setpoint rate limit allows.
+    fn ramp_setpoint(&self, target: f64, setpoint: f64, dt: &Duration) -> f64 {
+        match self.setpoint_rate_limit {
+            Some(ref cfg) => {
+                let state = rate_limiter::RateLimiterState {
+                    value: Some(target),
+                };
+                cfg.next((state, setpoint, dt)).1
+            }
+            None => setpoint,
         }
     }
 }
 
 /// The state of all inputs and outputs of a MSR system.
+///
+/// This is an alias of the [io_table::IoTable] that is kept for
+/// compatibility. The values can be accessed by their IDs like
+/// in a `HashMap`.
 /// # Example
 /// ```rust,no_run
 /// use std::{thread, time::Duration};
@@ -174,18 +684,12 @@ impl<'a>
 ///     thread::sleep(Duration::from_secs(2));
 /// }
 /// ```
-#[derive(Debug, Default, Clone, PartialEq)]
-pub struct IoState {
-    /// Input gates (sensors)
-    pub inputs: HashMap<String, Value>,
-    /// Output gates (actuators)
-    pub outputs: HashMap<String, Value>,
-    /// Values that only live in memory
-    pub mem: HashMap<String, Value>,
-}
+pub type IoState = io_table::IoTable;
 
 /// The state of a synchronous controlling system.
 #[derive(Debug, Default, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct SystemState {
     /// I/O states
     pub io: IoState,
@@ -193,18 +697,53 @@ pub struct SystemState {
     pub setpoints: HashMap<String, Value>,
     /// Controller states
     pub controllers: HashMap<String, ControllerState>,
+    /// Input filter states of the loops
+    pub filters: HashMap<String, Vec<filter::FilterState>>,
+    /// Input preprocessing states of the loops
+    pub preprocessing: HashMap<String, Vec<preprocessing::PreprocessingState>>,
+    /// Operating modes of the loops (auto if missing)
+    pub modes: HashMap<String, OperatingMode>,
+    /// Hysteresis block states
+    pub hysteresis_blocks: HashMap<String, hysteresis::HysteresisState>,
+    /// Operating-hours and switching-cycle counter states
+    pub counters: HashMap<String, counter::CounterState>,
+    /// Timer block states
+    pub timers: HashMap<String, timer::TimerState>,
+    /// Statistics block states
+    pub statistics: HashMap<String, statistics::StatisticsState>,
+    /// SPC block states
+    pub spc: HashMap<String, spc::SpcState>,
+    /// Plausibility check states
+    pub plausibility_checks: HashMap<String, plausibility::PlausibilityState>,
+    /// Trend block states
+    pub trends: HashMap<String, trend::TrendState>,
+    /// Function-block graph states
+    pub graphs: HashMap<String, graph::GraphState>,
     /// List of inactive loops
     pub inactive_loops: Vec<String>,
     /// Finite State Machine states
     pub state_machines: HashMap<String, String>,
+    /// Finite State Machine data (e.g. histories)
+    pub state_machine_data: HashMap<String, fsm::StateMachineData>,
     /// Rule states
     pub rules: HashMap<String, bool>,
-    /// Timeout states
+    /// Timeout states (see [Action::timeouts])
     pub timeouts: HashMap<String, Value>,
+    /// Timer states of delayed and pulsed actions
+    pub actions: HashMap<String, ActionState>,
+    /// Execution time measurement
+    pub cycle: CycleState,
+    /// Execution times of the components
+    pub profile: Profile,
+    /// Events of the last step
+    pub events: Vec<RuntimeEvent>,
 }
 
 impl SystemState {
     /// Get a specific value defined by a [Source].
+    ///
+    /// Expressions are not evaluated and `None` is returned
+    /// for them (see [SystemState::eval_source]).
     pub fn get<'a>(&'a self, src: &'a Source) -> Option<&'a Value> {
         use crate::Source::*;
         match src {
@@ -214,31 +753,25 @@ impl SystemState {
             Timeout(id) => self.timeouts.get(id),
             Const(v) => Some(v),
             Setpoint(id) => self.setpoints.get(id),
+            Expr(_) => None,
         }
     }
-}
-
-impl SyncIoSystem for IoState {
-    fn read(&mut self, id: &str) -> Result<Value> {
-        Ok(self
-            .inputs
-            .get(id)
-            .ok_or_else(|| Error::new(ErrorKind::NotFound, "no such input"))?
-            .clone())
-    }
-
-    fn read_output(&mut self, id: &str) -> Result<Option<Value>> {
-        Ok(self.outputs.get(id).cloned())
-    }
 
-    fn write(&mut self, id: &str, v: &Value) -> Result<()> {
-        self.outputs.insert(id.into(), v.clone());
-        Ok(())
+    /// Get or calculate a specific value defined by a [Source].
+    ///
+    /// The value of an expression is calculated and
+    /// `None` is returned if the calculation fails.
+    pub fn eval_source<'a>(&'a self, src: &'a Source) -> Option<Cow<'a, Value>> {
+        match src {
+            Source::Expr(e) => e.eval(self).ok().map(Cow::Owned),
+            _ => self.get(src).map(Cow::Borrowed),
+        }
     }
 }
 
 /// A data source
 #[derive(Debug, Clone, PartialEq, PartialOrd)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Source {
     In(String),
     Out(String),
@@ -246,6 +779,8 @@ pub enum Source {
     Setpoint(String),
     Timeout(String),
     Const(Value),
+    /// A calculated value
+    Expr(Box<Expr>),
 }
 
 impl Source {
@@ -267,6 +802,12 @@ impl Source {
     pub fn cmp_gt(self, right: Source) -> Comparison {
         self.cmp(right, Comparator::Greater)
     }
+    pub fn cmp_approx_eq(self, right: Source, tolerance: Tolerance) -> Comparison {
+        self.cmp(right, Comparator::ApproxEqual(tolerance))
+    }
+    pub fn cmp_approx_ne(self, right: Source, tolerance: Tolerance) -> Comparison {
+        self.cmp(right, Comparator::NotApproxEqual(tolerance))
+    }
     fn cmp(self, right: Source, cmp: Comparator) -> Comparison {
         Comparison {
             left: self,
@@ -278,6 +819,7 @@ impl Source {
 
 /// A boolean expression
 #[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum BoolExpr<T> {
     /// `true`
     True,
@@ -454,6 +996,22 @@ mod tests {
         assert_eq!(expr, BoolExpr::Eval(x_gt_5));
     }
 
+    #[test]
+    fn get_and_evaluate_sources() {
+        use crate::Source::*;
+        let mut state = SystemState::default();
+        state.io.inputs.insert("a".into(), 7.0.into());
+        let a = In("a".into());
+        assert_eq!(state.get(&a), Some(&Value::Decimal(7.0)));
+        assert_eq!(state.eval_source(&a).as_deref(), Some(&Value::Decimal(7.0)));
+        let sum = Source::from(crate::Expr::Add(a, Const(1.0.into())));
+        assert_eq!(state.get(&sum), None);
+        assert_eq!(
+            state.eval_source(&sum).as_deref(),
+            Some(&Value::Decimal(8.0))
+        );
+    }
+
     #[test]
     fn bool_expr_not_operation() {
         use crate::Source::*;
@@ -474,6 +1032,7 @@ mod tests {
             inputs: vec!["x".into()],
             outputs: vec!["y".into()],
             controller: ControllerConfig::Pid(pid_cfg),
+            ..Default::default()
         };
         let mut io = IoState::default();
         io.inputs.insert("x".into(), 140.0.into());
@@ -495,6 +1054,41 @@ mod tests {
         }
     }
 
+    #[test]
+    fn pure_pid_loop_with_feedforward() {
+        let pid_cfg = pid::PidConfig {
+            k_p: 2.0,
+            feedforward: Some(pid::FeedforwardConfig {
+                gain: 0.5,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut l = Loop {
+            id: "pid".into(),
+            inputs: vec!["x".into()],
+            outputs: vec!["y".into()],
+            controller: ControllerConfig::Pid(pid_cfg),
+            feedforward: Some("z".into()),
+            ..Default::default()
+        };
+        let mut io = IoState::default();
+        io.inputs.insert("x".into(), 140.0.into());
+        let pid_state = pid::PidState {
+            target: 150.0,
+            ..Default::default()
+        };
+        let controller = ControllerState::Pid(pid_state);
+        let dt = Duration::from_secs(1);
+        assert!(l.next((&controller, &io, &dt)).is_err());
+        io.inputs.insert("z".into(), 8.0.into());
+        let (_, io) = l.next((&controller, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("y").unwrap(), Value::Decimal(24.0));
+        l.controller = ControllerConfig::BangBang(bang_bang::BangBangConfig::default());
+        let controller = ControllerState::BangBang(bang_bang::BangBangState::default());
+        assert!(l.next((&controller, &io, &dt)).is_err());
+    }
+
     #[test]
     fn pure_bb_loop() {
         let bb_cfg = bang_bang::BangBangConfig {
@@ -506,6 +1100,7 @@ mod tests {
             inputs: vec!["x".into()],
             outputs: vec!["y".into()],
             controller: ControllerConfig::BangBang(bb_cfg),
+            ..Default::default()
         };
         let mut io = IoState::default();
         io.inputs.insert("x".into(), 5.1.into());
@@ -515,6 +1110,344 @@ mod tests {
         assert_eq!(*io.outputs.get("y").unwrap(), Value::Bit(true));
     }
 
+    #[test]
+    fn pure_relay_tuner_loop() {
+        let tuner_cfg = pid::RelayTunerConfig {
+            output_bias: 50.0,
+            output_amplitude: 10.0,
+            ..Default::default()
+        };
+        let l = Loop {
+            id: "tuner".into(),
+            inputs: vec!["x".into()],
+            outputs: vec!["y".into()],
+            controller: ControllerConfig::RelayTuner(tuner_cfg),
+            ..Default::default()
+        };
+        let mut io = IoState::default();
+        io.inputs.insert("x".into(), 1.0.into());
+        let controller = ControllerState::RelayTuner(pid::RelayTunerState::default());
+        let dt = Duration::from_secs(1);
+        let (c, io) = l.next((&controller, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("y").unwrap(), Value::Decimal(40.0));
+        match c {
+            ControllerState::RelayTuner(s) => {
+                assert!(!s.output_high);
+            }
+            _ => {
+                panic!("invalid controller state");
+            }
+        }
+    }
+
+    #[test]
+    fn pure_cascade_loop() {
+        let cfg = cascade::CascadeConfig {
+            outer: pid::PidConfig {
+                k_p: 2.0,
+                ..Default::default()
+            },
+            inner: pid::PidConfig::default(),
+        };
+        let mut l = Loop {
+            id: "cascade".into(),
+            inputs: vec!["temp".into()],
+            outputs: vec!["valve".into()],
+            controller: ControllerConfig::Cascade(cfg),
+            ..Default::default()
+        };
+        let mut io = IoState::default();
+        io.inputs.insert("temp".into(), 70.0.into());
+        io.inputs.insert("flow".into(), 5.0.into());
+        let state = cascade::CascadeState {
+            outer: pid::PidState {
+                target: 75.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let controller = ControllerState::Cascade(state);
+        let dt = Duration::from_secs(1);
+        assert!(l.next((&controller, &io, &dt)).is_err());
+        l.inputs.push("flow".into());
+        let (_, io) = l.next((&controller, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("valve").unwrap(), Value::Decimal(5.0));
+    }
+
+    #[test]
+    fn pure_three_point_loop() {
+        let cfg = three_point::ThreePointConfig::default();
+        let mut l = Loop {
+            id: "valve".into(),
+            inputs: vec!["x".into()],
+            outputs: vec!["open".into()],
+            controller: ControllerConfig::ThreePoint(cfg),
+            ..Default::default()
+        };
+        let mut io = IoState::default();
+        io.inputs.insert("x".into(), 10.0.into());
+        let controller = ControllerState::ThreePoint(three_point::ThreePointState::default());
+        let dt = Duration::from_secs(1);
+        assert!(l.next((&controller, &io, &dt)).is_err());
+        l.outputs.push("close".into());
+        let (c, mut io) = l.next((&controller, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("open").unwrap(), Value::Bit(false));
+        assert_eq!(*io.outputs.get("close").unwrap(), Value::Bit(false));
+        io.inputs.insert("x".into(), (-10.0).into());
+        let (_, io) = l.next((&c, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("open").unwrap(), Value::Bit(true));
+        assert_eq!(*io.outputs.get("close").unwrap(), Value::Bit(false));
+    }
+
+    #[test]
+    fn pure_fuzzy_loop() {
+        let set = |name: &str, left, peak, right| fuzzy::FuzzySet {
+            name: name.into(),
+            membership: fuzzy::Membership::Triangle { left, peak, right },
+        };
+        let cfg = fuzzy::FuzzyConfig {
+            error: fuzzy::FuzzyVariable {
+                min: -1.0,
+                max: 1.0,
+                sets: vec![set("positive", 0.0, 1.0, 2.0)],
+            },
+            output: fuzzy::FuzzyVariable {
+                min: 0.0,
+                max: 10.0,
+                sets: vec![set("high", 0.0, 10.0, 20.0)],
+            },
+            rules: vec![fuzzy::FuzzyRule::new(
+                fuzzy::FuzzyCondition::is(fuzzy::FuzzyInput::Error, "positive"),
+                "high",
+            )],
+            ..Default::default()
+        };
+        let l = Loop {
+            id: "fuzzy".into(),
+            inputs: vec!["x".into()],
+            outputs: vec!["y".into()],
+            controller: ControllerConfig::Fuzzy(cfg),
+            ..Default::default()
+        };
+        let mut io = IoState::default();
+        io.inputs.insert("x".into(), (-1.0).into());
+        let controller = ControllerState::Fuzzy(fuzzy::FuzzyState::default());
+        let dt = Duration::from_secs(1);
+        let (c, io) = l.next((&controller, &io, &dt)).unwrap();
+        match c {
+            ControllerState::Fuzzy(s) => assert_eq!(s.prev_err, Some(1.0)),
+            _ => panic!("invalid controller state"),
+        }
+        match io.outputs.get("y") {
+            Some(Value::Decimal(y)) => assert!(*y > 5.0),
+            _ => panic!("invalid output"),
+        }
+    }
+
+    #[test]
+    fn pure_pid_loop_with_split_range() {
+        let cfg = pid::PidConfig {
+            k_p: 10.0,
+            ..Default::default()
+        };
+        let mut l = Loop {
+            id: "temperature".into(),
+            inputs: vec!["x".into()],
+            outputs: vec!["heating".into()],
+            controller: ControllerConfig::Pid(cfg),
+            split_range: Some(split_range::SplitRangeConfig::default()),
+            ..Default::default()
+        };
+        let mut io = IoState::default();
+        io.inputs.insert("x".into(), 2.0.into());
+        let controller = ControllerState::Pid(pid::PidState::default());
+        let dt = Duration::from_secs(1);
+        assert!(l.next((&controller, &io, &dt)).is_err());
+        l.outputs.push("cooling".into());
+        let (_, mut io) = l.next((&controller, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("heating").unwrap(), Value::Decimal(20.0));
+        assert_eq!(*io.outputs.get("cooling").unwrap(), Value::Decimal(0.0));
+        io.inputs.insert("x".into(), (-3.0).into());
+        let (_, io) = l.next((&controller, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("heating").unwrap(), Value::Decimal(0.0));
+        assert_eq!(*io.outputs.get("cooling").unwrap(), Value::Decimal(30.0));
+
+        l.controller = ControllerConfig::BangBang(bang_bang::BangBangConfig::default());
+        let controller = ControllerState::BangBang(bang_bang::BangBangState::default());
+        assert!(l.next((&controller, &io, &dt)).is_err());
+    }
+
+    #[test]
+    fn pure_pid_loop_with_multiple_inputs_and_outputs() {
+        let cfg = pid::PidConfig {
+            k_p: 10.0,
+            ..Default::default()
+        };
+        let mut l = Loop {
+            id: "temperature".into(),
+            inputs: vec!["t1".into(), "t2".into(), "t3".into()],
+            outputs: vec!["heater1".into(), "heater2".into()],
+            controller: ControllerConfig::Pid(cfg),
+            ..Default::default()
+        };
+        let mut io = IoState::default();
+        io.inputs.insert("t1".into(), (-1.0).into());
+        io.inputs.insert("t2".into(), (-2.0).into());
+        io.inputs.insert("t3".into(), (-6.0).into());
+        let controller = ControllerState::Pid(pid::PidState::default());
+        let dt = Duration::from_secs(1);
+        assert!(l.next((&controller, &io, &dt)).is_err());
+
+        l.combiner = Some(selector::Selector::Average);
+        l.output_scaling.insert(
+            "heater2".into(),
+            scaling::Scaling {
+                raw_min: 0.0,
+                raw_max: 100.0,
+                min: 0.0,
+                max: 10.0,
+                clamp: true,
+            },
+        );
+        let (_, io) = l.next((&controller, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("heater1").unwrap(), Value::Decimal(30.0));
+        assert_eq!(*io.outputs.get("heater2").unwrap(), Value::Decimal(3.0));
+
+        l.combiner = Some(selector::Selector::Median);
+        let (_, mut io) = l.next((&controller, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("heater1").unwrap(), Value::Decimal(20.0));
+
+        io.inputs.insert("t3".into(), true.into());
+        assert!(l.next((&controller, &io, &dt)).is_err());
+    }
+
+    #[test]
+    fn pure_cascade_loop_with_combined_inputs() {
+        let cfg = cascade::CascadeConfig {
+            outer: pid::PidConfig {
+                k_p: 2.0,
+                ..Default::default()
+            },
+            inner: pid::PidConfig::default(),
+        };
+        let mut l = Loop {
+            id: "cascade".into(),
+            inputs: vec!["flow".into()],
+            outputs: vec!["valve".into()],
+            controller: ControllerConfig::Cascade(cfg),
+            combiner: Some(selector::Selector::Max),
+            ..Default::default()
+        };
+        let mut io = IoState::default();
+        io.inputs.insert("temp1".into(), 68.0.into());
+        io.inputs.insert("temp2".into(), 70.0.into());
+        io.inputs.insert("flow".into(), 5.0.into());
+        let state = cascade::CascadeState {
+            outer: pid::PidState {
+                target: 75.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let controller = ControllerState::Cascade(state);
+        let dt = Duration::from_secs(1);
+        assert!(l.process_inputs().is_empty());
+        assert!(l.next((&controller, &io, &dt)).is_err());
+        l.inputs = vec!["temp1".into(), "temp2".into(), "flow".into()];
+        assert_eq!(l.process_inputs(), ["temp1", "temp2"]);
+        assert_eq!(l.process_value(&io).unwrap(), 70.0);
+        let (_, io) = l.next((&controller, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("valve").unwrap(), Value::Decimal(5.0));
+    }
+
+    #[test]
+    fn pure_pid_loop_with_bumpless_transfer() {
+        let cfg = pid::PidConfig {
+            k_p: 2.0,
+            k_i: 0.5,
+            max: Some(100.0),
+            ..Default::default()
+        };
+        let mut l = Loop {
+            id: "pid".into(),
+            inputs: vec!["x".into()],
+            outputs: vec!["y".into()],
+            controller: ControllerConfig::Pid(cfg),
+            ..Default::default()
+        };
+        let mut io = IoState::default();
+        io.inputs.insert("x".into(), 140.0.into());
+        let controller = ControllerState::Pid(pid::PidState {
+            target: 150.0,
+            ..Default::default()
+        });
+        let dt = Duration::from_secs(1);
+
+        let manual = OperatingMode::Manual(30.0);
+        let (c, io) = l.next((&controller, manual, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("y").unwrap(), Value::Decimal(30.0));
+        let (_, io2) = l.next((&c, manual, &io, &dt)).unwrap();
+        assert_eq!(*io2.outputs.get("y").unwrap(), Value::Decimal(30.0));
+        let (_, io2) = l
+            .next((&c, OperatingMode::Manual(200.0), &io, &dt))
+            .unwrap();
+        assert_eq!(*io2.outputs.get("y").unwrap(), Value::Decimal(100.0));
+
+        // Only the integral portion of one step is added
+        let (_, io) = l.next((&c, OperatingMode::Auto, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("y").unwrap(), Value::Decimal(35.0));
+
+        assert!(l.next((&c, OperatingMode::Tracking, &io, &dt)).is_err());
+        l.tracking = Some("z".into());
+        assert!(l.next((&c, OperatingMode::Tracking, &io, &dt)).is_err());
+        let mut io = io;
+        io.inputs.insert("z".into(), 42.0.into());
+        let (_, io) = l.next((&c, OperatingMode::Tracking, &io, &dt)).unwrap();
+        assert_eq!(*io.outputs.get("y").unwrap(), Value::Decimal(42.0));
+
+        l.controller = ControllerConfig::BangBang(bang_bang::BangBangConfig::default());
+        let c = ControllerState::BangBang(bang_bang::BangBangState::default());
+        assert!(l.next((&c, manual, &io, &dt)).is_err());
+        assert!(l.next((&c, OperatingMode::Auto, &io, &dt)).is_ok());
+    }
+
+    #[test]
+    fn reject_manual_mode_of_pid_loops_in_velocity_form() {
+        let cfg = pid::PidConfig {
+            k_p: 2.0,
+            form: pid::PidForm::Velocity,
+            ..Default::default()
+        };
+        let mut l = Loop {
+            id: "pid".into(),
+            inputs: vec!["x".into()],
+            outputs: vec!["y".into()],
+            tracking: Some("z".into()),
+            controller: ControllerConfig::Pid(cfg.clone()),
+            ..Default::default()
+        };
+        let mut io = IoState::default();
+        io.inputs.insert("x".into(), 140.0.into());
+        io.inputs.insert("z".into(), 42.0.into());
+        let c = ControllerState::Pid(pid::PidState::default());
+        let dt = Duration::from_secs(1);
+        let manual = OperatingMode::Manual(30.0);
+        let err = l.next((&c, manual, &io, &dt)).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        assert!(l.next((&c, OperatingMode::Tracking, &io, &dt)).is_err());
+        assert!(l.next((&c, OperatingMode::Auto, &io, &dt)).is_ok());
+
+        l.controller = ControllerConfig::Cascade(cascade::CascadeConfig {
+            outer: Default::default(),
+            inner: cfg,
+        });
+        l.inputs.push("x_inner".into());
+        io.inputs.insert("x_inner".into(), 10.0.into());
+        let c = ControllerState::Cascade(Default::default());
+        assert!(l.next((&c, manual, &io, &dt)).is_err());
+        assert!(l.next((&c, OperatingMode::Auto, &io, &dt)).is_ok());
+    }
+
     #[test]
     fn check_loops_inputs_and_outputs_len() {
         let controller = ControllerConfig::BangBang(bang_bang::BangBangConfig::default());
@@ -524,6 +1457,7 @@ mod tests {
             inputs: vec![],
             outputs: vec![],
             controller,
+            ..Default::default()
         };
         let mut io = IoState::default();
         io.inputs.insert("input".into(), 0.0.into());
@@ -533,5 +1467,10 @@ mod tests {
         assert!(loop0.next((&controller, &io, &dt)).is_err());
         loop0.outputs = vec!["output".into()];
         assert!(loop0.next((&controller, &io, &dt)).is_ok());
+        loop0.outputs.push("second_output".into());
+        let (_, io) = loop0.next((&controller, &io, &dt)).unwrap();
+        assert_eq!(io.outputs.get("output"), io.outputs.get("second_output"));
+        loop0.inputs.push("second_input".into());
+        assert!(loop0.next((&controller, &io, &dt)).is_err());
     }
 }
@@ -0,0 +1,115 @@
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{PureController, limiter::*};
+//!
+//! let valve = LimiterConfig {
+//!     low: Some(10.0),
+//!     high: Some(90.0),
+//! };
+//!
+//! assert_eq!(valve.next(50.0), (50.0, LimitStatus::Inactive));
+//! assert_eq!(valve.next(95.0), (90.0, LimitStatus::High));
+//! assert!(valve.next(2.0).1.is_active());
+//! ```
+
+use super::PureController;
+
+/// Limiter configuration
+///
+/// The output follows the input within the low and the high limit.
+/// If the low limit is greater than the high limit the high limit wins.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct LimiterConfig {
+    /// Low limit
+    pub low: Option<f64>,
+    /// High limit
+    pub high: Option<f64>,
+}
+
+/// The active limit of a limiter
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LimitStatus {
+    /// The input is within the limits
+    #[default]
+    Inactive,
+    /// The input is limited by the low limit
+    Low,
+    /// The input is limited by the high limit
+    High,
+}
+
+impl LimitStatus {
+    /// Check whether the input is limited.
+    pub fn is_active(&self) -> bool {
+        *self != LimitStatus::Inactive
+    }
+}
+
+impl PureController<f64, (f64, LimitStatus)> for LimiterConfig {
+    fn next(&self, x: f64) -> (f64, LimitStatus) {
+        if let Some(high) = self.high {
+            if x > high {
+                return (high, LimitStatus::High);
+            }
+        }
+        if let Some(low) = self.low {
+            if x < low {
+                return match self.high {
+                    Some(high) if high < low => (high, LimitStatus::High),
+                    _ => (low, LimitStatus::Low),
+                };
+            }
+        }
+        (x, LimitStatus::Inactive)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn limit_input() {
+        let cfg = LimiterConfig {
+            low: Some(-1.0),
+            high: Some(1.0),
+        };
+        assert_eq!(cfg.next(0.5), (0.5, LimitStatus::Inactive));
+        assert_eq!(cfg.next(1.0), (1.0, LimitStatus::Inactive));
+        assert_eq!(cfg.next(1.5), (1.0, LimitStatus::High));
+        assert_eq!(cfg.next(-3.0), (-1.0, LimitStatus::Low));
+        assert!(!LimitStatus::Inactive.is_active());
+    }
+
+    #[test]
+    fn limit_one_side() {
+        let cfg = LimiterConfig {
+            high: Some(1.0),
+            ..Default::default()
+        };
+        assert_eq!(cfg.next(-100.0), (-100.0, LimitStatus::Inactive));
+        assert_eq!(cfg.next(100.0), (1.0, LimitStatus::High));
+        let cfg = LimiterConfig {
+            low: Some(1.0),
+            ..Default::default()
+        };
+        assert_eq!(cfg.next(100.0), (100.0, LimitStatus::Inactive));
+        assert_eq!(cfg.next(-100.0), (1.0, LimitStatus::Low));
+    }
+
+    #[test]
+    fn prefer_high_limit() {
+        let cfg = LimiterConfig {
+            low: Some(5.0),
+            high: Some(3.0),
+        };
+        assert_eq!(cfg.next(4.0), (3.0, LimitStatus::High));
+        assert_eq!(cfg.next(0.0), (3.0, LimitStatus::High));
+    }
+}
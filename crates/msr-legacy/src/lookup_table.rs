@@ -0,0 +1,249 @@
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{PureController, lookup_table::*};
+//!
+//! // Linearization of a sensor characteristic
+//! let table = LookupTable {
+//!     breakpoints: vec![0.0, 10.0, 20.0],
+//!     values: vec![0.0, 50.0, 80.0],
+//!     ..Default::default()
+//! };
+//! assert!(table.validate().is_ok());
+//!
+//! assert_eq!(table.next(5.0), 25.0);
+//! assert_eq!(table.next(15.0), 65.0);
+//! assert_eq!(table.next(30.0), 80.0); // clamped
+//! ```
+
+use super::PureController;
+use std::{
+    cmp::Ordering,
+    io::{Error, ErrorKind, Result},
+};
+
+/// Behaviour outside of the breakpoint range
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Extrapolation {
+    /// Use the value of the first or the last breakpoint
+    #[default]
+    Clamp,
+    /// Extend the first or the last segment linearly
+    Linear,
+}
+
+/// One-dimensional lookup table with linear interpolation
+///
+/// The breakpoints have to be strictly ascending.
+/// An empty table returns NaN.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LookupTable {
+    /// Input values
+    pub breakpoints: Vec<f64>,
+    /// Output values (one for each breakpoint)
+    pub values: Vec<f64>,
+    /// Behaviour outside of the breakpoint range
+    pub extrapolation: Extrapolation,
+}
+
+/// Two-dimensional lookup table with bilinear interpolation
+///
+/// The breakpoints of both axes have to be strictly ascending.
+/// An empty table returns NaN.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LookupTable2d {
+    /// Breakpoints of the first input
+    pub x: Vec<f64>,
+    /// Breakpoints of the second input
+    pub y: Vec<f64>,
+    /// Output values (`values[i][j]` belongs to `x[i]` and `y[j]`)
+    pub values: Vec<Vec<f64>>,
+    /// Behaviour outside of the breakpoint ranges
+    pub extrapolation: Extrapolation,
+}
+
+fn validate_axis(breakpoints: &[f64], values: usize) -> Result<()> {
+    if breakpoints.is_empty() {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "At least one breakpoint is required",
+        ));
+    }
+    if breakpoints.len() != values {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "The number of values has to match the number of breakpoints",
+        ));
+    }
+    if breakpoints
+        .windows(2)
+        .any(|w| w[0].partial_cmp(&w[1]) != Some(Ordering::Less))
+    {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "The breakpoints have to be strictly ascending",
+        ));
+    }
+    Ok(())
+}
+
+/// The index of the segment that contains `x` and
+/// the relative position of `x` within that segment.
+fn segment(breakpoints: &[f64], x: f64, extrapolation: Extrapolation) -> (usize, f64) {
+    if breakpoints.len() < 2 {
+        return (0, 0.0);
+    }
+    let idx = breakpoints[1..breakpoints.len() - 1]
+        .iter()
+        .take_while(|b| x > **b)
+        .count();
+    let (lower, upper) = (breakpoints[idx], breakpoints[idx + 1]);
+    let t = (x - lower) / (upper - lower);
+    match extrapolation {
+        Extrapolation::Clamp => (idx, t.clamp(0.0, 1.0)),
+        Extrapolation::Linear => (idx, t),
+    }
+}
+
+fn interpolate(values: &[f64], idx: usize, t: f64) -> f64 {
+    match values.get(idx + 1) {
+        Some(upper) => values[idx] + t * (upper - values[idx]),
+        None => values[idx],
+    }
+}
+
+impl LookupTable {
+    /// Check the consistency of the table.
+    pub fn validate(&self) -> Result<()> {
+        validate_axis(&self.breakpoints, self.values.len())
+    }
+}
+
+impl LookupTable2d {
+    /// Check the consistency of the table.
+    pub fn validate(&self) -> Result<()> {
+        validate_axis(&self.x, self.values.len())?;
+        for row in &self.values {
+            validate_axis(&self.y, row.len())?;
+        }
+        Ok(())
+    }
+}
+
+impl PureController<f64, f64> for LookupTable {
+    fn next(&self, x: f64) -> f64 {
+        if self.values.is_empty() {
+            return f64::NAN;
+        }
+        let (idx, t) = segment(&self.breakpoints, x, self.extrapolation);
+        interpolate(&self.values, idx, t)
+    }
+}
+
+impl PureController<(f64, f64), f64> for LookupTable2d {
+    fn next(&self, input: (f64, f64)) -> f64 {
+        let (x, y) = input;
+        if self.values.is_empty() || self.values[0].is_empty() {
+            return f64::NAN;
+        }
+        let (idx_x, t_x) = segment(&self.x, x, self.extrapolation);
+        let (idx_y, t_y) = segment(&self.y, y, self.extrapolation);
+        let lower = interpolate(&self.values[idx_x], idx_y, t_y);
+        match self.values.get(idx_x + 1) {
+            Some(row) => lower + t_x * (interpolate(row, idx_y, t_y) - lower),
+            None => lower,
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn validate_tables() {
+        let mut t = LookupTable::default();
+        assert!(t.validate().is_err());
+        t.breakpoints = vec![1.0, 2.0];
+        t.values = vec![1.0];
+        assert!(t.validate().is_err());
+        t.values.push(2.0);
+        assert!(t.validate().is_ok());
+        t.breakpoints = vec![2.0, 2.0];
+        assert!(t.validate().is_err());
+        t.breakpoints = vec![1.0, f64::NAN];
+        assert!(t.validate().is_err());
+
+        let mut t = LookupTable2d {
+            x: vec![0.0, 1.0],
+            y: vec![0.0, 1.0, 2.0],
+            values: vec![vec![0.0, 1.0, 2.0], vec![3.0, 4.0, 5.0]],
+            ..Default::default()
+        };
+        assert!(t.validate().is_ok());
+        t.values[1].pop();
+        assert!(t.validate().is_err());
+    }
+
+    #[test]
+    fn interpolate_1d() {
+        let mut t = LookupTable {
+            breakpoints: vec![-1.0, 0.0, 2.0],
+            values: vec![10.0, 0.0, 4.0],
+            ..Default::default()
+        };
+        assert_eq!(t.next(-1.0), 10.0);
+        assert_eq!(t.next(-0.5), 5.0);
+        assert_eq!(t.next(0.0), 0.0);
+        assert_eq!(t.next(1.5), 3.0);
+        assert_eq!(t.next(2.0), 4.0);
+        assert_eq!(t.next(-3.0), 10.0);
+        assert_eq!(t.next(5.0), 4.0);
+        t.extrapolation = Extrapolation::Linear;
+        assert_eq!(t.next(-3.0), 30.0);
+        assert_eq!(t.next(5.0), 10.0);
+    }
+
+    #[test]
+    fn interpolate_1d_with_degenerated_tables() {
+        let t = LookupTable {
+            breakpoints: vec![1.0],
+            values: vec![7.0],
+            extrapolation: Extrapolation::Linear,
+        };
+        assert_eq!(t.next(0.0), 7.0);
+        assert_eq!(t.next(9.0), 7.0);
+        assert!(LookupTable::default().next(1.0).is_nan());
+    }
+
+    #[test]
+    fn interpolate_2d() {
+        let mut t = LookupTable2d {
+            x: vec![0.0, 10.0],
+            y: vec![0.0, 1.0, 2.0],
+            values: vec![vec![0.0, 1.0, 2.0], vec![10.0, 11.0, 12.0]],
+            ..Default::default()
+        };
+        assert_eq!(t.next((0.0, 0.0)), 0.0);
+        assert_eq!(t.next((10.0, 2.0)), 12.0);
+        assert_eq!(t.next((5.0, 0.5)), 5.5);
+        assert_eq!(t.next((2.5, 1.5)), 4.0);
+        assert_eq!(t.next((20.0, -1.0)), 10.0);
+        t.extrapolation = Extrapolation::Linear;
+        assert_eq!(t.next((20.0, -1.0)), 19.0);
+
+        let t = LookupTable2d {
+            x: vec![0.0],
+            y: vec![0.0, 1.0],
+            values: vec![vec![0.0, 1.0]],
+            ..Default::default()
+        };
+        assert_eq!(t.next((3.0, 0.5)), 0.5);
+        assert!(LookupTable2d::default().next((0.0, 0.0)).is_nan());
+    }
+}
@@ -0,0 +1,651 @@
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::{thread, time::Duration};
+//! use msr_legacy::{Cropping, TimeStepController, mpc::*};
+//!
+//! // A single heater with a first-order step response
+//! let step_response = (1..=30)
+//!     .map(|k| 2.0 * (1.0 - (-(k as f64) / 8.0).exp()))
+//!     .collect();
+//! let cfg = MpcConfig {
+//!     step_response: vec![vec![step_response]],
+//!     sample_time: Duration::from_secs(1),
+//!     default_targets: vec![60.0],
+//!     input_limits: vec![Cropping {
+//!         low: Some(0.0),
+//!         high: Some(100.0),
+//!     }],
+//!     ..Default::default()
+//! };
+//! let mut c = Mpc::new(cfg).unwrap();
+//!
+//! let delta_t = Duration::from_secs(1);
+//! loop {
+//!     let temperature = 21.3;
+//!     let heating = c.next(&[temperature][..], &delta_t);
+//!     // Here you'd use s.th. like `write_actuator(heating[0])`
+//!     thread::sleep(delta_t);
+//! }
+//! ```
+
+use super::{Controller, Cropping, PureController};
+use std::{
+    io::{Error, ErrorKind, Result},
+    time::Duration,
+};
+
+/// Linear model-predictive controller
+///
+/// The process is described by the sampled step responses of each
+/// output to each input (dynamic matrix control). In every sample
+/// period the future outputs are predicted over the prediction horizon
+/// and the input moves within the control horizon are optimized so that
+/// the weighted squared deviation from the targets plus the weighted
+/// squared input moves is minimal. Input limits are hard constraints,
+/// output limits are soft constraints that are penalized by
+/// the constraint weight.
+/// Only the first move is applied (receding horizon).
+#[derive(Debug, Clone)]
+pub struct Mpc {
+    cfg: MpcConfig,
+    /// Current controller state
+    pub state: MpcState,
+}
+
+/// MPC configuration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct MpcConfig {
+    /// Step response coefficients `[output][input][k]` for
+    /// `k = 1, 2, ...` sample periods after a unit step of the input.
+    ///
+    /// The last coefficient is used for all following periods.
+    pub step_response: Vec<Vec<Vec<f64>>>,
+    /// The sample period of the step responses
+    pub sample_time: Duration,
+    /// Number of predicted sample periods
+    pub prediction_horizon: usize,
+    /// Number of optimized input moves
+    pub control_horizon: usize,
+    /// Weights of the output deviations (`1.0` if missing)
+    pub output_weights: Vec<f64>,
+    /// Weight of the squared input moves
+    pub move_suppression: f64,
+    /// Hard limits of the inputs
+    pub input_limits: Vec<Cropping>,
+    /// Soft limits of the outputs
+    pub output_limits: Vec<Cropping>,
+    /// Weight of the squared output limit violations
+    pub constraint_weight: f64,
+    /// Targets that are used after initialization
+    pub default_targets: Vec<f64>,
+    /// Number of iterations of the optimizer
+    pub iterations: usize,
+}
+
+impl Default for MpcConfig {
+    fn default() -> Self {
+        MpcConfig {
+            step_response: vec![],
+            sample_time: Duration::from_secs(1),
+            prediction_horizon: 10,
+            control_horizon: 2,
+            output_weights: vec![],
+            move_suppression: 0.1,
+            input_limits: vec![],
+            output_limits: vec![],
+            constraint_weight: 10.0,
+            default_targets: vec![],
+            iterations: 200,
+        }
+    }
+}
+
+/// Internal MPC state
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct MpcState {
+    /// Current targets of the outputs
+    pub targets: Vec<f64>,
+    /// Current values of the inputs
+    pub inputs: Vec<f64>,
+    /// Past input moves (the most recent first)
+    pub moves: Vec<Vec<f64>>,
+    /// Time since the last calculation
+    pub elapsed: Option<Duration>,
+}
+
+impl MpcConfig {
+    /// Number of controlled outputs
+    pub fn output_count(&self) -> usize {
+        self.step_response.len()
+    }
+
+    /// Number of manipulated inputs
+    pub fn input_count(&self) -> usize {
+        self.step_response.first().map(Vec::len).unwrap_or(0)
+    }
+
+    fn model_length(&self) -> usize {
+        self.step_response
+            .iter()
+            .flatten()
+            .map(Vec::len)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Step response coefficient of `output` to `input` after `k` periods
+    fn coefficient(&self, output: usize, input: usize, k: usize) -> f64 {
+        let Some(s) = self
+            .step_response
+            .get(output)
+            .and_then(|s| s.get(input))
+            .filter(|s| !s.is_empty())
+        else {
+            return 0.0;
+        };
+        if k == 0 {
+            return 0.0;
+        }
+        s[(k - 1).min(s.len() - 1)]
+    }
+
+    /// Check the consistency of the configuration.
+    ///
+    /// The step responses of all outputs must be given for
+    /// the same number of inputs.
+    pub fn validate(&self) -> Result<()> {
+        let input_count = self.input_count();
+        if input_count == 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "At least one step response is required",
+            ));
+        }
+        if let Some(i) = self
+            .step_response
+            .iter()
+            .position(|s| s.len() != input_count)
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "The step responses of output {i} must be given for {input_count} input(s)"
+                ),
+            ));
+        }
+        Ok(())
+    }
+
+    fn state_matches(&self, state: &MpcState) -> bool {
+        state.targets.len() == self.output_count()
+            && state.inputs.len() == self.input_count()
+            && state.moves.len() == self.input_count()
+    }
+
+    fn limit_input(&self, input: usize, x: f64) -> f64 {
+        self.input_limits.get(input).map(|c| c.crop(x)).unwrap_or(x)
+    }
+
+    /// Create the initial state.
+    pub fn initial_state(&self) -> MpcState {
+        let mut targets = self.default_targets.clone();
+        targets.resize(self.output_count(), 0.0);
+        MpcState {
+            targets,
+            inputs: (0..self.input_count())
+                .map(|j| self.limit_input(j, 0.0))
+                .collect(),
+            moves: vec![vec![]; self.input_count()],
+            elapsed: None,
+        }
+    }
+
+    /// Predicted outputs `[output][p]` for the given input moves `[input][m]`
+    fn predict(&self, free: &[Vec<f64>], moves: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        free.iter()
+            .enumerate()
+            .map(|(i, free)| {
+                free.iter()
+                    .enumerate()
+                    .map(|(p, f)| {
+                        let p = p + 1;
+                        f + moves
+                            .iter()
+                            .enumerate()
+                            .map(|(j, m)| {
+                                m.iter()
+                                    .take(p)
+                                    .enumerate()
+                                    .map(|(m, x)| self.coefficient(i, j, p - m) * x)
+                                    .sum::<f64>()
+                            })
+                            .sum::<f64>()
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Optimize the input values `[input][m]` by an accelerated
+    /// projected gradient method.
+    fn optimize(&self, state: &MpcState, free: &[Vec<f64>]) -> Vec<Vec<f64>> {
+        let n_u = self.input_count();
+        let n_y = self.output_count();
+        let horizon = self.prediction_horizon;
+        let control_horizon = self.control_horizon.clamp(1, horizon.max(1));
+        let weight = |i: usize| self.output_weights.get(i).copied().unwrap_or(1.0);
+
+        let moves_of = |u: &[Vec<f64>]| -> Vec<Vec<f64>> {
+            u.iter()
+                .enumerate()
+                .map(|(j, u)| {
+                    let mut prev = state.inputs[j];
+                    u.iter()
+                        .map(|x| {
+                            let dx = x - prev;
+                            prev = *x;
+                            dx
+                        })
+                        .collect()
+                })
+                .collect()
+        };
+
+        // Upper bound of the Lipschitz constant of the gradient
+        let w_max = (0..n_y).map(weight).fold(0.0, f64::max);
+        let mut g_norm = 0.0;
+        for i in 0..n_y {
+            for j in 0..n_u {
+                for p in 1..=horizon {
+                    for m in 0..p.min(control_horizon) {
+                        g_norm += self.coefficient(i, j, p - m).powi(2);
+                    }
+                }
+            }
+        }
+        let lipschitz = 8.0 * ((w_max + self.constraint_weight) * g_norm + self.move_suppression);
+        let mut u: Vec<Vec<f64>> = state
+            .inputs
+            .iter()
+            .map(|x| vec![*x; control_horizon])
+            .collect();
+        if lipschitz <= 0.0 {
+            return u;
+        }
+        let step = 1.0 / lipschitz;
+
+        let project = |u: &mut Vec<Vec<f64>>| {
+            for (j, u) in u.iter_mut().enumerate() {
+                for x in u.iter_mut() {
+                    *x = self.limit_input(j, *x);
+                }
+            }
+        };
+
+        project(&mut u);
+        let mut v = u.clone();
+        let mut t = 1.0_f64;
+
+        for _ in 0..self.iterations {
+            let moves = moves_of(&v);
+            let y = self.predict(free, &moves);
+
+            // Gradient with respect to the input moves
+            let mut grad_moves = vec![vec![0.0; control_horizon]; n_u];
+            for (i, y) in y.iter().enumerate() {
+                let target = state.targets[i];
+                let limits = self.output_limits.get(i);
+                for (p, y) in y.iter().enumerate() {
+                    let p = p + 1;
+                    let mut e = weight(i) * (y - target);
+                    if let Some(limits) = limits {
+                        e += self.constraint_weight * (y - limits.crop(*y));
+                    }
+                    for (j, grad) in grad_moves.iter_mut().enumerate() {
+                        for (m, g) in grad.iter_mut().enumerate().take(p) {
+                            *g += 2.0 * self.coefficient(i, j, p - m) * e;
+                        }
+                    }
+                }
+            }
+            for (grad, moves) in grad_moves.iter_mut().zip(&moves) {
+                for (g, dx) in grad.iter_mut().zip(moves) {
+                    *g += 2.0 * self.move_suppression * dx;
+                }
+            }
+
+            // Gradient with respect to the input values
+            let mut next: Vec<Vec<f64>> = v
+                .iter()
+                .zip(&grad_moves)
+                .map(|(v, grad)| {
+                    (0..control_horizon)
+                        .map(|m| {
+                            let g = grad[m] - grad.get(m + 1).copied().unwrap_or(0.0);
+                            v[m] - step * g
+                        })
+                        .collect()
+                })
+                .collect();
+            project(&mut next);
+
+            let t_next = (1.0 + (1.0 + 4.0 * t * t).sqrt()) / 2.0;
+            let beta = (t - 1.0) / t_next;
+            v = next
+                .iter()
+                .zip(&u)
+                .map(|(next, u)| {
+                    next.iter()
+                        .zip(u)
+                        .map(|(x, prev)| x + beta * (x - prev))
+                        .collect()
+                })
+                .collect();
+            project(&mut v);
+            u = next;
+            t = t_next;
+        }
+        u
+    }
+}
+
+impl Mpc {
+    /// Create a new controller instance.
+    ///
+    /// Fails if the configuration is invalid.
+    pub fn new(cfg: MpcConfig) -> Result<Self> {
+        cfg.validate()?;
+        let state = cfg.initial_state();
+        Ok(Mpc { cfg, state })
+    }
+    /// Set the target of an output.
+    pub fn set_target(&mut self, output: usize, target: f64) {
+        if let Some(t) = self.state.targets.get_mut(output) {
+            *t = target;
+        }
+    }
+    /// Reset the internal controller state.
+    pub fn reset(&mut self) {
+        self.state = self.cfg.initial_state();
+    }
+}
+
+/// The input consists of the measured outputs of the process
+/// and the result are the new values of the process inputs.
+impl Controller<(&[f64], &Duration), Vec<f64>> for Mpc {
+    fn next(&mut self, input: (&[f64], &Duration)) -> Vec<f64> {
+        let (actual, duration) = input;
+        let (state, result) = self.cfg.next((self.state.clone(), actual, duration));
+        self.state = state;
+        result
+    }
+}
+
+/// A state that does not match the configuration is reinitialized.
+impl PureController<(MpcState, &[f64], &Duration), (MpcState, Vec<f64>)> for MpcConfig {
+    fn next(&self, input: (MpcState, &[f64], &Duration)) -> (MpcState, Vec<f64>) {
+        let (mut state, actual, duration) = input;
+        if !self.state_matches(&state) {
+            state = self.initial_state();
+        }
+
+        let elapsed = match state.elapsed {
+            Some(elapsed) => elapsed + *duration,
+            None => self.sample_time,
+        };
+        if elapsed < self.sample_time {
+            state.elapsed = Some(elapsed);
+            let result = state.inputs.clone();
+            return (state, result);
+        }
+        // Missed sample periods are skipped
+        let leftover = elapsed - self.sample_time;
+        state.elapsed = Some(if leftover < self.sample_time {
+            leftover
+        } else {
+            Duration::ZERO
+        });
+
+        // Free response: the predicted outputs without further moves
+        let model_length = self.model_length();
+        let free: Vec<Vec<f64>> = (0..self.output_count())
+            .map(|i| {
+                let y = actual.get(i).copied().unwrap_or(state.targets[i]);
+                (1..=self.prediction_horizon)
+                    .map(|p| {
+                        y + state
+                            .moves
+                            .iter()
+                            .enumerate()
+                            .map(|(j, moves)| {
+                                moves
+                                    .iter()
+                                    .enumerate()
+                                    .map(|(l, dx)| {
+                                        let l = l + 1;
+                                        (self.coefficient(i, j, p + l) - self.coefficient(i, j, l))
+                                            * dx
+                                    })
+                                    .sum::<f64>()
+                            })
+                            .sum::<f64>()
+                    })
+                    .collect()
+            })
+            .collect();
+
+        let u = self.optimize(&state, &free);
+        for (j, u) in u.iter().enumerate() {
+            let x = u[0];
+            let moves = &mut state.moves[j];
+            moves.insert(0, x - state.inputs[j]);
+            moves.truncate(model_length);
+            state.inputs[j] = x;
+        }
+        let result = state.inputs.clone();
+        (state, result)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    /// Simulate a process with the given step response.
+    struct Process {
+        step_response: Vec<Vec<Vec<f64>>>,
+        moves: Vec<Vec<f64>>,
+    }
+
+    impl Process {
+        fn new(cfg: &MpcConfig) -> Self {
+            Process {
+                step_response: cfg.step_response.clone(),
+                moves: vec![vec![]; cfg.input_count()],
+            }
+        }
+        fn output(&self) -> Vec<f64> {
+            self.step_response
+                .iter()
+                .map(|s| {
+                    s.iter()
+                        .zip(&self.moves)
+                        .map(|(s, moves)| {
+                            moves
+                                .iter()
+                                .enumerate()
+                                .map(|(l, dx)| s[l.min(s.len() - 1)] * dx)
+                                .sum::<f64>()
+                        })
+                        .sum()
+                })
+                .collect()
+        }
+        fn apply(&mut self, prev: &[f64], u: &[f64]) {
+            for (j, moves) in self.moves.iter_mut().enumerate() {
+                moves.insert(0, u[j] - prev[j]);
+            }
+        }
+    }
+
+    fn first_order(gain: f64, tau: f64) -> Vec<f64> {
+        (1..=40)
+            .map(|k| gain * (1.0 - (-(k as f64) / tau).exp()))
+            .collect()
+    }
+
+    fn simulate(cfg: MpcConfig, steps: usize) -> (Mpc, Vec<f64>) {
+        let mut process = Process::new(&cfg);
+        let mut c = Mpc::new(cfg).unwrap();
+        let dt = Duration::from_secs(1);
+        let mut y = process.output();
+        for _ in 0..steps {
+            let prev = c.state.inputs.clone();
+            let u = c.next((&y[..], &dt));
+            process.apply(&prev, &u);
+            y = process.output();
+        }
+        (c, y)
+    }
+
+    #[test]
+    fn reach_target() {
+        let cfg = MpcConfig {
+            step_response: vec![vec![first_order(2.0, 3.0)]],
+            default_targets: vec![10.0],
+            ..Default::default()
+        };
+        let (c, y) = simulate(cfg, 40);
+        assert!((y[0] - 10.0).abs() < 0.1);
+        assert!((c.state.inputs[0] - 5.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn respect_input_limits() {
+        let cfg = MpcConfig {
+            step_response: vec![vec![first_order(2.0, 3.0)]],
+            default_targets: vec![10.0],
+            input_limits: vec![Cropping {
+                low: Some(0.0),
+                high: Some(4.0),
+            }],
+            ..Default::default()
+        };
+        let mut process = Process::new(&cfg);
+        let mut c = Mpc::new(cfg).unwrap();
+        let dt = Duration::from_secs(1);
+        for _ in 0..40 {
+            let y = process.output();
+            let prev = c.state.inputs.clone();
+            let u = c.next((&y[..], &dt));
+            assert!(u[0] >= 0.0 && u[0] <= 4.0);
+            process.apply(&prev, &u);
+        }
+        assert_eq!(c.state.inputs[0], 4.0);
+        assert!((process.output()[0] - 8.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn control_multiple_outputs() {
+        // Two coupled outputs
+        let cfg = MpcConfig {
+            step_response: vec![
+                vec![first_order(1.0, 2.0), first_order(0.3, 4.0)],
+                vec![first_order(0.2, 3.0), first_order(1.0, 2.0)],
+            ],
+            default_targets: vec![5.0, -2.0],
+            iterations: 500,
+            ..Default::default()
+        };
+        let (_, y) = simulate(cfg, 60);
+        assert!((y[0] - 5.0).abs() < 0.1);
+        assert!((y[1] + 2.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn calculate_once_per_sample_time() {
+        let cfg = MpcConfig {
+            step_response: vec![vec![first_order(1.0, 2.0)]],
+            sample_time: Duration::from_secs(2),
+            default_targets: vec![1.0],
+            ..Default::default()
+        };
+        let mut c = Mpc::new(cfg).unwrap();
+        let dt = Duration::from_secs(1);
+        let u0 = c.next((&[0.0][..], &dt));
+        assert!(u0[0] > 0.0);
+        assert_eq!(c.next((&[0.0][..], &dt)), u0);
+        assert_ne!(c.next((&[0.0][..], &dt)), u0);
+        c.reset();
+        assert_eq!(c.state.inputs, vec![0.0]);
+        assert_eq!(c.state.elapsed, None);
+        c.set_target(0, 3.0);
+        assert_eq!(c.state.targets, vec![3.0]);
+    }
+
+    #[test]
+    fn skip_missed_sample_periods() {
+        let cfg = MpcConfig {
+            step_response: vec![vec![first_order(1.0, 2.0)]],
+            sample_time: Duration::from_secs(2),
+            default_targets: vec![1.0],
+            ..Default::default()
+        };
+        let mut c = Mpc::new(cfg).unwrap();
+        c.next((&[0.0][..], &Duration::from_secs(1)));
+        let u = c.next((&[0.0][..], &Duration::from_secs(5)));
+        assert_eq!(c.state.elapsed, Some(Duration::ZERO));
+        // No catching up of the missed periods
+        assert_eq!(c.next((&[0.0][..], &Duration::from_secs(1))), u);
+        assert_eq!(c.state.elapsed, Some(Duration::from_secs(1)));
+        for _ in 0..10 {
+            c.next((&[0.0][..], &Duration::from_secs(7)));
+            assert!(c.state.elapsed.unwrap() < Duration::from_secs(2));
+        }
+    }
+
+    #[test]
+    fn reject_invalid_step_responses() {
+        assert!(Mpc::new(MpcConfig::default()).is_err());
+        let ragged = MpcConfig {
+            step_response: vec![
+                vec![first_order(1.0, 2.0), first_order(0.3, 4.0)],
+                vec![first_order(0.2, 3.0)],
+            ],
+            ..Default::default()
+        };
+        let err = Mpc::new(ragged.clone()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        // The unchecked configuration must not panic
+        let dt = Duration::from_secs(1);
+        let (_, u) = ragged.next((ragged.initial_state(), &[0.0, 0.0][..], &dt));
+        assert_eq!(u.len(), 2);
+    }
+
+    #[test]
+    fn reinitialize_mismatching_state() {
+        let cfg = MpcConfig {
+            step_response: vec![vec![first_order(1.0, 2.0), first_order(0.3, 4.0)]],
+            default_targets: vec![1.0],
+            ..Default::default()
+        };
+        let dt = Duration::from_secs(1);
+        let (state, u) = cfg.next((MpcState::default(), &[0.0][..], &dt));
+        assert_eq!(u.len(), 2);
+        assert_eq!(state.targets, vec![1.0]);
+        assert_eq!(state.moves.len(), 2);
+
+        let state = MpcState {
+            moves: vec![],
+            ..state
+        };
+        let (state, _) = cfg.next((state, &[0.0][..], &dt));
+        assert_eq!(state.moves.len(), 2);
+    }
+}
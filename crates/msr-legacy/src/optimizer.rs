@@ -0,0 +1,247 @@
+//! Optimization of conditions
+//!
+//! Conditions are simplified once when loading a configuration
+//! instead of being evaluated in every cycle:
+//!
+//! - Comparisons and calculations of constants are folded into
+//!   constants, e.g. `in.x > 2 * 5` becomes `in.x > 10`.
+//! - Constant operands of logical operations are eliminated,
+//!   e.g. `false && in.x` becomes `false`.
+//! - Duplicate and absorbed operands are removed, e.g.
+//!   `in.a || in.a && in.b` becomes `in.a`.
+//! - Common operands are extracted, e.g.
+//!   `in.a && in.b || in.a && in.c` becomes `in.a && (in.b || in.c)`.
+//!
+//! An optimized condition evaluates to the same result. Only
+//! errors of operands that don't affect the result anymore,
+//! like missing sources in `in.x && false`, are not reported.
+//!
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{optimizer::optimize, parser::parse};
+//!
+//! let expr = optimize(parse("in.a && in.b || in.a && !(1 > 2)").unwrap());
+//! assert_eq!(expr.to_string(), "in.a");
+//! ```
+
+use super::{BoolExpr, Comparison, Evaluation, Expr, Source, Sources, SyncRuntime, SystemState};
+
+/// Optimize a condition.
+pub fn optimize(expr: BoolExpr<Comparison>) -> BoolExpr<Comparison> {
+    use crate::BoolExpr::*;
+    match expr {
+        True => True,
+        False => False,
+        Eval(c) => fold_comparison(c),
+        Not(x) => match optimize(*x) {
+            True => False,
+            False => True,
+            Not(x) => *x,
+            x => Not(Box::new(x)),
+        },
+        And(a, b) => match (optimize(*a), optimize(*b)) {
+            (False, _) | (_, False) => False,
+            (True, x) | (x, True) => x,
+            (a, b) if a == b => a,
+            (a, Or(x, y)) | (Or(x, y), a) if *x == a || *y == a => a,
+            (Or(a1, a2), Or(b1, b2)) => match extract_common(*a1, *a2, *b1, *b2) {
+                Ok((common, a, b)) => optimize(Or(Box::new(common), Box::new(And(a, b)))),
+                Err((a1, a2, b1, b2)) => And(Box::new(Or(a1, a2)), Box::new(Or(b1, b2))),
+            },
+            (a, b) => And(Box::new(a), Box::new(b)),
+        },
+        Or(a, b) => match (optimize(*a), optimize(*b)) {
+            (True, _) | (_, True) => True,
+            (False, x) | (x, False) => x,
+            (a, b) if a == b => a,
+            (a, And(x, y)) | (And(x, y), a) if *x == a || *y == a => a,
+            (And(a1, a2), And(b1, b2)) => match extract_common(*a1, *a2, *b1, *b2) {
+                Ok((common, a, b)) => optimize(And(Box::new(common), Box::new(Or(a, b)))),
+                Err((a1, a2, b1, b2)) => Or(Box::new(And(a1, a2)), Box::new(And(b1, b2))),
+            },
+            (a, b) => Or(Box::new(a), Box::new(b)),
+        },
+    }
+}
+
+type Boxed = Box<BoolExpr<Comparison>>;
+
+/// Find an operand that occurs on both sides of `(a1 op a2)` and `(b1 op b2)`.
+///
+/// Returns the common operand and the remaining operands of both sides.
+#[allow(clippy::type_complexity)]
+fn extract_common(
+    a1: BoolExpr<Comparison>,
+    a2: BoolExpr<Comparison>,
+    b1: BoolExpr<Comparison>,
+    b2: BoolExpr<Comparison>,
+) -> Result<(BoolExpr<Comparison>, Boxed, Boxed), (Boxed, Boxed, Boxed, Boxed)> {
+    if a1 == b1 {
+        Ok((a1, Box::new(a2), Box::new(b2)))
+    } else if a1 == b2 {
+        Ok((a1, Box::new(a2), Box::new(b1)))
+    } else if a2 == b1 {
+        Ok((a2, Box::new(a1), Box::new(b2)))
+    } else if a2 == b2 {
+        Ok((a2, Box::new(a1), Box::new(b1)))
+    } else {
+        Err((Box::new(a1), Box::new(a2), Box::new(b1), Box::new(b2)))
+    }
+}
+
+fn is_const(src: &Source) -> bool {
+    match src {
+        Source::Const(_) => true,
+        Source::Expr(e) => e.sources().iter().all(is_const),
+        _ => false,
+    }
+}
+
+/// Replace calculations of constants by their result.
+///
+/// Calculations that fail are kept to report the error
+/// when they are evaluated.
+fn fold_source(src: Source) -> Source {
+    let Source::Expr(e) = src else {
+        return src;
+    };
+    if !is_const(&Source::Expr(e.clone())) {
+        return Source::Expr(Box::new(fold_operands(*e)));
+    }
+    match e.eval(&SystemState::default()) {
+        Ok(v) => Source::Const(v),
+        Err(_) => Source::Expr(e),
+    }
+}
+
+fn fold_operands(e: Expr) -> Expr {
+    use crate::Expr::*;
+    match e {
+        Add(a, b) => Add(fold_source(a), fold_source(b)),
+        Sub(a, b) => Sub(fold_source(a), fold_source(b)),
+        Mul(a, b) => Mul(fold_source(a), fold_source(b)),
+        Div(a, b) => Div(fold_source(a), fold_source(b)),
+        Min(a, b) => Min(fold_source(a), fold_source(b)),
+        Max(a, b) => Max(fold_source(a), fold_source(b)),
+        Abs(a) => Abs(fold_source(a)),
+    }
+}
+
+fn fold_comparison(c: Comparison) -> BoolExpr<Comparison> {
+    let Comparison { left, cmp, right } = c;
+    let c = Comparison {
+        left: fold_source(left),
+        cmp,
+        right: fold_source(right),
+    };
+    if !matches!((&c.left, &c.right), (Source::Const(_), Source::Const(_))) {
+        return BoolExpr::Eval(c);
+    }
+    match c.eval(&SystemState::default()) {
+        Ok(true) => BoolExpr::True,
+        Ok(false) => BoolExpr::False,
+        Err(_) => BoolExpr::Eval(c),
+    }
+}
+
+impl SyncRuntime {
+    /// Optimize the conditions of all rules and state machine transitions.
+    pub fn optimize_conditions(&mut self) {
+        for rule in &mut self.rules {
+            rule.condition = optimize(std::mem::replace(&mut rule.condition, BoolExpr::False));
+        }
+        for fsm in self.state_machines.values_mut() {
+            for t in &mut fsm.transitions {
+                t.condition = optimize(std::mem::replace(&mut t.condition, BoolExpr::False));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::parser::parse;
+
+    fn optimized(s: &str) -> String {
+        optimize(parse(s).unwrap()).to_string()
+    }
+
+    #[test]
+    fn fold_constants() {
+        assert_eq!(optimized("1 < 2"), "true");
+        assert_eq!(optimized("'a' == 'b'"), "false");
+        assert_eq!(optimized("in.x > 2 * 5"), "in.x > 10");
+        assert_eq!(optimized("in.x > in.y * (2 + 3)"), "in.x > in.y * 5");
+        assert_eq!(optimized("abs(-2.5) ~= 2.0 +/- 0.5"), "true");
+        // Errors are kept
+        assert_eq!(
+            optimized("9223372036854775807 + 1 > in.x"),
+            "9223372036854775807 + 1 > in.x"
+        );
+        assert_eq!(optimized("true > false"), "true > false");
+    }
+
+    #[test]
+    fn eliminate_constant_operands() {
+        assert_eq!(optimized("false && in.x"), "false");
+        assert_eq!(optimized("in.x && 1 > 2"), "false");
+        assert_eq!(optimized("true && in.x"), "in.x");
+        assert_eq!(optimized("in.x || 1 < 2"), "true");
+        assert_eq!(optimized("false || in.x"), "in.x");
+        assert_eq!(optimized("!(1 > 2)"), "true");
+        assert_eq!(optimized("!!in.x"), "in.x");
+        assert_eq!(optimized("!(!in.x || false)"), "in.x");
+    }
+
+    #[test]
+    fn extract_common_operands() {
+        assert_eq!(optimized("in.a && in.a"), "in.a");
+        assert_eq!(optimized("in.a || in.a"), "in.a");
+        assert_eq!(
+            optimized("in.a && in.b || in.a && in.c"),
+            "in.a && (in.b || in.c)"
+        );
+        assert_eq!(
+            optimized("in.b && in.a || in.c && in.a"),
+            "in.a && (in.b || in.c)"
+        );
+        assert_eq!(
+            optimized("(in.a || in.b) && (in.c || in.a)"),
+            "in.a || in.b && in.c"
+        );
+        assert_eq!(optimized("in.a && in.b || in.a && in.b"), "in.a && in.b");
+        assert_eq!(optimized("in.a || in.a && in.b"), "in.a");
+        assert_eq!(optimized("(in.b || in.a) && in.a"), "in.a");
+        assert_eq!(
+            optimized("in.a && in.b || in.c && in.d"),
+            "in.a && in.b || in.c && in.d"
+        );
+    }
+
+    #[test]
+    fn keep_results() {
+        let mut state = SystemState::default();
+        let exprs = [
+            "in.a && in.b || in.a && in.c",
+            "(in.a || in.b) && (in.c || in.a)",
+            "!(in.a && true) || in.b && 3 > 2",
+            "in.c && (in.a || in.c) || in.b",
+        ];
+        for s in exprs {
+            let expr = parse(s).unwrap();
+            let opt = optimize(expr.clone());
+            for bits in 0..8 {
+                for (i, id) in ["a", "b", "c"].iter().enumerate() {
+                    state
+                        .io
+                        .inputs
+                        .insert((*id).into(), (bits & (1 << i) != 0).into());
+                }
+                assert_eq!(expr.eval(&state).unwrap(), opt.eval(&state).unwrap(), "{s}");
+            }
+        }
+    }
+}
@@ -1,20 +1,423 @@
+//! Expression parser
+//!
+//! Conditions of rules and state machine transitions can be written
+//! as text, e.g. in configuration files or HMIs, and be printed back
+//! to text again.
+//!
+//! | Syntax                            | Meaning                            |
+//! |-----------------------------------|------------------------------------|
+//! | `in.x`, `out.x`, `mem.x`          | Input, output or memory value      |
+//! | `setpoint.x`, `timeout.x`         | Setpoint or timeout                |
+//! | `true`, `-5`, `80.0`, `'text'`    | Constant values                    |
+//! | `+`, `-`, `*`, `/`                | Arithmetic                         |
+//! | `abs(a)`, `min(a, b)`, `max(a, b)`| Arithmetic functions               |
+//! | `==`, `!=`, `<`, `<=`, `>`, `>=`  | Comparison                         |
+//! | `~= b +/- 0.1`, `!~= b +/- 2%`    | Approximate comparison             |
+//! | `!`, `&&`, `\|\|`                 | Logical NOT, AND and OR            |
+//! | `( ... )`                         | Grouping                           |
+//!
+//! A source without a comparator, like `out.pump`, is short for `out.pump == true`.
+//!
+//! The tolerance of an approximate comparison is either absolute
+//! or a percentage of the larger magnitude of both sides.
+//!
+//! Rules and actions are printed in a canonical form for reviews
+//! and diffs, but can't be parsed. Conditions of rules and state
+//! machine transitions are serialized as text (see `condition_text`).
+//!
+//! A `-` that directly follows an identifier is part of the identifier
+//! (e.g. `mem.too-hot`), so the minus operator has to be separated by whitespace.
+//!
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{parser, BoolExpr, Comparison, Source};
+//!
+//! let expr = parser::parse("in.temp > 80.0 && !out.pump").unwrap();
+//! assert_eq!(
+//!     expr,
+//!     BoolExpr::And(
+//!         Box::new(Source::In("temp".into()).cmp_gt(80.0.into()).into()),
+//!         Box::new(!BoolExpr::from(Source::Out("pump".into()).cmp_eq(true.into()))),
+//!     )
+//! );
+//! assert_eq!(expr.to_string(), "in.temp > 80.0 && !out.pump");
+//!
+//! // Or use `str::parse`
+//! let expr: BoolExpr<Comparison> = "(in.a || in.b) && in.a - in.b > 5.0".parse().unwrap();
+//! assert_eq!(expr.to_string(), "(in.a || in.b) && in.a - in.b > 5.0");
+//! ```
+
 use super::*;
-use std::io::{Error, ErrorKind, Result};
-use std::str::FromStr;
+use std::{
+    fmt,
+    io::{Error, ErrorKind, Result},
+    str::FromStr,
+};
 
-impl FromStr for Comparison {
+/// Parse a boolean expression of comparisons.
+///
+/// The error message contains the position (in bytes) of the invalid token.
+pub fn parse(s: &str) -> Result<BoolExpr<Comparison>> {
+    let mut parser = Parser::new(s)?;
+    if parser.tokens.is_empty() {
+        return Err(invalid_input("empty expression"));
+    }
+    let expr = parser.or()?;
+    parser.finish()?;
+    Ok(expr)
+}
+
+impl FromStr for BoolExpr<Comparison> {
     type Err = Error;
     fn from_str(s: &str) -> Result<Self> {
+        parse(s)
+    }
+}
+
+fn invalid_input<E>(msg: E) -> Error
+where
+    E: Into<Box<dyn std::error::Error + Send + Sync>>,
+{
+    Error::new(ErrorKind::InvalidInput, msg)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TokenKind {
+    LeftParen,
+    RightParen,
+    Comma,
+    Not,
+    And,
+    Or,
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Cmp(Comparator),
+    PlusMinus,
+    Operand,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Token<'a> {
+    kind: TokenKind,
+    text: &'a str,
+}
+
+const SPECIAL_CHARS: &str = "()!&|=<>'+*/,~";
+
+/// Placeholder until the tolerance has been parsed
+const NO_TOLERANCE: Tolerance = Tolerance::Absolute(0.0);
+
+fn tokenize(s: &str) -> Result<Vec<(usize, Token<'_>)>> {
+    use crate::Comparator::*;
+    let mut tokens: Vec<(usize, Token<'_>)> = vec![];
+    let mut chars = s.char_indices().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let next = chars.peek().map(|(_, c)| *c);
+        let after_operand = matches!(
+            tokens.last(),
+            Some((_, t)) if matches!(t.kind, TokenKind::Operand | TokenKind::RightParen)
+        );
+        let (kind, len) = match (c, next) {
+            ('(', _) => (TokenKind::LeftParen, 1),
+            (')', _) => (TokenKind::RightParen, 1),
+            (',', _) => (TokenKind::Comma, 1),
+            ('+', Some('/')) if s[start..].starts_with("+/-") => (TokenKind::PlusMinus, 3),
+            ('+', _) => (TokenKind::Plus, 1),
+            ('*', _) => (TokenKind::Star, 1),
+            ('/', _) => (TokenKind::Slash, 1),
+            ('-', _) if after_operand => (TokenKind::Minus, 1),
+            ('!', Some('=')) => (TokenKind::Cmp(NotEqual), 2),
+            ('!', Some('~')) if s[start..].starts_with("!~=") => {
+                (TokenKind::Cmp(NotApproxEqual(NO_TOLERANCE)), 3)
+            }
+            ('~', Some('=')) => (TokenKind::Cmp(ApproxEqual(NO_TOLERANCE)), 2),
+            ('!', _) => (TokenKind::Not, 1),
+            ('&', Some('&')) => (TokenKind::And, 2),
+            ('|', Some('|')) => (TokenKind::Or, 2),
+            ('=', Some('=')) => (TokenKind::Cmp(Equal), 2),
+            ('<', Some('=')) => (TokenKind::Cmp(LessOrEqual), 2),
+            ('<', _) => (TokenKind::Cmp(Less), 1),
+            ('>', Some('=')) => (TokenKind::Cmp(GreaterOrEqual), 2),
+            ('>', _) => (TokenKind::Cmp(Greater), 1),
+            ('\'', _) => {
+                let end = s[start + 1..]
+                    .find('\'')
+                    .map(|i| start + i + 2)
+                    .ok_or_else(|| {
+                        invalid_input(format!("unterminated text at position {start}"))
+                    })?;
+                (TokenKind::Operand, end - start)
+            }
+            ('&' | '|' | '=' | '~', _) => {
+                return Err(invalid_input(format!(
+                    "unexpected character {c} at position {start}"
+                )));
+            }
+            _ => {
+                let end = s[start + 1..]
+                    .find(|c: char| c.is_whitespace() || SPECIAL_CHARS.contains(c))
+                    .map(|i| start + 1 + i)
+                    .unwrap_or(s.len());
+                (TokenKind::Operand, end - start)
+            }
+        };
+        let text = &s[start..start + len];
+        // Skip the remaining characters of the token
+        while chars.peek().map(|(i, _)| *i < start + len) == Some(true) {
+            chars.next();
+        }
+        tokens.push((start, Token { kind, text }));
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<(usize, Token<'a>)>,
+    pos: usize,
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(s: &'a str) -> Result<Self> {
+        Ok(Parser {
+            tokens: tokenize(s)?,
+            pos: 0,
+            end: s.len(),
+        })
+    }
+
+    fn peek(&self) -> Option<TokenKind> {
+        self.tokens.get(self.pos).map(|(_, t)| t.kind)
+    }
+
+    fn next_token(&mut self) -> Result<(usize, Token<'a>)> {
+        let token = self.tokens.get(self.pos).cloned().ok_or_else(|| {
+            invalid_input(format!(
+                "unexpected end of expression at position {}",
+                self.end
+            ))
+        })?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, kind: TokenKind, expected: &str) -> Result<()> {
+        let (pos, token) = self.next_token()?;
+        if token.kind != kind {
+            return Err(invalid_input(format!(
+                "expected {expected} instead of {} at position {pos}",
+                token.text
+            )));
+        }
+        Ok(())
+    }
+
+    fn finish(&self) -> Result<()> {
+        if let Some((pos, token)) = self.tokens.get(self.pos) {
+            return Err(invalid_input(format!(
+                "unexpected token {} at position {pos}",
+                token.text
+            )));
+        }
+        Ok(())
+    }
+
+    fn or(&mut self) -> Result<BoolExpr<Comparison>> {
+        let mut expr = self.and()?;
+        while self.peek() == Some(TokenKind::Or) {
+            self.pos += 1;
+            expr = BoolExpr::Or(Box::new(expr), Box::new(self.and()?));
+        }
+        Ok(expr)
+    }
+
+    fn and(&mut self) -> Result<BoolExpr<Comparison>> {
+        let mut expr = self.unary()?;
+        while self.peek() == Some(TokenKind::And) {
+            self.pos += 1;
+            expr = BoolExpr::And(Box::new(expr), Box::new(self.unary()?));
+        }
+        Ok(expr)
+    }
+
+    fn unary(&mut self) -> Result<BoolExpr<Comparison>> {
+        match self.peek() {
+            Some(TokenKind::Not) => {
+                self.pos += 1;
+                Ok(!self.unary()?)
+            }
+            Some(TokenKind::LeftParen) => {
+                // The parenthesis either groups an arithmetic
+                // or a boolean expression.
+                let start = self.pos;
+                if let Ok(expr) = self.comparison() {
+                    return Ok(expr);
+                }
+                self.pos = start + 1;
+                let expr = self.or()?;
+                self.expect(TokenKind::RightParen, ")")?;
+                Ok(expr)
+            }
+            _ => self.comparison(),
+        }
+    }
+
+    fn comparison(&mut self) -> Result<BoolExpr<Comparison>> {
+        let left = self.sum()?;
+        if let Some(TokenKind::Cmp(cmp)) = self.peek() {
+            self.pos += 1;
+            let right = self.sum()?;
+            let cmp = self.tolerance(cmp)?;
+            return Ok(BoolExpr::Eval(Comparison { left, cmp, right }));
+        }
+        match left {
+            Source::Const(Value::Bit(true)) => Ok(BoolExpr::True),
+            Source::Const(Value::Bit(false)) => Ok(BoolExpr::False),
+            left => Ok(BoolExpr::Eval(left.cmp_eq(true.into()))),
+        }
+    }
+
+    /// Parse the tolerance of an approximate comparator.
+    fn tolerance(&mut self, cmp: Comparator) -> Result<Comparator> {
         use crate::Comparator::*;
-        if s.trim().is_empty() {
-            return Err(Error::new(ErrorKind::InvalidInput, "empty str"));
+        if !matches!(cmp, ApproxEqual(_) | NotApproxEqual(_)) {
+            return Ok(cmp);
         }
-        for cmp in &[GreaterOrEqual, Greater, Equal, LessOrEqual, Less, NotEqual] {
-            if let Some(cmp) = parse_comparison(s, *cmp)? {
-                return Ok(cmp);
+        self.expect(TokenKind::PlusMinus, "+/-")?;
+        let (pos, token) = self.next_token()?;
+        let (text, percent) = match token.text.strip_suffix('%') {
+            Some(text) => (text, true),
+            None => (token.text, false),
+        };
+        let epsilon = text
+            .parse::<f64>()
+            .ok()
+            .filter(|epsilon| token.kind == TokenKind::Operand && *epsilon >= 0.0)
+            .ok_or_else(|| {
+                invalid_input(format!(
+                    "invalid tolerance {} at position {pos}",
+                    token.text
+                ))
+            })?;
+        let tolerance = if percent {
+            Tolerance::Percent(epsilon)
+        } else {
+            Tolerance::Absolute(epsilon)
+        };
+        Ok(match cmp {
+            ApproxEqual(_) => ApproxEqual(tolerance),
+            _ => NotApproxEqual(tolerance),
+        })
+    }
+
+    fn sum(&mut self) -> Result<Source> {
+        let mut src = self.product()?;
+        loop {
+            let op = match self.peek() {
+                Some(TokenKind::Plus) => Expr::Add,
+                Some(TokenKind::Minus) => Expr::Sub,
+                _ => return Ok(src),
+            };
+            self.pos += 1;
+            src = op(src, self.product()?).into();
+        }
+    }
+
+    fn product(&mut self) -> Result<Source> {
+        let mut src = self.factor()?;
+        loop {
+            let op = match self.peek() {
+                Some(TokenKind::Star) => Expr::Mul,
+                Some(TokenKind::Slash) => Expr::Div,
+                _ => return Ok(src),
+            };
+            self.pos += 1;
+            src = op(src, self.factor()?).into();
+        }
+    }
+
+    fn factor(&mut self) -> Result<Source> {
+        let (pos, token) = self.next_token()?;
+        match token.kind {
+            TokenKind::LeftParen => {
+                let src = self.sum()?;
+                self.expect(TokenKind::RightParen, ")")?;
+                Ok(src)
             }
+            TokenKind::Operand if self.peek() == Some(TokenKind::LeftParen) => {
+                self.pos += 1;
+                let mut args = vec![self.sum()?];
+                while self.peek() == Some(TokenKind::Comma) {
+                    self.pos += 1;
+                    args.push(self.sum()?);
+                }
+                self.expect(TokenKind::RightParen, ")")?;
+                function(pos, token.text, args)
+            }
+            TokenKind::Operand => operand(pos, token.text),
+            _ => Err(invalid_input(format!(
+                "expected an operand instead of {} at position {pos}",
+                token.text
+            ))),
         }
-        Err(Error::new(ErrorKind::InvalidInput, "invalid comparison"))
+    }
+}
+
+fn function(pos: usize, name: &str, args: Vec<Source>) -> Result<Source> {
+    let op = match name.to_lowercase().as_str() {
+        "abs" if args.len() == 1 => {
+            return Ok(Expr::Abs(args.into_iter().next().unwrap()).into());
+        }
+        "min" => Expr::Min,
+        "max" => Expr::Max,
+        "abs" => {
+            return Err(invalid_input(format!(
+                "abs requires one argument at position {pos}"
+            )));
+        }
+        _ => {
+            return Err(invalid_input(format!(
+                "unknown function {name} at position {pos}"
+            )));
+        }
+    };
+    if args.len() < 2 {
+        return Err(invalid_input(format!(
+            "{name} requires at least two arguments at position {pos}"
+        )));
+    }
+    let mut args = args.into_iter();
+    let first = args.next().unwrap();
+    Ok(args.fold(first, |a, b| op(a, b).into()))
+}
+
+fn operand(pos: usize, text: &str) -> Result<Source> {
+    atom(text)
+        .map_err(|err| invalid_input(format!("invalid operand {text} at position {pos}: {err}")))
+}
+
+impl FromStr for Comparison {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser::new(s)?;
+        if parser.tokens.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "empty str"));
+        }
+        let left = parser.sum()?;
+        let Some(TokenKind::Cmp(cmp)) = parser.peek() else {
+            return Err(Error::new(ErrorKind::InvalidInput, "invalid comparison"));
+        };
+        parser.pos += 1;
+        let right = parser.sum()?;
+        let cmp = parser.tolerance(cmp)?;
+        parser.finish()?;
+        Ok(Comparison { left, cmp, right })
     }
 }
 
@@ -27,93 +430,375 @@ fn comparator_as_str(cmp: Comparator) -> &'static str {
         GreaterOrEqual => ">=",
         Equal => "==",
         NotEqual => "!=",
+        ApproxEqual(_) => "~=",
+        NotApproxEqual(_) => "!~=",
     }
 }
 
-fn parse_comparison(s: &str, cmp: Comparator) -> Result<Option<Comparison>> {
-    let cmp_str = comparator_as_str(cmp);
-    if s.contains(cmp_str) {
-        let mut vals = s.split(cmp_str);
-        if let Some(lhs) = vals.next() {
-            if let Some(rhs) = vals.next() {
-                if vals.next().is_none() {
-                    return Ok(Some(Comparison {
-                        left: Source::from_str(lhs)?,
-                        cmp,
-                        right: Source::from_str(rhs)?,
-                    }));
-                }
+impl FromStr for Source {
+    type Err = Error;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut parser = Parser::new(s)?;
+        if parser.tokens.is_empty() {
+            return Err(Error::new(ErrorKind::InvalidInput, "empty str"));
+        }
+        let src = parser.sum()?;
+        parser.finish()?;
+        Ok(src)
+    }
+}
+
+/// Parse a single (non-arithmetic) source.
+fn atom(s: &str) -> Result<Source> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(Error::new(ErrorKind::InvalidInput, "empty str"));
+    }
+    if s.contains('\'') {
+        return Ok(Source::Const(Value::Text(s.replace('\'', ""))));
+    }
+    if let Ok(v) = s.parse::<i64>() {
+        return Ok(Source::Const(v.into()));
+    }
+    if let Ok(v) = s.parse::<f64>() {
+        return Ok(Source::Const(v.into()));
+    }
+    let s = s.to_lowercase();
+    let prefixes = [
+        ("timeout.", Source::Timeout as fn(String) -> Source),
+        ("in.", Source::In),
+        ("out.", Source::Out),
+        ("mem.", Source::Mem),
+        ("setpoint.", Source::Setpoint),
+    ];
+    for (prefix, src) in prefixes {
+        if let Some(id) = s.strip_prefix(prefix) {
+            if id.trim().is_empty() {
+                return Err(Error::new(ErrorKind::InvalidInput, "invalid identifier"));
             }
+            return Ok(src(id.into()));
         }
-        Err(Error::new(
-            ErrorKind::InvalidInput,
-            format!("invalid number of arguments for comparator {cmp_str}"),
-        ))
+    }
+    match s.as_str() {
+        "true" => Ok(Source::Const(true.into())),
+        "false" => Ok(Source::Const(false.into())),
+        _ => Err(Error::new(ErrorKind::InvalidInput, "invalid source")),
+    }
+}
+
+impl fmt::Display for Comparator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(comparator_as_str(*self))
+    }
+}
+
+/// Binding strength of an arithmetic operand.
+fn precedence(src: &Source) -> u8 {
+    match src {
+        Source::Expr(e) => match **e {
+            Expr::Add(..) | Expr::Sub(..) => 1,
+            Expr::Mul(..) | Expr::Div(..) => 2,
+            _ => 3,
+        },
+        _ => 3,
+    }
+}
+
+/// Print an operand in parentheses if it doesn't bind stronger than `min`.
+fn grouped_if(src: &Source, min: u8) -> String {
+    if precedence(src) <= min {
+        format!("({src})")
     } else {
-        // Ignore input strings without a comparator
-        Ok(None)
+        src.to_string()
     }
 }
 
-impl FromStr for Source {
-    type Err = Error;
-    fn from_str(s: &str) -> Result<Self> {
-        let s = s.trim();
-        if s.is_empty() {
-            return Err(Error::new(ErrorKind::InvalidInput, "empty str"));
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use crate::Expr::*;
+        match self {
+            Add(a, b) => write!(f, "{} + {}", grouped_if(a, 0), grouped_if(b, 1)),
+            Sub(a, b) => write!(f, "{} - {}", grouped_if(a, 0), grouped_if(b, 1)),
+            Mul(a, b) => write!(f, "{} * {}", grouped_if(a, 1), grouped_if(b, 2)),
+            Div(a, b) => write!(f, "{} / {}", grouped_if(a, 1), grouped_if(b, 2)),
+            Min(a, b) => write!(f, "min({a}, {b})"),
+            Max(a, b) => write!(f, "max({a}, {b})"),
+            Abs(a) => write!(f, "abs({a})"),
         }
-        if s.contains('\'') {
-            return Ok(Source::Const(Value::Text(s.replace('\'', ""))));
+    }
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use crate::Source::*;
+        match self {
+            In(id) => write!(f, "in.{id}"),
+            Out(id) => write!(f, "out.{id}"),
+            Mem(id) => write!(f, "mem.{id}"),
+            Setpoint(id) => write!(f, "setpoint.{id}"),
+            Timeout(id) => write!(f, "timeout.{id}"),
+            Const(Value::Bit(b)) => write!(f, "{b}"),
+            Const(Value::Integer(i)) => write!(f, "{i}"),
+            // The debug output keeps the decimal point
+            Const(Value::Decimal(d)) => write!(f, "{d:?}"),
+            Const(Value::Text(t)) => write!(f, "'{t}'"),
+            Const(v) => write!(f, "{v:?}"),
+            Expr(e) => write!(f, "{e}"),
         }
-        if let Ok(v) = s.parse::<i64>() {
-            return Ok(Source::Const(v.into()));
+    }
+}
+
+impl fmt::Display for Tolerance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Tolerance::Absolute(epsilon) => write!(f, "{epsilon}"),
+            Tolerance::Percent(percent) => write!(f, "{percent}%"),
         }
-        if let Ok(v) = s.parse::<f64>() {
-            return Ok(Source::Const(v.into()));
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.left, self.cmp, self.right)?;
+        match self.cmp {
+            Comparator::ApproxEqual(tol) | Comparator::NotApproxEqual(tol) => {
+                write!(f, " +/- {tol}")
+            }
+            _ => Ok(()),
         }
-        let s = s.to_lowercase();
-        if s.contains("timeout.") {
-            let res = s.split("timeout.").collect::<Vec<&str>>();
-            if res.len() < 2 || res[1].is_empty() {
-                return Err(Error::new(ErrorKind::InvalidInput, "invalid identifier"));
+    }
+}
+
+/// A comparison that is written as a single source.
+fn is_shorthand(c: &Comparison) -> bool {
+    c.cmp == Comparator::Equal
+        && c.right == Source::Const(Value::Bit(true))
+        && !matches!(c.left, Source::Const(_))
+}
+
+impl fmt::Display for BoolExpr<Comparison> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use crate::BoolExpr::*;
+        let grouped = |expr: &BoolExpr<Comparison>| format!("({expr})");
+        match self {
+            True => f.write_str("true"),
+            False => f.write_str("false"),
+            Eval(c) if is_shorthand(c) => write!(f, "{}", c.left),
+            Eval(c) => write!(f, "{c}"),
+            Not(x) => match x.as_ref() {
+                True | False | Not(_) => write!(f, "!{x}"),
+                Eval(c) if is_shorthand(c) => write!(f, "!{x}"),
+                _ => write!(f, "!{}", grouped(x)),
+            },
+            And(a, b) => {
+                let a = match a.as_ref() {
+                    Or(..) => grouped(a),
+                    _ => a.to_string(),
+                };
+                let b = match b.as_ref() {
+                    Or(..) | And(..) => grouped(b),
+                    _ => b.to_string(),
+                };
+                write!(f, "{a} && {b}")
+            }
+            Or(a, b) => {
+                let b = match b.as_ref() {
+                    Or(..) => grouped(b),
+                    _ => b.to_string(),
+                };
+                write!(f, "{a} || {b}")
             }
-            return Ok(Source::Timeout(res[1].into()));
         }
-        if s.contains("in.") {
-            let res = s.split("in.").collect::<Vec<&str>>();
-            if res.len() < 2 || res[1].is_empty() {
-                return Err(Error::new(ErrorKind::InvalidInput, "invalid identifier"));
+    }
+}
+
+impl fmt::Display for Trigger {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Trigger::Level => "level",
+            Trigger::Rising => "rising",
+            Trigger::Falling => "falling",
+            Trigger::Changed => "changed",
+        })
+    }
+}
+
+/// `id: condition => action, ...` or `id: rising(condition) => ...`
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.trigger {
+            Trigger::Level => write!(f, "{}: {}", self.id, self.condition)?,
+            trigger => write!(f, "{}: {trigger}({})", self.id, self.condition)?,
+        }
+        if !self.actions.is_empty() {
+            write!(f, " => {}", self.actions.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Key-value pairs in a canonical order
+fn sorted<V>(map: &HashMap<String, V>) -> Vec<(&String, &V)> {
+    let mut pairs: Vec<_> = map.iter().collect();
+    pairs.sort_by(|a, b| a.0.cmp(b.0));
+    pairs
+}
+
+/// `id: out.x = source, ...` followed by the timing, if any
+///
+/// All assignments are sorted by their kind and ID.
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut assignments = vec![];
+        for (prefix, map) in [
+            ("out", &self.outputs),
+            ("mem", &self.memory),
+            ("setpoint", &self.setpoints),
+        ] {
+            for (id, src) in sorted(map) {
+                assignments.push(format!("{prefix}.{id} = {src}"));
             }
-            return Ok(Source::In(res[1].into()));
         }
-        if s.contains("out.") {
-            let res = s.split("out.").collect::<Vec<&str>>();
-            if res.len() < 2 || res[1].is_empty() {
-                return Err(Error::new(ErrorKind::InvalidInput, "invalid identifier"));
+        for (id, timeout) in sorted(&self.timeouts) {
+            match timeout {
+                Some(t) => assignments.push(format!("timeout.{id} = {t:?}")),
+                None => assignments.push(format!("timeout.{id} = none")),
             }
-            return Ok(Source::Out(res[1].into()));
         }
-        if s.contains("mem.") {
-            let res = s.split("mem.").collect::<Vec<&str>>();
-            if res.len() < 2 || res[1].is_empty() {
-                return Err(Error::new(ErrorKind::InvalidInput, "invalid identifier"));
+        for (id, action) in sorted(&self.controllers) {
+            if action.reset {
+                assignments.push(format!("controller.{id}.reset"));
+            }
+            if let Some(active) = action.active {
+                assignments.push(format!("controller.{id}.active = {active}"));
+            }
+            if let Some(mode) = action.mode {
+                assignments.push(format!("controller.{id}.mode = {mode:?}"));
             }
-            return Ok(Source::Mem(res[1].into()));
         }
-        if s.contains("setpoint.") {
-            let res = s.split("setpoint.").collect::<Vec<&str>>();
-            if res.len() < 2 || res[1].is_empty() {
-                return Err(Error::new(ErrorKind::InvalidInput, "invalid identifier"));
+        for (id, action) in sorted(&self.counters) {
+            match action {
+                CounterAction::Reset => assignments.push(format!("counter.{id}.reset")),
+                CounterAction::Preset { run_time, cycles } => {
+                    assignments.push(format!("counter.{id}.preset = ({run_time:?}, {cycles})"));
+                }
             }
-            return Ok(Source::Setpoint(res[1].into()));
         }
-        if s.contains("true") {
-            return Ok(Source::Const(true.into()));
+        for (id, action) in sorted(&self.timers) {
+            let action = match action {
+                TimerAction::Start => "start",
+                TimerAction::Stop => "stop",
+                TimerAction::Reset => "reset",
+            };
+            assignments.push(format!("timer.{id}.{action}"));
+        }
+        write!(f, "{}: {}", self.id, assignments.join(", "))?;
+
+        let mut timing = vec![];
+        if let Some(t) = self.on_delay {
+            timing.push(format!("on_delay = {t:?}"));
+        }
+        if let Some(t) = self.off_delay {
+            timing.push(format!("off_delay = {t:?}"));
         }
-        if s.contains("false") {
-            return Ok(Source::Const(false.into()));
+        if let Some(t) = self.pulse {
+            timing.push(format!("pulse = {t:?}"));
+        }
+        if !self.release.is_empty() {
+            timing.push(format!("release = {}", self.release.join(" ")));
+        }
+        if !timing.is_empty() {
+            write!(f, " [{}]", timing.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// (De)serialization of conditions as text
+///
+/// A condition is serialized as text if it can be parsed back
+/// into the same expression, otherwise the structured representation
+/// is used. Both representations are accepted when deserializing.
+///
+/// ```rust
+/// # #[cfg(feature = "serde")]
+/// # {
+/// use msr_legacy::{BoolExpr, Comparison};
+///
+/// #[derive(serde::Serialize, serde::Deserialize)]
+/// struct Interlock {
+///     #[serde(with = "msr_legacy::parser::condition_text")]
+///     condition: BoolExpr<Comparison>,
+/// }
+///
+/// let interlock: Interlock = serde_json::from_str(r#"{"condition":"in.p > 6.0"}"#).unwrap();
+/// assert_eq!(
+///     serde_json::to_string(&interlock).unwrap(),
+///     r#"{"condition":"in.p > 6.0"}"#
+/// );
+/// # }
+/// ```
+#[cfg(feature = "serde")]
+pub mod condition_text {
+    use super::*;
+    use serde::{
+        de::{self, value::EnumAccessDeserializer, value::MapAccessDeserializer},
+        Deserialize, Deserializer, Serialize, Serializer,
+    };
+
+    pub fn serialize<S>(
+        expr: &BoolExpr<Comparison>,
+        serializer: S,
+    ) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let text = expr.to_string();
+        if parse(&text).ok().as_ref() == Some(expr) {
+            serializer.serialize_str(&text)
+        } else {
+            expr.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D>(
+        deserializer: D,
+    ) -> std::result::Result<BoolExpr<Comparison>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        deserializer.deserialize_any(ConditionVisitor)
+    }
+
+    struct ConditionVisitor;
+
+    impl<'de> de::Visitor<'de> for ConditionVisitor {
+        type Value = BoolExpr<Comparison>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a condition as text or a structured expression")
+        }
+
+        fn visit_str<E>(self, s: &str) -> std::result::Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            parse(s).map_err(E::custom)
+        }
+
+        fn visit_map<A>(self, map: A) -> std::result::Result<Self::Value, A::Error>
+        where
+            A: de::MapAccess<'de>,
+        {
+            BoolExpr::deserialize(MapAccessDeserializer::new(map))
+        }
+
+        fn visit_enum<A>(self, data: A) -> std::result::Result<Self::Value, A::Error>
+        where
+            A: de::EnumAccess<'de>,
+        {
+            BoolExpr::deserialize(EnumAccessDeserializer::new(data))
         }
-        unimplemented!()
     }
 }
 
@@ -250,4 +935,338 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn parse_approx_cmp() {
+        use crate::Comparator::*;
+        use crate::Source::*;
+        let tests = vec![
+            ("in.x ~= 5.0 +/- 0.1", ApproxEqual(Tolerance::Absolute(0.1))),
+            (
+                "in.x !~= 5.0 +/- 2%",
+                NotApproxEqual(Tolerance::Percent(2.0)),
+            ),
+            ("in.x~=5.0+/-0", ApproxEqual(Tolerance::Absolute(0.0))),
+        ];
+        for (s, cmp) in tests {
+            assert_eq!(
+                Comparison::from_str(s).unwrap(),
+                Comparison {
+                    left: In("x".into()),
+                    cmp,
+                    right: Const(5.0.into())
+                }
+            );
+        }
+        let msg = |s: &str| parse(s).unwrap_err().to_string();
+        assert_eq!(
+            msg("in.x ~= 5.0"),
+            "unexpected end of expression at position 11"
+        );
+        assert_eq!(
+            msg("in.x ~= 5.0 && in.y"),
+            "expected +/- instead of && at position 12"
+        );
+        assert_eq!(
+            msg("in.x ~= 5.0 +/- -1"),
+            "invalid tolerance -1 at position 16"
+        );
+        assert_eq!(
+            msg("in.x ~= 5.0 +/- in.t"),
+            "invalid tolerance in.t at position 16"
+        );
+        assert_eq!(msg("in.x ~ 5.0"), "unexpected character ~ at position 5");
+        // The tolerance binds to the comparison
+        assert_eq!(
+            parse("in.x ~= in.y + 1 +/- 1% && in.z").unwrap(),
+            BoolExpr::And(
+                Box::new(
+                    In("x".into())
+                        .cmp_approx_eq(
+                            crate::Expr::Add(In("y".into()), Const(1.into())).into(),
+                            Tolerance::Percent(1.0)
+                        )
+                        .into()
+                ),
+                Box::new(In("z".into()).cmp_eq(true.into()).into()),
+            )
+        );
+    }
+
+    #[test]
+    fn reject_unknown_src() {
+        assert!(Source::from_str("foo").is_err());
+        assert!(Source::from_str("trueish").is_err());
+        assert_eq!(
+            Source::from_str("main.x").unwrap_err().kind(),
+            ErrorKind::InvalidInput
+        );
+    }
+
+    #[test]
+    fn parse_bool_expr() {
+        use crate::BoolExpr::*;
+        let x = || Source::In("x".into());
+        let y = || Source::Out("y".into());
+        let tests = vec![
+            ("true", True),
+            ("!false", !False),
+            ("in.x > 5", Eval(x().cmp_gt(5.into()))),
+            ("in.x>5.0", Eval(x().cmp_gt(5.0.into()))),
+            ("out.y", Eval(y().cmp_eq(true.into()))),
+            ("!out.y", !Eval(y().cmp_eq(true.into()))),
+            (
+                "in.x != 'a b' || out.y && in.x <= -1",
+                Or(
+                    Box::new(Eval(x().cmp_ne(Value::Text("a b".into()).into()))),
+                    Box::new(And(
+                        Box::new(Eval(y().cmp_eq(true.into()))),
+                        Box::new(Eval(x().cmp_le((-1).into()))),
+                    )),
+                ),
+            ),
+            (
+                "!(in.x < 1 || ((out.y)))",
+                !Or(
+                    Box::new(Eval(x().cmp_lt(1.into()))),
+                    Box::new(Eval(y().cmp_eq(true.into()))),
+                ),
+            ),
+            (
+                "in.x >= 1 && out.y && timeout.t",
+                And(
+                    Box::new(And(
+                        Box::new(Eval(x().cmp_ge(1.into()))),
+                        Box::new(Eval(y().cmp_eq(true.into()))),
+                    )),
+                    Box::new(Eval(Source::Timeout("t".into()).cmp_eq(true.into()))),
+                ),
+            ),
+        ];
+        for (s, expr) in tests {
+            assert_eq!(parse(s).unwrap(), expr, "{s}");
+        }
+    }
+
+    #[test]
+    fn report_invalid_bool_expr() {
+        let msg = |s: &str| parse(s).unwrap_err().to_string();
+        assert_eq!(msg(""), "empty expression");
+        assert_eq!(
+            msg(" in.x > "),
+            "unexpected end of expression at position 8"
+        );
+        assert_eq!(msg("in.x & in.y"), "unexpected character & at position 5");
+        assert_eq!(msg("(in.x"), "unexpected end of expression at position 5");
+        assert_eq!(msg("in.x in.y"), "unexpected token in.y at position 5");
+        assert_eq!(
+            msg("in.x > && in.y"),
+            "expected an operand instead of && at position 7"
+        );
+        assert_eq!(msg("in.x == 'a"), "unterminated text at position 8");
+        assert_eq!(
+            msg("foo && in.x"),
+            "invalid operand foo at position 0: invalid source"
+        );
+    }
+
+    #[test]
+    fn print_bool_expr() {
+        let tests = vec![
+            ("true", "true"),
+            ("!!out.y", "!!out.y"),
+            ("in.x==5.0", "in.x == 5.0"),
+            ("in.x == 'a b'", "in.x == 'a b'"),
+            ("!(in.x > 3)", "!(in.x > 3)"),
+            ("in.x == true", "in.x"),
+            ("true == true", "true == true"),
+            ("(in.a && in.b) && in.c", "in.a && in.b && in.c"),
+            ("in.a && (in.b && in.c)", "in.a && (in.b && in.c)"),
+            ("in.a || in.b && in.c", "in.a || in.b && in.c"),
+            ("(in.a || in.b) && in.c", "(in.a || in.b) && in.c"),
+            ("in.a || (in.b || in.c)", "in.a || (in.b || in.c)"),
+            ("!(in.a || in.b)", "!(in.a || in.b)"),
+            ("in.x~=5.0+/-0.1", "in.x ~= 5.0 +/- 0.1"),
+            ("!(in.x !~= in.y +/- 2.5%)", "!(in.x !~= in.y +/- 2.5%)"),
+        ];
+        for (s, printed) in tests {
+            let expr = parse(s).unwrap();
+            assert_eq!(expr.to_string(), printed);
+            // Round trip
+            assert_eq!(parse(printed).unwrap(), expr);
+        }
+    }
+
+    #[test]
+    fn parse_arithmetic_src() {
+        let a = || Source::In("a".into());
+        let b = || Source::In("b".into());
+        let c = || Source::Mem("too-hot".into());
+        let tests: Vec<(&str, Source)> = vec![
+            ("in.a - in.b", Expr::Sub(a(), b()).into()),
+            ("in.a -in.b", Expr::Sub(a(), b()).into()),
+            ("in.a - -5", Expr::Sub(a(), (-5).into()).into()),
+            ("mem.too-hot*2", Expr::Mul(c(), 2.into()).into()),
+            (
+                "in.a + in.b * 2.5",
+                Expr::Add(a(), Expr::Mul(b(), 2.5.into()).into()).into(),
+            ),
+            (
+                "(in.a + in.b) / 2",
+                Expr::Div(Expr::Add(a(), b()).into(), 2.into()).into(),
+            ),
+            ("ABS(in.a)", Expr::Abs(a()).into()),
+            (
+                "max(in.a, in.b, 0)",
+                Expr::Max(Expr::Max(a(), b()).into(), 0.into()).into(),
+            ),
+            (
+                "min(abs(in.a - in.b), 10)",
+                Expr::Min(Expr::Abs(Expr::Sub(a(), b()).into()).into(), 10.into()).into(),
+            ),
+        ];
+        for (s, src) in tests {
+            assert_eq!(Source::from_str(s).unwrap(), src, "{s}");
+        }
+        assert!(Source::from_str("in.a +").is_err());
+        assert!(Source::from_str("in.a in.b").is_err());
+        assert!(Source::from_str("abs(in.a, in.b)").is_err());
+        assert!(Source::from_str("max(in.a)").is_err());
+        assert!(Source::from_str("sqrt(in.a)").is_err());
+    }
+
+    #[test]
+    fn parse_arithmetic_bool_expr() {
+        use crate::BoolExpr::*;
+        let diff = || Source::from(Expr::Sub(Source::In("a".into()), Source::In("b".into())));
+        assert_eq!(
+            parse("in.a - in.b > 5.0").unwrap(),
+            Eval(diff().cmp_gt(5.0.into()))
+        );
+        assert_eq!(
+            parse("(in.a - in.b) > 5.0 && (in.c)").unwrap(),
+            And(
+                Box::new(Eval(diff().cmp_gt(5.0.into()))),
+                Box::new(Eval(Source::In("c".into()).cmp_eq(true.into()))),
+            )
+        );
+        assert_eq!(
+            parse("((in.a - in.b > 5.0 || in.c))").unwrap(),
+            Or(
+                Box::new(Eval(diff().cmp_gt(5.0.into()))),
+                Box::new(Eval(Source::In("c".into()).cmp_eq(true.into()))),
+            )
+        );
+    }
+
+    #[test]
+    fn print_arithmetic() {
+        let tests = vec![
+            ("in.a-x - in.b", "in.a-x - in.b"),
+            ("in.a - (in.b - in.c)", "in.a - (in.b - in.c)"),
+            ("(in.a - in.b) - in.c", "in.a - in.b - in.c"),
+            ("(in.a + in.b) * in.c", "(in.a + in.b) * in.c"),
+            ("in.a * (in.b / in.c)", "in.a * (in.b / in.c)"),
+            ("in.a / 2 + 1", "in.a / 2 + 1"),
+            ("MAX(in.a,abs(in.b),0.5)", "max(max(in.a, abs(in.b)), 0.5)"),
+        ];
+        for (s, printed) in tests {
+            let src = Source::from_str(s).unwrap();
+            assert_eq!(src.to_string(), printed);
+            assert_eq!(Source::from_str(printed).unwrap(), src);
+        }
+        let expr = parse("in.a - in.b >= in.c * 2").unwrap();
+        assert_eq!(expr.to_string(), "in.a - in.b >= in.c * 2");
+    }
+
+    #[test]
+    fn print_rule() {
+        let mut rule = Rule {
+            id: "overheated".into(),
+            condition: parse("in.temp > 80.0 && !out.fan").unwrap(),
+            actions: vec!["alarm".into(), "fan".into()],
+            trigger: Trigger::Level,
+        };
+        assert_eq!(
+            rule.to_string(),
+            "overheated: in.temp > 80.0 && !out.fan => alarm, fan"
+        );
+        rule.trigger = Trigger::Rising;
+        rule.actions.clear();
+        assert_eq!(
+            rule.to_string(),
+            "overheated: rising(in.temp > 80.0 && !out.fan)"
+        );
+    }
+
+    #[test]
+    fn print_action() {
+        let mut action = Action {
+            id: "start".into(),
+            ..Default::default()
+        };
+        assert_eq!(action.to_string(), "start: ");
+        action.outputs.insert("pump".into(), true.into());
+        action
+            .outputs
+            .insert("fan".into(), Source::In("fan".into()));
+        action.memory.insert("n".into(), 1.into());
+        action.setpoints.insert("t".into(), 20.0.into());
+        action
+            .timeouts
+            .insert("a".into(), Some(Duration::from_millis(1500)));
+        action.timeouts.insert("b".into(), None);
+        action.controllers.insert(
+            "pid".into(),
+            ControllerAction {
+                reset: true,
+                active: Some(false),
+                mode: None,
+            },
+        );
+        action.counters.insert("c".into(), CounterAction::Reset);
+        action.timers.insert("t".into(), TimerAction::Start);
+        action.on_delay = Some(Duration::from_secs(2));
+        action.release = vec!["stop".into()];
+        assert_eq!(
+            action.to_string(),
+            "start: out.fan = in.fan, out.pump = true, mem.n = 1, setpoint.t = 20.0, \
+             timeout.a = 1.5s, timeout.b = none, controller.pid.reset, \
+             controller.pid.active = false, counter.c.reset, timer.t.start \
+             [on_delay = 2s, release = stop]"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_conditions_as_text() {
+        let rule = Rule {
+            id: "r".into(),
+            condition: parse("in.x ~= 5.0 +/- 1% || !mem.y").unwrap(),
+            actions: vec![],
+            trigger: Trigger::Level,
+        };
+        let json = serde_json::to_string(&rule).unwrap();
+        assert_eq!(
+            json,
+            r#"{"id":"r","condition":"in.x ~= 5.0 +/- 1% || !mem.y","actions":[],"trigger":"Level"}"#
+        );
+        assert_eq!(serde_json::from_str::<Rule>(&json).unwrap(), rule);
+
+        // Binary constants can't be written as text
+        let rule = Rule {
+            condition: Source::In("x".into())
+                .cmp_eq(Value::Bin(vec![1]).into())
+                .into(),
+            ..rule
+        };
+        let json = serde_json::to_string(&rule).unwrap();
+        assert!(json.contains(r#""condition":{"Eval""#));
+        assert_eq!(serde_json::from_str::<Rule>(&json).unwrap(), rule);
+
+        let err = serde_json::from_str::<Rule>(r#"{"id":"r","condition":"in.x >"}"#)
+            .unwrap_err()
+            .to_string();
+        assert!(err.starts_with("unexpected end of expression"), "{err}");
+    }
 }
@@ -0,0 +1,305 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::{Duration, SystemTime};
+//! use msr_legacy::{persistence::*, SyncRuntime, SystemState};
+//!
+//! let rt = SyncRuntime::default();
+//! let mut persistence = Persistence::new(
+//!     PersistenceConfig {
+//!         interval: Duration::from_secs(60),
+//!         max_age: Some(Duration::from_secs(600)),
+//!     },
+//!     MemoryStorage::default(),
+//! );
+//!
+//! // Warm start
+//! let mut state = match persistence.restore(&rt, SystemTime::now()).unwrap() {
+//!     Restored::State(state) => *state,
+//!     Restored::Stale(_) | Restored::Missing => SystemState::default(),
+//! };
+//!
+//! // Cyclic execution
+//! let delta_t = Duration::from_secs(1);
+//! for _ in 0..60 {
+//!     // state = rt.next((&state, &delta_t)).unwrap();
+//!     persistence.next(&rt, &state, &delta_t, SystemTime::now()).unwrap();
+//! }
+//! assert!(persistence.storage().load().unwrap().is_some());
+//! ```
+
+use super::{SyncRuntime, SystemState};
+use std::{
+    io::Result,
+    time::{Duration, SystemTime},
+};
+
+/// A persisted state
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StoredState {
+    /// The time when the state was saved
+    pub saved_at: SystemTime,
+    /// The snapshot of the state (see [SyncRuntime::snapshot])
+    pub state: SystemState,
+}
+
+/// A storage that keeps the most recent state
+pub trait StateStorage {
+    /// Replace the stored state.
+    fn save(&mut self, state: &StoredState) -> Result<()>;
+    /// Load the stored state if there is any.
+    fn load(&mut self) -> Result<Option<StoredState>>;
+}
+
+/// A storage that keeps the state in memory, e.g. for tests
+#[derive(Debug, Clone, Default)]
+pub struct MemoryStorage(Option<StoredState>);
+
+impl StateStorage for MemoryStorage {
+    fn save(&mut self, state: &StoredState) -> Result<()> {
+        self.0 = Some(state.clone());
+        Ok(())
+    }
+    fn load(&mut self) -> Result<Option<StoredState>> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A storage that keeps the state in a YAML file
+///
+/// The file is replaced atomically, so a power failure
+/// while saving does not corrupt the previous state.
+/// Requires the `file-storage` feature.
+#[cfg(feature = "file-storage")]
+#[derive(Debug, Clone)]
+pub struct YamlFileStorage {
+    path: std::path::PathBuf,
+}
+
+#[cfg(feature = "file-storage")]
+impl YamlFileStorage {
+    /// Create a storage for a file path.
+    pub fn new(path: impl Into<std::path::PathBuf>) -> Self {
+        YamlFileStorage { path: path.into() }
+    }
+}
+
+#[cfg(feature = "file-storage")]
+impl StateStorage for YamlFileStorage {
+    fn save(&mut self, state: &StoredState) -> Result<()> {
+        use std::io::{Error, ErrorKind};
+        let yaml =
+            serde_yaml::to_string(state).map_err(|err| Error::new(ErrorKind::InvalidData, err))?;
+        msr_core::fs::write_atomically(&self.path, yaml)
+    }
+    fn load(&mut self) -> Result<Option<StoredState>> {
+        use std::io::{Error, ErrorKind};
+        match std::fs::read_to_string(&self.path) {
+            Ok(yaml) => serde_yaml::from_str(&yaml)
+                .map(Some)
+                .map_err(|err| Error::new(ErrorKind::InvalidData, err)),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+}
+
+/// Persistence configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct PersistenceConfig {
+    /// Time between two saves
+    pub interval: Duration,
+    /// Stored states that are older are not restored
+    pub max_age: Option<Duration>,
+}
+
+/// The result of a restore
+#[derive(Debug, Clone, PartialEq)]
+pub enum Restored {
+    /// The stored state
+    State(Box<SystemState>),
+    /// The stored state is too old (with its age)
+    Stale(Duration),
+    /// There is no stored state
+    Missing,
+}
+
+/// Periodically saves the state of a runtime to restore it
+/// after a restart, e.g. to keep the integral portions
+/// of the PID controllers across a brief power cycle.
+#[derive(Debug)]
+pub struct Persistence<S> {
+    cfg: PersistenceConfig,
+    storage: S,
+    since_save: Duration,
+}
+
+impl<S: StateStorage> Persistence<S> {
+    /// Create a new persistence instance.
+    pub fn new(cfg: PersistenceConfig, storage: S) -> Self {
+        Persistence {
+            cfg,
+            storage,
+            since_save: Duration::ZERO,
+        }
+    }
+
+    /// The underlying storage.
+    pub fn storage(&mut self) -> &mut S {
+        &mut self.storage
+    }
+
+    /// Load the stored state for a warm start.
+    ///
+    /// The state is restored as by [SyncRuntime::restore].
+    /// A state that was saved in the future (e.g. because
+    /// the clock was adjusted) is considered to be fresh.
+    pub fn restore(&mut self, rt: &SyncRuntime, now: SystemTime) -> Result<Restored> {
+        let Some(stored) = self.storage.load()? else {
+            return Ok(Restored::Missing);
+        };
+        let age = now.duration_since(stored.saved_at).unwrap_or_default();
+        if self.cfg.max_age.map(|max| age > max).unwrap_or(false) {
+            return Ok(Restored::Stale(age));
+        }
+        Ok(Restored::State(Box::new(rt.restore(stored.state))))
+    }
+
+    /// Save the state if the interval has elapsed.
+    ///
+    /// Returns whether the state was saved.
+    pub fn next(
+        &mut self,
+        rt: &SyncRuntime,
+        state: &SystemState,
+        dt: &Duration,
+        now: SystemTime,
+    ) -> Result<bool> {
+        self.since_save += *dt;
+        if self.since_save < self.cfg.interval {
+            return Ok(false);
+        }
+        self.save(rt, state, now)?;
+        Ok(true)
+    }
+
+    /// Save the state immediately, e.g. before a shutdown.
+    pub fn save(&mut self, rt: &SyncRuntime, state: &SystemState, now: SystemTime) -> Result<()> {
+        self.since_save = Duration::ZERO;
+        self.storage.save(&StoredState {
+            saved_at: now,
+            state: rt.snapshot(state),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::{pid, ControllerState, Loop, PureController};
+
+    fn runtime() -> SyncRuntime {
+        SyncRuntime {
+            loops: vec![Loop {
+                id: "pid".into(),
+                inputs: vec!["x".into()],
+                outputs: vec!["y".into()],
+                controller: crate::ControllerConfig::Pid(pid::PidConfig {
+                    k_i: 1.0,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    fn persistence() -> Persistence<MemoryStorage> {
+        Persistence::new(
+            PersistenceConfig {
+                interval: Duration::from_secs(3),
+                max_age: Some(Duration::from_secs(60)),
+            },
+            MemoryStorage::default(),
+        )
+    }
+
+    #[test]
+    fn save_periodically() {
+        let rt = runtime();
+        let mut p = persistence();
+        let dt = Duration::from_secs(1);
+        let now = SystemTime::UNIX_EPOCH;
+        let mut state = SystemState::default();
+        state.io.inputs.insert("x".into(), (-1.0).into());
+        let mut saved = vec![];
+        for _ in 0..6 {
+            state = rt.next((&state, &dt)).unwrap();
+            saved.push(p.next(&rt, &state, &dt, now).unwrap());
+        }
+        assert_eq!(saved, [false, false, true, false, false, true]);
+        let stored = p.storage().load().unwrap().unwrap();
+        assert!(stored.state.io.inputs.is_empty());
+        assert_eq!(stored.state.controllers, state.controllers);
+    }
+
+    #[test]
+    fn restore_fresh_states_only() {
+        let rt = runtime();
+        let mut p = persistence();
+        let t0 = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        assert_eq!(p.restore(&rt, t0).unwrap(), Restored::Missing);
+
+        let mut state = SystemState::default();
+        state.controllers.insert(
+            "pid".into(),
+            ControllerState::Pid(pid::PidState {
+                i: 5.0,
+                ..Default::default()
+            }),
+        );
+        state
+            .controllers
+            .insert("removed".into(), state.controllers["pid"].clone());
+        p.save(&rt, &state, t0).unwrap();
+
+        let restored = p.restore(&rt, t0 + Duration::from_secs(60)).unwrap();
+        let Restored::State(restored) = restored else {
+            panic!("unexpected result: {restored:?}");
+        };
+        assert_eq!(restored.controllers.len(), 1);
+        assert_eq!(restored.controllers["pid"], state.controllers["pid"]);
+        assert_eq!(
+            p.restore(&rt, t0 + Duration::from_secs(61)).unwrap(),
+            Restored::Stale(Duration::from_secs(61))
+        );
+        // The clock was adjusted
+        assert!(matches!(
+            p.restore(&rt, SystemTime::UNIX_EPOCH).unwrap(),
+            Restored::State(_)
+        ));
+    }
+
+    #[cfg(feature = "file-storage")]
+    #[test]
+    fn save_to_file() {
+        let dir = std::env::temp_dir().join(format!("msr-persistence-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("state.yaml");
+        let mut storage = YamlFileStorage::new(&path);
+        assert_eq!(storage.load().unwrap(), None);
+        let mut state = SystemState::default();
+        state.state_machines.insert("fsm".into(), "busy".into());
+        let stored = StoredState {
+            saved_at: SystemTime::UNIX_EPOCH + Duration::from_millis(1500),
+            state,
+        };
+        storage.save(&stored).unwrap();
+        assert_eq!(storage.load().unwrap(), Some(stored));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -53,6 +53,7 @@ pub struct Pid {
 
 /// Internal PID controller state
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PidState {
     /// Current target
     pub target: f64,
@@ -60,10 +61,22 @@ pub struct PidState {
     pub prev_value: Option<f64>,
     /// Proportional portion
     pub p: f64,
-    /// Integral portion (error sum)
+    /// Integral portion (error sum, or its increment in velocity form)
     pub i: f64,
     /// Derivative portion
     pub d: f64,
+    /// Feedforward portion
+    pub ff: f64,
+    /// Current value of the measured disturbance
+    pub disturbance: Option<f64>,
+    /// Measured disturbance of the previous step
+    pub prev_disturbance: Option<f64>,
+    /// Control error of the previous step
+    pub prev_error: Option<f64>,
+    /// Output of the previous step
+    pub output: Option<f64>,
+    /// Output change that was suppressed by the deadband
+    pub suppressed: f64,
 }
 
 impl Default for PidState {
@@ -74,6 +87,12 @@ impl Default for PidState {
             p: 0.0,
             i: 0.0,
             d: 0.0,
+            ff: 0.0,
+            disturbance: None,
+            prev_disturbance: None,
+            prev_error: None,
+            output: None,
+            suppressed: 0.0,
         }
     }
 }
@@ -91,16 +110,39 @@ impl Pid {
     pub fn set_target(&mut self, target: f64) {
         self.state.target = target;
     }
+    /// Set the current value of the measured disturbance.
+    pub fn set_disturbance(&mut self, disturbance: f64) {
+        self.state.disturbance = Some(disturbance);
+    }
     /// Reset the internal controller state.
     pub fn reset(&mut self) {
         self.state = PidState::default();
         self.state.target = self.cfg.default_target;
     }
+    /// Let the controller follow an externally set output
+    /// (see [PidState::track]).
+    pub fn track(&mut self, output: f64) {
+        self.state.track(output);
+    }
+}
+
+impl PidState {
+    /// Re-initialize the integral portion so that the
+    /// last output equals the given value.
+    ///
+    /// Call this while the actuator is driven manually
+    /// to switch back to automatic control without a bump.
+    pub fn track(&mut self, output: f64) {
+        self.i = output - self.p - self.d - self.ff;
+        self.output = Some(output);
+        self.suppressed = 0.0;
+    }
 }
 
 /// PID Configuration
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct PidConfig {
     /// Proportional coefficient
     pub k_p: f64,
@@ -122,6 +164,53 @@ pub struct PidConfig {
     pub i_min: Option<f64>,
     /// Maximum integral portion
     pub i_max: Option<f64>,
+    /// Feedforward of a measured disturbance
+    pub feedforward: Option<FeedforwardConfig>,
+    /// Algorithm that calculates the output
+    pub form: PidForm,
+    /// Derivative filter coefficient `N`
+    ///
+    /// The derivative portion is filtered by a first-order lag
+    /// with the time constant `1 / N` seconds
+    /// (transfer function `k_d * s / (1 + s / N)`).
+    pub derivative_filter: Option<f64>,
+    /// The signal the derivative portion is calculated from
+    pub derivative_on: DerivativeOn,
+    /// Minimum change of the output
+    ///
+    /// Smaller changes are suppressed and the previous output is kept.
+    /// In velocity form the increments are accumulated until
+    /// their sum reaches the deadband.
+    pub output_deadband: Option<f64>,
+}
+
+/// The signal the derivative portion of a PID controller is calculated from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DerivativeOn {
+    /// The (negated) change of the measured value
+    ///
+    /// A change of the target does not cause a derivative kick.
+    #[default]
+    Measurement,
+    /// The change of the control error
+    Error,
+}
+
+/// The form of the PID algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PidForm {
+    /// The output is the absolute actuator value
+    #[default]
+    Position,
+    /// The output is the change of the actuator value (incremental form)
+    ///
+    /// This is suited for integrating actuators like stepper-driven valves.
+    /// The integral portion only contains the increment of the current step
+    /// so that the controller can not wind up.
+    /// The output limits restrict the increment per step.
+    Velocity,
 }
 
 impl Default for PidConfig {
@@ -137,10 +226,61 @@ impl Default for PidConfig {
             p_max: None,
             i_min: None,
             i_max: None,
+            feedforward: None,
+            form: PidForm::Position,
+            derivative_filter: None,
+            derivative_on: DerivativeOn::Measurement,
+            output_deadband: None,
         }
     }
 }
 
+/// Feedforward configuration
+///
+/// The measured disturbance is passed through a lead-lag element
+/// `gain * (1 + t_lead * s) / (1 + t_lag * s)` and added to the
+/// controller output. With both time constants set to zero it
+/// acts as a static gain.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct FeedforwardConfig {
+    /// Static gain
+    pub gain: f64,
+    /// Lead time constant in seconds
+    pub t_lead: f64,
+    /// Lag time constant in seconds
+    pub t_lag: f64,
+}
+
+impl Default for FeedforwardConfig {
+    fn default() -> Self {
+        FeedforwardConfig {
+            gain: 1.0,
+            t_lead: 0.0,
+            t_lag: 0.0,
+        }
+    }
+}
+
+impl FeedforwardConfig {
+    /// Calculate the next feedforward portion (backward Euler discretization).
+    fn next(&self, prev_output: f64, prev_input: Option<f64>, input: f64, delta_t: f64) -> f64 {
+        let prev_input = match prev_input {
+            Some(prev_input) => prev_input,
+            // Start in steady state
+            None => return self.gain * input,
+        };
+        let denominator = self.t_lag + delta_t;
+        if denominator <= 0.0 {
+            return self.gain * input;
+        }
+        (self.t_lag * prev_output
+            + self.gain * (self.t_lead * (input - prev_input) + input * delta_t))
+            / denominator
+    }
+}
+
 impl<'a> Controller<(f64, &'a Duration), f64> for Pid {
     fn next(&mut self, input: (f64, &Duration)) -> f64 {
         let (actual, duration) = input;
@@ -157,6 +297,7 @@ impl<'a> PureController<(PidState, f64, &'a Duration), (PidState, f64)> for PidC
         let delta_t = DurationInSeconds::from(*duration);
         debug_assert!(delta_t.is_valid());
 
+        let prev_state = state;
         let mut state = state;
 
         let err_p = state.target - actual;
@@ -164,28 +305,494 @@ impl<'a> PureController<(PidState, f64, &'a Duration), (PidState, f64)> for PidC
         state.p = limit(self.p_min, self.p_max, state.p);
 
         let err_i = err_p * f64::from(delta_t);
-        state.i += self.k_i * err_i;
+        state.i = match self.form {
+            PidForm::Position => state.i + self.k_i * err_i,
+            PidForm::Velocity => self.k_i * err_i,
+        };
         state.i = limit(self.i_min, self.i_max, state.i);
 
+        let delta_v = match self.derivative_on {
+            DerivativeOn::Measurement => state.prev_value.map(|prev_value| prev_value - actual),
+            DerivativeOn::Error => state.prev_error.map(|prev_error| err_p - prev_error),
+        };
         state.d = if delta_t.is_empty() {
             0.0
-        } else if let Some(prev_value) = state.prev_value {
-            let delta_v = prev_value - actual;
-            // Both delta_v and delta_t are correlated somehow. Calculating
-            // their ratio before multiplying with k_d should improve the
-            // numeric robustness of the algorithm.
-            let err_d = delta_v / f64::from(delta_t);
-            self.k_d * err_d
+        } else if let Some(delta_v) = delta_v {
+            match self.derivative_filter {
+                Some(n) if n > 0.0 => {
+                    // Backward Euler discretization of the first-order lag
+                    let t_f = 1.0 / n;
+                    (t_f * state.d + self.k_d * delta_v) / (t_f + f64::from(delta_t))
+                }
+                _ => {
+                    // Both delta_v and delta_t are correlated somehow. Calculating
+                    // their ratio before multiplying with k_d should improve the
+                    // numeric robustness of the algorithm.
+                    let err_d = delta_v / f64::from(delta_t);
+                    self.k_d * err_d
+                }
+            }
         } else {
             0.0
         };
 
         state.prev_value = Some(actual);
+        state.prev_error = Some(err_p);
 
-        let result = state.p + state.i + state.d;
+        state.ff = match (&self.feedforward, state.disturbance) {
+            (Some(ff), Some(disturbance)) => ff.next(
+                state.ff,
+                state.prev_disturbance,
+                disturbance,
+                f64::from(delta_t),
+            ),
+            _ => 0.0,
+        };
+        state.prev_disturbance = state.disturbance;
+
+        let result = match self.form {
+            PidForm::Position => state.p + state.i + state.d + state.ff,
+            PidForm::Velocity => {
+                if prev_state.prev_value.is_some() {
+                    (state.p - prev_state.p)
+                        + state.i
+                        + (state.d - prev_state.d)
+                        + (state.ff - prev_state.ff)
+                } else {
+                    // There is no previous step to compare with
+                    state.i
+                }
+            }
+        };
 
         let result = limit(self.min, self.max, result);
 
+        let result = match (self.output_deadband, self.form, prev_state.output) {
+            (Some(deadband), PidForm::Position, Some(prev_output)) => {
+                let delta = result - prev_output;
+                if delta.abs() < deadband {
+                    state.suppressed = delta;
+                    prev_output
+                } else {
+                    state.suppressed = 0.0;
+                    result
+                }
+            }
+            (Some(deadband), PidForm::Velocity, _) => {
+                let delta = state.suppressed + result;
+                if delta.abs() < deadband {
+                    state.suppressed = delta;
+                    0.0
+                } else {
+                    state.suppressed = 0.0;
+                    delta
+                }
+            }
+            _ => result,
+        };
+        state.output = Some(result);
+
+        (state, result)
+    }
+}
+
+/// PID controller with gain scheduling
+///
+/// The coefficients `k_p`, `k_i` and `k_d` are linearly interpolated
+/// from a table that is keyed by a scheduling variable.
+/// Outside of the table range the first or last entry is used.
+#[derive(Debug, Clone)]
+pub struct ScheduledPid {
+    cfg: ScheduledPidConfig,
+    /// Current PID state
+    pub state: PidState,
+}
+
+/// The variable that selects the coefficients of a [ScheduledPid]
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ScheduleVariable {
+    /// The current target (setpoint)
+    Target,
+    /// An auxiliary input with the given ID
+    Input(String),
+}
+
+/// A single entry of a gain schedule
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GainSchedulePoint {
+    /// Value of the scheduling variable
+    pub at: f64,
+    /// Proportional coefficient
+    pub k_p: f64,
+    /// Integral coefficient
+    pub k_i: f64,
+    /// Derivative coefficient
+    pub k_d: f64,
+}
+
+/// Scheduled PID Configuration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ScheduledPidConfig {
+    /// The underlying PID configuration
+    ///
+    /// The coefficients are only used if the schedule is empty.
+    pub pid: PidConfig,
+    /// The scheduling variable
+    pub scheduled_by: ScheduleVariable,
+    /// Gain schedule (sorted by `at` in ascending order)
+    pub schedule: Vec<GainSchedulePoint>,
+}
+
+impl Default for ScheduledPidConfig {
+    fn default() -> Self {
+        ScheduledPidConfig {
+            pid: PidConfig::default(),
+            scheduled_by: ScheduleVariable::Target,
+            schedule: vec![],
+        }
+    }
+}
+
+impl ScheduledPidConfig {
+    /// Interpolate the coefficients `(k_p, k_i, k_d)` for the given value
+    /// of the scheduling variable.
+    pub fn gains(&self, x: f64) -> (f64, f64, f64) {
+        let gains = |p: &GainSchedulePoint| (p.k_p, p.k_i, p.k_d);
+        let (first, last) = match (self.schedule.first(), self.schedule.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return (self.pid.k_p, self.pid.k_i, self.pid.k_d),
+        };
+        if x <= first.at {
+            return gains(first);
+        }
+        if x >= last.at {
+            return gains(last);
+        }
+        for w in self.schedule.windows(2) {
+            let (a, b) = (&w[0], &w[1]);
+            if x >= a.at && x <= b.at {
+                if b.at == a.at {
+                    return gains(b);
+                }
+                let f = (x - a.at) / (b.at - a.at);
+                return (
+                    a.k_p + f * (b.k_p - a.k_p),
+                    a.k_i + f * (b.k_i - a.k_i),
+                    a.k_d + f * (b.k_d - a.k_d),
+                );
+            }
+        }
+        gains(last)
+    }
+}
+
+impl ScheduledPid {
+    /// Create a new scheduled PID controller instance.
+    pub fn new(cfg: ScheduledPidConfig) -> Self {
+        let state = PidState {
+            target: cfg.pid.default_target,
+            ..Default::default()
+        };
+        ScheduledPid { state, cfg }
+    }
+    /// Set target value.
+    pub fn set_target(&mut self, target: f64) {
+        self.state.target = target;
+    }
+    /// Reset the internal controller state.
+    pub fn reset(&mut self) {
+        self.state = PidState::default();
+        self.state.target = self.cfg.pid.default_target;
+    }
+}
+
+/// The input consists of the actual value and the current value
+/// of the scheduling variable.
+impl Controller<((f64, f64), &Duration), f64> for ScheduledPid {
+    fn next(&mut self, input: ((f64, f64), &Duration)) -> f64 {
+        let ((actual, x), duration) = input;
+        let (state, result) = self.cfg.next((self.state, actual, x, duration));
+        self.state = state;
+        result
+    }
+}
+
+impl PureController<(PidState, f64, f64, &Duration), (PidState, f64)> for ScheduledPidConfig {
+    fn next(&self, input: (PidState, f64, f64, &Duration)) -> (PidState, f64) {
+        let (state, actual, x, duration) = input;
+        let (k_p, k_i, k_d) = self.gains(x);
+        let cfg = PidConfig {
+            k_p,
+            k_i,
+            k_d,
+            ..self.pid.clone()
+        };
+        cfg.next((state, actual, duration))
+    }
+}
+
+/// Rules to derive PID parameters from the ultimate gain and period.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TuningRule {
+    /// Classic Ziegler–Nichols PID rule
+    ZieglerNichols,
+    /// Ziegler–Nichols PI rule (no derivative action)
+    ZieglerNicholsPi,
+    /// Tyreus–Luyben PID rule (less aggressive, more robust)
+    TyreusLuyben,
+}
+
+/// Ultimate gain and period of a control loop
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UltimateParameters {
+    /// Ultimate gain `K_u`
+    pub gain: f64,
+    /// Ultimate period `T_u` in seconds
+    pub period: f64,
+}
+
+impl UltimateParameters {
+    /// Calculate the PID coefficients according to a tuning rule.
+    pub fn pid_config(&self, rule: TuningRule) -> PidConfig {
+        let (k_p, t_i, t_d) = match rule {
+            TuningRule::ZieglerNichols => (0.6 * self.gain, 0.5 * self.period, 0.125 * self.period),
+            TuningRule::ZieglerNicholsPi => (0.45 * self.gain, self.period / 1.2, 0.0),
+            TuningRule::TyreusLuyben => (self.gain / 2.2, 2.2 * self.period, self.period / 6.3),
+        };
+        PidConfig {
+            k_p,
+            k_i: if t_i > 0.0 { k_p / t_i } else { 0.0 },
+            k_d: k_p * t_d,
+            ..Default::default()
+        }
+    }
+}
+
+/// Relay feedback (Åström–Hägglund) auto-tuner
+///
+/// The tuner replaces the PID controller while the experiment is running:
+/// It switches the output between `bias + amplitude` and `bias - amplitude`
+/// whenever the process value crosses the target, which forces the loop
+/// into a limit cycle. The period and the amplitude of the resulting
+/// oscillation are used to estimate the ultimate gain and period.
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use msr_legacy::{TimeStepController, pid::*};
+///
+/// let cfg = RelayTunerConfig {
+///     output_bias: 50.0,
+///     output_amplitude: 20.0,
+///     ..Default::default()
+/// };
+/// let mut tuner = RelayTuner::new(cfg);
+/// tuner.set_target(33.7);
+///
+/// let delta_t = Duration::from_millis(100);
+/// while !tuner.state.finished {
+///     let sensor_value = 11.0;
+///     let _actuator_value = tuner.next(sensor_value, &delta_t);
+/// }
+/// let suggested_cfg = tuner.pid_config().unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct RelayTuner {
+    cfg: RelayTunerConfig,
+    /// Current tuner state
+    pub state: RelayTunerState,
+}
+
+/// Relay auto-tuner configuration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct RelayTunerConfig {
+    /// The default setpoint
+    pub default_target: f64,
+    /// Output value around which the relay switches
+    pub output_bias: f64,
+    /// Relay amplitude `d` (half of the peak-to-peak output step)
+    pub output_amplitude: f64,
+    /// Hysteresis of the relay to suppress switching due to noise
+    pub hysteresis: f64,
+    /// Number of oscillation periods that are averaged
+    pub cycles: u32,
+    /// Rule that is used to suggest the PID coefficients
+    pub rule: TuningRule,
+}
+
+impl Default for RelayTunerConfig {
+    fn default() -> Self {
+        RelayTunerConfig {
+            default_target: 0.0,
+            output_bias: 0.0,
+            output_amplitude: 1.0,
+            hysteresis: 0.0,
+            cycles: 3,
+            rule: TuningRule::ZieglerNichols,
+        }
+    }
+}
+
+/// Internal relay auto-tuner state
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RelayTunerState {
+    /// Current target
+    pub target: f64,
+    /// Current relay position
+    pub output_high: bool,
+    /// Seconds since the experiment has been started
+    pub elapsed: f64,
+    /// Point in time of the last switch from low to high
+    pub last_switch_on: Option<f64>,
+    /// Maximum process value within the current period
+    pub cycle_max: f64,
+    /// Minimum process value within the current period
+    pub cycle_min: f64,
+    /// Number of measured oscillation periods
+    pub cycles: u32,
+    /// Sum of all measured periods
+    pub period_sum: f64,
+    /// Sum of all measured amplitudes
+    pub amplitude_sum: f64,
+    /// The experiment is completed
+    pub finished: bool,
+}
+
+impl Default for RelayTunerState {
+    fn default() -> Self {
+        RelayTunerState {
+            target: 0.0,
+            output_high: true,
+            elapsed: 0.0,
+            last_switch_on: None,
+            cycle_max: f64::NEG_INFINITY,
+            cycle_min: f64::INFINITY,
+            cycles: 0,
+            period_sum: 0.0,
+            amplitude_sum: 0.0,
+            finished: false,
+        }
+    }
+}
+
+impl RelayTunerState {
+    /// Estimate the ultimate parameters from the measured oscillations.
+    ///
+    /// Returns `None` if no complete period has been measured yet
+    /// or if the process value did not oscillate at all.
+    pub fn ultimate_parameters(&self, cfg: &RelayTunerConfig) -> Option<UltimateParameters> {
+        if self.cycles == 0 {
+            return None;
+        }
+        let amplitude = self.amplitude_sum / f64::from(self.cycles);
+        let period = self.period_sum / f64::from(self.cycles);
+        if amplitude <= 0.0 || period <= 0.0 {
+            return None;
+        }
+        let gain = 4.0 * cfg.output_amplitude / (f64::consts::PI * amplitude);
+        Some(UltimateParameters { gain, period })
+    }
+}
+
+impl RelayTuner {
+    /// Create a new auto-tuner instance.
+    pub fn new(cfg: RelayTunerConfig) -> Self {
+        let state = RelayTunerState {
+            target: cfg.default_target,
+            ..Default::default()
+        };
+        RelayTuner { cfg, state }
+    }
+    /// Set target value.
+    pub fn set_target(&mut self, target: f64) {
+        self.state.target = target;
+    }
+    /// Restart the experiment.
+    pub fn reset(&mut self) {
+        self.state = RelayTunerState::default();
+        self.state.target = self.cfg.default_target;
+    }
+    /// The estimated ultimate parameters.
+    pub fn ultimate_parameters(&self) -> Option<UltimateParameters> {
+        self.state.ultimate_parameters(&self.cfg)
+    }
+    /// The suggested PID configuration.
+    pub fn pid_config(&self) -> Option<PidConfig> {
+        self.cfg.pid_config(&self.state)
+    }
+}
+
+impl RelayTunerConfig {
+    /// Suggest a PID configuration based on the current experiment state.
+    pub fn pid_config(&self, state: &RelayTunerState) -> Option<PidConfig> {
+        state.ultimate_parameters(self).map(|u| PidConfig {
+            default_target: state.target,
+            ..u.pid_config(self.rule)
+        })
+    }
+}
+
+impl Controller<(f64, &Duration), f64> for RelayTuner {
+    fn next(&mut self, input: (f64, &Duration)) -> f64 {
+        let (actual, duration) = input;
+        let (state, result) = self.cfg.next((self.state, actual, duration));
+        self.state = state;
+        result
+    }
+}
+
+impl PureController<(RelayTunerState, f64, &Duration), (RelayTunerState, f64)>
+    for RelayTunerConfig
+{
+    fn next(&self, input: (RelayTunerState, f64, &Duration)) -> (RelayTunerState, f64) {
+        let (mut state, actual, duration) = input;
+
+        if state.finished {
+            return (state, self.output_bias);
+        }
+
+        let delta_t = DurationInSeconds::from(*duration);
+        debug_assert!(delta_t.is_valid());
+        state.elapsed += f64::from(delta_t);
+
+        state.cycle_max = state.cycle_max.max(actual);
+        state.cycle_min = state.cycle_min.min(actual);
+
+        let err = state.target - actual;
+        if !state.output_high && err > self.hysteresis {
+            state.output_high = true;
+            if let Some(last_switch_on) = state.last_switch_on {
+                state.cycles += 1;
+                state.period_sum += state.elapsed - last_switch_on;
+                state.amplitude_sum += (state.cycle_max - state.cycle_min) / 2.0;
+            }
+            state.last_switch_on = Some(state.elapsed);
+            state.cycle_max = actual;
+            state.cycle_min = actual;
+        } else if state.output_high && err < -self.hysteresis {
+            state.output_high = false;
+        }
+
+        if state.cycles >= self.cycles {
+            state.finished = true;
+            return (state, self.output_bias);
+        }
+
+        let result = if state.output_high {
+            self.output_bias + self.output_amplitude
+        } else {
+            self.output_bias - self.output_amplitude
+        };
+
         (state, result)
     }
 }
@@ -243,6 +850,11 @@ mod tests {
         assert_eq!(cfg.i_max, None);
         assert_eq!(cfg.p_min, None);
         assert_eq!(cfg.p_max, None);
+        assert_eq!(cfg.feedforward, None);
+        assert_eq!(cfg.form, PidForm::Position);
+        assert_eq!(cfg.derivative_filter, None);
+        assert_eq!(cfg.derivative_on, DerivativeOn::Measurement);
+        assert_eq!(cfg.output_deadband, None);
     }
 
     #[test]
@@ -317,6 +929,71 @@ mod tests {
         assert_eq!(pid.state.d, -1.0);
     }
 
+    #[test]
+    fn calculate_d_on_error() {
+        let cfg = PidConfig {
+            k_p: 0.0,
+            k_d: 2.0,
+            default_target: 1.0,
+            derivative_on: DerivativeOn::Error,
+            ..Default::default()
+        };
+        let mut pid = Pid::new(cfg.clone());
+        let dt = Duration::from_secs(1);
+        assert_eq!(pid.next((0.0, &dt)), 0.0);
+        assert_eq!(pid.next((0.5, &dt)), -1.0);
+        // A target change causes a derivative kick
+        pid.set_target(3.0);
+        assert_eq!(pid.next((0.5, &dt)), 4.0);
+
+        let mut pid = Pid::new(PidConfig {
+            derivative_on: DerivativeOn::Measurement,
+            ..cfg
+        });
+        pid.next((0.0, &dt));
+        pid.next((0.5, &dt));
+        pid.set_target(3.0);
+        assert_eq!(pid.next((0.5, &dt)), 0.0);
+    }
+
+    #[test]
+    fn calculate_filtered_d() {
+        let cfg = PidConfig {
+            k_p: 0.0,
+            k_d: 2.0,
+            derivative_filter: Some(1.0),
+            ..Default::default()
+        };
+        let mut pid = Pid::new(cfg);
+        let dt = Duration::from_secs(1);
+        assert_eq!(pid.next((0.0, &dt)), 0.0);
+        // The unfiltered step would be -2.0
+        assert_eq!(pid.next((1.0, &dt)), -1.0);
+        assert_eq!(pid.next((1.0, &dt)), -0.5);
+        assert_eq!(pid.next((1.0, &dt)), -0.25);
+
+        // Noise is attenuated
+        let mut pid = Pid::new(PidConfig {
+            k_p: 0.0,
+            k_d: 1.0,
+            derivative_filter: Some(0.1),
+            ..Default::default()
+        });
+        let mut unfiltered = Pid::new(PidConfig {
+            k_p: 0.0,
+            k_d: 1.0,
+            ..Default::default()
+        });
+        let mut max = 0.0_f64;
+        let mut max_unfiltered = 0.0_f64;
+        for step in 0..100 {
+            let x = if step % 2 == 0 { 0.1 } else { -0.1 };
+            max = max.max(pid.next((x, &dt)).abs());
+            max_unfiltered = max_unfiltered.max(unfiltered.next((x, &dt)).abs());
+        }
+        assert!(max < max_unfiltered / 5.0);
+    }
+
     #[test]
     fn calculate_d_with_zero_delta_t() {
         let cfg = PidConfig {
@@ -376,6 +1053,138 @@ mod tests {
         assert_eq!(pid.next((40.0, &dt)), -0.5);
     }
 
+    #[test]
+    fn calculate_static_feedforward() {
+        let cfg = PidConfig {
+            k_p: 0.0,
+            feedforward: Some(FeedforwardConfig {
+                gain: -0.5,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut pid = Pid::new(cfg);
+        let dt = Duration::from_secs(1);
+        assert_eq!(pid.next((0.0, &dt)), 0.0);
+        pid.set_disturbance(4.0);
+        assert_eq!(pid.next((0.0, &dt)), -2.0);
+        assert_eq!(pid.state.ff, -2.0);
+        pid.set_disturbance(6.0);
+        assert_eq!(pid.next((0.0, &dt)), -3.0);
+    }
+
+    #[test]
+    fn calculate_lead_lag_feedforward() {
+        let cfg = PidConfig {
+            k_p: 0.0,
+            max: Some(10.0),
+            feedforward: Some(FeedforwardConfig {
+                gain: 1.0,
+                t_lead: 2.0,
+                t_lag: 1.0,
+            }),
+            ..Default::default()
+        };
+        let mut pid = Pid::new(cfg);
+        let dt = Duration::from_secs(1);
+        pid.set_disturbance(1.0);
+        assert_eq!(pid.next((0.0, &dt)), 1.0);
+        // step from 1.0 to 2.0: (1.0 * 1.0 + (2.0 * 1.0 + 2.0 * 1.0)) / 2.0
+        pid.set_disturbance(2.0);
+        assert_eq!(pid.next((0.0, &dt)), 2.5);
+        // decays towards the static value
+        assert_eq!(pid.next((0.0, &dt)), 2.25);
+        // the output limit includes the feedforward portion
+        pid.set_disturbance(20.0);
+        assert_eq!(pid.next((0.0, &dt)), 10.0);
+    }
+
+    #[test]
+    fn calculate_velocity_form() {
+        let dt = Duration::from_secs(1);
+        let cfg = PidConfig {
+            k_p: 2.0,
+            k_i: 0.5,
+            k_d: 1.0,
+            default_target: 10.0,
+            ..Default::default()
+        };
+        let mut position = Pid::new(cfg.clone());
+        let mut velocity = Pid::new(PidConfig {
+            form: PidForm::Velocity,
+            ..cfg
+        });
+        // The first step only contains the integral portion
+        position.next((0.0, &dt));
+        assert_eq!(velocity.next((0.0, &dt)), 5.0);
+        // The increments are the changes of the position form output
+        let mut prev = position.state.p + position.state.i + position.state.d;
+        for x in [0.0, 4.0, 7.0, 11.0, 10.0] {
+            let y = position.next((x, &dt));
+            assert_eq!(velocity.next((x, &dt)), y - prev);
+            prev = y;
+        }
+    }
+
+    #[test]
+    fn velocity_form_does_not_wind_up() {
+        let dt = Duration::from_secs(1);
+        let mut pid = Pid::new(PidConfig {
+            k_p: 1.0,
+            k_i: 1.0,
+            default_target: 10.0,
+            min: Some(-1.0),
+            max: Some(1.0),
+            form: PidForm::Velocity,
+            ..Default::default()
+        });
+        for _ in 0..100 {
+            assert_eq!(pid.next((0.0, &dt)), 1.0);
+        }
+        assert_eq!(pid.state.i, 10.0);
+        // The output reverses as soon as the target is exceeded
+        assert!(pid.next((10.5, &dt)) < 0.0);
+    }
+
+    #[test]
+    fn suppress_small_output_changes() {
+        let dt = Duration::from_secs(1);
+        let mut pid = Pid::new(PidConfig {
+            output_deadband: Some(0.5),
+            ..Default::default()
+        });
+        assert_eq!(pid.next((-10.0, &dt)), 10.0);
+        assert_eq!(pid.next((-10.25, &dt)), 10.0);
+        assert_eq!(pid.state.suppressed, 0.25);
+        assert_eq!(pid.next((-9.75, &dt)), 10.0);
+        assert_eq!(pid.state.suppressed, -0.25);
+        assert_eq!(pid.next((-10.5, &dt)), 10.5);
+        assert_eq!(pid.state.suppressed, 0.0);
+        // The reference is the last output that was applied
+        assert_eq!(pid.next((-10.75, &dt)), 10.5);
+        assert_eq!(pid.next((-11.0, &dt)), 11.0);
+    }
+
+    #[test]
+    fn accumulate_suppressed_increments() {
+        let dt = Duration::from_secs(1);
+        let mut pid = Pid::new(PidConfig {
+            k_p: 0.0,
+            k_i: 0.25,
+            default_target: 1.0,
+            form: PidForm::Velocity,
+            output_deadband: Some(0.5),
+            ..Default::default()
+        });
+        assert_eq!(pid.next((0.0, &dt)), 0.0);
+        assert_eq!(pid.state.suppressed, 0.25);
+        assert_eq!(pid.next((0.0, &dt)), 0.5);
+        assert_eq!(pid.state.suppressed, 0.0);
+        assert_eq!(pid.next((0.0, &dt)), 0.0);
+        assert_eq!(pid.next((2.0, &dt)), 0.0);
+        assert_eq!(pid.state.suppressed, 0.0);
+    }
+
     #[test]
     fn reset() {
         let cfg = PidConfig {
@@ -397,4 +1206,155 @@ mod tests {
         assert_eq!(pid.state.target, 9.9);
         assert_eq!(pid.state.prev_value, None);
     }
+
+    #[test]
+    fn interpolate_scheduled_gains() {
+        let mut cfg = ScheduledPidConfig {
+            pid: PidConfig {
+                k_p: 3.0,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert_eq!(cfg.gains(1.0), (3.0, 0.0, 0.0));
+        cfg.schedule = vec![
+            GainSchedulePoint {
+                at: 0.0,
+                k_p: 1.0,
+                k_i: 0.1,
+                k_d: 0.0,
+            },
+            GainSchedulePoint {
+                at: 10.0,
+                k_p: 2.0,
+                k_i: 0.3,
+                k_d: 1.0,
+            },
+            GainSchedulePoint {
+                at: 20.0,
+                k_p: 4.0,
+                k_i: 0.3,
+                k_d: 1.0,
+            },
+        ];
+        assert_eq!(cfg.gains(-5.0), (1.0, 0.1, 0.0));
+        assert_eq!(cfg.gains(0.0), (1.0, 0.1, 0.0));
+        let (k_p, k_i, k_d) = cfg.gains(5.0);
+        assert_eq!(k_p, 1.5);
+        assert!((k_i - 0.2).abs() < 1e-12);
+        assert_eq!(k_d, 0.5);
+        assert_eq!(cfg.gains(15.0), (3.0, 0.3, 1.0));
+        assert_eq!(cfg.gains(25.0), (4.0, 0.3, 1.0));
+    }
+
+    #[test]
+    fn calculate_scheduled_pid() {
+        let cfg = ScheduledPidConfig {
+            pid: PidConfig {
+                max: Some(15.0),
+                ..Default::default()
+            },
+            schedule: vec![
+                GainSchedulePoint {
+                    at: 0.0,
+                    k_p: 1.0,
+                    k_i: 0.0,
+                    k_d: 0.0,
+                },
+                GainSchedulePoint {
+                    at: 100.0,
+                    k_p: 3.0,
+                    k_i: 0.0,
+                    k_d: 0.0,
+                },
+            ],
+            ..Default::default()
+        };
+        let mut pid = ScheduledPid::new(cfg);
+        let dt = Duration::from_secs(1);
+        pid.set_target(10.0);
+        assert_eq!(pid.next(((5.0, 0.0), &dt)), 5.0);
+        assert_eq!(pid.next(((5.0, 50.0), &dt)), 10.0);
+        assert_eq!(pid.next(((5.0, 100.0), &dt)), 15.0);
+        assert_eq!(pid.next(((0.0, 100.0), &dt)), 15.0);
+        pid.reset();
+        assert_eq!(pid.state.target, 0.0);
+    }
+
+    #[test]
+    fn relay_tuner_switches_output() {
+        let cfg = RelayTunerConfig {
+            output_bias: 10.0,
+            output_amplitude: 2.0,
+            hysteresis: 0.5,
+            default_target: 5.0,
+            ..Default::default()
+        };
+        let mut tuner = RelayTuner::new(cfg);
+        let dt = Duration::from_secs(1);
+        assert_eq!(tuner.next((4.0, &dt)), 12.0);
+        assert_eq!(tuner.next((5.4, &dt)), 12.0);
+        assert_eq!(tuner.next((5.6, &dt)), 8.0);
+        assert_eq!(tuner.next((4.6, &dt)), 8.0);
+        assert_eq!(tuner.next((4.4, &dt)), 12.0);
+        assert_eq!(tuner.state.last_switch_on, Some(5.0));
+        assert_eq!(tuner.state.cycles, 0);
+        assert!(tuner.pid_config().is_none());
+    }
+
+    #[test]
+    fn relay_tuner_estimates_ultimate_parameters() {
+        // Integrating process with dead time: dx/dt = u(t - L)
+        // The relay experiment results in a period of 4 * L
+        // and an amplitude of d * L.
+        let dead_time_steps = 100;
+        let dt = Duration::from_millis(10);
+        let cfg = RelayTunerConfig {
+            output_amplitude: 1.0,
+            cycles: 4,
+            ..Default::default()
+        };
+        let mut tuner = RelayTuner::new(cfg);
+        let mut delayed = std::collections::VecDeque::from(vec![0.0; dead_time_steps]);
+        let mut x = 0.0;
+        let mut steps = 0;
+        while !tuner.state.finished {
+            let u = tuner.next((x, &dt));
+            delayed.push_back(u);
+            x += delayed.pop_front().unwrap() * 0.01;
+            steps += 1;
+            assert!(steps < 100_000);
+        }
+        let u = tuner.ultimate_parameters().unwrap();
+        assert!((u.period - 4.0).abs() < 0.05);
+        assert!((u.gain - 4.0 / f64::consts::PI).abs() < 0.05);
+        assert_eq!(tuner.next((x, &dt)), 0.0);
+
+        let pid_cfg = tuner.pid_config().unwrap();
+        assert!((pid_cfg.k_p - 0.6 * u.gain).abs() < 1e-9);
+        assert!((pid_cfg.k_i - pid_cfg.k_p / (0.5 * u.period)).abs() < 1e-9);
+        assert!((pid_cfg.k_d - pid_cfg.k_p * 0.125 * u.period).abs() < 1e-9);
+
+        tuner.reset();
+        assert!(!tuner.state.finished);
+        assert!(tuner.ultimate_parameters().is_none());
+    }
+
+    #[test]
+    fn tuning_rules() {
+        let u = UltimateParameters {
+            gain: 10.0,
+            period: 2.0,
+        };
+        let cfg = u.pid_config(TuningRule::ZieglerNichols);
+        assert_eq!(cfg.k_p, 6.0);
+        assert_eq!(cfg.k_i, 6.0);
+        assert_eq!(cfg.k_d, 1.5);
+        let cfg = u.pid_config(TuningRule::ZieglerNicholsPi);
+        assert_eq!(cfg.k_p, 4.5);
+        assert_eq!(cfg.k_d, 0.0);
+        let cfg = u.pid_config(TuningRule::TyreusLuyben);
+        assert!((cfg.k_p - 10.0 / 2.2).abs() < 1e-9);
+        assert!((cfg.k_i - cfg.k_p / 4.4).abs() < 1e-9);
+    }
 }
@@ -0,0 +1,273 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, pid::*, plant::*};
+//!
+//! let mut plant = Plant::new(PlantConfig {
+//!     dead_time: Duration::from_secs(1),
+//!     ..PlantConfig::pt2(2.0, Duration::from_secs(10), Duration::from_secs(3))
+//! });
+//! let mut pid = Pid::new(PidConfig {
+//!     k_p: 0.8,
+//!     k_i: 0.1,
+//!     default_target: 20.0,
+//!     ..Default::default()
+//! });
+//!
+//! let delta_t = Duration::from_millis(500);
+//! let mut x = 0.0;
+//! for _ in 0..1_000 {
+//!     let y = pid.next(x, &delta_t);
+//!     x = plant.next(y, &delta_t);
+//! }
+//! assert!((x - 20.0).abs() < 0.1);
+//! ```
+
+use super::{Controller, PureController};
+use std::{collections::VecDeque, time::Duration};
+
+/// Process simulation
+///
+/// A first-order (PT1) or second-order (PT2) lag with dead time.
+/// The second-order lag is modelled by two first-order lags in series.
+/// The output is superimposed by uniformly distributed noise.
+#[derive(Debug, Clone)]
+pub struct Plant {
+    cfg: PlantConfig,
+    /// Current simulation state
+    pub state: PlantState,
+}
+
+/// Process simulation configuration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlantConfig {
+    /// Steady-state gain
+    pub gain: f64,
+    /// Time constant of the first lag
+    pub t1: Duration,
+    /// Time constant of the second lag (none for PT1)
+    pub t2: Option<Duration>,
+    /// Dead time of the input
+    pub dead_time: Duration,
+    /// Output in the steady state with zero input
+    pub offset: f64,
+    /// Maximum amplitude of the noise
+    pub noise: f64,
+    /// Seed of the noise generator
+    pub seed: u64,
+}
+
+impl PlantConfig {
+    /// First-order lag
+    pub fn pt1(gain: f64, t1: Duration) -> Self {
+        PlantConfig {
+            gain,
+            t1,
+            ..Default::default()
+        }
+    }
+    /// Second-order lag
+    pub fn pt2(gain: f64, t1: Duration, t2: Duration) -> Self {
+        PlantConfig {
+            gain,
+            t1,
+            t2: Some(t2),
+            ..Default::default()
+        }
+    }
+}
+
+impl Default for PlantConfig {
+    fn default() -> Self {
+        PlantConfig {
+            gain: 1.0,
+            t1: Duration::from_secs(1),
+            t2: None,
+            dead_time: Duration::ZERO,
+            offset: 0.0,
+            noise: 0.0,
+            seed: 1,
+        }
+    }
+}
+
+/// Internal simulation state
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PlantState {
+    /// Output of the first lag
+    pub x1: f64,
+    /// Output of the second lag
+    pub x2: f64,
+    /// Simulated time
+    pub time: Duration,
+    /// Inputs that are delayed by the dead time
+    pub delayed: VecDeque<(Duration, f64)>,
+    /// State of the noise generator
+    pub rng: u64,
+}
+
+impl Plant {
+    /// Create a new simulation instance.
+    pub fn new(cfg: PlantConfig) -> Self {
+        let state = cfg.initial_state();
+        Plant { cfg, state }
+    }
+    /// Reset the simulation.
+    pub fn reset(&mut self) {
+        self.state = self.cfg.initial_state();
+    }
+}
+
+impl PlantConfig {
+    /// The state at rest with zero input.
+    pub fn initial_state(&self) -> PlantState {
+        PlantState {
+            rng: self.seed.max(1),
+            ..Default::default()
+        }
+    }
+}
+
+/// Exact discretization of a first-order lag
+fn lag(x: f64, input: f64, t: Duration, delta_t: f64) -> f64 {
+    let t = t.as_secs_f64();
+    if t > 0.0 {
+        x + (1.0 - (-delta_t / t).exp()) * (input - x)
+    } else {
+        input
+    }
+}
+
+/// A uniformly distributed random number in `-1.0 ..= 1.0` (xorshift64)
+fn random(rng: &mut u64) -> f64 {
+    let mut x = *rng;
+    x ^= x << 13;
+    x ^= x >> 7;
+    x ^= x << 17;
+    *rng = x;
+    (x >> 11) as f64 / ((1_u64 << 53) - 1) as f64 * 2.0 - 1.0
+}
+
+impl Controller<(f64, &Duration), f64> for Plant {
+    fn next(&mut self, input: (f64, &Duration)) -> f64 {
+        let (u, duration) = input;
+        let state = std::mem::take(&mut self.state);
+        let (state, y) = self.cfg.next((state, u, duration));
+        self.state = state;
+        y
+    }
+}
+
+impl PureController<(PlantState, f64, &Duration), (PlantState, f64)> for PlantConfig {
+    fn next(&self, input: (PlantState, f64, &Duration)) -> (PlantState, f64) {
+        let (mut state, u, duration) = input;
+
+        state.delayed.push_back((state.time, u));
+        state.time += *duration;
+        let due = state.time.checked_sub(self.dead_time);
+        while state.delayed.len() > 1 && Some(state.delayed[1].0) <= due {
+            state.delayed.pop_front();
+        }
+        let u = match state.delayed.front() {
+            Some((t, u)) if Some(*t) <= due => *u,
+            _ => 0.0,
+        };
+
+        let delta_t = duration.as_secs_f64();
+        state.x1 = lag(state.x1, self.gain * u, self.t1, delta_t);
+        state.x2 = match self.t2 {
+            Some(t2) => lag(state.x2, state.x1, t2, delta_t),
+            None => state.x1,
+        };
+
+        let mut y = self.offset + state.x2;
+        if self.noise != 0.0 {
+            y += self.noise * random(&mut state.rng);
+        }
+        (state, y)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn pt1_step_response() {
+        let mut p = Plant::new(PlantConfig::pt1(2.0, Duration::from_secs(10)));
+        let dt = Duration::from_secs(1);
+        let mut y = 0.0;
+        for _ in 0..10 {
+            y = p.next((1.0, &dt));
+        }
+        assert!((y - 2.0 * (1.0 - (-1.0_f64).exp())).abs() < 1e-9);
+        for _ in 0..100 {
+            y = p.next((1.0, &dt));
+        }
+        assert!((y - 2.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn pt2_step_response() {
+        let mut p = Plant::new(PlantConfig::pt2(
+            1.0,
+            Duration::from_secs(5),
+            Duration::from_secs(5),
+        ));
+        let dt = Duration::from_millis(10);
+        let y = p.next((1.0, &dt));
+        // The second-order lag starts with a horizontal tangent
+        assert!(y < 1e-5);
+        let mut prev = y;
+        for _ in 0..10_000 {
+            let y = p.next((1.0, &dt));
+            assert!(y >= prev);
+            prev = y;
+        }
+        assert!((prev - 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn delay_input_by_dead_time() {
+        let mut p = Plant::new(PlantConfig {
+            t1: Duration::ZERO,
+            dead_time: Duration::from_secs(3),
+            offset: 5.0,
+            ..Default::default()
+        });
+        let dt = Duration::from_secs(1);
+        assert_eq!(p.next((1.0, &dt)), 5.0);
+        assert_eq!(p.next((2.0, &dt)), 5.0);
+        assert_eq!(p.next((3.0, &dt)), 6.0);
+        assert_eq!(p.next((4.0, &dt)), 7.0);
+        assert_eq!(p.next((4.0, &Duration::from_secs(2))), 4.0 + 5.0);
+        p.reset();
+        assert_eq!(p.state.time, Duration::ZERO);
+        assert!(p.state.delayed.is_empty());
+    }
+
+    #[test]
+    fn add_reproducible_noise() {
+        let cfg = PlantConfig {
+            t1: Duration::ZERO,
+            noise: 0.5,
+            seed: 42,
+            ..Default::default()
+        };
+        let mut a = Plant::new(cfg.clone());
+        let mut b = Plant::new(cfg);
+        let dt = Duration::from_secs(1);
+        let mut distinct = false;
+        for _ in 0..100 {
+            let y = a.next((1.0, &dt));
+            assert!((0.5..=1.5).contains(&y));
+            assert_eq!(y, b.next((1.0, &dt)));
+            distinct |= y != 1.0;
+        }
+        assert!(distinct);
+    }
+}
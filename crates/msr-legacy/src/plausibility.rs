@@ -0,0 +1,178 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, plausibility::*};
+//!
+//! let mut check = PlausibilityCheck::new(PlausibilityConfig {
+//!     stuck_time: Some(Duration::from_secs(60)),
+//!     max_delta: Some(5.0),
+//! });
+//! let delta_t = Duration::from_secs(30);
+//!
+//! assert_eq!(check.next(20.0, &delta_t), vec![]);
+//! assert_eq!(check.next(21.0, &delta_t), vec![]);
+//! assert_eq!(check.next(40.0, &delta_t), vec![Fault::Spike]);
+//! assert!(!check.state.is_plausible());
+//!
+//! assert_eq!(check.next(40.0, &delta_t), vec![]);
+//! assert_eq!(check.next(40.0, &delta_t), vec![]);
+//! assert_eq!(check.next(40.0, &delta_t), vec![Fault::Stuck]);
+//! ```
+
+use super::{Controller, PureController};
+use std::time::Duration;
+
+/// Sensor plausibility check
+///
+/// Detects stuck sensors and spikes. Each step returns the faults
+/// that are detected for the first time (fault events), e.g. to write
+/// them into an event journal.
+#[derive(Debug, Clone)]
+pub struct PlausibilityCheck {
+    cfg: PlausibilityConfig,
+    /// Current state
+    pub state: PlausibilityState,
+}
+
+/// Plausibility check configuration
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct PlausibilityConfig {
+    /// A value that stays bit-identical for longer than this is stuck
+    pub stuck_time: Option<Duration>,
+    /// Maximum change of the value per step
+    pub max_delta: Option<f64>,
+}
+
+/// An implausible signal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Fault {
+    /// The value did not change for longer than the configured period
+    Stuck,
+    /// The value changed by more than the configured delta
+    Spike,
+}
+
+/// Internal plausibility check state
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PlausibilityState {
+    /// The value of the previous step
+    pub prev_value: Option<f64>,
+    /// The time since the value last changed
+    pub unchanged: Duration,
+    /// Currently detected faults
+    pub faults: Vec<Fault>,
+}
+
+impl PlausibilityState {
+    /// The quality flag of the signal.
+    pub fn is_plausible(&self) -> bool {
+        self.faults.is_empty()
+    }
+}
+
+impl PlausibilityCheck {
+    /// Create a new plausibility check instance.
+    pub fn new(cfg: PlausibilityConfig) -> Self {
+        PlausibilityCheck {
+            cfg,
+            state: PlausibilityState::default(),
+        }
+    }
+    /// Reset the internal state.
+    pub fn reset(&mut self) {
+        self.state = PlausibilityState::default();
+    }
+}
+
+impl Controller<(f64, &Duration), Vec<Fault>> for PlausibilityCheck {
+    fn next(&mut self, input: (f64, &Duration)) -> Vec<Fault> {
+        let (x, duration) = input;
+        let state = std::mem::take(&mut self.state);
+        let (state, events) = self.cfg.next((state, x, duration));
+        self.state = state;
+        events
+    }
+}
+
+impl PureController<(PlausibilityState, f64, &Duration), (PlausibilityState, Vec<Fault>)>
+    for PlausibilityConfig
+{
+    fn next(&self, input: (PlausibilityState, f64, &Duration)) -> (PlausibilityState, Vec<Fault>) {
+        let (mut state, x, duration) = input;
+        let mut faults = vec![];
+        match state.prev_value {
+            Some(prev) if prev.to_bits() == x.to_bits() => {
+                state.unchanged += *duration;
+            }
+            _ => {
+                state.unchanged = Duration::ZERO;
+            }
+        }
+        if let Some(stuck_time) = self.stuck_time {
+            if state.unchanged > stuck_time {
+                faults.push(Fault::Stuck);
+            }
+        }
+        if let (Some(prev), Some(max_delta)) = (state.prev_value, self.max_delta) {
+            let delta = (x - prev).abs();
+            if delta.is_nan() || delta > max_delta {
+                faults.push(Fault::Spike);
+            }
+        }
+        state.prev_value = Some(x);
+        let events = faults
+            .iter()
+            .filter(|f| !state.faults.contains(f))
+            .copied()
+            .collect();
+        state.faults = faults;
+        (state, events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn detect_stuck_values() {
+        let mut c = PlausibilityCheck::new(PlausibilityConfig {
+            stuck_time: Some(Duration::from_secs(2)),
+            ..Default::default()
+        });
+        let dt = Duration::from_secs(1);
+        for _ in 0..3 {
+            assert!(c.next((1.0, &dt)).is_empty());
+        }
+        assert_eq!(c.next((1.0, &dt)), vec![Fault::Stuck]);
+        // The fault is only reported once
+        assert!(c.next((1.0, &dt)).is_empty());
+        assert!(!c.state.is_plausible());
+        // Even the smallest change counts
+        assert!(c.next((1.0 + f64::EPSILON, &dt)).is_empty());
+        assert!(c.state.is_plausible());
+    }
+
+    #[test]
+    fn detect_spikes() {
+        let mut c = PlausibilityCheck::new(PlausibilityConfig {
+            max_delta: Some(1.0),
+            ..Default::default()
+        });
+        let dt = Duration::from_secs(1);
+        assert!(c.next((0.0, &dt)).is_empty());
+        assert!(c.next((1.0, &dt)).is_empty());
+        assert_eq!(c.next((-0.5, &dt)), vec![Fault::Spike]);
+        assert!(c.next((-0.5, &dt)).is_empty());
+        assert!(c.state.is_plausible());
+        assert_eq!(c.next((f64::NAN, &dt)), vec![Fault::Spike]);
+        c.reset();
+        assert_eq!(c.state, PlausibilityState::default());
+    }
+}
@@ -0,0 +1,246 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{PureController, filter::*, preprocessing::*, scaling::Scaling};
+//!
+//! // 4-20 mA as 4000-20000 counts → 0-10 bar, smoothed and checked
+//! let chain = vec![
+//!     Preprocessing::Scaling(Scaling {
+//!         raw_min: 4000.0,
+//!         raw_max: 20000.0,
+//!         min: 0.0,
+//!         max: 10.0,
+//!         clamp: false,
+//!     }),
+//!     Preprocessing::Validation(ValidationConfig {
+//!         min: Some(0.0),
+//!         max: Some(10.0),
+//!         ..Default::default()
+//!     }),
+//!     Preprocessing::Filter(FilterConfig::MovingAverage(MovingAverageConfig { window: 2 })),
+//! ];
+//! let delta_t = Duration::from_millis(100);
+//!
+//! let (states, x) = chain.next((vec![], 12000.0, &delta_t));
+//! assert_eq!(x.unwrap(), 5.0);
+//! let (states, x) = chain.next((states, 16000.0, &delta_t));
+//! assert_eq!(x.unwrap(), 6.25);
+//! let (_, x) = chain.next((states, 0.0, &delta_t)); // wire break
+//! assert!(x.is_err());
+//! ```
+
+use super::{filter, plausibility, scaling, PureController};
+use std::{
+    io::{Error, ErrorKind, Result},
+    time::Duration,
+};
+
+/// A step of an input preprocessing chain
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Preprocessing {
+    /// Smooth the value
+    Filter(filter::FilterConfig),
+    /// Convert the value into another range
+    Scaling(scaling::Scaling),
+    /// Reject invalid values
+    Validation(ValidationConfig),
+}
+
+/// Input validation
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ValidationConfig {
+    /// Smallest valid value
+    pub min: Option<f64>,
+    /// Greatest valid value
+    pub max: Option<f64>,
+    /// Reject stuck values and spikes
+    pub plausibility: Option<plausibility::PlausibilityConfig>,
+}
+
+/// Internal state of a preprocessing step
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PreprocessingState {
+    Filter(filter::FilterState),
+    Scaling,
+    Validation(plausibility::PlausibilityState),
+}
+
+impl Preprocessing {
+    /// The state of a step that has not received any value yet.
+    pub fn initial_state(&self) -> PreprocessingState {
+        match self {
+            Preprocessing::Filter(cfg) => PreprocessingState::Filter(cfg.initial_state()),
+            Preprocessing::Scaling(_) => PreprocessingState::Scaling,
+            Preprocessing::Validation(_) => {
+                PreprocessingState::Validation(plausibility::PlausibilityState::default())
+            }
+        }
+    }
+}
+
+/// A state that does not match the configuration is reinitialized.
+impl PureController<(PreprocessingState, f64, &Duration), (PreprocessingState, Result<f64>)>
+    for Preprocessing
+{
+    fn next(
+        &self,
+        input: (PreprocessingState, f64, &Duration),
+    ) -> (PreprocessingState, Result<f64>) {
+        let (state, x, duration) = input;
+        match (self, state) {
+            (Preprocessing::Filter(cfg), PreprocessingState::Filter(s)) => {
+                let (s, y) = cfg.next((s, x, duration));
+                (PreprocessingState::Filter(s), Ok(y))
+            }
+            (Preprocessing::Scaling(cfg), PreprocessingState::Scaling) => {
+                (PreprocessingState::Scaling, Ok(cfg.next(x).0))
+            }
+            (Preprocessing::Validation(cfg), PreprocessingState::Validation(s)) => {
+                let (s, _) = match cfg.plausibility {
+                    Some(ref p) => p.next((s, x, duration)),
+                    None => (s, vec![]),
+                };
+                let res = if x.is_nan()
+                    || cfg.min.map(|min| x < min).unwrap_or(false)
+                    || cfg.max.map(|max| x > max).unwrap_or(false)
+                {
+                    Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid input value {x}: out of range"),
+                    ))
+                } else if !s.is_plausible() {
+                    Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Invalid input value {x}: implausible"),
+                    ))
+                } else {
+                    Ok(x)
+                };
+                (PreprocessingState::Validation(s), res)
+            }
+            (cfg, _) => cfg.next((cfg.initial_state(), x, duration)),
+        }
+    }
+}
+
+/// A chain of steps that are applied one after another.
+///
+/// The chain stops at the first rejected value
+/// and the states of the following steps are kept.
+impl
+    PureController<
+        (Vec<PreprocessingState>, f64, &Duration),
+        (Vec<PreprocessingState>, Result<f64>),
+    > for [Preprocessing]
+{
+    fn next(
+        &self,
+        input: (Vec<PreprocessingState>, f64, &Duration),
+    ) -> (Vec<PreprocessingState>, Result<f64>) {
+        let (states, x, duration) = input;
+        let mut states = states.into_iter();
+        let mut y = Ok(x);
+        let states = self
+            .iter()
+            .map(|cfg| {
+                let state = states.next().unwrap_or_else(|| cfg.initial_state());
+                match y {
+                    Ok(x) => {
+                        let (state, res) = cfg.next((state, x, duration));
+                        y = res;
+                        state
+                    }
+                    Err(_) => state,
+                }
+            })
+            .collect();
+        (states, y)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn reject_values_out_of_range() {
+        let cfg = Preprocessing::Validation(ValidationConfig {
+            min: Some(-1.0),
+            max: Some(1.0),
+            ..Default::default()
+        });
+        let dt = Duration::from_secs(1);
+        let s = cfg.initial_state();
+        let (s, x) = cfg.next((s, 1.0, &dt));
+        assert_eq!(x.unwrap(), 1.0);
+        let (s, x) = cfg.next((s, 1.5, &dt));
+        assert!(x.is_err());
+        let (s, x) = cfg.next((s, f64::NAN, &dt));
+        assert!(x.is_err());
+        let (_, x) = cfg.next((s, -1.0, &dt));
+        assert_eq!(x.unwrap(), -1.0);
+    }
+
+    #[test]
+    fn reject_implausible_values() {
+        let cfg = Preprocessing::Validation(ValidationConfig {
+            plausibility: Some(plausibility::PlausibilityConfig {
+                max_delta: Some(1.0),
+                ..Default::default()
+            }),
+            ..Default::default()
+        });
+        let dt = Duration::from_secs(1);
+        let (s, x) = cfg.next((cfg.initial_state(), 5.0, &dt));
+        assert_eq!(x.unwrap(), 5.0);
+        let (s, x) = cfg.next((s, 9.0, &dt));
+        assert_eq!(
+            x.unwrap_err().to_string(),
+            "Invalid input value 9: implausible"
+        );
+        let (_, x) = cfg.next((s, 9.5, &dt));
+        assert_eq!(x.unwrap(), 9.5);
+    }
+
+    #[test]
+    fn stop_chain_at_rejected_value() {
+        let chain = [
+            Preprocessing::Validation(ValidationConfig {
+                max: Some(10.0),
+                ..Default::default()
+            }),
+            Preprocessing::Filter(filter::FilterConfig::MovingAverage(
+                filter::MovingAverageConfig { window: 2 },
+            )),
+        ];
+        let dt = Duration::from_secs(1);
+        let (states, x) = chain.next((vec![], 2.0, &dt));
+        assert_eq!(x.unwrap(), 2.0);
+        let (states, x) = chain.next((states, 20.0, &dt));
+        assert!(x.is_err());
+        // The rejected value does not reach the filter
+        let (states, x) = chain.next((states, 4.0, &dt));
+        assert_eq!(x.unwrap(), 3.0);
+        assert_eq!(states.len(), 2);
+    }
+
+    #[test]
+    fn reinitialize_mismatching_states() {
+        let cfg = Preprocessing::Scaling(scaling::Scaling {
+            raw_max: 10.0,
+            ..Default::default()
+        });
+        let dt = Duration::from_secs(1);
+        let state = PreprocessingState::Validation(Default::default());
+        let (state, x) = cfg.next((state, 5.0, &dt));
+        assert_eq!(state, PreprocessingState::Scaling);
+        assert_eq!(x.unwrap(), 0.5);
+    }
+}
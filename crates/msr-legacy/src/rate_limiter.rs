@@ -0,0 +1,135 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, rate_limiter::*};
+//!
+//! let cfg = RateLimiterConfig {
+//!     rising: Some(5.0),   // 5 % per second
+//!     falling: Some(20.0), // 20 % per second
+//! };
+//! let mut r = RateLimiter::new(cfg);
+//! let delta_t = Duration::from_secs(1);
+//!
+//! assert_eq!(r.next(0.0, &delta_t), 0.0);
+//! assert_eq!(r.next(100.0, &delta_t), 5.0);
+//! assert_eq!(r.next(100.0, &delta_t), 10.0);
+//! assert_eq!(r.next(0.0, &delta_t), 0.0);
+//! ```
+
+use super::{Controller, PureController};
+use std::time::Duration;
+
+/// Rate limiter (slew rate limiter)
+///
+/// The output follows the input but its change per second is
+/// limited by the rising and the falling limit.
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    cfg: RateLimiterConfig,
+    /// Current state
+    pub state: RateLimiterState,
+}
+
+/// Rate limiter configuration
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct RateLimiterConfig {
+    /// Maximum increase per second
+    pub rising: Option<f64>,
+    /// Maximum decrease per second (a positive value)
+    pub falling: Option<f64>,
+}
+
+/// Internal rate limiter state
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RateLimiterState {
+    /// The output of the previous step
+    pub value: Option<f64>,
+}
+
+impl RateLimiter {
+    /// Create a new rate limiter instance.
+    pub fn new(cfg: RateLimiterConfig) -> Self {
+        RateLimiter {
+            cfg,
+            state: RateLimiterState::default(),
+        }
+    }
+    /// Reset the internal state.
+    ///
+    /// The next input is passed through without limitation.
+    pub fn reset(&mut self) {
+        self.state = RateLimiterState::default();
+    }
+}
+
+impl Controller<(f64, &Duration), f64> for RateLimiter {
+    fn next(&mut self, input: (f64, &Duration)) -> f64 {
+        let (x, duration) = input;
+        let (state, y) = self.cfg.next((self.state, x, duration));
+        self.state = state;
+        y
+    }
+}
+
+impl PureController<(RateLimiterState, f64, &Duration), (RateLimiterState, f64)>
+    for RateLimiterConfig
+{
+    fn next(&self, input: (RateLimiterState, f64, &Duration)) -> (RateLimiterState, f64) {
+        let (state, x, duration) = input;
+        let y = match state.value {
+            Some(prev) => {
+                let delta_t = duration.as_secs_f64();
+                let mut y = x;
+                if let Some(rising) = self.rising {
+                    y = y.min(prev + rising.abs() * delta_t);
+                }
+                if let Some(falling) = self.falling {
+                    y = y.max(prev - falling.abs() * delta_t);
+                }
+                y
+            }
+            None => x,
+        };
+        (RateLimiterState { value: Some(y) }, y)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn limit_rising_and_falling_rate() {
+        let cfg = RateLimiterConfig {
+            rising: Some(2.0),
+            falling: Some(4.0),
+        };
+        let mut r = RateLimiter::new(cfg);
+        let dt = Duration::from_millis(500);
+        assert_eq!(r.next((10.0, &dt)), 10.0);
+        assert_eq!(r.next((20.0, &dt)), 11.0);
+        assert_eq!(r.next((11.5, &dt)), 11.5);
+        assert_eq!(r.next((0.0, &dt)), 9.5);
+        assert_eq!(r.next((0.0, &Duration::ZERO)), 9.5);
+        r.reset();
+        assert_eq!(r.next((0.0, &dt)), 0.0);
+    }
+
+    #[test]
+    fn limit_only_one_direction() {
+        let cfg = RateLimiterConfig {
+            rising: Some(1.0),
+            ..Default::default()
+        };
+        let dt = Duration::from_secs(1);
+        let s = RateLimiterState { value: Some(5.0) };
+        assert_eq!(cfg.next((s, 10.0, &dt)).1, 6.0);
+        assert_eq!(cfg.next((s, -10.0, &dt)).1, -10.0);
+    }
+}
@@ -1,9 +1,16 @@
 use super::*;
 use crate::fsm::*;
-use std::{collections::HashMap, io, result, time::Duration};
+use std::{
+    collections::HashMap,
+    io, result,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 /// A simple synchronous closed-loop runtime.
 #[derive(Debug, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct SyncRuntime {
     /// Loops
     pub loops: Vec<Loop>,
@@ -13,6 +20,265 @@ pub struct SyncRuntime {
     pub actions: Vec<Action>,
     /// Finite State Machines
     pub state_machines: HashMap<String, StateMachine>,
+    /// Hysteresis blocks that will be evaluated on each step.
+    pub hysteresis_blocks: Vec<HysteresisBlock>,
+    /// Counter blocks that will be evaluated on each step.
+    pub counters: Vec<CounterBlock>,
+    /// Timer blocks that will be evaluated on each step.
+    pub timers: Vec<TimerBlock>,
+    /// Statistics blocks that will be evaluated on each step.
+    pub statistics: Vec<StatisticsBlock>,
+    /// SPC blocks that will be evaluated on each step.
+    pub spc: Vec<SpcBlock>,
+    /// Plausibility blocks that will be evaluated on each step.
+    pub plausibility_checks: Vec<PlausibilityBlock>,
+    /// Trend blocks that will be evaluated on each step.
+    pub trends: Vec<TrendBlock>,
+    /// Scaling blocks that will be evaluated on each step.
+    pub scalings: Vec<ScalingBlock>,
+    /// Selector blocks that will be evaluated on each step.
+    pub selectors: Vec<SelectorBlock>,
+    /// Limiter blocks that will be evaluated on each step.
+    pub limiters: Vec<LimiterBlock>,
+    /// Function-block graphs that will be executed after the loops.
+    pub graphs: Vec<graph::GraphConfig>,
+    /// Target cycle time
+    ///
+    /// If set, the execution time of each step is measured (see [CycleState])
+    /// and steps that take longer are counted as overruns.
+    pub cycle_time: Option<Duration>,
+    /// Measure the execution time of each loop, rule
+    /// and state machine (see [Profile])
+    pub profiling: bool,
+    /// The IDs of the I/O values that are resolved
+    /// when the configuration is loaded
+    ///
+    /// The I/O values of the state are keyed by this
+    /// registry (see [io_table::IoTable::resolve]).
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub io_registry: Arc<io_table::IoRegistry>,
+}
+
+/// An event that was raised during a runtime step
+///
+/// The events of the last step are collected in [SystemState::events],
+/// e.g. to create the entries of an event journal.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum RuntimeEvent {
+    /// A plausibility check detected a new fault
+    ImplausibleSignal {
+        /// ID of the plausibility block
+        block: String,
+        /// The detected fault
+        fault: plausibility::Fault,
+    },
+    /// An SPC block detected a new non-random pattern
+    SpcRuleViolation {
+        /// ID of the SPC block
+        block: String,
+        /// The violated rule
+        rule: spc::SpcRule,
+        /// The value that violated the rule
+        value: f64,
+    },
+}
+
+/// Execution time measurement of the runtime steps
+///
+/// Use it to detect when the control logic no longer fits the cycle.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CycleState {
+    /// Number of executed steps
+    pub steps: u64,
+    /// Execution time of the last step
+    pub last: Duration,
+    /// Longest execution time of a step
+    pub worst: Duration,
+    /// The last step exceeded the target cycle time
+    pub overrun: bool,
+    /// Number of steps that exceeded the target cycle time
+    pub overruns: u64,
+    /// Execution times of the phases of the last step
+    pub phases: CyclePhases,
+    /// Execution times of the phases of the longest step
+    pub worst_phases: CyclePhases,
+}
+
+/// Execution times of the phases of a runtime step
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct CyclePhases {
+    /// Setpoint ramps and loops
+    pub loops: Duration,
+    /// Function-block graphs
+    pub graphs: Duration,
+    /// Timeouts and blocks
+    pub blocks: Duration,
+    /// Rules and their actions
+    pub rules: Duration,
+    /// State machines and their actions
+    pub state_machines: Duration,
+    /// Delayed and pulsed actions
+    pub actions: Duration,
+}
+
+impl CycleState {
+    fn record(&mut self, last: Duration, phases: CyclePhases, cycle_time: Duration) {
+        self.steps += 1;
+        self.last = last;
+        self.phases = phases;
+        if last >= self.worst {
+            self.worst = last;
+            self.worst_phases = phases;
+        }
+        self.overrun = last > cycle_time;
+        if self.overrun {
+            self.overruns += 1;
+        }
+    }
+}
+
+/// Execution times of the components of the runtime
+///
+/// Use it to find the loops, rules or state machines that
+/// consume the cycle budget (see [SyncRuntime::profiling]).
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Profile {
+    /// Loops including their input preprocessing
+    pub loops: HashMap<String, ExecutionTime>,
+    /// Rule conditions and the actions of the rules
+    pub rules: HashMap<String, ExecutionTime>,
+    /// State machines including their actions
+    pub state_machines: HashMap<String, ExecutionTime>,
+}
+
+/// Aggregated execution time of a component
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ExecutionTime {
+    /// Number of steps in which the component was executed
+    pub steps: u64,
+    /// Execution time of the last step
+    pub last: Duration,
+    /// Longest execution time of a step
+    pub worst: Duration,
+    /// Sum of the execution times
+    pub total: Duration,
+}
+
+/// A profiled component of the runtime
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Component {
+    Loop(String),
+    Rule(String),
+    StateMachine(String),
+}
+
+impl ExecutionTime {
+    fn record(&mut self, last: Duration) {
+        self.steps += 1;
+        self.last = last;
+        self.worst = self.worst.max(last);
+        self.total += last;
+    }
+
+    /// The average execution time of a step.
+    pub fn mean(&self) -> Duration {
+        match u32::try_from(self.steps) {
+            Ok(0) => Duration::ZERO,
+            Ok(steps) => self.total / steps,
+            Err(_) => Duration::from_secs_f64(self.total.as_secs_f64() / self.steps as f64),
+        }
+    }
+}
+
+impl Profile {
+    fn record(&mut self, component: Component, last: Duration) {
+        let (times, id) = match component {
+            Component::Loop(id) => (&mut self.loops, id),
+            Component::Rule(id) => (&mut self.rules, id),
+            Component::StateMachine(id) => (&mut self.state_machines, id),
+        };
+        times.entry(id).or_default().record(last);
+    }
+
+    /// All components, the one with the longest total execution time first.
+    pub fn report(&self) -> Vec<(Component, ExecutionTime)> {
+        let mut report = self
+            .loops
+            .iter()
+            .map(|(id, t)| (Component::Loop(id.clone()), *t))
+            .chain(
+                self.rules
+                    .iter()
+                    .map(|(id, t)| (Component::Rule(id.clone()), *t)),
+            )
+            .chain(
+                self.state_machines
+                    .iter()
+                    .map(|(id, t)| (Component::StateMachine(id.clone()), *t)),
+            )
+            .collect::<Vec<_>>();
+        report.sort_by(|(a_id, a), (b_id, b)| b.total.cmp(&a.total).then_with(|| a_id.cmp(b_id)));
+        report
+    }
+}
+
+/// Collects the execution times of the components within a step.
+#[derive(Default)]
+struct Timings(HashMap<Component, Duration>);
+
+impl Timings {
+    fn measure<T>(
+        timings: &mut Option<Self>,
+        component: impl FnOnce() -> Component,
+        f: impl FnOnce() -> T,
+    ) -> T {
+        match timings {
+            Some(Timings(t)) => {
+                let start = Instant::now();
+                let res = f();
+                *t.entry(component()).or_default() += start.elapsed();
+                res
+            }
+            None => f(),
+        }
+    }
+}
+
+/// Measures the time between laps.
+struct Stopwatch {
+    start: Instant,
+    lap: Instant,
+}
+
+impl Stopwatch {
+    fn start() -> Self {
+        let now = Instant::now();
+        Stopwatch {
+            start: now,
+            lap: now,
+        }
+    }
+
+    fn lap(&mut self) -> Duration {
+        let now = Instant::now();
+        let lap = now - self.lap;
+        self.lap = now;
+        lap
+    }
+
+    fn elapsed(&self) -> Duration {
+        self.start.elapsed()
+    }
 }
 
 /// A runtime error
@@ -24,32 +290,115 @@ pub struct Error<T> {
 
 type Result<T> = result::Result<T, Error<T>>;
 
+/// The changes of a reconfiguration (see [SyncRuntime::reconfigure])
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Reconfiguration {
+    /// IDs of the added loops
+    pub added_loops: Vec<String>,
+    /// IDs of the removed loops
+    pub removed_loops: Vec<String>,
+    /// IDs of the kept loops whose controller state was reset
+    /// because the controller type changed
+    pub reset_loops: Vec<String>,
+    /// IDs of the added rules
+    pub added_rules: Vec<String>,
+    /// IDs of the removed rules
+    pub removed_rules: Vec<String>,
+}
+
+/// Check if a controller state belongs to the controller configuration.
+fn controller_matches(cfg: &ControllerConfig, state: &ControllerState) -> bool {
+    matches!(
+        (cfg, state),
+        (ControllerConfig::Pid(_), ControllerState::Pid(_))
+            | (ControllerConfig::ScheduledPid(_), ControllerState::Pid(_))
+            | (ControllerConfig::BangBang(_), ControllerState::BangBang(_))
+            | (
+                ControllerConfig::RelayTuner(_),
+                ControllerState::RelayTuner(_)
+            )
+            | (ControllerConfig::Cascade(_), ControllerState::Cascade(_))
+            | (
+                ControllerConfig::ThreePoint(_),
+                ControllerState::ThreePoint(_)
+            )
+            | (ControllerConfig::Fuzzy(_), ControllerState::Fuzzy(_))
+            | (ControllerConfig::Mpc(_), ControllerState::Mpc(_))
+    )
+}
+
 //TODO: tidy up!
 impl<'a> PureController<(&'a SystemState, &'a Duration), Result<SystemState>> for SyncRuntime {
     fn next(&self, input: (&SystemState, &Duration)) -> Result<SystemState> {
         let (orig_state, dt) = input;
+        let mut stopwatch = Stopwatch::start();
+        let mut phases = CyclePhases::default();
         let mut state = orig_state.clone();
+        if !self.io_registry.is_empty() {
+            state.io.resolve(&self.io_registry);
+        }
+        state.events.clear();
         let mut errors = vec![];
+        let mut timings = self.profiling.then(Timings::default);
 
         for (id, s) in &orig_state.setpoints {
-            if self.loops.iter().any(|l| l.id == *id) {
+            if let Some(l) = self.loops.iter().find(|l| l.id == *id) {
                 if let Some(c) = orig_state.controllers.get(id) {
                     if let Value::Decimal(v) = s {
                         match c {
                             ControllerState::Pid(pid) => {
                                 let mut pid = *pid;
-                                pid.target = *v;
+                                pid.target = l.ramp_setpoint(pid.target, *v, dt);
                                 state
                                     .controllers
                                     .insert(id.clone(), ControllerState::Pid(pid));
                             }
                             ControllerState::BangBang(bb) => {
                                 let mut bb = *bb;
-                                bb.threshold = *v;
+                                bb.threshold = l.ramp_setpoint(bb.threshold, *v, dt);
                                 state
                                     .controllers
                                     .insert(id.clone(), ControllerState::BangBang(bb));
                             }
+                            ControllerState::RelayTuner(tuner) => {
+                                let mut tuner = *tuner;
+                                tuner.target = l.ramp_setpoint(tuner.target, *v, dt);
+                                state
+                                    .controllers
+                                    .insert(id.clone(), ControllerState::RelayTuner(tuner));
+                            }
+                            ControllerState::Cascade(cascade) => {
+                                let mut cascade = *cascade;
+                                cascade.outer.target =
+                                    l.ramp_setpoint(cascade.outer.target, *v, dt);
+                                state
+                                    .controllers
+                                    .insert(id.clone(), ControllerState::Cascade(cascade));
+                            }
+                            ControllerState::ThreePoint(tp) => {
+                                let mut tp = *tp;
+                                tp.pid.target = l.ramp_setpoint(tp.pid.target, *v, dt);
+                                state
+                                    .controllers
+                                    .insert(id.clone(), ControllerState::ThreePoint(tp));
+                            }
+                            ControllerState::Fuzzy(fuzzy) => {
+                                let mut fuzzy = *fuzzy;
+                                fuzzy.target = l.ramp_setpoint(fuzzy.target, *v, dt);
+                                state
+                                    .controllers
+                                    .insert(id.clone(), ControllerState::Fuzzy(fuzzy));
+                            }
+                            ControllerState::Mpc(mpc) => {
+                                // The setpoint is the target of the first controlled output
+                                let mut mpc = mpc.clone();
+                                if let Some(target) = mpc.targets.first_mut() {
+                                    *target = l.ramp_setpoint(*target, *v, dt);
+                                }
+                                state
+                                    .controllers
+                                    .insert(id.clone(), ControllerState::Mpc(mpc));
+                            }
                         }
                     }
                 }
@@ -63,29 +412,32 @@ impl<'a> PureController<(&'a SystemState, &'a Duration), Result<SystemState>> fo
             .iter()
             .filter(|l| !ignore.iter().any(|x| *x == l.id))
         {
-            if state.controllers.get(&l.id).is_none() {
-                self.initialize_controller_state(l, &mut state);
+            let res = Timings::measure(
+                &mut timings,
+                || Component::Loop(l.id.clone()),
+                || self.update_loop(l, &mut state, dt),
+            );
+            if let Err(err) = res {
+                errors.push(err);
             }
+        }
+        phases.loops = stopwatch.lap();
 
-            let res = l.next((
-                state
-                    .controllers
-                    .get(&l.id)
-                    .expect("The controller state was not initialized"),
-                &state.io,
-                dt,
-            ));
+        for g in &self.graphs {
+            let res = match state.graphs.get(&g.id) {
+                Some(s) => g.run(s, &mut state.io, dt),
+                None => g.run(&g.initial_state(), &mut state.io, dt),
+            };
             match res {
-                Ok(x) => {
-                    let (new_controller, new_io) = x;
-                    state.io = new_io;
-                    state.controllers.insert(l.id.clone(), new_controller);
+                Ok(new_graph) => {
+                    state.graphs.insert(g.id.clone(), new_graph);
                 }
                 Err(err) => {
                     errors.push(err);
                 }
             }
         }
+        phases.graphs = stopwatch.lap();
 
         for (id, t) in &orig_state.timeouts {
             if let Value::Timeout(t) = t {
@@ -101,7 +453,57 @@ impl<'a> PureController<(&'a SystemState, &'a Duration), Result<SystemState>> fo
                 }
             }
         }
-        match self.rules_state(&state) {
+        for b in &self.hysteresis_blocks {
+            if let Err(err) = self.update_hysteresis_block(b, &mut state, dt) {
+                errors.push(err);
+            }
+        }
+        for b in &self.counters {
+            if let Err(err) = self.update_counter_block(b, &mut state, dt) {
+                errors.push(err);
+            }
+        }
+        for b in &self.timers {
+            self.update_timer_block(b, &mut state, dt);
+        }
+        for b in &self.statistics {
+            if let Err(err) = self.update_statistics_block(b, &mut state) {
+                errors.push(err);
+            }
+        }
+        for b in &self.spc {
+            if let Err(err) = self.update_spc_block(b, &mut state) {
+                errors.push(err);
+            }
+        }
+        for b in &self.plausibility_checks {
+            if let Err(err) = self.update_plausibility_block(b, &mut state, dt) {
+                errors.push(err);
+            }
+        }
+        for b in &self.trends {
+            if let Err(err) = self.update_trend_block(b, &mut state, dt) {
+                errors.push(err);
+            }
+        }
+        for b in &self.scalings {
+            if let Err(err) = self.update_scaling_block(b, &mut state) {
+                errors.push(err);
+            }
+        }
+        for b in &self.selectors {
+            if let Err(err) = self.update_selector_block(b, &mut state) {
+                errors.push(err);
+            }
+        }
+        for b in &self.limiters {
+            if let Err(err) = self.update_limiter_block(b, &mut state) {
+                errors.push(err);
+            }
+        }
+        phases.blocks = stopwatch.lap();
+
+        match self.rules_state(&state, &mut timings) {
             Ok(rules) => {
                 state.rules = rules;
             }
@@ -113,37 +515,91 @@ impl<'a> PureController<(&'a SystemState, &'a Duration), Result<SystemState>> fo
             }
         }
 
-        let rule_actions = state
+        let triggered_rules = state
             .rules
             .iter()
-            .filter(|(_, active)| **active)
-            .filter_map(|(r_id, _)| {
+            .filter_map(|(r_id, active)| {
                 self.rules
                     .iter()
                     .find(|r| r.id == *r_id)
-                    .map(|r| &r.actions)
+                    .map(|r| (r, *active))
             })
+            .filter(|(r, active)| {
+                let previous = orig_state.rules.get(&r.id).copied().unwrap_or(false);
+                r.trigger.is_triggered(previous, *active)
+            })
+            .map(|(r, _)| r)
             .collect::<Vec<_>>();
 
-        for x in rule_actions {
-            self.apply_actions(x, orig_state, &mut state);
+        for r in &triggered_rules {
+            Timings::measure(
+                &mut timings,
+                || Component::Rule(r.id.clone()),
+                || self.apply_actions(&r.actions, orig_state, &mut state),
+            );
         }
+        phases.rules = stopwatch.lap();
 
         let mut actions = vec![];
 
         for (m_id, machine) in &self.state_machines {
-            if let Some((new_fsm_state, fsm_actions)) =
-                machine.next((state.state_machines.get(m_id).map(|x| &**x), &state))
-            {
-                if !fsm_actions.is_empty() {
-                    actions.push(fsm_actions);
-                }
-                state.state_machines.insert(m_id.clone(), new_fsm_state);
-            }
+            Timings::measure(
+                &mut timings,
+                || Component::StateMachine(m_id.clone()),
+                || {
+                    let started = state.state_machine_data.contains_key(m_id);
+                    let mut data = state.state_machine_data.remove(m_id).unwrap_or_default();
+                    let current = state
+                        .state_machines
+                        .get(m_id)
+                        .cloned()
+                        .unwrap_or_else(|| machine.enter(&machine.initial, &data.history));
+                    if !started {
+                        actions.push((m_id, machine.entry_actions(&current)));
+                    }
+                    machine.tick(&current, &mut data, dt);
+                    let fsm_state = state.state_machines.get(m_id).map(|x| &**x);
+                    if let Some((new_fsm_state, fsm_actions)) =
+                        machine.next((fsm_state, &data, &state))
+                    {
+                        if !fsm_actions.is_empty() {
+                            actions.push((m_id, fsm_actions));
+                        }
+                        machine.transit(&current, &new_fsm_state, &mut data);
+                        state.state_machines.insert(m_id.clone(), new_fsm_state);
+                    }
+                    let active = state.state_machines.get(m_id).unwrap_or(&current);
+                    actions.push((m_id, machine.during_actions(active)));
+                    state.state_machine_data.insert(m_id.clone(), data);
+                },
+            );
+        }
+
+        for (m_id, x) in &actions {
+            Timings::measure(
+                &mut timings,
+                || Component::StateMachine(m_id.to_string()),
+                || self.apply_actions(x, orig_state, &mut state),
+            );
         }
+        phases.state_machines = stopwatch.lap();
+
+        let requested = triggered_rules
+            .iter()
+            .map(|r| &r.actions)
+            .chain(actions.iter().map(|(_, x)| x))
+            .flatten()
+            .collect::<Vec<_>>();
+        self.update_timed_actions(&requested, orig_state, &mut state, dt);
+        phases.actions = stopwatch.lap();
 
-        for x in actions {
-            self.apply_actions(&x, orig_state, &mut state);
+        if let Some(cycle_time) = self.cycle_time {
+            state.cycle.record(stopwatch.elapsed(), phases, cycle_time);
+        }
+        if let Some(Timings(timings)) = timings {
+            for (component, last) in timings {
+                state.profile.record(component, last);
+            }
         }
 
         if !errors.is_empty() {
@@ -157,12 +613,142 @@ impl<'a> PureController<(&'a SystemState, &'a Duration), Result<SystemState>> fo
 }
 
 impl SyncRuntime {
+    /// Create a snapshot of the state, e.g. to persist it periodically.
+    ///
+    /// The inputs are omitted because they are read again after a restart.
+    /// With the `serde` feature the snapshot can be (de-)serialized.
+    pub fn snapshot(&self, state: &SystemState) -> SystemState {
+        let mut snapshot = self.restore(state.clone());
+        snapshot.io.inputs.clear();
+        snapshot
+    }
+
+    /// Replace the configuration of a running system.
+    ///
+    /// The state is carried over as by [SyncRuntime::restore],
+    /// so modified loops keep their controller states and
+    /// continue bumplessly unless the controller type changed.
+    pub fn reconfigure(
+        &mut self,
+        runtime: SyncRuntime,
+        state: SystemState,
+    ) -> (SystemState, Reconfiguration) {
+        let previous = std::mem::replace(self, runtime);
+        let missing = |ids: &[&String], other: &[&String]| {
+            ids.iter()
+                .filter(|id| !other.contains(id))
+                .map(|id| id.to_string())
+                .collect::<Vec<_>>()
+        };
+        let loops = self.loops.iter().map(|l| &l.id).collect::<Vec<_>>();
+        let previous_loops = previous.loops.iter().map(|l| &l.id).collect::<Vec<_>>();
+        let rules = self.rules.iter().map(|r| &r.id).collect::<Vec<_>>();
+        let previous_rules = previous.rules.iter().map(|r| &r.id).collect::<Vec<_>>();
+        let reset_loops = self
+            .loops
+            .iter()
+            .filter(|l| previous_loops.contains(&&l.id))
+            .filter(|l| {
+                state
+                    .controllers
+                    .get(&l.id)
+                    .map(|c| !controller_matches(&l.controller, c))
+                    .unwrap_or(false)
+            })
+            .map(|l| l.id.clone())
+            .collect();
+        let changes = Reconfiguration {
+            added_loops: missing(&loops, &previous_loops),
+            removed_loops: missing(&previous_loops, &loops),
+            reset_loops,
+            added_rules: missing(&rules, &previous_rules),
+            removed_rules: missing(&previous_rules, &rules),
+        };
+        (self.restore(state), changes)
+    }
+
+    /// Restore the state from a snapshot for a warm start.
+    ///
+    /// States of loops, rules, blocks, graphs and state machines
+    /// that are no longer configured are discarded, and so are
+    /// controller states that don't match the configured controller.
+    /// The missing states are initialized in the next step.
+    pub fn restore(&self, snapshot: SystemState) -> SystemState {
+        let mut state = snapshot;
+        let loop_ids = || self.loops.iter().map(|l| &l.id);
+        let has_loop = |id: &String| loop_ids().any(|l| l == id);
+        state.controllers.retain(|id, c| {
+            self.loops
+                .iter()
+                .any(|l| l.id == *id && controller_matches(&l.controller, c))
+        });
+        state.filters.retain(|id, _| has_loop(id));
+        state.preprocessing.retain(|id, _| has_loop(id));
+        state.modes.retain(|id, _| has_loop(id));
+        state.inactive_loops.retain(has_loop);
+        state.profile.loops.retain(|id, _| has_loop(id));
+        state
+            .profile
+            .rules
+            .retain(|id, _| self.rules.iter().any(|r| r.id == *id));
+        state
+            .profile
+            .state_machines
+            .retain(|id, _| self.state_machines.contains_key(id));
+        state
+            .rules
+            .retain(|id, _| self.rules.iter().any(|r| r.id == *id));
+        state
+            .actions
+            .retain(|id, _| self.actions.iter().any(|a| a.id == *id));
+        state
+            .state_machines
+            .retain(|id, _| self.state_machines.contains_key(id));
+        state
+            .state_machine_data
+            .retain(|id, _| self.state_machines.contains_key(id));
+        state
+            .hysteresis_blocks
+            .retain(|id, _| self.hysteresis_blocks.iter().any(|b| b.id == *id));
+        state
+            .counters
+            .retain(|id, _| self.counters.iter().any(|b| b.id == *id));
+        state
+            .timers
+            .retain(|id, _| self.timers.iter().any(|b| b.id == *id));
+        state
+            .statistics
+            .retain(|id, _| self.statistics.iter().any(|b| b.id == *id));
+        state
+            .spc
+            .retain(|id, _| self.spc.iter().any(|b| b.id == *id));
+        state
+            .plausibility_checks
+            .retain(|id, _| self.plausibility_checks.iter().any(|b| b.id == *id));
+        state
+            .trends
+            .retain(|id, _| self.trends.iter().any(|b| b.id == *id));
+        state
+            .graphs
+            .retain(|id, _| self.graphs.iter().any(|g| g.id == *id));
+        state
+    }
+
     /// Check for active [Rule]s.
-    fn rules_state(&self, state: &SystemState) -> Result<HashMap<String, bool>> {
+    fn rules_state(
+        &self,
+        state: &SystemState,
+        timings: &mut Option<Timings>,
+    ) -> Result<HashMap<String, bool>> {
         let mut rules_state = HashMap::new();
         let mut errors = vec![];
         for r in &self.rules {
-            match r.condition.eval(state) {
+            let res = Timings::measure(
+                timings,
+                || Component::Rule(r.id.clone()),
+                || r.condition.eval(state),
+            );
+            match res {
                 Ok(r_state) => {
                     rules_state.insert(r.id.clone(), r_state);
                 }
@@ -180,6 +766,336 @@ impl SyncRuntime {
         Ok(rules_state)
     }
 
+    /// Compare the input of a [HysteresisBlock] and
+    /// write the result to the memory.
+    fn update_hysteresis_block(
+        &self,
+        b: &HysteresisBlock,
+        state: &mut SystemState,
+        dt: &Duration,
+    ) -> io::Result<()> {
+        let x = match state.eval_source(&b.input).as_deref() {
+            Some(Value::Decimal(x)) => *x,
+            Some(Value::Integer(x)) => *x as f64,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid hysteresis input: a numeric value is required",
+                ));
+            }
+        };
+        let s = state
+            .hysteresis_blocks
+            .get(&b.id)
+            .copied()
+            .unwrap_or_default();
+        let s = b.hysteresis.next((s, x, dt));
+        state.hysteresis_blocks.insert(b.id.clone(), s);
+        state.io.mem.set(&b.id, s.output.into());
+        Ok(())
+    }
+
+    fn update_counter_block(
+        &self,
+        b: &CounterBlock,
+        state: &mut SystemState,
+        dt: &Duration,
+    ) -> io::Result<()> {
+        let on = match state.eval_source(&b.input).as_deref() {
+            Some(Value::Bit(on)) => *on,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid counter input: a boolean value is required",
+                ));
+            }
+        };
+        let s = state.counters.get(&b.id).copied().unwrap_or_default();
+        let s = b.counter.next((s, on, dt));
+        state.counters.insert(b.id.clone(), s);
+        state
+            .io
+            .mem
+            .insert(format!("{}.hours", b.id), s.hours().into());
+        state
+            .io
+            .mem
+            .insert(format!("{}.cycles", b.id), (s.cycles as i64).into());
+        state.io.mem.insert(
+            format!("{}.service", b.id),
+            b.counter.service_due(&s).into(),
+        );
+        Ok(())
+    }
+
+    /// Update the timer of a timer block and
+    /// write its output and elapsed time to the memory.
+    fn update_timer_block(&self, b: &TimerBlock, state: &mut SystemState, dt: &Duration) {
+        let s = state.timers.get(&b.id).copied().unwrap_or_default();
+        let s = b.timer.next((s, s.input, dt));
+        state.timers.insert(b.id.clone(), s);
+        state.io.mem.set(&b.id, s.output.into());
+        state
+            .io
+            .mem
+            .insert(format!("{}.elapsed", b.id), s.elapsed.as_secs_f64().into());
+    }
+
+    fn update_statistics_block(
+        &self,
+        b: &StatisticsBlock,
+        state: &mut SystemState,
+    ) -> io::Result<()> {
+        let x = match state.eval_source(&b.input).as_deref() {
+            Some(Value::Decimal(x)) => *x,
+            Some(Value::Integer(x)) => *x as f64,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid statistics input: a numeric value is required",
+                ));
+            }
+        };
+        let s = state.statistics.remove(&b.id).unwrap_or_default();
+        let (s, summary) = b.statistics.next((s, x));
+        state.statistics.insert(b.id.clone(), s);
+        for (name, v) in [
+            ("mean", summary.mean),
+            ("stddev", summary.stddev),
+            ("min", summary.min),
+            ("max", summary.max),
+            ("ewma", summary.ewma),
+            ("ewm_stddev", summary.ewm_stddev),
+        ] {
+            state.io.mem.insert(format!("{}.{name}", b.id), v.into());
+        }
+        Ok(())
+    }
+
+    fn update_spc_block(&self, b: &SpcBlock, state: &mut SystemState) -> io::Result<()> {
+        let x = match state.eval_source(&b.input).as_deref() {
+            Some(Value::Decimal(x)) => *x,
+            Some(Value::Integer(x)) => *x as f64,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid SPC input: a numeric value is required",
+                ));
+            }
+        };
+        let s = state.spc.remove(&b.id).unwrap_or_default();
+        let (s, rules) = b.spc.next((s, x));
+        state.events.extend(
+            rules
+                .into_iter()
+                .map(|rule| RuntimeEvent::SpcRuleViolation {
+                    block: b.id.clone(),
+                    rule,
+                    value: x,
+                }),
+        );
+        state.io.mem.set(&b.id, (!s.violations.is_empty()).into());
+        state.spc.insert(b.id.clone(), s);
+        Ok(())
+    }
+
+    fn update_plausibility_block(
+        &self,
+        b: &PlausibilityBlock,
+        state: &mut SystemState,
+        dt: &Duration,
+    ) -> io::Result<()> {
+        let x = match state.eval_source(&b.input).as_deref() {
+            Some(Value::Decimal(x)) => *x,
+            Some(Value::Integer(x)) => *x as f64,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid plausibility input: a numeric value is required",
+                ));
+            }
+        };
+        let s = state.plausibility_checks.remove(&b.id).unwrap_or_default();
+        let (s, faults) = b.plausibility.next((s, x, dt));
+        state.events.extend(
+            faults
+                .into_iter()
+                .map(|fault| RuntimeEvent::ImplausibleSignal {
+                    block: b.id.clone(),
+                    fault,
+                }),
+        );
+        let mem = &mut state.io.mem;
+        mem.set(&b.id, s.is_plausible().into());
+        for (name, fault) in [
+            ("stuck", plausibility::Fault::Stuck),
+            ("spike", plausibility::Fault::Spike),
+        ] {
+            mem.insert(format!("{}.{name}", b.id), s.faults.contains(&fault).into());
+        }
+        state.plausibility_checks.insert(b.id.clone(), s);
+        Ok(())
+    }
+
+    fn update_trend_block(
+        &self,
+        b: &TrendBlock,
+        state: &mut SystemState,
+        dt: &Duration,
+    ) -> io::Result<()> {
+        let x = match state.eval_source(&b.input).as_deref() {
+            Some(Value::Decimal(x)) => *x,
+            Some(Value::Integer(x)) => *x as f64,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid trend input: a numeric value is required",
+                ));
+            }
+        };
+        let s = state.trends.remove(&b.id).unwrap_or_default();
+        let (s, slope, t) = b.trend.next((s, x, dt));
+        state.trends.insert(b.id.clone(), s);
+        let mem = &mut state.io.mem;
+        mem.insert(format!("{}.slope", b.id), slope.into());
+        mem.insert(
+            format!("{}.rising", b.id),
+            (t == trend::Trend::Rising).into(),
+        );
+        mem.insert(
+            format!("{}.falling", b.id),
+            (t == trend::Trend::Falling).into(),
+        );
+        Ok(())
+    }
+
+    fn update_scaling_block(&self, b: &ScalingBlock, state: &mut SystemState) -> io::Result<()> {
+        b.scaling.validate()?;
+        let x = match state.eval_source(&b.input).as_deref() {
+            Some(Value::Decimal(x)) => *x,
+            Some(Value::Integer(x)) => *x as f64,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid scaling input: a numeric value is required",
+                ));
+            }
+        };
+        let (y, out_of_range) = b.scaling.next(x);
+        let mem = &mut state.io.mem;
+        mem.set(&b.id, y.into());
+        mem.insert(format!("{}.out_of_range", b.id), out_of_range.into());
+        Ok(())
+    }
+
+    fn update_selector_block(&self, b: &SelectorBlock, state: &mut SystemState) -> io::Result<()> {
+        let x = b
+            .inputs
+            .iter()
+            .map(|src| match state.eval_source(src).as_deref() {
+                Some(Value::Decimal(x)) => Ok(*x),
+                Some(Value::Integer(x)) => Ok(*x as f64),
+                _ => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid selector input: a numeric value is required",
+                )),
+            })
+            .collect::<io::Result<Vec<_>>>()?;
+        let mem = &mut state.io.mem;
+        mem.set(&b.id, b.selector.next(&x).into());
+        let selected = format!("{}.selected", b.id);
+        match b.selector.selected(&x) {
+            Some(idx) => {
+                mem.insert(selected, (idx as i64).into());
+            }
+            None => {
+                mem.remove(&selected);
+            }
+        }
+        Ok(())
+    }
+
+    fn update_limiter_block(&self, b: &LimiterBlock, state: &mut SystemState) -> io::Result<()> {
+        let x = match state.eval_source(&b.input).as_deref() {
+            Some(Value::Decimal(x)) => *x,
+            Some(Value::Integer(x)) => *x as f64,
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    "Invalid limiter input: a numeric value is required",
+                ));
+            }
+        };
+        let (y, status) = b.limiter.next(x);
+        let mem = &mut state.io.mem;
+        mem.set(&b.id, y.into());
+        mem.insert(
+            format!("{}.low", b.id),
+            (status == limiter::LimitStatus::Low).into(),
+        );
+        mem.insert(
+            format!("{}.high", b.id),
+            (status == limiter::LimitStatus::High).into(),
+        );
+        Ok(())
+    }
+
+    /// Run the controller of a loop.
+    fn update_loop(&self, l: &Loop, state: &mut SystemState, dt: &Duration) -> io::Result<()> {
+        if state.controllers.get(&l.id).is_none() {
+            self.initialize_controller_state(l, state);
+        }
+
+        // The outputs are held if the input is rejected
+        let filtered = self.preprocess_input(l, state, dt)?;
+        let new_controller = l.control(
+            (
+                state
+                    .controllers
+                    .get(&l.id)
+                    .expect("The controller state was not initialized"),
+                state.modes.get(&l.id).copied().unwrap_or_default(),
+                &mut state.io,
+                dt,
+            ),
+            filtered,
+        )?;
+        state.controllers.insert(l.id.clone(), new_controller);
+        Ok(())
+    }
+
+    /// Apply the input preprocessing and the filters of a loop.
+    ///
+    /// Returns the conditioned process value that
+    /// is only visible to the controller or an error
+    /// if the value was rejected.
+    fn preprocess_input(
+        &self,
+        l: &Loop,
+        state: &mut SystemState,
+        dt: &Duration,
+    ) -> io::Result<Option<f64>> {
+        if l.preprocessing.is_empty() && l.filters.is_empty() {
+            return Ok(None);
+        }
+        let Ok(mut x) = l.process_value(&state.io) else {
+            return Ok(None);
+        };
+        if !l.preprocessing.is_empty() {
+            let steps = state.preprocessing.remove(&l.id).unwrap_or_default();
+            let (steps, res) = l.preprocessing.next((steps, x, dt));
+            state.preprocessing.insert(l.id.clone(), steps);
+            x = res?;
+        }
+        if !l.filters.is_empty() {
+            let filters = state.filters.remove(&l.id).unwrap_or_default();
+            let (filters, y) = l.filters.next((filters, x, dt));
+            state.filters.insert(l.id.clone(), filters);
+            x = y;
+        }
+        Ok(Some(x))
+    }
+
     fn initialize_controller_state(&self, l: &Loop, state: &mut SystemState) {
         match l.controller {
             ControllerConfig::Pid(ref cfg) => {
@@ -191,6 +1107,15 @@ impl SyncRuntime {
                     .controllers
                     .insert(l.id.clone(), ControllerState::Pid(s));
             }
+            ControllerConfig::ScheduledPid(ref cfg) => {
+                let s = pid::PidState {
+                    target: cfg.pid.default_target,
+                    ..Default::default()
+                };
+                state
+                    .controllers
+                    .insert(l.id.clone(), ControllerState::Pid(s));
+            }
             ControllerConfig::BangBang(ref cfg) => {
                 let s = bang_bang::BangBangState {
                     threshold: cfg.default_threshold,
@@ -200,56 +1125,159 @@ impl SyncRuntime {
                     .controllers
                     .insert(l.id.clone(), ControllerState::BangBang(s));
             }
+            ControllerConfig::RelayTuner(ref cfg) => {
+                let s = pid::RelayTunerState {
+                    target: cfg.default_target,
+                    ..Default::default()
+                };
+                state
+                    .controllers
+                    .insert(l.id.clone(), ControllerState::RelayTuner(s));
+            }
+            ControllerConfig::Cascade(ref cfg) => {
+                let s = cascade::CascadeState {
+                    outer: pid::PidState {
+                        target: cfg.outer.default_target,
+                        ..Default::default()
+                    },
+                    inner: pid::PidState {
+                        target: cfg.inner.default_target,
+                        ..Default::default()
+                    },
+                };
+                state
+                    .controllers
+                    .insert(l.id.clone(), ControllerState::Cascade(s));
+            }
+            ControllerConfig::ThreePoint(ref cfg) => {
+                let s = three_point::ThreePointState {
+                    pid: pid::PidState {
+                        target: cfg.pid.default_target,
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                };
+                state
+                    .controllers
+                    .insert(l.id.clone(), ControllerState::ThreePoint(s));
+            }
+            ControllerConfig::Fuzzy(ref cfg) => {
+                let s = fuzzy::FuzzyState {
+                    target: cfg.default_target,
+                    ..Default::default()
+                };
+                state
+                    .controllers
+                    .insert(l.id.clone(), ControllerState::Fuzzy(s));
+            }
+            ControllerConfig::Mpc(ref cfg) => {
+                state
+                    .controllers
+                    .insert(l.id.clone(), ControllerState::Mpc(cfg.initial_state()));
+            }
         }
     }
 
     fn apply_actions(&self, actions: &[String], orig_state: &SystemState, state: &mut SystemState) {
         for a_id in actions {
             if let Some(a) = self.actions.iter().find(|a| a.id == *a_id) {
-                for (k, src) in &a.outputs {
-                    if let Some(v) = orig_state.get(src) {
-                        state.io.outputs.insert(k.clone(), v.clone());
-                    }
+                // Timed actions are applied in `update_timed_actions`
+                if !a.is_timed() {
+                    self.apply_action(a, orig_state, state);
                 }
-                for (k, src) in &a.setpoints {
-                    if let Some(v) = orig_state.get(src) {
-                        state.setpoints.insert(k.clone(), v.clone());
-                    }
-                }
-                for (k, src) in &a.memory {
-                    if let Some(v) = orig_state.get(src) {
-                        state.io.mem.insert(k.clone(), v.clone());
-                    }
-                }
-                for (id, ctl) in &a.controllers {
-                    if ctl.reset {
-                        state.controllers.remove(id);
-                        if let Some(l) = self.loops.iter().find(|l| l.id == *id) {
-                            self.initialize_controller_state(l, state);
-                        }
-                    }
-                    if let Some(act) = ctl.active {
-                        if act {
-                            if let Some(idx) = state.inactive_loops.iter().position(|x| x == id) {
-                                state.inactive_loops.remove(idx);
-                            }
-                        } else if !state.inactive_loops.iter().any(|l| l == id) {
-                            state.inactive_loops.push(id.to_string());
-                        }
+            }
+        }
+    }
+
+    /// Update the timers of the timed actions and apply the active ones.
+    fn update_timed_actions(
+        &self,
+        requested: &[&String],
+        orig_state: &SystemState,
+        state: &mut SystemState,
+        dt: &Duration,
+    ) {
+        for a in self.actions.iter().filter(|a| a.is_timed()) {
+            let mut s = state.actions.remove(&a.id).unwrap_or_default();
+            let was_active = s.active;
+            if s.update(a, requested.contains(&&a.id), dt) {
+                self.apply_action(a, orig_state, state);
+            } else if was_active {
+                self.apply_actions(&a.release, orig_state, state);
+            }
+            if s != ActionState::default() {
+                state.actions.insert(a.id.clone(), s);
+            }
+        }
+    }
+
+    fn apply_action(&self, a: &Action, orig_state: &SystemState, state: &mut SystemState) {
+        for (k, src) in &a.outputs {
+            if let Some(v) = orig_state.eval_source(src) {
+                state.io.outputs.set(k, v.into_owned());
+            }
+        }
+        for (k, src) in &a.setpoints {
+            if let Some(v) = orig_state.eval_source(src) {
+                state.setpoints.insert(k.clone(), v.into_owned());
+            }
+        }
+        for (k, src) in &a.memory {
+            if let Some(v) = orig_state.eval_source(src) {
+                state.io.mem.set(k, v.into_owned());
+            }
+        }
+        for (id, ctl) in &a.controllers {
+            if ctl.reset {
+                state.controllers.remove(id);
+                if let Some(l) = self.loops.iter().find(|l| l.id == *id) {
+                    self.initialize_controller_state(l, state);
+                }
+            }
+            if let Some(mode) = ctl.mode {
+                state.modes.insert(id.clone(), mode);
+            }
+            if let Some(act) = ctl.active {
+                if act {
+                    if let Some(idx) = state.inactive_loops.iter().position(|x| x == id) {
+                        state.inactive_loops.remove(idx);
                     }
+                } else if !state.inactive_loops.iter().any(|l| l == id) {
+                    state.inactive_loops.push(id.to_string());
                 }
-                for (id, t) in &a.timeouts {
-                    match t {
-                        Some(t) => {
-                            if state.timeouts.get(id).is_none() {
-                                state.timeouts.insert(id.clone(), (*t).into());
-                            }
-                        }
-                        None => {
-                            state.timeouts.remove(id);
-                        }
+            }
+        }
+        for (id, c) in &a.counters {
+            let s = state.counters.entry(id.clone()).or_default();
+            match c {
+                CounterAction::Reset => {
+                    s.run_time = Duration::ZERO;
+                    s.cycles = 0;
+                }
+                CounterAction::Preset { run_time, cycles } => {
+                    s.run_time = *run_time;
+                    s.cycles = *cycles;
+                }
+            }
+        }
+        for (id, t) in &a.timers {
+            let s = state.timers.entry(id.clone()).or_default();
+            match t {
+                TimerAction::Start => s.input = true,
+                TimerAction::Stop => s.input = false,
+                TimerAction::Reset => *s = timer::TimerState::default(),
+            }
+        }
+        for (id, t) in &a.timeouts {
+            match t {
+                Some(t) => {
+                    if state.timeouts.get(id).is_none() {
+                        state.timeouts.insert(id.clone(), (*t).into());
                     }
                 }
+                None => {
+                    state.timeouts.remove(id);
+                }
             }
         }
     }
@@ -258,7 +1286,10 @@ impl SyncRuntime {
 #[cfg(test)]
 mod tests {
 
-    use super::{super::*, bang_bang::*, pid::*, *};
+    use super::{
+        super::*, bang_bang::*, counter, filter::*, graph::*, hysteresis::*, pid::*,
+        rate_limiter::*, selector::*, spc, statistics, trend, *,
+    };
 
     #[test]
     fn check_loops_inputs_and_outputs_len() {
@@ -269,65 +1300,841 @@ mod tests {
             inputs: vec![],
             outputs: vec![],
             controller,
+            ..Default::default()
+        };
+        let mut rt = SyncRuntime::default();
+        let mut s = SystemState::default();
+        s.io.inputs.insert("input".into(), 0.0.into());
+        assert!(rt.next((&s, &dt)).is_ok());
+        rt.loops.push(loop0);
+        assert!(rt.next((&s, &dt)).is_err());
+        rt.loops[0].inputs = vec!["input".into()];
+        assert!(rt.next((&s, &dt)).is_err());
+        rt.loops[0].outputs = vec!["output".into()];
+        assert!(rt.next((&s, &dt)).is_ok());
+    }
+
+    #[test]
+    fn check_input_value_type() {
+        let controller = ControllerConfig::Pid(PidConfig::default());
+        let dt = Duration::from_millis(5);
+        let loops = vec![Loop {
+            id: "foo".into(),
+            inputs: vec!["input".into()],
+            outputs: vec!["output".into()],
+            controller,
+            ..Default::default()
+        }];
+        let rt = SyncRuntime {
+            loops,
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("input".into(), true.into());
+        assert!(rt.next((&s, &dt)).is_err());
+        s.io.inputs.insert("input".into(), Value::Bin(vec![]));
+        assert!(rt.next((&s, &dt)).is_err());
+        s.io.inputs.insert("input".into(), 0.0.into());
+        assert!(rt.next((&s, &dt)).is_ok());
+    }
+
+    #[test]
+    fn run_pid_controllers() {
+        let pid_cfg = PidConfig {
+            k_p: 2.0,
+            default_target: 10.0,
+            ..Default::default()
+        };
+        let controller = ControllerConfig::Pid(pid_cfg);
+        let dt = Duration::from_secs(1);
+        let loops = vec![Loop {
+            id: "foo".into(),
+            inputs: vec!["sensor".into()],
+            outputs: vec!["actuator".into()],
+            controller,
+            ..Default::default()
+        }];
+        let rt = SyncRuntime {
+            loops,
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("sensor".into(), 0.0.into());
+        let s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(20.0));
+    }
+
+    #[test]
+    fn run_scheduled_pid_controllers() {
+        let cfg = ScheduledPidConfig {
+            pid: PidConfig {
+                default_target: 10.0,
+                ..Default::default()
+            },
+            scheduled_by: ScheduleVariable::Input("load".into()),
+            schedule: vec![
+                GainSchedulePoint {
+                    at: 0.0,
+                    k_p: 1.0,
+                    k_i: 0.0,
+                    k_d: 0.0,
+                },
+                GainSchedulePoint {
+                    at: 1.0,
+                    k_p: 2.0,
+                    k_i: 0.0,
+                    k_d: 0.0,
+                },
+            ],
+        };
+        let dt = Duration::from_secs(1);
+        let rt = SyncRuntime {
+            loops: vec![Loop {
+                id: "foo".into(),
+                inputs: vec!["sensor".into()],
+                outputs: vec!["actuator".into()],
+                controller: ControllerConfig::ScheduledPid(cfg),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("sensor".into(), 0.0.into());
+        assert!(rt.next((&s, &dt)).is_err());
+        s.io.inputs.insert("load".into(), 0.5.into());
+        let s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(15.0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn snapshot_and_restore_state() {
+        let dt = Duration::from_secs(1);
+        let pid_loop = |id: &str| Loop {
+            id: id.into(),
+            inputs: vec![format!("{id}-sensor")],
+            outputs: vec![format!("{id}-actuator")],
+            controller: ControllerConfig::Pid(PidConfig {
+                k_p: 1.0,
+                k_i: 0.5,
+                default_target: 10.0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let mut rt = SyncRuntime {
+            loops: vec![pid_loop("a"), pid_loop("b")],
+            ..Default::default()
+        };
+        rt.counters.push(CounterBlock {
+            id: "pump".into(),
+            input: Source::In("running".into()),
+            counter: counter::CounterConfig::default(),
+        });
+        let mut s = SystemState::default();
+        s.io.inputs.insert("a-sensor".into(), 8.0.into());
+        s.io.inputs.insert("b-sensor".into(), 8.0.into());
+        s.io.inputs.insert("running".into(), true.into());
+        for _ in 0..3 {
+            s = rt.next((&s, &dt)).unwrap();
+        }
+
+        let snapshot = rt.snapshot(&s);
+        assert!(snapshot.io.inputs.is_empty());
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let restored: SystemState = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, snapshot);
+
+        // Warm start with the same configuration
+        let mut warm = rt.restore(restored.clone());
+        warm.io.inputs = s.io.inputs.clone();
+        let s = rt.next((&s, &dt)).unwrap();
+        let warm = rt.next((&warm, &dt)).unwrap();
+        assert_eq!(warm.io.outputs, s.io.outputs);
+        assert_eq!(warm.counters, s.counters);
+
+        // Discard the states of removed or changed loops and blocks
+        rt.loops.truncate(1);
+        rt.loops.push(Loop {
+            controller: ControllerConfig::BangBang(BangBangConfig::default()),
+            ..pid_loop("b")
+        });
+        rt.counters.clear();
+        let restored = rt.restore(restored);
+        assert!(restored.controllers.contains_key("a"));
+        assert!(!restored.controllers.contains_key("b"));
+        assert!(restored.counters.is_empty());
+        assert_eq!(restored.io.outputs, snapshot.io.outputs);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn restore_incomplete_snapshot() {
+        let s: SystemState = serde_json::from_str(r#"{"io":{"outputs":{"valve":true}}}"#).unwrap();
+        assert_eq!(*s.io.outputs.get("valve").unwrap(), Value::Bit(true));
+        assert!(s.controllers.is_empty());
+    }
+
+    #[test]
+    fn reconfigure_loops_and_rules() {
+        let dt = Duration::from_secs(1);
+        let pid = |id: &str, k_p: f64| Loop {
+            id: id.into(),
+            inputs: vec!["x".into()],
+            outputs: vec![format!("{id}-y")],
+            controller: ControllerConfig::Pid(PidConfig {
+                k_p,
+                k_i: 1.0,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let rule = |id: &str| Rule {
+            id: id.into(),
+            condition: BoolExpr::True,
+            actions: vec![],
+            trigger: Trigger::Level,
+        };
+        let mut rt = SyncRuntime {
+            loops: vec![pid("a", 1.0), pid("b", 1.0)],
+            rules: vec![rule("old")],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("x".into(), 1.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        let tuned = s.controllers["a"].clone();
+
+        let mut b = pid("b", 1.0);
+        b.controller = ControllerConfig::BangBang(BangBangConfig::default());
+        let new = SyncRuntime {
+            loops: vec![pid("a", 2.0), b, pid("c", 1.0)],
+            rules: vec![rule("new")],
+            ..Default::default()
+        };
+        let (s, changes) = rt.reconfigure(new, s);
+        assert_eq!(
+            changes,
+            Reconfiguration {
+                added_loops: vec!["c".into()],
+                removed_loops: vec![],
+                reset_loops: vec!["b".into()],
+                added_rules: vec!["new".into()],
+                removed_rules: vec!["old".into()],
+            }
+        );
+        assert_eq!(rt.loops.len(), 3);
+        // The integral portion of the tuned loop is kept
+        assert_eq!(s.controllers["a"], tuned);
+        assert!(!s.controllers.contains_key("b"));
+        assert!(!s.rules.contains_key("old"));
+        let s = rt.next((&s, &dt)).unwrap();
+        assert!(matches!(s.controllers["b"], ControllerState::BangBang(_)));
+        assert!(s.rules["new"]);
+    }
+
+    #[test]
+    fn use_hysteresis_blocks_in_rules() {
+        let dt = Duration::from_secs(1);
+        let mut rt = SyncRuntime::default();
+        rt.hysteresis_blocks.push(HysteresisBlock {
+            id: "too-hot".into(),
+            input: Source::In("temperature".into()),
+            hysteresis: HysteresisConfig {
+                on: 80.0,
+                off: 75.0,
+                ..Default::default()
+            },
+        });
+        rt.rules.push(Rule {
+            id: "alarm".into(),
+            condition: BoolExpr::Eval(
+                Source::Mem("too-hot".into()).cmp_eq(Source::Const(true.into())),
+            ),
+            actions: vec![],
+            trigger: Trigger::Level,
+        });
+        let mut s = SystemState::default();
+        assert!(rt.next((&s, &dt)).is_err());
+        s.io.inputs.insert("temperature".into(), 80.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert!(*s.rules.get("alarm").unwrap());
+        s.io.inputs.insert("temperature".into(), 76.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert!(*s.rules.get("alarm").unwrap());
+        s.io.inputs.insert("temperature".into(), 74.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert!(!*s.rules.get("alarm").unwrap());
+        assert_eq!(*s.io.mem.get("too-hot").unwrap(), Value::Bit(false));
+    }
+
+    #[test]
+    fn count_operating_hours_and_cycles() {
+        let dt = Duration::from_secs(1800);
+        let mut counters = HashMap::new();
+        counters.insert(
+            "pump".into(),
+            CounterAction::Preset {
+                run_time: Duration::from_secs(36_000),
+                cycles: 100,
+            },
+        );
+        let rt = SyncRuntime {
+            counters: vec![CounterBlock {
+                id: "pump".into(),
+                input: Source::In("running".into()),
+                counter: counter::CounterConfig {
+                    service_cycles: Some(101),
+                    ..Default::default()
+                },
+            }],
+            rules: vec![Rule {
+                id: "replaced".into(),
+                condition: BoolExpr::Eval(
+                    Source::In("replaced".into()).cmp_eq(Source::Const(true.into())),
+                ),
+                actions: vec!["preset".into()],
+                trigger: Trigger::Level,
+            }],
+            actions: vec![Action {
+                id: "preset".into(),
+                counters,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("replaced".into(), false.into());
+        assert!(rt.next((&s, &dt)).is_err());
+        for running in [false, true, true, false, true] {
+            s.io.inputs.insert("running".into(), running.into());
+            s = rt.next((&s, &dt)).unwrap();
+        }
+        assert_eq!(*s.io.mem.get("pump.hours").unwrap(), Value::Decimal(1.5));
+        assert_eq!(*s.io.mem.get("pump.cycles").unwrap(), Value::Integer(2));
+        assert_eq!(*s.io.mem.get("pump.service").unwrap(), Value::Bit(false));
+
+        s.io.inputs.insert("replaced".into(), true.into());
+        s = rt.next((&s, &dt)).unwrap();
+        s.io.inputs.insert("replaced".into(), false.into());
+        s.io.inputs.insert("running".into(), false.into());
+        s = rt.next((&s, &dt)).unwrap();
+        s.io.inputs.insert("running".into(), true.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.mem.get("pump.hours").unwrap(), Value::Decimal(10.5));
+        assert_eq!(*s.io.mem.get("pump.cycles").unwrap(), Value::Integer(101));
+        assert_eq!(*s.io.mem.get("pump.service").unwrap(), Value::Bit(true));
+    }
+
+    #[test]
+    fn summarize_values_with_statistics_blocks() {
+        let dt = Duration::from_secs(1);
+        let rt = SyncRuntime {
+            statistics: vec![StatisticsBlock {
+                id: "temperature".into(),
+                input: Source::In("temperature".into()),
+                statistics: statistics::StatisticsConfig {
+                    window: 2,
+                    smoothing: 0.5,
+                },
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        assert!(rt.next((&s, &dt)).is_err());
+        for x in [10.0, 20.0, 30.0] {
+            s.io.inputs.insert("temperature".into(), x.into());
+            s = rt.next((&s, &dt)).unwrap();
+        }
+        let mem = |name: &str| s.io.mem.get(&format!("temperature.{name}")).cloned();
+        assert_eq!(mem("mean"), Some(Value::Decimal(25.0)));
+        assert_eq!(mem("min"), Some(Value::Decimal(20.0)));
+        assert_eq!(mem("max"), Some(Value::Decimal(30.0)));
+        assert_eq!(mem("ewma"), Some(Value::Decimal(22.5)));
+    }
+
+    #[test]
+    fn use_spc_blocks_in_rules() {
+        let dt = Duration::from_secs(1);
+        let rt = SyncRuntime {
+            spc: vec![SpcBlock {
+                id: "drift".into(),
+                input: Source::In("pressure".into()),
+                spc: spc::SpcConfig {
+                    limits: spc::ControlLimits {
+                        center: 5.0,
+                        sigma: 0.1,
+                    },
+                    rules: vec![spc::SpcRule::SameSide(3)],
+                },
+            }],
+            rules: vec![Rule {
+                id: "warning".into(),
+                condition: BoolExpr::Eval(
+                    Source::Mem("drift".into()).cmp_eq(Source::Const(true.into())),
+                ),
+                actions: vec![],
+                trigger: Trigger::Level,
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        assert!(rt.next((&s, &dt)).is_err());
+        for x in [5.01, 5.02, 5.01] {
+            s.io.inputs.insert("pressure".into(), x.into());
+            s = rt.next((&s, &dt)).unwrap();
+        }
+        assert!(*s.rules.get("warning").unwrap());
+        assert_eq!(
+            s.events,
+            vec![RuntimeEvent::SpcRuleViolation {
+                block: "drift".into(),
+                rule: spc::SpcRule::SameSide(3),
+                value: 5.01,
+            }]
+        );
+        s.io.inputs.insert("pressure".into(), 4.99.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert!(!*s.rules.get("warning").unwrap());
+        assert!(s.events.is_empty());
+    }
+
+    #[test]
+    fn detect_trends() {
+        let dt = Duration::from_secs(2);
+        let rt = SyncRuntime {
+            trends: vec![TrendBlock {
+                id: "level".into(),
+                input: Source::In("level".into()),
+                trend: trend::TrendConfig {
+                    window: Duration::from_secs(10),
+                    rising: 0.5,
+                    falling: 0.5,
+                },
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        assert!(rt.next((&s, &dt)).is_err());
+        for x in [10.0, 12.0, 14.0] {
+            s.io.inputs.insert("level".into(), x.into());
+            s = rt.next((&s, &dt)).unwrap();
+        }
+        let mem = |s: &SystemState, name: &str| s.io.mem.get(&format!("level.{name}")).cloned();
+        assert_eq!(mem(&s, "slope"), Some(Value::Decimal(1.0)));
+        assert_eq!(mem(&s, "rising"), Some(Value::Bit(true)));
+        assert_eq!(mem(&s, "falling"), Some(Value::Bit(false)));
+        for x in [12.0, 10.0, 8.0, 6.0, 4.0] {
+            s.io.inputs.insert("level".into(), x.into());
+            s = rt.next((&s, &dt)).unwrap();
+        }
+        assert_eq!(mem(&s, "slope"), Some(Value::Decimal(-1.0)));
+        assert_eq!(mem(&s, "falling"), Some(Value::Bit(true)));
+        assert_eq!(s.trends.get("level").unwrap().samples.len(), 6);
+    }
+
+    #[test]
+    fn override_control_with_selector_and_limiter_blocks() {
+        let dt = Duration::from_secs(1);
+        let rt = SyncRuntime {
+            selectors: vec![SelectorBlock {
+                id: "min".into(),
+                inputs: vec![
+                    Source::In("temperature_ctrl".into()),
+                    Source::In("pressure_ctrl".into()),
+                ],
+                selector: Selector::Min,
+            }],
+            limiters: vec![LimiterBlock {
+                id: "valve".into(),
+                input: Source::Mem("min".into()),
+                limiter: limiter::LimiterConfig {
+                    low: Some(10.0),
+                    high: Some(90.0),
+                },
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        assert!(rt.next((&s, &dt)).is_err());
+        s.io.inputs.insert("temperature_ctrl".into(), 95.0.into());
+        s.io.inputs.insert("pressure_ctrl".into(), 97.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.io.mem["min"], Value::Decimal(95.0));
+        assert_eq!(s.io.mem["min.selected"], Value::Integer(0));
+        assert_eq!(s.io.mem["valve"], Value::Decimal(90.0));
+        assert_eq!(s.io.mem["valve.high"], Value::Bit(true));
+        assert_eq!(s.io.mem["valve.low"], Value::Bit(false));
+        s.io.inputs.insert("pressure_ctrl".into(), 40.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.io.mem["min.selected"], Value::Integer(1));
+        assert_eq!(s.io.mem["valve"], Value::Decimal(40.0));
+        assert_eq!(s.io.mem["valve.high"], Value::Bit(false));
+    }
+
+    #[test]
+    fn scale_raw_values() {
+        let dt = Duration::from_secs(1);
+        let rt = SyncRuntime {
+            scalings: vec![ScalingBlock {
+                id: "pressure".into(),
+                input: Source::In("ai_3".into()),
+                scaling: scaling::Scaling {
+                    raw_min: 4000.0,
+                    raw_max: 20000.0,
+                    min: 0.0,
+                    max: 10.0,
+                    clamp: true,
+                },
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        assert!(rt.next((&s, &dt)).is_err());
+        s.io.inputs.insert("ai_3".into(), Value::Integer(8000));
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.io.mem["pressure"], Value::Decimal(2.5));
+        assert_eq!(s.io.mem["pressure.out_of_range"], Value::Bit(false));
+        s.io.inputs.insert("ai_3".into(), Value::Integer(32767));
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.io.mem["pressure"], Value::Decimal(10.0));
+        assert_eq!(s.io.mem["pressure.out_of_range"], Value::Bit(true));
+    }
+
+    #[test]
+    fn check_plausibility() {
+        let dt = Duration::from_secs(1);
+        let rt = SyncRuntime {
+            plausibility_checks: vec![PlausibilityBlock {
+                id: "level_ok".into(),
+                input: Source::In("level".into()),
+                plausibility: plausibility::PlausibilityConfig {
+                    stuck_time: Some(Duration::from_secs(1)),
+                    max_delta: Some(10.0),
+                },
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        assert!(rt.next((&s, &dt)).is_err());
+        s.io.inputs.insert("level".into(), 50.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.io.mem["level_ok"], Value::Bit(true));
+        assert!(s.events.is_empty());
+        s.io.inputs.insert("level".into(), 70.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.io.mem["level_ok"], Value::Bit(false));
+        assert_eq!(s.io.mem["level_ok.spike"], Value::Bit(true));
+        assert_eq!(
+            s.events,
+            vec![RuntimeEvent::ImplausibleSignal {
+                block: "level_ok".into(),
+                fault: plausibility::Fault::Spike,
+            }]
+        );
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.io.mem["level_ok"], Value::Bit(true));
+        assert!(s.events.is_empty());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.io.mem["level_ok"], Value::Bit(false));
+        assert_eq!(s.io.mem["level_ok.stuck"], Value::Bit(true));
+        assert_eq!(s.io.mem["level_ok.spike"], Value::Bit(false));
+        assert_eq!(
+            s.events,
+            vec![RuntimeEvent::ImplausibleSignal {
+                block: "level_ok".into(),
+                fault: plausibility::Fault::Stuck,
+            }]
+        );
+        s = rt.next((&s, &dt)).unwrap();
+        assert!(s.events.is_empty());
+    }
+
+    #[test]
+    fn run_on_the_resolved_io_table() {
+        let dt = Duration::from_secs(1);
+        let rt = SyncRuntime {
+            loops: vec![Loop {
+                id: "cooling".into(),
+                inputs: vec!["temperature".into()],
+                outputs: vec!["fan".into()],
+                controller: ControllerConfig::BangBang(BangBangConfig {
+                    default_threshold: 20.0,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            io_registry: Arc::new(["temperature", "fan"].into_iter().collect()),
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("temperature".into(), 22.0.into());
+        s.io.outputs.insert("horn".into(), false.into());
+        s = rt.next((&s, &dt)).unwrap();
+        let fan = rt.io_registry.key("fan").unwrap();
+        assert_eq!(s.io.outputs.key("fan"), Some(fan));
+        assert_eq!(s.io.outputs.get_by_key(fan), Some(&Value::Bit(true)));
+        assert_eq!(s.io.outputs["horn"], Value::Bit(false));
+
+        // Nothing is written if the loop fails
+        s.io.inputs.insert("temperature".into(), true.into());
+        let err = rt.next((&s, &dt)).unwrap_err();
+        assert_eq!(err.state.io, s.io);
+    }
+
+    #[test]
+    fn run_graphs() {
+        let dt = Duration::from_secs(1);
+        let rt = SyncRuntime {
+            graphs: vec![GraphConfig {
+                id: "integrator".into(),
+                nodes: vec![
+                    Node::new("sum", BlockConfig::Math(MathOp::Add), &["x", "y_prev"], "y"),
+                    Node::new("delay", BlockConfig::UnitDelay(0.0), &["y"], "y_prev"),
+                ],
+                outputs: vec!["y".into()],
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        assert!(rt.next((&s, &dt)).is_err());
+        s.io.inputs.insert("x".into(), 2.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("y").unwrap(), Value::Decimal(4.0));
+        assert_eq!(
+            *s.graphs["integrator"].signals.get("y_prev").unwrap(),
+            Value::Decimal(2.0)
+        );
+    }
+
+    #[test]
+    fn run_loops_with_rate_limits() {
+        let dt = Duration::from_secs(1);
+        let rt = SyncRuntime {
+            loops: vec![Loop {
+                id: "foo".into(),
+                inputs: vec!["sensor".into()],
+                outputs: vec!["actuator".into()],
+                controller: ControllerConfig::Pid(PidConfig {
+                    k_p: 1.0,
+                    ..Default::default()
+                }),
+                output_rate_limit: Some(RateLimiterConfig {
+                    rising: Some(3.0),
+                    falling: Some(1.0),
+                }),
+                setpoint_rate_limit: Some(RateLimiterConfig {
+                    rising: Some(2.0),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("sensor".into(), 0.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(0.0));
+        s.setpoints.insert("foo".into(), 10.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(2.0));
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(4.0));
+        s.io.inputs.insert("sensor".into(), 10.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(3.0));
+        s.io.inputs.insert("sensor".into(), (-10.0).into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(6.0));
+    }
+
+    #[test]
+    fn switch_operating_mode_with_actions() {
+        let dt = Duration::from_secs(1);
+        let mode_action = |id: &str, mode| {
+            let mut controllers = HashMap::new();
+            controllers.insert(
+                "foo".into(),
+                ControllerAction {
+                    mode: Some(mode),
+                    ..Default::default()
+                },
+            );
+            Action {
+                id: id.into(),
+                outputs: HashMap::new(),
+                setpoints: HashMap::new(),
+                memory: HashMap::new(),
+                timeouts: HashMap::new(),
+                controllers,
+                counters: HashMap::new(),
+                ..Default::default()
+            }
+        };
+        let rt = SyncRuntime {
+            loops: vec![Loop {
+                id: "foo".into(),
+                inputs: vec!["sensor".into()],
+                outputs: vec!["actuator".into()],
+                controller: ControllerConfig::Pid(PidConfig {
+                    k_p: 1.0,
+                    k_i: 1.0,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            rules: vec![
+                Rule {
+                    id: "manual".into(),
+                    condition: BoolExpr::Eval(
+                        Source::In("hmi".into()).cmp_eq(Source::Const(1.into())),
+                    ),
+                    actions: vec!["manual".into()],
+                    trigger: Trigger::Level,
+                },
+                Rule {
+                    id: "auto".into(),
+                    condition: BoolExpr::Eval(
+                        Source::In("hmi".into()).cmp_eq(Source::Const(0.into())),
+                    ),
+                    actions: vec!["auto".into()],
+                    trigger: Trigger::Level,
+                },
+            ],
+            actions: vec![
+                mode_action("manual", OperatingMode::Manual(50.0)),
+                mode_action("auto", OperatingMode::Auto),
+            ],
+            ..Default::default()
         };
-        let mut rt = SyncRuntime::default();
         let mut s = SystemState::default();
-        s.io.inputs.insert("input".into(), 0.0.into());
-        assert!(rt.next((&s, &dt)).is_ok());
-        rt.loops.push(loop0);
-        assert!(rt.next((&s, &dt)).is_err());
-        rt.loops[0].inputs = vec!["input".into()];
-        assert!(rt.next((&s, &dt)).is_err());
-        rt.loops[0].outputs = vec!["output".into()];
-        assert!(rt.next((&s, &dt)).is_ok());
+        s.io.inputs.insert("sensor".into(), 0.0.into());
+        s.io.inputs.insert("hmi".into(), 1.into());
+        s.setpoints.insert("foo".into(), 10.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.modes.get("foo").unwrap(), OperatingMode::Manual(50.0));
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(50.0));
+        s.io.inputs.insert("sensor".into(), 10.0.into());
+        s.io.inputs.insert("hmi".into(), 0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.modes.get("foo").unwrap(), OperatingMode::Auto);
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(50.0));
     }
 
     #[test]
-    fn check_input_value_type() {
-        let controller = ControllerConfig::Pid(PidConfig::default());
-        let dt = Duration::from_millis(5);
-        let loops = vec![Loop {
-            id: "foo".into(),
-            inputs: vec!["input".into()],
-            outputs: vec!["output".into()],
-            controller,
-        }];
+    fn run_loops_with_input_filters() {
+        let dt = Duration::from_secs(1);
         let rt = SyncRuntime {
-            loops,
+            loops: vec![Loop {
+                id: "foo".into(),
+                inputs: vec!["sensor".into()],
+                outputs: vec!["actuator".into()],
+                controller: ControllerConfig::Pid(PidConfig {
+                    k_p: 1.0,
+                    ..Default::default()
+                }),
+                filters: vec![FilterConfig::MovingAverage(MovingAverageConfig {
+                    window: 2,
+                })],
+                ..Default::default()
+            }],
             ..Default::default()
         };
         let mut s = SystemState::default();
-        s.io.inputs.insert("input".into(), true.into());
-        assert!(rt.next((&s, &dt)).is_err());
-        s.io.inputs.insert("input".into(), Value::Bin(vec![]));
-        assert!(rt.next((&s, &dt)).is_err());
-        s.io.inputs.insert("input".into(), 0.0.into());
-        assert!(rt.next((&s, &dt)).is_ok());
+        s.io.inputs.insert("sensor".into(), 2.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(-2.0));
+        s.io.inputs.insert("sensor".into(), 4.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(-3.0));
+        assert_eq!(*s.io.inputs.get("sensor").unwrap(), Value::Decimal(4.0));
+        assert_eq!(s.filters.get("foo").unwrap().len(), 1);
     }
 
     #[test]
-    fn run_pid_controllers() {
-        let pid_cfg = PidConfig {
-            k_p: 2.0,
-            default_target: 10.0,
+    fn preprocess_loop_inputs() {
+        use crate::preprocessing::{Preprocessing, ValidationConfig};
+        let dt = Duration::from_secs(1);
+        let rt = SyncRuntime {
+            loops: vec![Loop {
+                id: "foo".into(),
+                inputs: vec!["sensor".into()],
+                outputs: vec!["actuator".into()],
+                controller: ControllerConfig::Pid(PidConfig {
+                    k_p: 1.0,
+                    ..Default::default()
+                }),
+                preprocessing: vec![
+                    Preprocessing::Scaling(scaling::Scaling {
+                        raw_max: 100.0,
+                        max: 10.0,
+                        ..Default::default()
+                    }),
+                    Preprocessing::Validation(ValidationConfig {
+                        min: Some(0.0),
+                        ..Default::default()
+                    }),
+                ],
+                filters: vec![FilterConfig::MovingAverage(MovingAverageConfig {
+                    window: 2,
+                })],
+                ..Default::default()
+            }],
             ..Default::default()
         };
-        let controller = ControllerConfig::Pid(pid_cfg);
+        let mut s = SystemState::default();
+        s.io.inputs.insert("sensor".into(), 20.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(-2.0));
+        s.io.inputs.insert("sensor".into(), (-10.0).into());
+        let err = rt.next((&s, &dt)).unwrap_err();
+        assert_eq!(err.causes.len(), 1);
+        // The output is held and the filter is not fed
+        assert_eq!(
+            *err.state.io.outputs.get("actuator").unwrap(),
+            Value::Decimal(-2.0)
+        );
+        s = err.state;
+        s.io.inputs.insert("sensor".into(), 40.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(-3.0));
+        assert_eq!(*s.io.inputs.get("sensor").unwrap(), Value::Decimal(40.0));
+        assert_eq!(s.preprocessing.get("foo").unwrap().len(), 2);
+    }
+
+    #[test]
+    fn filter_combined_inputs() {
         let dt = Duration::from_secs(1);
-        let loops = vec![Loop {
-            id: "foo".into(),
-            inputs: vec!["sensor".into()],
-            outputs: vec!["actuator".into()],
-            controller,
-        }];
         let rt = SyncRuntime {
-            loops,
+            loops: vec![Loop {
+                id: "foo".into(),
+                inputs: vec!["s1".into(), "s2".into()],
+                outputs: vec!["actuator".into()],
+                controller: ControllerConfig::Pid(PidConfig {
+                    k_p: 1.0,
+                    ..Default::default()
+                }),
+                combiner: Some(Selector::Average),
+                filters: vec![FilterConfig::MovingAverage(MovingAverageConfig {
+                    window: 2,
+                })],
+                ..Default::default()
+            }],
             ..Default::default()
         };
         let mut s = SystemState::default();
-        s.io.inputs.insert("sensor".into(), 0.0.into());
-        let s = rt.next((&s, &dt)).unwrap();
-        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(20.0));
+        s.io.inputs.insert("s1".into(), 1.0.into());
+        s.io.inputs.insert("s2".into(), 3.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(-2.0));
+        s.io.inputs.insert("s2".into(), 7.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(*s.io.outputs.get("actuator").unwrap(), Value::Decimal(-3.0));
+        assert_eq!(*s.io.inputs.get("s1").unwrap(), Value::Decimal(1.0));
+        assert_eq!(*s.io.inputs.get("s2").unwrap(), Value::Decimal(7.0));
     }
 
     #[test]
@@ -345,6 +2152,7 @@ mod tests {
             inputs: vec![sensor.clone()],
             outputs: vec![actuator.clone()],
             controller,
+            ..Default::default()
         }];
         let rt = SyncRuntime {
             loops,
@@ -363,16 +2171,21 @@ mod tests {
     fn check_active_rules() {
         let mut state = SystemState::default();
         let mut rt = SyncRuntime::default();
-        assert_eq!(rt.rules_state(&state).unwrap().len(), 0);
+        assert_eq!(rt.rules_state(&state, &mut None).unwrap().len(), 0);
         rt.rules = vec![Rule {
             id: "foo".into(),
             condition: BoolExpr::Eval(Source::In("x".into()).cmp_ge(Source::Out("y".into()))),
             actions: vec!["a".into()],
+            trigger: Trigger::Level,
         }];
-        assert!(rt.rules_state(&state).is_err());
+        assert!(rt.rules_state(&state, &mut None).is_err());
         state.io.inputs.insert("x".into(), 33.3.into());
         state.io.outputs.insert("y".into(), 33.3.into());
-        assert!(*rt.rules_state(&state).unwrap().get("foo").unwrap(),);
+        assert!(*rt
+            .rules_state(&state, &mut None)
+            .unwrap()
+            .get("foo")
+            .unwrap(),);
     }
 
     #[test]
@@ -384,6 +2197,7 @@ mod tests {
             id: "foo".into(),
             condition: BoolExpr::Eval(Source::In("x".into()).cmp_eq(Source::Const(10.0.into()))),
             actions: vec!["a".into()],
+            trigger: Trigger::Level,
         }];
         let mut outputs = HashMap::new();
         let mut setpoints = HashMap::new();
@@ -414,6 +2228,8 @@ mod tests {
             timeouts,
             memory,
             controllers,
+            counters: HashMap::new(),
+            ..Default::default()
         }];
         state.io.inputs.insert("x".into(), 0.0.into());
         state
@@ -471,6 +2287,262 @@ mod tests {
         );
     }
 
+    #[test]
+    fn profile_components() {
+        let dt = Duration::from_millis(1);
+        let mut fsm = StateMachine {
+            initial: "idle".into(),
+            ..Default::default()
+        };
+        fsm.transitions.push(Transition {
+            from: "idle".into(),
+            to: "busy".into(),
+            condition: BoolExpr::True,
+            actions: vec![],
+            after: None,
+        });
+        let mut rt = SyncRuntime {
+            loops: vec![Loop {
+                id: "pid".into(),
+                inputs: vec!["x".into()],
+                outputs: vec!["y".into()],
+                controller: ControllerConfig::Pid(PidConfig::default()),
+                ..Default::default()
+            }],
+            rules: vec![Rule {
+                id: "rule".into(),
+                condition: BoolExpr::True,
+                actions: vec![],
+                trigger: Trigger::Level,
+            }],
+            state_machines: [("fsm".to_string(), fsm)].into_iter().collect(),
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("x".into(), 1.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.profile, Profile::default());
+
+        rt.profiling = true;
+        for _ in 0..3 {
+            s = rt.next((&s, &dt)).unwrap();
+        }
+        let report = s.profile.report();
+        assert_eq!(report.len(), 3);
+        assert!(report.windows(2).all(|x| x[0].1.total >= x[1].1.total));
+        for (_, t) in report {
+            assert_eq!(t.steps, 3);
+            assert!(t.worst >= t.last);
+            assert!(t.mean() <= t.worst);
+        }
+        assert!(s.profile.loops.contains_key("pid"));
+        rt.loops.clear();
+        let s = rt.restore(s);
+        assert!(s.profile.loops.is_empty());
+        assert_eq!(s.profile.rules.len(), 1);
+    }
+
+    #[test]
+    fn apply_actions_with_calculated_values() {
+        let mut rt = SyncRuntime::default();
+        let dt = Duration::from_millis(1);
+        let diff = Source::from(Expr::Sub(
+            Source::In("supply".into()),
+            Source::In("return".into()),
+        ));
+        rt.rules = vec![Rule {
+            id: "spread".into(),
+            condition: BoolExpr::Eval(diff.cmp_gt(Source::Const(5.0.into()))),
+            actions: vec!["raise".into()],
+            trigger: Trigger::Level,
+        }];
+        let mut setpoints = HashMap::new();
+        setpoints.insert(
+            "pump".into(),
+            Expr::Mul(Source::Setpoint("pump".into()), Source::Const(1.5.into())).into(),
+        );
+        rt.actions = vec![Action {
+            id: "raise".into(),
+            setpoints,
+            ..Default::default()
+        }];
+        let mut state = SystemState::default();
+        state.setpoints.insert("pump".into(), 10.0.into());
+        state.io.inputs.insert("supply".into(), 60.0.into());
+        state.io.inputs.insert("return".into(), 55.0.into());
+        let mut state = rt.next((&state, &dt)).unwrap();
+        assert_eq!(*state.setpoints.get("pump").unwrap(), Value::Decimal(10.0));
+        state.io.inputs.insert("return".into(), 54.0.into());
+        let state = rt.next((&state, &dt)).unwrap();
+        assert_eq!(*state.setpoints.get("pump").unwrap(), Value::Decimal(15.0));
+    }
+
+    #[test]
+    fn trigger_actions_on_edges() {
+        let dt = Duration::from_millis(1);
+        let count = |id: &str| Action {
+            id: id.into(),
+            memory: [(
+                id.to_string(),
+                Expr::Add(Source::Mem(id.into()), Source::Const(1.into())).into(),
+            )]
+            .into_iter()
+            .collect(),
+            ..Default::default()
+        };
+        let rule = |trigger: Trigger, action: &str| Rule {
+            id: action.into(),
+            condition: BoolExpr::Eval(Source::In("x".into()).cmp_eq(Source::Const(true.into()))),
+            actions: vec![action.into()],
+            trigger,
+        };
+        let rt = SyncRuntime {
+            rules: vec![
+                rule(Trigger::Level, "level"),
+                rule(Trigger::Rising, "rising"),
+                rule(Trigger::Falling, "falling"),
+                rule(Trigger::Changed, "changed"),
+            ],
+            actions: vec![
+                count("level"),
+                count("rising"),
+                count("falling"),
+                count("changed"),
+            ],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        for id in ["level", "rising", "falling", "changed"] {
+            s.io.mem.insert(id.into(), 0.into());
+        }
+        for x in [true, true, true, false, false, true] {
+            s.io.inputs.insert("x".into(), x.into());
+            s = rt.next((&s, &dt)).unwrap();
+        }
+        assert_eq!(*s.io.mem.get("level").unwrap(), Value::Integer(4));
+        assert_eq!(*s.io.mem.get("rising").unwrap(), Value::Integer(2));
+        assert_eq!(*s.io.mem.get("falling").unwrap(), Value::Integer(1));
+        assert_eq!(*s.io.mem.get("changed").unwrap(), Value::Integer(3));
+    }
+
+    #[test]
+    fn run_timer_blocks() {
+        let dt = Duration::from_secs(1);
+        let control = |id: &str, t: TimerAction| Action {
+            id: id.into(),
+            timers: [("delay".to_string(), t)].into_iter().collect(),
+            ..Default::default()
+        };
+        let rule = |id: &str, input: &str, value: bool| Rule {
+            id: id.into(),
+            condition: BoolExpr::Eval(Source::In(input.into()).cmp_eq(Source::Const(value.into()))),
+            actions: vec![id.into()],
+            trigger: Trigger::Level,
+        };
+        let rt = SyncRuntime {
+            timers: vec![TimerBlock {
+                id: "delay".into(),
+                timer: timer::TimerConfig {
+                    kind: timer::TimerKind::OnDelay,
+                    preset: Duration::from_secs(2),
+                },
+            }],
+            rules: vec![
+                rule("start", "x", true),
+                rule("stop", "x", false),
+                rule("reset", "reset", true),
+            ],
+            actions: vec![
+                control("start", TimerAction::Start),
+                control("stop", TimerAction::Stop),
+                control("reset", TimerAction::Reset),
+            ],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("reset".into(), false.into());
+        let mut outputs = vec![];
+        for x in [true, true, true, true, false, false] {
+            s.io.inputs.insert("x".into(), x.into());
+            s = rt.next((&s, &dt)).unwrap();
+            outputs.push(s.io.mem.get("delay").cloned().unwrap());
+        }
+        // The timer is started by the action of the first step
+        assert_eq!(
+            outputs,
+            [false, false, false, true, true, false].map(Value::from)
+        );
+        assert_eq!(*s.io.mem.get("delay.elapsed").unwrap(), Value::Decimal(0.0));
+
+        for _ in 0..3 {
+            s.io.inputs.insert("x".into(), true.into());
+            s = rt.next((&s, &dt)).unwrap();
+        }
+        assert_eq!(*s.io.mem.get("delay.elapsed").unwrap(), Value::Decimal(1.0));
+        s.io.inputs.insert("reset".into(), true.into());
+        s.io.inputs.insert("x".into(), false.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.timers["delay"], timer::TimerState::default());
+    }
+
+    #[test]
+    fn apply_delayed_and_pulsed_actions() {
+        let dt = Duration::from_secs(1);
+        let set = |id: &str, output: &str, value: bool| Action {
+            id: id.into(),
+            outputs: [(output.to_string(), Source::Const(value.into()))]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let rt = SyncRuntime {
+            rules: vec![Rule {
+                id: "start".into(),
+                condition: BoolExpr::Eval(
+                    Source::In("start".into()).cmp_eq(Source::Const(true.into())),
+                ),
+                actions: vec!["pump".into(), "horn".into()],
+                trigger: Trigger::Level,
+            }],
+            actions: vec![
+                Action {
+                    on_delay: Some(Duration::from_secs(2)),
+                    ..set("pump", "pump", true)
+                },
+                Action {
+                    pulse: Some(Duration::from_secs(2)),
+                    release: vec!["horn-off".into()],
+                    ..set("horn", "horn", true)
+                },
+                set("horn-off", "horn", false),
+            ],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("start".into(), true.into());
+        let mut outputs = vec![];
+        for _ in 0..4 {
+            s = rt.next((&s, &dt)).unwrap();
+            outputs.push((
+                s.io.outputs.get("pump").cloned(),
+                s.io.outputs.get("horn").cloned(),
+            ));
+        }
+        let on = Some(Value::Bit(true));
+        let off = Some(Value::Bit(false));
+        assert_eq!(
+            outputs,
+            vec![
+                (None, on.clone()),
+                (None, on.clone()),
+                (on.clone(), off.clone()),
+                (on.clone(), off),
+            ]
+        );
+        assert!(s.actions.get("pump").unwrap().active);
+        assert!(!s.actions.get("horn").unwrap().active);
+    }
+
     #[test]
     fn apply_controller_reset_actions() {
         let mut rt = SyncRuntime::default();
@@ -489,11 +2561,13 @@ mod tests {
             inputs: vec!["sensor".into()],
             outputs: vec!["actuator".into()],
             controller,
+            ..Default::default()
         });
         rt.rules = vec![Rule {
             id: "foo".into(),
             condition: BoolExpr::Eval(Source::In("x".into()).cmp_eq(Source::Const(10.0.into()))),
             actions: vec!["a".into()],
+            trigger: Trigger::Level,
         }];
 
         let mut controllers = HashMap::new();
@@ -502,6 +2576,7 @@ mod tests {
             ControllerAction {
                 reset: true,
                 active: None,
+                mode: None,
             },
         );
 
@@ -512,6 +2587,8 @@ mod tests {
             memory: HashMap::new(),
             timeouts: HashMap::new(),
             controllers,
+            counters: HashMap::new(),
+            ..Default::default()
         }];
         state.io.inputs.insert("x".into(), 0.0.into());
         state.io.inputs.insert("sensor".into(), 0.0.into());
@@ -534,6 +2611,9 @@ mod tests {
                 p: 40.0,
                 i: 3000.0,
                 d: 0.0,
+                prev_error: Some(20.0),
+                output: Some(3040.0),
+                ..Default::default()
             })
         );
         // trigger the rule
@@ -549,6 +2629,7 @@ mod tests {
                 p: 0.0,
                 i: 0.0,
                 d: 0.0,
+                ..Default::default()
             })
         );
     }
@@ -573,12 +2654,14 @@ mod tests {
             inputs: vec!["sensor".into()],
             outputs: vec!["actuator_0".into()],
             controller: controller_0,
+            ..Default::default()
         });
         rt.loops.push(Loop {
             id: "pid_1".into(),
             inputs: vec!["sensor".into()],
             outputs: vec!["actuator_1".into()],
             controller: controller_1,
+            ..Default::default()
         });
 
         rt.rules = vec![
@@ -588,6 +2671,7 @@ mod tests {
                     Source::In("x".into()).cmp_eq(Source::Const(10.0.into())),
                 ),
                 actions: vec!["a".into()],
+                trigger: Trigger::Level,
             },
             Rule {
                 id: "bar".into(),
@@ -595,6 +2679,7 @@ mod tests {
                     Source::In("x".into()).cmp_eq(Source::Const(20.0.into())),
                 ),
                 actions: vec!["b".into()],
+                trigger: Trigger::Level,
             },
         ];
 
@@ -606,6 +2691,7 @@ mod tests {
             ControllerAction {
                 reset: false,
                 active: Some(false),
+                mode: None,
             },
         );
 
@@ -614,6 +2700,7 @@ mod tests {
             ControllerAction {
                 reset: false,
                 active: Some(true),
+                mode: None,
             },
         );
 
@@ -622,6 +2709,7 @@ mod tests {
             ControllerAction {
                 reset: false,
                 active: Some(true), // start it, even if it's already running
+                mode: None,
             },
         );
 
@@ -633,6 +2721,8 @@ mod tests {
                 memory: HashMap::new(),
                 timeouts: HashMap::new(),
                 controllers: controllers_a,
+                counters: HashMap::new(),
+                ..Default::default()
             },
             Action {
                 id: "b".into(),
@@ -641,6 +2731,8 @@ mod tests {
                 memory: HashMap::new(),
                 timeouts: HashMap::new(),
                 controllers: controllers_b,
+                counters: HashMap::new(),
+                ..Default::default()
             },
         ];
 
@@ -694,6 +2786,45 @@ mod tests {
         assert_eq!(state.io.outputs.get("actuator_1"), Some(&Value::from(50.0)));
     }
 
+    #[test]
+    fn measure_cycle_time() {
+        let dt = Duration::from_millis(10);
+        let mut rt = SyncRuntime {
+            loops: vec![Loop {
+                id: "foo".into(),
+                inputs: vec!["sensor".into()],
+                outputs: vec!["actuator".into()],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let mut s = SystemState::default();
+        s.io.inputs.insert("sensor".into(), 1.0.into());
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.cycle, CycleState::default());
+
+        rt.cycle_time = Some(Duration::from_secs(3600));
+        for _ in 0..3 {
+            s = rt.next((&s, &dt)).unwrap();
+        }
+        let c = &s.cycle;
+        assert_eq!(c.steps, 3);
+        assert_eq!(c.overruns, 0);
+        assert!(!c.overrun);
+        assert!(c.last > Duration::ZERO);
+        assert!(c.worst >= c.last);
+        let p = c.phases;
+        let sum = p.loops + p.graphs + p.blocks + p.rules + p.state_machines + p.actions;
+        assert!(p.loops > Duration::ZERO);
+        assert!(sum <= c.last);
+
+        rt.cycle_time = Some(Duration::ZERO);
+        s = rt.next((&s, &dt)).unwrap();
+        assert_eq!(s.cycle.steps, 4);
+        assert_eq!(s.cycle.overruns, 1);
+        assert!(s.cycle.overrun);
+    }
+
     #[test]
     fn runtime_state() {
         let mut s = SystemState::default();
@@ -714,6 +2845,7 @@ mod tests {
             id: "foo".into(),
             condition: BoolExpr::Eval(Source::In("x".into()).cmp_ge(Source::Out("y".into()))),
             actions: vec!["a".into()],
+            trigger: Trigger::Level,
         }];
         let state = rt.next((&s, &dt)).unwrap();
         assert_eq!(state.rules.len(), 1);
@@ -740,12 +2872,14 @@ mod tests {
                 inputs: vec!["a".into()],
                 outputs: vec!["b".into()],
                 controller: bb,
+                ..Default::default()
             },
             Loop {
                 id: "pid".into(),
                 inputs: vec!["j".into()],
                 outputs: vec!["k".into()],
                 controller: pid,
+                ..Default::default()
             },
         ];
         rt.loops = loops;
@@ -756,7 +2890,10 @@ mod tests {
             state.controllers.get("bb").unwrap(),
             &ControllerState::BangBang(BangBangState {
                 current: true,
-                threshold: 2.0
+                threshold: 2.0,
+                last_switch: Some(Duration::ZERO),
+                last_switch_on: Some(Duration::ZERO),
+                ..Default::default()
             })
         );
         assert_eq!(
@@ -767,10 +2904,68 @@ mod tests {
                 d: 0.0,
                 prev_value: Some(0.0),
                 target: 10.0,
+                prev_error: Some(10.0),
+                output: Some(20.0),
+                ..Default::default()
             })
         );
     }
 
+    #[test]
+    fn run_mpc_controllers() {
+        let step_response = (1..=20)
+            .map(|k| 2.0 * (1.0 - (-f64::from(k) / 4.0).exp()))
+            .collect();
+        let cfg = mpc::MpcConfig {
+            step_response: vec![vec![step_response]],
+            input_limits: vec![Cropping {
+                low: Some(0.0),
+                high: Some(100.0),
+            }],
+            ..Default::default()
+        };
+        let dt = Duration::from_secs(1);
+        let mut l = Loop {
+            id: "mpc".into(),
+            inputs: vec!["temperature".into()],
+            outputs: vec!["heater".into(), "cooler".into()],
+            controller: ControllerConfig::Mpc(cfg),
+            ..Default::default()
+        };
+        let mut state = SystemState::default();
+        state.io.inputs.insert("temperature".into(), 20.0.into());
+
+        // The MPC has a single manipulated input
+        let runtime = SyncRuntime {
+            loops: vec![l.clone()],
+            ..Default::default()
+        };
+        assert!(runtime.next((&state, &dt)).is_err());
+
+        l.outputs.pop();
+        let runtime = SyncRuntime {
+            loops: vec![l],
+            ..Default::default()
+        };
+        let mut state = runtime.next((&state, &dt)).unwrap();
+        assert_eq!(
+            *state.io.outputs.get("heater").unwrap(),
+            Value::Decimal(0.0)
+        );
+        state.setpoints.insert("mpc".into(), Value::Decimal(60.0));
+        let state = runtime.next((&state, &dt)).unwrap();
+        match state.controllers.get("mpc").unwrap() {
+            ControllerState::Mpc(s) => assert_eq!(s.targets, vec![60.0]),
+            s => panic!("unexpected controller state: {s:?}"),
+        }
+        match state.io.outputs.get("heater").unwrap() {
+            Value::Decimal(y) => assert!(*y > 0.0),
+            y => panic!("unexpected output: {y:?}"),
+        }
+        let restored = runtime.restore(state.clone());
+        assert_eq!(restored.controllers, state.controllers);
+    }
+
     #[test]
     fn apply_setpoints_to_controllers() {
         let pid_cfg = PidConfig {
@@ -790,12 +2985,14 @@ mod tests {
                 inputs: vec!["sensor".into()],
                 outputs: vec!["actuator".into()],
                 controller: pid_controller,
+                ..Default::default()
             },
             Loop {
                 id: "bb".into(),
                 inputs: vec!["a".into()],
                 outputs: vec!["b".into()],
                 controller: bb,
+                ..Default::default()
             },
         ];
         let mut state = SystemState::default();
@@ -808,6 +3005,8 @@ mod tests {
         let mut state = runtime.next((&state, &dt)).unwrap();
         let mut expected_pid_state = PidState {
             prev_value: Some(0.0),
+            prev_error: Some(0.0),
+            output: Some(0.0),
             ..Default::default()
         };
         assert_eq!(
@@ -843,6 +3042,7 @@ mod tests {
                     from: "start".into(),
                     to: "step-one".into(),
                     actions: vec![],
+                    after: None,
                 },
                 Transition {
                     condition: BoolExpr::Eval(
@@ -851,8 +3051,10 @@ mod tests {
                     from: "step-one".into(),
                     to: "step-two".into(),
                     actions: vec![],
+                    after: None,
                 },
             ],
+            ..Default::default()
         };
         let mut rt = SyncRuntime::default();
         rt.state_machines.insert("fsm".into(), sm);
@@ -869,6 +3071,159 @@ mod tests {
         );
     }
 
+    #[test]
+    fn reenter_nested_fsm_states_with_history() {
+        let dt = Duration::from_secs(1);
+        let cond =
+            |id: &str| BoolExpr::Eval(Source::In(id.into()).cmp_eq(Source::Const(true.into())));
+        let sm = StateMachine {
+            initial: "running".into(),
+            transitions: vec![
+                Transition {
+                    condition: cond("next"),
+                    from: "step-one".into(),
+                    to: "step-two".into(),
+                    actions: vec![],
+                    after: None,
+                },
+                Transition {
+                    condition: cond("pause"),
+                    from: "running".into(),
+                    to: "paused".into(),
+                    actions: vec![],
+                    after: None,
+                },
+                Transition {
+                    condition: BoolExpr::Not(Box::new(cond("pause"))),
+                    from: "paused".into(),
+                    to: "running".into(),
+                    actions: vec![],
+                    after: None,
+                },
+            ],
+            states: vec![
+                State {
+                    id: "running".into(),
+                    history: History::Shallow,
+                    ..Default::default()
+                },
+                State {
+                    id: "step-one".into(),
+                    parent: Some("running".into()),
+                    ..Default::default()
+                },
+                State {
+                    id: "step-two".into(),
+                    parent: Some("running".into()),
+                    ..Default::default()
+                },
+            ],
+        };
+        let mut rt = SyncRuntime::default();
+        rt.state_machines.insert("fsm".into(), sm);
+        let mut state = SystemState::default();
+        state.io.inputs.insert("next".into(), true.into());
+        state.io.inputs.insert("pause".into(), false.into());
+        state = rt.next((&state, &dt)).unwrap();
+        assert_eq!(state.state_machines["fsm"], "step-two");
+        state.io.inputs.insert("pause".into(), true.into());
+        state = rt.next((&state, &dt)).unwrap();
+        assert_eq!(state.state_machines["fsm"], "paused");
+        assert_eq!(
+            state.state_machine_data["fsm"].history["running"],
+            "step-two"
+        );
+        state.io.inputs.insert("pause".into(), false.into());
+        state = rt.next((&state, &dt)).unwrap();
+        assert_eq!(state.state_machines["fsm"], "step-two");
+    }
+
+    #[test]
+    fn fire_timed_fsm_transitions() {
+        let dt = Duration::from_millis(500);
+        let sm = StateMachine {
+            initial: "flushing".into(),
+            transitions: vec![Transition {
+                condition: BoolExpr::True,
+                from: "flushing".into(),
+                to: "ready".into(),
+                actions: vec![],
+                after: Some(Duration::from_secs(2)),
+            }],
+            ..Default::default()
+        };
+        let mut rt = SyncRuntime::default();
+        rt.state_machines.insert("fsm".into(), sm);
+        let mut state = SystemState::default();
+        state.state_machines.insert("fsm".into(), "flushing".into());
+        for _ in 0..3 {
+            state = rt.next((&state, &dt)).unwrap();
+            assert_eq!(state.state_machines["fsm"], "flushing");
+        }
+        state = rt.next((&state, &dt)).unwrap();
+        assert_eq!(state.state_machines["fsm"], "ready");
+        assert_eq!(state.state_machine_data["fsm"].elapsed, HashMap::new());
+    }
+
+    #[test]
+    fn apply_fsm_state_actions() {
+        let dt = Duration::from_secs(1);
+        let action = |id: &str, memory: &[(&str, Source)]| Action {
+            id: id.into(),
+            memory: memory
+                .iter()
+                .map(|(k, src)| (k.to_string(), src.clone()))
+                .collect(),
+            ..Default::default()
+        };
+        let mut rt = SyncRuntime {
+            actions: vec![
+                action("open", &[("valve", Source::Const(true.into()))]),
+                action("close", &[("valve", Source::Const(false.into()))]),
+                action("busy", &[("busy", Source::Const(true.into()))]),
+            ],
+            ..Default::default()
+        };
+        let sm = StateMachine {
+            initial: "idle".into(),
+            transitions: vec![Transition {
+                condition: BoolExpr::Eval(
+                    Source::In("start".into()).cmp_eq(Source::Const(true.into())),
+                ),
+                from: "idle".into(),
+                to: "filling".into(),
+                actions: vec![],
+                after: None,
+            }],
+            states: vec![
+                State {
+                    id: "idle".into(),
+                    entry: vec!["close".into()],
+                    ..Default::default()
+                },
+                State {
+                    id: "filling".into(),
+                    entry: vec!["open".into()],
+                    exit: vec!["close".into()],
+                    during: vec!["busy".into()],
+                    ..Default::default()
+                },
+            ],
+        };
+        rt.state_machines.insert("fsm".into(), sm);
+        let mut state = SystemState::default();
+        state.io.inputs.insert("start".into(), false.into());
+        state = rt.next((&state, &dt)).unwrap();
+        // The entry actions of the initial state are applied on start
+        assert_eq!(state.io.mem["valve"], Value::Bit(false));
+        assert!(!state.io.mem.contains_key("busy"));
+        state.io.inputs.insert("start".into(), true.into());
+        state = rt.next((&state, &dt)).unwrap();
+        assert_eq!(state.state_machines["fsm"], "filling");
+        assert_eq!(state.io.mem["valve"], Value::Bit(true));
+        assert_eq!(state.io.mem["busy"], Value::Bit(true));
+    }
+
     #[test]
     fn apply_fsm_transition_actions() {
         let dt = Duration::from_secs(1);
@@ -882,6 +3237,7 @@ mod tests {
                     from: "start".into(),
                     to: "step-one".into(),
                     actions: vec!["foo".into()],
+                    after: None,
                 },
                 Transition {
                     condition: BoolExpr::Eval(
@@ -890,8 +3246,10 @@ mod tests {
                     from: "step-one".into(),
                     to: "step-two".into(),
                     actions: vec!["bar".into()],
+                    after: None,
                 },
             ],
+            ..Default::default()
         };
         let mut rt = SyncRuntime::default();
         let mut foo_outputs = HashMap::new();
@@ -906,6 +3264,8 @@ mod tests {
                 memory: HashMap::new(),
                 timeouts: HashMap::new(),
                 controllers: HashMap::new(),
+                counters: HashMap::new(),
+                ..Default::default()
             },
             Action {
                 id: "bar".into(),
@@ -914,6 +3274,8 @@ mod tests {
                 memory: HashMap::new(),
                 timeouts: HashMap::new(),
                 controllers: HashMap::new(),
+                counters: HashMap::new(),
+                ..Default::default()
             },
         ];
         rt.state_machines.insert("fsm".into(), sm);
@@ -949,12 +3311,14 @@ mod tests {
                 inputs: vec!["sensor_0".into()],
                 outputs: vec!["actuator_0".into()],
                 controller: pid_controller_0,
+                ..Default::default()
             },
             Loop {
                 id: "pid_1".into(),
                 inputs: vec!["sensor_1".into()],
                 outputs: vec!["actuator_1".into()],
                 controller: pid_controller_1,
+                ..Default::default()
             },
         ];
         state.io.inputs.insert("sensor_1".into(), 5.0.into());
@@ -0,0 +1,173 @@
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{PureController, scaling::*};
+//!
+//! // Analog input with 4-20 mA as 4000-20000 counts → 0-10 bar
+//! let pressure = Scaling {
+//!     raw_min: 4000.0,
+//!     raw_max: 20000.0,
+//!     min: 0.0,
+//!     max: 10.0,
+//!     clamp: true,
+//! };
+//! assert!(pressure.validate().is_ok());
+//!
+//! assert_eq!(pressure.next(12000.0), (5.0, false));
+//! assert_eq!(pressure.next(0.0), (0.0, true)); // wire break
+//! assert_eq!(pressure.raw(2.5), 8000.0);
+//! ```
+
+use super::PureController;
+use std::io::{Error, ErrorKind, Result};
+
+/// Linear scaling from a raw range to an engineering range
+///
+/// Inverted ranges (e.g. `raw_min > raw_max`) are supported.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct Scaling {
+    /// Raw value that corresponds to `min`
+    pub raw_min: f64,
+    /// Raw value that corresponds to `max`
+    pub raw_max: f64,
+    /// Lower end of the engineering range
+    pub min: f64,
+    /// Upper end of the engineering range
+    pub max: f64,
+    /// Limit the output to the engineering range
+    pub clamp: bool,
+}
+
+impl Default for Scaling {
+    fn default() -> Self {
+        Scaling {
+            raw_min: 0.0,
+            raw_max: 1.0,
+            min: 0.0,
+            max: 1.0,
+            clamp: false,
+        }
+    }
+}
+
+impl Scaling {
+    /// Check the consistency of the ranges.
+    pub fn validate(&self) -> Result<()> {
+        if !self.raw_min.is_finite()
+            || !self.raw_max.is_finite()
+            || !self.min.is_finite()
+            || !self.max.is_finite()
+        {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The range limits have to be finite",
+            ));
+        }
+        if self.raw_min == self.raw_max {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "The raw range must not be empty",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Check whether a raw value is outside of the raw range.
+    pub fn is_out_of_range(&self, raw: f64) -> bool {
+        let (lower, upper) = if self.raw_min <= self.raw_max {
+            (self.raw_min, self.raw_max)
+        } else {
+            (self.raw_max, self.raw_min)
+        };
+        !(lower..=upper).contains(&raw)
+    }
+
+    /// Convert an engineering value back to a raw value
+    /// (e.g. to write an analog output).
+    pub fn raw(&self, value: f64) -> f64 {
+        let t = (value - self.min) / (self.max - self.min);
+        self.raw_min + t * (self.raw_max - self.raw_min)
+    }
+}
+
+/// The output is the engineering value and the out-of-range flag.
+impl PureController<f64, (f64, bool)> for Scaling {
+    fn next(&self, raw: f64) -> (f64, bool) {
+        let out_of_range = self.is_out_of_range(raw);
+        let mut t = (raw - self.raw_min) / (self.raw_max - self.raw_min);
+        if self.clamp && out_of_range {
+            t = t.clamp(0.0, 1.0);
+        }
+        (self.min + t * (self.max - self.min), out_of_range)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn scale_values() {
+        let s = Scaling {
+            raw_min: 0.0,
+            raw_max: 27648.0,
+            min: -50.0,
+            max: 150.0,
+            clamp: false,
+        };
+        assert_eq!(s.next(0.0), (-50.0, false));
+        assert_eq!(s.next(13824.0), (50.0, false));
+        assert_eq!(s.next(27648.0), (150.0, false));
+        // Overrange is not limited without clamping
+        assert_eq!(s.next(41472.0), (250.0, true));
+        assert_eq!(s.raw(50.0), 13824.0);
+    }
+
+    #[test]
+    fn clamp_values() {
+        let s = Scaling {
+            raw_min: 4.0,
+            raw_max: 20.0,
+            min: 100.0,
+            max: 0.0,
+            clamp: true,
+        };
+        assert_eq!(s.next(4.0), (100.0, false));
+        assert_eq!(s.next(8.0), (75.0, false));
+        assert_eq!(s.next(22.0), (0.0, true));
+        assert_eq!(s.next(3.0), (100.0, true));
+    }
+
+    #[test]
+    fn support_inverted_raw_ranges() {
+        let s = Scaling {
+            raw_min: 10.0,
+            raw_max: 0.0,
+            clamp: true,
+            ..Default::default()
+        };
+        assert_eq!(s.next(2.5), (0.75, false));
+        assert_eq!(s.next(-1.0), (1.0, true));
+        assert_eq!(s.next(11.0), (0.0, true));
+    }
+
+    #[test]
+    fn validate_ranges() {
+        assert!(Scaling::default().validate().is_ok());
+        let s = Scaling {
+            raw_min: 5.0,
+            raw_max: 5.0,
+            ..Default::default()
+        };
+        assert!(s.validate().is_err());
+        let s = Scaling {
+            max: f64::NAN,
+            ..Default::default()
+        };
+        assert!(s.validate().is_err());
+    }
+}
@@ -0,0 +1,109 @@
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{PureController, selector::*};
+//!
+//! // Two out of three temperature sensors
+//! let temperatures = [21.3, 85.0, 21.5];
+//!
+//! assert_eq!(Selector::Median.next(&temperatures), 21.5);
+//! assert_eq!(Selector::Median.selected(&temperatures), Some(2));
+//! assert_eq!(Selector::Max.next(&temperatures), 85.0);
+//! ```
+
+use super::PureController;
+
+/// Signal selector
+///
+/// Selects one of N signals, e.g. the smallest output of
+/// several controllers in an override control scheme or the
+/// median of redundant sensors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Selector {
+    /// The smallest input
+    Min,
+    /// The greatest input
+    Max,
+    /// The median of all inputs
+    ///
+    /// For an even number of inputs the lower of
+    /// the two middle values is selected.
+    Median,
+    /// The mean value of all inputs
+    Average,
+}
+
+impl Selector {
+    /// The index of the selected input.
+    ///
+    /// Returns `None` if there are no inputs or if the output
+    /// is not one of the inputs (average).
+    pub fn selected(&self, x: &[f64]) -> Option<usize> {
+        let cmp = |a: &(usize, &f64), b: &(usize, &f64)| a.1.total_cmp(b.1);
+        match self {
+            Selector::Min => x.iter().enumerate().min_by(cmp).map(|(i, _)| i),
+            Selector::Max => x.iter().enumerate().max_by(cmp).map(|(i, _)| i),
+            Selector::Median => {
+                let mut sorted = x.iter().enumerate().collect::<Vec<_>>();
+                sorted.sort_by(cmp);
+                if sorted.is_empty() {
+                    None
+                } else {
+                    Some(sorted[(sorted.len() - 1) / 2].0)
+                }
+            }
+            Selector::Average => None,
+        }
+    }
+}
+
+/// The output is NaN if there are no inputs.
+impl PureController<&[f64], f64> for Selector {
+    fn next(&self, x: &[f64]) -> f64 {
+        match self {
+            Selector::Average => {
+                if x.is_empty() {
+                    f64::NAN
+                } else {
+                    x.iter().sum::<f64>() / x.len() as f64
+                }
+            }
+            _ => self.selected(x).map(|i| x[i]).unwrap_or(f64::NAN),
+        }
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn select_inputs() {
+        let x = [3.0, -1.0, 7.0, 5.0];
+        assert_eq!(Selector::Min.next(&x), -1.0);
+        assert_eq!(Selector::Min.selected(&x), Some(1));
+        assert_eq!(Selector::Max.next(&x), 7.0);
+        assert_eq!(Selector::Max.selected(&x), Some(2));
+        assert_eq!(Selector::Median.next(&x), 3.0);
+        assert_eq!(Selector::Median.selected(&x), Some(0));
+        assert_eq!(Selector::Median.next(&x[1..]), 5.0);
+        assert_eq!(Selector::Average.next(&x), 3.5);
+        assert_eq!(Selector::Average.selected(&x), None);
+    }
+
+    #[test]
+    fn select_without_inputs() {
+        for sel in [
+            Selector::Min,
+            Selector::Max,
+            Selector::Median,
+            Selector::Average,
+        ] {
+            assert!(sel.next(&[]).is_nan());
+            assert_eq!(sel.selected(&[]), None);
+        }
+    }
+}
@@ -0,0 +1,302 @@
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{Controller, spc::*};
+//!
+//! let limits = ControlLimits {
+//!     center: 50.0,
+//!     sigma: 2.0,
+//! };
+//! let mut spc = Spc::new(SpcConfig::western_electric(limits));
+//!
+//! assert!(spc.next(51.0).is_empty());
+//! assert!(spc.next(55.0).is_empty());
+//! assert_eq!(spc.next(55.5), vec![SpcRule::TwoOfThree]);
+//! assert_eq!(spc.next(57.0), vec![SpcRule::BeyondLimits]);
+//! assert_eq!(spc.state.violations, vec![SpcRule::BeyondLimits, SpcRule::TwoOfThree]);
+//! ```
+
+use super::{Controller, PureController};
+use std::collections::VecDeque;
+
+/// Statistical process control
+///
+/// Evaluates Western Electric or Nelson rules against a signal relative
+/// to its control limits. Each step returns the rules that are violated
+/// for the first time (rule violation events).
+#[derive(Debug, Clone)]
+pub struct Spc {
+    cfg: SpcConfig,
+    /// Current state
+    pub state: SpcState,
+}
+
+/// Control limits of a process
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ControlLimits {
+    /// Center line (process mean)
+    pub center: f64,
+    /// Standard deviation of the process
+    pub sigma: f64,
+}
+
+/// A rule that detects a non-random pattern
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum SpcRule {
+    /// One point is more than 3 sigma away from the center line
+    BeyondLimits,
+    /// The given number of points in a row are on the same side of the center line
+    SameSide(usize),
+    /// The given number of points in a row are continually increasing or decreasing
+    Trend(usize),
+    /// The given number of points in a row alternate in direction
+    Alternating(usize),
+    /// Two out of three points in a row are more than 2 sigma
+    /// away from the center line on the same side
+    TwoOfThree,
+    /// Four out of five points in a row are more than 1 sigma
+    /// away from the center line on the same side
+    FourOfFive,
+    /// The given number of points in a row are within 1 sigma of the center line
+    Stratification(usize),
+    /// The given number of points in a row are more than 1 sigma
+    /// away from the center line on both sides
+    Mixture(usize),
+}
+
+/// SPC configuration
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpcConfig {
+    /// Control limits
+    pub limits: ControlLimits,
+    /// Evaluated rules
+    pub rules: Vec<SpcRule>,
+}
+
+impl SpcConfig {
+    /// The Western Electric rules.
+    pub fn western_electric(limits: ControlLimits) -> Self {
+        SpcConfig {
+            limits,
+            rules: vec![
+                SpcRule::BeyondLimits,
+                SpcRule::TwoOfThree,
+                SpcRule::FourOfFive,
+                SpcRule::SameSide(8),
+            ],
+        }
+    }
+    /// The Nelson rules.
+    pub fn nelson(limits: ControlLimits) -> Self {
+        SpcConfig {
+            limits,
+            rules: vec![
+                SpcRule::BeyondLimits,
+                SpcRule::SameSide(9),
+                SpcRule::Trend(6),
+                SpcRule::Alternating(14),
+                SpcRule::TwoOfThree,
+                SpcRule::FourOfFive,
+                SpcRule::Stratification(15),
+                SpcRule::Mixture(8),
+            ],
+        }
+    }
+}
+
+/// Internal SPC state
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpcState {
+    /// The latest values (as many as required by the rules)
+    pub values: VecDeque<f64>,
+    /// Currently violated rules
+    pub violations: Vec<SpcRule>,
+}
+
+impl Spc {
+    /// Create a new SPC instance.
+    pub fn new(cfg: SpcConfig) -> Self {
+        Spc {
+            cfg,
+            state: SpcState::default(),
+        }
+    }
+    /// Reset the internal state.
+    pub fn reset(&mut self) {
+        self.state = SpcState::default();
+    }
+}
+
+impl SpcRule {
+    /// The number of values that are required to evaluate the rule.
+    pub fn window(&self) -> usize {
+        match self {
+            SpcRule::BeyondLimits => 1,
+            SpcRule::TwoOfThree => 3,
+            SpcRule::FourOfFive => 5,
+            SpcRule::SameSide(n)
+            | SpcRule::Trend(n)
+            | SpcRule::Alternating(n)
+            | SpcRule::Stratification(n)
+            | SpcRule::Mixture(n) => (*n).max(1),
+        }
+    }
+
+    /// Check whether the latest values violate the rule.
+    pub fn is_violated(&self, limits: &ControlLimits, values: &VecDeque<f64>) -> bool {
+        let n = self.window();
+        if values.len() < n {
+            return false;
+        }
+        let last = values
+            .iter()
+            .skip(values.len() - n)
+            .copied()
+            .collect::<Vec<_>>();
+        // Deviation from the center line in multiples of sigma
+        let z = last
+            .iter()
+            .map(|x| (x - limits.center) / limits.sigma)
+            .collect::<Vec<_>>();
+        let diffs = last.windows(2).map(|w| w[1] - w[0]).collect::<Vec<_>>();
+        let same_side = |k: usize, limit: f64| {
+            z.iter().filter(|z| **z > limit).count() >= k
+                || z.iter().filter(|z| **z < -limit).count() >= k
+        };
+        match self {
+            SpcRule::BeyondLimits => z[0].abs() > 3.0,
+            SpcRule::SameSide(_) => same_side(n, 0.0),
+            SpcRule::Trend(_) => diffs.iter().all(|d| *d > 0.0) || diffs.iter().all(|d| *d < 0.0),
+            SpcRule::Alternating(_) => {
+                diffs.iter().all(|d| *d != 0.0) && diffs.windows(2).all(|w| w[0] * w[1] < 0.0)
+            }
+            SpcRule::TwoOfThree => same_side(2, 2.0),
+            SpcRule::FourOfFive => same_side(4, 1.0),
+            SpcRule::Stratification(_) => z.iter().all(|z| z.abs() < 1.0),
+            SpcRule::Mixture(_) => {
+                z.iter().all(|z| z.abs() > 1.0)
+                    && z.iter().any(|z| *z > 0.0)
+                    && z.iter().any(|z| *z < 0.0)
+            }
+        }
+    }
+}
+
+impl Controller<f64, Vec<SpcRule>> for Spc {
+    fn next(&mut self, x: f64) -> Vec<SpcRule> {
+        let state = std::mem::take(&mut self.state);
+        let (state, events) = self.cfg.next((state, x));
+        self.state = state;
+        events
+    }
+}
+
+impl PureController<(SpcState, f64), (SpcState, Vec<SpcRule>)> for SpcConfig {
+    fn next(&self, input: (SpcState, f64)) -> (SpcState, Vec<SpcRule>) {
+        let (mut state, x) = input;
+        let len = self.rules.iter().map(SpcRule::window).max().unwrap_or(1);
+        state.values.push_back(x);
+        while state.values.len() > len {
+            state.values.pop_front();
+        }
+        let violations = self
+            .rules
+            .iter()
+            .filter(|r| r.is_violated(&self.limits, &state.values))
+            .copied()
+            .collect::<Vec<_>>();
+        let events = violations
+            .iter()
+            .filter(|r| !state.violations.contains(r))
+            .copied()
+            .collect();
+        state.violations = violations;
+        (state, events)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    const LIMITS: ControlLimits = ControlLimits {
+        center: 0.0,
+        sigma: 1.0,
+    };
+
+    fn violated(rule: SpcRule, values: &[f64]) -> bool {
+        rule.is_violated(&LIMITS, &values.iter().copied().collect())
+    }
+
+    #[test]
+    fn evaluate_rules() {
+        assert!(violated(SpcRule::BeyondLimits, &[3.1]));
+        assert!(violated(SpcRule::BeyondLimits, &[0.0, -3.5]));
+        assert!(!violated(SpcRule::BeyondLimits, &[3.5, 0.0]));
+
+        assert!(violated(SpcRule::SameSide(3), &[-1.0, 0.1, 0.2, 0.3]));
+        assert!(!violated(SpcRule::SameSide(3), &[0.1, -0.2, 0.3]));
+        assert!(!violated(SpcRule::SameSide(3), &[0.1, 0.3]));
+
+        assert!(violated(SpcRule::Trend(4), &[1.0, 0.5, 0.0, -2.0]));
+        assert!(!violated(SpcRule::Trend(4), &[1.0, 0.5, 0.5, -2.0]));
+
+        assert!(violated(SpcRule::Alternating(4), &[0.0, 1.0, 0.5, 0.7]));
+        assert!(!violated(SpcRule::Alternating(4), &[0.0, 1.0, 1.5, 0.7]));
+
+        assert!(violated(SpcRule::TwoOfThree, &[2.1, 0.0, 2.5]));
+        assert!(!violated(SpcRule::TwoOfThree, &[2.1, 0.0, -2.5]));
+
+        assert!(violated(
+            SpcRule::FourOfFive,
+            &[-1.1, -1.5, 0.0, -2.0, -1.2]
+        ));
+        assert!(!violated(
+            SpcRule::FourOfFive,
+            &[-1.1, -1.5, 0.0, -2.0, 1.2]
+        ));
+
+        assert!(violated(SpcRule::Stratification(3), &[0.5, -0.9, 0.1]));
+        assert!(!violated(SpcRule::Stratification(3), &[0.5, -1.9, 0.1]));
+
+        assert!(violated(SpcRule::Mixture(3), &[1.5, -1.9, 1.1]));
+        assert!(!violated(SpcRule::Mixture(3), &[1.5, 1.9, 1.1]));
+        assert!(!violated(SpcRule::Mixture(3), &[1.5, -0.9, 1.1]));
+    }
+
+    #[test]
+    fn emit_violation_events() {
+        let mut spc = Spc::new(SpcConfig {
+            limits: LIMITS,
+            rules: vec![SpcRule::SameSide(3), SpcRule::Trend(3)],
+        });
+        assert!(spc.next(0.1).is_empty());
+        assert!(spc.next(0.2).is_empty());
+        assert_eq!(spc.next(0.3), vec![SpcRule::SameSide(3), SpcRule::Trend(3)]);
+        // The violations are only reported once
+        assert!(spc.next(0.4).is_empty());
+        assert!(spc.next(0.2).is_empty());
+        assert_eq!(spc.state.violations, vec![SpcRule::SameSide(3)]);
+        assert_eq!(spc.state.values.len(), 3);
+        assert_eq!(spc.next(-0.2), vec![SpcRule::Trend(3)]);
+        assert_eq!(spc.state.violations, vec![SpcRule::Trend(3)]);
+        spc.reset();
+        assert_eq!(spc.state, SpcState::default());
+    }
+
+    #[test]
+    fn use_predefined_rule_sets() {
+        let mut spc = Spc::new(SpcConfig::nelson(LIMITS));
+        let mut events = vec![];
+        for x in [0.1, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9] {
+            events.extend(spc.next(x));
+        }
+        assert_eq!(events, vec![SpcRule::Trend(6), SpcRule::SameSide(9)]);
+        assert_eq!(spc.state.values.len(), 9);
+    }
+}
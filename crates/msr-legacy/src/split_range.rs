@@ -0,0 +1,143 @@
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{PureController, split_range::*};
+//!
+//! // Heating below 0 %, cooling above 0 %
+//! let cfg = SplitRangeConfig::new(-100.0, 0.0, 100.0, 0.0);
+//!
+//! assert_eq!(cfg.next(-50.0), (50.0, 0.0));
+//! assert_eq!(cfg.next(0.0), (0.0, 0.0));
+//! assert_eq!(cfg.next(100.0), (0.0, 100.0));
+//! ```
+
+use super::{PureController, ValueBounds, ValueMapping};
+
+/// Split-range configuration
+///
+/// Maps a single controller output onto two actuator outputs.
+/// Each actuator has its own linear mapping and the results are
+/// limited to the respective target range.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SplitRangeConfig {
+    /// Mapping of the controller output onto the first actuator
+    pub first: ValueMapping,
+    /// Mapping of the controller output onto the second actuator
+    pub second: ValueMapping,
+}
+
+impl SplitRangeConfig {
+    /// Create a split-range configuration for the common case:
+    ///
+    /// The first actuator is opened from `0 %` to `100 %` while the
+    /// controller output decreases from the breakpoint to `min`.
+    /// The second actuator is opened from `0 %` to `100 %` while the
+    /// controller output increases from the breakpoint to `max`.
+    /// A positive `overlap` shifts the starting points of both ranges
+    /// by `overlap / 2` across the breakpoint, a negative one creates
+    /// a dead band.
+    pub fn new(min: f64, breakpoint: f64, max: f64, overlap: f64) -> Self {
+        let percent = ValueBounds {
+            low: 0.0,
+            high: 100.0,
+        };
+        SplitRangeConfig {
+            first: ValueMapping {
+                from: ValueBounds {
+                    low: breakpoint + overlap / 2.0,
+                    high: min,
+                },
+                to: percent.clone(),
+            },
+            second: ValueMapping {
+                from: ValueBounds {
+                    low: breakpoint - overlap / 2.0,
+                    high: max,
+                },
+                to: percent,
+            },
+        }
+    }
+}
+
+impl Default for SplitRangeConfig {
+    fn default() -> Self {
+        Self::new(-100.0, 0.0, 100.0, 0.0)
+    }
+}
+
+fn map_limited(mapping: &ValueMapping, x: f64) -> f64 {
+    let (low, high) = if mapping.to.low <= mapping.to.high {
+        (mapping.to.low, mapping.to.high)
+    } else {
+        (mapping.to.high, mapping.to.low)
+    };
+    mapping.map(x).clamp(low, high)
+}
+
+impl PureController<f64, (f64, f64)> for SplitRangeConfig {
+    fn next(&self, input: f64) -> (f64, f64) {
+        (
+            map_limited(&self.first, input),
+            map_limited(&self.second, input),
+        )
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn split_without_overlap() {
+        let cfg = SplitRangeConfig::default();
+        assert_eq!(cfg.next(-150.0), (100.0, 0.0));
+        assert_eq!(cfg.next(-100.0), (100.0, 0.0));
+        assert_eq!(cfg.next(-25.0), (25.0, 0.0));
+        assert_eq!(cfg.next(0.0), (0.0, 0.0));
+        assert_eq!(cfg.next(25.0), (0.0, 25.0));
+        assert_eq!(cfg.next(150.0), (0.0, 100.0));
+    }
+
+    #[test]
+    fn split_with_breakpoint_and_overlap() {
+        let cfg = SplitRangeConfig::new(0.0, 50.0, 100.0, 20.0);
+        assert_eq!(cfg.next(0.0), (100.0, 0.0));
+        assert_eq!(cfg.next(50.0).0, 100.0 * 10.0 / 60.0);
+        assert_eq!(cfg.next(50.0).1, 100.0 * 10.0 / 60.0);
+        assert_eq!(cfg.next(60.0), (0.0, 100.0 * 20.0 / 60.0));
+        assert_eq!(cfg.next(100.0), (0.0, 100.0));
+    }
+
+    #[test]
+    fn split_with_dead_band() {
+        let cfg = SplitRangeConfig::new(-100.0, 0.0, 100.0, -10.0);
+        assert_eq!(cfg.next(-5.0), (0.0, 0.0));
+        assert_eq!(cfg.next(5.0), (0.0, 0.0));
+        assert!(cfg.next(-6.0).0 > 0.0);
+        assert!(cfg.next(6.0).1 > 0.0);
+    }
+
+    #[test]
+    fn limit_to_descending_target_range() {
+        let cfg = SplitRangeConfig {
+            first: ValueMapping {
+                from: ValueBounds {
+                    low: 0.0,
+                    high: 10.0,
+                },
+                to: ValueBounds {
+                    low: 100.0,
+                    high: 0.0,
+                },
+            },
+            ..Default::default()
+        };
+        assert_eq!(cfg.next(-1.0).0, 100.0);
+        assert_eq!(cfg.next(5.0).0, 50.0);
+        assert_eq!(cfg.next(11.0).0, 0.0);
+    }
+}
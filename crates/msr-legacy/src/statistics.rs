@@ -0,0 +1,244 @@
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{Controller, statistics::*};
+//!
+//! let mut s = Statistics::new(StatisticsConfig {
+//!     window: 4,
+//!     ..Default::default()
+//! });
+//!
+//! let mut summary = Summary::default();
+//! for x in [1.0, 9.0, 2.0, 4.0, 6.0] {
+//!     summary = s.next(x);
+//! }
+//! assert_eq!(summary.mean, 5.25);
+//! assert_eq!(summary.min, 2.0);
+//! assert_eq!(summary.max, 9.0);
+//! assert_eq!(summary.count, 5);
+//! ```
+
+use super::{Controller, PureController};
+use std::collections::VecDeque;
+
+/// Online statistics of a signal
+///
+/// The windowed statistics are calculated from the last `window` values.
+/// The exponentially weighted statistics cover all values with
+/// decreasing weights. The memory usage is bounded by the window size.
+#[derive(Debug, Clone)]
+pub struct Statistics {
+    cfg: StatisticsConfig,
+    /// Current state
+    pub state: StatisticsState,
+}
+
+/// Statistics configuration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct StatisticsConfig {
+    /// Number of values of the windowed statistics
+    pub window: usize,
+    /// Weight of the latest value in the exponentially weighted statistics
+    /// (`0.0 < smoothing <= 1.0`)
+    pub smoothing: f64,
+}
+
+impl Default for StatisticsConfig {
+    fn default() -> Self {
+        StatisticsConfig {
+            window: 10,
+            smoothing: 0.1,
+        }
+    }
+}
+
+/// Internal statistics state
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StatisticsState {
+    /// The latest values (at most `window`)
+    pub values: VecDeque<f64>,
+    /// Exponentially weighted moving average
+    pub ewma: Option<f64>,
+    /// Exponentially weighted moving variance
+    pub ewmv: f64,
+    /// Number of values since the last reset
+    pub count: u64,
+}
+
+/// The statistics of a signal
+///
+/// All values are NaN if no value has been received yet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Summary {
+    /// Exponentially weighted moving average
+    pub ewma: f64,
+    /// Exponentially weighted moving standard deviation
+    pub ewm_stddev: f64,
+    /// Mean value of the window
+    pub mean: f64,
+    /// Sample standard deviation of the window
+    pub stddev: f64,
+    /// Minimum of the window
+    pub min: f64,
+    /// Maximum of the window
+    pub max: f64,
+    /// Number of values since the last reset
+    pub count: u64,
+}
+
+impl Default for Summary {
+    fn default() -> Self {
+        Summary {
+            ewma: f64::NAN,
+            ewm_stddev: f64::NAN,
+            mean: f64::NAN,
+            stddev: f64::NAN,
+            min: f64::NAN,
+            max: f64::NAN,
+            count: 0,
+        }
+    }
+}
+
+impl Statistics {
+    /// Create a new statistics instance.
+    pub fn new(cfg: StatisticsConfig) -> Self {
+        Statistics {
+            cfg,
+            state: StatisticsState::default(),
+        }
+    }
+    /// Reset the internal state.
+    pub fn reset(&mut self) {
+        self.state = StatisticsState::default();
+    }
+}
+
+impl StatisticsState {
+    /// Summarize the current state.
+    pub fn summary(&self) -> Summary {
+        let n = self.values.len();
+        if n == 0 {
+            return Summary {
+                count: self.count,
+                ..Default::default()
+            };
+        }
+        let mean = self.values.iter().sum::<f64>() / n as f64;
+        let stddev = if n > 1 {
+            let sum_sq = self.values.iter().map(|x| (x - mean).powi(2)).sum::<f64>();
+            (sum_sq / (n - 1) as f64).sqrt()
+        } else {
+            0.0
+        };
+        Summary {
+            ewma: self.ewma.unwrap_or(f64::NAN),
+            ewm_stddev: self.ewmv.sqrt(),
+            mean,
+            stddev,
+            min: self.values.iter().copied().fold(f64::INFINITY, f64::min),
+            max: self
+                .values
+                .iter()
+                .copied()
+                .fold(f64::NEG_INFINITY, f64::max),
+            count: self.count,
+        }
+    }
+}
+
+impl Controller<f64, Summary> for Statistics {
+    fn next(&mut self, x: f64) -> Summary {
+        let state = std::mem::take(&mut self.state);
+        let (state, summary) = self.cfg.next((state, x));
+        self.state = state;
+        summary
+    }
+}
+
+impl PureController<(StatisticsState, f64), (StatisticsState, Summary)> for StatisticsConfig {
+    fn next(&self, input: (StatisticsState, f64)) -> (StatisticsState, Summary) {
+        let (mut state, x) = input;
+        let window = self.window.max(1);
+        while state.values.len() >= window {
+            state.values.pop_front();
+        }
+        state.values.push_back(x);
+        state.count += 1;
+        let alpha = self.smoothing.clamp(f64::EPSILON, 1.0);
+        state.ewma = Some(match state.ewma {
+            Some(ewma) => {
+                let delta = x - ewma;
+                state.ewmv = (1.0 - alpha) * (state.ewmv + alpha * delta * delta);
+                ewma + alpha * delta
+            }
+            None => x,
+        });
+        let summary = state.summary();
+        (state, summary)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn calculate_windowed_statistics() {
+        let mut s = Statistics::new(StatisticsConfig {
+            window: 3,
+            ..Default::default()
+        });
+        let summary = s.next(2.0);
+        assert_eq!(summary.mean, 2.0);
+        assert_eq!(summary.stddev, 0.0);
+        s.next(4.0);
+        let summary = s.next(6.0);
+        assert_eq!(summary.mean, 4.0);
+        assert_eq!(summary.stddev, 2.0);
+        assert_eq!(summary.min, 2.0);
+        assert_eq!(summary.max, 6.0);
+        let summary = s.next(-3.0);
+        assert_eq!(summary.min, -3.0);
+        assert_eq!(summary.max, 6.0);
+        assert_eq!(summary.count, 4);
+        assert_eq!(s.state.values.len(), 3);
+    }
+
+    #[test]
+    fn calculate_exponentially_weighted_statistics() {
+        let mut s = Statistics::new(StatisticsConfig {
+            smoothing: 0.5,
+            ..Default::default()
+        });
+        let summary = s.next(4.0);
+        assert_eq!(summary.ewma, 4.0);
+        assert_eq!(summary.ewm_stddev, 0.0);
+        let summary = s.next(8.0);
+        assert_eq!(summary.ewma, 6.0);
+        assert_eq!(summary.ewm_stddev, 2.0);
+        for _ in 0..100 {
+            s.next(10.0);
+        }
+        let summary = s.next(10.0);
+        assert!((summary.ewma - 10.0).abs() < 1e-9);
+        assert!(summary.ewm_stddev < 1e-9);
+    }
+
+    #[test]
+    fn summarize_empty_state() {
+        let summary = StatisticsState::default().summary();
+        assert!(summary.mean.is_nan());
+        assert!(summary.ewma.is_nan());
+        assert_eq!(summary.count, 0);
+        let mut s = Statistics::new(StatisticsConfig::default());
+        s.next(1.0);
+        s.reset();
+        assert_eq!(s.state, StatisticsState::default());
+    }
+}
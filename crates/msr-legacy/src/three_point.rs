@@ -0,0 +1,284 @@
+//! # Example
+//!
+//! ```rust,no_run
+//! use std::{thread, time::Duration};
+//! use msr_legacy::{TimeStepController, pid::PidConfig, three_point::*};
+//!
+//! let cfg = ThreePointConfig {
+//!     pid: PidConfig {
+//!         k_p: 4.0,
+//!         k_i: 0.2,
+//!         default_target: 21.5,
+//!         min: Some(0.0),
+//!         max: Some(100.0),
+//!         ..Default::default()
+//!     },
+//!     travel_time: Duration::from_secs(90),
+//!     dead_zone: 2.0,
+//!     min_pulse: Duration::from_millis(500),
+//! };
+//! let mut c = ThreePoint::new(cfg);
+//!
+//! let delta_t = Duration::from_millis(100);
+//! loop {
+//!     let room_temperature = 19.8;
+//!     let motion = c.next(room_temperature, &delta_t);
+//!     // Here you'd use s.th. like `write_actuator("open", motion.is_opening())`
+//!     // and `write_actuator("close", motion.is_closing())`.
+//!     thread::sleep(delta_t);
+//! }
+//! ```
+
+use super::{pid::*, Controller, PureController};
+use std::time::Duration;
+
+/// Three-point step controller for motorized actuators
+///
+/// Valve or damper drives without an analog input are driven by
+/// two digital outputs (open/close). The embedded PID controller
+/// calculates the demanded position in percent. The actual position
+/// is estimated by integrating the motion of the drive over its
+/// full travel time. The drive is moved as long as the demanded and
+/// the estimated position differ by more than the dead zone.
+#[derive(Debug, Clone)]
+pub struct ThreePoint {
+    cfg: ThreePointConfig,
+    /// Current controller state
+    pub state: ThreePointState,
+}
+
+/// Three-point step controller configuration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct ThreePointConfig {
+    /// Calculates the demanded position (`0.0 ..= 100.0`)
+    pub pid: PidConfig,
+    /// Time to move the drive from fully closed to fully open
+    pub travel_time: Duration,
+    /// Tolerated position deviation in percent
+    pub dead_zone: f64,
+    /// Minimum duration of a single pulse
+    pub min_pulse: Duration,
+}
+
+impl Default for ThreePointConfig {
+    fn default() -> Self {
+        ThreePointConfig {
+            pid: PidConfig {
+                min: Some(0.0),
+                max: Some(100.0),
+                ..Default::default()
+            },
+            travel_time: Duration::from_secs(60),
+            dead_zone: 1.0,
+            min_pulse: Duration::from_secs(0),
+        }
+    }
+}
+
+/// Motion of the drive
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Motion {
+    /// Move towards the open position
+    Open,
+    /// Do not move
+    #[default]
+    Stop,
+    /// Move towards the closed position
+    Close,
+}
+
+impl Motion {
+    /// The "open" output is active.
+    pub fn is_opening(self) -> bool {
+        self == Motion::Open
+    }
+    /// The "close" output is active.
+    pub fn is_closing(self) -> bool {
+        self == Motion::Close
+    }
+}
+
+/// Internal three-point step controller state
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ThreePointState {
+    /// State of the embedded PID controller
+    pub pid: PidState,
+    /// Demanded position in percent
+    pub demand: f64,
+    /// Estimated position in percent
+    pub position: f64,
+    /// Current motion of the drive
+    pub motion: Motion,
+    /// Seconds since the current motion has been started
+    pub pulse_elapsed: f64,
+}
+
+impl ThreePoint {
+    /// Create a new controller instance.
+    pub fn new(cfg: ThreePointConfig) -> Self {
+        let state = ThreePointState {
+            pid: PidState {
+                target: cfg.pid.default_target,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        ThreePoint { cfg, state }
+    }
+    /// Set target value.
+    pub fn set_target(&mut self, target: f64) {
+        self.state.pid.target = target;
+    }
+    /// Set the estimated position, e.g. after the drive has been moved
+    /// into an end position for synchronization.
+    pub fn set_position(&mut self, position: f64) {
+        self.state.position = position.clamp(0.0, 100.0);
+    }
+}
+
+impl Controller<(f64, &Duration), Motion> for ThreePoint {
+    fn next(&mut self, input: (f64, &Duration)) -> Motion {
+        let (actual, duration) = input;
+        self.state = self.cfg.next((self.state, actual, duration));
+        self.state.motion
+    }
+}
+
+impl PureController<(ThreePointState, f64, &Duration), ThreePointState> for ThreePointConfig {
+    fn next(&self, input: (ThreePointState, f64, &Duration)) -> ThreePointState {
+        let (mut state, actual, duration) = input;
+        let delta_t = duration.as_secs_f64();
+
+        // The previous motion has been active during the elapsed time
+        let travel_time = self.travel_time.as_secs_f64();
+        let movement = if travel_time > 0.0 {
+            100.0 * delta_t / travel_time
+        } else {
+            100.0
+        };
+        match state.motion {
+            Motion::Open => {
+                state.position = (state.position + movement).min(100.0);
+                state.pulse_elapsed += delta_t;
+            }
+            Motion::Close => {
+                state.position = (state.position - movement).max(0.0);
+                state.pulse_elapsed += delta_t;
+            }
+            Motion::Stop => {
+                state.pulse_elapsed = 0.0;
+            }
+        }
+
+        let (pid, demand) = self.pid.next((state.pid, actual, duration));
+        state.pid = pid;
+        state.demand = demand;
+
+        let pulse_pending =
+            state.motion != Motion::Stop && state.pulse_elapsed < self.min_pulse.as_secs_f64();
+        if pulse_pending {
+            return state;
+        }
+
+        let deviation = state.demand - state.position;
+        let motion = if deviation > self.dead_zone {
+            Motion::Open
+        } else if deviation < -self.dead_zone {
+            Motion::Close
+        } else {
+            Motion::Stop
+        };
+        if motion != state.motion {
+            state.pulse_elapsed = 0.0;
+        }
+        state.motion = motion;
+        state
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    fn cfg() -> ThreePointConfig {
+        ThreePointConfig {
+            pid: PidConfig {
+                k_p: 10.0,
+                default_target: 5.0,
+                min: Some(0.0),
+                max: Some(100.0),
+                ..Default::default()
+            },
+            travel_time: Duration::from_secs(10),
+            dead_zone: 5.0,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn move_towards_demanded_position() {
+        let mut c = ThreePoint::new(cfg());
+        let dt = Duration::from_secs(1);
+        // demand: 10 * (5 - 2) = 30 %
+        assert_eq!(c.next((2.0, &dt)), Motion::Open);
+        assert_eq!(c.state.demand, 30.0);
+        assert_eq!(c.next((2.0, &dt)), Motion::Open);
+        assert_eq!(c.state.position, 10.0);
+        assert_eq!(c.next((2.0, &dt)), Motion::Open);
+        // 30 % - 30 % is within the dead zone
+        assert_eq!(c.next((2.0, &dt)), Motion::Stop);
+        assert_eq!(c.state.position, 30.0);
+        assert_eq!(c.next((2.0, &dt)), Motion::Stop);
+        // demand: 10 * (5 - 4) = 10 %
+        assert_eq!(c.next((4.0, &dt)), Motion::Close);
+        assert_eq!(c.next((4.0, &dt)), Motion::Close);
+        assert_eq!(c.next((4.0, &dt)), Motion::Stop);
+        assert_eq!(c.state.position, 10.0);
+    }
+
+    #[test]
+    fn keep_moving_for_minimum_pulse_time() {
+        let mut c = ThreePoint::new(ThreePointConfig {
+            dead_zone: 20.0,
+            min_pulse: Duration::from_secs(3),
+            ..cfg()
+        });
+        let dt = Duration::from_secs(1);
+        // demand: 10 * (5 - 2.5) = 25 %
+        assert_eq!(c.next((2.5, &dt)), Motion::Open);
+        // 25 % - 10 % would be within the dead zone
+        assert_eq!(c.next((2.5, &dt)), Motion::Open);
+        assert_eq!(c.next((2.5, &dt)), Motion::Open);
+        assert_eq!(c.next((2.5, &dt)), Motion::Stop);
+        assert_eq!(c.state.position, 30.0);
+    }
+
+    #[test]
+    fn limit_estimated_position() {
+        let mut c = ThreePoint::new(cfg());
+        let dt = Duration::from_secs(4);
+        assert_eq!(c.next((-100.0, &dt)), Motion::Open);
+        for _ in 0..5 {
+            c.next((-100.0, &dt));
+        }
+        assert_eq!(c.state.position, 100.0);
+        assert_eq!(c.state.motion, Motion::Stop);
+        c.set_position(-3.0);
+        assert_eq!(c.state.position, 0.0);
+    }
+
+    #[test]
+    fn motion_outputs() {
+        assert!(Motion::Open.is_opening());
+        assert!(!Motion::Open.is_closing());
+        assert!(Motion::Close.is_closing());
+        assert!(!Motion::Stop.is_opening());
+        assert!(!Motion::Stop.is_closing());
+    }
+}
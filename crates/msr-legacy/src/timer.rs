@@ -0,0 +1,215 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, timer::*};
+//!
+//! // Switch the fan on after the temperature is high for 2 seconds
+//! let mut ton = Timer::new(TimerConfig {
+//!     kind: TimerKind::OnDelay,
+//!     preset: Duration::from_secs(2),
+//! });
+//! let delta_t = Duration::from_secs(1);
+//!
+//! assert!(!ton.next(true, &delta_t));
+//! assert!(!ton.next(true, &delta_t));
+//! assert!(ton.next(true, &delta_t));
+//! assert_eq!(ton.state.elapsed, Duration::from_secs(2));
+//! assert!(!ton.next(false, &delta_t));
+//! ```
+
+use super::{Controller, PureController};
+use std::time::Duration;
+
+/// A timer as defined by IEC 61131-3
+///
+/// The timer measures the time since its input (`IN`) changed
+/// and switches its output (`Q`) after the preset time (`PT`).
+/// The elapsed time (`ET`) never exceeds the preset time.
+#[derive(Debug, Clone)]
+pub struct Timer {
+    cfg: TimerConfig,
+    /// Current state
+    pub state: TimerState,
+}
+
+/// Timer configuration
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TimerConfig {
+    /// The timer behaviour
+    pub kind: TimerKind,
+    /// The preset time
+    pub preset: Duration,
+}
+
+/// The behaviour of a timer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TimerKind {
+    /// `TON`: the output is on after the input is on for the preset time
+    #[default]
+    OnDelay,
+    /// `TOF`: the output stays on for the preset time after the input is off
+    OffDelay,
+    /// `TP`: a rising input switches the output on for exactly the preset time
+    Pulse,
+}
+
+/// Internal timer state
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TimerState {
+    /// The input (e.g. set by start and stop actions)
+    pub input: bool,
+    /// The input of the previous step
+    pub prev_input: bool,
+    /// The elapsed time
+    pub elapsed: Duration,
+    /// The output
+    pub output: bool,
+}
+
+impl Timer {
+    /// Create a new timer instance.
+    pub fn new(cfg: TimerConfig) -> Self {
+        Timer {
+            cfg,
+            state: TimerState::default(),
+        }
+    }
+    /// Reset the internal state.
+    pub fn reset(&mut self) {
+        self.state = TimerState::default();
+    }
+}
+
+impl Controller<(bool, &Duration), bool> for Timer {
+    fn next(&mut self, input: (bool, &Duration)) -> bool {
+        let (on, duration) = input;
+        self.state = self.cfg.next((self.state, on, duration));
+        self.state.output
+    }
+}
+
+impl PureController<(TimerState, bool, &Duration), TimerState> for TimerConfig {
+    fn next(&self, input: (TimerState, bool, &Duration)) -> TimerState {
+        let (mut state, on, duration) = input;
+        let rising = on && !state.prev_input;
+        let falling = !on && state.prev_input;
+        let count = |elapsed: Duration| (elapsed + *duration).min(self.preset);
+        match self.kind {
+            TimerKind::OnDelay => {
+                state.elapsed = match on {
+                    true if rising => Duration::ZERO,
+                    true => count(state.elapsed),
+                    false => Duration::ZERO,
+                };
+                state.output = on && state.elapsed >= self.preset;
+            }
+            TimerKind::OffDelay => {
+                if on {
+                    state.elapsed = Duration::ZERO;
+                    state.output = true;
+                } else if falling {
+                    state.elapsed = Duration::ZERO;
+                    state.output = self.preset > Duration::ZERO;
+                } else if state.output {
+                    state.elapsed = count(state.elapsed);
+                    state.output = state.elapsed < self.preset;
+                }
+            }
+            TimerKind::Pulse => {
+                if state.output {
+                    state.elapsed = count(state.elapsed);
+                    state.output = state.elapsed < self.preset;
+                } else if rising {
+                    state.elapsed = Duration::ZERO;
+                    state.output = self.preset > Duration::ZERO;
+                } else if !on {
+                    state.elapsed = Duration::ZERO;
+                }
+            }
+        }
+        state.input = on;
+        state.prev_input = on;
+        state
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn timer(kind: TimerKind) -> Timer {
+        Timer::new(TimerConfig {
+            kind,
+            preset: Duration::from_secs(2),
+        })
+    }
+
+    fn run(t: &mut Timer, inputs: &[bool]) -> Vec<bool> {
+        let dt = Duration::from_secs(1);
+        inputs.iter().map(|on| t.next((*on, &dt))).collect()
+    }
+
+    #[test]
+    fn on_delay() {
+        let mut t = timer(TimerKind::OnDelay);
+        assert_eq!(
+            run(&mut t, &[true, true, false, true, true, true, true]),
+            [false, false, false, false, false, true, true]
+        );
+        assert_eq!(t.state.elapsed, Duration::from_secs(2));
+        run(&mut t, &[false]);
+        assert_eq!(t.state.elapsed, Duration::ZERO);
+    }
+
+    #[test]
+    fn off_delay() {
+        let mut t = timer(TimerKind::OffDelay);
+        assert_eq!(
+            run(
+                &mut t,
+                &[false, true, false, true, false, false, false, false]
+            ),
+            [false, true, true, true, true, true, false, false]
+        );
+        assert_eq!(t.state.elapsed, Duration::from_secs(2));
+    }
+
+    #[test]
+    fn pulse() {
+        let mut t = timer(TimerKind::Pulse);
+        // The pulse can't be retriggered or shortened
+        assert_eq!(
+            run(&mut t, &[true, false, true, true, true, false, true]),
+            [true, true, false, false, false, false, true]
+        );
+        let mut t = timer(TimerKind::Pulse);
+        run(&mut t, &[true, true, true]);
+        assert_eq!(t.state.elapsed, Duration::from_secs(2));
+        run(&mut t, &[false]);
+        assert_eq!(t.state.elapsed, Duration::ZERO);
+    }
+
+    #[test]
+    fn zero_preset() {
+        for kind in [TimerKind::OnDelay, TimerKind::OffDelay, TimerKind::Pulse] {
+            let mut t = Timer::new(TimerConfig {
+                kind,
+                preset: Duration::ZERO,
+            });
+            let expected = kind != TimerKind::Pulse;
+            assert_eq!(run(&mut t, &[true]), [expected]);
+            assert_eq!(run(&mut t, &[false]), [false]);
+        }
+        let mut t = timer(TimerKind::OnDelay);
+        run(&mut t, &[true, true]);
+        t.reset();
+        assert_eq!(t.state, TimerState::default());
+    }
+}
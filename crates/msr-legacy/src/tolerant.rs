@@ -0,0 +1,199 @@
+//! Tolerant evaluation of conditions
+//!
+//! A condition fails to evaluate as long as one of its sources
+//! is missing, e.g. during startup before all inputs have been
+//! read. The tolerant evaluation substitutes missing sources by
+//! default values or forces the condition to `false` and reports
+//! the missing sources instead.
+//!
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{tolerant::*, BoolExpr, Comparison, Source, SystemState};
+//!
+//! let expr: BoolExpr<Comparison> = "in.temp > 80.0 || in.alarm".parse().unwrap();
+//! let state = SystemState::default();
+//!
+//! // Missing sources without defaults force the condition to false
+//! let res = expr.eval_tolerant(&state, &TolerantEval::default()).unwrap();
+//! assert!(!res.value);
+//! assert_eq!(res.missing.len(), 2);
+//!
+//! let mut cfg = TolerantEval::default();
+//! cfg.defaults.insert("in.temp".into(), 20.0.into());
+//! cfg.defaults.insert("in.alarm".into(), true.into());
+//! let res = expr.eval_tolerant(&state, &cfg).unwrap();
+//! assert!(res.value);
+//! assert_eq!(
+//!     res.missing[0],
+//!     MissingSource {
+//!         source: Source::In("temp".into()),
+//!         defaulted: true,
+//!     }
+//! );
+//! ```
+
+use super::{BoolExpr, Comparison, Evaluation, Source, Sources, SystemState, Value};
+use std::{collections::HashMap, io::Result};
+
+/// Tolerant evaluation configuration
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TolerantEval {
+    /// Values of missing sources by their textual
+    /// representation, e.g. `in.temp` or `setpoint.t`
+    pub defaults: HashMap<String, Value>,
+}
+
+/// A source that was missing during an evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub struct MissingSource {
+    pub source: Source,
+    /// The source has been substituted by its default value
+    pub defaulted: bool,
+}
+
+/// The result of a tolerant evaluation
+#[derive(Debug, Clone, PartialEq)]
+pub struct TolerantResult {
+    pub value: bool,
+    /// All missing sources (without duplicates)
+    pub missing: Vec<MissingSource>,
+}
+
+impl BoolExpr<Comparison> {
+    /// Evaluate the expression even if sources are missing.
+    ///
+    /// Missing sources are replaced by their default values. If a
+    /// missing source has no default value, the whole expression
+    /// evaluates to `false`. Other errors, like comparisons of
+    /// incompatible values, are still returned.
+    pub fn eval_tolerant(&self, state: &SystemState, cfg: &TolerantEval) -> Result<TolerantResult> {
+        let mut missing: Vec<MissingSource> = vec![];
+        for source in leaf_sources(self) {
+            if state.get(&source).is_some() || missing.iter().any(|m| m.source == source) {
+                continue;
+            }
+            let defaulted = cfg.defaults.contains_key(&source.to_string());
+            missing.push(MissingSource { source, defaulted });
+        }
+        if missing.is_empty() {
+            let value = self.eval(state)?;
+            return Ok(TolerantResult { value, missing });
+        }
+        if missing.iter().any(|m| !m.defaulted) {
+            return Ok(TolerantResult {
+                value: false,
+                missing,
+            });
+        }
+        // Only cloned while sources are missing, i.e. usually during startup
+        let mut state = state.clone();
+        for m in &missing {
+            let value = cfg.defaults[&m.source.to_string()].clone();
+            insert(&mut state, &m.source, value);
+        }
+        let value = self.eval(&state)?;
+        Ok(TolerantResult { value, missing })
+    }
+}
+
+/// All sources that are not calculated
+fn leaf_sources(expr: &BoolExpr<Comparison>) -> Vec<Source> {
+    expr.sources()
+        .into_iter()
+        .flat_map(|src| match src {
+            Source::Expr(e) => e.sources(),
+            src => vec![src],
+        })
+        .collect()
+}
+
+fn insert(state: &mut SystemState, src: &Source, value: Value) {
+    use crate::Source::*;
+    match src {
+        In(id) => state.io.inputs.set(id, value),
+        Out(id) => state.io.outputs.set(id, value),
+        Mem(id) => state.io.mem.set(id, value),
+        Setpoint(id) => state.setpoints.insert(id.clone(), value),
+        Timeout(id) => state.timeouts.insert(id.clone(), value),
+        // Never missing
+        Const(_) | Expr(_) => None,
+    };
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn parse(s: &str) -> BoolExpr<Comparison> {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn evaluate_complete_state() {
+        let mut state = SystemState::default();
+        state.io.inputs.insert("x".into(), 5.into());
+        let res = parse("in.x > 3")
+            .eval_tolerant(&state, &TolerantEval::default())
+            .unwrap();
+        assert!(res.value);
+        assert!(res.missing.is_empty());
+    }
+
+    #[test]
+    fn force_false_without_defaults() {
+        let mut state = SystemState::default();
+        state.io.inputs.insert("x".into(), 5.into());
+        let mut cfg = TolerantEval::default();
+        cfg.defaults.insert("mem.y".into(), 1.into());
+        // Would be true for any value of the missing output
+        let res = parse("!(out.z < in.x + mem.y) || in.x == 5 || out.z > 0")
+            .eval_tolerant(&state, &cfg)
+            .unwrap();
+        assert!(!res.value);
+        assert_eq!(
+            res.missing,
+            vec![
+                MissingSource {
+                    source: Source::Out("z".into()),
+                    defaulted: false,
+                },
+                MissingSource {
+                    source: Source::Mem("y".into()),
+                    defaulted: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn substitute_defaults() {
+        let mut state = SystemState::default();
+        state.setpoints.insert("t".into(), 20.0.into());
+        let mut cfg = TolerantEval::default();
+        cfg.defaults.insert("in.t".into(), 25.0.into());
+        cfg.defaults.insert("timeout.warmup".into(), true.into());
+        cfg.defaults.insert("setpoint.t".into(), 100.0.into());
+        let expr = parse("in.t - setpoint.t > 2.0 && timeout.warmup");
+        let res = expr.eval_tolerant(&state, &cfg).unwrap();
+        assert!(res.value);
+        assert_eq!(res.missing.len(), 2);
+        assert!(res.missing.iter().all(|m| m.defaulted));
+        // The state itself is not modified
+        assert!(state.io.inputs.is_empty());
+        assert!(expr.eval(&state).is_err());
+    }
+
+    #[test]
+    fn report_other_errors() {
+        let mut cfg = TolerantEval::default();
+        cfg.defaults
+            .insert("in.x".into(), "text".to_string().into());
+        assert!(parse("in.x > 3")
+            .eval_tolerant(&SystemState::default(), &cfg)
+            .is_err());
+    }
+}
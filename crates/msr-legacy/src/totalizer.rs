@@ -0,0 +1,184 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, totalizer::*};
+//!
+//! // Flow in m³/h → volume in m³
+//! let cfg = TotalizerConfig {
+//!     time_base: Duration::from_secs(3600),
+//!     ..Default::default()
+//! };
+//! let mut t = Totalizer::new(cfg.clone());
+//! let delta_t = Duration::from_secs(900);
+//!
+//! assert_eq!(t.next(8.0, &delta_t), 0.0);
+//! assert_eq!(t.next(8.0, &delta_t), 2.0);
+//!
+//! // Continue with the accumulated value after a restart
+//! let saved = t.state;
+//! let mut t = Totalizer::with_state(cfg, saved);
+//! assert_eq!(t.next(8.0, &delta_t), 4.0);
+//! ```
+
+use super::{Controller, PureController};
+use std::time::Duration;
+
+/// Totalizer (integrator)
+///
+/// Integrates a rate signal over time with the trapezoidal rule,
+/// e.g. flow to volume or power to energy.
+#[derive(Debug, Clone)]
+pub struct Totalizer {
+    cfg: TotalizerConfig,
+    /// Current state
+    pub state: TotalizerState,
+}
+
+/// Totalizer configuration
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TotalizerConfig {
+    /// The time unit of the rate (e.g. one hour for m³/h)
+    pub time_base: Duration,
+    /// Conversion factor from the integrated unit to the unit of the total
+    pub scale: f64,
+    /// The total starts again at zero when it reaches this value
+    pub rollover: Option<f64>,
+}
+
+impl Default for TotalizerConfig {
+    fn default() -> Self {
+        TotalizerConfig {
+            time_base: Duration::from_secs(1),
+            scale: 1.0,
+            rollover: None,
+        }
+    }
+}
+
+/// Internal totalizer state
+///
+/// The state can be stored to keep the total across restarts.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TotalizerState {
+    /// The accumulated value
+    pub total: f64,
+    /// Number of rollovers (negative if the total dropped below zero)
+    pub rollovers: i64,
+    /// The rate of the previous step
+    pub prev_rate: Option<f64>,
+}
+
+impl Totalizer {
+    /// Create a new totalizer instance.
+    pub fn new(cfg: TotalizerConfig) -> Self {
+        Self::with_state(cfg, TotalizerState::default())
+    }
+    /// Create a totalizer that continues with a stored state.
+    pub fn with_state(cfg: TotalizerConfig, state: TotalizerState) -> Self {
+        Totalizer { cfg, state }
+    }
+    /// Reset the accumulated value.
+    pub fn reset(&mut self) {
+        self.preset(0.0);
+    }
+    /// Set the accumulated value.
+    pub fn preset(&mut self, total: f64) {
+        self.state = TotalizerState {
+            total,
+            ..Default::default()
+        };
+    }
+}
+
+impl Controller<(f64, &Duration), f64> for Totalizer {
+    fn next(&mut self, input: (f64, &Duration)) -> f64 {
+        let (rate, duration) = input;
+        self.state = self.cfg.next((self.state, rate, duration));
+        self.state.total
+    }
+}
+
+impl PureController<(TotalizerState, f64, &Duration), TotalizerState> for TotalizerConfig {
+    fn next(&self, input: (TotalizerState, f64, &Duration)) -> TotalizerState {
+        let (mut state, rate, duration) = input;
+        let time_base = self.time_base.as_secs_f64();
+        if let Some(prev_rate) = state.prev_rate {
+            if time_base > 0.0 {
+                let delta_t = duration.as_secs_f64() / time_base;
+                state.total += self.scale * (prev_rate + rate) / 2.0 * delta_t;
+            }
+        }
+        state.prev_rate = Some(rate);
+        if let Some(rollover) = self.rollover.filter(|r| *r > 0.0) {
+            let wraps = (state.total / rollover).floor();
+            if wraps != 0.0 {
+                state.total -= wraps * rollover;
+                state.rollovers += wraps as i64;
+            }
+        }
+        state
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn integrate_rate() {
+        let mut t = Totalizer::new(TotalizerConfig::default());
+        let dt = Duration::from_secs(2);
+        assert_eq!(t.next((1.0, &dt)), 0.0);
+        assert_eq!(t.next((3.0, &dt)), 4.0);
+        assert_eq!(t.next((3.0, &dt)), 10.0);
+        assert_eq!(t.next((-1.0, &dt)), 12.0);
+        t.reset();
+        assert_eq!(t.state, TotalizerState::default());
+        assert_eq!(t.next((5.0, &dt)), 0.0);
+        t.preset(100.0);
+        assert_eq!(t.next((5.0, &dt)), 100.0);
+        assert_eq!(t.next((5.0, &dt)), 110.0);
+    }
+
+    #[test]
+    fn scale_units() {
+        // Power in W → energy in kWh
+        let cfg = TotalizerConfig {
+            time_base: Duration::from_secs(3600),
+            scale: 0.001,
+            ..Default::default()
+        };
+        let mut t = Totalizer::new(cfg);
+        let dt = Duration::from_secs(900);
+        t.next((2000.0, &dt));
+        for _ in 0..4 {
+            t.next((2000.0, &dt));
+        }
+        assert_eq!(t.state.total, 2.0);
+    }
+
+    #[test]
+    fn roll_over() {
+        let cfg = TotalizerConfig {
+            rollover: Some(10.0),
+            ..Default::default()
+        };
+        let mut t = Totalizer::new(cfg);
+        let dt = Duration::from_secs(1);
+        t.next((4.0, &dt));
+        assert_eq!(t.next((4.0, &dt)), 4.0);
+        assert_eq!(t.next((4.0, &dt)), 8.0);
+        assert_eq!(t.next((4.0, &dt)), 2.0);
+        assert_eq!(t.state.rollovers, 1);
+        assert_eq!(t.next((25.0, &dt)), 6.5);
+        assert_eq!(t.state.rollovers, 2);
+        assert_eq!(t.next((-40.0, &dt)), 9.0);
+        assert_eq!(t.state.rollovers, 1);
+    }
+}
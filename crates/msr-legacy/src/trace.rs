@@ -0,0 +1,310 @@
+//! Tracing of rule evaluations
+//!
+//! A trace explains why a rule fired (or didn't) by recording
+//! the values and results of all comparisons of its condition.
+//! Traces can be printed, e.g. as the text of an event journal
+//! entry, or serialized with the `serde` feature.
+//!
+//! # Example
+//!
+//! ```rust
+//! use msr_legacy::{trace::*, Rule, SyncRuntime, SystemState, Trigger};
+//!
+//! let rt = SyncRuntime {
+//!     rules: vec![Rule {
+//!         id: "overheated".into(),
+//!         condition: "in.temp > 80.0 && !out.fan".parse().unwrap(),
+//!         actions: vec!["alarm".into()],
+//!         trigger: Trigger::Rising,
+//!     }],
+//!     ..Default::default()
+//! };
+//! let mut state = SystemState::default();
+//! state.io.inputs.insert("temp".into(), 85.0.into());
+//! state.io.outputs.insert("fan".into(), false.into());
+//!
+//! let trace = rt.trace_rule("overheated", &state).unwrap();
+//! assert!(trace.triggered);
+//! assert_eq!(
+//!     trace.to_string(),
+//!     "overheated: rising(false -> true) => triggered\n\
+//!      \x20 in.temp > 80.0 && !out.fan = true\n\
+//!      \x20   in.temp > 80.0: 85.0 > 80.0 = true\n\
+//!      \x20   out.fan == true: false == true = false"
+//! );
+//! ```
+
+use super::{BoolExpr, Comparison, Evaluation, Rule, SyncRuntime, SystemState, Trigger, Value};
+use std::fmt;
+
+/// The evaluation of a single comparison
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ComparisonTrace {
+    /// The comparison as text
+    pub comparison: String,
+    /// The value of the left side (if available)
+    pub left: Option<Value>,
+    /// The value of the right side (if available)
+    pub right: Option<Value>,
+    /// The result or the error message
+    pub result: Result<bool, String>,
+}
+
+/// The evaluation of a condition
+///
+/// All comparisons are recorded, even those that are skipped
+/// when evaluating the condition, e.g. `b` in `false && b`.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ConditionTrace {
+    /// The condition as text
+    pub condition: String,
+    /// The comparisons in order of their occurrence
+    pub comparisons: Vec<ComparisonTrace>,
+    /// The result or the error message
+    pub result: Result<bool, String>,
+}
+
+/// The evaluation of a rule
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RuleTrace {
+    /// The ID of the rule
+    pub id: String,
+    pub trigger: Trigger,
+    /// The result of the condition in the previous cycle
+    pub previous: bool,
+    pub condition: ConditionTrace,
+    /// The actions of the rule are applied
+    pub triggered: bool,
+}
+
+impl BoolExpr<Comparison> {
+    /// Evaluate the expression and record all comparisons.
+    pub fn trace(&self, state: &SystemState) -> ConditionTrace {
+        let mut comparisons = vec![];
+        collect_comparisons(self, state, &mut comparisons);
+        ConditionTrace {
+            condition: self.to_string(),
+            comparisons,
+            result: self.eval(state).map_err(|err| err.to_string()),
+        }
+    }
+}
+
+fn collect_comparisons(
+    expr: &BoolExpr<Comparison>,
+    state: &SystemState,
+    comparisons: &mut Vec<ComparisonTrace>,
+) {
+    use crate::BoolExpr::*;
+    match expr {
+        True | False => {}
+        Not(x) => collect_comparisons(x, state, comparisons),
+        And(a, b) | Or(a, b) => {
+            collect_comparisons(a, state, comparisons);
+            collect_comparisons(b, state, comparisons);
+        }
+        Eval(c) => comparisons.push(ComparisonTrace {
+            comparison: c.to_string(),
+            left: state.eval_source(&c.left).map(|v| v.into_owned()),
+            right: state.eval_source(&c.right).map(|v| v.into_owned()),
+            result: c.eval(state).map_err(|err| err.to_string()),
+        }),
+    }
+}
+
+impl Rule {
+    /// Evaluate the rule and record all comparisons.
+    ///
+    /// The result of the previous cycle is taken from `state.rules`.
+    pub fn trace(&self, state: &SystemState) -> RuleTrace {
+        let previous = state.rules.get(&self.id).copied().unwrap_or(false);
+        let condition = self.condition.trace(state);
+        let triggered = condition
+            .result
+            .as_ref()
+            .map(|current| self.trigger.is_triggered(previous, *current))
+            .unwrap_or(false);
+        RuleTrace {
+            id: self.id.clone(),
+            trigger: self.trigger,
+            previous,
+            condition,
+            triggered,
+        }
+    }
+}
+
+impl SyncRuntime {
+    /// Trace the evaluation of a rule (see [Rule::trace]).
+    pub fn trace_rule(&self, id: &str, state: &SystemState) -> Option<RuleTrace> {
+        self.rules
+            .iter()
+            .find(|r| r.id == id)
+            .map(|r| r.trace(state))
+    }
+
+    /// Trace the evaluation of all rules (see [Rule::trace]).
+    pub fn trace_rules(&self, state: &SystemState) -> Vec<RuleTrace> {
+        self.rules.iter().map(|r| r.trace(state)).collect()
+    }
+}
+
+fn fmt_result(f: &mut fmt::Formatter<'_>, result: &Result<bool, String>) -> fmt::Result {
+    match result {
+        Ok(res) => write!(f, "{res}"),
+        Err(err) => write!(f, "error ({err})"),
+    }
+}
+
+fn fmt_value(f: &mut fmt::Formatter<'_>, value: Option<&Value>) -> fmt::Result {
+    match value {
+        Some(Value::Decimal(d)) => write!(f, "{d:?}"),
+        Some(Value::Integer(i)) => write!(f, "{i}"),
+        Some(Value::Bit(b)) => write!(f, "{b}"),
+        Some(Value::Text(t)) => write!(f, "'{t}'"),
+        Some(v) => write!(f, "{v:?}"),
+        None => f.write_str("missing"),
+    }
+}
+
+/// `comparison: left cmp right = result`
+impl fmt::Display for ComparisonTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // The comparator is the second token of the comparison
+        let cmp = self
+            .comparison
+            .parse::<Comparison>()
+            .map(|c| c.cmp.to_string())
+            .unwrap_or_default();
+        write!(f, "{}: ", self.comparison)?;
+        fmt_value(f, self.left.as_ref())?;
+        write!(f, " {cmp} ")?;
+        fmt_value(f, self.right.as_ref())?;
+        f.write_str(" = ")?;
+        fmt_result(f, &self.result)
+    }
+}
+
+/// The condition with its result, followed by one line per comparison
+impl fmt::Display for ConditionTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = ", self.condition)?;
+        fmt_result(f, &self.result)?;
+        for c in &self.comparisons {
+            write!(f, "\n  {c}")?;
+        }
+        Ok(())
+    }
+}
+
+/// The rule with its trigger state, followed by the condition trace
+impl fmt::Display for RuleTrace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}({} -> ", self.id, self.trigger, self.previous)?;
+        fmt_result(f, &self.condition.result)?;
+        let triggered = if self.triggered {
+            "triggered"
+        } else {
+            "not triggered"
+        };
+        write!(f, ") => {triggered}")?;
+        for line in self.condition.to_string().lines() {
+            write!(f, "\n  {line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn rule(condition: &str, trigger: Trigger) -> Rule {
+        Rule {
+            id: "r".into(),
+            condition: condition.parse().unwrap(),
+            actions: vec![],
+            trigger,
+        }
+    }
+
+    #[test]
+    fn trace_all_comparisons() {
+        let mut state = SystemState::default();
+        state.io.inputs.insert("x".into(), 1.into());
+        let trace = rule("in.x > 5 && in.y == 'on' || !mem.z", Trigger::Level).trace(&state);
+        assert_eq!(
+            trace.condition.result,
+            Err("The state of memory 'z' does not exist".into())
+        );
+        assert!(!trace.triggered);
+        assert_eq!(
+            trace.condition.comparisons,
+            vec![
+                ComparisonTrace {
+                    comparison: "in.x > 5".into(),
+                    left: Some(1.into()),
+                    right: Some(5.into()),
+                    result: Ok(false),
+                },
+                ComparisonTrace {
+                    comparison: "in.y == 'on'".into(),
+                    left: None,
+                    right: Some("on".to_string().into()),
+                    result: Err("The state of input 'y' does not exist".into()),
+                },
+                ComparisonTrace {
+                    comparison: "mem.z == true".into(),
+                    left: None,
+                    right: Some(true.into()),
+                    result: Err("The state of memory 'z' does not exist".into()),
+                },
+            ]
+        );
+        assert_eq!(
+            trace.to_string(),
+            "r: level(false -> error (The state of memory 'z' does not exist)) => not triggered\n\
+             \x20 in.x > 5 && in.y == 'on' || !mem.z = error (The state of memory 'z' does not exist)\n\
+             \x20   in.x > 5: 1 > 5 = false\n\
+             \x20   in.y == 'on': missing == 'on' = error (The state of input 'y' does not exist)\n\
+             \x20   mem.z == true: missing == true = error (The state of memory 'z' does not exist)"
+        );
+    }
+
+    #[test]
+    fn trace_triggers() {
+        let mut state = SystemState::default();
+        state.io.inputs.insert("x".into(), 1.into());
+        let r = rule("in.x == 1", Trigger::Rising);
+        assert!(r.trace(&state).triggered);
+        state.rules.insert("r".into(), true);
+        let trace = r.trace(&state);
+        assert!(trace.previous);
+        assert!(!trace.triggered);
+        assert_eq!(trace.condition.result, Ok(true));
+    }
+
+    #[test]
+    fn trace_runtime_rules() {
+        let rt = SyncRuntime {
+            rules: vec![rule("true", Trigger::Level)],
+            ..Default::default()
+        };
+        let state = SystemState::default();
+        assert_eq!(rt.trace_rules(&state).len(), 1);
+        assert!(rt.trace_rule("r", &state).unwrap().triggered);
+        assert!(rt.trace_rule("unknown", &state).is_none());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_trace() {
+        let trace = rule("in.x > 5", Trigger::Level).trace(&SystemState::default());
+        let json = serde_json::to_string(&trace).unwrap();
+        assert_eq!(serde_json::from_str::<RuleTrace>(&json).unwrap(), trace);
+    }
+}
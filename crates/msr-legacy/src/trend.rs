@@ -0,0 +1,211 @@
+//! # Example
+//!
+//! ```rust
+//! use std::time::Duration;
+//! use msr_legacy::{TimeStepController, trend::*};
+//!
+//! // Detect a level drop of more than 0.1 % per minute
+//! let mut t = TrendDetector::new(TrendConfig {
+//!     window: Duration::from_secs(300),
+//!     rising: 0.1 / 60.0,
+//!     falling: 0.1 / 60.0,
+//! });
+//! let delta_t = Duration::from_secs(60);
+//!
+//! let mut result = (0.0, Trend::Steady);
+//! for level in [80.0, 79.8, 79.6, 79.4] {
+//!     result = t.next(level, &delta_t);
+//! }
+//! assert!((result.0 * 60.0 + 0.2).abs() < 1e-9);
+//! assert_eq!(result.1, Trend::Falling);
+//! ```
+
+use super::{Controller, PureController};
+use std::{collections::VecDeque, time::Duration};
+
+/// Trend detection
+///
+/// Estimates the slope of a signal by a least squares fit over
+/// the values of a time window and classifies the trend.
+#[derive(Debug, Clone)]
+pub struct TrendDetector {
+    cfg: TrendConfig,
+    /// Current state
+    pub state: TrendState,
+}
+
+/// Trend detection configuration
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct TrendConfig {
+    /// Time window of the fitted values
+    pub window: Duration,
+    /// Minimum slope (per second) of a rising signal
+    pub rising: f64,
+    /// Minimum negative slope (per second, a positive value) of a falling signal
+    pub falling: f64,
+}
+
+/// Classification of a trend
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Trend {
+    /// The signal increases
+    Rising,
+    /// The signal decreases
+    Falling,
+    /// The signal neither rises nor falls
+    #[default]
+    Steady,
+}
+
+/// Internal trend detection state
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TrendState {
+    /// Time since the start
+    pub time: Duration,
+    /// The values within the window
+    pub samples: VecDeque<(Duration, f64)>,
+}
+
+impl TrendDetector {
+    /// Create a new trend detection instance.
+    pub fn new(cfg: TrendConfig) -> Self {
+        TrendDetector {
+            cfg,
+            state: TrendState::default(),
+        }
+    }
+    /// Reset the internal state.
+    pub fn reset(&mut self) {
+        self.state = TrendState::default();
+    }
+}
+
+impl TrendState {
+    /// The least squares slope (per second) of the samples.
+    ///
+    /// Returns zero if there are less than two samples.
+    pub fn slope(&self) -> f64 {
+        let n = self.samples.len() as f64;
+        if n < 2.0 {
+            return 0.0;
+        }
+        let t_mean = self
+            .samples
+            .iter()
+            .map(|(t, _)| t.as_secs_f64())
+            .sum::<f64>()
+            / n;
+        let x_mean = self.samples.iter().map(|(_, x)| x).sum::<f64>() / n;
+        let (cov, var) = self.samples.iter().fold((0.0, 0.0), |(cov, var), (t, x)| {
+            let dt = t.as_secs_f64() - t_mean;
+            (cov + dt * (x - x_mean), var + dt * dt)
+        });
+        if var > 0.0 {
+            cov / var
+        } else {
+            0.0
+        }
+    }
+}
+
+impl TrendConfig {
+    /// Classify a slope.
+    pub fn classify(&self, slope: f64) -> Trend {
+        if slope > self.rising.abs() {
+            Trend::Rising
+        } else if slope < -self.falling.abs() {
+            Trend::Falling
+        } else {
+            Trend::Steady
+        }
+    }
+}
+
+impl Controller<(f64, &Duration), (f64, Trend)> for TrendDetector {
+    fn next(&mut self, input: (f64, &Duration)) -> (f64, Trend) {
+        let (x, duration) = input;
+        let state = std::mem::take(&mut self.state);
+        let (state, slope, trend) = self.cfg.next((state, x, duration));
+        self.state = state;
+        (slope, trend)
+    }
+}
+
+impl PureController<(TrendState, f64, &Duration), (TrendState, f64, Trend)> for TrendConfig {
+    fn next(&self, input: (TrendState, f64, &Duration)) -> (TrendState, f64, Trend) {
+        let (mut state, x, duration) = input;
+        if !state.samples.is_empty() {
+            state.time += *duration;
+        }
+        state.samples.push_back((state.time, x));
+        while let Some((t, _)) = state.samples.front() {
+            if state.time - *t > self.window {
+                state.samples.pop_front();
+            } else {
+                break;
+            }
+        }
+        let slope = state.slope();
+        let trend = self.classify(slope);
+        (state, slope, trend)
+    }
+}
+
+#[cfg(test)]
+#[allow(clippy::float_cmp)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn estimate_slope() {
+        let mut t = TrendDetector::new(TrendConfig {
+            window: Duration::from_secs(10),
+            ..Default::default()
+        });
+        let dt = Duration::from_secs(2);
+        assert_eq!(t.next((5.0, &dt)).0, 0.0);
+        assert_eq!(t.next((6.0, &dt)).0, 0.5);
+        // Noise is averaged out
+        assert_eq!(t.next((9.0, &dt)).0, 1.0);
+        assert_eq!(t.next((8.0, &dt)).0, 0.6);
+        assert_eq!(t.state.samples.len(), 4);
+    }
+
+    #[test]
+    fn forget_values_outside_the_window() {
+        let mut t = TrendDetector::new(TrendConfig {
+            window: Duration::from_secs(2),
+            ..Default::default()
+        });
+        let dt = Duration::from_secs(1);
+        for x in [10.0, 20.0, 30.0, 1.0, 2.0, 3.0] {
+            t.next((x, &dt));
+        }
+        assert_eq!(t.state.samples.len(), 3);
+        assert_eq!(t.state.slope(), 1.0);
+        t.reset();
+        assert_eq!(t.state, TrendState::default());
+    }
+
+    #[test]
+    fn classify_trend() {
+        let cfg = TrendConfig {
+            window: Duration::from_secs(10),
+            rising: 0.5,
+            falling: 1.0,
+        };
+        assert_eq!(cfg.classify(0.6), Trend::Rising);
+        assert_eq!(cfg.classify(0.5), Trend::Steady);
+        assert_eq!(cfg.classify(-0.9), Trend::Steady);
+        assert_eq!(cfg.classify(-1.1), Trend::Falling);
+        let mut t = TrendDetector::new(cfg);
+        let dt = Duration::from_secs(1);
+        t.next((0.0, &dt));
+        assert_eq!(t.next((2.0, &dt)), (2.0, Trend::Rising));
+    }
+}
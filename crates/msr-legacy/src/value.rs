@@ -110,6 +110,14 @@ impl<'de> Visitor<'de> for ValueVisitor {
     fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
         Ok(Value::Integer(value))
     }
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E>
+    where
+        E: ::serde::de::Error,
+    {
+        i64::try_from(value)
+            .map(Value::Integer)
+            .map_err(|_| E::custom("integer out of range"))
+    }
     fn visit_str<E>(self, value: &str) -> Result<Value, E>
     where
         E: ::serde::de::Error,
@@ -220,6 +228,10 @@ mod tests {
         let v: Value = serde_json::from_str("-8").unwrap();
         assert_eq!(v, Value::Integer(-8));
 
+        let v: Value = serde_json::from_str("8").unwrap();
+        assert_eq!(v, Value::Integer(8));
+        assert!(serde_json::from_str::<Value>("18446744073709551615").is_err());
+
         let v: Value = serde_json::from_str("\"blabla\"").unwrap();
         assert_eq!(v, Value::Text("blabla".into()));
 